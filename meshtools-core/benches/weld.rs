@@ -0,0 +1,47 @@
+//! Times welding generated meshes into a `TriangleMesh`, which all of the topology analysis waits on.
+//!
+//! Run with `cargo bench -p meshtools-core`.
+
+use std::time::Instant;
+
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use meshtools_core::triangle::Triangle;
+use meshtools_core::triangle_mesh::{self, TriangleMesh};
+
+/// Times each size this many times, and reports the fastest
+const RUNS: usize = 3;
+
+/// A wavy square surface of `size` by `size` cells, two triangles to a cell,
+/// like the height map of a scan
+fn surface(size: usize) -> Vec<Triangle> {
+    let point = |x: usize, y: usize| {
+        let (x, y) = (x as f32 / size as f32, y as f32 / size as f32);
+        return Vec3::new(x, y, (x * 20.).sin() * (y * 20.).cos() * 0.05);
+    };
+    let mut triangles = Vec::with_capacity(size * size * 2);
+    for x in 0..size {
+        for y in 0..size {
+            triangles.push([point(x, y), point(x + 1, y), point(x + 1, y + 1)]);
+            triangles.push([point(x, y), point(x + 1, y + 1), point(x, y + 1)]);
+        }
+    }
+    return triangles;
+}
+
+fn main() {
+    for size in [100, 500, 1000, 2000] {
+        let triangles = surface(size);
+        let tolerance = triangle_mesh::default_tolerance(&triangles);
+        let mut fastest = f64::MAX;
+        let mut vertices = 0;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let mesh = TriangleMesh::new(&triangles, tolerance);
+            fastest = fastest.min(start.elapsed().as_secs_f64());
+            vertices = mesh.vertices.len();
+        }
+        println!("{:>9} triangles, {:>9} vertices: {:>8.1} ms", triangles.len(), vertices, fastest * 1000.);
+    }
+}
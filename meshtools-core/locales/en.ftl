@@ -0,0 +1,652 @@
+# English UI strings
+
+menu-file = File
+menu-open = Open
+menu-create-shape = Create Primitive…
+menu-create-shape-hint = Add a box, sphere, cylinder, cone, or ring to the scene
+menu-import-heightmap = Import Heightmap…
+menu-import-heightmap-hint = Turn a grayscale image into a relief or lithophane, with brightness as height
+menu-import-point-cloud = Import Point Cloud…
+menu-import-point-cloud-hint = Show the points of an XYZ or PLY scan alongside the models
+menu-point-clouds = Point Clouds…
+menu-quick-info = Quick Info…
+menu-quick-info-hint = Count the triangles and measure a file without opening it, for files too large to load
+menu-recent = Recent
+menu-save = Save
+menu-save-each = Save Each Model…
+menu-save-each-hint = Save every visible model to its own numbered file
+menu-export-visible = Export Visible Models…
+menu-export-visible-hint = Save every visible model, as placed, together in one STL or OBJ file
+menu-save-screenshot = Save Screenshot…
+menu-save-depth = Save Depth Map
+menu-save-depth-hint = Save the distance to each point of the view as a 16 bit grayscale PNG, with the nearest white and empty space black
+menu-export-gltf = Export glTF
+menu-operations = Operations
+menu-simplify = Simplify…
+menu-cut = Cut by Plane…
+menu-slice = Cross Sections…
+menu-slice-hint = Look through the selected model one height at a time
+menu-offset = Offset…
+menu-extrude = Extrude Outline…
+menu-extrude-hint = Make a closed solid from the selected model's silhouette, or from an open surface
+menu-morph = Morph…
+menu-morph-hint = Blend the selected model into the shape of another one aligned with it
+menu-deviation = Deviation…
+menu-deviation-hint = Measure how far the selected model is from another, aligned with it, and check it against tolerance bands
+menu-separate-bodies = Separate Bodies
+menu-separate-bodies-hint = Split the selected model into the pieces that share no edges
+menu-islands = Delete Small Islands…
+menu-islands-hint = Remove the small pieces of the selected model that touch nothing else, like scan noise
+menu-select = Select Faces…
+menu-select-hint = Pick out faces of the selected model in the view and delete them
+menu-measure = Measure…
+menu-measure-hint = Place landmarks in order to fill in a measurement template
+menu-annotate = Annotations…
+menu-annotate-hint = Drop named pins on the mesh, kept in a file next to it
+menu-section = Section planes…
+menu-section-hint = Clip the view with planes to look inside the models, without changing them
+menu-fit = Fit Primitives…
+menu-fit-hint = Fit planes, cylinders, and spheres to regions of the selected model, and export them for CAD
+menu-lay-flat = Lay Flat…
+menu-lay-flat-hint = Turn the selected model so a face picked in the view lies on the bed
+menu-align = Align Models…
+menu-align-hint = Move the selected model onto another by pairs of points that should meet
+menu-mold-box = Create Mold Box…
+menu-mold-box-hint = Add a block around the selected model, with room on every side, to cut the model's shape out of
+menu-duplicate-mirror = Duplicate and Mirror
+menu-mirror-axis = Across { $axis }
+menu-settings = Settings
+menu-language = Language
+menu-theme = Theme
+menu-units = Units
+menu-gpu-picking = Always pick with GPU
+menu-reduce-detail = Reduce detail while moving
+menu-reduce-detail-hint = Draws large models with fewer triangles while the view turns or moves, if drawing them in full is slow
+menu-antialiasing = Anti-aliasing
+menu-antialiasing-hint = Smooths the jagged edges of the models, at some cost to speed
+menu-antialiasing-off = Off
+menu-antialiasing-samples = { $samples }× samples
+menu-decimate = Simplify large meshes
+menu-auto-run = Automatic analysis
+menu-disk-cache = Cache on disk
+menu-disk-cache-hint = Keep simplified copies, picking indexes, and welded meshes of opened files in a temporary folder, so they open faster next time
+menu-clear-cache = Clear
+menu-update-url = Check for updates at:
+menu-update-url-hint = An http:// or https:// address whose first line is the latest version, checked at startup. Leave empty to not check.
+menu-update-url-invalid = Updates can't be checked at this address: { $error }
+menu-printer = Printer
+menu-edit-printers = Edit Printers…
+menu-build-volume-check = Warn about geometry outside the build volume
+build-volume-width = Width (X)
+build-volume-depth = Depth (Y)
+build-volume-height = Height (Z)
+printers = Printers
+printers-add = Add
+printers-remove = Remove
+printers-name = Name
+printers-unit = Units
+printers-min-wall = Thinnest wall
+printers-max-overhang = Steepest overhang
+printer-generic = Generic
+
+alert-title = Alert
+alert-ok = OK
+alert-saved = Saved: { $file }
+alert-saved-outside-volume = Saved: { $file }\nIt does not fit in the build volume.
+alert-cut-missed = The plane does not cross the model, so there was nothing to cut.
+alert-offset-empty = Nothing is left of the model at that distance.
+alert-morph-exported = Saved { $count } stages, from { $first } to { $last }
+alert-one-body = The model is a single body, so there is nothing to separate.
+alert-saved-each = Saved { $count } models, from { $first } to { $last }
+alert-saved-frames = Saved { $count } frames, from { $first } to { $last }
+alert-slices-exported = Exported { $count } slices, from { $first } to { $last }
+alert-islands-deleted = Deleted { $count } islands, with { $triangles } triangles
+alert-faces-deleted = Deleted { $count } faces
+alert-annotations-loaded = Loaded { $count } pins from { $file }
+alert-annotations-failed = Could not load the pins: { $error }
+alert-update-available = MeshTools { $latest } is available. This is version { $current }.
+alert-update-check-failed = Couldn't check { $url } for updates: { $error }
+alert-open-failed = Could not open file { $file }:\n\t{ $error }
+alert-save-failed = Could not save mesh:\n\t{ $error }
+alert-render-failed = Could not render mesh:\n\t{ $error }
+alert-context-lost = The graphics card was reset, so the models are being uploaded to it again. Colors from analyses have to be shown again.
+render-error-create = The graphics card could not make an object: { $error }
+render-error-shader = A shader could not be compiled: { $error }
+render-error-framebuffer = The graphics card cannot draw offscreen (status { $status })
+alert-clear-cache-failed = Could not clear the cache:\n\t{ $error }
+alert-import-failed = Could not import { $file }:\n\t{ $error }
+alert-no-triangles = There is no triangle data to save
+
+view-right-handed = right handed
+view-screenshot = Screenshot
+view-exaggerated = Exaggerated view: X ×{ $x }  Y ×{ $y }  Z ×{ $z }
+view-fit = Fit
+view-fit-hint = Fit the visible models in the view (F)
+view-front = Front
+view-back = Back
+view-left = Left
+view-right = Right
+view-top = Top
+view-bottom = Bottom
+view-isometric = Isometric
+view-shortcut = Key: { $key }
+view-saved = Saved Views
+view-saved-go = Go to this view
+view-saved-remove = Forget this view
+view-save = Save Current View
+view-saved-name = View { $number }
+view-turntable = Turntable
+view-turntable-hint = Keep turning the view around an axis of the scene
+projection = Projection
+projection-orthographic = Orthographic
+projection-perspective = Perspective
+projection-fov = Field of view
+projection-near = Near
+projection-far = Far
+projection-clip-hint = Nothing nearer or farther than these is drawn, measured from the eye in half heights of the view
+render-mode = Surface
+render-shaded = Shaded
+render-wireframe = Wireframe
+render-shaded-edges = Shaded with edges
+render-depth = Depth
+render-depth-hint = Shade by distance from the viewer, from black at the back of the models to white at the front
+render-smooth = Smooth shading
+render-smooth-hint = Blends the light across the edges between triangles, so scanned surfaces don't look faceted
+render-back-faces = Color back faces
+render-back-faces-hint = Colors the backs of triangles, so flipped normals show up wherever they are
+viewport = Viewport
+viewport-background = Background
+viewport-gradient = Gradient to
+viewport-grid = Grid floor
+viewport-grid-hint = A grid under the models, with the red X axis and the green Y axis, to judge their size and which way they face
+viewport-grid-spacing = Spacing
+viewport-grid-count = Lines each side
+section = Section planes
+section-plane = Plane { $number }
+section-flip = Flip
+section-flip-hint = Hide what is below the plane instead of above it
+section-caps = Caps
+section-caps-hint = Color the inside of cut models, so they look solid where they are cut
+screenshot = Save Screenshot
+screenshot-width = Width
+screenshot-height = Height
+screenshot-view-size = View Size
+screenshot-preset = { $width }×{ $height }
+screenshot-transparent = Transparent background
+screenshot-transparent-hint = Leave the background out, so the image can be placed over something else
+screenshot-save = Save…
+screenshot-turntable = Turntable frames
+screenshot-frames-hint = Number of images in a full turn
+screenshot-save-turntable = Save Turntable…
+screenshot-save-turntable-hint = Save numbered images of the view turning all the way around the chosen axis, to make an animation from
+exaggeration = Exaggeration
+exaggeration-hint = Stretches the view along each axis to show shallow relief. The models are not changed.
+exaggeration-reset = Reset
+
+scene = Scene
+scene-add = Add mesh
+scene-remove = Remove
+scene-rename = Rename
+scene-visible = Show or hide
+scene-decimated = preview (decimated)
+scene-cut-name = { $name } ({ $side })
+scene-mirrored-name = { $name } (mirrored { $axis })
+scene-morph-name = { $from } → { $to } ({ $percent }%)
+scene-body-name = { $name } (body { $number })
+scene-color = Color
+scene-build-bvh = Index
+scene-build-bvh-hint = Build the index used to find the point under the cursor
+
+simplify = Simplify
+simplify-count = Triangles: { $before } → { $after }
+simplify-keep = Keep
+simplify-max-error = Maximum error
+simplify-apply = Apply
+simplify-cancel = Cancel
+cut = Cut by Plane
+cut-axis = Across
+cut-position = Position along { $axis }
+cut-apply = Cut
+cut-cancel = Cancel
+slice = Cross Sections
+slice-height = Height (Z)
+slice-up = Up one step
+slice-down = Down one step
+slice-step = Layer height
+slice-empty = The model doesn't reach this height
+slice-totals = { $count } loops, { $perimeter } around, { $area } inside
+slice-open-hint = Some loops don't close, so the surface has gaps at this height
+slice-loop = Loop
+slice-perimeter = Perimeter
+slice-area = Area
+slice-open = open
+slice-format = Export as
+slice-export = Export Stack…
+slice-export-hint = Write every layer, from the bottom up, to its own numbered file
+slice-cancel-export = Cancel
+offset = Offset
+offset-shell = Shell
+offset-inflate = Inflate
+offset-shell-hint = Thickens the surface into a wall centered on it. The surface does not need to be closed.
+offset-inflate-hint = Moves a closed surface outward, or inward for a negative distance.
+offset-thickness = Wall thickness
+offset-distance = Distance
+offset-resolution = Resolution
+offset-spacing = Details smaller than { $spacing } are lost
+offset-apply = Apply
+offset-cancel = Cancel
+extrude = Extrude Outline
+extrude-silhouette = Silhouette
+extrude-surface = Open surface
+extrude-silhouette-hint = Makes a flat solid in the shape the model covers, seen along the direction. It starts at the back of the model.
+extrude-surface-hint = Gives an open surface depth, with a copy of it moved along the direction and walls around its edges.
+extrude-direction = Direction
+extrude-thickness = Thickness
+extrude-resolution = Resolution
+extrude-spacing = The outline follows a grid of { $spacing }
+extrude-invalid = The thickness must be above zero.
+extrude-closed = The surface has no open edges. Try the silhouette instead.
+extrude-apply = Create
+extrude-cancel = Cancel
+extrude-name = Extrusion of { $name }
+islands = Delete Small Islands
+islands-found = The model has { $count } islands.
+islands-finding = Finding the islands…
+islands-by-triangles = By triangle count
+islands-by-volume = By volume
+islands-smaller-than = Delete islands smaller than
+islands-triangles = triangles
+islands-to-delete = { $count } islands, with { $triangles } triangles, will be deleted. The largest island is always kept.
+islands-delete = Delete
+islands-cancel = Cancel
+select = Select Faces
+select-click = Click
+select-box = Box
+select-lasso = Lasso
+select-brush = Brush
+select-facing = Only faces turned toward me
+select-click-hint = Click a face to select it. Shift-click adds or removes faces.
+select-drag-hint = Drag around faces to select them. Hold shift to add to the selection, or ctrl to take away from it.
+select-brush-hint = Paint over faces to select them. The brush spreads across connected faces within its radius. Hold ctrl to take faces away.
+select-radius = Radius
+select-count = { $count } of { $total } faces selected
+select-all = All
+select-none = None
+select-invert = Invert
+select-delete = Delete Selected
+select-delete-hint = Remove the selected faces from the model. The Delete key does the same.
+select-export = Export Selected…
+select-export-hint = Save the selected faces, as placed, to an STL or OBJ file of their own
+morph = Morph
+morph-from = From: { $name }
+morph-to = To
+morph-needs-two = Choose a different model to blend toward.
+morph-matching = Matching surfaces
+morph-blend = Blend
+morph-add = Add to Scene
+morph-stages = Stages
+morph-export = Export Stages…
+morph-cancel-export = Cancel
+morph-close = Close
+deviation = Deviation
+deviation-measured = Measured: { $name }
+deviation-reference = Reference
+deviation-needs-two = Choose a different model to measure against.
+deviation-measuring = Measuring
+deviation-pass-within = Pass within ±
+deviation-warning-within = Warning within ±
+deviation-min-pass = Least area passing
+deviation-pass = Pass
+deviation-warning = Warning
+deviation-fail = Fail
+deviation-accepted = Accepted
+deviation-rejected = Rejected
+deviation-close = Close
+deviation-bands = Tolerance bands
+deviation-heat-map = Heat map
+deviation-range = Color range ±
+deviation-auto-range = Fit to largest
+deviation-statistics = Min { $min }, max { $max }, RMS { $rms }
+measure = Measure
+measure-template = Template
+measure-edit = Edit Templates
+measure-place = Click to place { $name } ({ $number } of { $count })
+measure-done = All landmarks are placed.
+measure-back = Undo Last
+measure-restart = Restart
+measure-export = Export Report…
+measure-new = New Template
+measure-new-name = New template
+measure-remove-template = Remove Template
+measure-name = Name
+measure-landmarks = Landmarks, in the order they are placed
+measure-landmark-name = Landmark { $number }
+measure-add-landmark = Add Landmark
+measure-measurements = Measurements
+measure-distance = Distance
+measure-angle = Angle
+measure-add-distance = Add Distance
+measure-add-angle = Add Angle
+measure-add-angle-hint = The angle is taken at the middle landmark
+measure-remove = Remove
+measure-tool = Tool
+measure-tool-template = Template
+measure-snap = Snap to
+measure-snap-off = Nothing
+measure-snap-vertex = Vertex
+measure-snap-edge = Edge
+measure-pick-first = Click the first point
+measure-pick-vertex = Click the vertex of the angle
+measure-pick-last = Click the last point
+measure-distance-name = Distance { $number }
+measure-angle-name = Angle { $number }
+measure-results = Results
+measure-clear = Clear Results
+measure-example = Foot
+measure-example-heel = Heel
+measure-example-toe = Longest toe tip
+measure-example-inner = First metatarsal head
+measure-example-outer = Fifth metatarsal head
+measure-example-length = Foot length
+measure-example-width = Forefoot width
+measure-example-angle = Forefoot angle at heel
+annotate = Annotations
+annotate-add = Add Pin
+annotate-placing = Click the model to drop the pin
+annotate-cancel = Cancel
+annotate-show = Show pins in the view
+annotate-none = No pins yet
+annotate-pin-name = Pin { $number }
+annotate-remove = Remove
+annotate-save = Save Pins…
+annotate-save-hint = Pins saved next to the mesh, named after it, are loaded when it is opened
+annotate-load = Load Pins…
+fit = Fit Primitives
+fit-plane = Plane
+fit-cylinder = Cylinder
+fit-sphere = Sphere
+fit-max-angle = Region stops at bends over
+fit-hint = Click a surface of the model to fit the chosen primitive to the region around it.
+fit-failed = The region could not be fitted with this primitive.
+fit-name = { $kind } { $number }
+fit-plane-entry = Point { $point }, normal { $normal }
+fit-cylinder-entry = Axis through { $point } along { $axis }, radius { $radius }
+fit-sphere-entry = Center { $center }, radius { $radius }
+fit-error = { $triangles } triangles, RMS error { $rms }, largest { $max }
+fit-remove = Remove
+fit-export = Export Report…
+lay-flat = Lay Flat
+lay-flat-face = Face
+lay-flat-points = Three points
+lay-flat-face-hint = Click a face of the model to lay it on the bed.
+lay-flat-points-hint = Click three points on the model to lay the plane through them on the bed ({ $count } of 3).
+align = Align Models
+align-moving = Moving { $name }
+align-onto = Onto
+align-needs-two = Choose a different model to align onto.
+align-click = Click a point on { $name }.
+align-pair = Pair { $number }
+align-remove = Remove
+align-rms = RMS distance once aligned: { $rms }
+align-needs-three = At least three pairs, not all in a line, are needed.
+align-apply = Align
+align-refine-hint = Once roughly aligned, the model can be fitted closely onto the other's surface.
+align-refine = Refine
+align-cancel = Cancel
+align-refined = Refined in { $steps } steps, RMS distance to the surface: { $rms }
+shape = Create Primitive
+shape-box = Box
+shape-sphere = Sphere
+shape-cylinder = Cylinder
+shape-cone = Cone
+shape-torus = Ring
+shape-size = Size along { $axis }
+shape-radius = Radius
+shape-ring-radius = Ring radius
+shape-tube-radius = Tube radius
+shape-height = Height
+shape-segments = Segments around
+shape-invalid = The sizes must be above zero, and a ring wider than its tube.
+shape-hint = Shapes are centered on the origin.
+shape-create = Create
+shape-name = { $kind } { $number }
+mold-box = Mold Box
+mold-box-around = Around { $name }, as placed in the scene
+mold-box-margin = Margin along { $axis }
+mold-box-draft = Draft
+mold-box-draft-hint = How far the sides lean in toward the top, so a cast block slides out of its frame
+mold-box-invalid = Margins can't be negative, and the draft must be under 45°.
+mold-box-create = Create
+mold-box-name = Mold box for { $name }
+heightmap = Import Heightmap
+heightmap-image = { $name }, { $width } × { $height } pixels
+heightmap-reading = Reading { $name }…
+heightmap-pixel-size = Distance between pixels
+heightmap-min-height = Height of black
+heightmap-max-height = Height of white
+heightmap-invert = Dark is high
+heightmap-invert-hint = For a lithophane, which is thickest where the picture is darkest
+heightmap-detail = Detail
+heightmap-size = { $width } by { $height }
+heightmap-triangles = About { $count } triangles
+heightmap-invalid = The sizes must be above zero, and white at least as high as black.
+heightmap-create = Create
+heightmap-cancel = Cancel
+point-clouds = Point Clouds
+point-cloud-reading = Reading { $name }…
+point-cloud-cancel = Cancel
+point-cloud-none = No point clouds are shown.
+point-cloud-count = { $count } points
+point-cloud-color-hint = Tints the colors from the file, or colors the points if it has none
+point-cloud-size = Point size
+point-cloud-remove = Remove
+
+lighting = Lighting
+lighting-ambient = Ambient:
+lighting-diffuse = Diffuse:
+lighting-specular = Specular:
+lighting-source = Light { $number }
+lighting-remove = Remove the light
+lighting-follow = Follows the camera
+lighting-follow-hint = Keeps shining from the same side of the view as it turns, like a headlight, rather than staying put in the scene
+lighting-add = Add light
+material = Material
+material-own = Own material
+material-own-hint = Gives the selected model its own colors, in place of the lighting's, to tell it apart from the others
+material-opacity = Opacity
+material-opacity-hint = Lower it to see parts inside or behind the selected model through it
+
+transformations = Transformations
+transformations-save = Save Transformed Mesh
+transformations-apply = Apply to Mesh
+transformations-apply-hint = Move the triangles themselves, and clear the list
+transformations-view-rotation = Include view rotation when saving
+transformations-bounds = Size: { $x } × { $y } × { $z }
+transformations-outside-volume = Outside the build volume
+transformations-center-volume = Center on Bed
+transformations-fit-volume = Scale to Fit
+transformations-mirror = Mirror
+transformations-mirror-hint = Reflect the model across a plane through the middle of its box
+transformations-mirror-axis = Across { $axis }
+transformations-convert-units = Convert Units
+transformations-convert-units-hint = Scale a model that was drawn in other units, so its sizes are right in { $unit }
+transformations-convert-from = From { $unit }
+transformations-import = Import…
+transformations-export = Export…
+analysis = Analysis
+analysis-hint = Assumes a closed mesh of uniform density
+analysis-no-volume = The mesh encloses no volume
+analysis-measuring = Measuring the model…
+analysis-area = Surface area: { $area }
+analysis-topology = { $vertices } vertices in { $bodies } bodies
+analysis-closed = Closed, with every edge shared by two triangles
+analysis-open = Not closed: { $boundary } open edges, { $non_manifold } non-manifold edges, { $misoriented } misoriented edges
+analysis-finding-topology = Checking how the triangles connect…
+analysis-volume = Volume: { $volume }
+analysis-center-of-mass = Center of mass: { $center }
+analysis-center-origin = Move Center of Mass to Origin
+analysis-principal-axes = Principal moments ({ $unit }) and axes:
+analysis-inertia = Inertia tensor about the center of mass ({ $unit }):
+analysis-bounding-box = Bounding box: { $x } × { $y } × { $z } ({ $volume })
+analysis-oriented-box = Oriented box: { $x } × { $y } × { $z } ({ $volume })
+analysis-find-box = Find Oriented Box
+analysis-finding-box = Finding the oriented box
+analysis-cancel = Cancel
+analysis-align-box = Align to Box
+analysis-align-box-hint = Turn the model so the box's longest side is along X and its shortest along Z
+analysis-color-by = Color by
+analysis-color-off = Nothing
+curvature-mean = Mean curvature
+curvature-gaussian = Gaussian curvature
+curvature-finding = Finding curvature…
+curvature-range = Color range ±
+curvature-auto-range = Auto
+curvature-hint = Mean curvature is red where the surface bulges out and blue where it dips in. Gaussian curvature is red at bumps and dents, and blue at saddles.
+thickness = Wall thickness
+thickness-threshold = Thin below
+thickness-finding = Measuring walls…
+thickness-thin = under { $threshold }
+thickness-open = open
+thickness-thinnest = Thinnest wall: { $thickness }
+thickness-none-thin = No walls thinner than { $threshold }
+thickness-thin-regions = { $count } regions thinner than { $threshold }, { $area } in all. Largest (area, thinnest):
+thickness-hint = Measured straight in from each triangle to the other side of the wall. Gray triangles have no wall behind them.
+overhang = Overhang
+overhang-max = Steepest without support
+overhang-finding = Finding overhangs…
+overhang-unsupported = needs support
+overhang-supported = prints unsupported
+overhang-area = Area needing support: { $area }
+overhang-hint = Undersides leaning further from vertical than the printer's steepest overhang are red. Faces resting on the bed need no support.
+transformation-matrix = Matrix
+transformation-import-invalid = Not a transform file: expected JSON with steps or a matrix, or 16 numbers
+transformation-edit = edit
+transformation-axis = Axis
+transformation-angle = Angle
+transformation-rotation = Rotation
+transformation-scale = Scale
+transformation-translation = Translation
+transformation-mirror = Mirror
+transformation-normal = Plane normal
+transformation-offset = Distance from origin
+transformation-rotation-summary = Rotate { $degrees }°
+transformation-scale-summary = Scale { $x }, { $y }, { $z }
+transformation-translation-summary = Translation { $x }, { $y }, { $z }
+transformation-mirror-summary = Mirror across { $x }, { $y }, { $z } at { $offset }
+transformation-snap = Rotate by degrees
+transformation-rotated = Rotated { $degrees }° about { $x }, { $y }, { $z }
+transformation-not-rotated = Not rotated
+
+theme-dark = Dark
+theme-light = Light
+theme-high-contrast = High contrast
+
+unit-millimeter = Millimeters
+unit-centimeter = Centimeters
+unit-inch = Inches
+
+status-unit = Units: { $unit }
+status-right-handed = Right handed, Z up
+status-left-handed = Left handed, Z up
+status-faces = Faces: { $input } input, { $rendered } rendered
+status-faces-hint = Faces with no area are kept in the model, but cover no pixels in the view
+status-cursor = X: { $x }  Y: { $y }  Z: { $z }
+status-uploading = Uploading to GPU
+
+timings = Timings
+timings-clear = Clear
+timings-save = Save Trace
+
+decimate-title = Large mesh
+decimate-prompt = This mesh has { $count } triangles. Show a simplified preview with about { $target } triangles? The file itself is not changed.
+decimate-yes = Simplify
+decimate-no = Keep full detail
+decimate-working = Simplifying { $file }
+decimate-threshold = Triangle limit:
+decimate-never = Never
+decimate-ask = Ask
+decimate-always = Always
+auto-run-bvh = Picking index
+auto-run-issues = Issue check
+auto-run-threshold = Small mesh limit (triangles):
+auto-run-always = Always
+auto-run-small = Small meshes only
+auto-run-on-demand = On demand
+
+
+loading-title = Loading
+loading-file = Reading { $file }
+loading-cancel = Cancel
+
+issues = Issues
+issues-analyze = Find issues
+issues-clean = Clean Mesh
+issues-clean-hint = Merge vertices closer than the tolerance, then remove triangles that lost a corner, repeated faces, and unused vertices
+issues-weld-tolerance = Tolerance
+issues-cleaned = Triangles: { $triangles_before } → { $triangles_after }, vertices: { $vertices_before } → { $vertices_after }\nRemoved { $degenerate } degenerate triangles, { $duplicates } duplicate faces, and { $unreferenced } unused vertices
+issues-cleaned-thin = Kept { $thin } triangles with next to no area
+issues-cleaned-thin-hint = Their three corners are distinct, but lie on a line within the weld tolerance. They are kept because the triangles around them share their edges, so removing them would open holes
+issues-export = Export list
+issues-count = Issues found: { $count }
+issues-thin-threshold = Minimum wall:
+issues-holes = Holes
+issues-non-manifold = Non-manifold edges
+issues-intersections = Intersecting triangles
+issues-thin-walls = Thin walls
+issues-flipped = Flipped normals
+issues-hole-entry = #{ $index }: { $perimeter }, { $area }
+issues-edge-entry = #{ $index }: { $length }
+issues-intersection-entry = #{ $index }
+issues-thin-entry = #{ $index }: { $thickness } thick
+issues-flipped-entry = #{ $index }: { $count } triangles
+issues-fill = Fill
+issues-fill-holes = Fill { $count } holes
+issues-fill-holes-hint = Close every hole whose perimeter is at most the limit
+issues-fill-perimeter = up to perimeter
+issues-fill-mode = Fill holes
+issues-fill-flat = Flat
+issues-fill-flat-hint = A fan of triangles straight across the hole
+issues-fill-smooth = Smooth
+issues-fill-smooth-hint = A patch that carries on the curvature of the surface around the hole
+issues-watertight = Watertight: every edge joins two consistently wound triangles
+issues-not-watertight = Not watertight: { $open } open, { $non_manifold } non-manifold and { $misoriented } inconsistently wound edges
+issues-show-edges = Show edges
+issues-flip = Flip
+issues-remove = Remove extra faces
+
+info-title = Quick Info
+info-triangles = Triangles: { $count }
+info-size = Size: { $x } × { $y } × { $z }
+info-bounds = From { $min } to { $max }
+info-area = Surface area: { $area }
+info-volume = Volume: { $volume }
+info-failed = Could not read the file: { $error }
+info-close = Close
+diff-usage = Usage: meshtools diff FIRST SECOND [--tolerance DISTANCE]
+diff-failed = Could not read { $file }: { $error }
+diff-header = Comparing { $first } with { $second }
+diff-triangles = Triangles: { $first } / { $second }
+diff-vertices = Vertices: { $first } / { $second }
+diff-bodies = Bodies: { $first } / { $second }
+diff-open-edges = Open edges: { $first } / { $second }
+diff-non-manifold = Non-manifold edges: { $first } / { $second }
+diff-deviation = Largest deviation: { $deviation } (tolerance { $tolerance })
+diff-match = The meshes match.
+diff-differ = The meshes differ.
+analyze-usage = Usage: meshtools analyze FILE [--thin-wall DISTANCE]
+analyze-issues = { $kind }: { $count }
+analyze-clean = No issues found.
+convert-usage = Usage: meshtools convert INPUT OUTPUT
+render-usage = Usage: meshtools render INPUT OUTPUT [--size WIDTHxHEIGHT]\nImages are at most 4096 pixels on a side.
+watch-usage = Usage: meshtools watch INCOMING OUTPUT [--interval SECONDS] [--fill flat|smooth] [--thumbnail PIXELS]\nImages are at most 4096 pixels on a side.
+watch-started = Watching { $incoming } for mesh files, writing results to { $output }
+watch-stopped = Stopped watching: { $error }
+watch-overlap = The output directory can't be the incoming directory, or inside it or around it
+watch-processed = Processed { $file }
+watch-failed = Could not process { $file }: { $error }
+watch-before = Before repair:
+watch-after = After repair:
+watch-cleaned = Removed { $degenerate } degenerate triangles, { $duplicates } duplicate faces, and { $unreferenced } unused vertices
+watch-cleaned-thin = Kept { $thin } triangles with next to no area, whose edges the triangles around them share
+watch-repaired = Flipped { $flipped } triangles and filled { $filled } holes
+watch-remaining = Remaining { $kind }: { $count }
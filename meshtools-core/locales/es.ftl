@@ -0,0 +1,652 @@
+# Spanish UI strings
+
+menu-file = Archivo
+menu-open = Abrir
+menu-create-shape = Crear primitiva…
+menu-create-shape-hint = Añadir una caja, esfera, cilindro, cono o anillo a la escena
+menu-import-heightmap = Importar mapa de alturas…
+menu-import-heightmap-hint = Convertir una imagen en escala de grises en un relieve o litofanía, con el brillo como altura
+menu-import-point-cloud = Importar nube de puntos…
+menu-import-point-cloud-hint = Mostrar los puntos de un escaneo XYZ o PLY junto a los modelos
+menu-point-clouds = Nubes de puntos…
+menu-quick-info = Información rápida…
+menu-quick-info-hint = Contar los triángulos y medir un archivo sin abrirlo, para archivos demasiado grandes para cargar
+menu-recent = Recientes
+menu-save = Guardar
+menu-save-each = Guardar cada modelo…
+menu-save-each-hint = Guarda cada modelo visible en su propio archivo numerado
+menu-export-visible = Exportar modelos visibles…
+menu-export-visible-hint = Guarda todos los modelos visibles, tal como están colocados, juntos en un archivo STL u OBJ
+menu-save-screenshot = Guardar captura…
+menu-save-depth = Guardar mapa de profundidad
+menu-save-depth-hint = Guarda la distancia a cada punto de la vista como un PNG en escala de grises de 16 bits, con lo más cercano en blanco y el espacio vacío en negro
+menu-export-gltf = Exportar glTF
+menu-operations = Operaciones
+menu-simplify = Simplificar…
+menu-cut = Cortar por un plano…
+menu-slice = Secciones transversales…
+menu-slice-hint = Recorrer el modelo seleccionado una altura a la vez
+menu-offset = Desplazar superficie…
+menu-extrude = Extruir contorno…
+menu-extrude-hint = Crear un sólido cerrado a partir de la silueta del modelo seleccionado, o de una superficie abierta
+menu-morph = Transformar en otro…
+menu-morph-hint = Mezcla el modelo seleccionado con la forma de otro alineado con él
+menu-deviation = Desviación…
+menu-deviation-hint = Mide cuánto se aleja el modelo seleccionado de otro, alineado con él, y lo compara con bandas de tolerancia
+menu-separate-bodies = Separar cuerpos
+menu-separate-bodies-hint = Divide el modelo seleccionado en las piezas que no comparten aristas
+menu-islands = Eliminar islas pequeñas…
+menu-islands-hint = Quita las piezas pequeñas del modelo seleccionado que no tocan nada más, como el ruido del escaneo
+menu-select = Seleccionar caras…
+menu-select-hint = Elegir caras del modelo seleccionado en la vista y eliminarlas
+menu-measure = Medir…
+menu-measure-hint = Coloque puntos de referencia en orden para completar una plantilla de medidas
+menu-annotate = Anotaciones…
+menu-annotate-hint = Coloca chinchetas con nombre sobre la malla, guardadas en un archivo junto a ella
+menu-section = Planos de sección…
+menu-section-hint = Recorta la vista con planos para ver el interior de los modelos, sin modificarlos
+menu-fit = Ajustar primitivas…
+menu-fit-hint = Ajusta planos, cilindros y esferas a regiones del modelo seleccionado y los exporta para CAD
+menu-lay-flat = Apoyar plano…
+menu-lay-flat-hint = Girar el modelo seleccionado para que una cara elegida en la vista quede sobre la cama
+menu-align = Alinear modelos…
+menu-align-hint = Mover el modelo seleccionado sobre otro mediante pares de puntos que deben coincidir
+menu-mold-box = Crear caja de molde…
+menu-mold-box-hint = Añadir un bloque alrededor del modelo seleccionado, con espacio en cada lado, del que recortar la forma del modelo
+menu-duplicate-mirror = Duplicar y reflejar
+menu-mirror-axis = En { $axis }
+menu-settings = Configuración
+menu-language = Idioma
+menu-theme = Tema
+menu-units = Unidades
+menu-gpu-picking = Seleccionar siempre con la GPU
+menu-reduce-detail = Reducir el detalle al moverse
+menu-reduce-detail-hint = Dibuja los modelos grandes con menos triángulos mientras la vista gira o se mueve, si dibujarlos completos es lento
+menu-antialiasing = Suavizado de bordes
+menu-antialiasing-hint = Suaviza los bordes dentados de los modelos, a costa de algo de velocidad
+menu-antialiasing-off = Desactivado
+menu-antialiasing-samples = { $samples }× muestras
+menu-decimate = Simplificar mallas grandes
+menu-auto-run = Análisis automático
+menu-disk-cache = Caché en disco
+menu-disk-cache-hint = Guardar copias simplificadas, índices de selección y mallas soldadas de los archivos abiertos en una carpeta temporal, para abrirlos más rápido la próxima vez
+menu-clear-cache = Vaciar
+menu-update-url = Buscar actualizaciones en:
+menu-update-url-hint = Una dirección http:// o https:// cuya primera línea es la última versión, consultada al iniciar. Dejar vacía para no consultar.
+menu-update-url-invalid = No se pueden buscar actualizaciones en esta dirección: { $error }
+menu-printer = Impresora
+menu-edit-printers = Editar impresoras…
+menu-build-volume-check = Avisar de la geometría fuera del volumen de impresión
+build-volume-width = Ancho (X)
+build-volume-depth = Fondo (Y)
+build-volume-height = Alto (Z)
+printers = Impresoras
+printers-add = Añadir
+printers-remove = Quitar
+printers-name = Nombre
+printers-unit = Unidades
+printers-min-wall = Pared más fina
+printers-max-overhang = Voladizo más inclinado
+printer-generic = Genérica
+
+alert-title = Aviso
+alert-ok = Aceptar
+alert-saved = Guardado: { $file }
+alert-saved-outside-volume = Guardado: { $file }\nNo cabe en el volumen de impresión.
+alert-cut-missed = El plano no atraviesa el modelo, así que no había nada que cortar.
+alert-offset-empty = No queda nada del modelo a esa distancia.
+alert-morph-exported = Se guardaron { $count } etapas, de { $first } a { $last }
+alert-one-body = El modelo es un solo cuerpo, así que no hay nada que separar.
+alert-saved-each = Se guardaron { $count } modelos, de { $first } a { $last }
+alert-saved-frames = Se guardaron { $count } fotogramas, de { $first } a { $last }
+alert-slices-exported = Se exportaron { $count } capas, de { $first } a { $last }
+alert-islands-deleted = Se eliminaron { $count } islas, con { $triangles } triángulos
+alert-faces-deleted = Se eliminaron { $count } caras
+alert-annotations-loaded = Se cargaron { $count } chinchetas de { $file }
+alert-annotations-failed = No se pudieron cargar las chinchetas: { $error }
+alert-update-available = MeshTools { $latest } está disponible. Esta es la versión { $current }.
+alert-update-check-failed = No se pudo buscar actualizaciones en { $url }: { $error }
+alert-open-failed = No se pudo abrir el archivo { $file }:\n\t{ $error }
+alert-save-failed = No se pudo guardar la malla:\n\t{ $error }
+alert-render-failed = No se pudo renderizar la malla:\n\t{ $error }
+alert-context-lost = La tarjeta gráfica se reinició, así que los modelos se están subiendo de nuevo. Los colores de los análisis deben mostrarse otra vez.
+render-error-create = La tarjeta gráfica no pudo crear un objeto: { $error }
+render-error-shader = No se pudo compilar un shader: { $error }
+render-error-framebuffer = La tarjeta gráfica no puede dibujar fuera de pantalla (estado { $status })
+alert-clear-cache-failed = No se pudo vaciar la caché:\n\t{ $error }
+alert-import-failed = No se pudo importar { $file }:\n\t{ $error }
+alert-no-triangles = No hay triángulos para guardar
+
+view-right-handed = dextrógiro
+view-screenshot = Captura
+view-exaggerated = Vista exagerada: X ×{ $x }  Y ×{ $y }  Z ×{ $z }
+view-fit = Encuadrar
+view-fit-hint = Encuadra los modelos visibles en la vista (F)
+view-front = Frente
+view-back = Detrás
+view-left = Izquierda
+view-right = Derecha
+view-top = Arriba
+view-bottom = Abajo
+view-isometric = Isométrica
+view-shortcut = Tecla: { $key }
+view-saved = Vistas guardadas
+view-saved-go = Ir a esta vista
+view-saved-remove = Olvidar esta vista
+view-save = Guardar vista actual
+view-saved-name = Vista { $number }
+view-turntable = Plato giratorio
+view-turntable-hint = Gira la vista continuamente alrededor de un eje de la escena
+projection = Proyección
+projection-orthographic = Ortográfica
+projection-perspective = Perspectiva
+projection-fov = Campo de visión
+projection-near = Cerca
+projection-far = Lejos
+projection-clip-hint = No se dibuja nada más cerca ni más lejos que esto, medido desde el ojo en medias alturas de la vista
+render-mode = Superficie
+render-shaded = Sombreada
+render-wireframe = Alambre
+render-shaded-edges = Sombreada con aristas
+render-depth = Profundidad
+render-depth-hint = Sombrea según la distancia al observador, de negro al fondo de los modelos a blanco al frente
+render-smooth = Sombreado suave
+render-smooth-hint = Difumina la luz entre triángulos vecinos, para que las superficies escaneadas no se vean facetadas
+render-back-faces = Colorear caras traseras
+render-back-faces-hint = Colorea el reverso de los triángulos, para que las normales invertidas se vean dondequiera que estén
+viewport = Vista
+viewport-background = Fondo
+viewport-gradient = Degradado hacia
+viewport-grid = Suelo con cuadrícula
+viewport-grid-hint = Una cuadrícula bajo los modelos, con el eje X en rojo y el eje Y en verde, para juzgar su tamaño y hacia dónde miran
+viewport-grid-spacing = Separación
+viewport-grid-count = Líneas a cada lado
+section = Planos de sección
+section-plane = Plano { $number }
+section-flip = Invertir
+section-flip-hint = Oculta lo que queda debajo del plano en lugar de encima
+section-caps = Tapas
+section-caps-hint = Colorea el interior de los modelos cortados, para que se vean macizos donde se cortan
+screenshot = Guardar captura
+screenshot-width = Ancho
+screenshot-height = Alto
+screenshot-view-size = Tamaño de la vista
+screenshot-preset = { $width }×{ $height }
+screenshot-transparent = Fondo transparente
+screenshot-transparent-hint = Omite el fondo, para poder colocar la imagen sobre otra cosa
+screenshot-save = Guardar…
+screenshot-turntable = Fotogramas del giro
+screenshot-frames-hint = Número de imágenes en un giro completo
+screenshot-save-turntable = Guardar giro…
+screenshot-save-turntable-hint = Guarda imágenes numeradas de la vista girando por completo alrededor del eje elegido, para hacer una animación
+exaggeration = Exageración
+exaggeration-hint = Estira la vista en cada eje para mostrar relieves poco profundos. Los modelos no cambian.
+exaggeration-reset = Restablecer
+
+scene = Escena
+scene-add = Añadir malla
+scene-remove = Quitar
+scene-rename = Renombrar
+scene-visible = Mostrar u ocultar
+scene-decimated = vista previa (simplificada)
+scene-cut-name = { $name } ({ $side })
+scene-mirrored-name = { $name } (reflejado en { $axis })
+scene-morph-name = { $from } → { $to } ({ $percent }%)
+scene-body-name = { $name } (cuerpo { $number })
+scene-color = Color
+scene-build-bvh = Indexar
+scene-build-bvh-hint = Construir el índice usado para encontrar el punto bajo el cursor
+
+simplify = Simplificar
+simplify-count = Triángulos: { $before } → { $after }
+simplify-keep = Conservar
+simplify-max-error = Error máximo
+simplify-apply = Aplicar
+simplify-cancel = Cancelar
+cut = Cortar por un plano
+cut-axis = Perpendicular a
+cut-position = Posición en { $axis }
+cut-apply = Cortar
+cut-cancel = Cancelar
+slice = Secciones transversales
+slice-height = Altura (Z)
+slice-up = Subir un paso
+slice-down = Bajar un paso
+slice-step = Altura de capa
+slice-empty = El modelo no llega a esta altura
+slice-totals = { $count } contornos, { $perimeter } de perímetro, { $area } de área
+slice-open-hint = Algunos contornos no se cierran, así que la superficie tiene huecos a esta altura
+slice-loop = Contorno
+slice-perimeter = Perímetro
+slice-area = Área
+slice-open = abierto
+slice-format = Exportar como
+slice-export = Exportar capas…
+slice-export-hint = Guardar cada capa, de abajo hacia arriba, en su propio archivo numerado
+slice-cancel-export = Cancelar
+offset = Desplazar superficie
+offset-shell = Cáscara
+offset-inflate = Inflar
+offset-shell-hint = Engrosa la superficie en una pared centrada en ella. La superficie no necesita estar cerrada.
+offset-inflate-hint = Mueve una superficie cerrada hacia afuera, o hacia adentro con una distancia negativa.
+offset-thickness = Grosor de pared
+offset-distance = Distancia
+offset-resolution = Resolución
+offset-spacing = Se pierden los detalles menores de { $spacing }
+offset-apply = Aplicar
+offset-cancel = Cancelar
+extrude = Extruir contorno
+extrude-silhouette = Silueta
+extrude-surface = Superficie abierta
+extrude-silhouette-hint = Crea un sólido plano con la forma que cubre el modelo, visto en la dirección. Empieza en la parte trasera del modelo.
+extrude-surface-hint = Da profundidad a una superficie abierta, con una copia desplazada en la dirección y paredes alrededor de sus bordes.
+extrude-direction = Dirección
+extrude-thickness = Grosor
+extrude-resolution = Resolución
+extrude-spacing = El contorno sigue una cuadrícula de { $spacing }
+extrude-invalid = El grosor debe ser mayor que cero.
+extrude-closed = La superficie no tiene bordes abiertos. Pruebe con la silueta.
+extrude-apply = Crear
+extrude-cancel = Cancelar
+extrude-name = Extrusión de { $name }
+islands = Eliminar islas pequeñas
+islands-found = El modelo tiene { $count } islas.
+islands-finding = Buscando las islas…
+islands-by-triangles = Por número de triángulos
+islands-by-volume = Por volumen
+islands-smaller-than = Eliminar islas menores que
+islands-triangles = triángulos
+islands-to-delete = Se eliminarán { $count } islas, con { $triangles } triángulos. La isla más grande siempre se conserva.
+islands-delete = Eliminar
+islands-cancel = Cancelar
+select = Seleccionar caras
+select-click = Clic
+select-box = Rectángulo
+select-lasso = Lazo
+select-brush = Pincel
+select-facing = Solo caras orientadas hacia mí
+select-click-hint = Haz clic en una cara para seleccionarla. Mayús+clic agrega o quita caras.
+select-drag-hint = Arrastra alrededor de las caras para seleccionarlas. Mantén Mayús para agregar a la selección, o Ctrl para quitar de ella.
+select-brush-hint = Pinta sobre las caras para seleccionarlas. El pincel se extiende por las caras conectadas dentro de su radio. Mantén ctrl para quitar caras.
+select-radius = Radio
+select-count = { $count } de { $total } caras seleccionadas
+select-all = Todas
+select-none = Ninguna
+select-invert = Invertir
+select-delete = Eliminar selección
+select-delete-hint = Quitar las caras seleccionadas del modelo. La tecla Supr hace lo mismo.
+select-export = Exportar selección…
+select-export-hint = Guarda las caras seleccionadas, tal como están colocadas, en su propio archivo STL u OBJ
+morph = Transformar en otro
+morph-from = Desde: { $name }
+morph-to = Hacia
+morph-needs-two = Elige otro modelo hacia el que mezclar.
+morph-matching = Emparejando superficies
+morph-blend = Mezcla
+morph-add = Añadir a la escena
+morph-stages = Etapas
+morph-export = Exportar etapas…
+morph-cancel-export = Cancelar
+morph-close = Cerrar
+deviation = Desviación
+deviation-measured = Medido: { $name }
+deviation-reference = Referencia
+deviation-needs-two = Elija otro modelo con el que comparar.
+deviation-measuring = Midiendo
+deviation-pass-within = Aprobado dentro de ±
+deviation-warning-within = Advertencia dentro de ±
+deviation-min-pass = Área mínima aprobada
+deviation-pass = Aprobado
+deviation-warning = Advertencia
+deviation-fail = Fallo
+deviation-accepted = Aceptado
+deviation-rejected = Rechazado
+deviation-close = Cerrar
+deviation-bands = Bandas de tolerancia
+deviation-heat-map = Mapa de calor
+deviation-range = Rango de color ±
+deviation-auto-range = Ajustar al mayor
+deviation-statistics = Mín { $min }, máx { $max }, RMS { $rms }
+measure = Medir
+measure-template = Plantilla
+measure-edit = Editar plantillas
+measure-place = Haga clic para colocar { $name } ({ $number } de { $count })
+measure-done = Todos los puntos de referencia están colocados.
+measure-back = Deshacer último
+measure-restart = Reiniciar
+measure-export = Exportar informe…
+measure-new = Nueva plantilla
+measure-new-name = Nueva plantilla
+measure-remove-template = Eliminar plantilla
+measure-name = Nombre
+measure-landmarks = Puntos de referencia, en el orden en que se colocan
+measure-landmark-name = Punto { $number }
+measure-add-landmark = Añadir punto
+measure-measurements = Medidas
+measure-distance = Distancia
+measure-angle = Ángulo
+measure-add-distance = Añadir distancia
+measure-add-angle = Añadir ángulo
+measure-add-angle-hint = El ángulo se mide en el punto del medio
+measure-remove = Eliminar
+measure-tool = Herramienta
+measure-tool-template = Plantilla
+measure-snap = Ajustar a
+measure-snap-off = Nada
+measure-snap-vertex = Vértice
+measure-snap-edge = Arista
+measure-pick-first = Haz clic en el primer punto
+measure-pick-vertex = Haz clic en el vértice del ángulo
+measure-pick-last = Haz clic en el último punto
+measure-distance-name = Distancia { $number }
+measure-angle-name = Ángulo { $number }
+measure-results = Resultados
+measure-clear = Borrar resultados
+measure-example = Pie
+measure-example-heel = Talón
+measure-example-toe = Punta del dedo más largo
+measure-example-inner = Cabeza del primer metatarsiano
+measure-example-outer = Cabeza del quinto metatarsiano
+measure-example-length = Longitud del pie
+measure-example-width = Anchura del antepié
+measure-example-angle = Ángulo del antepié en el talón
+annotate = Anotaciones
+annotate-add = Añadir chincheta
+annotate-placing = Haz clic en el modelo para colocar la chincheta
+annotate-cancel = Cancelar
+annotate-show = Mostrar chinchetas en la vista
+annotate-none = Todavía no hay chinchetas
+annotate-pin-name = Chincheta { $number }
+annotate-remove = Quitar
+annotate-save = Guardar chinchetas…
+annotate-save-hint = Las chinchetas guardadas junto a la malla, con su nombre, se cargan al abrirla
+annotate-load = Cargar chinchetas…
+fit = Ajustar primitivas
+fit-plane = Plano
+fit-cylinder = Cilindro
+fit-sphere = Esfera
+fit-max-angle = La región se detiene en pliegues de más de
+fit-hint = Haga clic en una superficie del modelo para ajustar la primitiva elegida a la región que la rodea.
+fit-failed = No se pudo ajustar la región con esta primitiva.
+fit-name = { $kind } { $number }
+fit-plane-entry = Punto { $point }, normal { $normal }
+fit-cylinder-entry = Eje por { $point } en dirección { $axis }, radio { $radius }
+fit-sphere-entry = Centro { $center }, radio { $radius }
+fit-error = { $triangles } triángulos, error RMS { $rms }, máximo { $max }
+fit-remove = Quitar
+fit-export = Exportar informe…
+lay-flat = Apoyar plano
+lay-flat-face = Cara
+lay-flat-points = Tres puntos
+lay-flat-face-hint = Haz clic en una cara del modelo para apoyarla sobre la cama.
+lay-flat-points-hint = Haz clic en tres puntos del modelo para apoyar sobre la cama el plano que pasa por ellos ({ $count } de 3).
+align = Alinear modelos
+align-moving = Moviendo { $name }
+align-onto = Sobre
+align-needs-two = Elige otro modelo sobre el que alinear.
+align-click = Haz clic en un punto de { $name }.
+align-pair = Par { $number }
+align-remove = Quitar
+align-rms = Distancia RMS una vez alineado: { $rms }
+align-needs-three = Se necesitan al menos tres pares que no estén en línea.
+align-apply = Alinear
+align-refine-hint = Una vez alineado a grandes rasgos, el modelo puede ajustarse con precisión a la superficie del otro.
+align-refine = Refinar
+align-cancel = Cancelar
+align-refined = Refinado en { $steps } pasos, distancia RMS a la superficie: { $rms }
+shape = Crear primitiva
+shape-box = Caja
+shape-sphere = Esfera
+shape-cylinder = Cilindro
+shape-cone = Cono
+shape-torus = Anillo
+shape-size = Tamaño en { $axis }
+shape-radius = Radio
+shape-ring-radius = Radio del anillo
+shape-tube-radius = Radio del tubo
+shape-height = Altura
+shape-segments = Segmentos alrededor
+shape-invalid = Los tamaños deben ser mayores que cero, y el anillo más ancho que su tubo.
+shape-hint = Las formas se centran en el origen.
+shape-create = Crear
+shape-name = { $kind } { $number }
+mold-box = Caja de molde
+mold-box-around = Alrededor de { $name }, tal como está en la escena
+mold-box-margin = Margen en { $axis }
+mold-box-draft = Desmoldeo
+mold-box-draft-hint = Cuánto se inclinan los lados hacia dentro por arriba, para que un bloque colado salga de su marco
+mold-box-invalid = Los márgenes no pueden ser negativos, y el desmoldeo debe ser menor de 45°.
+mold-box-create = Crear
+mold-box-name = Caja de molde para { $name }
+heightmap = Importar mapa de alturas
+heightmap-image = { $name }, { $width } × { $height } píxeles
+heightmap-reading = Leyendo { $name }…
+heightmap-pixel-size = Distancia entre píxeles
+heightmap-min-height = Altura del negro
+heightmap-max-height = Altura del blanco
+heightmap-invert = Oscuro es alto
+heightmap-invert-hint = Para una litofanía, que es más gruesa donde la imagen es más oscura
+heightmap-detail = Detalle
+heightmap-size = { $width } por { $height }
+heightmap-triangles = Unos { $count } triángulos
+heightmap-invalid = Los tamaños deben ser mayores que cero, y el blanco al menos tan alto como el negro.
+heightmap-create = Crear
+heightmap-cancel = Cancelar
+point-clouds = Nubes de puntos
+point-cloud-reading = Leyendo { $name }…
+point-cloud-cancel = Cancelar
+point-cloud-none = No se muestra ninguna nube de puntos.
+point-cloud-count = { $count } puntos
+point-cloud-color-hint = Tiñe los colores del archivo, o colorea los puntos si no tiene
+point-cloud-size = Tamaño de punto
+point-cloud-remove = Quitar
+
+lighting = Iluminación
+lighting-ambient = Ambiental:
+lighting-diffuse = Difusa:
+lighting-specular = Especular:
+lighting-source = Luz { $number }
+lighting-remove = Quitar la luz
+lighting-follow = Sigue a la cámara
+lighting-follow-hint = Sigue iluminando desde el mismo lado de la vista al girarla, como un faro, en lugar de quedarse fija en la escena
+lighting-add = Añadir luz
+material = Material
+material-own = Material propio
+material-own-hint = Da al modelo seleccionado sus propios colores, en lugar de los de la iluminación, para distinguirlo de los demás
+material-opacity = Opacidad
+material-opacity-hint = Bájela para ver a través del modelo seleccionado las piezas que hay dentro o detrás
+
+transformations = Transformaciones
+transformations-save = Guardar malla transformada
+transformations-apply = Aplicar a la malla
+transformations-apply-hint = Mover los propios triángulos y vaciar la lista
+transformations-view-rotation = Incluir la rotación de la vista al guardar
+transformations-bounds = Tamaño: { $x } × { $y } × { $z }
+transformations-outside-volume = Fuera del volumen de impresión
+transformations-center-volume = Centrar en la cama
+transformations-fit-volume = Escalar para que quepa
+transformations-mirror = Reflejar
+transformations-mirror-hint = Reflejar el modelo en un plano que pasa por el centro de su caja
+transformations-mirror-axis = En { $axis }
+transformations-convert-units = Convertir unidades
+transformations-convert-units-hint = Escalar un modelo dibujado en otras unidades, para que sus medidas sean correctas en { $unit }
+transformations-convert-from = Desde { $unit }
+transformations-import = Importar…
+transformations-export = Exportar…
+analysis = Análisis
+analysis-hint = Supone una malla cerrada de densidad uniforme
+analysis-no-volume = La malla no encierra ningún volumen
+analysis-measuring = Midiendo el modelo…
+analysis-area = Área de superficie: { $area }
+analysis-topology = { $vertices } vértices en { $bodies } cuerpos
+analysis-closed = Cerrada, con cada arista compartida por dos triángulos
+analysis-open = No está cerrada: { $boundary } aristas abiertas, { $non_manifold } aristas no múltiples, { $misoriented } aristas mal orientadas
+analysis-finding-topology = Comprobando cómo se conectan los triángulos…
+analysis-volume = Volumen: { $volume }
+analysis-center-of-mass = Centro de masa: { $center }
+analysis-center-origin = Mover el centro de masa al origen
+analysis-principal-axes = Momentos principales ({ $unit }) y ejes:
+analysis-inertia = Tensor de inercia respecto al centro de masa ({ $unit }):
+analysis-bounding-box = Caja envolvente: { $x } × { $y } × { $z } ({ $volume })
+analysis-oriented-box = Caja orientada: { $x } × { $y } × { $z } ({ $volume })
+analysis-find-box = Buscar caja orientada
+analysis-finding-box = Buscando la caja orientada
+analysis-cancel = Cancelar
+analysis-align-box = Alinear con la caja
+analysis-align-box-hint = Girar el modelo para que el lado más largo de la caja quede en X y el más corto en Z
+analysis-color-by = Colorear por
+analysis-color-off = Nada
+curvature-mean = Curvatura media
+curvature-gaussian = Curvatura gaussiana
+curvature-finding = Calculando la curvatura…
+curvature-range = Rango de color ±
+curvature-auto-range = Automático
+curvature-hint = La curvatura media es roja donde la superficie sobresale y azul donde se hunde. La curvatura gaussiana es roja en bultos y hoyos, y azul en las sillas.
+thickness = Grosor de pared
+thickness-threshold = Delgada bajo
+thickness-finding = Midiendo paredes…
+thickness-thin = menos de { $threshold }
+thickness-open = abierta
+thickness-thinnest = Pared más delgada: { $thickness }
+thickness-none-thin = No hay paredes más delgadas que { $threshold }
+thickness-thin-regions = { $count } regiones más delgadas que { $threshold }, { $area } en total. Las mayores (área, mínimo):
+thickness-hint = Medido en línea recta desde cada triángulo hasta el otro lado de la pared. Los triángulos grises no tienen pared detrás.
+overhang = Voladizo
+overhang-max = Máximo sin soporte
+overhang-finding = Buscando voladizos…
+overhang-unsupported = necesita soporte
+overhang-supported = se imprime sin soporte
+overhang-area = Área que necesita soporte: { $area }
+overhang-hint = Las caras inferiores que se inclinan desde la vertical más que el voladizo máximo de la impresora son rojas. Las caras apoyadas en la cama no necesitan soporte.
+transformation-matrix = Matriz
+transformation-import-invalid = No es un archivo de transformación: se esperaba JSON con pasos o una matriz, o 16 números
+transformation-edit = editar
+transformation-axis = Eje
+transformation-angle = Ángulo
+transformation-rotation = Rotación
+transformation-scale = Escala
+transformation-translation = Traslación
+transformation-mirror = Reflejo
+transformation-normal = Normal del plano
+transformation-offset = Distancia al origen
+transformation-rotation-summary = Rotar { $degrees }°
+transformation-scale-summary = Escalar { $x }, { $y }, { $z }
+transformation-translation-summary = Trasladar { $x }, { $y }, { $z }
+transformation-mirror-summary = Reflejar en { $x }, { $y }, { $z } a { $offset }
+transformation-snap = Girar en grados
+transformation-rotated = Girado { $degrees }° alrededor de { $x }, { $y }, { $z }
+transformation-not-rotated = Sin rotación
+
+theme-dark = Oscuro
+theme-light = Claro
+theme-high-contrast = Alto contraste
+
+unit-millimeter = Milímetros
+unit-centimeter = Centímetros
+unit-inch = Pulgadas
+
+status-unit = Unidades: { $unit }
+status-right-handed = Dextrógiro, Z arriba
+status-left-handed = Levógiro, Z arriba
+status-faces = Caras: { $input } de entrada, { $rendered } dibujadas
+status-faces-hint = Las caras sin área se conservan en el modelo, pero no cubren ningún píxel de la vista
+status-cursor = X: { $x }  Y: { $y }  Z: { $z }
+status-uploading = Subiendo a la GPU
+
+timings = Tiempos
+timings-clear = Borrar
+timings-save = Guardar traza
+
+decimate-title = Malla grande
+decimate-prompt = Esta malla tiene { $count } triángulos. ¿Mostrar una vista previa simplificada con unos { $target } triángulos? El archivo no se modifica.
+decimate-yes = Simplificar
+decimate-no = Mantener todo el detalle
+decimate-working = Simplificando { $file }
+decimate-threshold = Límite de triángulos:
+decimate-never = Nunca
+decimate-ask = Preguntar
+decimate-always = Siempre
+auto-run-bvh = Índice de selección
+auto-run-issues = Revisión de problemas
+auto-run-threshold = Límite de malla pequeña (triángulos):
+auto-run-always = Siempre
+auto-run-small = Solo mallas pequeñas
+auto-run-on-demand = Bajo demanda
+
+
+loading-title = Cargando
+loading-file = Leyendo { $file }
+loading-cancel = Cancelar
+
+issues = Problemas
+issues-analyze = Buscar problemas
+issues-clean = Limpiar malla
+issues-clean-hint = Une los vértices más cercanos que la tolerancia y quita los triángulos que perdieron una esquina, las caras repetidas y los vértices sin usar
+issues-weld-tolerance = Tolerancia
+issues-cleaned = Triángulos: { $triangles_before } → { $triangles_after }, vértices: { $vertices_before } → { $vertices_after }\nSe quitaron { $degenerate } triángulos degenerados, { $duplicates } caras duplicadas y { $unreferenced } vértices sin usar
+issues-cleaned-thin = Se conservaron { $thin } triángulos casi sin área
+issues-cleaned-thin-hint = Sus tres esquinas son distintas, pero están en línea dentro de la tolerancia de soldadura. Se conservan porque los triángulos de alrededor comparten sus aristas, y quitarlos abriría agujeros
+issues-export = Exportar lista
+issues-count = Problemas encontrados: { $count }
+issues-thin-threshold = Pared mínima:
+issues-holes = Agujeros
+issues-non-manifold = Aristas no variedad
+issues-intersections = Triángulos que se cruzan
+issues-thin-walls = Paredes delgadas
+issues-flipped = Normales invertidas
+issues-hole-entry = #{ $index }: { $perimeter }, { $area }
+issues-edge-entry = #{ $index }: { $length }
+issues-intersection-entry = #{ $index }
+issues-thin-entry = #{ $index }: { $thickness } de grosor
+issues-flipped-entry = #{ $index }: { $count } triángulos
+issues-fill = Rellenar
+issues-fill-holes = Rellenar { $count } agujeros
+issues-fill-holes-hint = Cerrar cada agujero cuyo perímetro no supere el límite
+issues-fill-perimeter = hasta un perímetro de
+issues-fill-mode = Rellenar agujeros
+issues-fill-flat = Plano
+issues-fill-flat-hint = Un abanico de triángulos que cruza el agujero en línea recta
+issues-fill-smooth = Suave
+issues-fill-smooth-hint = Un parche que continúa la curvatura de la superficie alrededor del agujero
+issues-watertight = Estanca: cada arista une dos triángulos con orientación coherente
+issues-not-watertight = No estanca: { $open } aristas abiertas, { $non_manifold } no múltiples y { $misoriented } con orientación incoherente
+issues-show-edges = Mostrar aristas
+issues-flip = Invertir
+issues-remove = Quitar caras sobrantes
+
+info-title = Información rápida
+info-triangles = Triángulos: { $count }
+info-size = Tamaño: { $x } × { $y } × { $z }
+info-bounds = De { $min } a { $max }
+info-area = Área de la superficie: { $area }
+info-volume = Volumen: { $volume }
+info-failed = No se pudo leer el archivo: { $error }
+info-close = Cerrar
+diff-usage = Uso: meshtools diff PRIMERO SEGUNDO [--tolerance DISTANCIA]
+diff-failed = No se pudo leer { $file }: { $error }
+diff-header = Comparando { $first } con { $second }
+diff-triangles = Triángulos: { $first } / { $second }
+diff-vertices = Vértices: { $first } / { $second }
+diff-bodies = Cuerpos: { $first } / { $second }
+diff-open-edges = Aristas abiertas: { $first } / { $second }
+diff-non-manifold = Aristas no variedad: { $first } / { $second }
+diff-deviation = Mayor desviación: { $deviation } (tolerancia { $tolerance })
+diff-match = Las mallas coinciden.
+diff-differ = Las mallas son distintas.
+analyze-usage = Uso: meshtools analyze ARCHIVO [--thin-wall DISTANCIA]
+analyze-issues = { $kind }: { $count }
+analyze-clean = No se encontraron problemas.
+convert-usage = Uso: meshtools convert ENTRADA SALIDA
+render-usage = Uso: meshtools render ENTRADA SALIDA [--size ANCHOxALTO]\nLas imágenes miden como mucho 4096 píxeles por lado.
+watch-usage = Uso: meshtools watch ENTRADA SALIDA [--interval SEGUNDOS] [--fill flat|smooth] [--thumbnail PÍXELES]\nLas imágenes miden como mucho 4096 píxeles por lado.
+watch-started = Vigilando { $incoming } en busca de mallas, con los resultados en { $output }
+watch-stopped = Se dejó de vigilar: { $error }
+watch-overlap = El directorio de salida no puede ser el de entrada, ni estar dentro de él ni contenerlo
+watch-processed = Procesado { $file }
+watch-failed = No se pudo procesar { $file }: { $error }
+watch-before = Antes de reparar:
+watch-after = Después de reparar:
+watch-cleaned = Se eliminaron { $degenerate } triángulos degenerados, { $duplicates } caras duplicadas y { $unreferenced } vértices sin usar
+watch-cleaned-thin = Se conservaron { $thin } triángulos casi sin área, cuyas aristas comparten los triángulos de alrededor
+watch-repaired = Se invirtieron { $flipped } triángulos y se rellenaron { $filled } agujeros
+watch-remaining = { $kind } restantes: { $count }
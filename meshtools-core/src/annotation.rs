@@ -0,0 +1,56 @@
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Write};
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::json::{self, Json};
+use crate::version;
+
+/// A note pinned to a point on the mesh surface
+#[derive(Clone, PartialEq, Debug)]
+pub struct Annotation {
+    pub text: String,
+    /// Where the pin was dropped, in scene coordinates
+    pub position: Vec3
+}
+
+impl Annotation {
+    fn to_json(&self) -> String {
+        return format!(r#"{{"text": {}, "position": [{}, {}, {}]}}"#,
+            json::quote(&self.text), self.position.x, self.position.y, self.position.z);
+    }
+
+    fn from_json(value: &Json) -> Option<Self> {
+        let position = value.get("position")?.as_f32s()?;
+        if position.len() != 3 {
+            return None;
+        }
+        return Some(Self {
+            text: value.get("text")?.as_str()?.to_string(),
+            position: Vec3::new(position[0], position[1], position[2])
+        });
+    }
+}
+
+/// The file a mesh's annotations are kept in, next to it, like "part.stl.pins.json"
+pub fn sidecar_path(mesh_path: &str) -> String {
+    return format!("{}.pins.json", mesh_path);
+}
+
+/// Writes annotations to a JSON file, with the version of MeshTools that wrote them
+pub fn write_annotations(path: &str, annotations: &[Annotation]) -> Result<(), Error> {
+    let pins: Vec<String> = annotations.iter().map(|a| a.to_json()).collect();
+    let mut output = File::create(path)?;
+    writeln!(output, r#"{{"generator": {}, "annotations": [{}]}}"#,
+        json::quote(&version::description()), pins.join(", "))?;
+    return Ok(());
+}
+
+pub fn read_annotations(path: &str) -> Result<Vec<Annotation>, Error> {
+    let text = fs::read_to_string(path)?;
+    let value = Json::parse(&text).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    let annotations: Option<Vec<Annotation>> = value.get("annotations")
+        .and_then(|a| a.as_array())
+        .and_then(|a| a.iter().map(Annotation::from_json).collect());
+    return annotations.ok_or_else(|| Error::new(ErrorKind::InvalidData, "Not an annotations file"));
+}
@@ -0,0 +1,75 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::triangle::{self, Triangle};
+
+/// Most triangles drawn as out of bounds, since each is drawn separately over the view
+pub const MAX_DRAWN_TRIANGLES: usize = 20_000;
+
+/// The space a printer can build in. It is centered on the origin
+/// in X and Y, and stands on Z = 0, as most slicers place the bed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BuildVolume {
+    /// Width, depth and height, in the mesh's units
+    pub size: Vec3,
+    /// Warn about geometry outside the volume
+    pub enabled: bool
+}
+
+impl BuildVolume {
+    /// The lowest and highest corners of the volume
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let half = Vec3::new(self.size.x / 2., self.size.y / 2., 0.);
+        return (-half, half + Vec3::new(0., 0., self.size.z));
+    }
+
+    /// Whether a box fits inside the volume
+    pub fn contains(&self, min: &Vec3, max: &Vec3) -> bool {
+        let (low, high) = self.bounds();
+        return (0..3).all(|i| min[i] >= low[i] && max[i] <= high[i]);
+    }
+
+    /// Indices of the triangles with a corner outside the volume, once transformed
+    pub fn outside(&self, triangles: &[Triangle], transformation: &Mat4) -> Vec<usize> {
+        let (low, high) = self.bounds();
+        return triangles.iter()
+            .enumerate()
+            .filter(|(_, t)| triangle::transform(t, transformation).iter()
+                .any(|v| (0..3).any(|i| v[i] < low[i] || v[i] > high[i])))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    /// The movement placing a box in the middle of the bed
+    pub fn center_offset(&self, min: &Vec3, max: &Vec3) -> Vec3 {
+        let center = (min + max) / 2.;
+        return Vec3::new(-center.x, -center.y, -min.z);
+    }
+
+    /// The largest uniform scale at which a box fits, no more than 1
+    pub fn fit_scale(&self, min: &Vec3, max: &Vec3) -> f32 {
+        let size = max - min;
+        return (0..3)
+            .filter(|i| size[*i] > 0.)
+            .map(|i| self.size[i] / size[i])
+            .fold(1., f32::min);
+    }
+
+    /// The edges of the volume, as loops to draw over the view
+    pub fn outline(&self) -> Vec<Vec<Vec3>> {
+        let (low, high) = self.bounds();
+        let corner = |x: f32, y: f32, z: f32| Vec3::new(x, y, z);
+        let mut outline = Vec::<Vec<Vec3>>::new();
+        for z in [low.z, high.z] {
+            outline.push(vec![
+                corner(low.x, low.y, z),
+                corner(high.x, low.y, z),
+                corner(high.x, high.y, z),
+                corner(low.x, high.y, z)]);
+        }
+        for (x, y) in [(low.x, low.y), (high.x, low.y), (high.x, high.y), (low.x, high.y)] {
+            outline.push(vec![corner(x, y, low.z), corner(x, y, high.z)]);
+        }
+        return outline;
+    }
+}
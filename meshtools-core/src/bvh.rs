@@ -0,0 +1,329 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::mesh_cache::{self, ByteReader};
+use crate::spatial_grid;
+use crate::triangle::{self, Triangle};
+
+/// Most triangles kept in a leaf before it is split
+const LEAF_SIZE: usize = 4;
+
+/// A node covering `count` triangles starting at `start` in the ordered list.
+/// Branches have their children directly after them and at `second`.
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    start: usize,
+    count: usize,
+    second: Option<usize>
+}
+
+/// A bounding volume hierarchy over a list of triangles, for finding which triangle is under the cursor,
+/// the nearest point on the triangles, and which triangles may touch each other.
+///
+/// The hierarchy only stores triangle indices,
+/// so the same triangles must be passed in when picking.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    order: Vec<usize>
+}
+
+fn bounds(triangles: &[Triangle], indices: &[usize]) -> (Vec3, Vec3) {
+    let mut min = Vec3::repeat(f32::MAX);
+    let mut max = Vec3::repeat(f32::MIN);
+    for i in indices {
+        for vertex in &triangles[*i] {
+            min = glm::min2(&min, vertex);
+            max = glm::max2(&max, vertex);
+        }
+    }
+    return (min, max);
+}
+
+/// The range of distances along a line where it is inside a box
+fn line_box(origin: &Vec3, direction: &Vec3, min: &Vec3, max: &Vec3) -> Option<(f32, f32)> {
+    let mut near = f32::MIN;
+    let mut far = f32::MAX;
+    for i in 0..3 {
+        if direction[i] == 0. {
+            if origin[i] < min[i] || origin[i] > max[i] {
+                return None;
+            }
+            continue;
+        }
+        let a = (min[i] - origin[i]) / direction[i];
+        let b = (max[i] - origin[i]) / direction[i];
+        near = near.max(a.min(b));
+        far = far.min(a.max(b));
+    }
+    if near > far {
+        return None;
+    }
+    return Some((near, far));
+}
+
+/// Whether two boxes share any point
+fn boxes_overlap(min: &Vec3, max: &Vec3, other_min: &Vec3, other_max: &Vec3) -> bool {
+    return (0..3).all(|i| min[i] <= other_max[i] && other_min[i] <= max[i]);
+}
+
+/// Whether the boxes around two triangles share any point
+fn triangle_boxes_overlap(a: &Triangle, b: &Triangle) -> bool {
+    let [a_min, a_max, b_min, b_max] = [
+        glm::min2(&glm::min2(&a[0], &a[1]), &a[2]), glm::max2(&glm::max2(&a[0], &a[1]), &a[2]),
+        glm::min2(&glm::min2(&b[0], &b[1]), &b[2]), glm::max2(&glm::max2(&b[0], &b[1]), &b[2])];
+    return boxes_overlap(&a_min, &a_max, &b_min, &b_max);
+}
+
+/// The squared distance from a point to the nearest point of a box
+fn box_distance_squared(point: &Vec3, min: &Vec3, max: &Vec3) -> f32 {
+    let outside = glm::max2(&glm::max2(&(min - point), &(point - max)), &Vec3::zeros());
+    return outside.norm_squared();
+}
+
+impl Bvh {
+    /// Builds the hierarchy by splitting each node at the median of its longest axis
+    pub fn new(triangles: &[Triangle]) -> Self {
+        profile_span!("build_bvh");
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            order: (0..triangles.len()).collect()
+        };
+        let centers: Vec<Vec3> = triangles.iter()
+            .map(|[a, b, c]| (a + b + c) / 3.)
+            .collect();
+        if !triangles.is_empty() {
+            bvh.build(triangles, &centers, 0, triangles.len());
+        }
+        return bvh;
+    }
+
+    fn build(&mut self, triangles: &[Triangle], centers: &[Vec3], start: usize, count: usize) {
+        let (min, max) = bounds(triangles, &self.order[start..start + count]);
+        let index = self.nodes.len();
+        self.nodes.push(Node { min, max, start, count, second: None });
+        if count <= LEAF_SIZE {
+            return;
+        }
+        let size = max - min;
+        let axis = if size.x >= size.y && size.x >= size.z { 0 } else if size.y >= size.z { 1 } else { 2 };
+        let half = count / 2;
+        self.order[start..start + count].select_nth_unstable_by(half, |a, b| {
+            centers[*a][axis].total_cmp(&centers[*b][axis])
+        });
+        self.build(triangles, centers, start, half);
+        self.nodes[index].second = Some(self.nodes.len());
+        self.build(triangles, centers, start + half, count - half);
+    }
+
+    /// Appends the hierarchy to a buffer, for the disk cache
+    pub fn write_to(&self, output: &mut Vec<u8>) {
+        mesh_cache::write_u64(output, self.nodes.len() as u64);
+        for node in &self.nodes {
+            mesh_cache::write_vec3(output, &node.min);
+            mesh_cache::write_vec3(output, &node.max);
+            mesh_cache::write_u64(output, node.start as u64);
+            mesh_cache::write_u64(output, node.count as u64);
+            mesh_cache::write_u64(output, node.second.map_or(u64::MAX, |second| second as u64));
+        }
+        mesh_cache::write_u64(output, self.order.len() as u64);
+        for i in &self.order {
+            mesh_cache::write_u64(output, *i as u64);
+        }
+    }
+
+    /// Reads a hierarchy written by `write_to`, if it is valid for that many triangles
+    pub fn read_from(reader: &mut ByteReader, triangle_count: usize) -> Option<Self> {
+        let node_count = reader.count(48)?;
+        let mut nodes = Vec::<Node>::with_capacity(node_count);
+        for _ in 0..node_count {
+            let min = reader.vec3()?;
+            let max = reader.vec3()?;
+            let start = reader.usize()?;
+            let count = reader.usize()?;
+            let second = match reader.u64()? {
+                u64::MAX => None,
+                second => Some(second as usize)
+            };
+            if start.checked_add(count)? > triangle_count || second.is_some_and(|second| second >= node_count) {
+                return None;
+            }
+            nodes.push(Node { min, max, start, count, second });
+        }
+        let order_count = reader.count(8)?;
+        if order_count != triangle_count {
+            return None;
+        }
+        let mut order = Vec::<usize>::with_capacity(order_count);
+        for _ in 0..order_count {
+            let i = reader.usize()?;
+            if i >= triangle_count {
+                return None;
+            }
+            order.push(i);
+        }
+        // A branch's first child comes directly after it
+        if nodes.iter().enumerate().any(|(i, node)| node.second.is_some() && i + 1 >= node_count) {
+            return None;
+        }
+        return Some(Self { nodes, order });
+    }
+
+    /// Finds the first triangle hit by a line, going along the direction.
+    ///
+    /// The whole line is searched, including behind the origin.
+    /// Returns the triangle's index and the distance along the line
+    /// in multiples of the direction vector.
+    pub fn pick(&self, triangles: &[Triangle], origin: &Vec3, direction: &Vec3) -> Option<(usize, f32)> {
+        return self.first_hit(triangles, origin, direction, |_, _| true);
+    }
+
+    /// Finds the nearest triangle a ray hits in front of its origin, among the triangles
+    /// the filter accepts. The filter gets each hit triangle's index and distance.
+    pub fn cast(&self, triangles: &[Triangle], origin: &Vec3, direction: &Vec3, filter: impl Fn(usize, f32) -> bool)
+    -> Option<(usize, f32)> {
+        return self.first_hit(triangles, origin, direction, |i, t| t > 0. && filter(i, t));
+    }
+
+    /// Finds the hit furthest back along a line, among the hits accepted
+    fn first_hit(&self, triangles: &[Triangle], origin: &Vec3, direction: &Vec3, accept: impl Fn(usize, f32) -> bool)
+    -> Option<(usize, f32)> {
+        let mut best: Option<(usize, f32)> = None;
+        let mut stack = Vec::<usize>::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let (near, _) = match line_box(origin, direction, &node.min, &node.max) {
+                Some(range) => range,
+                None => continue
+            };
+            if matches!(best, Some((_, t)) if t < near) {
+                continue;
+            }
+            match node.second {
+                Some(second) => {
+                    stack.push(second);
+                    stack.push(index + 1);
+                },
+                None => {
+                    for i in &self.order[node.start..node.start + node.count] {
+                        if let Some(t) = spatial_grid::line_triangle(origin, direction, &triangles[*i]) {
+                            if accept(*i, t) && !matches!(best, Some((_, best_t)) if best_t <= t) {
+                                best = Some((*i, t));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        return best;
+    }
+
+    /// Finds the point on the triangles nearest to a point.
+    /// Returns the triangle's index and the point on it.
+    pub fn closest(&self, triangles: &[Triangle], point: &Vec3) -> Option<(usize, Vec3)> {
+        let mut best: Option<(usize, Vec3, f32)> = None;
+        let mut stack = Vec::<usize>::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if matches!(best, Some((_, _, d)) if d <= box_distance_squared(point, &node.min, &node.max)) {
+                continue;
+            }
+            match node.second {
+                Some(second) => {
+                    // Visit the nearer child first, so more of the other can be skipped
+                    let first_distance = box_distance_squared(point, &self.nodes[index + 1].min, &self.nodes[index + 1].max);
+                    let second_distance = box_distance_squared(point, &self.nodes[second].min, &self.nodes[second].max);
+                    if first_distance <= second_distance {
+                        stack.push(second);
+                        stack.push(index + 1);
+                    } else {
+                        stack.push(index + 1);
+                        stack.push(second);
+                    }
+                },
+                None => {
+                    for i in &self.order[node.start..node.start + node.count] {
+                        let nearest = triangle::closest_point(point, &triangles[*i]);
+                        let d = (nearest - point).norm_squared();
+                        if !matches!(best, Some((_, _, best_d)) if best_d <= d) {
+                            best = Some((*i, nearest, d));
+                        }
+                    }
+                }
+            }
+        }
+        return best.map(|(i, nearest, _)| (i, nearest));
+    }
+
+    /// Calls `visit` with each pair of triangles whose boxes overlap, one from this hierarchy
+    /// and one from the other, until it returns false. Each hierarchy's own triangles must be given with it.
+    pub fn overlapping_pairs(&self, triangles: &[Triangle], other: &Bvh, other_triangles: &[Triangle],
+        mut visit: impl FnMut(usize, usize) -> bool) {
+        self.pairs(triangles, other, other_triangles, false, &mut visit);
+    }
+
+    /// Calls `visit` with each pair of different triangles in this hierarchy whose boxes overlap,
+    /// lower index first, until it returns false. Checking each pair for crossing finds self intersections.
+    pub fn overlapping_self_pairs(&self, triangles: &[Triangle], mut visit: impl FnMut(usize, usize) -> bool) {
+        self.pairs(triangles, self, triangles, true, &mut visit);
+    }
+
+    /// Walks both hierarchies together, splitting pairs of nodes whose boxes overlap down to their triangles.
+    /// When the hierarchies are the same, each pair is only visited once.
+    fn pairs(&self, triangles: &[Triangle], other: &Bvh, other_triangles: &[Triangle], same: bool,
+        visit: &mut impl FnMut(usize, usize) -> bool) {
+        let mut stack = Vec::<(usize, usize)>::new();
+        if !self.nodes.is_empty() && !other.nodes.is_empty() {
+            stack.push((0, 0));
+        }
+        while let Some((a, b)) = stack.pop() {
+            let (node, other_node) = (&self.nodes[a], &other.nodes[b]);
+            if !boxes_overlap(&node.min, &node.max, &other_node.min, &other_node.max) {
+                continue;
+            }
+            // A node paired with itself is split into the pairs of its children, without repeating any
+            if let (true, Some(second)) = (same && a == b, node.second) {
+                stack.push((a + 1, a + 1));
+                stack.push((a + 1, second));
+                stack.push((second, second));
+                continue;
+            }
+            match (node.second, other_node.second) {
+                (Some(second), None) => {
+                    stack.push((a + 1, b));
+                    stack.push((second, b));
+                },
+                // The bigger node is split, so the boxes compared stay about the same size
+                (Some(second), Some(_)) if node.count >= other_node.count => {
+                    stack.push((a + 1, b));
+                    stack.push((second, b));
+                },
+                (_, Some(other_second)) => {
+                    stack.push((a, b + 1));
+                    stack.push((a, other_second));
+                },
+                (None, None) => {
+                    let other_order = &other.order[other_node.start..other_node.start + other_node.count];
+                    for (i, first) in self.order[node.start..node.start + node.count].iter().enumerate() {
+                        let others = if same && a == b { &other_order[i + 1..] } else { other_order };
+                        for second in others {
+                            if !triangle_boxes_overlap(&triangles[*first], &other_triangles[*second]) {
+                                continue;
+                            }
+                            let (first, second) = if same { (*first.min(second), *first.max(second)) } else { (*first, *second) };
+                            if !visit(first, second) {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::deviation;
+use crate::i18n::tr;
+use crate::triangle::Triangle;
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// Which curvature a model is colored by
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CurvatureKind {
+    Mean,
+    Gaussian
+}
+
+impl CurvatureKind {
+    pub const ALL: [CurvatureKind; 2] = [CurvatureKind::Mean, CurvatureKind::Gaussian];
+
+    pub fn label(&self) -> String {
+        match self {
+            CurvatureKind::Mean => tr("curvature-mean"),
+            CurvatureKind::Gaussian => tr("curvature-gaussian")
+        }
+    }
+}
+
+/// The curvature at each corner of a model's triangles
+pub struct CornerCurvature {
+    pub mean: Vec<[f32; 3]>,
+    pub gaussian: Vec<[f32; 3]>,
+    /// Ranges of mean and Gaussian curvature covering most of the surface
+    typical: [f32; 2]
+}
+
+impl CornerCurvature {
+    pub fn new(triangles: &[Triangle]) -> Self {
+        let mesh = TriangleMesh::with_all_triangles(triangles, triangle_mesh::default_tolerance(triangles));
+        let curvature = mesh.curvature();
+        return Self {
+            mean: mesh.triangles.iter().map(|t| t.map(|v| curvature.mean[v])).collect(),
+            gaussian: mesh.triangles.iter().map(|t| t.map(|v| curvature.gaussian[v])).collect(),
+            typical: [percentile(&curvature.mean), percentile(&curvature.gaussian)]
+        };
+    }
+
+    pub fn values(&self, kind: CurvatureKind) -> &[[f32; 3]] {
+        return match kind {
+            CurvatureKind::Mean => &self.mean,
+            CurvatureKind::Gaussian => &self.gaussian
+        };
+    }
+
+    /// A range covering most of the surface. A few sharp corners curve far more than
+    /// the rest, so the largest curvature would leave everything else looking flat.
+    pub fn typical_range(&self, kind: CurvatureKind) -> f32 {
+        return match kind {
+            CurvatureKind::Mean => self.typical[0],
+            CurvatureKind::Gaussian => self.typical[1]
+        };
+    }
+
+    /// The color of each corner, three to a triangle, on a heat map
+    /// where the given curvature is the furthest color either way
+    pub fn colors(&self, kind: CurvatureKind, range: f32) -> Vec<[f32; 3]> {
+        let range = range.max(f32::EPSILON);
+        return self.values(kind).iter()
+            .flat_map(|corners| corners.map(|c| deviation::heat_color(c / range)))
+            .collect();
+    }
+}
+
+/// The size that 95% of the values are within
+fn percentile(values: &[f32]) -> f32 {
+    let mut magnitudes: Vec<f32> = values.iter().map(|c| c.abs()).collect();
+    if magnitudes.is_empty() {
+        return f32::EPSILON;
+    }
+    let index = (magnitudes.len() - 1) * 95 / 100;
+    let (_, percentile, _) = magnitudes.select_nth_unstable_by(index, |a, b| a.total_cmp(b));
+    return percentile.max(f32::EPSILON);
+}
@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+extern crate nalgebra_glm as glm;
+
+use crate::i18n::tr;
+use crate::triangle::Triangle;
+use crate::triangle_mesh::TriangleMesh;
+
+/// Whether large meshes are decimated when they are loaded
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AutoDecimate {
+    Never,
+    /// Ask each time a large mesh is loaded
+    Ask,
+    Always
+}
+
+impl AutoDecimate {
+    pub const ALL: [AutoDecimate; 3] = [AutoDecimate::Never, AutoDecimate::Ask, AutoDecimate::Always];
+
+    /// The name used to store the choice in the settings
+    pub fn code(&self) -> &'static str {
+        match self {
+            AutoDecimate::Never => "never",
+            AutoDecimate::Ask => "ask",
+            AutoDecimate::Always => "always"
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        return AutoDecimate::ALL.into_iter().find(|d| d.code() == code);
+    }
+
+    /// The translated name of the choice
+    pub fn label(&self) -> String {
+        match self {
+            AutoDecimate::Never => tr("decimate-never"),
+            AutoDecimate::Ask => tr("decimate-ask"),
+            AutoDecimate::Always => tr("decimate-always")
+        }
+    }
+}
+
+/// Most passes made trying to get under the target triangle count
+const MAX_PASSES: usize = 4;
+
+/// Reduces a mesh to about the target number of triangles by vertex clustering.
+///
+/// Vertices are snapped to a grid sized for the target, and triangles which collapse
+/// or duplicate another are dropped. This is fast and keeps the overall shape,
+/// but loses detail smaller than the grid, so it is only meant for previews.
+/// The progress callback gets the fraction of passes done.
+pub fn decimate(triangles: &[Triangle], target: usize, mut progress: impl FnMut(f32)) -> Vec<Triangle> {
+    profile_span!("decimate");
+    if triangles.len() <= target || target == 0 {
+        return triangles.to_vec();
+    }
+    // A surface with area A has about 2A/s² triangles on a grid with spacing s
+    let area: f32 = triangles.iter()
+        .map(|[a, b, c]| glm::cross(&(b - a), &(c - a)).norm() / 2.)
+        .sum();
+    let mut cell_size = (2. * area / target as f32).sqrt();
+    let mut result = triangles.to_vec();
+    for pass in 0..MAX_PASSES {
+        if cell_size.is_nan() || cell_size <= 0. {
+            break;
+        }
+        result = cluster(triangles, cell_size);
+        progress((pass + 1) as f32 / MAX_PASSES as f32);
+        if result.len() <= target {
+            break;
+        }
+        cell_size *= (result.len() as f32 / target as f32).sqrt();
+    }
+    return result;
+}
+
+fn cluster(triangles: &[Triangle], cell_size: f32) -> Vec<Triangle> {
+    let mesh = TriangleMesh::new(triangles, cell_size);
+    // Both sides of a thin part can collapse onto the same vertices
+    let mut seen = HashSet::<[usize; 3]>::new();
+    return mesh.triangles.iter()
+        .filter(|triangle| {
+            let mut key = **triangle;
+            key.sort();
+            seen.insert(key)
+        })
+        .map(|triangle| triangle.map(|v| mesh.vertices[v]))
+        .collect();
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::bvh::Bvh;
+use crate::i18n::tr;
+use crate::triangle::Triangle;
+
+/// Which tolerance band a deviation falls in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Band {
+    Pass,
+    Warning,
+    Fail
+}
+
+impl Band {
+    pub const ALL: [Band; 3] = [Band::Pass, Band::Warning, Band::Fail];
+
+    pub fn label(&self) -> String {
+        match self {
+            Band::Pass => tr("deviation-pass"),
+            Band::Warning => tr("deviation-warning"),
+            Band::Fail => tr("deviation-fail")
+        }
+    }
+
+    /// The color surfaces in the band are drawn with
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            Band::Pass => [0.2, 0.9, 0.3],
+            Band::Warning => [1.0, 0.8, 0.1],
+            Band::Fail => [1.0, 0.15, 0.1]
+        }
+    }
+}
+
+/// Acceptance criteria for a deviation, as a QA specification writes them:
+/// within ±pass is fine, within ±warning is tolerated, and beyond that fails
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ToleranceBands {
+    pub pass: f32,
+    pub warning: f32,
+    /// Least share of the surface, in percent, that has to be in the pass band
+    pub min_pass_percent: f32
+}
+
+impl ToleranceBands {
+    pub fn band(&self, distance: f32) -> Band {
+        let distance = distance.abs();
+        if distance <= self.pass {
+            return Band::Pass;
+        }
+        if distance <= self.warning.max(self.pass) {
+            return Band::Warning;
+        }
+        return Band::Fail;
+    }
+
+    /// Whether a surface with this share of its area in each band, in the order of `Band::ALL`,
+    /// is accepted. Nothing may fail, and enough has to pass.
+    pub fn accepts(&self, percentages: &[f32; 3]) -> bool {
+        return percentages[2] == 0. && percentages[0] >= self.min_pass_percent;
+    }
+}
+
+/// How a model is colored by its deviation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorMode {
+    /// A color for each tolerance band
+    Bands,
+    /// Colors running from blue, behind the reference, through green to red, in front of it
+    HeatMap
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 2] = [ColorMode::Bands, ColorMode::HeatMap];
+
+    pub fn label(&self) -> String {
+        match self {
+            ColorMode::Bands => tr("deviation-bands"),
+            ColorMode::HeatMap => tr("deviation-heat-map")
+        }
+    }
+}
+
+/// The color of a deviation on the heat map, from -1 to 1 of its range
+pub fn heat_color(fraction: f32) -> [f32; 3] {
+    const STOPS: [[f32; 3]; 5] = [[0., 0., 1.], [0., 1., 1.], [0., 1., 0.], [1., 1., 0.], [1., 0., 0.]];
+    let position = (fraction.clamp(-1., 1.) + 1.) * 2.;
+    let low = (position.floor() as usize).min(STOPS.len() - 2);
+    let t = position - low as f32;
+    return [0, 1, 2].map(|i| STOPS[low][i] * (1. - t) + STOPS[low + 1][i] * t);
+}
+
+/// Summary of the distances from a surface to a reference
+#[derive(Clone, Copy, Debug)]
+pub struct DeviationStatistics {
+    /// The smallest signed distance, which is negative where the surface is behind the reference
+    pub min: f32,
+    /// The largest signed distance
+    pub max: f32,
+    /// Root mean square distance
+    pub rms: f32
+}
+
+impl DeviationStatistics {
+    /// The largest distance either way
+    pub fn largest(&self) -> f32 {
+        return self.min.abs().max(self.max.abs());
+    }
+}
+
+/// How far the surface of a model is from a reference surface, at each corner of its triangles
+pub struct Deviation {
+    /// Distance from each corner to the nearest point of the reference,
+    /// negative where the corner is behind the reference's surface
+    pub distances: Vec<[f32; 3]>,
+    pub areas: Vec<f32>,
+    pub statistics: DeviationStatistics
+}
+
+impl Deviation {
+    /// Measures triangles against a reference. Both should already be aligned.
+    ///
+    /// The progress callback gets the fraction done, and returns false to stop,
+    /// in which case None is returned.
+    pub fn new(triangles: &[Triangle], reference: &[Triangle], mut progress: impl FnMut(f32) -> bool) -> Option<Self> {
+        profile_span!("deviation");
+        let bvh = Bvh::new(reference);
+        // Corners are shared by several triangles, so each position is only looked up once
+        let mut measured = HashMap::<[u32; 3], f32>::new();
+        let mut distances = Vec::with_capacity(triangles.len());
+        for (i, triangle) in triangles.iter().enumerate() {
+            distances.push(triangle.map(|corner| *measured
+                .entry([corner.x.to_bits(), corner.y.to_bits(), corner.z.to_bits()])
+                .or_insert_with(|| signed_distance(&bvh, reference, &corner))));
+            if i % 4096 == 0 && !progress(i as f32 / triangles.len() as f32) {
+                return None;
+            }
+        }
+        let areas = triangles.iter()
+            .map(|t| (t[1] - t[0]).cross(&(t[2] - t[0])).norm() / 2.)
+            .collect();
+        let statistics = statistics(&distances);
+        return Some(Self { distances, areas, statistics });
+    }
+
+    /// The share of the surface area, in percent, in each band in the order of `Band::ALL`.
+    /// Each triangle counts in the band of its furthest corner.
+    pub fn band_percentages(&self, bands: &ToleranceBands) -> [f32; 3] {
+        let mut areas = [0f32; 3];
+        for (distances, area) in self.distances.iter().zip(&self.areas) {
+            let furthest = distances.iter().fold(0f32, |furthest, d| furthest.max(d.abs()));
+            let band = bands.band(furthest);
+            areas[Band::ALL.iter().position(|b| *b == band).unwrap()] += area;
+        }
+        let total: f32 = areas.iter().sum();
+        if total == 0. {
+            return [0.; 3];
+        }
+        return areas.map(|area| area / total * 100.);
+    }
+
+    /// The color of each corner, three to a triangle, on a heat map
+    /// where the given distance is the furthest color either way
+    pub fn heat_colors(&self, range: f32) -> Vec<[f32; 3]> {
+        let range = range.max(f32::EPSILON);
+        return self.distances.iter()
+            .flat_map(|distances| distances.map(|d| heat_color(d / range)))
+            .collect();
+    }
+
+    /// The color of each corner, three to a triangle, by the band it is in
+    pub fn band_colors(&self, bands: &ToleranceBands) -> Vec<[f32; 3]> {
+        return self.distances.iter()
+            .flat_map(|distances| distances.map(|d| bands.band(d).color()))
+            .collect();
+    }
+}
+
+/// The smallest, largest, and root mean square distances of the triangles' corners
+fn statistics(distances: &[[f32; 3]]) -> DeviationStatistics {
+    if distances.is_empty() {
+        return DeviationStatistics { min: 0., max: 0., rms: 0. };
+    }
+    let corners = || distances.iter().flatten();
+    let count = (distances.len() * 3) as f32;
+    return DeviationStatistics {
+        min: corners().fold(f32::INFINITY, |min, d| min.min(*d)),
+        max: corners().fold(f32::NEG_INFINITY, |max, d| max.max(*d)),
+        rms: (corners().map(|d| d * d).sum::<f32>() / count).sqrt()
+    };
+}
+
+/// Distance from a point to the nearest point of the triangles,
+/// negative if the point is behind the triangle it is nearest to
+fn signed_distance(bvh: &Bvh, triangles: &[Triangle], point: &Vec3) -> f32 {
+    let (index, nearest) = match bvh.closest(triangles, point) {
+        Some(found) => found,
+        None => return 0.
+    };
+    let t = &triangles[index];
+    let normal = (t[1] - t[0]).cross(&(t[2] - t[0]));
+    let offset = point - nearest;
+    let distance = offset.norm();
+    if offset.dot(&normal) < 0. {
+        return -distance;
+    }
+    return distance;
+}
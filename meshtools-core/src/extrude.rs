@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+extern crate nalgebra_glm as glm;
+use glm::{Vec2, Vec3};
+
+use crate::i18n::tr;
+use crate::plane_cut;
+use crate::triangle::Triangle;
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// Most grid cells along the longest side of the silhouette, to keep the memory used in check
+pub const MAX_RESOLUTION: usize = 2048;
+
+/// What outline is extruded into a solid
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExtrudeMode {
+    /// The shape the model covers, seen along the direction, made flat
+    Silhouette,
+    /// The surface itself, given depth by a copy of it moved along the direction.
+    /// The surface should be open, such as a scanned relief.
+    Surface
+}
+
+impl ExtrudeMode {
+    pub const ALL: [ExtrudeMode; 2] = [ExtrudeMode::Silhouette, ExtrudeMode::Surface];
+
+    /// The translated name of the mode
+    pub fn label(&self) -> String {
+        match self {
+            ExtrudeMode::Silhouette => tr("extrude-silhouette"),
+            ExtrudeMode::Surface => tr("extrude-surface")
+        }
+    }
+}
+
+/// Two directions across the plane perpendicular to a direction, so that u × v is the direction
+fn plane_basis(direction: &Vec3) -> (Vec3, Vec3) {
+    let u = if direction.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+    let u = glm::cross(direction, &u).normalize();
+    let v = glm::cross(direction, &u);
+    return (u, v);
+}
+
+fn cross(a: &Vec2, b: &Vec2) -> f32 {
+    return a.x * b.y - a.y * b.x;
+}
+
+/// Samples on an even grid in a plane, set where a triangle covers them
+struct Coverage {
+    origin: Vec2,
+    spacing: f32,
+    size: [usize; 2],
+    filled: Vec<bool>
+}
+
+impl Coverage {
+    /// Samples are off outside the grid, so every outline closes
+    fn get(&self, i: usize, j: usize) -> bool {
+        return i < self.size[0] && j < self.size[1] && self.filled[j * self.size[0] + i];
+    }
+
+    /// Sets the samples inside a triangle, whichever way round it is
+    fn fill(&mut self, [a, b, c]: [Vec2; 3]) {
+        let area = cross(&(b - a), &(c - a));
+        if area == 0. {
+            return;
+        }
+        let min = a.inf(&b.inf(&c));
+        let max = a.sup(&b.sup(&c));
+        let start = |low: f32, axis: usize| ((low - self.origin[axis]) / self.spacing).ceil().max(0.) as usize;
+        let end = |high: f32, axis: usize| (((high - self.origin[axis]) / self.spacing).floor() as usize + 1).min(self.size[axis]);
+        for j in start(min.y, 1)..end(max.y, 1) {
+            for i in start(min.x, 0)..end(max.x, 0) {
+                let p = self.origin + Vec2::new(i as f32, j as f32) * self.spacing;
+                let sides = [cross(&(b - a), &(p - a)), cross(&(c - b), &(p - b)), cross(&(a - c), &(p - c))];
+                if sides.iter().all(|s| s * area >= 0.) {
+                    self.filled[j * self.size[0] + i] = true;
+                }
+            }
+        }
+    }
+
+    /// The outlines around the set samples (marching squares), as points on the grid in half steps.
+    /// Outlines go counterclockwise around covered areas and clockwise around holes,
+    /// and samples touching only at a corner are kept apart.
+    fn outlines(&self) -> Vec<Vec<[i64; 2]>> {
+        // Each crossing starts one segment and ends another, so the segments chain into loops
+        let mut next = HashMap::<[i64; 2], [i64; 2]>::new();
+        for j in 0..self.size[1] - 1 {
+            for i in 0..self.size[0] - 1 {
+                let corners = [self.get(i, j), self.get(i + 1, j), self.get(i + 1, j + 1), self.get(i, j + 1)];
+                let (x, y) = (2 * i as i64, 2 * j as i64);
+                let midpoints = [[x + 1, y], [x + 2, y + 1], [x + 1, y + 2], [x, y + 1]];
+                for k in 0..4 {
+                    // Crossing out of the covered corners, counterclockwise, the segment runs back
+                    // to the nearest side crossing into them, which keeps the covered corner on its left
+                    if !corners[k] || corners[(k + 1) % 4] {
+                        continue;
+                    }
+                    let end = (1..4).map(|back| (k + 4 - back) % 4)
+                        .find(|e| !corners[*e] && corners[(e + 1) % 4]);
+                    if let Some(end) = end {
+                        next.insert(midpoints[k], midpoints[end]);
+                    }
+                }
+            }
+        }
+        let mut starts: Vec<[i64; 2]> = next.keys().copied().collect();
+        starts.sort();
+        let mut loops = Vec::<Vec<[i64; 2]>>::new();
+        for start in starts {
+            let mut outline = Vec::<[i64; 2]>::new();
+            let mut current = start;
+            while let Some(following) = next.remove(&current) {
+                outline.push(current);
+                current = following;
+            }
+            if current == start {
+                loops.push(straighten(outline));
+            }
+        }
+        return loops.into_iter().filter(|outline| outline.len() >= 3).collect();
+    }
+}
+
+/// Leaves out points in line with the points on either side, so straight runs are single edges
+fn straighten(outline: Vec<[i64; 2]>) -> Vec<[i64; 2]> {
+    let count = outline.len();
+    return (0..count)
+        .filter(|i| {
+            let (a, b, c) = (outline[(i + count - 1) % count], outline[*i], outline[(i + 1) % count]);
+            return (b[0] - a[0]) * (c[1] - b[1]) != (b[1] - a[1]) * (c[0] - b[0]);
+        })
+        .map(|i| outline[i])
+        .collect();
+}
+
+/// Grid spacing for a silhouette with this many cells along its longest side
+pub fn grid_spacing(min: &Vec2, max: &Vec2, resolution: usize) -> f32 {
+    let extent = (max - min).max();
+    return if extent > 0. { extent / resolution.clamp(1, MAX_RESOLUTION) as f32 } else { 1. };
+}
+
+/// A closed solid in the shape of the area the triangles cover, seen along a direction.
+/// It starts level with the point of the triangles furthest back along the direction,
+/// and reaches `thickness` along it. The outline follows a grid with `resolution` cells
+/// along its longest side, with corners cut at 45°.
+/// Reports the fraction done, and stops early with None if that returns false.
+pub fn extrude_silhouette(triangles: &[Triangle], direction: &Vec3, thickness: f32, resolution: usize,
+    mut progress: impl FnMut(f32) -> bool) -> Option<Vec<Triangle>> {
+    profile_span!("extrude_silhouette");
+    let direction = direction.try_normalize(0.)?;
+    if triangles.is_empty() {
+        return None;
+    }
+    let (u, v) = plane_basis(&direction);
+    let flat = |p: &Vec3| Vec2::new(p.dot(&u), p.dot(&v));
+    let mut min = Vec2::repeat(f32::MAX);
+    let mut max = Vec2::repeat(f32::MIN);
+    let mut base = f32::MAX;
+    for p in triangles.iter().flatten() {
+        let q = flat(p);
+        min = min.inf(&q);
+        max = max.sup(&q);
+        base = base.min(p.dot(&direction));
+    }
+    let spacing = grid_spacing(&min, &max, resolution);
+    // A step of empty samples on every side
+    let size = [((max.x - min.x) / spacing).ceil() as usize + 3, ((max.y - min.y) / spacing).ceil() as usize + 3];
+    let mut coverage = Coverage {
+        origin: min - Vec2::repeat(spacing),
+        spacing,
+        size,
+        filled: vec![false; size[0] * size[1]]
+    };
+    for (i, triangle) in triangles.iter().enumerate() {
+        coverage.fill(triangle.map(|p| flat(&p)));
+        if i % 10_000 == 0 && !progress(i as f32 / triangles.len() as f32) {
+            return None;
+        }
+    }
+
+    let outlines = coverage.outlines();
+    let mut points = Vec::<Vec2>::new();
+    let polygons: Vec<Vec<(usize, Vec2)>> = outlines.iter()
+        .map(|outline| outline.iter()
+            .map(|[x, y]| {
+                let point = coverage.origin + Vec2::new(*x as f32, *y as f32) * (spacing / 2.);
+                points.push(point);
+                (points.len() - 1, point)
+            })
+            .collect())
+        .collect();
+    let place = |point: &Vec2, level: f32| u * point.x + v * point.y + direction * level;
+    let bottom: Vec<Vec3> = points.iter().map(|p| place(p, base)).collect();
+    let top: Vec<Vec3> = points.iter().map(|p| place(p, base + thickness)).collect();
+    let mut solid = Vec::<Triangle>::new();
+    // Counterclockwise across the plane faces along the direction, so the top is kept and the bottom turned over
+    for [a, b, c] in plane_cut::triangulate(polygons.clone()) {
+        solid.push([top[a], top[b], top[c]]);
+        solid.push([bottom[a], bottom[c], bottom[b]]);
+    }
+    // Covered area is on the left of each outline, so the walls face right
+    for polygon in &polygons {
+        for k in 0..polygon.len() {
+            let (a, b) = (polygon[k].0, polygon[(k + 1) % polygon.len()].0);
+            solid.push([bottom[a], bottom[b], top[b]]);
+            solid.push([bottom[a], top[b], top[a]]);
+        }
+    }
+    return Some(solid);
+}
+
+/// A closed solid made of an open surface, a copy of it moved `thickness` along a direction,
+/// and walls joining their edges. The surface is turned over if needed so the solid faces outward.
+/// None if the surface has no open edges to join.
+pub fn extrude_surface(triangles: &[Triangle], direction: &Vec3, thickness: f32) -> Option<Vec<Triangle>> {
+    profile_span!("extrude_surface");
+    let direction = direction.try_normalize(0.)?;
+    let mesh = TriangleMesh::new(triangles, triangle_mesh::default_tolerance(triangles));
+    let boundaries = mesh.boundary_loops();
+    if boundaries.is_empty() {
+        return None;
+    }
+    // The surface is the back of the solid, so it should face away from the direction
+    let facing: f32 = (0..mesh.triangles.len()).map(|i| mesh.area_normal(i).dot(&direction)).sum();
+    let flip = facing > 0.;
+    let shift = direction * thickness;
+    let mut solid = Vec::<Triangle>::with_capacity(mesh.triangles.len() * 2);
+    for [a, b, c] in mesh.to_triangles() {
+        if flip {
+            solid.push([a, c, b]);
+            solid.push([a + shift, b + shift, c + shift]);
+        } else {
+            solid.push([a, b, c]);
+            solid.push([a + shift, c + shift, b + shift]);
+        }
+    }
+    for boundary in &boundaries {
+        for k in 0..boundary.len() {
+            let (a, b) = (mesh.vertices[boundary[k]], mesh.vertices[boundary[(k + 1) % boundary.len()]]);
+            // Each wall takes the edge of the surface backward
+            let (a, b) = if flip { (b, a) } else { (a, b) };
+            solid.push([b, a, a + shift]);
+            solid.push([b, a + shift, b + shift]);
+        }
+    }
+    return Some(solid);
+}
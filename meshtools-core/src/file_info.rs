@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io::{BufReader, Error};
+extern crate nalgebra_glm as glm;
+use glm::{DVec3, Vec3};
+
+use crate::i18n::tr_args;
+use crate::mesh_file;
+use crate::triangle::{self, Triangle};
+
+/// Statistics of a mesh file, found without keeping its triangles in memory
+pub struct FileInfo {
+    pub triangle_count: usize,
+    /// The lowest and highest corners of the box around the mesh, if it has any triangles
+    pub bounds: Option<(Vec3, Vec3)>,
+    pub area: f64,
+    /// The enclosed volume, which is only meaningful for closed meshes.
+    /// It is negative if the triangles face inward.
+    pub volume: f64
+}
+
+impl FileInfo {
+    fn new() -> Self {
+        return Self { triangle_count: 0, bounds: None, area: 0., volume: 0. };
+    }
+
+    /// The statistics of triangles already in memory
+    pub fn from_triangles(triangles: &[Triangle]) -> Self {
+        let mut info = Self::new();
+        info.add(triangles);
+        return info;
+    }
+
+    fn add(&mut self, triangles: &[Triangle]) {
+        for triangle in triangles {
+            let (mut min, mut max) = self.bounds.unwrap_or((triangle[0], triangle[0]));
+            for vertex in triangle {
+                min = glm::min2(&min, vertex);
+                max = glm::max2(&max, vertex);
+            }
+            self.bounds = Some((min, max));
+            // Sums of millions of small values lose too much in single precision
+            let [a, b, c] = triangle.map(|v| DVec3::new(v.x as f64, v.y as f64, v.z as f64));
+            let cross = glm::cross(&(b - a), &(c - a));
+            self.area += cross.norm() / 2.;
+            self.volume += glm::dot(&a, &glm::cross(&b, &c)) / 6.;
+        }
+        self.triangle_count += triangles.len();
+    }
+
+    /// The statistics as translated lines of text, with lengths in the given unit,
+    /// or without units if the suffix is empty
+    pub fn lines(&self, suffix: &str) -> Vec<String> {
+        let with_unit = |value: f64, power: &str| {
+            if suffix.is_empty() { format!("{:.3}", value) } else { format!("{:.3} {}{}", value, suffix, power) }
+        };
+        let mut lines = vec![tr_args("info-triangles", &[("count", &self.triangle_count)])];
+        if let Some((min, max)) = self.bounds {
+            let size = max - min;
+            let vector = |v: &Vec3| format!("{:.3}, {:.3}, {:.3}", v.x, v.y, v.z);
+            lines.push(tr_args("info-size", &[
+                ("x", &with_unit(size.x as f64, "")),
+                ("y", &with_unit(size.y as f64, "")),
+                ("z", &with_unit(size.z as f64, ""))]));
+            lines.push(tr_args("info-bounds", &[("min", &vector(&min)), ("max", &vector(&max))]));
+        }
+        lines.push(tr_args("info-area", &[("area", &with_unit(self.area, "²"))]));
+        lines.push(tr_args("info-volume", &[("volume", &with_unit(self.volume, "³"))]));
+        return lines;
+    }
+}
+
+/// Finds the statistics of a mesh file, reporting the fraction read so far.
+///
+/// Binary STL files are streamed, so they can be far larger than memory.
+/// Other formats are loaded whole first.
+/// Reading stops with an error if the progress callback returns false.
+pub fn file_info(path: &str, mut progress: impl FnMut(f32) -> bool) -> Result<FileInfo, Error> {
+    profile_span!("file_info");
+    let mut info = FileInfo::new();
+    if !path.to_lowercase().ends_with(".stl") {
+        info.add(&mesh_file::read_mesh_file_with_progress(path, progress)?);
+        return Ok(info);
+    }
+    let mut input = BufReader::with_capacity(1 << 20, File::open(path)?);
+    triangle::stream_stl_binary_from(&mut input, |chunk, fraction| {
+        info.add(chunk);
+        return progress(fraction);
+    })?;
+    return Ok(info);
+}
@@ -0,0 +1,204 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat3, Vec3};
+
+use crate::i18n::tr;
+use crate::json;
+use crate::version;
+
+/// The kinds of surface a region can be fitted with
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PrimitiveKind {
+    Plane,
+    Cylinder,
+    Sphere
+}
+
+impl PrimitiveKind {
+    pub const ALL: [PrimitiveKind; 3] = [PrimitiveKind::Plane, PrimitiveKind::Cylinder, PrimitiveKind::Sphere];
+
+    /// The name used in exported reports
+    pub fn code(&self) -> &'static str {
+        match self {
+            PrimitiveKind::Plane => "plane",
+            PrimitiveKind::Cylinder => "cylinder",
+            PrimitiveKind::Sphere => "sphere"
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            PrimitiveKind::Plane => tr("fit-plane"),
+            PrimitiveKind::Cylinder => tr("fit-cylinder"),
+            PrimitiveKind::Sphere => tr("fit-sphere")
+        }
+    }
+}
+
+/// A simple surface which CAD can rebuild exactly
+#[derive(Clone, PartialEq, Debug)]
+pub enum Primitive {
+    /// The plane through a point, facing along a unit normal
+    Plane { point: Vec3, normal: Vec3 },
+    /// An infinite cylinder around the line through a point along a unit axis
+    Cylinder { point: Vec3, axis: Vec3, radius: f32 },
+    Sphere { center: Vec3, radius: f32 }
+}
+
+impl Primitive {
+    pub fn kind(&self) -> PrimitiveKind {
+        match self {
+            Primitive::Plane { .. } => PrimitiveKind::Plane,
+            Primitive::Cylinder { .. } => PrimitiveKind::Cylinder,
+            Primitive::Sphere { .. } => PrimitiveKind::Sphere
+        }
+    }
+
+    /// Fits a primitive to points sampled from a surface, with the surface's normals there.
+    /// Cylinders need the normals to find their axis.
+    pub fn fit(kind: PrimitiveKind, points: &[Vec3], normals: &[Vec3]) -> Option<Self> {
+        return match kind {
+            PrimitiveKind::Plane => fit_plane(points),
+            PrimitiveKind::Cylinder => fit_cylinder(points, normals),
+            PrimitiveKind::Sphere => fit_sphere(points)
+        };
+    }
+
+    /// How far a point is from the surface
+    pub fn distance(&self, p: &Vec3) -> f32 {
+        return match self {
+            Primitive::Plane { point, normal } => (p - point).dot(normal).abs(),
+            Primitive::Cylinder { point, axis, radius } => {
+                let offset = p - point;
+                ((offset - axis * offset.dot(axis)).norm() - radius).abs()
+            },
+            Primitive::Sphere { center, radius } => (glm::distance(p, center) - radius).abs()
+        };
+    }
+
+    /// Root mean square distance of points from the surface
+    pub fn rms(&self, points: &[Vec3]) -> f32 {
+        if points.is_empty() {
+            return 0.;
+        }
+        let squares: f32 = points.iter().map(|p| self.distance(p).powi(2)).sum();
+        return (squares / points.len() as f32).sqrt();
+    }
+
+    /// The parameters of the primitive as JSON object entries, without the braces
+    fn json_entries(&self) -> String {
+        let vector = |v: &Vec3| format!("[{}, {}, {}]", v.x, v.y, v.z);
+        return match self {
+            Primitive::Plane { point, normal } => format!(
+                r#""point": {}, "normal": {}, "d": {}"#, vector(point), vector(normal), -normal.dot(point)),
+            Primitive::Cylinder { point, axis, radius } => format!(
+                r#""point": {}, "axis": {}, "radius": {}"#, vector(point), vector(axis), radius),
+            Primitive::Sphere { center, radius } => format!(
+                r#""center": {}, "radius": {}"#, vector(center), radius)
+        };
+    }
+}
+
+/// A primitive fitted to a region of a model, with how well it fits
+#[derive(Clone, PartialEq, Debug)]
+pub struct FittedPrimitive {
+    pub name: String,
+    pub primitive: Primitive,
+    /// Triangles in the region it was fitted to
+    pub triangles: usize,
+    /// Root mean square distance of the region's vertices from the primitive
+    pub rms: f32,
+    /// Largest distance of a vertex of the region from the primitive
+    pub max_error: f32
+}
+
+impl FittedPrimitive {
+    pub fn to_json(&self) -> String {
+        return format!(r#"{{"name": {}, "type": "{}", {}, "triangles": {}, "rms": {}, "max_error": {}}}"#,
+            json::quote(&self.name), self.primitive.kind().code(), self.primitive.json_entries(),
+            self.triangles, self.rms, self.max_error);
+    }
+}
+
+/// Writes fitted primitives to a JSON report, in scene coordinates of the given unit,
+/// with the version of MeshTools that fitted them
+pub fn write_report(path: &str, primitives: &[FittedPrimitive], unit: &str) -> Result<(), std::io::Error> {
+    let entries: Vec<String> = primitives.iter().map(|p| format!("    {}", p.to_json())).collect();
+    let text = format!("{{\n  {}: {},\n  {}: {},\n  {}: [\n{}\n  ]\n}}\n",
+        json::quote("generator"), json::quote(&version::description()),
+        json::quote("unit"), json::quote(unit),
+        json::quote("primitives"), entries.join(",\n"));
+    return std::fs::write(path, text);
+}
+
+fn centroid(points: &[Vec3]) -> Vec3 {
+    return points.iter().sum::<Vec3>() / points.len() as f32;
+}
+
+/// The unit direction along which vectors spread the least
+fn least_spread(vectors: impl Iterator<Item = Vec3>) -> Option<Vec3> {
+    let covariance = vectors.fold(Mat3::zeros(), |sum, v| sum + v * v.transpose());
+    let eigen = covariance.symmetric_eigen();
+    let smallest = eigen.eigenvalues.imin();
+    return eigen.eigenvectors.column(smallest).into_owned().try_normalize(f32::EPSILON);
+}
+
+fn fit_plane(points: &[Vec3]) -> Option<Primitive> {
+    if points.len() < 3 {
+        return None;
+    }
+    let point = centroid(points);
+    let normal = least_spread(points.iter().map(|p| p - point))?;
+    return Some(Primitive::Plane { point, normal });
+}
+
+fn fit_sphere(points: &[Vec3]) -> Option<Primitive> {
+    if points.len() < 4 {
+        return None;
+    }
+    // Points on the sphere satisfy 2c·p + (r² - |c|²) = |p|², which is linear in c and the constant.
+    // Centered on the centroid to keep the least squares well conditioned.
+    let origin = centroid(points);
+    let mut normal_matrix = glm::Mat4::zeros();
+    let mut rhs = glm::Vec4::zeros();
+    for p in points {
+        let p = p - origin;
+        let row = glm::Vec4::new(2. * p.x, 2. * p.y, 2. * p.z, 1.);
+        normal_matrix += row * row.transpose();
+        rhs += row * p.norm_squared();
+    }
+    let solution = normal_matrix.try_inverse()? * rhs;
+    let center = solution.xyz();
+    let radius_squared = solution.w + center.norm_squared();
+    if radius_squared <= 0. {
+        return None;
+    }
+    return Some(Primitive::Sphere { center: center + origin, radius: radius_squared.sqrt() });
+}
+
+fn fit_cylinder(points: &[Vec3], normals: &[Vec3]) -> Option<Primitive> {
+    if points.len() < 3 {
+        return None;
+    }
+    // The normals of a cylinder are all perpendicular to its axis
+    let axis = least_spread(normals.iter().copied())?;
+    // The points then lie on a circle in the plane across the axis
+    let u = axis.cross(&if axis.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() }).normalize();
+    let v = axis.cross(&u);
+    let origin = centroid(points);
+    let mut normal_matrix = glm::Mat3::zeros();
+    let mut rhs = Vec3::zeros();
+    for p in points {
+        let offset = p - origin;
+        let (x, y) = (offset.dot(&u), offset.dot(&v));
+        let row = Vec3::new(2. * x, 2. * y, 1.);
+        normal_matrix += row * row.transpose();
+        rhs += row * (x * x + y * y);
+    }
+    let solution = normal_matrix.try_inverse()? * rhs;
+    let radius_squared = solution.z + solution.x * solution.x + solution.y * solution.y;
+    if radius_squared <= 0. {
+        return None;
+    }
+    let point = origin + u * solution.x + v * solution.y;
+    return Some(Primitive::Cylinder { point, axis, radius: radius_squared.sqrt() });
+}
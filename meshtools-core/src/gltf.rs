@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::Write;
+extern crate nalgebra_glm as glm;
+use glm::{Vec3, Mat4};
+
+use crate::json;
+use crate::triangle::Triangle;
+use crate::version;
+
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F534A;
+const CHUNK_BIN: u32 = 0x004E4942;
+const ARRAY_BUFFER: u32 = 34962;
+const FLOAT: u32 = 5126;
+
+/// Rotates the Z-up coordinates used by STL files into glTF's Y-up convention
+fn z_up_to_y_up() -> Mat4 {
+    return glm::rotate_x(&Mat4::identity(), -std::f32::consts::FRAC_PI_2);
+}
+
+fn write_f32s(buffer: &mut Vec<u8>, values: &[f32]) {
+    for value in values {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn format_vec3(vector: &Vec3) -> String {
+    return format!("[{},{},{}]", vector.x, vector.y, vector.z);
+}
+
+fn format_matrix(matrix: &Mat4) -> String {
+    // glTF matrices are column-major, which matches nalgebra's storage order
+    let values: Vec<String> = matrix.as_slice().iter().map(|v| v.to_string()).collect();
+    return format!("[{}]", values.join(","));
+}
+
+/// Writes a list of models to a binary glTF (GLB) file.
+///
+/// Each model becomes a mesh with flat normals in its own node,
+/// using the model's matrix as the node transformation.
+/// All nodes are children of a root node which converts from Z-up to Y-up.
+/// Triangles with zero area are skipped.
+pub fn write_glb(
+    path: &str,
+    models: &[(&[Triangle], Mat4)])
+-> Result<(), std::io::Error> {
+    let mut binary = Vec::<u8>::new();
+    let mut buffer_views = Vec::<String>::new();
+    let mut accessors = Vec::<String>::new();
+    let mut meshes = Vec::<String>::new();
+    let mut nodes = Vec::<String>::new();
+    for (i, (triangles, matrix)) in models.iter().enumerate() {
+        let mut positions = Vec::<f32>::new();
+        let mut normals = Vec::<f32>::new();
+        let mut min = Vec3::repeat(f32::MAX);
+        let mut max = Vec3::repeat(f32::MIN);
+        for t in triangles.iter() {
+            let cross_product = glm::cross(&(t[1] - t[0]), &(t[2] - t[0]));
+            if glm::dot(&cross_product, &cross_product) <= 0.0 {
+                continue;
+            }
+            let normal = cross_product.normalize();
+            for v in t {
+                positions.extend_from_slice(&[v.x, v.y, v.z]);
+                normals.extend_from_slice(&[normal.x, normal.y, normal.z]);
+                min = glm::min2(&min, v);
+                max = glm::max2(&max, v);
+            }
+        }
+        let vertex_count = positions.len() / 3;
+        if vertex_count == 0 {
+            min = Vec3::zeros();
+            max = Vec3::zeros();
+        }
+        let mesh_index = meshes.len();
+        for (attribute, values) in [positions, normals].iter().enumerate() {
+            let view_index = buffer_views.len();
+            buffer_views.push(format!(
+                r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+                binary.len(), values.len() * 4, ARRAY_BUFFER));
+            write_f32s(&mut binary, values);
+            let bounds = if attribute == 0 {
+                format!(r#","min":{},"max":{}"#, format_vec3(&min), format_vec3(&max))
+            } else {
+                String::new()
+            };
+            accessors.push(format!(
+                r#"{{"bufferView":{},"componentType":{},"count":{},"type":"VEC3"{}}}"#,
+                view_index, FLOAT, vertex_count, bounds));
+        }
+        meshes.push(format!(
+            r#"{{"name":"model {}","primitives":[{{"attributes":{{"POSITION":{},"NORMAL":{}}},"mode":4}}]}}"#,
+            i, mesh_index * 2, mesh_index * 2 + 1));
+        nodes.push(format!(
+            r#"{{"name":"model {}","mesh":{},"matrix":{}}}"#,
+            i, mesh_index, format_matrix(matrix)));
+    }
+    let children: Vec<String> = (1..=nodes.len()).map(|i| i.to_string()).collect();
+    nodes.insert(0, format!(
+        r#"{{"name":"root","children":[{}],"matrix":{}}}"#,
+        children.join(","), format_matrix(&z_up_to_y_up())));
+
+    let mut json = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":{}}},"#,
+            r#""scene":0,"scenes":[{{"nodes":[0]}}],"#,
+            r#""nodes":[{}],"meshes":[{}],"accessors":[{}],"bufferViews":[{}],"#,
+            r#""buffers":[{{"byteLength":{}}}]}}"#),
+        json::quote(&version::description()),
+        nodes.join(","), meshes.join(","), accessors.join(","),
+        buffer_views.join(","), binary.len());
+    // Chunks must be 4 byte aligned, JSON with spaces and binary with zeros
+    while json.len() % 4 != 0 {
+        json.push(' ');
+    }
+    binary.resize(binary.len().next_multiple_of(4), 0);
+
+    let total_length = 12 + 8 + json.len() + 8 + binary.len();
+    let mut output = File::create(path)?;
+    output.write_all(&GLB_MAGIC.to_le_bytes())?;
+    output.write_all(&GLB_VERSION.to_le_bytes())?;
+    output.write_all(&(total_length as u32).to_le_bytes())?;
+    output.write_all(&(json.len() as u32).to_le_bytes())?;
+    output.write_all(&CHUNK_JSON.to_le_bytes())?;
+    output.write_all(json.as_bytes())?;
+    output.write_all(&(binary.len() as u32).to_le_bytes())?;
+    output.write_all(&CHUNK_BIN.to_le_bytes())?;
+    output.write_all(&binary)?;
+    return Ok(());
+}
@@ -0,0 +1,95 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::triangle::Triangle;
+
+/// Brightness sampled on a grid of pixels, from 0 for black to 1 for white
+pub struct Heightmap {
+    pub width: usize,
+    pub height: usize,
+    /// Row by row from the top of the image
+    pub values: Vec<f32>
+}
+
+impl Heightmap {
+    /// Reads an image, such as a grayscale PNG, as its brightness.
+    /// Transparent pixels count as black.
+    pub fn read(path: &str) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.into_luma_alpha16();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let values = image.pixels()
+            .map(|pixel| {
+                let [luma, alpha] = pixel.0;
+                return luma as f32 / u16::MAX as f32 * (alpha as f32 / u16::MAX as f32);
+            })
+            .collect();
+        return Ok(Self { width, height, values });
+    }
+
+    fn value(&self, i: usize, j: usize) -> f32 {
+        return self.values[j * self.width + i];
+    }
+
+    /// The heightmap with each square of `step` pixels averaged into one, for images with more pixels than are needed
+    pub fn downsampled(&self, step: usize) -> Self {
+        if step <= 1 {
+            return Self { width: self.width, height: self.height, values: self.values.clone() };
+        }
+        let (width, height) = (self.width.div_ceil(step), self.height.div_ceil(step));
+        let mut values = Vec::with_capacity(width * height);
+        for j in 0..height {
+            for i in 0..width {
+                let rows = j * step..((j + 1) * step).min(self.height);
+                let columns = i * step..((i + 1) * step).min(self.width);
+                let count = rows.len() * columns.len();
+                let sum: f32 = rows.flat_map(|y| columns.clone().map(move |x| (x, y)))
+                    .map(|(x, y)| self.value(x, y))
+                    .sum();
+                values.push(sum / count as f32);
+            }
+        }
+        return Self { width, height, values };
+    }
+
+    /// A closed solid standing on Z = 0, with its top at each pixel's height: `min_height` for black
+    /// and `max_height` for white, or the other way round if inverted, as for a lithophane.
+    /// Pixels are `pixel_size` apart, and the top of the image is toward +Y.
+    /// Empty if there are fewer than two pixels in a row or a column.
+    pub fn to_triangles(&self, pixel_size: f32, min_height: f32, max_height: f32, invert: bool) -> Vec<Triangle> {
+        profile_span!("heightmap_triangles");
+        let (width, height) = (self.width, self.height);
+        if width < 2 || height < 2 {
+            return Vec::new();
+        }
+        let top = |i: usize, j: usize| {
+            let value = if invert { 1. - self.value(i, j) } else { self.value(i, j) };
+            return Vec3::new(i as f32 * pixel_size, (height - 1 - j) as f32 * pixel_size,
+                min_height + value * (max_height - min_height));
+        };
+        let bottom = |i: usize, j: usize| Vec3::new(i as f32 * pixel_size, (height - 1 - j) as f32 * pixel_size, 0.);
+        let mut triangles = Vec::<Triangle>::with_capacity((width - 1) * (height - 1) * 2 + (width + height) * 6);
+        for j in 0..height - 1 {
+            for i in 0..width - 1 {
+                // Counterclockwise from above, with rows going down the image
+                let (a, b, c, d) = (top(i, j + 1), top(i + 1, j + 1), top(i + 1, j), top(i, j));
+                triangles.push([a, b, c]);
+                triangles.push([a, c, d]);
+            }
+        }
+        // The pixels around the edge, counterclockwise from above
+        let mut edge = Vec::<(usize, usize)>::with_capacity((width + height) * 2);
+        edge.extend((0..width - 1).map(|i| (i, height - 1)));
+        edge.extend((1..height).rev().map(|j| (width - 1, j)));
+        edge.extend((1..width).rev().map(|i| (i, 0)));
+        edge.extend((0..height - 1).map(|j| (0, j)));
+        // The base is a fan from its center, which is in line with none of its sides
+        let center = Vec3::new((width - 1) as f32, (height - 1) as f32, 0.) * (pixel_size / 2.);
+        for k in 0..edge.len() {
+            let (p, q) = (edge[k], edge[(k + 1) % edge.len()]);
+            triangles.push([bottom(p.0, p.1), bottom(q.0, q.1), top(q.0, q.1)]);
+            triangles.push([bottom(p.0, p.1), top(q.0, q.1), top(p.0, p.1)]);
+            triangles.push([center, bottom(q.0, q.1), bottom(p.0, p.1)]);
+        }
+        return triangles;
+    }
+}
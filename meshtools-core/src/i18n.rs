@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A language with a bundled translation of the UI strings.
+///
+/// Translations live in this crate's `locales/<code>.ftl` as `key = value` lines,
+/// where values may contain `{ $name }` placeholders filled in by `tr_args`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    English,
+    Spanish
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    /// The language code used for the locale file and in the settings
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Spanish => "es"
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        return Language::ALL.into_iter().find(|l| code.starts_with(l.code()));
+    }
+
+    /// The name of the language, written in that language
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español"
+        }
+    }
+
+    /// Picks a language from the LANG environment variable, if it is supported
+    pub fn from_environment() -> Option<Self> {
+        return Language::from_code(&std::env::var("LANG").ok()?);
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.ftl"),
+            Language::Spanish => include_str!("../locales/es.ftl")
+        }
+    }
+
+    fn index(&self) -> usize {
+        return Language::ALL.iter().position(|l| l == self).unwrap();
+    }
+}
+
+static LANGUAGE: AtomicUsize = AtomicUsize::new(0);
+static BUNDLES: OnceLock<Vec<HashMap<&'static str, String>>> = OnceLock::new();
+
+/// Parses a locale file into a map of keys to messages.
+///
+/// Blank lines and lines starting with `#` are ignored.
+/// `\n` and `\t` in messages are replaced with newlines and tabs.
+fn parse_locale(source: &'static str) -> HashMap<&'static str, String> {
+    let mut messages = HashMap::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().replace("\\n", "\n").replace("\\t", "\t");
+            messages.insert(key.trim(), value);
+        }
+    }
+    return messages;
+}
+
+fn bundles() -> &'static Vec<HashMap<&'static str, String>> {
+    return BUNDLES.get_or_init(|| {
+        Language::ALL.iter().map(|l| parse_locale(l.source())).collect()
+    });
+}
+
+/// Sets the language used by `tr` and `tr_args`
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language.index(), Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    return Language::ALL[LANGUAGE.load(Ordering::Relaxed)];
+}
+
+/// Translates a message key into the current language.
+///
+/// Falls back to English if the key is missing from the current language,
+/// and to the key itself if it is missing from English as well.
+pub fn tr(key: &str) -> String {
+    let bundles = bundles();
+    return bundles[language().index()].get(key)
+        .or_else(|| bundles[Language::English.index()].get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string());
+}
+
+/// Translates a message key, replacing each `{ $name }` placeholder
+/// with the matching argument.
+pub fn tr_args(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut message = tr(key);
+    for (name, value) in args {
+        message = message.replace(&format!("{{ ${} }}", name), &value.to_string());
+    }
+    return message;
+}
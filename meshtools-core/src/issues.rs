@@ -0,0 +1,256 @@
+use std::fs::File;
+use std::io::Write;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::bvh::Bvh;
+use crate::i18n::tr;
+use crate::spatial_grid::{self, SpatialGrid};
+use crate::triangle_mesh::{HoleFill, TriangleMesh};
+use crate::version;
+
+/// Most issues of each kind that will be listed, so a badly broken
+/// mesh doesn't produce an unusably long list
+pub const MAX_ISSUES_PER_KIND: usize = 500;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IssueKind {
+    Hole,
+    NonManifoldEdge,
+    Intersection,
+    ThinWall,
+    FlippedNormals
+}
+
+impl IssueKind {
+    pub const ALL: [IssueKind; 5] = [
+        IssueKind::Hole,
+        IssueKind::NonManifoldEdge,
+        IssueKind::Intersection,
+        IssueKind::ThinWall,
+        IssueKind::FlippedNormals];
+
+    /// The name used for the issue in exported lists
+    pub fn code(&self) -> &'static str {
+        match self {
+            IssueKind::Hole => "hole",
+            IssueKind::NonManifoldEdge => "non-manifold-edge",
+            IssueKind::Intersection => "intersection",
+            IssueKind::ThinWall => "thin-wall",
+            IssueKind::FlippedNormals => "flipped-normals"
+        }
+    }
+
+    /// The translated name of the kind, for headings and reports
+    pub fn label(&self) -> String {
+        match self {
+            IssueKind::Hole => tr("issues-holes"),
+            IssueKind::NonManifoldEdge => tr("issues-non-manifold"),
+            IssueKind::Intersection => tr("issues-intersections"),
+            IssueKind::ThinWall => tr("issues-thin-walls"),
+            IssueKind::FlippedNormals => tr("issues-flipped")
+        }
+    }
+}
+
+/// A change to the mesh which resolves an issue
+pub enum Fix {
+    /// Close a boundary loop of vertices
+    Fill(Vec<usize>),
+    /// Reverse the winding of these triangles
+    Flip(Vec<usize>),
+    /// Delete these triangles
+    Remove(Vec<usize>)
+}
+
+/// A problem found in a mesh, and where to find it
+pub struct Issue {
+    pub kind: IssueKind,
+    /// Perimeter of a hole, length of an edge, thickness of a wall,
+    /// or number of flipped triangles
+    pub size: f32,
+    /// Area enclosed by a hole
+    pub area: Option<f32>,
+    pub center: Vec3,
+    pub radius: f32,
+    /// Closed loops to highlight the issue with
+    pub outlines: Vec<Vec<Vec3>>,
+    pub fix: Option<Fix>
+}
+
+impl Issue {
+    /// Applies the fix for this issue to the mesh it was found in,
+    /// closing holes the given way. Returns false if there is no fix.
+    pub fn apply_fix(&self, mesh: &mut TriangleMesh, fill: HoleFill) -> bool {
+        match &self.fix {
+            Some(Fix::Fill(boundary)) => mesh.fill_loop(boundary, fill),
+            Some(Fix::Flip(triangles)) => {
+                for t in triangles {
+                    mesh.flip(*t);
+                }
+            },
+            Some(Fix::Remove(triangles)) => mesh.remove_triangles(triangles),
+            None => return false
+        }
+        return true;
+    }
+}
+
+fn triangle_outlines(mesh: &TriangleMesh, triangles: &[usize]) -> Vec<Vec<Vec3>> {
+    return triangles.iter()
+        .take(MAX_ISSUES_PER_KIND)
+        .map(|t| mesh.triangle(*t).to_vec())
+        .collect();
+}
+
+/// Center and radius of a sphere around some points
+fn bounding_sphere(points: impl Iterator<Item = Vec3>) -> (Vec3, f32) {
+    let mut min = Vec3::repeat(f32::MAX);
+    let mut max = Vec3::repeat(f32::MIN);
+    for point in points {
+        min = glm::min2(&min, &point);
+        max = glm::max2(&max, &point);
+    }
+    return ((min + max) / 2., (max - min).norm() / 2.);
+}
+
+fn triangle_group_issue(mesh: &TriangleMesh, kind: IssueKind, size: f32, triangles: Vec<usize>, fix: Option<Fix>)
+-> Issue {
+    let (center, radius) = bounding_sphere(
+        triangles.iter().flat_map(|t| mesh.triangle(*t)));
+    return Issue {
+        kind,
+        size,
+        area: None,
+        center,
+        radius,
+        outlines: triangle_outlines(mesh, &triangles),
+        fix
+    };
+}
+
+fn find_holes(mesh: &TriangleMesh) -> Vec<Issue> {
+    profile_span!("find_holes");
+    return mesh.holes().into_iter().map(|hole| Issue {
+        kind: IssueKind::Hole,
+        size: hole.perimeter,
+        area: Some(hole.area),
+        center: hole.center,
+        radius: hole.radius,
+        outlines: vec![hole.vertices.iter().map(|v| mesh.vertices[*v]).collect()],
+        fix: Some(Fix::Fill(hole.vertices))
+    }).collect();
+}
+
+fn find_non_manifold_edges(mesh: &TriangleMesh) -> Vec<Issue> {
+    profile_span!("find_non_manifold_edges");
+    return mesh.non_manifold_edges().into_iter().map(|((a, b), faces)| {
+        let (a, b) = (mesh.vertices[a], mesh.vertices[b]);
+        Issue {
+            kind: IssueKind::NonManifoldEdge,
+            size: (b - a).norm(),
+            area: None,
+            center: (a + b) / 2.,
+            radius: (b - a).norm() / 2.,
+            outlines: vec![vec![a, b]],
+            // Keep the first two faces, which is usually the original surface
+            fix: Some(Fix::Remove(faces[2..].to_vec()))
+        }
+    }).collect();
+}
+
+fn find_intersections(mesh: &TriangleMesh) -> Vec<Issue> {
+    profile_span!("find_intersections");
+    let triangles = mesh.to_triangles();
+    let bvh = Bvh::new(&triangles);
+    let mut issues = Vec::<Issue>::new();
+    bvh.overlapping_self_pairs(&triangles, |a, b| {
+        // Neighboring triangles touch, but that doesn't count as crossing
+        let shares_vertex = mesh.triangles[a].iter().any(|v| mesh.triangles[b].contains(v));
+        if !shares_vertex && spatial_grid::triangles_intersect(&triangles[a], &triangles[b]) {
+            issues.push(triangle_group_issue(mesh, IssueKind::Intersection, 0., vec![a, b], None));
+        }
+        return issues.len() < MAX_ISSUES_PER_KIND;
+    });
+    return issues;
+}
+
+/// Finds regions where a ray cast inward from a triangle hits the
+/// back of the opposite wall within the threshold distance
+fn find_thin_walls(mesh: &TriangleMesh, grid: &SpatialGrid, threshold: f32) -> Vec<Issue> {
+    profile_span!("find_thin_walls");
+    let mut thin = Vec::<usize>::new();
+    let mut thickness = vec![f32::MAX; mesh.triangles.len()];
+    for (i, triangle_thickness) in thickness.iter_mut().enumerate() {
+        let normal = mesh.area_normal(i);
+        if normal.norm_squared() == 0. {
+            continue;
+        }
+        let normal = normal.normalize();
+        let [a, b, c] = mesh.triangle(i);
+        let start = (a + b + c) / 3.;
+        let end = start - normal * threshold;
+        for j in grid.query(&glm::min2(&start, &end), &glm::max2(&start, &end)) {
+            let shares_vertex = mesh.triangles[i].iter().any(|v| mesh.triangles[j].contains(v));
+            if shares_vertex || glm::dot(&mesh.area_normal(j), &normal) >= 0. {
+                continue;
+            }
+            if let Some(t) = spatial_grid::segment_triangle(&start, &end, &mesh.triangle(j)) {
+                *triangle_thickness = triangle_thickness.min(t * threshold);
+            }
+        }
+        if *triangle_thickness < threshold {
+            thin.push(i);
+        }
+    }
+    return mesh.connected_groups(&thin).into_iter()
+        .take(MAX_ISSUES_PER_KIND)
+        .map(|group| {
+            let thinnest = group.iter().map(|t| thickness[*t]).fold(f32::MAX, f32::min);
+            triangle_group_issue(mesh, IssueKind::ThinWall, thinnest, group, None)
+        })
+        .collect();
+}
+
+fn find_flipped_normals(mesh: &TriangleMesh) -> Vec<Issue> {
+    profile_span!("find_flipped_normals");
+    return mesh.connected_groups(&mesh.flipped_triangles()).into_iter()
+        .take(MAX_ISSUES_PER_KIND)
+        .map(|group| {
+            let count = group.len() as f32;
+            triangle_group_issue(mesh, IssueKind::FlippedNormals, count, group.clone(), Some(Fix::Flip(group)))
+        })
+        .collect();
+}
+
+/// Runs every check on a mesh, listing the issues in the order of `IssueKind::ALL`
+///
+/// Walls thinner than the threshold are reported as thin.
+pub fn find_issues(mesh: &TriangleMesh, thin_wall_threshold: f32) -> Vec<Issue> {
+    profile_span!("find_issues");
+    let mut issues = find_holes(mesh);
+    issues.truncate(MAX_ISSUES_PER_KIND);
+    issues.extend(find_non_manifold_edges(mesh).into_iter().take(MAX_ISSUES_PER_KIND));
+    issues.extend(find_intersections(mesh));
+    if thin_wall_threshold > 0. {
+        let grid = SpatialGrid::new(mesh,
+            SpatialGrid::suggested_cell_size(mesh).max(thin_wall_threshold));
+        issues.extend(find_thin_walls(mesh, &grid, thin_wall_threshold));
+    }
+    issues.extend(find_flipped_normals(mesh));
+    return issues;
+}
+
+/// Writes issues to a CSV file with their kind, location, size, and area,
+/// after a line naming the version of MeshTools that found them
+pub fn write_csv(path: &str, issues: &[Issue]) -> Result<(), std::io::Error> {
+    let mut output = File::create(path)?;
+    writeln!(output, "generator,{}", version::description())?;
+    writeln!(output, "kind,x,y,z,size,area")?;
+    for issue in issues {
+        let area = issue.area.map(|a| a.to_string()).unwrap_or_default();
+        writeln!(output, "{},{},{},{},{},{}",
+            issue.kind.code(), issue.center.x, issue.center.y, issue.center.z, issue.size, area)?;
+    }
+    return Ok(());
+}
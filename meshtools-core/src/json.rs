@@ -0,0 +1,219 @@
+/// A parsed JSON value. Only as much of JSON as the app's own files need,
+/// which is all of it apart from unicode escapes outside the basic plane.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keys and values in the order they were written
+    Object(Vec<(String, Json)>)
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser { chars: text.chars().collect(), position: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("end of input"));
+        }
+        return Ok(value);
+    }
+
+    /// The value of a key, if this is an object that has it
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(array) => Some(array),
+            _ => None
+        }
+    }
+
+    /// The numbers in an array, if it only holds numbers
+    pub fn as_f32s(&self) -> Option<Vec<f32>> {
+        return self.as_array()?.iter().map(|v| v.as_f64().map(|n| n as f32)).collect();
+    }
+}
+
+/// Quotes a string for writing into JSON
+pub fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize
+}
+
+impl Parser {
+    fn error(&self, expected: &str) -> String {
+        return format!("Expected {} at character {}", expected, self.position);
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.chars.len() && self.chars[self.position].is_whitespace() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        return self.chars.get(self.position).copied();
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("'{}'", c)));
+        }
+        self.position += 1;
+        return Ok(());
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for c in word.chars() {
+            if self.chars.get(self.position) != Some(&c) {
+                return Err(self.error(word));
+            }
+            self.position += 1;
+        }
+        return Ok(value);
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('n') => self.keyword("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.error("a value"))
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut entries = Vec::<(String, Json)>::new();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            if self.peek() != Some('"') {
+                return Err(self.error("a key"));
+            }
+            let key = self.string()?;
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(entries));
+                },
+                _ => return Err(self.error("',' or '}'"))
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::<Json>::new();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                },
+                _ => return Err(self.error("',' or ']'"))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let c = *self.chars.get(self.position).ok_or_else(|| self.error("'\"'"))?;
+            self.position += 1;
+            match c {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escaped = *self.chars.get(self.position).ok_or_else(|| self.error("an escape"))?;
+                    self.position += 1;
+                    string.push(match escaped {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => {
+                            let digits: String = self.chars.iter().skip(self.position).take(4).collect();
+                            self.position += 4;
+                            u32::from_str_radix(&digits, 16).ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("four hex digits"))?
+                        },
+                        other => other
+                    });
+                },
+                c => string.push(c)
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self.position < self.chars.len()
+            && (self.chars[self.position].is_ascii_digit() || "+-.eE".contains(self.chars[self.position])) {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        return text.parse::<f64>()
+            .map(Json::Number)
+            .map_err(|_| format!("Invalid number {} at character {}", text, start));
+    }
+}
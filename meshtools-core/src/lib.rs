@@ -0,0 +1,43 @@
+//! Reading, writing, analyzing, and repairing triangle meshes, without a user interface.
+//!
+//! Meshes are lists of `triangle::Triangle`, read and written by `mesh_file`, and welded into a
+//! `triangle_mesh::TriangleMesh` for the algorithms that need to know which triangles share edges.
+
+#[macro_use]
+mod profiling;
+extern crate nalgebra_glm as glm;
+pub mod triangle;
+pub mod i18n;
+pub mod gltf;
+pub mod mesh_file;
+pub mod units;
+pub mod triangle_mesh;
+pub mod spatial_grid;
+pub mod issues;
+pub mod bvh;
+pub mod decimate;
+pub mod simplify;
+pub mod mesh_cache;
+mod sha256;
+pub mod json;
+pub mod build_volume;
+pub mod file_info;
+pub mod plane_cut;
+pub mod slice_export;
+pub mod offset;
+pub mod morph;
+pub mod measurement;
+pub mod annotation;
+pub mod deviation;
+pub mod fitting;
+pub mod registration;
+pub mod shapes;
+pub mod extrude;
+pub mod heightmap;
+pub mod mesh_diff;
+pub mod mass_properties;
+pub mod oriented_box;
+pub mod curvature;
+pub mod thickness;
+pub mod overhang;
+pub mod version;
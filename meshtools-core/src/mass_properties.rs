@@ -0,0 +1,61 @@
+extern crate nalgebra_glm as glm;
+use glm::{DMat3, DVec3, Mat3, Mat4, Vec3};
+
+use crate::triangle::Triangle;
+
+/// Volume, center of mass, and inertia of a closed mesh of uniform density
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MassProperties {
+    pub volume: f32,
+    pub center: Vec3,
+    /// Inertia tensor about the center of mass, per unit density
+    pub inertia: Mat3
+}
+
+impl MassProperties {
+    /// Finds the properties of triangles as placed by a transformation.
+    /// Triangles facing inward give the same result, but None is returned if
+    /// they enclose no volume.
+    pub fn new(triangles: &[Triangle], transformation: &Mat4) -> Option<Self> {
+        profile_span!("mass_properties");
+        // Each triangle makes a tetrahedron with the origin, whose signed volume
+        // and moments sum to those of the solid. Summed in double precision, like the file statistics.
+        let mut volume = 0f64;
+        let mut first_moment = DVec3::zeros();
+        let mut second_moment = DMat3::zeros();
+        for triangle in triangles {
+            let [a, b, c] = triangle.map(|v| {
+                let v = transformation.transform_point(&v.into()).coords;
+                return DVec3::new(v.x as f64, v.y as f64, v.z as f64);
+            });
+            let determinant = a.dot(&b.cross(&c));
+            let sum = a + b + c;
+            volume += determinant / 6.;
+            first_moment += sum * determinant / 24.;
+            second_moment += (a * a.transpose() + b * b.transpose() + c * c.transpose() + sum * sum.transpose())
+                * determinant / 120.;
+        }
+        if volume.abs() < f64::EPSILON {
+            return None;
+        }
+        let center = first_moment / volume;
+        // Moved to the center of mass, and made positive if the triangles face inward
+        let covariance = (second_moment - center * center.transpose() * volume) * volume.signum();
+        let inertia = DMat3::identity() * covariance.trace() - covariance;
+        return Some(Self {
+            volume: volume.abs() as f32,
+            center: glm::convert(center),
+            inertia: glm::convert(inertia)
+        });
+    }
+
+    /// The principal moments of inertia, smallest first, and the unit axis of each as a column
+    pub fn principal_axes(&self) -> (Vec3, Mat3) {
+        let eigen = self.inertia.symmetric_eigen();
+        let mut order = [0, 1, 2];
+        order.sort_by(|a, b| eigen.eigenvalues[*a].total_cmp(&eigen.eigenvalues[*b]));
+        let moments = Vec3::new(eigen.eigenvalues[order[0]], eigen.eigenvalues[order[1]], eigen.eigenvalues[order[2]]);
+        let axes = Mat3::from_columns(&order.map(|i| eigen.eigenvectors.column(i).into_owned()));
+        return (moments, axes);
+    }
+}
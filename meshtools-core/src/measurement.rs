@@ -0,0 +1,218 @@
+use std::fs::File;
+use std::io::Write;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::i18n::tr;
+use crate::json::{self, Json};
+use crate::triangle::Triangle;
+use crate::version;
+
+/// Where a picked point moves to on the face it was picked on
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Snap {
+    Off,
+    /// The nearest corner of the face
+    Vertex,
+    /// The nearest point on an edge of the face
+    Edge
+}
+
+impl Snap {
+    pub const ALL: [Snap; 3] = [Snap::Off, Snap::Vertex, Snap::Edge];
+
+    pub fn label(&self) -> String {
+        match self {
+            Snap::Off => tr("measure-snap-off"),
+            Snap::Vertex => tr("measure-snap-vertex"),
+            Snap::Edge => tr("measure-snap-edge")
+        }
+    }
+
+    /// Moves a point picked on a face to where this snaps it
+    pub fn apply(&self, point: &Vec3, corners: &Triangle) -> Vec3 {
+        let nearest = |candidates: [Vec3; 3]| candidates.into_iter()
+            .min_by(|a, b| glm::distance2(a, point).total_cmp(&glm::distance2(b, point)))
+            .unwrap();
+        return match self {
+            Snap::Off => *point,
+            Snap::Vertex => nearest(*corners),
+            Snap::Edge => nearest([0, 1, 2].map(|i| {
+                let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                let length2 = glm::distance2(&a, &b);
+                if length2 == 0. {
+                    return a;
+                }
+                let t = ((point - a).dot(&(b - a)) / length2).clamp(0., 1.);
+                a + (b - a) * t
+            }))
+        };
+    }
+}
+
+/// A value derived from placed landmarks, which are given by their index in the template
+#[derive(Clone, PartialEq, Debug)]
+pub enum MeasurementKind {
+    /// Straight distance between two landmarks
+    Distance(usize, usize),
+    /// Angle at the middle landmark, between the lines to the other two
+    Angle(usize, usize, usize)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Measurement {
+    pub name: String,
+    pub kind: MeasurementKind
+}
+
+impl Measurement {
+    /// The landmarks the measurement is taken between
+    pub fn landmarks(&self) -> Vec<usize> {
+        return match self.kind {
+            MeasurementKind::Distance(a, b) => vec![a, b],
+            MeasurementKind::Angle(a, vertex, b) => vec![a, vertex, b]
+        };
+    }
+
+    /// A length, or an angle in degrees, once the landmarks it uses are placed
+    pub fn value(&self, points: &[Option<Vec3>]) -> Option<f32> {
+        let point = |i: usize| points.get(i).copied().flatten();
+        return match self.kind {
+            MeasurementKind::Distance(a, b) => Some(glm::distance(&point(a)?, &point(b)?)),
+            MeasurementKind::Angle(a, vertex, b) => {
+                let vertex = point(vertex)?;
+                let (first, second) = (point(a)? - vertex, point(b)? - vertex);
+                if first.norm() == 0. || second.norm() == 0. {
+                    return None;
+                }
+                Some(glm::angle(&first, &second).to_degrees())
+            }
+        };
+    }
+
+    fn to_json(&self) -> String {
+        let (kind, landmarks) = match self.kind {
+            MeasurementKind::Distance(a, b) => ("distance", format!("{}, {}", a, b)),
+            MeasurementKind::Angle(a, vertex, b) => ("angle", format!("{}, {}, {}", a, vertex, b))
+        };
+        return format!(r#"{{"name": {}, "kind": "{}", "landmarks": [{}]}}"#,
+            json::quote(&self.name), kind, landmarks);
+    }
+
+    fn from_json(value: &Json) -> Option<Self> {
+        let landmarks: Vec<usize> = value.get("landmarks")?.as_f32s()?.iter().map(|i| *i as usize).collect();
+        let kind = match (value.get("kind")?.as_str()?, landmarks.as_slice()) {
+            ("distance", [a, b]) => MeasurementKind::Distance(*a, *b),
+            ("angle", [a, vertex, b]) => MeasurementKind::Angle(*a, *vertex, *b),
+            _ => return None
+        };
+        return Some(Self { name: value.get("name")?.as_str()?.to_string(), kind });
+    }
+}
+
+/// A protocol repeated on every scan: the landmarks to place, in order,
+/// and the measurements taken between them
+#[derive(Clone, PartialEq, Debug)]
+pub struct MeasurementTemplate {
+    pub name: String,
+    pub landmarks: Vec<String>,
+    pub measurements: Vec<Measurement>
+}
+
+impl MeasurementTemplate {
+    /// A foot protocol, to show how templates are set up
+    pub fn example() -> Self {
+        let measurement = |key: &str, kind| Measurement { name: tr(key), kind };
+        return Self {
+            name: tr("measure-example"),
+            landmarks: ["measure-example-heel", "measure-example-toe",
+                "measure-example-inner", "measure-example-outer"].iter().map(|key| tr(key)).collect(),
+            measurements: vec![
+                measurement("measure-example-length", MeasurementKind::Distance(0, 1)),
+                measurement("measure-example-width", MeasurementKind::Distance(2, 3)),
+                measurement("measure-example-angle", MeasurementKind::Angle(2, 0, 3))
+            ]
+        };
+    }
+
+    /// Removes a landmark, with the measurements that use it
+    pub fn remove_landmark(&mut self, index: usize) {
+        self.landmarks.remove(index);
+        self.measurements.retain(|m| !m.landmarks().contains(&index));
+        let shift = |i: &mut usize| if *i > index { *i -= 1; };
+        for measurement in &mut self.measurements {
+            match &mut measurement.kind {
+                MeasurementKind::Distance(a, b) => {
+                    shift(a);
+                    shift(b);
+                },
+                MeasurementKind::Angle(a, vertex, b) => {
+                    shift(a);
+                    shift(vertex);
+                    shift(b);
+                }
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let landmarks: Vec<String> = self.landmarks.iter().map(|l| json::quote(l)).collect();
+        let measurements: Vec<String> = self.measurements.iter().map(|m| m.to_json()).collect();
+        return format!(r#"{{"name": {}, "landmarks": [{}], "measurements": [{}]}}"#,
+            json::quote(&self.name), landmarks.join(", "), measurements.join(", "));
+    }
+
+    pub fn from_json(value: &Json) -> Option<Self> {
+        let landmarks: Option<Vec<String>> = value.get("landmarks")?.as_array()?.iter()
+            .map(|l| l.as_str().map(|s| s.to_string()))
+            .collect();
+        let measurements: Option<Vec<Measurement>> = value.get("measurements")?.as_array()?.iter()
+            .map(Measurement::from_json)
+            .collect();
+        let template = Self {
+            name: value.get("name")?.as_str()?.to_string(),
+            landmarks: landmarks?,
+            measurements: measurements?
+        };
+        let count = template.landmarks.len();
+        if template.measurements.iter().any(|m| m.landmarks().iter().any(|i| *i >= count)) {
+            return None;
+        }
+        return Some(template);
+    }
+}
+
+/// Quotes a field for CSV if it has a separator, quote, or line break in it
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        return format!("\"{}\"", text.replace('"', "\"\""));
+    }
+    return text.to_string();
+}
+
+/// Writes a report of a template filled in on a scan: the version of MeshTools that wrote it,
+/// where each landmark was placed, then each measurement. Lengths are in the given unit suffix,
+/// and angles in degrees.
+pub fn write_report(path: &str, template: &MeasurementTemplate, points: &[Option<Vec3>], unit: &str)
+-> Result<(), std::io::Error> {
+    let mut output = File::create(path)?;
+    writeln!(output, "generator,{}", csv_field(&version::description()))?;
+    writeln!(output, "template,{}", csv_field(&template.name))?;
+    writeln!(output, "landmark,x,y,z")?;
+    for (name, point) in template.landmarks.iter().zip(points) {
+        match point {
+            Some(p) => writeln!(output, "{},{},{},{}", csv_field(name), p.x, p.y, p.z)?,
+            None => writeln!(output, "{},,,", csv_field(name))?
+        }
+    }
+    writeln!(output, "measurement,value,unit")?;
+    for measurement in &template.measurements {
+        let unit = match measurement.kind {
+            MeasurementKind::Distance(..) => unit,
+            MeasurementKind::Angle(..) => "deg"
+        };
+        let value = measurement.value(points).map(|v| v.to_string()).unwrap_or_default();
+        writeln!(output, "{},{},{}", csv_field(&measurement.name), value, unit)?;
+    }
+    return Ok(());
+}
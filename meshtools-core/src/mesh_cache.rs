@@ -0,0 +1,271 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::bvh::Bvh;
+use crate::sha256::Sha256;
+use crate::triangle::Triangle;
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// Starts every cached file. The last byte is bumped when the format changes,
+/// so files from older versions are ignored.
+const MAGIC: &[u8; 8] = b"MTCACHE\x01";
+
+/// Most bytes kept in the cache. The least recently used files are removed past this.
+const MAX_CACHE_BYTES: u64 = 4 << 30;
+
+/// The directory holding the cache, under the system's temporary directory
+pub fn cache_dir() -> PathBuf {
+    return std::env::temp_dir().join("meshtools-cache");
+}
+
+/// A key for the data derived from a file: a SHA-256 hash of all of its bytes, followed by its
+/// length and modification time, so a cached entry is only used for the same file, unchanged since
+pub fn file_key(path: &str) -> Result<String, std::io::Error> {
+    profile_span!("hash_file");
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let modified = metadata.modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+    let mut buffer = vec![0u8; 1 << 20];
+    let mut hash = Sha256::new();
+    let mut length: u64 = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hash.update(&buffer[..read]);
+        length += read as u64;
+    }
+    if length != metadata.len() {
+        return Err(std::io::Error::other("the file changed while it was read"));
+    }
+    let hex: String = hash.finish().iter().map(|byte| format!("{:02x}", byte)).collect();
+    return Ok(format!("{}-{:x}-{:x}", hex, length, modified));
+}
+
+/// Reads little endian values from cached bytes, failing at the end of the data
+pub struct ByteReader<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        return Self { bytes };
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        if self.bytes.len() < N {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        return taken.try_into().ok();
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        return Some(u64::from_le_bytes(self.take()?));
+    }
+
+    /// A length or index, which must fit in memory
+    pub fn usize(&mut self) -> Option<usize> {
+        return self.u64()?.try_into().ok();
+    }
+
+    pub fn vec3(&mut self) -> Option<Vec3> {
+        let x = f32::from_le_bytes(self.take()?);
+        let y = f32::from_le_bytes(self.take()?);
+        let z = f32::from_le_bytes(self.take()?);
+        return Some(Vec3::new(x, y, z));
+    }
+
+    /// A count which can't be more than the values left, each at least `size` bytes,
+    /// so corrupt data can't ask for a huge allocation
+    pub fn count(&mut self, size: usize) -> Option<usize> {
+        let count = self.usize()?;
+        if count > self.bytes.len() / size {
+            return None;
+        }
+        return Some(count);
+    }
+}
+
+pub fn write_u64(output: &mut Vec<u8>, value: u64) {
+    output.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_vec3(output: &mut Vec<u8>, vector: &Vec3) {
+    for value in vector.iter() {
+        output.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn file_path(key: &str, kind: &str) -> PathBuf {
+    return cache_dir().join(format!("{}.{}", key, kind));
+}
+
+/// Reads a cached file, without its header, marking it as recently used
+fn read(key: &str, kind: &str) -> Option<Vec<u8>> {
+    let path = file_path(key, kind);
+    let bytes = fs::read(&path).ok()?;
+    if !bytes.starts_with(MAGIC) {
+        return None;
+    }
+    if let Ok(file) = File::options().write(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    return Some(bytes[MAGIC.len()..].to_vec());
+}
+
+/// Writes a cached file. It is written under a temporary name first,
+/// so a half written file is never read.
+fn write(key: &str, kind: &str, data: &[u8]) -> Result<(), std::io::Error> {
+    fs::create_dir_all(cache_dir())?;
+    let path = file_path(key, kind);
+    let temporary = path.with_extension(format!("{}.part", kind));
+    let mut output = File::create(&temporary)?;
+    output.write_all(MAGIC)?;
+    output.write_all(data)?;
+    drop(output);
+    fs::rename(&temporary, &path)?;
+    return Ok(());
+}
+
+pub fn get_triangles(key: &str) -> Option<Vec<Triangle>> {
+    let bytes = read(key, "tri")?;
+    let mut reader = ByteReader::new(&bytes);
+    let count = reader.count(36)?;
+    let mut triangles = Vec::<Triangle>::with_capacity(count);
+    for _ in 0..count {
+        triangles.push([reader.vec3()?, reader.vec3()?, reader.vec3()?]);
+    }
+    return Some(triangles);
+}
+
+pub fn put_triangles(key: &str, triangles: &[Triangle]) -> Result<(), std::io::Error> {
+    let mut data = Vec::<u8>::with_capacity(8 + triangles.len() * 36);
+    write_u64(&mut data, triangles.len() as u64);
+    for triangle in triangles {
+        for vertex in triangle {
+            write_vec3(&mut data, vertex);
+        }
+    }
+    return write(key, "tri", &data);
+}
+
+/// The cached picking hierarchy for a list of triangles, if it fits them
+pub fn get_bvh(key: &str, triangle_count: usize) -> Option<Bvh> {
+    let bytes = read(key, "bvh")?;
+    return Bvh::read_from(&mut ByteReader::new(&bytes), triangle_count);
+}
+
+pub fn put_bvh(key: &str, bvh: &Bvh) -> Result<(), std::io::Error> {
+    let mut data = Vec::<u8>::new();
+    bvh.write_to(&mut data);
+    return write(key, "bvh", &data);
+}
+
+fn get_welded(key: &str) -> Option<TriangleMesh> {
+    let bytes = read(key, "weld")?;
+    let mut reader = ByteReader::new(&bytes);
+    let vertex_count = reader.count(12)?;
+    let mut vertices = Vec::<Vec3>::with_capacity(vertex_count);
+    for _ in 0..vertex_count {
+        vertices.push(reader.vec3()?);
+    }
+    let triangle_count = reader.count(24)?;
+    let mut triangles = Vec::<[usize; 3]>::with_capacity(triangle_count);
+    for _ in 0..triangle_count {
+        let triangle = [reader.usize()?, reader.usize()?, reader.usize()?];
+        if triangle.iter().any(|v| *v >= vertex_count) {
+            return None;
+        }
+        triangles.push(triangle);
+    }
+    return Some(TriangleMesh { vertices, triangles });
+}
+
+fn put_welded(key: &str, mesh: &TriangleMesh) -> Result<(), std::io::Error> {
+    let mut data = Vec::<u8>::new();
+    write_u64(&mut data, mesh.vertices.len() as u64);
+    for vertex in &mesh.vertices {
+        write_vec3(&mut data, vertex);
+    }
+    write_u64(&mut data, mesh.triangles.len() as u64);
+    for triangle in &mesh.triangles {
+        for v in triangle {
+            write_u64(&mut data, *v as u64);
+        }
+    }
+    return write(key, "weld", &data);
+}
+
+/// Welds triangles into an indexed mesh with the default tolerance,
+/// reusing the cached result for the key if there is one
+pub fn welded(key: Option<&str>, triangles: &[Triangle]) -> TriangleMesh {
+    if let Some(mesh) = key.and_then(get_welded) {
+        return mesh;
+    }
+    let mesh = TriangleMesh::new(triangles, triangle_mesh::default_tolerance(triangles));
+    if let Some(key) = key {
+        let _ = put_welded(key, &mesh);
+    }
+    return mesh;
+}
+
+/// Removes the least recently used files until the cache is small enough
+pub fn prune() -> Result<(), std::io::Error> {
+    let mut files = Vec::<(SystemTime, u64, PathBuf)>::new();
+    for entry in fs::read_dir(cache_dir())? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+    files.sort();
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        fs::remove_file(path)?;
+        total -= size;
+    }
+    return Ok(());
+}
+
+/// Removes everything in the cache
+pub fn clear() -> Result<(), std::io::Error> {
+    let dir = cache_dir();
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_key_follows_the_contents() {
+        let path = std::env::temp_dir().join(format!("meshtools-file-key-{}.stl", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, b"abc").unwrap();
+        let key = file_key(path_str).unwrap();
+        assert!(key.starts_with("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad-3-"));
+        assert_eq!(file_key(path_str).unwrap(), key);
+        // The same length with different bytes is a different file
+        fs::write(&path, b"abd").unwrap();
+        let changed = file_key(path_str).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_ne!(changed[..64], key[..64]);
+        assert!(file_key(path_str).is_err());
+    }
+}
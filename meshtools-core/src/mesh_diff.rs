@@ -0,0 +1,94 @@
+extern crate nalgebra_glm as glm;
+
+use crate::bvh::Bvh;
+use crate::i18n::tr_args;
+use crate::triangle::Triangle;
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// The shape of a mesh's connections, which shouldn't change when a result is reproduced
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Topology {
+    pub triangles: usize,
+    /// Vertices once welded
+    pub vertices: usize,
+    pub bodies: usize,
+    /// Edges of only one triangle
+    pub open_edges: usize,
+    /// Edges of more than two triangles
+    pub non_manifold_edges: usize
+}
+
+impl Topology {
+    fn of(mesh: &TriangleMesh, triangle_count: usize) -> Self {
+        let edges = mesh.edge_report();
+        return Self {
+            triangles: triangle_count,
+            vertices: mesh.vertices.len(),
+            bodies: mesh.bodies().len(),
+            open_edges: edges.boundary.len(),
+            non_manifold_edges: edges.non_manifold.len()
+        };
+    }
+}
+
+/// How two meshes differ, for checking that a result hasn't changed
+pub struct MeshDiff {
+    pub first: Topology,
+    pub second: Topology,
+    /// Largest distance from a vertex of either mesh to the surface of the other
+    pub max_deviation: f32
+}
+
+impl MeshDiff {
+    pub fn new(first: &[Triangle], second: &[Triangle]) -> Self {
+        profile_span!("mesh_diff");
+        let first_mesh = TriangleMesh::new(first, triangle_mesh::default_tolerance(first));
+        let second_mesh = TriangleMesh::new(second, triangle_mesh::default_tolerance(second));
+        let max_deviation = one_way_deviation(&first_mesh, second)
+            .max(one_way_deviation(&second_mesh, first));
+        return Self {
+            first: Topology::of(&first_mesh, first.len()),
+            second: Topology::of(&second_mesh, second.len()),
+            max_deviation
+        };
+    }
+
+    /// Whether the meshes have the same topology, and their surfaces are within the tolerance
+    pub fn matches(&self, tolerance: f32) -> bool {
+        return self.first == self.second && self.max_deviation <= tolerance;
+    }
+
+    /// The comparison as translated lines of text, marking what differs
+    pub fn lines(&self, tolerance: f32) -> Vec<String> {
+        let (a, b) = (&self.first, &self.second);
+        let row = |key: &str, first: usize, second: usize| {
+            let mark = if first == second { " " } else { "*" };
+            return format!("{} {}", mark, tr_args(key, &[("first", &first), ("second", &second)]));
+        };
+        let mark = if self.max_deviation <= tolerance { " " } else { "*" };
+        return vec![
+            row("diff-triangles", a.triangles, b.triangles),
+            row("diff-vertices", a.vertices, b.vertices),
+            row("diff-bodies", a.bodies, b.bodies),
+            row("diff-open-edges", a.open_edges, b.open_edges),
+            row("diff-non-manifold", a.non_manifold_edges, b.non_manifold_edges),
+            format!("{} {}", mark, tr_args("diff-deviation", &[
+                ("deviation", &self.max_deviation),
+                ("tolerance", &tolerance)]))
+        ];
+    }
+}
+
+/// Largest distance from a vertex of a mesh to the nearest point of some triangles
+fn one_way_deviation(mesh: &TriangleMesh, triangles: &[Triangle]) -> f32 {
+    if mesh.vertices.is_empty() {
+        return 0.;
+    }
+    if triangles.is_empty() {
+        return f32::INFINITY;
+    }
+    let bvh = Bvh::new(triangles);
+    return mesh.vertices.iter()
+        .filter_map(|v| bvh.closest(triangles, v).map(|(_, nearest)| glm::distance(v, &nearest)))
+        .fold(0., f32::max);
+}
@@ -0,0 +1,833 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Error, ErrorKind, Read, Write};
+use std::path::Path;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+use flate2::read::DeflateDecoder;
+
+use crate::triangle::{self, Triangle};
+use crate::version;
+
+/// File extensions that can be opened with `read_mesh_file`
+pub const SUPPORTED_EXTENSIONS: [&str; 4] = ["stl", "obj", "off", "zip"];
+
+fn invalid_data(message: &str) -> Error {
+    return Error::new(ErrorKind::InvalidData, message.to_string());
+}
+
+/// Most items reserved up front for a count read from a file. A corrupt or hostile count
+/// could ask for more memory than there is, which aborts rather than failing,
+/// so past this the list grows as the items are actually read.
+const MAX_RESERVED: usize = 1 << 16;
+
+/// Room to reserve for a count read from a file
+fn reserved(count: usize) -> usize {
+    return count.min(MAX_RESERVED);
+}
+
+fn extension(path: &str) -> String {
+    return Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+}
+
+/// One of a numbered series of files named after a path, with the number
+/// before the extension and padded to the width of the count, like "part_07.stl"
+pub fn numbered_path(path: &str, number: usize, count: usize) -> String {
+    let (stem, extension) = match path.rfind('.') {
+        Some(dot) if !path[dot..].contains(['/', '\\']) => (&path[..dot], &path[dot..]),
+        _ => (path, ".stl")
+    };
+    let width = count.to_string().len();
+    return format!("{}_{:0width$}{}", stem, number, extension, width = width);
+}
+
+/// Writes triangles to a mesh file, choosing the format from the file extension.
+/// Anything that isn't OBJ is written as binary STL.
+pub fn write_mesh_file(path: &str, triangles: &[Triangle]) -> Result<(), Error> {
+    return match extension(path).as_str() {
+        "obj" => write_obj(path, triangles),
+        _ => triangle::write_stl_binary(path, triangles)
+    };
+}
+
+/// Writes triangles to a Wavefront OBJ file, sharing corners that are exactly the same
+pub fn write_obj(path: &str, triangles: &[Triangle]) -> Result<(), Error> {
+    let mut output = BufWriter::new(File::create(path)?);
+    writeln!(output, "# {}", version::description())?;
+    let mut indices = HashMap::<[u32; 3], usize>::new();
+    let mut vertices = Vec::<Vec3>::new();
+    let mut faces = Vec::<[usize; 3]>::with_capacity(triangles.len());
+    for t in triangles {
+        // Indices start at 1, in the order vertices are first used
+        faces.push(t.map(|vertex| {
+            let key = [vertex.x.to_bits(), vertex.y.to_bits(), vertex.z.to_bits()];
+            return *indices.entry(key).or_insert_with(|| {
+                vertices.push(vertex);
+                vertices.len()
+            });
+        }));
+    }
+    for v in &vertices {
+        writeln!(output, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+    for [a, b, c] in faces {
+        writeln!(output, "f {} {} {}", a, b, c)?;
+    }
+    return output.flush();
+}
+
+/// Loads a mesh file into a list of triangles, choosing the format
+/// from the file extension.
+///
+/// Zip archives must contain exactly one mesh file, which is extracted in memory.
+#[allow(dead_code)]
+pub fn read_mesh_file(path: &str) -> Result<Vec::<Triangle>, Error> {
+    return read_mesh_file_with_progress(path, |_| true);
+}
+
+/// Like `read_mesh_file`, but reports the fraction of the file read so far.
+///
+/// Reading stops with an error if the progress callback returns false.
+pub fn read_mesh_file_with_progress(path: &str, mut progress: impl FnMut(f32) -> bool)
+-> Result<Vec::<Triangle>, Error> {
+    profile_span!("read_mesh_file");
+    let file = File::open(path)?;
+    let size = file.metadata()?.len().max(1) as f32;
+    let extension = extension(path);
+    if !["obj", "off", "zip"].contains(&extension.as_str()) {
+        return read_stl(&mut BufReader::with_capacity(1 << 20, file), progress);
+    }
+    // A zip archive is read into memory for the first half of the progress,
+    // and the mesh inside it is read for the second half
+    let share = if extension == "zip" { 0.5 } else { 1. };
+    let reader = ProgressReader {
+        inner: file,
+        read: 0,
+        callback: |read: u64| progress(read as f32 / size * share)
+    };
+    let mut input = BufReader::with_capacity(1 << 20, reader);
+    let bytes = match extension.as_str() {
+        "obj" => return read_obj(&mut input),
+        "off" => return read_off(&mut input),
+        _ => {
+            let mut bytes = Vec::<u8>::new();
+            input.read_to_end(&mut bytes)?;
+            bytes
+        }
+    };
+    return read_zip(&bytes, |fraction| progress(0.5 + fraction * 0.5));
+}
+
+/// Reads binary STL data, passing the fraction of triangles read to the progress callback.
+///
+/// Reading stops with an error if the progress callback returns false.
+fn read_stl<R: Read>(input: &mut R, mut progress: impl FnMut(f32) -> bool) -> Result<Vec::<Triangle>, Error> {
+    let mut triangles = Vec::<Triangle>::new();
+    triangle::stream_stl_binary_from(input, |chunk, fraction| {
+        triangles.extend_from_slice(chunk);
+        return progress(fraction);
+    })?;
+    return Ok(triangles);
+}
+
+/// Counts the bytes passing through a reader, and stops reading
+/// when the callback returns false
+struct ProgressReader<R, F> {
+    inner: R,
+    read: u64,
+    callback: F
+}
+
+impl<R: Read, F: FnMut(u64) -> bool> Read for ProgressReader<R, F> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        if !(self.callback)(self.read) {
+            return Err(Error::other("Cancelled"));
+        }
+        let count = self.inner.read(buffer)?;
+        self.read += count as u64;
+        return Ok(count);
+    }
+}
+
+fn parse_f32(token: Option<&str>) -> Result<f32, Error> {
+    return token
+        .and_then(|t| t.parse::<f32>().ok())
+        .ok_or_else(|| invalid_data("Expected a number"));
+}
+
+fn parse_usize(token: Option<&str>) -> Result<usize, Error> {
+    return token
+        .and_then(|t| t.parse::<usize>().ok())
+        .ok_or_else(|| invalid_data("Expected an index"));
+}
+
+/// Splits a polygon into a fan of triangles around its first vertex
+fn triangulate(vertices: &[Vec3], indices: &[usize], triangles: &mut Vec<Triangle>)
+-> Result<(), Error> {
+    for index in indices {
+        if *index >= vertices.len() {
+            return Err(invalid_data("Face refers to a missing vertex"));
+        }
+    }
+    for i in 2..indices.len() {
+        triangles.push([
+            vertices[indices[0]],
+            vertices[indices[i - 1]],
+            vertices[indices[i]]]);
+    }
+    return Ok(());
+}
+
+/// Reads an Object File Format (OFF) mesh into a list of triangles
+///
+/// Accepts the colored and normal variants of the header (COFF, NOFF, ...),
+/// but discards anything after the coordinates and indices.
+/// Polygons are split into triangle fans.
+pub fn read_off<R: BufRead>(input: &mut R) -> Result<Vec::<Triangle>, Error> {
+    // Comments and blank lines may appear anywhere
+    let mut lines = input.lines()
+        .map(|line| line.map(|l| l.split('#').next().unwrap_or("").trim().to_string()))
+        .filter(|line| !matches!(line, Ok(l) if l.is_empty()));
+    let mut next_line = || -> Result<String, Error> {
+        return lines.next().unwrap_or_else(|| Err(invalid_data("Unexpected end of file")));
+    };
+
+    let header = next_line()?;
+    if !header.ends_with("OFF") && !header.contains("OFF ") {
+        return Err(invalid_data("Missing OFF header"));
+    }
+    // The counts may follow the keyword on the same line
+    let mut counts = header.trim_start_matches(|c: char| c.is_ascii_alphabetic()).trim().to_string();
+    if counts.is_empty() {
+        counts = next_line()?;
+    }
+    let mut tokens = counts.split_whitespace();
+    let vertex_count = parse_usize(tokens.next())?;
+    let face_count = parse_usize(tokens.next())?;
+
+    let mut vertices = Vec::<Vec3>::with_capacity(reserved(vertex_count));
+    for _i in 0..vertex_count {
+        let line = next_line()?;
+        let mut tokens = line.split_whitespace();
+        vertices.push(Vec3::new(
+            parse_f32(tokens.next())?,
+            parse_f32(tokens.next())?,
+            parse_f32(tokens.next())?));
+    }
+    let mut triangles = Vec::<Triangle>::with_capacity(reserved(face_count));
+    for _i in 0..face_count {
+        let line = next_line()?;
+        let mut tokens = line.split_whitespace();
+        let corner_count = parse_usize(tokens.next())?;
+        let mut indices = Vec::<usize>::with_capacity(reserved(corner_count));
+        for _j in 0..corner_count {
+            indices.push(parse_usize(tokens.next())?);
+        }
+        triangulate(&vertices, &indices, &mut triangles)?;
+    }
+    return Ok(triangles);
+}
+
+/// Reads the vertices and faces of a Wavefront OBJ file into a list of triangles
+///
+/// Texture coordinates, normals, groups, and materials are discarded.
+/// Polygons are split into triangle fans.
+pub fn read_obj<R: BufRead>(input: &mut R) -> Result<Vec::<Triangle>, Error> {
+    let mut vertices = Vec::<Vec3>::new();
+    let mut triangles = Vec::<Triangle>::new();
+    for line in input.lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                vertices.push(Vec3::new(
+                    parse_f32(tokens.next())?,
+                    parse_f32(tokens.next())?,
+                    parse_f32(tokens.next())?));
+            },
+            Some("f") => {
+                let mut indices = Vec::<usize>::new();
+                for corner in tokens {
+                    // Corners look like v, v/vt, v//vn, or v/vt/vn
+                    let index = corner.split('/').next().unwrap_or("")
+                        .parse::<i64>()
+                        .map_err(|_| invalid_data("Expected an index"))?;
+                    // Indices start at 1, and negative indices count back from the end
+                    let index = if index < 0 {
+                        vertices.len() as i64 + index
+                    } else {
+                        index - 1
+                    };
+                    if index < 0 {
+                        return Err(invalid_data("Face refers to a missing vertex"));
+                    }
+                    indices.push(index as usize);
+                }
+                triangulate(&vertices, &indices, &mut triangles)?;
+            },
+            _ => {}
+        }
+    }
+    return Ok(triangles);
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, Error> {
+    let slice = bytes.get(offset..offset + 2).ok_or_else(|| invalid_data("Truncated zip file"))?;
+    return Ok(u16::from_le_bytes([slice[0], slice[1]]));
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Error> {
+    let slice = bytes.get(offset..offset + 4).ok_or_else(|| invalid_data("Truncated zip file"))?;
+    return Ok(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]));
+}
+
+/// An entry from a zip file's central directory
+struct ZipEntry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    uncompressed_size: usize,
+    header_offset: usize
+}
+
+fn read_zip_entries(bytes: &[u8]) -> Result<Vec<ZipEntry>, Error> {
+    const END_SIGNATURE: u32 = 0x06054b50;
+    const ENTRY_SIGNATURE: u32 = 0x02014b50;
+    // The end of central directory record is at least 22 bytes,
+    // followed by a comment of up to 65535 bytes
+    let mut end = None;
+    let lowest = bytes.len().saturating_sub(22 + 0xFFFF);
+    for offset in (lowest..=bytes.len().saturating_sub(22)).rev() {
+        if read_u32(bytes, offset)? == END_SIGNATURE {
+            end = Some(offset);
+            break;
+        }
+    }
+    let end = end.ok_or_else(|| invalid_data("Not a zip file"))?;
+    let entry_count = read_u16(bytes, end + 10)? as usize;
+    let mut offset = read_u32(bytes, end + 16)? as usize;
+
+    let mut entries = Vec::<ZipEntry>::with_capacity(reserved(entry_count));
+    for _i in 0..entry_count {
+        if read_u32(bytes, offset)? != ENTRY_SIGNATURE {
+            return Err(invalid_data("Corrupt zip directory"));
+        }
+        let name_length = read_u16(bytes, offset + 28)? as usize;
+        let extra_length = read_u16(bytes, offset + 30)? as usize;
+        let comment_length = read_u16(bytes, offset + 32)? as usize;
+        let name = bytes.get(offset + 46..offset + 46 + name_length)
+            .ok_or_else(|| invalid_data("Truncated zip file"))?;
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).to_string(),
+            method: read_u16(bytes, offset + 10)?,
+            compressed_size: read_u32(bytes, offset + 20)? as usize,
+            uncompressed_size: read_u32(bytes, offset + 24)? as usize,
+            header_offset: read_u32(bytes, offset + 42)? as usize
+        });
+        offset += 46 + name_length + extra_length + comment_length;
+    }
+    return Ok(entries);
+}
+
+fn extract_zip_entry(bytes: &[u8], entry: &ZipEntry) -> Result<Vec<u8>, Error> {
+    const HEADER_SIGNATURE: u32 = 0x04034b50;
+    if entry.compressed_size == u32::MAX as usize {
+        return Err(invalid_data("Zip64 archives are not supported"));
+    }
+    let offset = entry.header_offset;
+    if read_u32(bytes, offset)? != HEADER_SIGNATURE {
+        return Err(invalid_data("Corrupt zip entry"));
+    }
+    let name_length = read_u16(bytes, offset + 26)? as usize;
+    let extra_length = read_u16(bytes, offset + 28)? as usize;
+    let start = offset + 30 + name_length + extra_length;
+    let data = bytes.get(start..start + entry.compressed_size)
+        .ok_or_else(|| invalid_data("Truncated zip file"))?;
+    match entry.method {
+        0 => Ok(data.to_vec()),
+        8 => {
+            let mut contents = Vec::<u8>::with_capacity(reserved(entry.uncompressed_size));
+            DeflateDecoder::new(data).read_to_end(&mut contents)?;
+            Ok(contents)
+        },
+        _ => Err(invalid_data("Unsupported zip compression method"))
+    }
+}
+
+/// Loads the single mesh file inside a zip archive held in memory, without extracting it to disk,
+/// passing the fraction of that mesh file read to the progress callback.
+///
+/// Reading stops with an error if the progress callback returns false.
+pub fn read_zip(bytes: &[u8], mut progress: impl FnMut(f32) -> bool) -> Result<Vec::<Triangle>, Error> {
+    let entries = read_zip_entries(bytes)?;
+    let mut meshes = entries.iter().filter(|entry| {
+        let extension = extension(&entry.name);
+        !entry.name.ends_with('/') && extension != "zip" && SUPPORTED_EXTENSIONS.contains(&extension.as_str())
+    });
+    let entry = meshes.next().ok_or_else(|| invalid_data("No mesh file in the zip archive"))?;
+    if meshes.next().is_some() {
+        return Err(invalid_data("More than one mesh file in the zip archive"));
+    }
+    let contents = extract_zip_entry(bytes, entry)?;
+    let size = contents.len().max(1) as f32;
+    let extension = extension(&entry.name);
+    if extension != "obj" && extension != "off" {
+        return read_stl(&mut Cursor::new(contents), progress);
+    }
+    let mut contents = BufReader::new(ProgressReader {
+        inner: Cursor::new(contents),
+        read: 0,
+        callback: |read: u64| progress(read as f32 / size)
+    });
+    match extension.as_str() {
+        "obj" => read_obj(&mut contents),
+        _ => read_off(&mut contents)
+    }
+}
+
+/// File extensions that can be opened with `read_point_cloud_with_progress`
+pub const POINT_CLOUD_EXTENSIONS: [&str; 3] = ["xyz", "txt", "ply"];
+
+/// Points sampled on a surface, as a scanner gives them before they are meshed
+pub struct PointCloud {
+    pub points: Vec<Vec3>,
+    /// A color for each point, from 0 to 1, if the file has them
+    pub colors: Option<Vec<[f32; 3]>>
+}
+
+/// Loads a point cloud file, choosing the format from the file extension, and reports
+/// the fraction of the file read so far. Anything that isn't PLY is read as XYZ text.
+///
+/// Reading stops with an error if the progress callback returns false.
+pub fn read_point_cloud_with_progress(path: &str, mut progress: impl FnMut(f32) -> bool)
+-> Result<PointCloud, Error> {
+    profile_span!("read_point_cloud");
+    let file = File::open(path)?;
+    let size = file.metadata()?.len().max(1) as f32;
+    let reader = ProgressReader {
+        inner: file,
+        read: 0,
+        callback: |read: u64| progress(read as f32 / size)
+    };
+    let mut input = BufReader::with_capacity(1 << 20, reader);
+    match extension(path).as_str() {
+        "ply" => read_ply_points(&mut input),
+        _ => read_xyz(&mut input)
+    }
+}
+
+/// Reads a text file with a point on each line, as "x y z", optionally followed by
+/// a color as "r g b" from 0 to 255. Other columns, like normals, are discarded.
+/// Whether there are colors is decided by the first point.
+/// Blank lines, and comments starting with # or //, are skipped, and commas count as spaces.
+pub fn read_xyz<R: BufRead>(input: &mut R) -> Result<PointCloud, Error> {
+    let mut points = Vec::<Vec3>::new();
+    let mut colors: Option<Vec<[f32; 3]>> = None;
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()).collect();
+        points.push(Vec3::new(
+            parse_f32(tokens.first().copied())?,
+            parse_f32(tokens.get(1).copied())?,
+            parse_f32(tokens.get(2).copied())?));
+        let color: Option<Vec<u8>> = tokens.get(3..6).and_then(|rgb| rgb.iter().map(|t| t.parse::<u8>().ok()).collect());
+        if points.len() == 1 && color.is_some() {
+            colors = Some(Vec::new());
+        }
+        if let Some(colors) = &mut colors {
+            let color = color.ok_or_else(|| invalid_data("Expected a color"))?;
+            colors.push([0, 1, 2].map(|c| color[c] as f32 / 255.));
+        }
+    }
+    return Ok(PointCloud { points, colors });
+}
+
+/// The types a PLY property can have
+#[derive(Clone, Copy)]
+enum PlyType {
+    I8, U8, I16, U16, I32, U32, F32, F64
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Result<Self, Error> {
+        return match name {
+            "char" | "int8" => Ok(PlyType::I8),
+            "uchar" | "uint8" => Ok(PlyType::U8),
+            "short" | "int16" => Ok(PlyType::I16),
+            "ushort" | "uint16" => Ok(PlyType::U16),
+            "int" | "int32" => Ok(PlyType::I32),
+            "uint" | "uint32" => Ok(PlyType::U32),
+            "float" | "float32" => Ok(PlyType::F32),
+            "double" | "float64" => Ok(PlyType::F64),
+            _ => Err(invalid_data("Unknown PLY property type"))
+        };
+    }
+
+    fn size(&self) -> usize {
+        return match self {
+            PlyType::I8 | PlyType::U8 => 1,
+            PlyType::I16 | PlyType::U16 => 2,
+            PlyType::I32 | PlyType::U32 | PlyType::F32 => 4,
+            PlyType::F64 => 8
+        };
+    }
+
+    /// The largest value of an integer type, which is full brightness for a color
+    fn full_scale(&self) -> f64 {
+        return match self {
+            PlyType::U8 | PlyType::I8 => u8::MAX as f64,
+            PlyType::U16 | PlyType::I16 => u16::MAX as f64,
+            PlyType::U32 | PlyType::I32 => u32::MAX as f64,
+            PlyType::F32 | PlyType::F64 => 1.
+        };
+    }
+
+    /// Reads a value of this type from a binary PLY file
+    fn read<R: Read>(&self, input: &mut R, big_endian: bool) -> Result<f64, Error> {
+        let mut bytes = [0u8; 8];
+        let bytes = &mut bytes[..self.size()];
+        input.read_exact(bytes)?;
+        if big_endian {
+            bytes.reverse();
+        }
+        return Ok(match self {
+            PlyType::I8 => bytes[0] as i8 as f64,
+            PlyType::U8 => bytes[0] as f64,
+            PlyType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            PlyType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            PlyType::I32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            PlyType::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            PlyType::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            PlyType::F64 => f64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]])
+        });
+    }
+}
+
+/// A property of each item of a PLY element, which is a list if it has a type for the count
+struct PlyProperty {
+    name: String,
+    kind: PlyType,
+    count: Option<PlyType>
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>
+}
+
+/// Reads the values of one item of a PLY element, with each list's values after its count
+fn read_ply_item<R: BufRead>(input: &mut R, element: &PlyElement, format: &str, line: &mut String)
+-> Result<Vec<f64>, Error> {
+    let mut values = Vec::<f64>::with_capacity(element.properties.len());
+    if format == "ascii" {
+        line.clear();
+        if input.read_line(line)? == 0 {
+            return Err(invalid_data("Unexpected end of file"));
+        }
+        for token in line.split_whitespace() {
+            values.push(token.parse::<f64>().map_err(|_| invalid_data("Expected a number"))?);
+        }
+        return Ok(values);
+    }
+    let big_endian = format == "binary_big_endian";
+    for property in &element.properties {
+        if let Some(count) = property.count {
+            let count = count.read(input, big_endian)?;
+            values.push(count);
+            for _i in 0..count as usize {
+                values.push(property.kind.read(input, big_endian)?);
+            }
+        } else {
+            values.push(property.kind.read(input, big_endian)?);
+        }
+    }
+    return Ok(values);
+}
+
+/// Reads the vertices of a Polygon File Format (PLY) file as points, in ASCII or binary,
+/// with their colors if they have red, green, and blue properties. Faces are discarded.
+pub fn read_ply_points<R: BufRead>(input: &mut R) -> Result<PointCloud, Error> {
+    let mut line = String::new();
+    let mut next_line = |input: &mut R| -> Result<String, Error> {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Err(invalid_data("Unexpected end of file"));
+        }
+        return Ok(line.trim().to_string());
+    };
+    if next_line(input)? != "ply" {
+        return Err(invalid_data("Missing PLY header"));
+    }
+    let mut format = String::new();
+    let mut elements = Vec::<PlyElement>::new();
+    loop {
+        let header = next_line(input)?;
+        let tokens: Vec<&str> = header.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            ["format", name, ..] => format = name.to_string(),
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: parse_usize(Some(count))?,
+                properties: Vec::new()
+            }),
+            ["property", "list", count, kind, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid_data("Property outside an element"))?;
+                element.properties.push(PlyProperty { name: name.to_string(), kind: PlyType::parse(kind)?, count: Some(PlyType::parse(count)?) });
+            },
+            ["property", kind, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid_data("Property outside an element"))?;
+                element.properties.push(PlyProperty { name: name.to_string(), kind: PlyType::parse(kind)?, count: None });
+            },
+            _ => {}
+        }
+    }
+    if !["ascii", "binary_little_endian", "binary_big_endian"].contains(&format.as_str()) {
+        return Err(invalid_data("Unknown PLY format"));
+    }
+    let mut line = String::new();
+    for element in &elements {
+        if element.name != "vertex" {
+            // Elements before the vertices are skipped over, and those after them aren't read
+            for _i in 0..element.count {
+                read_ply_item(input, element, &format, &mut line)?;
+            }
+            continue;
+        }
+        // Lists would move the later properties, so only the properties before any list can be found by position
+        let position = |names: &[&str]| element.properties.iter()
+            .take_while(|property| property.count.is_none())
+            .position(|property| names.contains(&property.name.as_str()));
+        let coordinates = [position(&["x"]), position(&["y"]), position(&["z"])];
+        let [x, y, z] = match coordinates {
+            [Some(x), Some(y), Some(z)] => [x, y, z],
+            _ => return Err(invalid_data("The PLY vertices have no coordinates"))
+        };
+        let color = match [position(&["red", "r", "diffuse_red"]), position(&["green", "g", "diffuse_green"]), position(&["blue", "b", "diffuse_blue"])] {
+            [Some(r), Some(g), Some(b)] => Some(([r, g, b], element.properties[r].kind.full_scale())),
+            _ => None
+        };
+        let mut points = Vec::<Vec3>::with_capacity(reserved(element.count));
+        let mut colors = color.map(|_| Vec::<[f32; 3]>::with_capacity(reserved(element.count)));
+        for _i in 0..element.count {
+            let values = read_ply_item(input, element, &format, &mut line)?;
+            let value = |i: usize| values.get(i).copied().ok_or_else(|| invalid_data("Expected a number"));
+            points.push(Vec3::new(value(x)? as f32, value(y)? as f32, value(z)? as f32));
+            if let (Some(colors), Some((channels, scale))) = (&mut colors, color) {
+                let [r, g, b] = channels.map(|c| value(c).map(|v| (v / scale) as f32));
+                colors.push([r?, g?, b?]);
+            }
+        }
+        return Ok(PointCloud { points, colors });
+    }
+    return Err(invalid_data("The PLY file has no vertices"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    fn v(x: f32, y: f32, z: f32) -> Vec3 {
+        return Vec3::new(x, y, z);
+    }
+
+    /// Binary STL data for triangles, with zero normals and attributes
+    fn stl_bytes(triangles: &[Triangle]) -> Vec<u8> {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend((triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            bytes.extend([0u8; 12]);
+            for corner in triangle {
+                for coordinate in corner.iter() {
+                    bytes.extend(coordinate.to_le_bytes());
+                }
+            }
+            bytes.extend([0u8; 2]);
+        }
+        return bytes;
+    }
+
+    /// A zip archive of files with their names, deflated if asked.
+    /// The checksums are left at zero, since they aren't checked.
+    fn zip_bytes(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut bytes = Vec::<u8>::new();
+        let mut directory = Vec::<u8>::new();
+        for (name, contents, deflate) in files {
+            let (method, data) = if *deflate {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(contents).unwrap();
+                (8u16, encoder.finish().unwrap())
+            } else {
+                (0u16, contents.to_vec())
+            };
+            let offset = bytes.len() as u32;
+            bytes.extend(0x04034b50u32.to_le_bytes());
+            bytes.extend([20, 0, 0, 0]);
+            bytes.extend(method.to_le_bytes());
+            bytes.extend([0u8; 8]);
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend((contents.len() as u32).to_le_bytes());
+            bytes.extend((name.len() as u16).to_le_bytes());
+            bytes.extend([0, 0]);
+            bytes.extend(name.as_bytes());
+            bytes.extend(&data);
+
+            directory.extend(0x02014b50u32.to_le_bytes());
+            directory.extend([20, 0, 20, 0, 0, 0]);
+            directory.extend(method.to_le_bytes());
+            directory.extend([0u8; 8]);
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend((contents.len() as u32).to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0u8; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let directory_offset = bytes.len() as u32;
+        bytes.extend(&directory);
+        bytes.extend(0x06054b50u32.to_le_bytes());
+        bytes.extend([0u8; 4]);
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend((directory.len() as u32).to_le_bytes());
+        bytes.extend(directory_offset.to_le_bytes());
+        bytes.extend([0, 0]);
+        return bytes;
+    }
+
+    #[test]
+    fn off_polygons_become_fans() {
+        let text = "OFF # a unit square\n\n4 1 0\n0 0 0\n1 0 0\n1 1 0\n# comment\n0 1 0\n4 0 1 2 3\n";
+        let triangles = read_off(&mut text.as_bytes()).unwrap();
+        assert_eq!(triangles, vec![
+            [v(0., 0., 0.), v(1., 0., 0.), v(1., 1., 0.)],
+            [v(0., 0., 0.), v(1., 1., 0.), v(0., 1., 0.)]]);
+    }
+
+    #[test]
+    fn off_counts_on_the_header_line() {
+        let text = "COFF 3 1 0\n0 0 0 255 0 0\n1 0 0 255 0 0\n0 1 0 255 0 0\n3 0 1 2\n";
+        assert_eq!(read_off(&mut text.as_bytes()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn off_rejects_bad_files() {
+        assert!(read_off(&mut "PLY\n3 1 0\n".as_bytes()).is_err());
+        // A missing vertex
+        assert!(read_off(&mut "OFF\n3 1 0\n0 0 0\n1 0 0\n0 1 0\n3 0 1 5\n".as_bytes()).is_err());
+        // Huge counts in the header fail at the end of the data, instead of reserving memory for them
+        assert!(read_off(&mut "OFF\n4000000000 4000000000 0\n0 0 0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn xyz_points_and_colors() {
+        let text = "# scan\n1 2 3 255 0 51\n\n4,5,6,0,255,0\n// end\n";
+        let cloud = read_xyz(&mut text.as_bytes()).unwrap();
+        assert_eq!(cloud.points, vec![v(1., 2., 3.), v(4., 5., 6.)]);
+        assert_eq!(cloud.colors, Some(vec![[1., 0., 0.2], [0., 1., 0.]]));
+    }
+
+    #[test]
+    fn xyz_colors_follow_the_first_point() {
+        let cloud = read_xyz(&mut "1 2 3\n4 5 6 255 255 255\n".as_bytes()).unwrap();
+        assert_eq!(cloud.points.len(), 2);
+        assert!(cloud.colors.is_none());
+        assert!(read_xyz(&mut "1 2 3 255 255 255\n4 5 6\n".as_bytes()).is_err());
+        assert!(read_xyz(&mut "1 2\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn ply_ascii_points_with_colors() {
+        let text = "ply\nformat ascii 1.0\ncomment made by hand\nelement vertex 2\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property uchar red\nproperty uchar green\nproperty uchar blue\n\
+            element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+            1 2 3 255 0 0\n4 5 6 0 0 255\n3 0 1 0\n";
+        let cloud = read_ply_points(&mut text.as_bytes()).unwrap();
+        assert_eq!(cloud.points, vec![v(1., 2., 3.), v(4., 5., 6.)]);
+        assert_eq!(cloud.colors, Some(vec![[1., 0., 0.], [0., 0., 1.]]));
+    }
+
+    #[test]
+    fn ply_binary_points_after_other_elements() {
+        let mut bytes = b"ply\nformat binary_little_endian 1.0\nelement camera 1\nproperty list uchar short view\n\
+            element vertex 2\nproperty double x\nproperty double y\nproperty double z\nproperty float confidence\nend_header\n".to_vec();
+        bytes.push(2);
+        bytes.extend(7i16.to_le_bytes());
+        bytes.extend((-7i16).to_le_bytes());
+        for point in [[1., 2., 3.], [-4., 0.5, 6.]] {
+            for coordinate in point {
+                bytes.extend(f64::to_le_bytes(coordinate));
+            }
+            bytes.extend(1f32.to_le_bytes());
+        }
+        let cloud = read_ply_points(&mut bytes.as_slice()).unwrap();
+        assert_eq!(cloud.points, vec![v(1., 2., 3.), v(-4., 0.5, 6.)]);
+        assert!(cloud.colors.is_none());
+    }
+
+    #[test]
+    fn ply_binary_big_endian() {
+        let mut bytes = b"ply\nformat binary_big_endian 1.0\nelement vertex 1\n\
+            property float x\nproperty float y\nproperty float z\nend_header\n".to_vec();
+        for coordinate in [1f32, -2., 3.5] {
+            bytes.extend(coordinate.to_be_bytes());
+        }
+        assert_eq!(read_ply_points(&mut bytes.as_slice()).unwrap().points, vec![v(1., -2., 3.5)]);
+    }
+
+    #[test]
+    fn ply_rejects_bad_files() {
+        assert!(read_ply_points(&mut "off\n".as_bytes()).is_err());
+        assert!(read_ply_points(&mut "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n1\n".as_bytes()).is_err());
+        assert!(read_ply_points(&mut "ply\nformat ascii 1.0\nelement face 0\nend_header\n".as_bytes()).is_err());
+        // A huge vertex count fails at the end of the data, instead of reserving memory for it
+        let text = "ply\nformat ascii 1.0\nelement vertex 4000000000\n\
+            property float x\nproperty float y\nproperty float z\nend_header\n1 2 3\n";
+        assert!(read_ply_points(&mut text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn zip_with_one_mesh() {
+        let triangles = vec![[v(0., 0., 0.), v(1., 0., 0.), v(0., 1., 0.)], [v(0., 0., 1.), v(1., 0., 1.), v(0., 1., 1.)]];
+        let stl = stl_bytes(&triangles);
+        let off = b"OFF\n3 1 0\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        for deflate in [false, true] {
+            let bytes = zip_bytes(&[("readme/", b"", false), ("PART.STL", &stl, deflate)]);
+            assert_eq!(read_zip(&bytes, |_| true).unwrap(), triangles);
+            let bytes = zip_bytes(&[("notes.txt", b"hello", deflate), ("part.off", off, deflate)]);
+            assert_eq!(read_zip(&bytes, |_| true).unwrap(), vec![triangles[0]]);
+        }
+    }
+
+    #[test]
+    fn zip_needs_exactly_one_mesh() {
+        let off = b"OFF\n3 1 0\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        assert!(read_zip(&zip_bytes(&[("notes.txt", b"hello", false)]), |_| true).is_err());
+        assert!(read_zip(&zip_bytes(&[("a.off", off, false), ("b.off", off, true)]), |_| true).is_err());
+        assert!(read_zip(b"not a zip file at all, just some text that is long enough", |_| true).is_err());
+    }
+
+    #[test]
+    fn zip_reading_stops_when_cancelled() {
+        let stl = stl_bytes(&[[v(0., 0., 0.), v(1., 0., 0.), v(0., 1., 0.)]]);
+        let bytes = zip_bytes(&[("part.stl", &stl, false)]);
+        assert!(read_zip(&bytes, |_| false).is_err());
+    }
+
+    #[test]
+    fn numbered_paths() {
+        assert_eq!(numbered_path("part.stl", 7, 12), "part_07.stl");
+        assert_eq!(numbered_path("part", 3, 5), "part_3.stl");
+    }
+}
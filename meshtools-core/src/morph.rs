@@ -0,0 +1,66 @@
+use std::io::{Error, ErrorKind};
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::bvh::Bvh;
+use crate::mesh_file;
+use crate::triangle::{self, Triangle};
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// A mesh whose vertices each know the nearest point on another mesh,
+/// so it can be blended into that mesh's shape
+pub struct Morph {
+    mesh: TriangleMesh,
+    /// Where each vertex of the mesh ends up
+    targets: Vec<Vec3>
+}
+
+impl Morph {
+    /// Matches each vertex of the source to the nearest point of the target.
+    /// The two should already be aligned.
+    ///
+    /// The progress callback gets the fraction done, and returns false to stop,
+    /// in which case None is returned.
+    pub fn new(source: &[Triangle], target: &[Triangle], mut progress: impl FnMut(f32) -> bool) -> Option<Self> {
+        profile_span!("morph");
+        // Welded, so the vertices triangles share move together
+        let mesh = TriangleMesh::new(source, triangle_mesh::default_tolerance(source));
+        let bvh = Bvh::new(target);
+        let mut targets = Vec::with_capacity(mesh.vertices.len());
+        for (i, vertex) in mesh.vertices.iter().enumerate() {
+            targets.push(bvh.closest(target, vertex).map_or(*vertex, |(_, nearest)| nearest));
+            if i % 4096 == 0 && !progress(i as f32 / mesh.vertices.len() as f32) {
+                return None;
+            }
+        }
+        return Some(Self { mesh, targets });
+    }
+
+    /// The source with each vertex moved a fraction t of the way to its match,
+    /// from the source at 0 to the target's shape at 1
+    pub fn at(&self, t: f32) -> Vec<Triangle> {
+        return self.mesh.triangles.iter()
+            .map(|triangle| triangle.map(|v| self.mesh.vertices[v] + (self.targets[v] - self.mesh.vertices[v]) * t))
+            .collect();
+    }
+
+    /// Writes evenly spaced stages from the source to the target, both included,
+    /// as binary STL files numbered after the given path.
+    /// Returns the paths written.
+    ///
+    /// The progress callback gets the fraction done, and returns false to stop with an error.
+    pub fn export_stages(&self, path: &str, count: usize, mut progress: impl FnMut(f32) -> bool)
+    -> Result<Vec<String>, Error> {
+        let count = count.max(2);
+        let mut written = Vec::new();
+        for stage in 0..count {
+            if !progress(stage as f32 / count as f32) {
+                return Err(Error::new(ErrorKind::Interrupted, "Cancelled"));
+            }
+            let file = mesh_file::numbered_path(path, stage + 1, count);
+            triangle::write_stl_binary(&file, &self.at(stage as f32 / (count - 1) as f32))?;
+            written.push(file);
+        }
+        return Ok(written);
+    }
+}
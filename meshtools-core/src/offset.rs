@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::i18n::tr;
+use crate::triangle::{self, Triangle};
+
+/// Most grid points along the longest side of the box
+pub const MAX_RESOLUTION: usize = 512;
+
+/// Most grid points in all, to keep the memory used in check. Each point keeps a distance,
+/// the index of its closest triangle, and whether it is inside, so this is about 300 MB.
+const MAX_GRID_POINTS: usize = 1 << 25;
+
+/// What surface an offset builds
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OffsetMode {
+    /// A wall of the given thickness centered on the surface, which may be open,
+    /// such as a scanned surface
+    Shell,
+    /// The surface moved outward along its normals, or inward for a negative distance.
+    /// The mesh should be closed.
+    Inflate
+}
+
+impl OffsetMode {
+    pub const ALL: [OffsetMode; 2] = [OffsetMode::Shell, OffsetMode::Inflate];
+
+    /// The translated name of the mode
+    pub fn label(&self) -> String {
+        match self {
+            OffsetMode::Shell => tr("offset-shell"),
+            OffsetMode::Inflate => tr("offset-inflate")
+        }
+    }
+}
+
+/// A grid of values at evenly spaced points
+struct Grid {
+    origin: Vec3,
+    spacing: f32,
+    size: [usize; 3],
+    values: Vec<f32>
+}
+
+impl Grid {
+    fn index(&self, i: usize, j: usize, k: usize) -> usize {
+        return (k * self.size[1] + j) * self.size[0] + i;
+    }
+
+    fn point(&self, i: usize, j: usize, k: usize) -> Vec3 {
+        return self.origin + Vec3::new(i as f32, j as f32, k as f32) * self.spacing;
+    }
+
+    /// The range of grid indices along an axis covering coordinates from low to high
+    fn range(&self, axis: usize, low: f32, high: f32) -> std::ops::Range<usize> {
+        let start = ((low - self.origin[axis]) / self.spacing).floor().max(0.) as usize;
+        let end = (((high - self.origin[axis]) / self.spacing).ceil() as usize + 1).min(self.size[axis]);
+        return start..end.max(start);
+    }
+}
+
+/// Whether p is to the left of the line through a and b in the xy plane.
+/// An edge gives the same answer whichever way round it is passed, and
+/// points on the line fall on exactly one side, so a line along z crosses
+/// exactly one of two triangles meeting at an edge.
+fn left_of(p: &Vec3, a: &Vec3, b: &Vec3) -> bool {
+    if (a.x, a.y) > (b.x, b.y) {
+        return !left_of(p, b, a);
+    }
+    return (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x) >= 0.;
+}
+
+/// Marks the grid points inside a closed mesh, by counting the triangles
+/// crossed by a line along z below each point
+fn inside_points(triangles: &[Triangle], grid: &Grid) -> Vec<bool> {
+    let mut crossings = vec![Vec::<f32>::new(); grid.size[0] * grid.size[1]];
+    for [a, b, c] in triangles {
+        let min = glm::min2(&glm::min2(a, b), c);
+        let max = glm::max2(&glm::max2(a, b), c);
+        let area = (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y);
+        if area == 0. {
+            continue;
+        }
+        for j in grid.range(1, min.y, max.y) {
+            for i in grid.range(0, min.x, max.x) {
+                let p = grid.point(i, j, 0);
+                let side = left_of(&p, a, b);
+                if left_of(&p, b, c) != side || left_of(&p, c, a) != side {
+                    continue;
+                }
+                let u = ((b.x - p.x) * (c.y - p.y) - (c.x - p.x) * (b.y - p.y)) / area;
+                let v = ((c.x - p.x) * (a.y - p.y) - (a.x - p.x) * (c.y - p.y)) / area;
+                crossings[j * grid.size[0] + i].push(u * a.z + v * b.z + (1. - u - v) * c.z);
+            }
+        }
+    }
+    let mut inside = vec![false; grid.values.len()];
+    for j in 0..grid.size[1] {
+        for i in 0..grid.size[0] {
+            let column = &mut crossings[j * grid.size[0] + i];
+            column.sort_by(|a, b| a.total_cmp(b));
+            let mut crossed = 0;
+            for k in 0..grid.size[2] {
+                let z = grid.point(i, j, k).z;
+                while crossed < column.len() && column[crossed] < z {
+                    crossed += 1;
+                }
+                inside[grid.index(i, j, k)] = crossed % 2 == 1;
+            }
+        }
+    }
+    return inside;
+}
+
+/// Grid points needed to cover a box with these sides, at least as many as `offset` uses
+/// with the extra points around the box
+fn point_count(extent: &Vec3, spacing: f32) -> usize {
+    return extent.iter().map(|side| (side / spacing).ceil() as usize + 5).product();
+}
+
+/// The distance between grid points when offsetting a mesh with the given bounds,
+/// with `resolution` points along the longest side of the result.
+/// The spacing is widened where needed to keep the grid to `MAX_GRID_POINTS`.
+pub fn grid_spacing(min: &Vec3, max: &Vec3, mode: OffsetMode, distance: f32, resolution: usize) -> f32 {
+    let margin = level(mode, distance).max(0.);
+    let extent = (max - min).add_scalar(2. * margin);
+    let mut spacing = extent.max() / resolution.clamp(2, MAX_RESOLUTION) as f32;
+    // A box as deep as it is wide fills the grid fastest, while flat parts can keep the full resolution
+    while spacing > 0. && point_count(&extent, spacing) > MAX_GRID_POINTS {
+        spacing *= 1.05;
+    }
+    return spacing;
+}
+
+/// The distance from the mesh where the new surface lies
+fn level(mode: OffsetMode, distance: f32) -> f32 {
+    return match mode {
+        OffsetMode::Shell => distance.abs() / 2.,
+        OffsetMode::Inflate => distance
+    };
+}
+
+/// Builds the surface at a distance from a mesh, by finding the distance
+/// to the mesh on a grid of points, and meshing where it crosses the offset.
+/// Details smaller than the grid spacing are lost.
+///
+/// The progress callback gets the fraction done, and returns false to stop,
+/// in which case None is returned.
+pub fn offset(triangles: &[Triangle], mode: OffsetMode, distance: f32, resolution: usize,
+    mut progress: impl FnMut(f32) -> bool)
+-> Option<Vec<Triangle>> {
+    profile_span!("offset");
+    let (mut min, mut max) = (Vec3::repeat(f32::MAX), Vec3::repeat(f32::MIN));
+    for triangle in triangles {
+        for vertex in triangle {
+            min = glm::min2(&min, vertex);
+            max = glm::max2(&max, vertex);
+        }
+    }
+    if triangles.is_empty() {
+        return Some(Vec::new());
+    }
+    let level = level(mode, distance);
+    let margin = level.max(0.);
+    let spacing = grid_spacing(&min, &max, mode, distance, resolution);
+    // Only points near the new surface need an exact distance. The rest are just far.
+    let band = level.abs() + 2. * spacing;
+    let origin = min.add_scalar(-margin - 2. * spacing);
+    let size = ((max.add_scalar(margin + 2. * spacing) - origin) / spacing).map(|v| v.ceil() as usize + 1);
+    let size = [size.x, size.y, size.z];
+    let mut grid = Grid { origin, spacing, size, values: vec![f32::MAX; size[0] * size[1] * size[2]] };
+    // Triangle indices are kept as u32 to halve the memory of the largest grids
+    let mut closest = vec![u32::MAX; grid.values.len()];
+
+    // Exact distances at the points right around each triangle
+    for (n, triangle) in triangles.iter().enumerate() {
+        let low = glm::min2(&glm::min2(&triangle[0], &triangle[1]), &triangle[2]).add_scalar(-spacing);
+        let high = glm::max2(&glm::max2(&triangle[0], &triangle[1]), &triangle[2]).add_scalar(spacing);
+        for k in grid.range(2, low.z, high.z) {
+            for j in grid.range(1, low.y, high.y) {
+                for i in grid.range(0, low.x, high.x) {
+                    let point = grid.point(i, j, k);
+                    let d = (triangle::closest_point(&point, triangle) - point).norm();
+                    let index = grid.index(i, j, k);
+                    if d < grid.values[index] {
+                        grid.values[index] = d;
+                        closest[index] = n as u32;
+                    }
+                }
+            }
+        }
+        if n % 4096 == 0 && !progress(0.4 * n as f32 / triangles.len() as f32) {
+            return None;
+        }
+    }
+    // Then outward through the band, trying the triangles closest to each point's neighbors
+    let order = |count: usize, reverse: bool| -> Vec<usize> {
+        if reverse { (0..count).rev().collect() } else { (0..count).collect() }
+    };
+    let directions: Vec<[bool; 3]> = (0..8).map(|d| [d & 1 == 1, d & 2 == 2, d & 4 == 4]).collect();
+    for (n, [reverse_i, reverse_j, reverse_k]) in directions.iter().enumerate() {
+        let (columns, rows) = (order(size[0], *reverse_i), order(size[1], *reverse_j));
+        for k in order(size[2], *reverse_k) {
+            for &j in &rows {
+                for &i in &columns {
+                    let index = grid.index(i, j, k);
+                    let point = grid.point(i, j, k);
+                    let neighbors = [
+                        if *reverse_i { (i + 1 < size[0]).then(|| grid.index(i + 1, j, k)) } else { (i > 0).then(|| grid.index(i - 1, j, k)) },
+                        if *reverse_j { (j + 1 < size[1]).then(|| grid.index(i, j + 1, k)) } else { (j > 0).then(|| grid.index(i, j - 1, k)) },
+                        if *reverse_k { (k + 1 < size[2]).then(|| grid.index(i, j, k + 1)) } else { (k > 0).then(|| grid.index(i, j, k - 1)) }];
+                    for neighbor in neighbors.into_iter().flatten() {
+                        let nearest = closest[neighbor];
+                        if nearest == u32::MAX || nearest == closest[index] || grid.values[neighbor] > band {
+                            continue;
+                        }
+                        let d = (triangle::closest_point(&point, &triangles[nearest as usize]) - point).norm();
+                        if d < grid.values[index] {
+                            grid.values[index] = d;
+                            closest[index] = nearest;
+                        }
+                    }
+                }
+            }
+        }
+        if !progress(0.4 + 0.4 * (n + 1) as f32 / 8.) {
+            return None;
+        }
+    }
+    for value in grid.values.iter_mut() {
+        *value = value.min(band);
+    }
+    if mode == OffsetMode::Inflate {
+        let inside = inside_points(triangles, &grid);
+        for (value, inside) in grid.values.iter_mut().zip(inside) {
+            if inside {
+                *value = -*value;
+            }
+        }
+    }
+    for value in grid.values.iter_mut() {
+        *value -= level;
+    }
+    if !progress(0.9) {
+        return None;
+    }
+    return Some(surface_nets(&grid));
+}
+
+/// Meshes where the values of a grid cross zero, with a vertex in each cell
+/// the surface passes through, joined across each grid edge it crosses.
+/// Negative values are inside.
+fn surface_nets(grid: &Grid) -> Vec<Triangle> {
+    let [nx, ny, nz] = grid.size;
+    let corners: Vec<[usize; 3]> = (0..8).map(|c| [c & 1, (c >> 1) & 1, (c >> 2) & 1]).collect();
+    let mut vertices = HashMap::<[usize; 3], Vec3>::new();
+    for k in 0..nz - 1 {
+        for j in 0..ny - 1 {
+            for i in 0..nx - 1 {
+                let values: Vec<f32> = corners.iter()
+                    .map(|[x, y, z]| grid.values[grid.index(i + x, j + y, k + z)])
+                    .collect();
+                if values.iter().all(|v| *v < 0.) || values.iter().all(|v| *v >= 0.) {
+                    continue;
+                }
+                // The average of the points where the cell's edges cross zero
+                let mut sum = Vec3::zeros();
+                let mut count = 0;
+                for a in 0..8 {
+                    for axis in 0..3 {
+                        let b = a | (1 << axis);
+                        if b == a || (values[a] < 0.) == (values[b] < 0.) {
+                            continue;
+                        }
+                        let t = values[a] / (values[a] - values[b]);
+                        let [x, y, z] = corners[a];
+                        let start = grid.point(i + x, j + y, k + z);
+                        let mut step = Vec3::zeros();
+                        step[axis] = grid.spacing;
+                        sum += start + step * t;
+                        count += 1;
+                    }
+                }
+                vertices.insert([i, j, k], sum / count as f32);
+            }
+        }
+    }
+    let mut triangles = Vec::<Triangle>::new();
+    for k in 1..nz - 1 {
+        for j in 1..ny - 1 {
+            for i in 1..nx - 1 {
+                let value = grid.values[grid.index(i, j, k)];
+                for axis in 0..3 {
+                    let mut next = [i, j, k];
+                    next[axis] += 1;
+                    if next[axis] >= grid.size[axis] {
+                        continue;
+                    }
+                    let other = grid.values[grid.index(next[0], next[1], next[2])];
+                    if (value < 0.) == (other < 0.) {
+                        continue;
+                    }
+                    // The four cells around the edge
+                    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                    let cell = |du: usize, dv: usize| {
+                        let mut cell = [i, j, k];
+                        cell[u] -= du;
+                        cell[v] -= dv;
+                        return vertices.get(&cell).copied();
+                    };
+                    let quad = match (cell(1, 1), cell(0, 1), cell(0, 0), cell(1, 0)) {
+                        (Some(a), Some(b), Some(c), Some(d)) => [a, b, c, d],
+                        _ => continue
+                    };
+                    // Face out of the negative side
+                    let mut outward = Vec3::zeros();
+                    outward[axis] = if value < 0. { 1. } else { -1. };
+                    let normal = glm::cross(&(quad[2] - quad[0]), &(quad[3] - quad[1]));
+                    let [a, b, c, d] = if glm::dot(&normal, &outward) < 0. { [quad[3], quad[2], quad[1], quad[0]] } else { quad };
+                    triangles.push([a, b, c]);
+                    triangles.push([a, c, d]);
+                }
+            }
+        }
+    }
+    return triangles;
+}
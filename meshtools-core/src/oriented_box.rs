@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+extern crate nalgebra_glm as glm;
+use glm::{Mat3, Mat4, Vec2, Vec3};
+
+use crate::triangle::{self, Triangle};
+
+/// Most face directions tried as an axis of the box
+const MAX_CANDIDATES: usize = 64;
+
+/// A box around a mesh, turned to fit it as tightly as possible
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OrientedBox {
+    pub center: Vec3,
+    /// Unit axes of the box as columns, longest side first, turning the same way as x, y, and z
+    pub axes: Mat3,
+    /// Length of the box along each axis
+    pub size: Vec3
+}
+
+impl OrientedBox {
+    pub fn volume(&self) -> f32 {
+        return self.size.x * self.size.y * self.size.z;
+    }
+
+    /// Turns a model about the center of the box, so the box's axes line up with x, y, and z
+    pub fn alignment(&self) -> Mat4 {
+        let rotation = glm::mat3_to_mat4(&self.axes.transpose());
+        return glm::translation(&self.center) * rotation * glm::translation(&-self.center);
+    }
+
+    /// Finds a box of close to the smallest volume around triangles, as placed by a transformation.
+    ///
+    /// The smallest box usually has a side flush with a face of the mesh, so the directions
+    /// of its largest flat areas and its principal axes are tried, each with the smallest
+    /// rectangle around the mesh seen along it.
+    /// The progress callback gets the fraction done, and returns false to stop,
+    /// in which case None is returned.
+    pub fn new(triangles: &[Triangle], transformation: &Mat4, mut progress: impl FnMut(f32) -> bool) -> Option<Self> {
+        profile_span!("oriented_box");
+        let placed: Vec<Triangle> = triangles.iter().map(|t| triangle::transform(t, transformation)).collect();
+        let mut seen = HashSet::<[u32; 3]>::new();
+        let points: Vec<Vec3> = placed.iter()
+            .flatten()
+            .filter(|p| seen.insert([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]))
+            .copied()
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        let candidates = candidate_axes(&placed, &points);
+        let mut best: Option<OrientedBox> = None;
+        for (i, axis) in candidates.iter().enumerate() {
+            if !progress(i as f32 / candidates.len() as f32) {
+                return None;
+            }
+            let found = fit_around_axis(&points, axis);
+            if !matches!(&best, Some(best) if found.volume() >= best.volume()) {
+                best = Some(found);
+            }
+        }
+        return best;
+    }
+}
+
+/// Directions of the largest flat areas of triangles, then the principal axes of the points,
+/// then x, y, and z
+fn candidate_axes(triangles: &[Triangle], points: &[Vec3]) -> Vec<Vec3> {
+    // Triangles facing nearly the same way, either way, are grouped by rounding their normals
+    let mut areas = HashMap::<[i32; 3], (Vec3, f32)>::new();
+    for t in triangles {
+        let cross = (t[1] - t[0]).cross(&(t[2] - t[0]));
+        let mut normal = match cross.try_normalize(0.) {
+            Some(normal) => normal,
+            None => continue
+        };
+        let first = if normal.x.abs() > 1e-4 { normal.x } else if normal.y.abs() > 1e-4 { normal.y } else { normal.z };
+        if first < 0. {
+            normal = -normal;
+        }
+        let key = [normal.x, normal.y, normal.z].map(|c| (c * 64.).round() as i32);
+        let entry = areas.entry(key).or_insert((Vec3::zeros(), 0.));
+        let area = cross.norm() / 2.;
+        entry.0 += normal * area;
+        entry.1 += area;
+    }
+    let mut groups: Vec<(Vec3, f32)> = areas.into_values().collect();
+    groups.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut axes: Vec<Vec3> = groups.iter()
+        .take(MAX_CANDIDATES)
+        .filter_map(|(sum, _)| sum.try_normalize(f32::EPSILON))
+        .collect();
+
+    let center = points.iter().sum::<Vec3>() / points.len() as f32;
+    let covariance = points.iter().fold(Mat3::zeros(), |sum, p| sum + (p - center) * (p - center).transpose());
+    let eigen = covariance.symmetric_eigen();
+    for i in 0..3 {
+        axes.push(eigen.eigenvectors.column(i).into_owned());
+    }
+    axes.extend([Vec3::x(), Vec3::y(), Vec3::z()]);
+    return axes.into_iter().filter_map(|axis| axis.try_normalize(f32::EPSILON)).collect();
+}
+
+/// The box with one axis along a direction, and the smallest rectangle around the points across it
+fn fit_around_axis(points: &[Vec3], axis: &Vec3) -> OrientedBox {
+    let u = axis.cross(&if axis.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() }).normalize();
+    let v = axis.cross(&u);
+    let flat: Vec<Vec2> = points.iter().map(|p| Vec2::new(p.dot(&u), p.dot(&v))).collect();
+    let hull = convex_hull(flat);
+
+    // The smallest rectangle around a convex polygon has a side along one of its edges.
+    // If all the points line up with the axis, there are no edges, and the rectangle is the point.
+    let mut best = (f32::MAX, Vec2::x(), hull[0], hull[0]);
+    for i in 0..hull.len() {
+        let edge = match (hull[(i + 1) % hull.len()] - hull[i]).try_normalize(0.) {
+            Some(edge) => edge,
+            None => continue
+        };
+        let across = Vec2::new(-edge.y, edge.x);
+        let mut min = Vec2::repeat(f32::MAX);
+        let mut max = Vec2::repeat(f32::MIN);
+        for p in &hull {
+            let local = Vec2::new(p.dot(&edge), p.dot(&across));
+            min = glm::min2(&min, &local);
+            max = glm::max2(&max, &local);
+        }
+        let area = (max.x - min.x) * (max.y - min.y);
+        if area < best.0 {
+            best = (area, edge, min, max);
+        }
+    }
+    let (_, edge, min, max) = best;
+    let first = u * edge.x + v * edge.y;
+    let second = axis.cross(&first);
+    let along = points.iter().map(|p| p.dot(axis));
+    let (low, high) = along.fold((f32::MAX, f32::MIN), |(low, high), d| (low.min(d), high.max(d)));
+
+    let middle = (min + max) / 2.;
+    let center = first * middle.x + second * middle.y + axis * (low + high) / 2.;
+    let mut sides = [(first, max.x - min.x), (second, max.y - min.y), (*axis, high - low)];
+    sides.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut axes = Mat3::from_columns(&sides.map(|(axis, _)| axis));
+    if axes.determinant() < 0. {
+        axes.set_column(2, &-axes.column(2));
+    }
+    return OrientedBox { center, axes, size: Vec3::new(sides[0].1, sides[1].1, sides[2].1) };
+}
+
+/// The corners of the smallest convex polygon around points, counterclockwise
+fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let turn = |o: &Vec2, a: &Vec2, b: &Vec2| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+    let mut hull = Vec::<Vec2>::with_capacity(points.len() * 2);
+    // The lower chain left to right, then the upper chain back
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2 && turn(&hull[hull.len() - 2], &hull[hull.len() - 1], &p) <= 0. {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+    return hull;
+}
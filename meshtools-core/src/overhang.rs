@@ -0,0 +1,56 @@
+extern crate nalgebra_glm as glm;
+use glm::Mat4;
+
+use crate::triangle::{self, Triangle};
+
+/// Color of triangles leaning out too far to print without support
+pub const OVERHANG_COLOR: [f32; 3] = [1., 0.2, 0.2];
+/// Color of triangles that print without support
+pub const SUPPORTED_COLOR: [f32; 3] = [0.75, 0.75, 0.75];
+
+/// How far the underside of each triangle of a model, as placed in the scene, leans out from vertical
+pub struct Overhang {
+    /// Degrees from vertical each triangle faces down, from 0 for walls to 90 for ceilings.
+    /// Triangles facing up, or resting on the bed at the bottom of the model, are 0.
+    pub angles: Vec<f32>,
+    pub areas: Vec<f32>
+}
+
+impl Overhang {
+    /// Finds the overhang of each triangle, as placed by a transformation, with the bed below along -Z
+    pub fn new(triangles: &[Triangle], transformation: &Mat4) -> Self {
+        profile_span!("overhang");
+        let placed: Vec<Triangle> = triangles.iter().map(|t| triangle::transform(t, transformation)).collect();
+        let (bottom, top) = placed.iter().flatten()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(bottom, top), v| (bottom.min(v.z), top.max(v.z)));
+        let on_bed = ((top - bottom) * 1e-4).max(f32::EPSILON);
+        let mut angles = Vec::with_capacity(placed.len());
+        let mut areas = Vec::with_capacity(placed.len());
+        for [a, b, c] in &placed {
+            let area_normal = (b - a).cross(&(c - a));
+            areas.push(area_normal.norm() / 2.);
+            let resting = [a, b, c].iter().all(|v| v.z - bottom <= on_bed);
+            let angle = match area_normal.try_normalize(0.) {
+                Some(normal) if normal.z < 0. && !resting => (-normal.z).min(1.).asin().to_degrees(),
+                _ => 0.
+            };
+            angles.push(angle);
+        }
+        return Self { angles, areas };
+    }
+
+    /// The color of each corner, three to a triangle, marking triangles leaning out further than
+    /// `max_angle` degrees from vertical
+    pub fn colors(&self, max_angle: f32) -> Vec<[f32; 3]> {
+        return self.angles.iter()
+            .flat_map(|angle| [if *angle > max_angle { OVERHANG_COLOR } else { SUPPORTED_COLOR }; 3])
+            .collect();
+    }
+
+    /// The total area of the triangles leaning out further than `max_angle` degrees from vertical
+    pub fn area(&self, max_angle: f32) -> f32 {
+        return self.angles.iter().zip(&self.areas)
+            .filter(|(angle, _)| **angle > max_angle)
+            .fold(0., |total, (_, area)| total + area);
+    }
+}
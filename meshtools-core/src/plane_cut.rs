@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+extern crate nalgebra_glm as glm;
+use glm::{Vec2, Vec3};
+
+use crate::triangle::Triangle;
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// The points p where normal·p = offset
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub offset: f32
+}
+
+impl Plane {
+    /// Signed distance from the plane, positive on the side the normal points to
+    pub fn distance(&self, point: &Vec3) -> f32 {
+        return glm::dot(&self.normal, point) - self.offset;
+    }
+}
+
+/// Splits triangles by a plane into the part in front of it and the part behind it.
+/// Where the plane crosses a closed surface, each part is closed again with a flat cap.
+pub fn cut(triangles: &[Triangle], plane: &Plane) -> (Vec<Triangle>, Vec<Triangle>) {
+    profile_span!("cut");
+    let tolerance = triangle_mesh::default_tolerance(triangles);
+    let mut front = Vec::<Triangle>::new();
+    let mut back = Vec::<Triangle>::new();
+    for triangle in triangles {
+        split(triangle, plane, tolerance, &mut front, &mut back);
+    }
+    let front_cap = cap(&front, plane, tolerance, &-plane.normal);
+    let back_cap = cap(&back, plane, tolerance, &plane.normal);
+    front.extend(front_cap);
+    back.extend(back_cap);
+    return (front, back);
+}
+
+/// A chain of points where a plane crosses a surface
+pub struct SectionLoop {
+    pub points: Vec<Vec3>,
+    /// Whether the last point joins back to the first. Chains stay open where the surface has a gap.
+    pub closed: bool
+}
+
+impl SectionLoop {
+    pub fn perimeter(&self) -> f32 {
+        let mut length: f32 = self.points.windows(2).map(|pair| glm::distance(&pair[0], &pair[1])).sum();
+        if let (true, Some(first), Some(last)) = (self.closed, self.points.first(), self.points.last()) {
+            length += glm::distance(first, last);
+        }
+        return length;
+    }
+
+    /// Area inside a closed loop, seen along the plane's normal. It is positive around solid
+    /// and negative around holes, so adding up the loops gives the area of the cross section.
+    pub fn area(&self, normal: &Vec3) -> f32 {
+        if !self.closed || self.points.len() < 3 {
+            return 0.;
+        }
+        let origin = self.points[0];
+        let twice: Vec3 = (1..self.points.len() - 1)
+            .map(|i| glm::cross(&(self.points[i] - origin), &(self.points[i + 1] - origin)))
+            .sum();
+        return glm::dot(&twice, normal) / 2.;
+    }
+}
+
+/// Finds the loops where a plane crosses triangles. Loops around solid go counterclockwise
+/// seen along the plane's normal, and loops around holes go clockwise.
+pub fn section(triangles: &[Triangle], plane: &Plane) -> Vec<SectionLoop> {
+    profile_span!("section");
+    let mut segments = Vec::<(Vec3, Vec3)>::new();
+    for triangle in triangles {
+        // Corners on the plane count as in front, so each edge crossing has one end on each side
+        let distances = triangle.map(|v| plane.distance(&v));
+        let crossings: Vec<Vec3> = (0..3)
+            .filter(|i| (distances[*i] >= 0.) != (distances[(i + 1) % 3] >= 0.))
+            .map(|i| {
+                let j = (i + 1) % 3;
+                intersection(&triangle[i], &triangle[j], distances[i], distances[j])
+            })
+            .collect();
+        if crossings.len() != 2 || crossings[0] == crossings[1] {
+            continue;
+        }
+        // The surface's outward normal turned about the plane's normal gives the loop's direction
+        let normal = glm::cross(&(triangle[1] - triangle[0]), &(triangle[2] - triangle[0]));
+        let along = glm::cross(&plane.normal, &normal);
+        if glm::dot(&(crossings[1] - crossings[0]), &along) >= 0. {
+            segments.push((crossings[0], crossings[1]));
+        } else {
+            segments.push((crossings[1], crossings[0]));
+        }
+    }
+
+    // Triangles sharing an edge get exactly the same crossing, so ends can be matched exactly
+    let key = |p: &Vec3| [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+    let mut starting = HashMap::<[u32; 3], Vec<usize>>::new();
+    let mut ending = HashMap::<[u32; 3], Vec<usize>>::new();
+    for (i, (start, end)) in segments.iter().enumerate() {
+        starting.entry(key(start)).or_default().push(i);
+        ending.entry(key(end)).or_default().push(i);
+    }
+    let mut used = vec![false; segments.len()];
+    let take = |map: &HashMap<[u32; 3], Vec<usize>>, point: &Vec3, used: &mut Vec<bool>| -> Option<usize> {
+        let next = *map.get(&key(point))?.iter().find(|i| !used[**i])?;
+        used[next] = true;
+        return Some(next);
+    };
+    let mut loops = Vec::<SectionLoop>::new();
+    for first in 0..segments.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let (start, mut end) = segments[first];
+        let mut points = vec![start];
+        while key(&end) != key(&start) {
+            match take(&starting, &end, &mut used) {
+                Some(next) => {
+                    points.push(end);
+                    end = segments[next].1;
+                },
+                None => break
+            }
+        }
+        if key(&end) == key(&start) {
+            loops.push(SectionLoop { points, closed: true });
+            continue;
+        }
+        // Open chains are followed back from their start as well
+        points.push(end);
+        let mut backward = Vec::<Vec3>::new();
+        let mut start = start;
+        while let Some(previous) = take(&ending, &start, &mut used) {
+            start = segments[previous].0;
+            backward.push(start);
+        }
+        backward.reverse();
+        backward.extend(points);
+        loops.push(SectionLoop { points: backward, closed: false });
+    }
+    return loops;
+}
+
+/// Where the plane crosses the edge between two points. The ends are put in a fixed order,
+/// so both triangles sharing an edge get exactly the same point.
+fn intersection(a: &Vec3, b: &Vec3, da: f32, db: f32) -> Vec3 {
+    let (a, b, da, db) = if a.as_slice() < b.as_slice() { (a, b, da, db) } else { (b, a, db, da) };
+    return a + (b - a) * (da / (da - db));
+}
+
+/// Adds the parts of a triangle on each side of the plane to the front and back lists.
+/// Corners within the tolerance of the plane count as on it.
+fn split(triangle: &Triangle, plane: &Plane, tolerance: f32, front: &mut Vec<Triangle>, back: &mut Vec<Triangle>) {
+    let distances = triangle.map(|v| {
+        let d = plane.distance(&v);
+        if d.abs() <= tolerance { 0. } else { d }
+    });
+    if distances.iter().all(|d| *d >= 0.) {
+        // Triangles lying in the plane are left out, since the caps cover them
+        if distances.iter().any(|d| *d > 0.) {
+            front.push(*triangle);
+        }
+        return;
+    }
+    if distances.iter().all(|d| *d <= 0.) {
+        back.push(*triangle);
+        return;
+    }
+    let mut front_polygon = Vec::<Vec3>::with_capacity(4);
+    let mut back_polygon = Vec::<Vec3>::with_capacity(4);
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        let (d, next) = (distances[i], distances[j]);
+        if d >= 0. {
+            front_polygon.push(triangle[i]);
+        }
+        if d <= 0. {
+            back_polygon.push(triangle[i]);
+        }
+        if (d > 0. && next < 0.) || (d < 0. && next > 0.) {
+            let point = intersection(&triangle[i], &triangle[j], d, next);
+            front_polygon.push(point);
+            back_polygon.push(point);
+        }
+    }
+    // Each part is convex, so a fan keeps the winding
+    for (polygon, output) in [(front_polygon, front), (back_polygon, back)] {
+        for i in 1..polygon.len().saturating_sub(1) {
+            output.push([polygon[0], polygon[i], polygon[i + 1]]);
+        }
+    }
+}
+
+/// Triangles closing the open loops that lie in the plane, facing outward
+fn cap(triangles: &[Triangle], plane: &Plane, tolerance: f32, outward: &Vec3) -> Vec<Triangle> {
+    let mesh = TriangleMesh::new(triangles, tolerance);
+    let on_plane = |v: &usize| plane.distance(&mesh.vertices[*v]).abs() <= tolerance * 4.;
+    let loops: Vec<Vec<usize>> = mesh.boundary_loops().into_iter()
+        .filter(|boundary| boundary.len() >= 3 && boundary.iter().all(on_plane))
+        .collect();
+    // Flatten the loops onto the plane
+    let u = if plane.normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+    let u = glm::cross(&plane.normal, &u).normalize();
+    let v = glm::cross(&plane.normal, &u);
+    let polygons: Vec<Vec<(usize, Vec2)>> = loops.iter()
+        .map(|boundary| boundary.iter()
+            .map(|i| (*i, Vec2::new(glm::dot(&mesh.vertices[*i], &u), glm::dot(&mesh.vertices[*i], &v))))
+            .collect())
+        .collect();
+    // Counter-clockwise in u and v faces along the normal, since u × v is the normal
+    let reverse = glm::dot(&plane.normal, outward) < 0.;
+    return triangulate(polygons).into_iter()
+        .map(|corners| {
+            let [a, b, c] = corners.map(|i| mesh.vertices[i]);
+            if reverse { [a, c, b] } else { [a, b, c] }
+        })
+        .collect();
+}
+
+/// Twice the signed area of a polygon, positive when counter-clockwise
+fn signed_area(polygon: &[(usize, Vec2)]) -> f32 {
+    let mut area = 0.;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i].1, polygon[(i + 1) % polygon.len()].1);
+        area += a.x * b.y - b.x * a.y;
+    }
+    return area;
+}
+
+fn contains(polygon: &[(usize, Vec2)], point: &Vec2) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i].1, polygon[(i + 1) % polygon.len()].1);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    return inside;
+}
+
+fn cross(a: &Vec2, b: &Vec2) -> f32 {
+    return a.x * b.y - a.y * b.x;
+}
+
+/// Whether p is inside or on the edge of the counter-clockwise triangle abc
+fn in_triangle(p: &Vec2, a: &Vec2, b: &Vec2, c: &Vec2) -> bool {
+    return cross(&(b - a), &(p - a)) >= 0. && cross(&(c - b), &(p - b)) >= 0. && cross(&(a - c), &(p - c)) >= 0.;
+}
+
+/// Triangulates the regions bounded by loops in a plane. Loops inside other loops are holes,
+/// and loops inside holes are islands, and so on. Triangles are given by the ids of their corners.
+pub fn triangulate(polygons: Vec<Vec<(usize, Vec2)>>) -> Vec<[usize; 3]> {
+    let polygons: Vec<Vec<(usize, Vec2)>> = polygons.into_iter()
+        .filter(|polygon| signed_area(polygon).abs() > 0.)
+        .collect();
+    // How many other loops each loop is inside
+    let depths: Vec<usize> = polygons.iter().enumerate()
+        .map(|(i, polygon)| polygons.iter().enumerate()
+            .filter(|(j, other)| *j != i && contains(other, &polygon[0].1))
+            .count())
+        .collect();
+    let mut triangles = Vec::<[usize; 3]>::new();
+    for (i, outer) in polygons.iter().enumerate() {
+        if depths[i] % 2 == 1 {
+            continue;
+        }
+        let mut merged = outer.clone();
+        if signed_area(&merged) < 0. {
+            merged.reverse();
+        }
+        let mut holes: Vec<Vec<(usize, Vec2)>> = polygons.iter().enumerate()
+            .filter(|(j, hole)| depths[*j] == depths[i] + 1 && contains(outer, &hole[0].1))
+            .map(|(_, hole)| {
+                let mut hole = hole.clone();
+                if signed_area(&hole) > 0. {
+                    hole.reverse();
+                }
+                hole
+            })
+            .collect();
+        // Holes furthest along x are joined first, so later bridges can't cross earlier ones
+        let max_x = |hole: &Vec<(usize, Vec2)>| hole.iter().map(|p| p.1.x).fold(f32::MIN, f32::max);
+        holes.sort_by(|a, b| max_x(b).total_cmp(&max_x(a)));
+        for hole in holes {
+            bridge(&mut merged, &hole);
+        }
+        triangles.extend(ear_clip(&merged));
+    }
+    return triangles;
+}
+
+/// Joins a clockwise hole into a counter-clockwise polygon, with a pair of edges
+/// between the hole's rightmost point and a point of the polygon that can see it
+/// (Eberly, "Triangulation by Ear Clipping")
+fn bridge(polygon: &mut Vec<(usize, Vec2)>, hole: &[(usize, Vec2)]) {
+    let (m, point) = hole.iter().enumerate()
+        .max_by(|a, b| a.1.1.x.total_cmp(&b.1.1.x))
+        .map(|(i, p)| (i, p.1))
+        .unwrap();
+    // The nearest edge crossed by a ray from the point along x
+    let mut nearest: Option<(f32, usize)> = None;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i].1, polygon[(i + 1) % polygon.len()].1);
+        if (a.y > point.y) == (b.y > point.y) {
+            continue;
+        }
+        let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if x >= point.x && !matches!(nearest, Some((nearest_x, _)) if x >= nearest_x) {
+            nearest = Some((x, i));
+        }
+    }
+    let (x, edge) = match nearest {
+        Some(nearest) => nearest,
+        None => return
+    };
+    let hit = Vec2::new(x, point.y);
+    let next = (edge + 1) % polygon.len();
+    let mut visible = if polygon[edge].1.x > polygon[next].1.x { edge } else { next };
+    // Reflex corners inside the triangle between the point, the hit and the chosen corner
+    // could block the view, and the one at the smallest angle from the ray can't be blocked
+    let corner = polygon[visible].1;
+    let mut best_angle = f32::MAX;
+    for i in 0..polygon.len() {
+        let p = polygon[i].1;
+        let previous = polygon[(i + polygon.len() - 1) % polygon.len()].1;
+        let following = polygon[(i + 1) % polygon.len()].1;
+        let reflex = cross(&(p - previous), &(following - p)) < 0.;
+        if i == visible || !reflex || p == point {
+            continue;
+        }
+        let (a, b, c) = if cross(&(hit - point), &(corner - point)) >= 0. { (point, hit, corner) } else { (point, corner, hit) };
+        if in_triangle(&p, &a, &b, &c) {
+            let offset = p - point;
+            let angle = offset.y.abs().atan2(offset.x);
+            if angle < best_angle {
+                best_angle = angle;
+                visible = i;
+            }
+        }
+    }
+    let mut joined = Vec::<(usize, Vec2)>::with_capacity(polygon.len() + hole.len() + 2);
+    joined.extend_from_slice(&polygon[..=visible]);
+    joined.extend_from_slice(&hole[m..]);
+    joined.extend_from_slice(&hole[..=m]);
+    joined.extend_from_slice(&polygon[visible..]);
+    *polygon = joined;
+}
+
+/// Triangulates a counter-clockwise polygon by cutting off one corner at a time
+fn ear_clip(polygon: &[(usize, Vec2)]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<(usize, Vec2)> = polygon.to_vec();
+    let mut triangles = Vec::<[usize; 3]>::with_capacity(polygon.len());
+    let mut start = 0;
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let mut ear = None;
+        for step in 0..count {
+            let i = (start + step) % count;
+            let (a, b, c) = (remaining[(i + count - 1) % count].1, remaining[i].1, remaining[(i + 1) % count].1);
+            if cross(&(b - a), &(c - b)) <= 0. {
+                continue;
+            }
+            // Bridged holes repeat points, which don't block an ear they are a corner of
+            let blocked = remaining.iter().any(|(_, p)| {
+                *p != a && *p != b && *p != c && in_triangle(p, &a, &b, &c)
+            });
+            if !blocked {
+                ear = Some(i);
+                break;
+            }
+        }
+        // A polygon that folds over itself may have no clean ears, so cut off any corner to finish
+        let i = ear.unwrap_or(start % count);
+        if ear.is_some() {
+            triangles.push([remaining[(i + count - 1) % count].0, remaining[i].0, remaining[(i + 1) % count].0]);
+        }
+        remaining.remove(i);
+        start = if i == 0 { 0 } else { i - 1 };
+    }
+    if remaining.len() == 3 && cross(&(remaining[1].1 - remaining[0].1), &(remaining[2].1 - remaining[1].1)) > 0. {
+        triangles.push([remaining[0].0, remaining[1].0, remaining[2].0]);
+    }
+    return triangles;
+}
@@ -0,0 +1,16 @@
+/// Times the rest of the enclosing block under the given name.
+///
+/// With the `profiling` feature, this opens a `tracing` span, which the application
+/// can record. Otherwise it does nothing.
+#[cfg(feature = "profiling")]
+macro_rules! profile_span {
+    ( $name:expr ) => {
+        let _profile_span = tracing::info_span!($name).entered();
+    }
+}
+
+/// Times the rest of the enclosing block under the given name
+#[cfg(not(feature = "profiling"))]
+macro_rules! profile_span {
+    ( $name:expr ) => {}
+}
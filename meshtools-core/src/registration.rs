@@ -0,0 +1,158 @@
+use rayon::prelude::*;
+extern crate nalgebra_glm as glm;
+use glm::{DMat3, DVec3, Mat4, Vec3};
+
+use crate::bvh::Bvh;
+use crate::triangle::Triangle;
+
+/// Pairs farther apart than this many times the median distance are left out of each step,
+/// so the parts of a scan the reference doesn't have don't pull it off
+const OUTLIER_FACTOR: f32 = 3.;
+/// Refinement stops once a step improves the RMS distance by less than this fraction
+const CONVERGED: f32 = 1e-4;
+
+fn to_f64(v: &Vec3) -> DVec3 {
+    return DVec3::new(v.x as f64, v.y as f64, v.z as f64);
+}
+
+/// The rotation and translation that move points onto the points paired with them,
+/// with the least sum of squared distances (the Kabsch algorithm).
+/// None if there are fewer than three pairs, or the points are all on a line, so the turn about it is unknown.
+pub fn rigid_fit(from: &[Vec3], to: &[Vec3]) -> Option<Mat4> {
+    if from.len() != to.len() || from.len() < 3 {
+        return None;
+    }
+    // Summed in double precision, like the mass properties
+    let count = from.len() as f64;
+    let from_center = from.iter().map(to_f64).sum::<DVec3>() / count;
+    let to_center = to.iter().map(to_f64).sum::<DVec3>() / count;
+    let covariance: DMat3 = from.iter().zip(to)
+        .map(|(p, q)| (to_f64(p) - from_center) * (to_f64(q) - to_center).transpose())
+        .sum();
+    let svd = covariance.svd(true, true);
+    let mut singular = svd.singular_values.as_slice().to_vec();
+    singular.sort_by(|a, b| b.total_cmp(a));
+    if singular[0] <= f64::EPSILON || singular[1] <= singular[0] * 1e-9 {
+        return None;
+    }
+    let (u, v_t) = (svd.u?, svd.v_t?);
+    // A reflection fits as well when the points are flat, but the model can only be turned
+    let sign = (v_t.transpose() * u.transpose()).determinant().signum();
+    let rotation = v_t.transpose() * DMat3::from_diagonal(&DVec3::new(1., 1., sign)) * u.transpose();
+    let translation = to_center - rotation * from_center;
+    let mut matrix = glm::mat3_to_mat4(&glm::convert::<DMat3, glm::Mat3>(rotation));
+    matrix.set_column(3, &glm::convert::<DVec3, Vec3>(translation).push(1.));
+    return Some(matrix);
+}
+
+/// The root mean square distance between pairs of points, once the first of each is moved by a transformation
+pub fn rms_error(from: &[Vec3], to: &[Vec3], transformation: &Mat4) -> f32 {
+    if from.is_empty() {
+        return 0.;
+    }
+    let sum: f64 = from.iter().zip(to)
+        .map(|(p, q)| glm::distance2(&transformation.transform_point(&(*p).into()).coords, q) as f64)
+        .sum();
+    return (sum / from.len() as f64).sqrt() as f32;
+}
+
+/// How a set of points was fitted onto a surface by `refine`
+#[derive(Clone, Copy, Debug)]
+pub struct Refinement {
+    /// Moves the points onto the surface
+    pub transformation: Mat4,
+    /// The root mean square distance from the moved points to the surface
+    pub rms: f32,
+    pub iterations: usize
+}
+
+/// The nearest point on the triangles to each point, and the normal of the triangle it is on
+fn closest_points(points: &[Vec3], triangles: &[Triangle], bvh: &Bvh) -> Vec<Option<(Vec3, Vec3)>> {
+    return points.par_iter()
+        .map(|p| {
+            let (index, nearest) = bvh.closest(triangles, p)?;
+            let [a, b, c] = triangles[index];
+            return Some((nearest, glm::cross(&(b - a), &(c - a)).try_normalize(0.)?));
+        })
+        .collect();
+}
+
+fn surface_rms(points: &[Vec3], nearest: &[Option<(Vec3, Vec3)>]) -> f32 {
+    let distances: Vec<f32> = points.iter().zip(nearest)
+        .filter_map(|(p, q)| Some(glm::distance2(p, &(*q)?.0)))
+        .collect();
+    if distances.is_empty() {
+        return 0.;
+    }
+    return (distances.iter().map(|d| *d as f64).sum::<f64>() / distances.len() as f64).sqrt() as f32;
+}
+
+/// The small rigid motion that best moves points onto the planes through their paired points,
+/// with the rotation taken as nearly straight, so it is found by least squares (Chen and Medioni).
+/// None if the planes don't pin the motion down, as when they are all parallel.
+fn plane_fit(pairs: &[(Vec3, Vec3, Vec3)]) -> Option<Mat4> {
+    let mut normal_matrix = glm::TMat::<f64, 6, 6>::zeros();
+    let mut right = glm::TVec::<f64, 6>::zeros();
+    for (p, q, n) in pairs {
+        let (p, q, n) = (to_f64(p), to_f64(q), to_f64(n));
+        // Turning by a small angle about each axis and moving along each axis change the distance by these
+        let row = glm::TVec::<f64, 6>::from_iterator(p.cross(&n).iter().chain(n.iter()).copied());
+        normal_matrix += row * row.transpose();
+        right += row * (q - p).dot(&n);
+    }
+    let solution = normal_matrix.cholesky()?.solve(&right);
+    let turn = Vec3::new(solution[0] as f32, solution[1] as f32, solution[2] as f32);
+    let shift = Vec3::new(solution[3] as f32, solution[4] as f32, solution[5] as f32);
+    return Some(Mat4::new_translation(&shift) * Mat4::new_rotation(turn));
+}
+
+/// Moves points, already roughly in place, onto the surface of triangles by iterating closest points:
+/// each step pairs every point with the nearest point on the surface, and fits the rigid motion
+/// moving the points onto the planes of those triangles, which settles far sooner than onto the points themselves.
+/// Reports the fraction of the steps taken and the RMS distance so far, and stops early if that returns false.
+pub fn refine(points: &[Vec3], triangles: &[Triangle], bvh: &Bvh, max_iterations: usize,
+    mut progress: impl FnMut(f32, f32) -> bool) -> Option<Refinement> {
+    profile_span!("refine");
+    let mut moved = points.to_vec();
+    let mut transformation = Mat4::identity();
+    let mut nearest = closest_points(&moved, triangles, bvh);
+    let mut rms = surface_rms(&moved, &nearest);
+    let mut iterations = 0;
+    while iterations < max_iterations {
+        let mut distances: Vec<f32> = moved.iter().zip(&nearest)
+            .filter_map(|(p, q)| Some(glm::distance(p, &(*q)?.0)))
+            .collect();
+        if distances.is_empty() {
+            return None;
+        }
+        let middle = distances.len() / 2;
+        let median = *distances.select_nth_unstable_by(middle, |a, b| a.total_cmp(b)).1;
+        let limit = median * OUTLIER_FACTOR;
+        let pairs: Vec<(Vec3, Vec3, Vec3)> = moved.iter().zip(&nearest)
+            .filter_map(|(p, nearest)| {
+                let (q, n) = (*nearest)?;
+                return Some((*p, q, n));
+            })
+            .filter(|(p, q, _)| glm::distance(p, q) <= limit)
+            .collect();
+        let step = match plane_fit(&pairs) {
+            Some(step) => step,
+            None => break
+        };
+        for p in &mut moved {
+            *p = step.transform_point(&(*p).into()).coords;
+        }
+        transformation = step * transformation;
+        nearest = closest_points(&moved, triangles, bvh);
+        let previous = rms;
+        rms = surface_rms(&moved, &nearest);
+        iterations += 1;
+        if !progress(iterations as f32 / max_iterations as f32, rms) {
+            return None;
+        }
+        if previous - rms < previous * CONVERGED {
+            break;
+        }
+    }
+    return Some(Refinement { transformation, rms, iterations });
+}
@@ -0,0 +1,198 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+use std::f32::consts::TAU;
+
+use crate::i18n::tr;
+use crate::triangle::Triangle;
+
+/// The kinds of closed shape that can be generated
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShapeKind {
+    Box,
+    Sphere,
+    Cylinder,
+    Cone,
+    Torus
+}
+
+impl ShapeKind {
+    pub const ALL: [ShapeKind; 5] = [ShapeKind::Box, ShapeKind::Sphere, ShapeKind::Cylinder, ShapeKind::Cone, ShapeKind::Torus];
+
+    pub fn label(&self) -> String {
+        match self {
+            ShapeKind::Box => tr("shape-box"),
+            ShapeKind::Sphere => tr("shape-sphere"),
+            ShapeKind::Cylinder => tr("shape-cylinder"),
+            ShapeKind::Cone => tr("shape-cone"),
+            ShapeKind::Torus => tr("shape-torus")
+        }
+    }
+}
+
+/// A point on a circle around the Z axis, `step` of `steps` of the way around.
+/// Steps wrap around, so the last point meets the first exactly.
+fn around(radius: f32, step: usize, steps: usize, z: f32) -> Vec3 {
+    let angle = (step % steps) as f32 / steps as f32 * TAU;
+    return Vec3::new(radius * angle.cos(), radius * angle.sin(), z);
+}
+
+/// The two triangles of a four sided face, with its corners in order around the outside
+fn quad(a: Vec3, b: Vec3, c: Vec3, d: Vec3) -> [Triangle; 2] {
+    return [[a, b, c], [a, c, d]];
+}
+
+/// A solid with six four sided faces, from the corners of its bottom and its top,
+/// each in order counterclockwise as seen from above
+fn hexahedron(bottom: [Vec3; 4], top: [Vec3; 4]) -> Vec<Triangle> {
+    let mut triangles = Vec::with_capacity(12);
+    triangles.extend(quad(bottom[0], bottom[3], bottom[2], bottom[1]));
+    triangles.extend(quad(top[0], top[1], top[2], top[3]));
+    for i in 0..4 {
+        let next = (i + 1) % 4;
+        triangles.extend(quad(bottom[i], bottom[next], top[next], top[i]));
+    }
+    return triangles;
+}
+
+/// The corners of a rectangle at a height, counterclockwise as seen from above
+fn rectangle(min: &Vec3, max: &Vec3, z: f32) -> [Vec3; 4] {
+    return [Vec3::new(min.x, min.y, z), Vec3::new(max.x, min.y, z), Vec3::new(max.x, max.y, z), Vec3::new(min.x, max.y, z)];
+}
+
+/// A box of a size, centered on the origin
+pub fn cuboid(size: &Vec3) -> Vec<Triangle> {
+    let (min, max) = (-size / 2., size / 2.);
+    return hexahedron(rectangle(&min, &max, min.z), rectangle(&min, &max, max.z));
+}
+
+/// The bottom and top corners of a block enclosing a box with a margin on every side,
+/// to cut the shape of what is in the box from.
+/// With a draft angle, its sides lean in toward the top, so a cast block slides out of a frame;
+/// the margin is kept at the top, where the block is narrowest.
+pub fn mold_box_corners(min: &Vec3, max: &Vec3, margin: &Vec3, draft_degrees: f32) -> ([Vec3; 4], [Vec3; 4]) {
+    let (min, max) = (min - margin, max + margin);
+    let spread = (max.z - min.z) * draft_degrees.to_radians().tan();
+    let flare = Vec3::new(spread, spread, 0.);
+    return (rectangle(&(min - flare), &(max + flare), min.z), rectangle(&min, &max, max.z));
+}
+
+/// The block of `mold_box_corners`, as a closed solid
+pub fn mold_box(min: &Vec3, max: &Vec3, margin: &Vec3, draft_degrees: f32) -> Vec<Triangle> {
+    let (bottom, top) = mold_box_corners(min, max, margin, draft_degrees);
+    return hexahedron(bottom, top);
+}
+
+/// A sphere centered on the origin, with `segments` around its equator and half as many from pole to pole
+pub fn sphere(radius: f32, segments: usize) -> Vec<Triangle> {
+    let segments = segments.max(3);
+    let rings = (segments / 2).max(2);
+    let point = |ring: usize, segment: usize| {
+        let polar = ring as f32 / rings as f32 * TAU / 2.;
+        return around(radius * polar.sin(), segment, segments, -radius * polar.cos());
+    };
+    let mut triangles = Vec::with_capacity(segments * (rings - 1) * 2);
+    for segment in 0..segments {
+        let next = segment + 1;
+        triangles.push([point(0, segment), point(1, next), point(1, segment)]);
+        for ring in 1..rings - 1 {
+            triangles.extend(quad(point(ring, segment), point(ring, next), point(ring + 1, next), point(ring + 1, segment)));
+        }
+        triangles.push([point(rings - 1, segment), point(rings - 1, next), point(rings, segment)]);
+    }
+    return triangles;
+}
+
+/// A cylinder, or a cone if one of the radii is zero, around the Z axis and centered on the origin,
+/// with `segments` around it
+pub fn frustum(bottom_radius: f32, top_radius: f32, height: f32, segments: usize) -> Vec<Triangle> {
+    let segments = segments.max(3);
+    let (bottom, top) = (-height / 2., height / 2.);
+    let mut triangles = Vec::with_capacity(segments * 4);
+    for segment in 0..segments {
+        let (a, b) = (around(bottom_radius, segment, segments, bottom), around(bottom_radius, segment + 1, segments, bottom));
+        let (c, d) = (around(top_radius, segment + 1, segments, top), around(top_radius, segment, segments, top));
+        // A side narrowing to a point is one triangle, and a point has no cap
+        if bottom_radius > 0. {
+            triangles.push([Vec3::new(0., 0., bottom), b, a]);
+            triangles.push([a, b, c]);
+        }
+        if top_radius > 0. {
+            triangles.push([Vec3::new(0., 0., top), d, c]);
+            triangles.push([a, c, d]);
+        }
+    }
+    return triangles;
+}
+
+/// A ring around the Z axis centered on the origin, with the tube's center `major_radius` from the axis,
+/// and `segments` around both the ring and the tube
+pub fn torus(major_radius: f32, minor_radius: f32, segments: usize) -> Vec<Triangle> {
+    let segments = segments.max(3);
+    let point = |ring: usize, tube: usize| {
+        let angle = (tube % segments) as f32 / segments as f32 * TAU;
+        return around(major_radius + minor_radius * angle.cos(), ring, segments, minor_radius * angle.sin());
+    };
+    let mut triangles = Vec::with_capacity(segments * segments * 2);
+    for ring in 0..segments {
+        for tube in 0..segments {
+            triangles.extend(quad(point(ring, tube), point(ring + 1, tube), point(ring + 1, tube + 1), point(ring, tube + 1)));
+        }
+    }
+    return triangles;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+    use crate::triangle_mesh::{self, TriangleMesh};
+
+    /// Checks that triangles close up with every edge shared by two triangles wound the same way,
+    /// and facing outward, returning the volume they enclose
+    fn closed_volume(triangles: &[Triangle]) -> f32 {
+        let mesh = TriangleMesh::new(triangles, triangle_mesh::default_tolerance(triangles));
+        assert_eq!(mesh.triangles.len(), triangles.len());
+        assert!(mesh.edge_report().is_closed());
+        let volume: f32 = triangles.iter().map(|[a, b, c]| a.dot(&b.cross(c)) / 6.).sum();
+        assert!(volume > 0.);
+        return volume;
+    }
+
+    fn assert_near(value: f32, expected: f32, tolerance: f32) {
+        assert!((value - expected).abs() <= expected * tolerance, "{} is not near {}", value, expected);
+    }
+
+    #[test]
+    fn cuboid_is_closed() {
+        let triangles = cuboid(&Vec3::new(1., 2., 3.));
+        assert_eq!(triangles.len(), 12);
+        assert_near(closed_volume(&triangles), 6., 1e-5);
+    }
+
+    #[test]
+    fn mold_box_leans_in_toward_the_top() {
+        let (min, max, margin) = (Vec3::new(-1., -1., 0.), Vec3::new(1., 1., 2.), Vec3::new(1., 1., 1.));
+        assert_near(closed_volume(&mold_box(&min, &max, &margin, 0.)), 4. * 4. * 4., 1e-5);
+        let (bottom, top) = mold_box_corners(&min, &max, &margin, 10.);
+        assert_eq!(top[0], Vec3::new(-2., -2., 3.));
+        assert!(bottom[0].x < -2. && bottom[2].x > 2.);
+        closed_volume(&mold_box(&min, &max, &margin, 10.));
+    }
+
+    #[test]
+    fn round_shapes_are_closed() {
+        let radius = 2.;
+        assert_near(closed_volume(&sphere(radius, 64)), 4. / 3. * PI * radius.powi(3), 0.01);
+        assert_near(closed_volume(&frustum(radius, radius, 3., 64)), PI * radius * radius * 3., 0.01);
+        assert_near(closed_volume(&frustum(radius, 0., 3., 64)), PI * radius * radius, 0.01);
+        assert_near(closed_volume(&frustum(0., radius, 3., 64)), PI * radius * radius, 0.01);
+        assert_near(closed_volume(&torus(3., 1., 64)), 2. * PI * PI * 3., 0.01);
+    }
+
+    #[test]
+    fn segments_have_a_minimum() {
+        closed_volume(&sphere(1., 0));
+        closed_volume(&frustum(1., 1., 1., 1));
+        closed_volume(&torus(2., 1., 2));
+    }
+}
@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+extern crate nalgebra_glm as glm;
+use glm::{DMat3, DVec3, Vec3};
+
+use crate::triangle_mesh::TriangleMesh;
+
+/// Weight of the planes added along open edges, so the outline of a mesh is kept
+const BOUNDARY_WEIGHT: f64 = 1000.;
+
+/// Collapses made between progress reports
+const PROGRESS_INTERVAL: usize = 1024;
+
+/// The sum of squared distances to a set of planes, stored as the upper
+/// triangle of a symmetric 4x4 matrix: xx xy xz xw yy yz yw zz zw ww
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// The squared distance to the plane through points p with normal·p + d = 0
+    fn plane(normal: &DVec3, d: f64, weight: f64) -> Self {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        return Self([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d]
+            .map(|v| v * weight));
+    }
+
+    fn add(&self, other: &Quadric) -> Self {
+        let mut sum = self.0;
+        for (s, o) in sum.iter_mut().zip(&other.0) {
+            *s += o;
+        }
+        return Self(sum);
+    }
+
+    fn error(&self, p: &DVec3) -> f64 {
+        let [xx, xy, xz, xw, yy, yz, yw, zz, zw, ww] = self.0;
+        let (x, y, z) = (p.x, p.y, p.z);
+        return x * x * xx + 2. * x * y * xy + 2. * x * z * xz + 2. * x * xw
+            + y * y * yy + 2. * y * z * yz + 2. * y * yw
+            + z * z * zz + 2. * z * zw
+            + ww;
+    }
+
+    /// The point with the least error, unless the planes don't pin down a single one
+    fn minimum(&self) -> Option<DVec3> {
+        let [xx, xy, xz, xw, yy, yz, yw, zz, zw, _] = self.0;
+        let m = DMat3::new(
+            xx, xy, xz,
+            xy, yy, yz,
+            xz, yz, zz);
+        let scale = xx + yy + zz;
+        if m.determinant().abs() <= 1e-10 * scale * scale * scale {
+            return None;
+        }
+        return Some(-(m.try_inverse()? * DVec3::new(xw, yw, zw)));
+    }
+}
+
+/// Merging vertex `b` into vertex `a` at a new position
+struct Collapse {
+    cost: f64,
+    a: usize,
+    b: usize,
+    /// Versions of the vertices when this was found, to skip it once either changes
+    versions: (u32, u32),
+    position: DVec3
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        return self.cost == other.cost;
+    }
+}
+impl Eq for Collapse {}
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+impl Ord for Collapse {
+    /// Cheaper collapses come first out of a BinaryHeap
+    fn cmp(&self, other: &Self) -> Ordering {
+        return other.cost.total_cmp(&self.cost);
+    }
+}
+
+/// The working state of the simplification
+struct Simplifier {
+    positions: Vec<DVec3>,
+    triangles: Vec<[usize; 3]>,
+    alive: Vec<bool>,
+    /// Triangles using each vertex, which may include dead ones
+    faces: Vec<Vec<usize>>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>
+}
+
+impl Simplifier {
+    fn new(mesh: &TriangleMesh) -> Self {
+        let positions: Vec<DVec3> = mesh.vertices.iter()
+            .map(|v| DVec3::new(v.x as f64, v.y as f64, v.z as f64))
+            .collect();
+        let mut faces = vec![Vec::<usize>::new(); positions.len()];
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        for (i, triangle) in mesh.triangles.iter().enumerate() {
+            let normal = face_normal(&positions, triangle);
+            let plane = Quadric::plane(&normal, -glm::dot(&normal, &positions[triangle[0]]), 1.);
+            for v in triangle {
+                faces[*v].push(i);
+                quadrics[*v] = quadrics[*v].add(&plane);
+            }
+        }
+        // Open edges get a steep plane through them, across their triangle
+        for ((a, b), edge_faces) in mesh.edge_faces() {
+            if edge_faces.len() != 1 {
+                continue;
+            }
+            let normal = face_normal(&positions, &mesh.triangles[edge_faces[0]]);
+            let across = glm::cross(&(positions[b] - positions[a]), &normal);
+            if across.norm() == 0. {
+                continue;
+            }
+            let across = across.normalize();
+            let plane = Quadric::plane(&across, -glm::dot(&across, &positions[a]), BOUNDARY_WEIGHT);
+            quadrics[a] = quadrics[a].add(&plane);
+            quadrics[b] = quadrics[b].add(&plane);
+        }
+        return Self {
+            versions: vec![0; positions.len()],
+            positions,
+            triangles: mesh.triangles.clone(),
+            alive: vec![true; mesh.triangles.len()],
+            faces,
+            quadrics
+        };
+    }
+
+    fn candidate(&self, a: usize, b: usize) -> Collapse {
+        let quadric = self.quadrics[a].add(&self.quadrics[b]);
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let middle = (pa + pb) / 2.;
+        // A nearly flat region can put the best point far away, so stay near the edge
+        let position = match quadric.minimum() {
+            Some(best) if (best - middle).norm() <= (pb - pa).norm() => best,
+            _ => [pa, pb, middle].into_iter()
+                .min_by(|p, q| quadric.error(p).total_cmp(&quadric.error(q)))
+                .unwrap()
+        };
+        return Collapse {
+            cost: quadric.error(&position).max(0.),
+            a,
+            b,
+            versions: (self.versions[a], self.versions[b]),
+            position
+        };
+    }
+
+    fn neighbors(&self, vertex: usize) -> Vec<usize> {
+        let mut neighbors: Vec<usize> = self.faces[vertex].iter()
+            .filter(|f| self.alive[**f])
+            .flat_map(|f| self.triangles[*f])
+            .filter(|v| *v != vertex)
+            .collect();
+        neighbors.sort();
+        neighbors.dedup();
+        return neighbors;
+    }
+
+    /// Whether a collapse keeps the surface a simple sheet, without flipping any triangle over
+    fn allowed(&self, collapse: &Collapse) -> bool {
+        let (a, b) = (collapse.a, collapse.b);
+        let shared = self.faces[a].iter()
+            .filter(|f| self.alive[**f] && self.triangles[**f].contains(&b))
+            .count();
+        if shared == 0 {
+            return false;
+        }
+        // Vertices next to both ends, other than across the shared triangles,
+        // would end up joined by two different edges
+        let near_a = self.neighbors(a);
+        let common = self.neighbors(b).iter().filter(|v| near_a.binary_search(v).is_ok()).count();
+        if common > shared {
+            return false;
+        }
+        for vertex in [a, b] {
+            for f in &self.faces[vertex] {
+                let triangle = self.triangles[*f];
+                if !self.alive[*f] || (triangle.contains(&a) && triangle.contains(&b)) {
+                    continue;
+                }
+                let before = face_normal(&self.positions, &triangle);
+                if before == DVec3::zeros() {
+                    continue;
+                }
+                let corners = triangle.map(|v| if v == vertex { collapse.position } else { self.positions[v] });
+                let after = glm::cross(&(corners[1] - corners[0]), &(corners[2] - corners[0]));
+                if glm::dot(&before, &after) <= 0. {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    /// Merges b into a, returning the number of triangles removed
+    fn collapse(&mut self, collapse: &Collapse) -> usize {
+        let (a, b) = (collapse.a, collapse.b);
+        self.positions[a] = collapse.position;
+        self.quadrics[a] = self.quadrics[a].add(&self.quadrics[b]);
+        let mut removed = 0;
+        for f in std::mem::take(&mut self.faces[b]) {
+            if !self.alive[f] {
+                continue;
+            }
+            if self.triangles[f].contains(&a) {
+                self.alive[f] = false;
+                removed += 1;
+            } else {
+                for v in self.triangles[f].iter_mut() {
+                    if *v == b {
+                        *v = a;
+                    }
+                }
+                self.faces[a].push(f);
+            }
+        }
+        let alive = &self.alive;
+        self.faces[a].retain(|f| alive[*f]);
+        self.versions[a] += 1;
+        self.versions[b] += 1;
+        return removed;
+    }
+
+    /// The remaining triangles, with unused vertices left out
+    fn into_mesh(self) -> TriangleMesh {
+        let mut index = vec![usize::MAX; self.positions.len()];
+        let mut vertices = Vec::<Vec3>::new();
+        let mut triangles = Vec::<[usize; 3]>::new();
+        for (triangle, alive) in self.triangles.iter().zip(&self.alive) {
+            if !alive {
+                continue;
+            }
+            triangles.push(triangle.map(|v| {
+                if index[v] == usize::MAX {
+                    index[v] = vertices.len();
+                    let p = self.positions[v];
+                    vertices.push(Vec3::new(p.x as f32, p.y as f32, p.z as f32));
+                }
+                index[v]
+            }));
+        }
+        return TriangleMesh { vertices, triangles };
+    }
+}
+
+/// The unit normal of a triangle, or zero if it has no area
+fn face_normal(positions: &[DVec3], triangle: &[usize; 3]) -> DVec3 {
+    let [a, b, c] = triangle.map(|v| positions[v]);
+    let normal = glm::cross(&(b - a), &(c - a));
+    let length = normal.norm();
+    if length == 0. {
+        return normal;
+    }
+    return normal / length;
+}
+
+/// Reduces a mesh to at most the target number of triangles by collapsing edges,
+/// cheapest first, using quadric error metrics (Garland and Heckbert).
+///
+/// With a maximum error, it stops early rather than moving the surface further
+/// than that from where it was. Collapses which would fold the surface over,
+/// or join it to itself, are skipped, so the target isn't always reached.
+///
+/// The progress callback gets the fraction done, and returns false to stop,
+/// in which case None is returned.
+pub fn simplify(mesh: &TriangleMesh, target: usize, max_error: Option<f32>, mut progress: impl FnMut(f32) -> bool)
+-> Option<TriangleMesh> {
+    profile_span!("simplify");
+    let mut simplifier = Simplifier::new(mesh);
+    let mut heap = BinaryHeap::<Collapse>::new();
+    for (a, b) in mesh.edge_faces().keys() {
+        heap.push(simplifier.candidate(*a, *b));
+    }
+    let max_cost = max_error.map(|error| (error as f64).powi(2));
+    let start = mesh.triangles.len();
+    let mut remaining = start;
+    let mut steps = 0;
+    while remaining > target {
+        let collapse = match heap.pop() {
+            Some(collapse) => collapse,
+            None => break
+        };
+        if collapse.versions != (simplifier.versions[collapse.a], simplifier.versions[collapse.b]) {
+            continue;
+        }
+        if matches!(max_cost, Some(max_cost) if collapse.cost > max_cost) {
+            break;
+        }
+        if !simplifier.allowed(&collapse) {
+            continue;
+        }
+        remaining -= simplifier.collapse(&collapse);
+        for neighbor in simplifier.neighbors(collapse.a) {
+            heap.push(simplifier.candidate(collapse.a, neighbor));
+        }
+        steps += 1;
+        if steps % PROGRESS_INTERVAL == 0 && !progress((start - remaining) as f32 / (start - target) as f32) {
+            return None;
+        }
+    }
+    return Some(simplifier.into_mesh());
+}
@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Write};
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::mesh_file;
+use crate::plane_cut::{self, Plane, SectionLoop};
+use crate::triangle::{self, Triangle};
+use crate::units::Unit;
+use crate::version;
+
+/// Most layers exported at once, so a tiny layer height doesn't write files without end
+pub const MAX_LAYERS: usize = 10_000;
+
+/// File formats for exported slices
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SliceFormat {
+    Svg,
+    Dxf
+}
+
+impl SliceFormat {
+    pub const ALL: [SliceFormat; 2] = [SliceFormat::Svg, SliceFormat::Dxf];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SliceFormat::Svg => "svg",
+            SliceFormat::Dxf => "dxf"
+        }
+    }
+
+    /// The format's name, which is the same in every language
+    pub fn label(&self) -> &'static str {
+        match self {
+            SliceFormat::Svg => "SVG",
+            SliceFormat::Dxf => "DXF"
+        }
+    }
+}
+
+/// Heights through the middle of each layer from the bottom to the top of a model
+pub fn layer_heights(bottom: f32, top: f32, layer_height: f32) -> Vec<f32> {
+    if layer_height.is_nan() || layer_height <= 0. || top < bottom {
+        return Vec::new();
+    }
+    let count = (((top - bottom) / layer_height).ceil() as usize).max(1);
+    return (0..count).map(|i| bottom + (i as f32 + 0.5) * layer_height).collect();
+}
+
+/// Writes the loops of one slice as outlines in an SVG file, sized in real units so it
+/// prints or cuts at full scale. Every slice of a stack uses the same footprint, so they line up.
+pub fn write_svg(path: &str, loops: &[SectionLoop], min: &Vec3, max: &Vec3, unit: Unit) -> Result<(), Error> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let (width, height) = (max.x - min.x, max.y - min.y);
+    writeln!(file, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(file, "<!-- {} -->", version::description())?;
+    writeln!(file, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}{}\" height=\"{}{}\" viewBox=\"0 0 {} {}\">",
+        width, unit.suffix(), height, unit.suffix(), width, height)?;
+    // Lasers cut along hairlines, so the stroke is as thin as can still be seen
+    let stroke = width.max(height) / 1000.;
+    for section_loop in loops {
+        let mut data = String::new();
+        for (i, p) in section_loop.points.iter().enumerate() {
+            // SVG's y goes down, so the slice is flipped to look the same as from above
+            data += &format!("{}{:.4},{:.4} ", if i == 0 { "M" } else { "L" }, p.x - min.x, max.y - p.y);
+        }
+        if section_loop.closed {
+            data += "Z";
+        }
+        writeln!(file, "  <path d=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"{}\"/>", data.trim_end(), stroke)?;
+    }
+    writeln!(file, "</svg>")?;
+    return file.flush();
+}
+
+/// Writes the loops of one slice as polylines in an ASCII DXF file, in scene coordinates
+pub fn write_dxf(path: &str, loops: &[SectionLoop], unit: Unit) -> Result<(), Error> {
+    let mut file = BufWriter::new(File::create(path)?);
+    // Group codes and values alternate, one per line
+    let units = match unit {
+        Unit::Inch => 1,
+        Unit::Millimeter => 4,
+        Unit::Centimeter => 5
+    };
+    write!(file, "999\n{}\n0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n{}\n0\nENDSEC\n", version::description(), units)?;
+    write!(file, "0\nSECTION\n2\nENTITIES\n")?;
+    for section_loop in loops {
+        write!(file, "0\nPOLYLINE\n8\n0\n66\n1\n70\n{}\n10\n0.0\n20\n0.0\n30\n0.0\n",
+            if section_loop.closed { 1 } else { 0 })?;
+        for p in &section_loop.points {
+            write!(file, "0\nVERTEX\n8\n0\n10\n{:.6}\n20\n{:.6}\n30\n0.0\n", p.x, p.y)?;
+        }
+        write!(file, "0\nSEQEND\n8\n0\n")?;
+    }
+    write!(file, "0\nENDSEC\n0\nEOF\n")?;
+    return file.flush();
+}
+
+/// Slices placed triangles into layers and writes each to its own numbered file named after a path,
+/// from the bottom up. Layers with nothing in them are still written, so the numbers match heights.
+/// The progress callback gets the fraction done, and returns false to stop.
+/// Returns the files written.
+pub fn export_stack(path: &str, triangles: &[Triangle], layer_height: f32, format: SliceFormat, unit: Unit,
+    mut progress: impl FnMut(f32) -> bool) -> Result<Vec<String>, Error> {
+    profile_span!("export_slices");
+    let (min, max) = match triangle::transformed_bounds(triangles, &Mat4::identity()) {
+        Some(bounds) => bounds,
+        None => return Err(Error::new(ErrorKind::InvalidInput, "The model is empty"))
+    };
+    let heights = layer_heights(min.z, max.z, layer_height);
+    if heights.is_empty() || heights.len() > MAX_LAYERS {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Layers must number from 1 to {}", MAX_LAYERS)));
+    }
+    // The number goes before the extension, so the path needs the format's extension first
+    let extension = format!(".{}", format.extension());
+    let path = if path.to_lowercase().ends_with(&extension) { path.to_string() } else { format!("{}{}", path, extension) };
+    let mut files = Vec::<String>::new();
+    for (i, height) in heights.iter().enumerate() {
+        if !progress(i as f32 / heights.len() as f32) {
+            return Err(Error::new(ErrorKind::Interrupted, "Cancelled"));
+        }
+        let loops = plane_cut::section(triangles, &Plane { normal: Vec3::z(), offset: *height });
+        let file = mesh_file::numbered_path(&path, i + 1, heights.len());
+        match format {
+            SliceFormat::Svg => write_svg(&file, &loops, &min, &max, unit)?,
+            SliceFormat::Dxf => write_dxf(&file, &loops, unit)?
+        }
+        files.push(file);
+    }
+    return Ok(files);
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::triangle_mesh::TriangleMesh;
+
+/// A sparse uniform grid listing which triangles overlap each cell.
+///
+/// Used to find triangles near a point or segment without
+/// testing every triangle in the mesh.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<[i64; 3], Vec<usize>>
+}
+
+impl SpatialGrid {
+    /// Sorts the triangles of a mesh into cells by their bounding boxes
+    pub fn new(mesh: &TriangleMesh, cell_size: f32) -> Self {
+        profile_span!("build_spatial_grid");
+        let mut grid = Self {
+            cell_size,
+            cells: HashMap::new()
+        };
+        for i in 0..mesh.triangles.len() {
+            let [a, b, c] = mesh.triangle(i);
+            let min = glm::min2(&glm::min2(&a, &b), &c);
+            let max = glm::max2(&glm::max2(&a, &b), &c);
+            for key in grid.keys(&min, &max) {
+                grid.cells.entry(key).or_default().push(i);
+            }
+        }
+        return grid;
+    }
+
+    /// A cell size around twice the average edge length of the mesh
+    pub fn suggested_cell_size(mesh: &TriangleMesh) -> f32 {
+        if mesh.triangles.is_empty() {
+            return 1.;
+        }
+        let mut total = 0.;
+        for i in 0..mesh.triangles.len() {
+            let [a, b, c] = mesh.triangle(i);
+            total += (b - a).norm() + (c - b).norm() + (a - c).norm();
+        }
+        let size = 2. * total / (3 * mesh.triangles.len()) as f32;
+        return if size > 0. { size } else { 1. };
+    }
+
+    fn cell(&self, point: &Vec3) -> [i64; 3] {
+        return [0, 1, 2].map(|i| (point[i] / self.cell_size).floor() as i64);
+    }
+
+    fn keys(&self, min: &Vec3, max: &Vec3) -> Vec<[i64; 3]> {
+        let low = self.cell(min);
+        let high = self.cell(max);
+        let mut keys = Vec::new();
+        for x in low[0]..=high[0] {
+            for y in low[1]..=high[1] {
+                for z in low[2]..=high[2] {
+                    keys.push([x, y, z]);
+                }
+            }
+        }
+        return keys;
+    }
+
+    /// The triangles in any cell overlapping a bounding box, without duplicates
+    pub fn query(&self, min: &Vec3, max: &Vec3) -> Vec<usize> {
+        let mut result = Vec::new();
+        for key in self.keys(min, max) {
+            if let Some(triangles) = self.cells.get(&key) {
+                result.extend_from_slice(triangles);
+            }
+        }
+        result.sort();
+        result.dedup();
+        return result;
+    }
+
+    /// The lists of triangles sharing each occupied cell
+    pub fn cells(&self) -> impl Iterator<Item = &Vec<usize>> {
+        return self.cells.values();
+    }
+}
+
+/// Finds where a segment crosses a triangle.
+///
+/// Returns the fraction of the way from start to end, if it hits.
+pub fn segment_triangle(start: &Vec3, end: &Vec3, triangle: &[Vec3; 3]) -> Option<f32> {
+    return line_triangle(start, &(end - start), triangle)
+        .filter(|t| (0. ..=1.).contains(t));
+}
+
+/// Finds where an infinite line crosses a triangle (Möller–Trumbore).
+///
+/// Returns how many direction vectors from the origin the hit is,
+/// which is negative for hits behind the origin.
+pub fn line_triangle(origin: &Vec3, direction: &Vec3, triangle: &[Vec3; 3]) -> Option<f32> {
+    let edge1 = triangle[1] - triangle[0];
+    let edge2 = triangle[2] - triangle[0];
+    let p = glm::cross(direction, &edge2);
+    let determinant = glm::dot(&edge1, &p);
+    if determinant.abs() < f32::EPSILON * edge1.norm_squared().max(edge2.norm_squared()) {
+        return None;
+    }
+    let offset = origin - triangle[0];
+    let u = glm::dot(&offset, &p) / determinant;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let q = glm::cross(&offset, &edge1);
+    let v = glm::dot(direction, &q) / determinant;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    return Some(glm::dot(&edge2, &q) / determinant);
+}
+
+/// Checks whether two triangles cross each other, by testing each edge
+/// of one against the other. Coplanar overlaps are not detected.
+pub fn triangles_intersect(a: &[Vec3; 3], b: &[Vec3; 3]) -> bool {
+    for i in 0..3 {
+        if segment_triangle(&a[i], &a[(i + 1) % 3], b).is_some()
+            || segment_triangle(&b[i], &b[(i + 1) % 3], a).is_some() {
+            return true;
+        }
+    }
+    return false;
+}
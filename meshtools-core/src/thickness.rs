@@ -0,0 +1,104 @@
+extern crate nalgebra_glm as glm;
+use glm::Mat4;
+
+use crate::bvh::Bvh;
+use crate::deviation;
+use crate::triangle::{self, Triangle};
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// Color of triangles thinner than the threshold
+pub const THIN_COLOR: [f32; 3] = [1., 0., 1.];
+/// Color of triangles whose ray leaves the mesh, so they have no wall behind them
+pub const OPEN_COLOR: [f32; 3] = [0.6, 0.6, 0.6];
+/// Walls this many times the threshold or thicker are all the same color
+const COLOR_SPAN: f32 = 4.;
+
+/// A connected patch of triangles thinner than a threshold
+pub struct ThinRegion {
+    pub area: f32,
+    pub thinnest: f32
+}
+
+/// How thick the walls are behind each triangle of a model, as placed in the scene
+pub struct WallThickness {
+    /// The placed triangles, welded, in the same order as the model's
+    mesh: TriangleMesh,
+    /// Distance to the back of the opposite wall from each triangle's center,
+    /// or infinity if there is none
+    pub thickness: Vec<f32>
+}
+
+impl WallThickness {
+    /// Casts a ray inward from the center of each triangle, as placed by a transformation,
+    /// to the back of the opposite wall.
+    /// The progress callback gets the fraction done, and returns false to stop,
+    /// in which case None is returned.
+    pub fn new(triangles: &[Triangle], transformation: &Mat4, mut progress: impl FnMut(f32) -> bool) -> Option<Self> {
+        profile_span!("wall_thickness");
+        let placed: Vec<Triangle> = triangles.iter().map(|t| triangle::transform(t, transformation)).collect();
+        let mesh = TriangleMesh::with_all_triangles(&placed, triangle_mesh::default_tolerance(&placed));
+        let bvh = Bvh::new(&placed);
+        let mut thickness = vec![f32::INFINITY; placed.len()];
+        for (i, corners) in placed.iter().enumerate() {
+            if i % 4096 == 0 && !progress(i as f32 / placed.len() as f32) {
+                return None;
+            }
+            let normal = match mesh.area_normal(i).try_normalize(0.) {
+                Some(normal) => normal,
+                None => continue
+            };
+            let center = (corners[0] + corners[1] + corners[2]) / 3.;
+            // Like thin wall issues, neighbors and walls facing the same way are not the other side
+            let hit = bvh.cast(&placed, &center, &-normal, |j, _| {
+                let shares_vertex = mesh.triangles[i].iter().any(|v| mesh.triangles[j].contains(v));
+                return !shares_vertex && mesh.area_normal(j).dot(&normal) < 0.;
+            });
+            if let Some((_, t)) = hit {
+                thickness[i] = t;
+            }
+        }
+        return Some(Self { mesh, thickness });
+    }
+
+    /// The thinnest wall found, if any
+    pub fn thinnest(&self) -> Option<f32> {
+        return self.thickness.iter().copied().filter(|t| t.is_finite()).reduce(f32::min);
+    }
+
+    /// Groups the triangles thinner than a threshold into connected regions, largest first
+    pub fn thin_regions(&self, threshold: f32) -> Vec<ThinRegion> {
+        let thin: Vec<usize> = (0..self.thickness.len()).filter(|i| self.thickness[*i] < threshold).collect();
+        let mut regions: Vec<ThinRegion> = self.mesh.connected_groups(&thin).into_iter()
+            .map(|triangles| {
+                let area = triangles.iter().map(|t| self.mesh.area_normal(*t).norm() / 2.).sum();
+                let thinnest = triangles.iter().map(|t| self.thickness[*t]).fold(f32::MAX, f32::min);
+                ThinRegion { area, thinnest }
+            })
+            .collect();
+        regions.sort_by(|a, b| b.area.total_cmp(&a.area));
+        return regions;
+    }
+
+    /// The color of each corner, three to a triangle. Walls thinner than the threshold
+    /// are flagged in one color, and thicker walls go from red near the threshold to blue.
+    pub fn colors(&self, threshold: f32) -> Vec<[f32; 3]> {
+        let threshold = threshold.max(f32::EPSILON);
+        return self.thickness.iter()
+            .flat_map(|t| {
+                let color = if t.is_infinite() {
+                    OPEN_COLOR
+                } else if *t < threshold {
+                    THIN_COLOR
+                } else {
+                    deviation::heat_color(1. - 2. * (t / threshold - 1.) / (COLOR_SPAN - 1.))
+                };
+                [color; 3]
+            })
+            .collect();
+    }
+
+    /// Thicknesses shown at each end of the color scale, for a legend
+    pub fn color_span(threshold: f32) -> (f32, f32) {
+        return (threshold, threshold * COLOR_SPAN);
+    }
+}
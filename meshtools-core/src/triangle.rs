@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::{Write, Read};
+extern crate nalgebra_glm as glm;
+use glm::{Vec3, Mat4};
+
+use crate::version;
+
+pub type Triangle = [Vec3; 3];
+
+/// Moves each vertex of a triangle by a transformation, including its translation
+pub fn transform(triangle: &Triangle, transformation: &Mat4) -> Triangle {
+    let [a, b, c] = transform_corners(triangle, transformation);
+    // A mirroring transformation turns the triangle inside out, unless its corners are reordered
+    if glm::mat4_to_mat3(transformation).determinant() < 0. {
+        return [a, c, b];
+    }
+    return [a, b, c];
+}
+
+/// Moves each vertex of a triangle by a transformation, keeping the corners in order
+/// even when the transformation mirrors it, for values kept per corner of the original
+/// triangle, like colors. The result faces inward if the transformation mirrors.
+pub fn transform_corners(triangle: &Triangle, transformation: &Mat4) -> Triangle {
+    return triangle.map(|vertex| transformation.transform_point(&vertex.into()).coords);
+}
+
+/// Reflects a triangle across the plane where the coordinate on an axis equals `plane`.
+/// The corners are reordered so the triangle still faces outward.
+pub fn mirror(triangle: &Triangle, axis: usize, plane: f32) -> Triangle {
+    let [a, b, c] = triangle.map(|mut vertex| {
+        vertex[axis] = 2. * plane - vertex[axis];
+        vertex
+    });
+    return [a, c, b];
+}
+
+/// The closest point to p on a triangle (Ericson, "Real-Time Collision Detection")
+pub fn closest_point(p: &Vec3, [a, b, c]: &Triangle) -> Vec3 {
+    let (ab, ac, ap) = (b - a, c - a, p - a);
+    let (d1, d2) = (glm::dot(&ab, &ap), glm::dot(&ac, &ap));
+    if d1 <= 0. && d2 <= 0. {
+        return *a;
+    }
+    let bp = p - b;
+    let (d3, d4) = (glm::dot(&ab, &bp), glm::dot(&ac, &bp));
+    if d3 >= 0. && d4 <= d3 {
+        return *b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0. && d1 >= 0. && d3 <= 0. {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let (d5, d6) = (glm::dot(&ab, &cp), glm::dot(&ac, &cp));
+    if d6 >= 0. && d5 <= d6 {
+        return *c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0. && d2 >= 0. && d6 <= 0. {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denominator = 1. / (va + vb + vc);
+    return a + ab * (vb * denominator) + ac * (vc * denominator);
+}
+
+/// The corners of the box around triangles once they are transformed,
+/// or None if there are no triangles
+pub fn transformed_bounds(triangles: &[Triangle], transformation: &Mat4) -> Option<(Vec3, Vec3)> {
+    if triangles.is_empty() {
+        return None;
+    }
+    let mut min = Vec3::repeat(f32::MAX);
+    let mut max = Vec3::repeat(f32::MIN);
+    for triangle in triangles {
+        for vertex in transform(triangle, transformation) {
+            min = glm::min2(&min, &vertex);
+            max = glm::max2(&max, &vertex);
+        }
+    }
+    return Some((min, max));
+}
+
+fn write_vec3(file: &mut File, vector: &Vec3)
+-> Result<(), std::io::Error>{
+    file.write_all(&vector[0].to_le_bytes())?;
+    file.write_all(&vector[1].to_le_bytes())?;
+    file.write_all(&vector[2].to_le_bytes())?;
+    return Ok(());
+}
+
+/// Writes triangles to a binary stl file.
+/// The normal is set based on the triangle vertices.
+/// The header names the version of MeshTools that wrote the file, padded with zeros,
+/// and the attributes are left empty.
+pub fn write_stl_binary(
+    path: &str,
+    triangles: &[Triangle])
+-> Result<(), std::io::Error> {
+    let mut output = File::create(path)?;
+    let mut header = [0u8; 80];
+    let description = version::description();
+    let length = description.len().min(header.len());
+    header[..length].copy_from_slice(&description.as_bytes()[..length]);
+    output.write_all(&header)?;
+    output.write_all(&(triangles.len() as u32).to_le_bytes())?;
+    for triangle in triangles {
+        let edge1 = triangle[1] - triangle[0];
+        let edge2 = triangle[2] - triangle[0];
+        let normal = glm::cross(&edge1, &edge2).normalize();
+        write_vec3(&mut output, &normal)?;
+        for vertex in triangle {
+            write_vec3(&mut output, vertex)?;
+        }
+        output.write(&[0 as u8; 2])?;
+    }
+    return Ok(());
+}
+
+/// Writes triangles to a binary stl file, after transforming them.
+/// The header and attributes are written as in `write_stl_binary`.
+pub fn write_transformed_stl_binary(
+    path: &str,
+    triangles: &[Triangle],
+    transformation: &Mat4)
+-> Result<(), std::io::Error> {
+    let transformed_triangles: Vec<Triangle> =
+        triangles.iter()
+        .map(|t| {transform(t, &transformation)})
+        .collect();
+    return write_stl_binary(path, &transformed_triangles);
+}
+
+/// Bytes in each triangle record of a binary STL:
+/// a normal, three vertices, and two bytes of attributes
+const STL_RECORD_SIZE: usize = 50;
+/// Number of records read from the file at a time
+const STL_CHUNK_RECORDS: usize = 65536;
+
+/// Parses the vertices of a binary STL record, discarding the normal and attributes
+fn parse_stl_record(record: &[u8]) -> Triangle {
+    let values: [u32; 12] = bytemuck::pod_read_unaligned(&record[..48]);
+    let values = values.map(|bits| f32::from_bits(u32::from_le(bits)));
+    return [
+        Vec3::new(values[3], values[4], values[5]),
+        Vec3::new(values[6], values[7], values[8]),
+        Vec3::new(values[9], values[10], values[11])];
+}
+
+/// Loads a binary STL file into a list of triangles
+///
+/// Discards header, normals, and attributes
+#[allow(dead_code)]
+pub fn read_stl_binary(path: &str) -> Result<Vec::<Triangle>, std::io::Error> {
+    return read_stl_binary_with_progress(path, |_| {});
+}
+
+/// Loads a binary STL file into a list of triangles,
+/// reporting the fraction of triangles read after each chunk of the file.
+///
+/// Discards header, normals, and attributes
+#[allow(dead_code)]
+pub fn read_stl_binary_with_progress(path: &str, progress: impl FnMut(f32))
+-> Result<Vec::<Triangle>, std::io::Error> {
+    return read_stl_binary_from(&mut File::open(path)?, progress);
+}
+
+/// Reads binary STL data into a list of triangles,
+/// reporting the fraction of triangles read after each chunk of the input.
+///
+/// Records are read in large chunks rather than one value at a time,
+/// which matters for scans with hundreds of millions of triangles.
+/// Discards header, normals, and attributes
+pub fn read_stl_binary_from<R: Read>(input: &mut R, mut progress: impl FnMut(f32))
+-> Result<Vec::<Triangle>, std::io::Error> {
+    let mut triangles = Vec::<Triangle>::new();
+    stream_stl_binary_from(input, |chunk, fraction| {
+        triangles.extend_from_slice(chunk);
+        progress(fraction);
+        return true;
+    })?;
+    return Ok(triangles);
+}
+
+/// Reads binary STL data a chunk of triangles at a time, without keeping them,
+/// passing each chunk and the fraction of triangles read so far to a callback.
+///
+/// Reading stops with an error if the callback returns false.
+/// Returns the number of triangles in the file.
+pub fn stream_stl_binary_from<R: Read>(input: &mut R, mut visit: impl FnMut(&[Triangle], f32) -> bool)
+-> Result<usize, std::io::Error> {
+    let mut header = [0u8; 80];
+    input.read_exact(&mut header)?;
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    let triangle_count = u32::from_le_bytes(bytes) as usize;
+
+    let mut read = 0;
+    let mut chunk = vec![0u8; STL_RECORD_SIZE * STL_CHUNK_RECORDS.min(triangle_count)];
+    let mut triangles = Vec::<Triangle>::with_capacity(STL_CHUNK_RECORDS.min(triangle_count));
+    while read < triangle_count {
+        let records = STL_CHUNK_RECORDS.min(triangle_count - read);
+        let chunk = &mut chunk[..records * STL_RECORD_SIZE];
+        input.read_exact(chunk)?;
+        triangles.clear();
+        triangles.extend(chunk.chunks_exact(STL_RECORD_SIZE).map(parse_stl_record));
+        read += records;
+        if !visit(&triangles, read as f32 / triangle_count as f32) {
+            return Err(std::io::Error::other("Cancelled"));
+        }
+    }
+    return Ok(triangle_count);
+}
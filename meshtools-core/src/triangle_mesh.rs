@@ -0,0 +1,888 @@
+use std::collections::{HashMap, HashSet};
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+use rayon::prelude::*;
+
+use crate::triangle::Triangle;
+
+/// An indexed triangle mesh, where triangles share vertices.
+///
+/// Unlike a list of Triangles, this knows which triangles are connected,
+/// which is needed for any analysis of the mesh's topology.
+#[derive(Clone)]
+pub struct TriangleMesh {
+    pub vertices: Vec<Vec3>,
+    /// Vertex indices of each triangle, counter-clockwise when viewed from outside
+    pub triangles: Vec<[usize; 3]>
+}
+
+/// A closed loop of boundary edges, as found by `TriangleMesh::boundary_loops`
+pub struct Hole {
+    /// Vertex indices, ordered in the direction of the mesh's boundary edges
+    pub vertices: Vec<usize>,
+    /// Total length of the boundary edges
+    pub perimeter: f32,
+    /// Area of the loop projected onto its best fitting plane
+    pub area: f32,
+    /// Average position of the loop's vertices
+    pub center: Vec3,
+    /// Largest distance from the center to a vertex of the loop
+    pub radius: f32
+}
+
+/// How a hole is closed by `TriangleMesh::fill_loop`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HoleFill {
+    /// A fan of triangles around the middle of the hole
+    Flat,
+    /// Rings of new vertices, moved so the patch carries on the curvature of the surface around it
+    Smooth
+}
+
+/// Most rings of vertices in a smooth fill, however small the edges around the hole are
+const MAX_FILL_RINGS: usize = 32;
+
+/// The edges keeping a mesh from being watertight, as found by `TriangleMesh::edge_report`.
+/// Edges are vertex index pairs in ascending order.
+pub struct EdgeReport {
+    /// Edges of only one triangle
+    pub boundary: Vec<(usize, usize)>,
+    /// Edges of more than two triangles
+    pub non_manifold: Vec<(usize, usize)>,
+    /// Edges whose two triangles run along them the same way, so one of them is wound backwards
+    pub misoriented: Vec<(usize, usize)>
+}
+
+/// Curvature at each vertex of a mesh, as found by `TriangleMesh::curvature`
+pub struct Curvature {
+    /// Positive where the surface bulges outward, and negative where it is dished
+    pub mean: Vec<f32>,
+    /// Positive on bumps and dents, and negative on saddles
+    pub gaussian: Vec<f32>
+}
+
+/// What `TriangleMesh::clean` removed, with the counts before and after
+pub struct CleanReport {
+    /// Vertices once welded, before any were removed
+    pub vertices_before: usize,
+    pub vertices_after: usize,
+    pub triangles_before: usize,
+    pub triangles_after: usize,
+    /// Triangles which lost a corner to welding
+    pub degenerate: usize,
+    /// Triangles over the same three corners as an earlier one, however they are wound
+    pub duplicates: usize,
+    /// Vertices left without a triangle once the others were removed
+    pub unreferenced: usize,
+    /// Triangles kept with three distinct corners that lie within the tolerance of a line,
+    /// so they have next to no area
+    pub thin: usize
+}
+
+impl EdgeReport {
+    /// Every edge is shared by exactly two consistently wound triangles
+    pub fn is_closed(&self) -> bool {
+        return self.boundary.is_empty() && self.non_manifold.is_empty() && self.misoriented.is_empty();
+    }
+}
+
+/// A merge tolerance suitable for the size of the given triangles
+pub fn default_tolerance(triangles: &[Triangle]) -> f32 {
+    let mut min = Vec3::repeat(f32::MAX);
+    let mut max = Vec3::repeat(f32::MIN);
+    for triangle in triangles {
+        for vertex in triangle {
+            min = glm::min2(&min, vertex);
+            max = glm::max2(&max, vertex);
+        }
+    }
+    if triangles.is_empty() {
+        return f32::EPSILON;
+    }
+    return ((max - min).norm() * 1e-6).max(f32::EPSILON);
+}
+
+/// The averaged normal at each corner of the triangles, three to a triangle,
+/// so that shading blends smoothly across the edges between them
+pub fn smooth_normals(triangles: &[Triangle]) -> Vec<[f32; 3]> {
+    let mesh = TriangleMesh::with_all_triangles(triangles, default_tolerance(triangles));
+    let normals = mesh.vertex_normals();
+    return mesh.triangles.iter()
+        .flat_map(|t| t.map(|v| [normals[v].x, normals[v].y, normals[v].z]))
+        .collect();
+}
+
+impl TriangleMesh {
+    /// Creates an indexed mesh from a list of triangles.
+    ///
+    /// Vertices closer than the tolerance are merged, and triangles which
+    /// lose a corner to merging are dropped.
+    pub fn new(triangles: &[Triangle], tolerance: f32) -> Self {
+        profile_span!("weld_vertices");
+        let (vertices, indices) = merge_vertices(triangles, tolerance);
+        let triangles = indices.into_iter()
+            .filter(|[a, b, c]| a != b && b != c && c != a)
+            .collect();
+        return Self { vertices, triangles };
+    }
+
+    /// Welds triangles like `new`, but keeps the triangles which lose a corner,
+    /// so they still line up with the list they came from
+    pub fn with_all_triangles(triangles: &[Triangle], tolerance: f32) -> Self {
+        let (vertices, triangles) = merge_vertices(triangles, tolerance);
+        return Self { vertices, triangles };
+    }
+
+    /// Welds triangles like `new`, then removes triangles that lost a corner to welding, repeated faces,
+    /// and the vertices no triangle uses any more.
+    ///
+    /// Thin triangles with three distinct corners are kept, since the triangles around them
+    /// share their edges, and removing them would open holes in a closed mesh.
+    /// The report counts the ones whose corners lie within the tolerance of a line.
+    pub fn clean(triangles: &[Triangle], tolerance: f32) -> (Self, CleanReport) {
+        profile_span!("clean_mesh");
+        let (vertices, indices) = merge_vertices(triangles, tolerance);
+        let mut degenerate = 0;
+        let mut duplicates = 0;
+        let mut faces = HashSet::<[usize; 3]>::new();
+        let mut kept = Vec::<[usize; 3]>::with_capacity(indices.len());
+        for triangle in indices {
+            let [a, b, c] = triangle;
+            if a == b || b == c || c == a {
+                degenerate += 1;
+                continue;
+            }
+            let mut face = triangle;
+            face.sort();
+            if !faces.insert(face) {
+                duplicates += 1;
+                continue;
+            }
+            kept.push(triangle);
+        }
+        let thin = kept.iter()
+            .filter(|triangle| {
+                let [a, b, c] = triangle.map(|v| vertices[v]);
+                let longest = (b - a).norm().max((c - b).norm()).max((a - c).norm());
+                // The height over the longest edge, from twice the area
+                return (b - a).cross(&(c - a)).norm() <= tolerance * longest;
+            })
+            .count();
+        // Renumber the vertices still in use, keeping their order
+        let mut new_index = vec![None; vertices.len()];
+        for triangle in &kept {
+            for v in triangle {
+                new_index[*v] = Some(0);
+            }
+        }
+        let mut used = Vec::<Vec3>::new();
+        for (v, index) in new_index.iter_mut().enumerate() {
+            if index.is_some() {
+                *index = Some(used.len());
+                used.push(vertices[v]);
+            }
+        }
+        let kept: Vec<[usize; 3]> = kept.into_iter()
+            .map(|triangle| triangle.map(|v| new_index[v].unwrap()))
+            .collect();
+        let report = CleanReport {
+            vertices_before: vertices.len(),
+            vertices_after: used.len(),
+            triangles_before: triangles.len(),
+            triangles_after: kept.len(),
+            degenerate,
+            duplicates,
+            unreferenced: vertices.len() - used.len(),
+            thin
+        };
+        return (Self { vertices: used, triangles: kept }, report);
+    }
+
+    /// The positions of a triangle's corners
+    pub fn triangle(&self, index: usize) -> Triangle {
+        return self.triangles[index].map(|v| self.vertices[v]);
+    }
+
+    /// Converts back into a list of Triangles
+    pub fn to_triangles(&self) -> Vec<Triangle> {
+        return (0..self.triangles.len()).map(|i| self.triangle(i)).collect();
+    }
+
+    /// The normal of a triangle, scaled by twice its area
+    pub fn area_normal(&self, index: usize) -> Vec3 {
+        let [a, b, c] = self.triangle(index);
+        return glm::cross(&(b - a), &(c - a));
+    }
+
+    /// The normal at each vertex, averaged from the triangles around it and weighted by their areas.
+    /// Vertices of triangles without area get a zero normal.
+    pub fn vertex_normals(&self) -> Vec<Vec3> {
+        let mut normals = vec![Vec3::zeros(); self.vertices.len()];
+        for (i, triangle) in self.triangles.iter().enumerate() {
+            let normal = self.area_normal(i);
+            for &v in triangle {
+                normals[v] += normal;
+            }
+        }
+        for normal in &mut normals {
+            if glm::dot(normal, normal) > 0. {
+                *normal = normal.normalize();
+            }
+        }
+        return normals;
+    }
+
+    /// Lists the triangles using each edge, with edges keyed by their
+    /// vertex indices in ascending order
+    pub fn edge_faces(&self) -> HashMap<(usize, usize), Vec<usize>> {
+        let mut faces = HashMap::<(usize, usize), Vec<usize>>::new();
+        for (i, triangle) in self.triangles.iter().enumerate() {
+            for j in 0..3 {
+                let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+                faces.entry((a.min(b), a.max(b))).or_default().push(i);
+            }
+        }
+        return faces;
+    }
+
+    /// Finds edges shared by more than two triangles
+    pub fn non_manifold_edges(&self) -> Vec<((usize, usize), Vec<usize>)> {
+        let mut edges: Vec<((usize, usize), Vec<usize>)> = self.edge_faces()
+            .into_iter()
+            .filter(|(_, faces)| faces.len() > 2)
+            .collect();
+        edges.sort();
+        return edges;
+    }
+
+    /// Finds triangles wound the opposite way from their neighbors.
+    ///
+    /// Orientation is propagated across each connected piece of the mesh.
+    /// For closed pieces, the triangles making the enclosed volume negative
+    /// are the flipped ones. For open pieces, the smaller group is.
+    pub fn flipped_triangles(&self) -> Vec<usize> {
+        let edge_faces = self.edge_faces();
+        let edge_key = |a: usize, b: usize| (a.min(b), a.max(b));
+        let mut flipped = vec![false; self.triangles.len()];
+        let mut visited = vec![false; self.triangles.len()];
+        let mut result = Vec::<usize>::new();
+        for seed in 0..self.triangles.len() {
+            if visited[seed] {
+                continue;
+            }
+            visited[seed] = true;
+            let mut component = vec![seed];
+            let mut closed = true;
+            let mut volume = 0.;
+            let mut next = 0;
+            while next < component.len() {
+                let current = component[next];
+                next += 1;
+                let triangle = self.triangles[current];
+                let [a, b, c] = triangle.map(|v| self.vertices[v]);
+                let signed_volume = glm::dot(&a, &glm::cross(&b, &c)) / 6.;
+                volume += if flipped[current] { -signed_volume } else { signed_volume };
+                for j in 0..3 {
+                    let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+                    let faces = &edge_faces[&edge_key(a, b)];
+                    if faces.len() != 2 {
+                        closed = false;
+                        continue;
+                    }
+                    let neighbor = if faces[0] == current { faces[1] } else { faces[0] };
+                    if visited[neighbor] {
+                        continue;
+                    }
+                    // Consistent neighbors run the shared edge in the opposite direction
+                    let same_direction = (0..3).any(|k| {
+                        let n = self.triangles[neighbor];
+                        n[k] == a && n[(k + 1) % 3] == b
+                    });
+                    flipped[neighbor] = flipped[current] ^ same_direction;
+                    visited[neighbor] = true;
+                    component.push(neighbor);
+                }
+            }
+            let flipped_count = component.iter().filter(|t| flipped[**t]).count();
+            let invert = if closed {
+                volume < 0.
+            } else {
+                flipped_count * 2 > component.len()
+            };
+            result.extend(component.into_iter().filter(|t| flipped[*t] != invert));
+        }
+        result.sort();
+        return result;
+    }
+
+    /// Sorts every edge that keeps the mesh from being closed and manifold
+    pub fn edge_report(&self) -> EdgeReport {
+        let mut report = EdgeReport { boundary: Vec::new(), non_manifold: Vec::new(), misoriented: Vec::new() };
+        for ((a, b), faces) in self.edge_faces() {
+            match faces.len() {
+                1 => report.boundary.push((a, b)),
+                2 => {
+                    let forward = faces.iter()
+                        .filter(|f| {
+                            let t = self.triangles[**f];
+                            (0..3).any(|i| t[i] == a && t[(i + 1) % 3] == b)
+                        })
+                        .count();
+                    if forward != 1 {
+                        report.misoriented.push((a, b));
+                    }
+                },
+                _ => report.non_manifold.push((a, b))
+            }
+        }
+        report.boundary.sort();
+        report.non_manifold.sort();
+        report.misoriented.sort();
+        return report;
+    }
+
+    /// Reverses the winding of a triangle, flipping its normal
+    pub fn flip(&mut self, index: usize) {
+        self.triangles[index].swap(1, 2);
+    }
+
+    /// Removes triangles by index. Vertices are kept, even if unused.
+    pub fn remove_triangles(&mut self, indices: &[usize]) {
+        let mut removed = vec![false; self.triangles.len()];
+        for index in indices {
+            removed[*index] = true;
+        }
+        let mut i = 0;
+        self.triangles.retain(|_| {
+            i += 1;
+            !removed[i - 1]
+        });
+    }
+
+    /// Groups triangles into pieces connected by shared edges
+    pub fn connected_groups(&self, triangles: &[usize]) -> Vec<Vec<usize>> {
+        let mut edges = HashMap::<(usize, usize), Vec<usize>>::new();
+        for t in triangles {
+            let triangle = self.triangles[*t];
+            for j in 0..3 {
+                let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+                edges.entry((a.min(b), a.max(b))).or_default().push(*t);
+            }
+        }
+        let mut group_of = HashMap::<usize, usize>::new();
+        let mut groups = Vec::<Vec<usize>>::new();
+        for seed in triangles {
+            if group_of.contains_key(seed) {
+                continue;
+            }
+            let mut group = vec![*seed];
+            group_of.insert(*seed, groups.len());
+            let mut next = 0;
+            while next < group.len() {
+                let triangle = self.triangles[group[next]];
+                next += 1;
+                for j in 0..3 {
+                    let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+                    for neighbor in &edges[&(a.min(b), a.max(b))] {
+                        if !group_of.contains_key(neighbor) {
+                            group_of.insert(*neighbor, groups.len());
+                            group.push(*neighbor);
+                        }
+                    }
+                }
+            }
+            groups.push(group);
+        }
+        return groups;
+    }
+
+    /// Grows a region from a triangle across edges where the surface bends
+    /// less than the given angle, in radians, like the face of a part between its sharp edges
+    pub fn smooth_region(&self, seed: usize, max_angle: f32) -> Vec<usize> {
+        let edge_faces = self.edge_faces();
+        let normals: Vec<Vec3> = (0..self.triangles.len())
+            .map(|t| self.area_normal(t).try_normalize(0.).unwrap_or_else(Vec3::zeros))
+            .collect();
+        let min_cos = max_angle.cos();
+        let mut in_region = vec![false; self.triangles.len()];
+        in_region[seed] = true;
+        let mut region = vec![seed];
+        let mut next = 0;
+        while next < region.len() {
+            let current = region[next];
+            next += 1;
+            let triangle = self.triangles[current];
+            for j in 0..3 {
+                let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+                for neighbor in &edge_faces[&(a.min(b), a.max(b))] {
+                    if !in_region[*neighbor] && normals[current].dot(&normals[*neighbor]) >= min_cos {
+                        in_region[*neighbor] = true;
+                        region.push(*neighbor);
+                    }
+                }
+            }
+        }
+        return region;
+    }
+
+    /// Counts how many triangles use each directed edge
+    fn directed_edge_counts(&self) -> HashMap<(usize, usize), usize> {
+        let mut counts = HashMap::<(usize, usize), usize>::new();
+        for triangle in &self.triangles {
+            for i in 0..3 {
+                *counts.entry((triangle[i], triangle[(i + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+        return counts;
+    }
+
+    /// Estimates the curvature at each vertex from the angles and cotangent weights of
+    /// the triangles around it, with each vertex standing for a third of their area.
+    /// Vertices on the boundary are left flat, and triangles without area are skipped.
+    pub fn curvature(&self) -> Curvature {
+        profile_span!("curvature");
+        let count = self.vertices.len();
+        let mut areas = vec![0f32; count];
+        let mut angles = vec![0f32; count];
+        let mut laplacian = vec![Vec3::zeros(); count];
+        let mut normals = vec![Vec3::zeros(); count];
+        let mut edges = HashMap::<(usize, usize), usize>::new();
+        for t in &self.triangles {
+            let p = t.map(|v| self.vertices[v]);
+            let cross = (p[1] - p[0]).cross(&(p[2] - p[0]));
+            let area = cross.norm() / 2.;
+            if area == 0. {
+                continue;
+            }
+            for i in 0..3 {
+                let (a, b, c) = (t[i], t[(i + 1) % 3], t[(i + 2) % 3]);
+                let (u, v) = (p[(i + 1) % 3] - p[i], p[(i + 2) % 3] - p[i]);
+                areas[a] += area / 3.;
+                normals[a] += cross;
+                angles[a] += glm::angle(&u, &v);
+                // The angle at one corner weights the edge across from it
+                let cotangent = u.dot(&v) / (2. * area);
+                let edge = self.vertices[b] - self.vertices[c];
+                laplacian[b] += edge * cotangent;
+                laplacian[c] -= edge * cotangent;
+                *edges.entry((b.min(c), b.max(c))).or_insert(0) += 1;
+            }
+        }
+        let mut boundary = vec![false; count];
+        for ((a, b), faces) in edges {
+            if faces == 1 {
+                boundary[a] = true;
+                boundary[b] = true;
+            }
+        }
+        let mut curvature = Curvature { mean: vec![0.; count], gaussian: vec![0.; count] };
+        for v in 0..count {
+            if boundary[v] || areas[v] == 0. {
+                continue;
+            }
+            let mean = laplacian[v].norm() / (4. * areas[v]);
+            curvature.mean[v] = if laplacian[v].dot(&normals[v]) < 0. { -mean } else { mean };
+            curvature.gaussian[v] = (2. * std::f32::consts::PI - angles[v]) / areas[v];
+        }
+        return curvature;
+    }
+
+    /// Splits the triangles into bodies which share no edges, largest first
+    pub fn bodies(&self) -> Vec<Vec<usize>> {
+        let all: Vec<usize> = (0..self.triangles.len()).collect();
+        let mut bodies = self.connected_groups(&all);
+        bodies.sort_by_key(|body| std::cmp::Reverse(body.len()));
+        return bodies;
+    }
+
+    /// Volume enclosed by a group of triangles. An open group, like a speck of scan noise,
+    /// is measured as if closed by a fan from its center, so it still gets a size.
+    pub fn enclosed_volume(&self, triangles: &[usize]) -> f32 {
+        if triangles.is_empty() {
+            return 0.;
+        }
+        let center = triangles.iter()
+            .flat_map(|t| self.triangles[*t])
+            .map(|v| self.vertices[v])
+            .sum::<Vec3>() / (triangles.len() * 3) as f32;
+        let volume: f32 = triangles.iter()
+            .map(|t| {
+                let [a, b, c] = self.triangle(*t).map(|v| v - center);
+                a.dot(&b.cross(&c)) / 6.
+            })
+            .sum();
+        return volume.abs();
+    }
+
+    /// Finds the loops of edges which belong to only one triangle.
+    ///
+    /// Each loop is ordered in the direction its edges have in their triangles.
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let counts = self.directed_edge_counts();
+        // Outgoing boundary edges from each vertex
+        let mut next = HashMap::<usize, Vec<usize>>::new();
+        for ((a, b), count) in &counts {
+            if *count == 1 && !counts.contains_key(&(*b, *a)) {
+                next.entry(*a).or_default().push(*b);
+            }
+        }
+        let mut starts: Vec<usize> = next.keys().copied().collect();
+        starts.sort();
+
+        let mut loops = Vec::<Vec<usize>>::new();
+        for start in starts {
+            while let Some(mut current) = next.get_mut(&start).and_then(|n| n.pop()) {
+                let mut boundary = vec![start];
+                while current != start {
+                    boundary.push(current);
+                    current = match next.get_mut(&current).and_then(|n| n.pop()) {
+                        Some(vertex) => vertex,
+                        // The boundary is not closed, so it is not a hole
+                        None => break
+                    };
+                }
+                if current == start && boundary.len() >= 3 {
+                    loops.push(boundary);
+                }
+            }
+        }
+        return loops;
+    }
+
+    /// Finds the holes in the mesh, and measures their size
+    pub fn holes(&self) -> Vec<Hole> {
+        return self.boundary_loops().into_iter().map(|boundary| {
+            let points: Vec<Vec3> = boundary.iter().map(|v| self.vertices[*v]).collect();
+            let center = points.iter().sum::<Vec3>() / points.len() as f32;
+            let mut perimeter = 0.;
+            let mut vector_area = Vec3::zeros();
+            let mut radius: f32 = 0.;
+            for i in 0..points.len() {
+                let a = points[i] - center;
+                let b = points[(i + 1) % points.len()] - center;
+                perimeter += (b - a).norm();
+                vector_area += glm::cross(&a, &b) / 2.;
+                radius = radius.max(a.norm());
+            }
+            Hole {
+                vertices: boundary,
+                perimeter,
+                area: vector_area.norm(),
+                center,
+                radius
+            }
+        }).collect();
+    }
+
+    /// Closes a boundary loop, given in the direction of its boundary edges
+    pub fn fill_loop(&mut self, boundary: &[usize], fill: HoleFill) {
+        match fill {
+            HoleFill::Flat => self.fill_flat(boundary),
+            HoleFill::Smooth => self.fill_smooth(boundary)
+        }
+    }
+
+    /// Closes a boundary loop with a fan of triangles around a new center vertex
+    fn fill_flat(&mut self, boundary: &[usize]) {
+        let center = boundary.iter().map(|v| self.vertices[*v]).sum::<Vec3>()
+            / boundary.len() as f32;
+        let center_index = self.vertices.len();
+        self.vertices.push(center);
+        for i in 0..boundary.len() {
+            let a = boundary[i];
+            let b = boundary[(i + 1) % boundary.len()];
+            // The boundary edge runs a to b, so the new triangle must run b to a
+            self.triangles.push([b, a, center_index]);
+        }
+    }
+
+    /// Closes a boundary loop with rings of vertices shrinking toward its center,
+    /// spaced like the edges leading up to the loop, then faired into the surface.
+    /// Matching the spacing matters, since fairing carries the slope on per edge.
+    fn fill_smooth(&mut self, boundary: &[usize]) {
+        let points: Vec<Vec3> = boundary.iter().map(|v| self.vertices[*v]).collect();
+        let count = points.len();
+        let center = points.iter().sum::<Vec3>() / count as f32;
+        // How far around the loop each vertex is, from 0 to 1
+        let mut around = vec![0.];
+        for i in 1..=count {
+            around.push(around[i - 1] + (points[i % count] - points[i - 1]).norm());
+        }
+        let perimeter = around[count];
+        if perimeter <= 0. {
+            return self.fill_flat(boundary);
+        }
+        around.iter_mut().for_each(|a| *a /= perimeter);
+        let radius = points.iter().map(|p| (p - center).norm()).sum::<f32>() / count as f32;
+        let spacing = self.approach_length(boundary).unwrap_or(perimeter / count as f32);
+        let rings = ((radius / spacing).round() as usize).clamp(1, MAX_FILL_RINGS);
+        let along = |fraction: f32| -> Vec3 {
+            let i = around.partition_point(|a| *a <= fraction).clamp(1, count) - 1;
+            let t = (fraction - around[i]) / (around[i + 1] - around[i]).max(f32::EPSILON);
+            return points[i] + (points[(i + 1) % count] - points[i]) * t.clamp(0., 1.);
+        };
+
+        let first = self.vertices.len();
+        let mut outer = boundary.to_vec();
+        let mut outer_around = around.clone();
+        for ring in 1..rings {
+            let t = ring as f32 / rings as f32;
+            // Fewer vertices on smaller rings, so they stay evenly spaced
+            let size = ((count as f32 * (1. - t)).round() as usize).max(3);
+            let inner_around: Vec<f32> = (0..=size).map(|j| j as f32 / size as f32).collect();
+            let inner: Vec<usize> = inner_around[..size].iter().map(|fraction| {
+                let point = along(*fraction);
+                self.vertices.push(point + (center - point) * t);
+                self.vertices.len() - 1
+            }).collect();
+            // Zip the rings together, stepping along whichever is behind
+            let (mut i, mut j) = (0, 0);
+            while i < outer.len() || j < size {
+                let step_outer = j == size || (i < outer.len() && outer_around[i + 1] <= inner_around[j + 1]);
+                if step_outer {
+                    // The outer edge runs forward, so the new triangle must run backward along it
+                    self.triangles.push([outer[(i + 1) % outer.len()], outer[i], inner[j % size]]);
+                    i += 1;
+                } else {
+                    self.triangles.push([outer[i % outer.len()], inner[j], inner[(j + 1) % size]]);
+                    j += 1;
+                }
+            }
+            outer = inner;
+            outer_around = inner_around;
+        }
+        self.fill_flat(&outer);
+        self.fair(first, boundary);
+    }
+
+    /// Average over a loop's vertices of the shortest edge to a vertex off the loop,
+    /// which is how far apart the rows of vertices leading up to the loop are
+    fn approach_length(&self, boundary: &[usize]) -> Option<f32> {
+        let mut shortest: HashMap<usize, f32> = boundary.iter().map(|v| (*v, f32::MAX)).collect();
+        for triangle in &self.triangles {
+            for i in 0..3 {
+                for j in [(i + 1) % 3, (i + 2) % 3] {
+                    if shortest.contains_key(&triangle[j]) {
+                        continue;
+                    }
+                    if let Some(length) = shortest.get_mut(&triangle[i]) {
+                        *length = length.min((self.vertices[triangle[i]] - self.vertices[triangle[j]]).norm());
+                    }
+                }
+            }
+        }
+        let lengths: Vec<f32> = shortest.into_values().filter(|length| *length < f32::MAX).collect();
+        let average = lengths.iter().sum::<f32>() / lengths.len() as f32;
+        return if average > 0. { Some(average) } else { None };
+    }
+
+    /// Moves the vertices from `first` on so the curvature changes as little as possible
+    /// across them and the boundary around them, keeping every other vertex in place.
+    ///
+    /// This minimizes the squared umbrella Laplacian at the moved and boundary vertices,
+    /// solved for each axis by conjugate gradients.
+    fn fair(&mut self, first: usize, boundary: &[usize]) {
+        let moving = self.vertices.len() - first;
+        if moving == 0 {
+            return;
+        }
+        // The moved vertices come first, at their index less `first`, then the boundary
+        let mut near = Vec::<usize>::new();
+        let mut local = HashMap::<usize, usize>::new();
+        for vertex in (first..self.vertices.len()).chain(boundary.iter().copied()) {
+            local.entry(vertex).or_insert_with(|| {
+                near.push(vertex);
+                near.len() - 1
+            });
+        }
+        let mut neighbors = vec![Vec::<usize>::new(); near.len()];
+        for triangle in &self.triangles {
+            for i in 0..3 {
+                if let Some(&index) = local.get(&triangle[i]) {
+                    for other in [triangle[(i + 1) % 3], triangle[(i + 2) % 3]] {
+                        if !neighbors[index].contains(&other) {
+                            neighbors[index].push(other);
+                        }
+                    }
+                }
+            }
+        }
+
+        for axis in 0..3 {
+            // Laplacian at each near vertex, with the moved vertices at `values`,
+            // and the others at their positions, or at zero if `fixed` is false
+            let laplacian = |values: &[f64], fixed: bool| -> Vec<f64> {
+                let value = |vertex: usize| -> f64 {
+                    if vertex >= first {
+                        values[vertex - first]
+                    } else if fixed {
+                        self.vertices[vertex][axis] as f64
+                    } else {
+                        0.
+                    }
+                };
+                return near.iter().zip(&neighbors)
+                    .map(|(vertex, around)| around.len() as f64 * value(*vertex) - around.iter().map(|n| value(*n)).sum::<f64>())
+                    .collect();
+            };
+            // The Laplacian is symmetric, so applying it again gives the energy's gradient
+            let spread = |rows: &[f64]| -> Vec<f64> {
+                return (0..moving)
+                    .map(|i| neighbors[i].len() as f64 * rows[i] - neighbors[i].iter().map(|n| rows[local[n]]).sum::<f64>())
+                    .collect();
+            };
+            let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+
+            let mut x: Vec<f64> = (first..self.vertices.len()).map(|v| self.vertices[v][axis] as f64).collect();
+            let mut r: Vec<f64> = spread(&laplacian(&x, true)).iter().map(|g| -g).collect();
+            let mut p = r.clone();
+            let mut rs = dot(&r, &r);
+            let tolerance = rs * 1e-16;
+            for _ in 0..(4 * moving).clamp(100, 2000) {
+                if rs <= tolerance {
+                    break;
+                }
+                let hp = spread(&laplacian(&p, false));
+                let alpha = rs / dot(&p, &hp);
+                for i in 0..moving {
+                    x[i] += alpha * p[i];
+                    r[i] -= alpha * hp[i];
+                }
+                let next = dot(&r, &r);
+                for i in 0..moving {
+                    p[i] = r[i] + next / rs * p[i];
+                }
+                rs = next;
+            }
+            for (vertex, value) in self.vertices[first..first + moving].iter_mut().zip(&x) {
+                vertex[axis] = *value as f32;
+            }
+        }
+    }
+}
+
+/// Corners are split into this many partitions for each thread, so the threads stay busy
+/// when some partitions have more corners than others
+const PARTITIONS_PER_THREAD: usize = 4;
+
+/// Fewest corners worth merging on several threads
+const PARALLEL_CORNERS: usize = 100_000;
+
+/// Merges vertices that fall into the same cell of a grid with the given spacing
+///
+/// Returns the merged vertices, and the vertex indices for each triangle.
+/// Vertices are numbered in the order they first appear, as if the corners were merged one at a time.
+fn merge_vertices(triangles: &[Triangle], tolerance: f32) -> (Vec<Vec3>, Vec<[usize; 3]>) {
+    let keys: Vec<[i64; 3]> = triangles.par_iter()
+        .flat_map_iter(|triangle| triangle.map(|vertex| [0, 1, 2].map(|i| (vertex[i] / tolerance).round() as i64)))
+        .collect();
+    let mut vertex_of = if rayon::current_num_threads() > 1 && keys.len() >= PARALLEL_CORNERS {
+        first_corners_parallel(&keys)
+    } else {
+        let mut lookup = HashMap::<[i64; 3], usize>::new();
+        keys.iter().enumerate().map(|(corner, key)| *lookup.entry(*key).or_insert(corner)).collect()
+    };
+    // Each corner's first corner comes before it, so it already holds its vertex index
+    let mut vertices = Vec::<Vec3>::new();
+    for corner in 0..vertex_of.len() {
+        if vertex_of[corner] == corner {
+            vertex_of[corner] = vertices.len();
+            vertices.push(triangles[corner / 3][corner % 3]);
+        } else {
+            vertex_of[corner] = vertex_of[vertex_of[corner]];
+        }
+    }
+    let indices = vertex_of.chunks_exact(3).map(|corners| [corners[0], corners[1], corners[2]]).collect();
+    return (vertices, indices);
+}
+
+/// The first corner in the same grid cell as each corner, given the cells of the corners.
+///
+/// The cells are spread over partitions by a spatial hash, and each partition is searched on its own thread.
+fn first_corners_parallel(keys: &[[i64; 3]]) -> Vec<usize> {
+    let partitions = rayon::current_num_threads() * PARTITIONS_PER_THREAD;
+    let chunk_size = (keys.len() / partitions).max(1024);
+    // The corners of each partition, split by the chunk they are in, so they stay in order
+    let chunks: Vec<Vec<Vec<usize>>> = keys.par_chunks(chunk_size).enumerate()
+        .map(|(chunk, chunk_keys)| {
+            let mut corners = vec![Vec::new(); partitions];
+            for (i, key) in chunk_keys.iter().enumerate() {
+                corners[partition(key, partitions)].push(chunk * chunk_size + i);
+            }
+            return corners;
+        })
+        .collect();
+    // The first corner of each corner's cell, in the order of the partition's corners
+    let firsts: Vec<Vec<usize>> = (0..partitions).into_par_iter()
+        .map(|p| {
+            let mut lookup = HashMap::<[i64; 3], usize>::new();
+            return chunks.iter()
+                .flat_map(|corners| &corners[p])
+                .map(|&corner| *lookup.entry(keys[corner]).or_insert(corner))
+                .collect();
+        })
+        .collect();
+    let mut first_corners = vec![0; keys.len()];
+    for (p, partition_firsts) in firsts.iter().enumerate() {
+        let corners = chunks.iter().flat_map(|corners| &corners[p]);
+        for (&corner, &first) in corners.zip(partition_firsts) {
+            first_corners[corner] = first;
+        }
+    }
+    return first_corners;
+}
+
+/// The partition a grid cell is merged in, spreading nearby cells over different partitions
+fn partition(key: &[i64; 3], partitions: usize) -> usize {
+    let hash = key[0].wrapping_mul(73_856_093) ^ key[1].wrapping_mul(19_349_663) ^ key[2].wrapping_mul(83_492_791);
+    return (hash as u64 % partitions as u64) as usize;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32, y: f32, z: f32) -> Vec3 {
+        return Vec3::new(x, y, z);
+    }
+
+    #[test]
+    fn clean_welds_and_removes_what_welding_broke() {
+        let triangles = vec![
+            [v(0., 0., 0.), v(1., 0., 0.), v(0., 1., 0.)],
+            // Shares an edge with the first, with a corner just off
+            [v(1., 0.0001, 0.), v(1., 1., 0.), v(0., 1., 0.)],
+            // Two corners weld together
+            [v(5., 5., 5.), v(5.00001, 5., 5.), v(6., 5., 5.)],
+            // The first again, wound the other way
+            [v(0., 0., 0.), v(0., 1., 0.), v(1., 0., 0.)]
+        ];
+        let (mesh, report) = TriangleMesh::clean(&triangles, 0.001);
+        assert_eq!(mesh.triangles.len(), 2);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.triangle(0), triangles[0]);
+        assert_eq!(report.triangles_before, 4);
+        assert_eq!(report.triangles_after, 2);
+        assert_eq!(report.degenerate, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.vertices_before, 6);
+        assert_eq!(report.vertices_after, 4);
+        assert_eq!(report.unreferenced, 2);
+        assert!(mesh.triangles.iter().flatten().all(|v| *v < mesh.vertices.len()));
+    }
+
+    #[test]
+    fn clean_keeps_thin_triangles() {
+        // A sliver with three distinct corners, whose edges the triangles around it would share
+        let triangles = vec![
+            [v(0., 0., 0.), v(1., 0., 0.), v(2., 0.01, 0.)],
+            [v(0., 0., 0.), v(1., 0., 0.), v(1., -1., 0.)],
+            // No area at all, but still three distinct corners
+            [v(0., 0., 0.), v(2., 0., 0.), v(1., 0., 0.)]
+        ];
+        let (mesh, report) = TriangleMesh::clean(&triangles, 0.001);
+        assert_eq!(mesh.triangles.len(), 3);
+        assert_eq!(report.degenerate + report.duplicates + report.unreferenced, 0);
+        assert_eq!(report.thin, 1);
+    }
+}
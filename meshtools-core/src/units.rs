@@ -0,0 +1,81 @@
+use crate::i18n::tr;
+
+/// A unit of length for interpreting the (unitless) coordinates of a mesh
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Unit {
+    Millimeter,
+    Centimeter,
+    Inch
+}
+
+impl Unit {
+    pub const ALL: [Unit; 3] = [Unit::Millimeter, Unit::Centimeter, Unit::Inch];
+
+    /// The abbreviation shown after values, also used to store the unit in the settings
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Unit::Millimeter => "mm",
+            Unit::Centimeter => "cm",
+            Unit::Inch => "in"
+        }
+    }
+
+    /// The translated name of the unit
+    pub fn label(&self) -> String {
+        match self {
+            Unit::Millimeter => tr("unit-millimeter"),
+            Unit::Centimeter => tr("unit-centimeter"),
+            Unit::Inch => tr("unit-inch")
+        }
+    }
+
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        return Unit::ALL.into_iter().find(|u| u.suffix() == suffix);
+    }
+
+    /// The length of one of this unit in millimeters
+    pub fn millimeters(&self) -> f32 {
+        match self {
+            Unit::Millimeter => 1.0,
+            Unit::Centimeter => 10.0,
+            Unit::Inch => 25.4
+        }
+    }
+
+    /// The factor turning lengths in this unit into lengths in another
+    pub fn scale_to(&self, other: Unit) -> f32 {
+        return self.millimeters() / other.millimeters();
+    }
+}
+
+/// Millimeters per unit for the length suffixes accepted in typed values
+fn suffix_millimeters(suffix: &str) -> Option<f32> {
+    return match suffix.to_lowercase().as_str() {
+        "mm" => Some(1.0),
+        "cm" => Some(10.0),
+        "m" => Some(1000.0),
+        "in" | "inch" | "inches" | "\"" => Some(25.4),
+        "ft" | "'" => Some(304.8),
+        _ => None
+    };
+}
+
+/// Splits trailing unit letters (or quote marks) from a typed value,
+/// returning the value and the suffix, which may be empty
+pub fn split_suffix(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    let split = text
+        .rfind(|c: char| !(c.is_alphabetic() || c == '"' || c == '\'' || c == '°'))
+        .map(|i| i + text[i..].chars().next().unwrap().len_utf8())
+        .unwrap_or(0);
+    return (text[..split].trim(), &text[split..]);
+}
+
+/// Converts a length with an optional suffix (like "12.5mm" or "0.5in")
+/// to the given unit. Values without a suffix are taken to already be in that unit.
+pub fn length_in_unit(value: f32, suffix: &str, unit: Unit) -> Option<f32> {
+    if suffix.is_empty() {
+        return Some(value);
+    }
+    return Some(value * suffix_millimeters(suffix)? / unit.millimeters());
+}
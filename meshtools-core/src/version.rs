@@ -0,0 +1,11 @@
+/// Version of this build, from the crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The tool and its version, with the build id if one was set in MESHTOOLS_BUILD when it was compiled.
+/// It is written into exported files, so they can be traced back to the build that made them.
+pub fn description() -> String {
+    return match option_env!("MESHTOOLS_BUILD") {
+        Some(build) => format!("MeshTools {} ({})", VERSION, build),
+        None => format!("MeshTools {}", VERSION)
+    };
+}
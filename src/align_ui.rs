@@ -0,0 +1,237 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::bvh::Bvh;
+use crate::i18n::{tr, tr_args};
+use crate::mesh_widget::Hit;
+use crate::registration::{self, Refinement};
+use crate::scene::SceneModel;
+use crate::thread_request::Request;
+use crate::units::Unit;
+
+/// Most corners of the moving model matched to the target while refining, spread over the whole model
+const MAX_REFINE_POINTS: usize = 20_000;
+/// Most closest point steps taken while refining
+const MAX_REFINE_STEPS: usize = 50;
+
+/// What the application should do after the alignment dialog is shown
+pub enum AlignResponse {
+    None,
+    /// Move the model at this index by a further transformation, in the scene
+    Align(usize, Mat4)
+}
+
+/// A dialog to move one model onto another by points clicked on each that should meet,
+/// and then to fit it closely onto the other's surface
+pub struct AlignUI {
+    /// Index of the model being moved, while the dialog is open
+    pub source: Option<usize>,
+    /// Index of the model it is moved onto
+    pub target: usize,
+    /// Points clicked on the source, in the scene, each with the point it should meet on the target once clicked
+    pairs: Vec<(Vec3, Option<Vec3>)>,
+    /// The source being fitted onto the target's surface, giving the motion in the scene
+    refine_request: Option<Request<Option<(Mat4, Refinement)>>>,
+    /// The last fit onto the target's surface
+    refined: Option<Refinement>
+}
+
+impl AlignUI {
+    pub fn new() -> Self {
+        return Self { source: None, target: 0, pairs: Vec::new(), refine_request: None, refined: None };
+    }
+
+    /// Opens the dialog for a model, moving it onto the next model in the scene
+    pub fn open(&mut self, source: usize, model_count: usize) {
+        self.close();
+        self.source = Some(source);
+        self.target = (source + 1) % model_count.max(1);
+    }
+
+    pub fn close(&mut self) {
+        if let Some(request) = &self.refine_request {
+            request.cancel();
+        }
+        self.source = None;
+        self.pairs.clear();
+        self.refine_request = None;
+        self.refined = None;
+    }
+
+    /// Starts fitting corners of the source onto the target's surface in the background,
+    /// from where they are placed now
+    fn start_refine(&mut self, ctx: &egui::Context, source: &SceneModel, target: &SceneModel) {
+        let source_placement = source.transformation_ui.get_matrix();
+        let target_placement = target.transformation_ui.get_matrix();
+        let to_target = match target_placement.try_inverse() {
+            Some(inverse) => inverse * source_placement,
+            None => return
+        };
+        let stride = (source.triangles.len() / MAX_REFINE_POINTS).max(1);
+        // Fitted among the target's own triangles, so they needn't be copied
+        let points: Vec<Vec3> = source.triangles.iter()
+            .step_by(stride)
+            .map(|t| to_target.transform_point(&t[0].into()).coords)
+            .collect();
+        let triangles = target.triangles.clone();
+        self.refine_request = Some(Request::spawn(ctx, move |progress| {
+            let bvh = Bvh::new(&triangles);
+            let refinement = registration::refine(&points, &triangles, &bvh, MAX_REFINE_STEPS, |fraction, _| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            })?;
+            // The same motion, in the scene
+            let motion = target_placement * refinement.transformation * target_placement.try_inverse()?;
+            return Some((motion, refinement));
+        }));
+    }
+
+    /// The next point is clicked on the target, to finish a pair
+    fn picking_target(&self) -> bool {
+        return matches!(self.pairs.last(), Some((_, None)));
+    }
+
+    /// Adds a point clicked on the model it is expected on. Clicks on other models are ignored.
+    pub fn pick(&mut self, hit: &Hit) {
+        if self.picking_target() {
+            if hit.model == self.target {
+                self.pairs.last_mut().unwrap().1 = Some(hit.point);
+            }
+        } else if Some(hit.model) == self.source {
+            self.pairs.push((hit.point, None));
+        }
+    }
+
+    /// The finished pairs, as the points on the source and the points on the target
+    fn finished(&self) -> (Vec<Vec3>, Vec<Vec3>) {
+        return self.pairs.iter().filter_map(|(from, to)| Some((*from, (*to)?))).unzip();
+    }
+
+    /// Lines from each point on the source to its point on the target
+    pub fn outlines(&self) -> Vec<Vec<Vec3>> {
+        if self.source.is_none() {
+            return Vec::new();
+        }
+        return self.pairs.iter().filter_map(|(from, to)| Some(vec![*from, (*to)?])).collect();
+    }
+
+    /// Shows the dialog if it is open
+    pub fn show(&mut self, ctx: &egui::Context, scene: &[SceneModel], unit: Unit) -> AlignResponse {
+        let source = match self.source {
+            Some(source) if source < scene.len() && self.target < scene.len() => source,
+            Some(_) => {
+                self.close();
+                return AlignResponse::None;
+            },
+            None => return AlignResponse::None
+        };
+        let mut refined = None;
+        if let Some(result) = self.refine_request.as_mut().and_then(|request| request.take()) {
+            self.refine_request = None;
+            if let Some((motion, refinement)) = result {
+                self.refined = Some(refinement);
+                refined = Some(motion);
+            }
+        }
+        let (from, to) = self.finished();
+        let fit = registration::rigid_fit(&from, &to);
+
+        let mut open = true;
+        let mut target = self.target;
+        let mut removed = None;
+        let mut align = false;
+        let mut refine = false;
+        egui::Window::new(tr("align"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.label(tr_args("align-moving", &[("name", &scene[source].name)]));
+            ui.horizontal(|ui| {
+                ui.label(tr("align-onto"));
+                egui::ComboBox::from_id_source("align_target")
+                    .selected_text(&scene[target].name)
+                    .show_ui(ui, |ui| {
+                    for (i, model) in scene.iter().enumerate().filter(|(i, _)| *i != source) {
+                        ui.selectable_value(&mut target, i, &model.name);
+                    }
+                });
+            });
+            if source == target {
+                ui.label(tr("align-needs-two"));
+                return;
+            }
+            let next = if self.picking_target() { &scene[target].name } else { &scene[source].name };
+            ui.label(tr_args("align-click", &[("name", next)]));
+            egui::Grid::new("align_pairs").show(ui, |ui| {
+                for (i, (from, to)) in self.pairs.iter().enumerate() {
+                    ui.label(tr_args("align-pair", &[("number", &(i + 1))]));
+                    match to {
+                        Some(to) => ui.label(format!("{:.3} {}", glm::distance(from, to), unit.suffix())),
+                        None => ui.label("…")
+                    };
+                    if ui.small_button(tr("align-remove")).clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            match &fit {
+                Some(fit) => {
+                    let rms = registration::rms_error(&from, &to, fit);
+                    ui.label(tr_args("align-rms", &[("rms", &format!("{:.3} {}", rms, unit.suffix()))]));
+                },
+                None => {
+                    ui.label(tr("align-needs-three"));
+                }
+            }
+            align = ui.add_enabled(fit.is_some(), egui::Button::new(tr("align-apply"))).clicked();
+
+            ui.separator();
+            ui.label(tr("align-refine-hint"));
+            match &self.refine_request {
+                Some(request) => {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+                        if ui.button(tr("align-cancel")).clicked() {
+                            request.cancel();
+                        }
+                    });
+                },
+                None => {
+                    refine = ui.button(tr("align-refine")).clicked();
+                }
+            }
+            if let Some(refinement) = &self.refined {
+                ui.label(tr_args("align-refined", &[
+                    ("steps", &refinement.iterations),
+                    ("rms", &format!("{:.4} {}", refinement.rms, unit.suffix()))]));
+            }
+        });
+        if let Some(i) = removed {
+            self.pairs.remove(i);
+        }
+        if target != self.target {
+            self.target = target;
+            self.pairs.clear();
+            self.refined = None;
+        }
+        if refine && source != self.target {
+            self.start_refine(ctx, &scene[source], &scene[self.target]);
+        }
+        if !open {
+            self.close();
+        }
+        if let Some(motion) = refined {
+            self.pairs.clear();
+            return AlignResponse::Align(source, motion);
+        }
+        return match (align, fit) {
+            (true, Some(fit)) => {
+                // The points are where the source was, so they are cleared once it moves
+                self.pairs.clear();
+                AlignResponse::Align(source, fit)
+            },
+            _ => AlignResponse::None
+        };
+    }
+}
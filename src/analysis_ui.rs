@@ -0,0 +1,553 @@
+use std::sync::Arc;
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::curvature::{CornerCurvature, CurvatureKind};
+use crate::deviation;
+use crate::i18n::{tr, tr_args};
+use crate::mesh_cache;
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::oriented_box::OrientedBox;
+use crate::overhang::{self, Overhang};
+use crate::scene::{Measures, SceneModel, Topology};
+use crate::thickness::{self, ThinRegion, WallThickness};
+use crate::thread_request::{KeyedRequest, Priority, Request};
+use crate::units::Unit;
+
+/// What the application should do after the analysis panel is shown
+pub enum AnalysisResponse {
+    None,
+    /// Color the corners of a model's triangles, or stop coloring it
+    Colors(usize, Option<Vec<[f32; 3]>>)
+}
+
+/// What the selected model is colored by
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorBy {
+    Curvature(CurvatureKind),
+    Thickness,
+    Overhang
+}
+
+/// The colors a model was last given, and what they were found from
+enum Coloring {
+    /// The kind of curvature, the curvature at the ends of the scale, and the curvature found
+    Curvature(CurvatureKind, f32, Arc<CornerCurvature>),
+    /// The threshold for thin walls, and the thickness found
+    Thickness(f32, Arc<WallThickness>),
+    /// The steepest overhang printed without support, and the overhang found
+    Overhang(f32, Arc<Overhang>)
+}
+
+impl Coloring {
+    fn same(&self, other: &Coloring) -> bool {
+        return match (self, other) {
+            (Coloring::Curvature(k1, r1, c1), Coloring::Curvature(k2, r2, c2)) =>
+                k1 == k2 && r1 == r2 && Arc::ptr_eq(c1, c2),
+            (Coloring::Thickness(t1, w1), Coloring::Thickness(t2, w2)) => t1 == t2 && Arc::ptr_eq(w1, w2),
+            (Coloring::Overhang(a1, o1), Coloring::Overhang(a2, o2)) => a1 == a2 && Arc::ptr_eq(o1, o2),
+            _ => false
+        };
+    }
+
+    fn colors(&self) -> Vec<[f32; 3]> {
+        return match self {
+            Coloring::Curvature(kind, range, curvature) => curvature.colors(*kind, *range),
+            Coloring::Thickness(threshold, thickness) => thickness.colors(*threshold),
+            Coloring::Overhang(max_angle, overhang) => overhang.colors(*max_angle)
+        };
+    }
+}
+
+/// What a model's analysis is found for: its index, and the version of its triangles,
+/// so anything found before an operation changed them is dropped.
+/// Analyses that depend on where the model is placed add the placement.
+type ModelKey = (usize, usize);
+
+fn model_key(index: usize, model: &SceneModel) -> ModelKey {
+    return (index, model.version);
+}
+
+/// A panel with measurements of the selected model as placed in the scene:
+/// its mass properties, the boxes around it, its curvature, its wall thickness, and its overhangs
+pub struct AnalysisUI {
+    /// The area and mass properties being found, for a model as placed
+    measures_request: KeyedRequest<(ModelKey, Mat4), Measures>,
+    /// How the model's triangles connect, being found
+    topology_request: KeyedRequest<ModelKey, Topology>,
+    /// The oriented box being found, for a model as placed
+    request: KeyedRequest<(ModelKey, Mat4), Option<OrientedBox>>,
+    /// What the selected model is colored by, if anything
+    pub color_by: Option<ColorBy>,
+    /// The curvature at the ends of the color map, or None for a range covering most of the surface
+    pub curvature_range: Option<f32>,
+    /// Walls thinner than this are flagged
+    pub thickness_threshold: f32,
+    /// The curvature being found, for a model
+    curvature_request: KeyedRequest<ModelKey, CornerCurvature>,
+    /// The wall thickness being found, for a model as placed
+    thickness_request: KeyedRequest<(ModelKey, Mat4), Option<WallThickness>>,
+    /// Surfaces leaning out further than this many degrees from vertical need support
+    pub max_overhang: f32,
+    /// The overhang being found, for a model as placed
+    overhang_request: KeyedRequest<(ModelKey, Mat4), Overhang>,
+    /// The overhang last found, and the model and placement it was found for
+    overhang: Option<((ModelKey, Mat4), Arc<Overhang>)>,
+    /// The model last colored, with what it was colored by
+    colored: Option<(usize, Coloring)>,
+    /// Regions thinner than the threshold when the model was last colored by thickness
+    thin_regions: Vec<ThinRegion>
+}
+
+impl AnalysisUI {
+    pub fn new() -> Self {
+        return Self {
+            measures_request: KeyedRequest::new(),
+            topology_request: KeyedRequest::new(),
+            request: KeyedRequest::new(),
+            color_by: None,
+            curvature_range: None,
+            thickness_threshold: 1.0,
+            curvature_request: KeyedRequest::new(),
+            thickness_request: KeyedRequest::new(),
+            max_overhang: 45.,
+            overhang_request: KeyedRequest::new(),
+            overhang: None,
+            colored: None,
+            thin_regions: Vec::new()
+        };
+    }
+
+    /// Stops everything being found, such as when a new file replaces the models it was for
+    pub fn cancel_all(&mut self) {
+        self.measures_request.cancel();
+        self.topology_request.cancel();
+        self.request.cancel();
+        self.curvature_request.cancel();
+        self.thickness_request.cancel();
+        self.overhang_request.cancel();
+        self.overhang = None;
+        self.colored = None;
+        self.thin_regions.clear();
+    }
+
+    /// Keeps what was found for a model, and starts finding what is missing or out of date in the background
+    fn update_stats(&mut self, ctx: &egui::Context, index: usize, model: &mut SceneModel, placement: &Mat4) {
+        let key = model_key(index, model);
+        self.measures_request.keep_current(&(key, *placement));
+        if let Some(measures) = self.measures_request.take() {
+            model.set_measures(placement, measures);
+        }
+        let current = matches!(model.measures(), Some((found_with, _)) if found_with == *placement);
+        if !current && !self.measures_request.is_pending() {
+            let triangles = model.triangles.clone();
+            let transformation = *placement;
+            let request = Request::spawn(ctx, move |_| Measures::new(&triangles, &transformation));
+            self.measures_request.start((key, *placement), request);
+        }
+
+        self.topology_request.keep_current(&key);
+        if let Some(topology) = self.topology_request.take() {
+            model.set_topology(topology);
+        }
+        if model.topology().is_none() && !self.topology_request.is_pending() {
+            let triangles = model.triangles.clone();
+            let cache_key = model.cache_key.clone();
+            let request = Request::spawn_with(ctx, Priority::Background, move |_| {
+                return mesh_cache::welded(cache_key.as_deref(), &triangles);
+            }).then(|welded, _| Topology::new(&welded));
+            self.topology_request.start(key, request);
+        }
+    }
+
+    /// Starts finding the oriented box around a model in the background
+    fn start(&mut self, ctx: &egui::Context, index: usize, model: &SceneModel, placement: Mat4) {
+        let triangles = model.triangles.clone();
+        let request = Request::spawn(ctx, move |progress| {
+            return OrientedBox::new(&triangles, &placement, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            });
+        });
+        self.request.start((model_key(index, model), placement), request);
+    }
+
+    /// Shows the panel for the selected model. Whether the model is colored in the view
+    /// is needed to notice when something else has cleared its colors.
+    pub fn ui(&mut self, ui: &mut egui::Ui, index: usize, model: &mut SceneModel, view_colored: bool, unit: Unit)
+    -> AnalysisResponse {
+        let placement = model.transformation_ui.get_matrix();
+        // Drop a box being found for a different model or placement
+        self.request.keep_current(&(model_key(index, model), placement));
+        // Nothing is found if the work was stopped early
+        if let Some(Some(oriented_box)) = self.request.take() {
+            model.set_oriented_box(&placement, oriented_box);
+        }
+        self.update_stats(ui.ctx(), index, model, &placement);
+
+        let suffix = unit.suffix();
+        let length = |value: f32| format!("{:.3} {}", value, suffix);
+        ui.label(tr("analysis-hint"));
+        match model.measures() {
+            // The last measurements are shown while the model is measured again where it was moved
+            Some((measured_with, measures)) => Self::measures_ui(ui, model, &measures, measured_with == placement, unit),
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("analysis-measuring"));
+                    ui.spinner();
+                });
+            }
+        }
+
+        ui.separator();
+        match model.topology() {
+            Some(topology) => {
+                ui.label(tr_args("analysis-topology", &[
+                    ("vertices", &topology.vertices), ("bodies", &topology.bodies)]));
+                if topology.is_closed() {
+                    ui.label(tr("analysis-closed"));
+                } else {
+                    ui.label(tr_args("analysis-open", &[
+                        ("boundary", &topology.boundary_edges),
+                        ("non_manifold", &topology.non_manifold_edges),
+                        ("misoriented", &topology.misoriented_edges)]));
+                }
+            },
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("analysis-finding-topology"));
+                    ui.spinner();
+                });
+            }
+        }
+
+        ui.separator();
+        if let Some((min, max)) = model.transformed_bounds(&placement) {
+            let size = max - min;
+            ui.label(tr_args("analysis-bounding-box", &[
+                ("x", &length(size.x)), ("y", &length(size.y)), ("z", &length(size.z)),
+                ("volume", &format!("{:.3} {}³", size.x * size.y * size.z, suffix))]));
+        }
+        if let Some(oriented_box) = model.oriented_box(&placement) {
+            let size = oriented_box.size;
+            ui.label(tr_args("analysis-oriented-box", &[
+                ("x", &length(size.x)), ("y", &length(size.y)), ("z", &length(size.z)),
+                ("volume", &format!("{:.3} {}³", oriented_box.volume(), suffix))]));
+            if ui.button(tr("analysis-align-box")).on_hover_text(tr("analysis-align-box-hint")).clicked() {
+                model.transformation_ui.transform(oriented_box.alignment());
+            }
+        } else if let Some(progress) = self.request.progress() {
+            let cancelled = ui.horizontal(|ui| {
+                ui.label(tr("analysis-finding-box"));
+                ui.add(egui::ProgressBar::new(progress).show_percentage());
+                return ui.button(tr("analysis-cancel")).clicked();
+            }).inner;
+            if cancelled {
+                self.request.cancel();
+            }
+        } else if ui.button(tr("analysis-find-box")).clicked() {
+            self.start(ui.ctx(), index, model, placement);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(tr("analysis-color-by"));
+            ui.radio_value(&mut self.color_by, None, tr("analysis-color-off"));
+            for kind in CurvatureKind::ALL {
+                ui.radio_value(&mut self.color_by, Some(ColorBy::Curvature(kind)), kind.label());
+            }
+            ui.radio_value(&mut self.color_by, Some(ColorBy::Thickness), tr("thickness"));
+            ui.radio_value(&mut self.color_by, Some(ColorBy::Overhang), tr("overhang"));
+        });
+        match self.color_by {
+            Some(ColorBy::Curvature(kind)) => self.curvature_ui(ui, kind, model, unit),
+            Some(ColorBy::Thickness) => self.thickness_ui(ui, model, &placement, unit),
+            Some(ColorBy::Overhang) => self.overhang_ui(ui, (model_key(index, model), placement), unit),
+            None => ()
+        }
+        return self.update_colors(ui.ctx(), index, model, &placement, view_colored);
+    }
+
+    /// Shows a model's area and mass properties. Unless they are current, they can't be acted on.
+    fn measures_ui(ui: &mut egui::Ui, model: &mut SceneModel, measures: &Measures, current: bool, unit: Unit) {
+        let suffix = unit.suffix();
+        let vector = |v: &Vec3| format!("{:.3}, {:.3}, {:.3}", v.x, v.y, v.z);
+        ui.horizontal(|ui| {
+            ui.label(tr_args("analysis-area", &[("area", &format!("{:.3} {}²", measures.area, suffix))]));
+            if !current {
+                ui.spinner();
+            }
+        });
+        match measures.mass {
+            Some(properties) => {
+                ui.label(tr_args("analysis-volume", &[("volume", &format!("{:.3} {}³", properties.volume, suffix))]));
+                ui.label(tr_args("analysis-center-of-mass", &[("center", &vector(&properties.center))]));
+                if ui.add_enabled(current, egui::Button::new(tr("analysis-center-origin"))).clicked() {
+                    model.transformation_ui.translate(-properties.center);
+                }
+                // Inertia per unit density has units of length to the fifth power
+                ui.label(tr_args("analysis-principal-axes", &[("unit", &format!("{}⁵", suffix))]));
+                let (moments, axes) = properties.principal_axes();
+                egui::Grid::new("principal_axes").show(ui, |ui| {
+                    for i in 0..3 {
+                        ui.label(format!("{:.4e}", moments[i]));
+                        ui.label(vector(&axes.column(i).into_owned()));
+                        ui.end_row();
+                    }
+                });
+                ui.label(tr_args("analysis-inertia", &[("unit", &format!("{}⁵", suffix))]));
+                egui::Grid::new("inertia_tensor").show(ui, |ui| {
+                    for row in 0..3 {
+                        for column in 0..3 {
+                            ui.label(format!("{:.4e}", properties.inertia[(row, column)]));
+                        }
+                        ui.end_row();
+                    }
+                });
+            },
+            None => {
+                ui.label(tr("analysis-no-volume"));
+            }
+        }
+    }
+
+    fn curvature_ui(&mut self, ui: &mut egui::Ui, kind: CurvatureKind, model: &SceneModel, unit: Unit) {
+        let curvature = match model.curvature() {
+            Some(curvature) => curvature,
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("curvature-finding"));
+                    ui.spinner();
+                });
+                return;
+            }
+        };
+        // Mean curvature is one over a length, and Gaussian curvature one over an area
+        let suffix = match kind {
+            CurvatureKind::Mean => format!(" 1/{}", unit.suffix()),
+            CurvatureKind::Gaussian => format!(" 1/{}²", unit.suffix())
+        };
+        let typical = curvature.typical_range(kind);
+        ui.horizontal(|ui| {
+            ui.label(tr("curvature-range"));
+            let mut auto = self.curvature_range.is_none();
+            if ui.checkbox(&mut auto, tr("curvature-auto-range")).changed() {
+                self.curvature_range = if auto { None } else { Some(typical) };
+            }
+            if let Some(range) = &mut self.curvature_range {
+                ui.add(egui::DragValue::new(range)
+                    .speed(typical * 0.01)
+                    .clamp_range(0.0..=f32::MAX)
+                    .suffix(&suffix));
+            }
+        });
+        let range = self.curvature_range.unwrap_or(typical);
+        ui.horizontal(|ui| {
+            for fraction in [-1., -0.5, 0., 0.5, 1.] {
+                let [r, g, b] = deviation::heat_color(fraction).map(|c| (c * 255.) as u8);
+                ui.colored_label(egui::Color32::from_rgb(r, g, b), format!("{:+.4}", fraction * range));
+            }
+        });
+        ui.label(tr("curvature-hint"));
+    }
+
+    fn thickness_ui(&mut self, ui: &mut egui::Ui, model: &SceneModel, placement: &Mat4, unit: Unit) {
+        let suffix = unit.suffix();
+        let length = |value: f32| format!("{:.3} {}", value, suffix);
+        ui.horizontal(|ui| {
+            ui.label(tr("thickness-threshold"));
+            ui.add(NumericEdit::new("thickness_threshold", &mut self.thickness_threshold, Quantity::Length(unit)));
+        });
+        let thickness = match model.thickness(placement) {
+            Some(thickness) => thickness,
+            None => {
+                if let Some(progress) = self.thickness_request.progress() {
+                    let cancelled = ui.horizontal(|ui| {
+                        ui.label(tr("thickness-finding"));
+                        ui.add(egui::ProgressBar::new(progress).show_percentage());
+                        return ui.button(tr("analysis-cancel")).clicked();
+                    }).inner;
+                    // It would start again while the model is colored by it
+                    if cancelled {
+                        self.color_by = None;
+                    }
+                }
+                return;
+            }
+        };
+        let (thin, thick) = WallThickness::color_span(self.thickness_threshold);
+        let color = |color: [f32; 3]| {
+            let [r, g, b] = color.map(|c| (c * 255.) as u8);
+            return egui::Color32::from_rgb(r, g, b);
+        };
+        ui.horizontal(|ui| {
+            ui.colored_label(color(thickness::THIN_COLOR), tr_args("thickness-thin", &[("threshold", &length(thin))]));
+            for fraction in [1., 0.5, 0., -0.5, -1.] {
+                let value = thin + (thick - thin) * (1. - fraction) / 2.;
+                ui.colored_label(color(deviation::heat_color(fraction)), length(value));
+            }
+            ui.colored_label(color(thickness::OPEN_COLOR), tr("thickness-open"));
+        });
+        if let Some(thinnest) = thickness.thinnest() {
+            ui.label(tr_args("thickness-thinnest", &[("thickness", &length(thinnest))]));
+        }
+        if self.thin_regions.is_empty() {
+            ui.label(tr_args("thickness-none-thin", &[("threshold", &length(self.thickness_threshold))]));
+        } else {
+            let area: f32 = self.thin_regions.iter().map(|region| region.area).sum();
+            ui.label(tr_args("thickness-thin-regions", &[
+                ("count", &self.thin_regions.len().to_string()),
+                ("threshold", &length(self.thickness_threshold)),
+                ("area", &format!("{:.3} {}²", area, suffix))]));
+            egui::Grid::new("thin_regions").show(ui, |ui| {
+                for region in self.thin_regions.iter().take(8) {
+                    ui.label(format!("{:.3} {}²", region.area, suffix));
+                    ui.label(length(region.thinnest));
+                    ui.end_row();
+                }
+            });
+        }
+        ui.label(tr("thickness-hint"));
+    }
+
+    fn overhang_ui(&mut self, ui: &mut egui::Ui, key: (ModelKey, Mat4), unit: Unit) {
+        ui.horizontal(|ui| {
+            ui.label(tr("overhang-max"));
+            ui.add(NumericEdit::new("max_overhang", &mut self.max_overhang, Quantity::Angle));
+        });
+        self.max_overhang = self.max_overhang.clamp(0., 90.);
+        let overhang = match &self.overhang {
+            Some((found_for, overhang)) if *found_for == key => overhang,
+            _ => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("overhang-finding"));
+                    ui.spinner();
+                });
+                return;
+            }
+        };
+        let color = |color: [f32; 3]| {
+            let [r, g, b] = color.map(|c| (c * 255.) as u8);
+            return egui::Color32::from_rgb(r, g, b);
+        };
+        ui.horizontal(|ui| {
+            ui.colored_label(color(overhang::OVERHANG_COLOR), tr("overhang-unsupported"));
+            ui.colored_label(color(overhang::SUPPORTED_COLOR), tr("overhang-supported"));
+        });
+        let area = overhang.area(self.max_overhang);
+        ui.label(tr_args("overhang-area", &[("area", &format!("{:.3} {}²", area, unit.suffix()))]));
+        ui.label(tr("overhang-hint"));
+    }
+
+    /// Finds the curvature of a model in the background, returning it once it is found
+    fn find_curvature(&mut self, ctx: &egui::Context, index: usize, model: &mut SceneModel) -> Option<Arc<CornerCurvature>> {
+        let key = model_key(index, model);
+        self.curvature_request.keep_current(&key);
+        if let Some(curvature) = self.curvature_request.take() {
+            model.set_curvature(Arc::new(curvature));
+        }
+        if model.curvature().is_none() && !self.curvature_request.is_pending() {
+            let triangles = model.triangles.clone();
+            self.curvature_request.start(key, Request::spawn(ctx, move |_| CornerCurvature::new(&triangles)));
+        }
+        return model.curvature();
+    }
+
+    /// Finds the wall thickness of a model as placed in the background, returning it once it is found
+    fn find_thickness(&mut self, ctx: &egui::Context, index: usize, model: &mut SceneModel, placement: &Mat4)
+    -> Option<Arc<WallThickness>> {
+        let key = (model_key(index, model), *placement);
+        self.thickness_request.keep_current(&key);
+        // Nothing is found if the work was stopped early
+        if let Some(Some(thickness)) = self.thickness_request.take() {
+            model.set_thickness(placement, Arc::new(thickness));
+        }
+        if model.thickness(placement).is_none() && !self.thickness_request.is_pending() {
+            let triangles = model.triangles.clone();
+            let transformation = *placement;
+            let request = Request::spawn(ctx, move |progress| {
+                return WallThickness::new(&triangles, &transformation, |fraction| {
+                    progress.set(fraction);
+                    return !progress.cancelled();
+                });
+            });
+            self.thickness_request.start(key, request);
+        }
+        return model.thickness(placement);
+    }
+
+    /// Finds the overhang of a model as placed in the background, returning it once it is found
+    fn find_overhang(&mut self, ctx: &egui::Context, index: usize, model: &SceneModel, placement: &Mat4)
+    -> Option<Arc<Overhang>> {
+        let key = (model_key(index, model), *placement);
+        self.overhang_request.keep_current(&key);
+        if let Some(overhang) = self.overhang_request.take() {
+            self.overhang = Some((key, Arc::new(overhang)));
+        }
+        match &self.overhang {
+            Some((found_for, overhang)) if *found_for == key => return Some(overhang.clone()),
+            _ => ()
+        }
+        if !self.overhang_request.is_pending() {
+            let triangles = model.triangles.clone();
+            let transformation = *placement;
+            self.overhang_request.start(key, Request::spawn(ctx, move |_| Overhang::new(&triangles, &transformation)));
+        }
+        return None;
+    }
+
+    /// Finds what the model is colored by when it is needed, and colors the model by it
+    fn update_colors(&mut self, ctx: &egui::Context, index: usize, model: &mut SceneModel, placement: &Mat4,
+        view_colored: bool) -> AnalysisResponse {
+        if let Some((colored, _)) = &self.colored {
+            if *colored != index {
+                // The selection changed, so the model colored before goes back to normal
+                let colored = *colored;
+                self.colored = None;
+                return AnalysisResponse::Colors(colored, None);
+            }
+            if !view_colored {
+                self.colored = None;
+            }
+        }
+        if !matches!(self.color_by, Some(ColorBy::Curvature(_))) {
+            self.curvature_request.cancel();
+        }
+        if self.color_by != Some(ColorBy::Thickness) {
+            self.thickness_request.cancel();
+            self.thin_regions.clear();
+        }
+        if self.color_by != Some(ColorBy::Overhang) {
+            self.overhang_request.cancel();
+        }
+        let coloring = match self.color_by {
+            Some(ColorBy::Curvature(kind)) => self.find_curvature(ctx, index, model).map(|curvature| {
+                let range = self.curvature_range.unwrap_or_else(|| curvature.typical_range(kind));
+                Coloring::Curvature(kind, range, curvature)
+            }),
+            Some(ColorBy::Thickness) => self.find_thickness(ctx, index, model, placement)
+                .map(|thickness| Coloring::Thickness(self.thickness_threshold, thickness)),
+            Some(ColorBy::Overhang) => self.find_overhang(ctx, index, model, placement)
+                .map(|overhang| Coloring::Overhang(self.max_overhang, overhang)),
+            None => {
+                return match self.colored.take() {
+                    Some((colored, _)) => AnalysisResponse::Colors(colored, None),
+                    None => AnalysisResponse::None
+                };
+            }
+        };
+        let coloring = match coloring {
+            Some(coloring) => coloring,
+            None => return AnalysisResponse::None
+        };
+        if matches!(&self.colored, Some((_, colored)) if colored.same(&coloring)) {
+            return AnalysisResponse::None;
+        }
+        if let Coloring::Thickness(threshold, thickness) = &coloring {
+            self.thin_regions = thickness.thin_regions(*threshold);
+        }
+        let colors = coloring.colors();
+        self.colored = Some((index, coloring));
+        return AnalysisResponse::Colors(index, Some(colors));
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::Error;
+use std::path::Path;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::annotation::{self, Annotation};
+use crate::i18n::{tr, tr_args};
+use crate::mesh_widget::ViewState;
+
+/// Radius of the dot drawn where a pin was dropped, in points
+const PIN_RADIUS: f32 = 4.;
+
+/// What the application should do after the annotations window is shown
+pub enum AnnotationResponse {
+    None,
+    /// The pins were written to this file, or failed to be
+    Saved(Result<String, Error>),
+    /// The pins were read from this file, or failed to be
+    Loaded(Result<String, Error>)
+}
+
+/// A window to drop named pins on the mesh surface, which are drawn over the view
+/// and kept in a file next to the mesh
+pub struct AnnotationUI {
+    pub open: bool,
+    pub annotations: Vec<Annotation>,
+    /// Draw the pins over the view
+    pub visible: bool,
+    /// The next click on the view drops a pin
+    placing: bool
+}
+
+impl AnnotationUI {
+    pub fn new() -> Self {
+        return Self { open: false, annotations: Vec::new(), visible: true, placing: false };
+    }
+
+    /// Replaces the pins with the ones kept next to a mesh file, if there are any
+    pub fn load_sidecar(&mut self, mesh_path: &str) {
+        self.placing = false;
+        self.annotations = annotation::read_annotations(&annotation::sidecar_path(mesh_path)).unwrap_or_default();
+    }
+
+    /// Whether the next click on the view drops a pin, rather than doing anything else
+    pub fn placing(&self) -> bool {
+        return self.open && self.placing;
+    }
+
+    /// Drops a pin at a point picked on the mesh, named by its number
+    pub fn place(&mut self, point: Vec3) {
+        if !self.placing() {
+            return;
+        }
+        let text = tr_args("annotate-pin-name", &[("number", &(self.annotations.len() + 1))]);
+        self.annotations.push(Annotation { text, position: point });
+        self.placing = false;
+    }
+
+    /// Draws each pin over the view as a dot with its text beside it, facing the viewer
+    pub fn draw(&self, painter: &egui::Painter, view: &ViewState, rect: egui::Rect) {
+        if !self.visible {
+            return;
+        }
+        let [r, g, b] = view.overlay.map(|c| (c * 255.) as u8);
+        let color = egui::Color32::from_rgb(r, g, b);
+        let font = egui::FontId::proportional(14.);
+        for annotation in &self.annotations {
+            let position = view.project(&annotation.position, rect);
+            if !rect.contains(position) {
+                continue;
+            }
+            painter.circle(position, PIN_RADIUS, color, egui::Stroke::new(1., egui::Color32::BLACK));
+            let galley = painter.layout_no_wrap(annotation.text.clone(), font.clone(), egui::Color32::WHITE);
+            let label = egui::Rect::from_min_size(position + egui::vec2(PIN_RADIUS + 2., -galley.size().y / 2.), galley.size())
+                .expand(2.);
+            painter.rect_filled(label, 2., egui::Color32::from_black_alpha(180));
+            painter.galley(label.min + egui::vec2(2., 2.), galley);
+        }
+    }
+
+    /// Shows the window if it is open, given the mesh file the pins are kept beside
+    pub fn show(&mut self, ctx: &egui::Context, mesh_path: Option<&str>) -> AnnotationResponse {
+        if !self.open {
+            return AnnotationResponse::None;
+        }
+        let mut open = true;
+        let (mut save, mut load) = (false, false);
+        egui::Window::new(tr("annotate"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if self.placing {
+                    ui.strong(tr("annotate-placing"));
+                    if ui.button(tr("annotate-cancel")).clicked() {
+                        self.placing = false;
+                    }
+                } else if ui.button(tr("annotate-add")).clicked() {
+                    self.placing = true;
+                }
+            });
+            ui.checkbox(&mut self.visible, tr("annotate-show"));
+            ui.separator();
+            if self.annotations.is_empty() {
+                ui.label(tr("annotate-none"));
+            }
+            let mut removed = None;
+            egui::ScrollArea::vertical().max_height(240.).show(ui, |ui| {
+                for (i, annotation) in self.annotations.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}.", i + 1));
+                        ui.text_edit_singleline(&mut annotation.text);
+                        if ui.small_button("🗑").on_hover_text(tr("annotate-remove")).clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+            });
+            if let Some(i) = removed {
+                self.annotations.remove(i);
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                save = ui.add_enabled(!self.annotations.is_empty(), egui::Button::new(tr("annotate-save")))
+                    .on_hover_text(tr("annotate-save-hint"))
+                    .clicked();
+                load = ui.button(tr("annotate-load")).clicked();
+            });
+        });
+        if !open {
+            self.open = false;
+            self.placing = false;
+            return AnnotationResponse::None;
+        }
+        if save {
+            // Pins are offered a place next to the mesh, where they're found when it is opened again
+            let mut dialog = rfd::FileDialog::new().add_filter("json", &["json", "JSON"]);
+            if let Some(sidecar) = mesh_path.map(annotation::sidecar_path) {
+                let sidecar = Path::new(&sidecar);
+                if let (Some(directory), Some(name)) = (sidecar.parent(), sidecar.file_name()) {
+                    dialog = dialog.set_directory(directory).set_file_name(&name.to_string_lossy());
+                }
+            }
+            if let Some(path) = dialog.save_file() {
+                let path = path.display().to_string();
+                let result = annotation::write_annotations(&path, &self.annotations);
+                return AnnotationResponse::Saved(result.map(|_| path));
+            }
+        }
+        if load {
+            if let Some(path) = rfd::FileDialog::new().add_filter("json", &["json", "JSON"]).pick_file() {
+                let path = path.display().to_string();
+                let result = annotation::read_annotations(&path).map(|annotations| {
+                    self.annotations = annotations;
+                    path
+                });
+                return AnnotationResponse::Loaded(result);
+            }
+        }
+        return AnnotationResponse::None;
+    }
+}
@@ -0,0 +1,46 @@
+use crate::i18n::tr;
+
+/// When an analysis of a mesh runs after it is loaded
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AutoRun {
+    Always,
+    /// Only for meshes with at most the small mesh threshold of triangles
+    SmallMeshes,
+    /// Only when the user asks for it
+    OnDemand
+}
+
+impl AutoRun {
+    pub const ALL: [AutoRun; 3] = [AutoRun::Always, AutoRun::SmallMeshes, AutoRun::OnDemand];
+
+    /// The name used to store the choice in the settings
+    pub fn code(&self) -> &'static str {
+        match self {
+            AutoRun::Always => "always",
+            AutoRun::SmallMeshes => "small",
+            AutoRun::OnDemand => "on-demand"
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        return AutoRun::ALL.into_iter().find(|a| a.code() == code);
+    }
+
+    /// The translated name of the choice
+    pub fn label(&self) -> String {
+        match self {
+            AutoRun::Always => tr("auto-run-always"),
+            AutoRun::SmallMeshes => tr("auto-run-small"),
+            AutoRun::OnDemand => tr("auto-run-on-demand")
+        }
+    }
+
+    /// Whether to run the analysis on a newly loaded mesh
+    pub fn runs_for(&self, triangle_count: usize, threshold: usize) -> bool {
+        match self {
+            AutoRun::Always => true,
+            AutoRun::SmallMeshes => triangle_count <= threshold,
+            AutoRun::OnDemand => false
+        }
+    }
+}
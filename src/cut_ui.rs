@@ -0,0 +1,137 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::i18n::{tr, tr_args};
+use crate::plane_cut::{self, Plane};
+use crate::thread_request::Request;
+use crate::triangle::{self, Triangle};
+
+/// Names of the axes the plane can face along
+pub const AXES: [&str; 3] = ["X", "Y", "Z"];
+
+/// A plane across an axis where it crosses a box, slightly larger so it shows around the box
+pub fn outline(axis: usize, position: f32, min: &Vec3, max: &Vec3) -> Vec<Vec3> {
+    let margin = (max - min) * 0.1;
+    let (low, high) = (min - margin, max + margin);
+    let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+    return [(low[a], low[b]), (high[a], low[b]), (high[a], high[b]), (low[a], high[b])].iter()
+        .map(|(first, second)| {
+            let mut corner = Vec3::zeros();
+            corner[axis] = position;
+            corner[a] = *first;
+            corner[b] = *second;
+            corner
+        })
+        .collect();
+}
+
+/// What the application should do after the cut dialog is shown
+pub enum CutResponse {
+    None,
+    /// The model was split into the parts on the positive and negative sides of the plane,
+    /// both in scene coordinates
+    Cut(Vec<Triangle>, Vec<Triangle>)
+}
+
+/// A dialog to cut a model in two with a plane across one of the axes,
+/// drawing the plane in the view while it is placed
+pub struct CutUI {
+    /// Index of the model being cut, while the dialog is open
+    pub model: Option<usize>,
+    pub axis: usize,
+    /// Where the plane crosses the axis, in scene coordinates
+    pub position: f32,
+    request: Option<Request<(Vec<Triangle>, Vec<Triangle>)>>
+}
+
+impl CutUI {
+    pub fn new() -> Self {
+        return Self { model: None, axis: 2, position: 0., request: None };
+    }
+
+    /// Opens the dialog for a model with the given bounds, with the plane across its middle
+    pub fn open(&mut self, model: usize, bounds: Option<(Vec3, Vec3)>) {
+        self.close();
+        self.model = Some(model);
+        if let Some((min, max)) = bounds {
+            self.position = (min[self.axis] + max[self.axis]) / 2.;
+        }
+    }
+
+    pub fn close(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.model = None;
+        self.request = None;
+    }
+
+    pub fn plane(&self) -> Plane {
+        let mut normal = Vec3::zeros();
+        normal[self.axis] = 1.;
+        return Plane { normal, offset: self.position };
+    }
+
+    /// The plane where it crosses a box, slightly larger so it shows around the model
+    pub fn outline(&self, min: &Vec3, max: &Vec3) -> Vec<Vec3> {
+        return outline(self.axis, self.position, min, max);
+    }
+
+    /// Shows the dialog if it is open, given the triangles of the model being cut,
+    /// the transformation placing them in the scene, and their bounds once placed
+    pub fn show(&mut self, ctx: &egui::Context, triangles: &[Triangle], placement: &Mat4, bounds: Option<(Vec3, Vec3)>)
+    -> CutResponse {
+        if self.model.is_none() {
+            return CutResponse::None;
+        }
+        if let Some(halves) = self.request.as_mut().and_then(|request| request.take()) {
+            self.close();
+            return CutResponse::Cut(halves.0, halves.1);
+        }
+
+        let mut open = true;
+        let mut cut = false;
+        let mut cancel = false;
+        let working = self.request.is_some();
+        egui::Window::new(tr("cut"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.set_enabled(!working);
+            ui.horizontal(|ui| {
+                ui.label(tr("cut-axis"));
+                for (axis, name) in AXES.iter().enumerate() {
+                    if ui.radio_value(&mut self.axis, axis, *name).clicked() {
+                        if let Some((min, max)) = bounds {
+                            self.position = (min[axis] + max[axis]) / 2.;
+                        }
+                    }
+                }
+            });
+            if let Some((min, max)) = bounds {
+                ui.add(egui::Slider::new(&mut self.position, min[self.axis]..=max[self.axis])
+                    .text(tr_args("cut-position", &[("axis", &AXES[self.axis])])));
+            }
+            if working {
+                ui.add(egui::Spinner::new());
+            }
+            ui.horizontal(|ui| {
+                cut = ui.button(tr("cut-apply")).clicked();
+                cancel = ui.button(tr("cut-cancel")).clicked();
+            });
+        });
+
+        if cancel || !open {
+            self.close();
+        } else if cut {
+            let placed: Vec<Triangle> = triangles.iter()
+                .map(|t| triangle::transform(t, placement))
+                .collect();
+            let plane = self.plane();
+            self.request = Some(Request::spawn(ctx, move |_| {
+                return plane_cut::cut(&placed, &plane);
+            }));
+        }
+        return CutResponse::None;
+    }
+}
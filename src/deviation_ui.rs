@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use crate::deviation::{self, Band, ColorMode, Deviation, ToleranceBands};
+use crate::i18n::{tr, tr_args};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::scene::SceneModel;
+use crate::thread_request::Request;
+use crate::triangle::{self, Triangle};
+use crate::units::Unit;
+
+/// What the application should do after the deviation dialog is shown
+pub enum DeviationResponse {
+    None,
+    /// Color the corners of a model's triangles, or stop coloring it
+    Colors(usize, Option<Vec<[f32; 3]>>)
+}
+
+/// How a model was last colored, to know when it has to be colored again
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Coloring {
+    Bands(ToleranceBands),
+    /// A heat map running to this distance either way
+    HeatMap(f32)
+}
+
+/// A dialog to measure how far one model is from a reference model, aligned with it,
+/// and check the result against tolerance bands
+pub struct DeviationUI {
+    /// Index of the model being measured, while the dialog is open
+    pub model: Option<usize>,
+    /// Index of the model it is measured against
+    pub reference: usize,
+    pub bands: ToleranceBands,
+    pub mode: ColorMode,
+    /// The distance at the ends of the heat map, or None to fit the largest deviation
+    pub range: Option<f32>,
+    /// The distances, once measured for the current models
+    deviation: Option<Arc<Deviation>>,
+    request: Option<Request<Option<Deviation>>>,
+    /// How the model was last colored
+    colored: Option<Coloring>
+}
+
+impl DeviationUI {
+    pub fn new() -> Self {
+        return Self {
+            model: None,
+            reference: 0,
+            bands: ToleranceBands { pass: 0.5, warning: 1.0, min_pass_percent: 95. },
+            mode: ColorMode::Bands,
+            range: None,
+            deviation: None,
+            request: None,
+            colored: None
+        };
+    }
+
+    /// Opens the dialog for a model, measured against the next model in the scene
+    pub fn open(&mut self, model: usize, model_count: usize) {
+        self.forget();
+        self.model = Some(model);
+        self.reference = (model + 1) % model_count.max(1);
+    }
+
+    /// Closes the dialog. Returns the model whose colors have to be cleared, if it was colored.
+    pub fn close(&mut self) -> Option<usize> {
+        let colored = self.colored.and(self.model);
+        self.forget();
+        self.model = None;
+        return colored;
+    }
+
+    /// Drops the distances, for when either model changes
+    fn forget(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.request = None;
+        self.deviation = None;
+        self.colored = None;
+    }
+
+    /// Starts measuring in the background, with both models as placed in the scene
+    fn start(&mut self, ctx: &egui::Context, model: &SceneModel, reference: &SceneModel) {
+        self.forget();
+        // The distances color the measured model's corners, so they stay in its order
+        // even if its placement mirrors it. Only the reference's facing matters for their sign.
+        let placement = model.transformation_ui.get_matrix();
+        let measured: Vec<Triangle> = model.triangles.iter().map(|t| triangle::transform_corners(t, &placement)).collect();
+        let placement = reference.transformation_ui.get_matrix();
+        let reference: Vec<Triangle> = reference.triangles.iter().map(|t| triangle::transform(t, &placement)).collect();
+        self.request = Some(Request::spawn(ctx, move |progress| {
+            return Deviation::new(&measured, &reference, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            });
+        }));
+    }
+
+    /// Shows the dialog if it is open
+    pub fn show(&mut self, ctx: &egui::Context, scene: &[SceneModel], unit: Unit) -> DeviationResponse {
+        let index = match self.model {
+            Some(index) if index < scene.len() && self.reference < scene.len() => index,
+            Some(_) => {
+                return match self.close() {
+                    Some(colored) if colored < scene.len() => DeviationResponse::Colors(colored, None),
+                    _ => DeviationResponse::None
+                };
+            },
+            None => return DeviationResponse::None
+        };
+        if let Some(result) = self.request.as_mut().and_then(|request| request.take()) {
+            self.request = None;
+            self.deviation = result.map(Arc::new);
+        }
+        if self.deviation.is_none() && self.request.is_none() && index != self.reference {
+            self.start(ctx, &scene[index], &scene[self.reference]);
+        }
+
+        let mut open = true;
+        let mut reference = self.reference;
+        let mut close = false;
+        egui::Window::new(tr("deviation"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.label(tr_args("deviation-measured", &[("name", &scene[index].name)]));
+            ui.horizontal(|ui| {
+                ui.label(tr("deviation-reference"));
+                egui::ComboBox::from_id_source("deviation_reference")
+                    .selected_text(&scene[reference].name)
+                    .show_ui(ui, |ui| {
+                    for (i, model) in scene.iter().enumerate().filter(|(i, _)| *i != index) {
+                        ui.selectable_value(&mut reference, i, &model.name);
+                    }
+                });
+            });
+            if index == reference {
+                ui.label(tr("deviation-needs-two"));
+            }
+            if let Some(request) = &self.request {
+                ui.horizontal(|ui| {
+                    ui.label(tr("deviation-measuring"));
+                    ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+                });
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                for mode in ColorMode::ALL {
+                    ui.radio_value(&mut self.mode, mode, mode.label());
+                }
+            });
+            if self.mode == ColorMode::HeatMap {
+                let auto_range = self.deviation.as_ref().map_or(0., |d| d.statistics.largest());
+                ui.horizontal(|ui| {
+                    ui.label(tr("deviation-range"));
+                    let mut auto = self.range.is_none();
+                    if ui.checkbox(&mut auto, tr("deviation-auto-range")).changed() {
+                        self.range = if auto { None } else { Some(auto_range) };
+                    }
+                    if let Some(range) = &mut self.range {
+                        ui.add(NumericEdit::new("deviation_range", range, Quantity::Length(unit)));
+                    }
+                });
+                let range = self.range.unwrap_or(auto_range);
+                ui.horizontal(|ui| {
+                    for fraction in [-1., -0.5, 0., 0.5, 1.] {
+                        let [r, g, b] = deviation::heat_color(fraction).map(|c| (c * 255.) as u8);
+                        ui.colored_label(egui::Color32::from_rgb(r, g, b),
+                            format!("{:+.3} {}", fraction * range, unit.suffix()));
+                    }
+                });
+            }
+            egui::Grid::new("deviation_bands").show(ui, |ui| {
+                ui.label(tr("deviation-pass-within"));
+                ui.add(NumericEdit::new("deviation_pass", &mut self.bands.pass, Quantity::Length(unit)));
+                ui.end_row();
+                ui.label(tr("deviation-warning-within"));
+                ui.add(NumericEdit::new("deviation_warning", &mut self.bands.warning, Quantity::Length(unit)));
+                ui.end_row();
+                ui.label(tr("deviation-min-pass"));
+                ui.add(egui::DragValue::new(&mut self.bands.min_pass_percent)
+                    .speed(0.1)
+                    .clamp_range(0.0..=100.0)
+                    .suffix("%"));
+                ui.end_row();
+            });
+            if let Some(deviation) = &self.deviation {
+                ui.separator();
+                let statistics = &deviation.statistics;
+                let length = |value: f32| format!("{:.4} {}", value, unit.suffix());
+                ui.label(tr_args("deviation-statistics", &[
+                    ("min", &length(statistics.min)),
+                    ("max", &length(statistics.max)),
+                    ("rms", &length(statistics.rms))]));
+                let percentages = deviation.band_percentages(&self.bands);
+                egui::Grid::new("deviation_report").show(ui, |ui| {
+                    for (band, percent) in Band::ALL.iter().zip(percentages) {
+                        let [r, g, b] = band.color().map(|c| (c * 255.) as u8);
+                        ui.colored_label(egui::Color32::from_rgb(r, g, b), band.label());
+                        ui.label(format!("{:.2}%", percent));
+                        ui.end_row();
+                    }
+                });
+                if self.bands.accepts(&percentages) {
+                    ui.colored_label(egui::Color32::from_rgb(50, 230, 80), tr("deviation-accepted"));
+                } else {
+                    ui.colored_label(egui::Color32::from_rgb(255, 40, 25), tr("deviation-rejected"));
+                }
+            }
+            close = ui.button(tr("deviation-close")).clicked();
+        });
+
+        if close || !open {
+            return match self.close() {
+                Some(colored) => DeviationResponse::Colors(colored, None),
+                None => DeviationResponse::None
+            };
+        }
+        if reference != self.reference {
+            let colored = self.colored.is_some();
+            self.reference = reference;
+            self.forget();
+            if colored {
+                return DeviationResponse::Colors(index, None);
+            }
+        }
+        if let Some(deviation) = &self.deviation {
+            let coloring = match self.mode {
+                ColorMode::Bands => Coloring::Bands(self.bands),
+                ColorMode::HeatMap => Coloring::HeatMap(
+                    self.range.unwrap_or_else(|| deviation.statistics.largest()))
+            };
+            if self.colored != Some(coloring) {
+                self.colored = Some(coloring);
+                let colors = match coloring {
+                    Coloring::Bands(bands) => deviation.band_colors(&bands),
+                    Coloring::HeatMap(range) => deviation.heat_colors(range)
+                };
+                return DeviationResponse::Colors(index, Some(colors));
+            }
+        }
+        return DeviationResponse::None;
+    }
+}
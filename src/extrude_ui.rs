@@ -0,0 +1,170 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec2, Vec3};
+
+use crate::extrude::{self, ExtrudeMode};
+use crate::i18n::{tr, tr_args};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::thread_request::Request;
+use crate::triangle::{self, Triangle};
+use crate::units::Unit;
+
+/// The directions an outline can be extruded along, with their names
+const DIRECTIONS: [&str; 6] = ["+X", "+Y", "+Z", "-X", "-Y", "-Z"];
+
+/// The direction at an index of `DIRECTIONS`
+fn direction(index: usize) -> Vec3 {
+    let mut direction = Vec3::zeros();
+    direction[index % 3] = if index < 3 { 1. } else { -1. };
+    return direction;
+}
+
+/// What the application should do after the extrude dialog is shown
+pub enum ExtrudeResponse {
+    None,
+    /// Add a model with this name and these triangles, already placed in the scene
+    Add(String, Vec<Triangle>)
+}
+
+/// A dialog to make a closed solid from a model's silhouette or from an open surface,
+/// such as turning a scanned relief into a printable part
+pub struct ExtrudeUI {
+    /// Index of the model extruded, while the dialog is open
+    pub model: Option<usize>,
+    pub mode: ExtrudeMode,
+    /// Index of the direction in `DIRECTIONS`
+    pub direction: usize,
+    pub thickness: f32,
+    /// Grid cells along the longest side of a silhouette
+    pub resolution: usize,
+    request: Option<Request<Option<Vec<Triangle>>>>,
+    /// The last extrusion finished without a solid
+    failed: bool
+}
+
+impl ExtrudeUI {
+    pub fn new() -> Self {
+        return Self {
+            model: None,
+            mode: ExtrudeMode::Silhouette,
+            direction: 2,
+            thickness: 2.,
+            resolution: 400,
+            request: None,
+            failed: false
+        };
+    }
+
+    pub fn open(&mut self, model: usize) {
+        self.close();
+        self.model = Some(model);
+    }
+
+    pub fn close(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.model = None;
+        self.request = None;
+        self.failed = false;
+    }
+
+    /// Shows the dialog if it is open, given the name and triangles of the model,
+    /// and the transformation placing them in the scene. The solid is made in the scene,
+    /// so the directions are those of the view.
+    pub fn show(&mut self, ctx: &egui::Context, name: &str, triangles: &[Triangle], placement: &Mat4, unit: Unit)
+    -> ExtrudeResponse {
+        if self.model.is_none() {
+            return ExtrudeResponse::None;
+        }
+        if let Some(result) = self.request.as_mut().and_then(|request| request.take()) {
+            self.request = None;
+            match result {
+                Some(solid) => {
+                    self.close();
+                    return ExtrudeResponse::Add(tr_args("extrude-name", &[("name", &name)]), solid);
+                },
+                // Cancelling closes the dialog, so a request still here found nothing to extrude
+                None => self.failed = true
+            }
+        }
+
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new(tr("extrude"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.add_enabled_ui(self.request.is_none(), |ui| {
+                ui.horizontal(|ui| {
+                    for mode in ExtrudeMode::ALL {
+                        ui.radio_value(&mut self.mode, mode, mode.label());
+                    }
+                });
+                ui.label(match self.mode {
+                    ExtrudeMode::Silhouette => tr("extrude-silhouette-hint"),
+                    ExtrudeMode::Surface => tr("extrude-surface-hint")
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("extrude-direction"));
+                    egui::ComboBox::from_id_source("extrude_direction")
+                        .selected_text(DIRECTIONS[self.direction])
+                        .show_ui(ui, |ui| {
+                        for (i, name) in DIRECTIONS.iter().enumerate() {
+                            ui.selectable_value(&mut self.direction, i, *name);
+                        }
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("extrude-thickness"));
+                    ui.add(NumericEdit::new("extrude_thickness", &mut self.thickness, Quantity::Length(unit)));
+                });
+                if self.mode == ExtrudeMode::Silhouette {
+                    ui.add(egui::Slider::new(&mut self.resolution, 50..=extrude::MAX_RESOLUTION)
+                        .text(tr("extrude-resolution")));
+                    if let Some((min, max)) = triangle::transformed_bounds(triangles, placement) {
+                        // The silhouette spans the two axes across the direction
+                        let axis = self.direction % 3;
+                        let across = |p: Vec3| Vec2::new(p[(axis + 1) % 3], p[(axis + 2) % 3]);
+                        let spacing = extrude::grid_spacing(&across(min), &across(max), self.resolution);
+                        ui.label(tr_args("extrude-spacing", &[("spacing", &format!("{:.3} {}", spacing, unit.suffix()))]));
+                    }
+                }
+            });
+            if self.thickness <= 0. {
+                ui.label(tr("extrude-invalid"));
+            }
+            if self.failed && self.mode == ExtrudeMode::Surface {
+                ui.label(tr("extrude-closed"));
+            }
+            if let Some(request) = &self.request {
+                ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+            }
+            ui.horizontal(|ui| {
+                let ready = self.request.is_none() && self.thickness > 0.;
+                apply = ui.add_enabled(ready, egui::Button::new(tr("extrude-apply"))).clicked();
+                cancel = ui.button(tr("extrude-cancel")).clicked();
+            });
+        });
+
+        if cancel || !open {
+            self.close();
+        } else if apply {
+            let placed: Vec<Triangle> = triangles.iter()
+                .map(|t| triangle::transform(t, placement))
+                .collect();
+            let (mode, direction, thickness, resolution) = (self.mode, direction(self.direction), self.thickness, self.resolution);
+            self.failed = false;
+            self.request = Some(Request::spawn(ctx, move |progress| {
+                return match mode {
+                    ExtrudeMode::Silhouette => extrude::extrude_silhouette(&placed, &direction, thickness, resolution, |fraction| {
+                        progress.set(fraction);
+                        return !progress.cancelled();
+                    }),
+                    ExtrudeMode::Surface => extrude::extrude_surface(&placed, &direction, thickness)
+                };
+            }));
+        }
+        return ExtrudeResponse::None;
+    }
+}
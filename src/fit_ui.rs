@@ -0,0 +1,193 @@
+use std::collections::HashSet;
+use std::io::Error;
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::fitting::{self, FittedPrimitive, Primitive, PrimitiveKind};
+use crate::i18n::{tr, tr_args};
+use crate::mesh_cache;
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::triangle::{self, Triangle};
+use crate::triangle_mesh::TriangleMesh;
+use crate::units::Unit;
+
+/// Most triangles of a region outlined in the view, since each is drawn separately
+const MAX_OUTLINED_TRIANGLES: usize = 5_000;
+
+/// What the application should do after the fitting dialog is shown
+pub enum FitResponse {
+    None,
+    /// The report was written to this file, or failed to be
+    Exported(Result<String, Error>)
+}
+
+/// A dialog to fit planes, cylinders, and spheres to regions of a model picked in the view,
+/// and export them for rebuilding the reference geometry in CAD
+pub struct FitUI {
+    /// Index of the model being fitted, while the dialog is open
+    pub model: Option<usize>,
+    pub kind: PrimitiveKind,
+    /// Largest bend between neighboring triangles of a region, in degrees
+    pub max_angle: f32,
+    /// Everything fitted so far, in the scene
+    pub fitted: Vec<FittedPrimitive>,
+    /// The model's welded mesh, found when the first region is picked
+    mesh: Option<TriangleMesh>,
+    /// The last region picked, placed in the scene
+    region: Vec<Triangle>,
+    /// The last region picked couldn't be fitted with the chosen primitive
+    failed: bool
+}
+
+impl FitUI {
+    pub fn new() -> Self {
+        return Self {
+            model: None,
+            kind: PrimitiveKind::Plane,
+            max_angle: 20.,
+            fitted: Vec::new(),
+            mesh: None,
+            region: Vec::new(),
+            failed: false
+        };
+    }
+
+    /// Opens the dialog for a model. Primitives fitted before are kept,
+    /// since they are all in scene coordinates.
+    pub fn open(&mut self, model: usize) {
+        self.close();
+        self.model = Some(model);
+    }
+
+    pub fn close(&mut self) {
+        self.model = None;
+        self.mesh = None;
+        self.region.clear();
+        self.failed = false;
+    }
+
+    /// Fits the chosen primitive to the smooth region around a point picked on the model.
+    /// The point is in the scene, where the placement puts the model's triangles.
+    pub fn pick(&mut self, point: &Vec3, triangles: &[Triangle], cache_key: Option<&str>, placement: &Mat4) {
+        let inverse = match placement.try_inverse() {
+            Some(inverse) => inverse,
+            None => return
+        };
+        let local = inverse.transform_point(&(*point).into()).coords;
+        let mesh = self.mesh.get_or_insert_with(|| mesh_cache::welded(cache_key, triangles));
+        let seed = (0..mesh.triangles.len())
+            .map(|t| (t, glm::distance2(&triangle::closest_point(&local, &mesh.triangle(t)), &local)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let seed = match seed {
+            Some((seed, _)) => seed,
+            None => return
+        };
+        let region = mesh.smooth_region(seed, self.max_angle.to_radians());
+
+        // Fitted in the scene, so the report matches what is printed
+        self.region = region.iter().map(|t| triangle::transform(&mesh.triangle(*t), placement)).collect();
+        let vertices: HashSet<usize> = region.iter().flat_map(|t| mesh.triangles[*t]).collect();
+        let points: Vec<Vec3> = vertices.iter()
+            .map(|v| placement.transform_point(&mesh.vertices[*v].into()).coords)
+            .collect();
+        let normals: Vec<Vec3> = self.region.iter()
+            .filter_map(|t| (t[1] - t[0]).cross(&(t[2] - t[0])).try_normalize(0.))
+            .collect();
+        let primitive = match Primitive::fit(self.kind, &points, &normals) {
+            Some(primitive) => primitive,
+            None => {
+                self.failed = true;
+                return;
+            }
+        };
+        self.failed = false;
+        let number = self.fitted.iter().filter(|f| f.primitive.kind() == self.kind).count() + 1;
+        self.fitted.push(FittedPrimitive {
+            name: tr_args("fit-name", &[("kind", &self.kind.label()), ("number", &number)]),
+            rms: primitive.rms(&points),
+            max_error: points.iter().map(|p| primitive.distance(p)).fold(0., f32::max),
+            triangles: region.len(),
+            primitive
+        });
+    }
+
+    /// Outlines of the triangles of the last region picked
+    pub fn outlines(&self) -> Vec<Vec<Vec3>> {
+        if self.model.is_none() {
+            return Vec::new();
+        }
+        return self.region.iter().take(MAX_OUTLINED_TRIANGLES).map(|t| t.to_vec()).collect();
+    }
+
+    /// Shows the dialog if it is open
+    pub fn show(&mut self, ctx: &egui::Context, unit: Unit) -> FitResponse {
+        if self.model.is_none() {
+            return FitResponse::None;
+        }
+        let mut open = true;
+        let mut export = false;
+        let mut removed = None;
+        egui::Window::new(tr("fit"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for kind in PrimitiveKind::ALL {
+                    ui.radio_value(&mut self.kind, kind, kind.label());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("fit-max-angle"));
+                ui.add(NumericEdit::new("fit_max_angle", &mut self.max_angle, Quantity::Angle));
+            });
+            ui.label(tr("fit-hint"));
+            if self.failed {
+                ui.colored_label(egui::Color32::RED, tr("fit-failed"));
+            }
+            ui.separator();
+            let suffix = unit.suffix();
+            let length = |value: f32| format!("{:.4} {}", value, suffix);
+            let vector = |v: &Vec3| format!("({:.4}, {:.4}, {:.4})", v.x, v.y, v.z);
+            egui::ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+                for (i, fitted) in self.fitted.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut fitted.name);
+                        if ui.small_button("🗑").on_hover_text(tr("fit-remove")).clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                    ui.label(match &fitted.primitive {
+                        Primitive::Plane { point, normal } => tr_args("fit-plane-entry", &[
+                            ("point", &vector(point)), ("normal", &vector(normal))]),
+                        Primitive::Cylinder { point, axis, radius } => tr_args("fit-cylinder-entry", &[
+                            ("point", &vector(point)), ("axis", &vector(axis)), ("radius", &length(*radius))]),
+                        Primitive::Sphere { center, radius } => tr_args("fit-sphere-entry", &[
+                            ("center", &vector(center)), ("radius", &length(*radius))])
+                    });
+                    ui.label(tr_args("fit-error", &[
+                        ("triangles", &fitted.triangles),
+                        ("rms", &length(fitted.rms)),
+                        ("max", &length(fitted.max_error))]));
+                    ui.separator();
+                }
+            });
+            export = ui.add_enabled(!self.fitted.is_empty(), egui::Button::new(tr("fit-export"))).clicked();
+        });
+        if let Some(i) = removed {
+            self.fitted.remove(i);
+        }
+        if !open {
+            self.close();
+            return FitResponse::None;
+        }
+        if export {
+            let path = rfd::FileDialog::new().add_filter("json", &["json", "JSON"]).save_file();
+            if let Some(path) = path {
+                let path = path.display().to_string();
+                let result = fitting::write_report(&path, &self.fitted, unit.suffix());
+                return FitResponse::Exported(result.map(|_| path));
+            }
+        }
+        return FitResponse::None;
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use crate::heightmap::Heightmap;
+use crate::i18n::{tr, tr_args};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::thread_request::Request;
+use crate::triangle::Triangle;
+use crate::units::Unit;
+
+/// Most pixels along the longest side of the image that can be kept
+const MAX_DETAIL: usize = 2000;
+
+/// What the application should do after the heightmap dialog is shown
+pub enum HeightmapResponse {
+    None,
+    /// Add a model with this name and these triangles to the scene
+    Add(String, Vec<Triangle>),
+    /// The image with this name couldn't be read
+    Failed(String, image::ImageError)
+}
+
+/// A dialog to turn a grayscale image into a solid with its brightness as height,
+/// for reliefs and lithophanes
+pub struct HeightmapUI {
+    /// The name and brightness of the image being imported, while the dialog is open
+    image: Option<(String, Arc<Heightmap>)>,
+    /// Distance between pixels
+    pub pixel_size: f32,
+    /// Height of black pixels, or of white ones if inverted
+    pub min_height: f32,
+    pub max_height: f32,
+    /// Dark pixels are high, as for a lithophane, which is thickest where it should be darkest
+    pub invert: bool,
+    /// Most pixels kept along the longest side of the image
+    pub detail: usize,
+    /// The name of the image being read, and its brightness once read
+    reading: Option<(String, Request<Result<Heightmap, image::ImageError>>)>,
+    request: Option<Request<Vec<Triangle>>>
+}
+
+impl HeightmapUI {
+    pub fn new() -> Self {
+        return Self {
+            image: None,
+            pixel_size: 0.1,
+            min_height: 0.8,
+            max_height: 3.,
+            invert: false,
+            detail: 500,
+            reading: None,
+            request: None
+        };
+    }
+
+    /// Starts reading an image in the background, opening the dialog once it is read
+    pub fn load(&mut self, ctx: &egui::Context, path: String) {
+        self.close();
+        let name = std::path::Path::new(&path).file_stem()
+            .map_or(path.clone(), |stem| stem.to_string_lossy().to_string());
+        self.reading = Some((name, Request::spawn(ctx, move |_| Heightmap::read(&path))));
+    }
+
+    pub fn close(&mut self) {
+        if let Some((_, request)) = &self.reading {
+            request.cancel();
+        }
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.image = None;
+        self.reading = None;
+        self.request = None;
+    }
+
+    /// Pixels averaged into each point along each side, to keep the detail asked for
+    fn step(&self, heightmap: &Heightmap) -> usize {
+        return heightmap.width.max(heightmap.height).div_ceil(self.detail.max(1)).max(1);
+    }
+
+    /// Shows the dialog if it is open
+    pub fn show(&mut self, ctx: &egui::Context, unit: Unit) -> HeightmapResponse {
+        if let Some(result) = self.reading.as_mut().and_then(|(_, request)| request.take()) {
+            let (name, _) = self.reading.take().unwrap();
+            match result {
+                Ok(heightmap) => self.image = Some((name, Arc::new(heightmap))),
+                Err(err) => return HeightmapResponse::Failed(name, err)
+            }
+        }
+        if let Some((name, _)) = &self.reading {
+            let mut open = true;
+            let mut cancel = false;
+            egui::Window::new(tr("heightmap"))
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(tr_args("heightmap-reading", &[("name", name)]));
+                });
+                cancel = ui.button(tr("heightmap-cancel")).clicked();
+            });
+            if cancel || !open {
+                self.close();
+            }
+            return HeightmapResponse::None;
+        }
+        let (name, heightmap) = match &self.image {
+            Some((name, heightmap)) => (name.clone(), heightmap.clone()),
+            None => return HeightmapResponse::None
+        };
+        if let Some(triangles) = self.request.as_mut().and_then(|request| request.take()) {
+            self.close();
+            return HeightmapResponse::Add(name, triangles);
+        }
+
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        let length = Quantity::Length(unit);
+        let step = self.step(&heightmap);
+        egui::Window::new(tr("heightmap"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.label(tr_args("heightmap-image", &[("name", &name), ("width", &heightmap.width), ("height", &heightmap.height)]));
+            ui.add_enabled_ui(self.request.is_none(), |ui| {
+                egui::Grid::new("heightmap_sizes").show(ui, |ui| {
+                    ui.label(tr("heightmap-pixel-size"));
+                    ui.add(NumericEdit::new("pixel_size", &mut self.pixel_size, length));
+                    ui.end_row();
+                    ui.label(tr("heightmap-min-height"));
+                    ui.add(NumericEdit::new("min_height", &mut self.min_height, length));
+                    ui.end_row();
+                    ui.label(tr("heightmap-max-height"));
+                    ui.add(NumericEdit::new("max_height", &mut self.max_height, length));
+                    ui.end_row();
+                });
+                ui.checkbox(&mut self.invert, tr("heightmap-invert")).on_hover_text(tr("heightmap-invert-hint"));
+                ui.add(egui::Slider::new(&mut self.detail, 50..=MAX_DETAIL).text(tr("heightmap-detail")));
+            });
+            let size = |pixels: usize| format!("{:.2} {}", pixels.saturating_sub(1) as f32 * self.pixel_size, unit.suffix());
+            ui.label(tr_args("heightmap-size", &[("width", &size(heightmap.width)), ("height", &size(heightmap.height))]));
+            let points = heightmap.width.div_ceil(step) * heightmap.height.div_ceil(step);
+            ui.label(tr_args("heightmap-triangles", &[("count", &(points * 2))]));
+            let valid = self.pixel_size > 0. && self.min_height > 0. && self.max_height >= self.min_height
+                && heightmap.width >= 2 && heightmap.height >= 2;
+            if !valid {
+                ui.label(tr("heightmap-invalid"));
+            }
+            if self.request.is_some() {
+                ui.spinner();
+            }
+            ui.horizontal(|ui| {
+                apply = ui.add_enabled(valid && self.request.is_none(), egui::Button::new(tr("heightmap-create"))).clicked();
+                cancel = ui.button(tr("heightmap-cancel")).clicked();
+            });
+        });
+
+        if cancel || !open {
+            self.close();
+        } else if apply {
+            let (pixel_size, min_height, max_height, invert) = (self.pixel_size * step as f32, self.min_height, self.max_height, self.invert);
+            self.request = Some(Request::spawn(ctx, move |_| {
+                return heightmap.downsampled(step).to_triangles(pixel_size, min_height, max_height, invert);
+            }));
+        }
+        return HeightmapResponse::None;
+    }
+}
@@ -0,0 +1,78 @@
+use crate::file_info::{self, FileInfo};
+use crate::i18n::{tr, tr_args};
+use crate::thread_request::Request;
+use crate::units::Unit;
+
+/// A window showing the statistics of a file, found by streaming through it
+/// rather than opening it in the view
+pub struct InfoUI {
+    /// The file being inspected, while the window is open
+    path: Option<String>,
+    request: Option<Request<Result<FileInfo, std::io::Error>>>,
+    info: Option<Result<FileInfo, String>>
+}
+
+impl InfoUI {
+    pub fn new() -> Self {
+        return Self { path: None, request: None, info: None };
+    }
+
+    /// Starts reading a file in the background, replacing any file shown before
+    pub fn inspect(&mut self, ctx: &egui::Context, path: String) {
+        self.close();
+        let file = path.clone();
+        self.request = Some(Request::spawn(ctx, move |progress| {
+            return file_info::file_info(&file, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            });
+        }));
+        self.path = Some(path);
+    }
+
+    pub fn close(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.path = None;
+        self.request = None;
+        self.info = None;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, unit: Unit) {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => return
+        };
+        if let Some(result) = self.request.as_mut().and_then(|request| request.take()) {
+            self.request = None;
+            self.info = Some(result.map_err(|err| err.to_string()));
+        }
+        let mut open = true;
+        let mut close = false;
+        egui::Window::new(tr("info-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.label(&path);
+            if let Some(request) = &self.request {
+                ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+            }
+            match &self.info {
+                Some(Ok(info)) => {
+                    for line in info.lines(unit.suffix()) {
+                        ui.label(line);
+                    }
+                },
+                Some(Err(err)) => {
+                    ui.label(tr_args("info-failed", &[("error", err)]));
+                },
+                None => {}
+            }
+            close = ui.button(tr("info-close")).clicked();
+        });
+        if close || !open {
+            self.close();
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+extern crate nalgebra_glm as glm;
+use glm::Mat4;
+
+use crate::i18n::{tr, tr_args};
+use crate::mesh_cache;
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::thread_request::Request;
+use crate::triangle::Triangle;
+use crate::triangle_mesh::TriangleMesh;
+use crate::units::Unit;
+
+/// How the size of an island is judged
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IslandSize {
+    Triangles,
+    Volume
+}
+
+impl IslandSize {
+    pub const ALL: [IslandSize; 2] = [IslandSize::Triangles, IslandSize::Volume];
+
+    pub fn label(&self) -> String {
+        match self {
+            IslandSize::Triangles => tr("islands-by-triangles"),
+            IslandSize::Volume => tr("islands-by-volume")
+        }
+    }
+}
+
+/// What the application should do after the islands dialog is shown
+pub enum IslandsResponse {
+    None,
+    /// Replace the model's triangles with these, after deleting this many islands
+    Apply(Vec<Triangle>, usize)
+}
+
+/// A welded mesh, with each of its bodies and their volumes
+type Bodies = (TriangleMesh, Vec<(Vec<usize>, f32)>);
+
+/// A dialog to delete the small pieces of a model that touch nothing else,
+/// like floating specks of scan noise
+pub struct IslandsUI {
+    /// Index of the model being cleaned up, while the dialog is open
+    pub model: Option<usize>,
+    pub size: IslandSize,
+    /// Islands with fewer triangles than this are deleted
+    pub min_triangles: usize,
+    /// Islands enclosing less than this volume in the scene are deleted
+    pub min_volume: f32,
+    /// The model's welded mesh, with each body and its volume in the model's own coordinates,
+    /// largest first. Found in the background when the dialog is first shown.
+    bodies: Option<Bodies>,
+    request: Option<Request<Bodies>>
+}
+
+impl IslandsUI {
+    pub fn new() -> Self {
+        return Self {
+            model: None,
+            size: IslandSize::Triangles,
+            min_triangles: 100,
+            min_volume: 1.,
+            bodies: None,
+            request: None
+        };
+    }
+
+    pub fn open(&mut self, model: usize) {
+        self.close();
+        self.model = Some(model);
+    }
+
+    pub fn close(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.model = None;
+        self.bodies = None;
+        self.request = None;
+    }
+
+    /// Welds the model's triangles in the background, then finds its bodies in the welded mesh
+    fn start(&mut self, ctx: &egui::Context, triangles: &Arc<Vec<Triangle>>, cache_key: Option<&str>) {
+        let triangles = triangles.clone();
+        let cache_key = cache_key.map(str::to_string);
+        let request = Request::spawn(ctx, move |_| mesh_cache::welded(cache_key.as_deref(), &triangles))
+            .then(|welded, _| {
+                let bodies = welded.bodies().into_iter()
+                    .map(|body| {
+                        let volume = welded.enclosed_volume(&body);
+                        (body, volume)
+                    })
+                    .collect();
+                return (welded, bodies);
+            });
+        self.request = Some(request);
+    }
+
+    /// Indices of the bodies below the threshold. The largest body is always kept.
+    fn small(&self, bodies: &[(Vec<usize>, f32)], volume_scale: f32) -> Vec<usize> {
+        return (1..bodies.len())
+            .filter(|i| match self.size {
+                IslandSize::Triangles => bodies[*i].0.len() < self.min_triangles,
+                IslandSize::Volume => bodies[*i].1 * volume_scale < self.min_volume
+            })
+            .collect();
+    }
+
+    /// Shows the dialog if it is open, given the triangles of the model, their key in the disk cache,
+    /// and the transformation placing them in the scene, which volumes are measured after
+    pub fn show(&mut self, ctx: &egui::Context, triangles: &Arc<Vec<Triangle>>, cache_key: Option<&str>,
+        placement: &Mat4, unit: Unit)
+    -> IslandsResponse {
+        if self.model.is_none() {
+            return IslandsResponse::None;
+        }
+        if self.bodies.is_none() && self.request.is_none() {
+            self.start(ctx, triangles, cache_key);
+        }
+        if let Some(bodies) = self.request.as_mut().and_then(|request| request.take()) {
+            self.bodies = Some(bodies);
+            self.request = None;
+        }
+        if self.bodies.is_none() {
+            let mut open = true;
+            let mut cancel = false;
+            egui::Window::new(tr("islands"))
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("islands-finding"));
+                    ui.spinner();
+                });
+                cancel = ui.button(tr("islands-cancel")).clicked();
+            });
+            if cancel || !open {
+                self.close();
+            }
+            return IslandsResponse::None;
+        }
+        let volume_scale = placement.fixed_slice::<3, 3>(0, 0).determinant().abs();
+        let (welded, bodies) = self.bodies.as_ref().unwrap();
+        let small = self.small(bodies, volume_scale);
+        let small_triangles: usize = small.iter().map(|i| bodies[*i].0.len()).sum();
+
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new(tr("islands"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.label(tr_args("islands-found", &[("count", &bodies.len())]));
+            ui.horizontal(|ui| {
+                for size in IslandSize::ALL {
+                    ui.radio_value(&mut self.size, size, size.label());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("islands-smaller-than"));
+                match self.size {
+                    IslandSize::Triangles => ui.add(egui::DragValue::new(&mut self.min_triangles)
+                        .speed(1)
+                        .clamp_range(1..=usize::MAX)
+                        .suffix(format!(" {}", tr("islands-triangles")))),
+                    IslandSize::Volume => ui.add(NumericEdit::new("min_volume", &mut self.min_volume, Quantity::Volume(unit))
+                        .speed(0.1))
+                };
+                self.min_volume = self.min_volume.max(0.);
+            });
+            ui.label(tr_args("islands-to-delete", &[("count", &small.len()), ("triangles", &small_triangles)]));
+            ui.horizontal(|ui| {
+                apply = ui.add_enabled(!small.is_empty(), egui::Button::new(tr("islands-delete"))).clicked();
+                cancel = ui.button(tr("islands-cancel")).clicked();
+            });
+        });
+
+        if apply {
+            let mut keep = vec![true; bodies.len()];
+            for i in &small {
+                keep[*i] = false;
+            }
+            let kept = bodies.iter().zip(keep)
+                .filter(|(_, keep)| *keep)
+                .flat_map(|((body, _), _)| body.iter().map(|t| welded.triangle(*t)))
+                .collect();
+            let deleted = small.len();
+            self.close();
+            return IslandsResponse::Apply(kept, deleted);
+        }
+        if cancel || !open {
+            self.close();
+        }
+        return IslandsResponse::None;
+    }
+}
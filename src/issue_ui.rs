@@ -0,0 +1,348 @@
+use std::sync::Arc;
+extern crate nalgebra_glm as glm;
+
+use crate::i18n::{tr, tr_args};
+use crate::issues::{self, Issue, IssueKind};
+use crate::mesh_widget::ViewState;
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::thread_request::Request;
+use crate::triangle::Triangle;
+use crate::mesh_cache;
+use crate::triangle_mesh::{self, CleanReport, EdgeReport, HoleFill, TriangleMesh};
+use crate::units::Unit;
+
+/// What the application should do after the issue list is shown
+pub enum IssueResponse {
+    None,
+    /// A fix was applied, giving new triangles for the mesh
+    Fixed(Vec<Triangle>),
+    /// The user asked to save the issue list
+    Export
+}
+
+fn issue_label(index: usize, issue: &Issue, unit: Unit) -> String {
+    let length = format!("{:.2} {}", issue.size, unit.suffix());
+    return match issue.kind {
+        IssueKind::Hole => tr_args("issues-hole-entry", &[
+            ("index", &index),
+            ("perimeter", &length),
+            ("area", &format!("{:.2} {}²", issue.area.unwrap_or(0.), unit.suffix()))]),
+        IssueKind::NonManifoldEdge => tr_args("issues-edge-entry", &[("index", &index), ("length", &length)]),
+        IssueKind::Intersection => tr_args("issues-intersection-entry", &[("index", &index)]),
+        IssueKind::ThinWall => tr_args("issues-thin-entry", &[("index", &index), ("thickness", &length)]),
+        IssueKind::FlippedNormals => tr_args("issues-flipped-entry", &[("index", &index), ("count", &issue.size)])
+    };
+}
+
+fn fix_label(kind: IssueKind) -> String {
+    match kind {
+        IssueKind::Hole => tr("issues-fill"),
+        IssueKind::FlippedNormals => tr("issues-flip"),
+        _ => tr("issues-remove")
+    }
+}
+
+/// What the analysis finds in a welded mesh, which can take a while for large meshes
+struct Analysis {
+    mesh: TriangleMesh,
+    issues: Vec<Issue>,
+    edges: EdgeReport
+}
+
+impl Analysis {
+    fn new(mesh: TriangleMesh, thin_wall_threshold: f32) -> Self {
+        let issues = issues::find_issues(&mesh, thin_wall_threshold);
+        let edges = mesh.edge_report();
+        return Self { mesh, issues, edges };
+    }
+}
+
+/// Most edges drawn by "Show edges", since each is drawn separately over the view
+const MAX_HIGHLIGHTED_EDGES: usize = 20_000;
+
+/// A panel listing the problems found in a mesh, grouped by kind.
+///
+/// Selecting an issue frames the view on it and highlights it.
+/// Issues with a fix can be fixed one at a time.
+pub struct IssueUI {
+    mesh: Option<TriangleMesh>,
+    pub issues: Vec<Issue>,
+    /// The edges keeping the mesh from being watertight
+    edges: Option<EdgeReport>,
+    pub selection: Option<usize>,
+    /// Walls thinner than this are reported as issues
+    pub thin_wall_threshold: f32,
+    /// Holes with a perimeter up to this are closed by "Fill holes"
+    pub fill_perimeter: f32,
+    pub hole_fill: HoleFill,
+    /// Vertices closer than this are merged by "Clean mesh". Taken from the mesh's size until set.
+    weld_tolerance: Option<f32>,
+    /// What the last "Clean mesh" removed
+    cleaned: Option<CleanReport>,
+    /// The mesh being cleaned in the background
+    clean_request: Option<Request<(TriangleMesh, CleanReport)>>,
+    /// The issues being found in the background
+    request: Option<Request<Analysis>>,
+    /// Smallest radius to frame an issue with, so tiny issues aren't magnified too far
+    min_radius: f32
+}
+
+impl IssueUI {
+    pub fn new() -> Self {
+        return Self {
+            mesh: None,
+            issues: Vec::new(),
+            edges: None,
+            selection: None,
+            thin_wall_threshold: 1.0,
+            fill_perimeter: 50.0,
+            hole_fill: HoleFill::Smooth,
+            weld_tolerance: None,
+            cleaned: None,
+            clean_request: None,
+            request: None,
+            min_radius: 0.
+        }
+    }
+
+    /// Forgets the detected issues, for when the triangles change
+    pub fn clear(&mut self, view: &mut ViewState) {
+        self.mesh = None;
+        self.issues.clear();
+        self.edges = None;
+        self.weld_tolerance = None;
+        self.cleaned = None;
+        if let Some(request) = self.clean_request.take() {
+            request.cancel();
+        }
+        if let Some(request) = self.request.take() {
+            request.cancel();
+        }
+        self.select(None, view);
+    }
+
+    fn analyze(&mut self, mesh: TriangleMesh) {
+        self.apply(Analysis::new(mesh, self.thin_wall_threshold));
+    }
+
+    fn apply(&mut self, analysis: Analysis) {
+        let Analysis { mesh, issues, edges } = analysis;
+        self.issues = issues;
+        self.edges = Some(edges);
+        let (min, max) = mesh.vertices.iter().fold(
+            (glm::Vec3::repeat(f32::MAX), glm::Vec3::repeat(f32::MIN)),
+            |(min, max), v| (glm::min2(&min, v), glm::max2(&max, v)));
+        self.min_radius = if mesh.vertices.is_empty() { 0. } else { (max - min).norm() * 0.01 };
+        self.mesh = Some(mesh);
+    }
+
+    /// Starts finding the issues in a list of triangles in the background. They replace any found before
+    /// once the panel is shown. The welded mesh is reused from the disk cache when there is a key for it.
+    pub fn analyze_triangles(&mut self, ctx: &egui::Context, triangles: &Arc<Vec<Triangle>>, cache_key: Option<&str>) {
+        if let Some(request) = self.request.take() {
+            request.cancel();
+        }
+        let triangles = triangles.clone();
+        let cache_key = cache_key.map(str::to_string);
+        let thin_wall_threshold = self.thin_wall_threshold;
+        self.request = Some(Request::spawn(ctx, move |_| {
+            return Analysis::new(mesh_cache::welded(cache_key.as_deref(), &triangles), thin_wall_threshold);
+        }));
+    }
+
+    fn select(&mut self, selection: Option<usize>, view: &mut ViewState) {
+        self.selection = selection;
+        view.highlights.clear();
+        if let Some(issue) = selection.and_then(|i| self.issues.get(i)) {
+            view.highlights.extend(issue.outlines.iter().cloned());
+            view.frame(issue.center, issue.radius.max(self.min_radius));
+        }
+    }
+
+    /// Highlights every edge keeping the mesh from being watertight, in place of the selected issue
+    fn show_edges(&mut self, view: &mut ViewState) {
+        self.select(None, view);
+        let (mesh, edges) = match (&self.mesh, &self.edges) {
+            (Some(mesh), Some(edges)) => (mesh, edges),
+            _ => return
+        };
+        let all = edges.boundary.iter().chain(&edges.non_manifold).chain(&edges.misoriented);
+        view.highlights.extend(all
+            .take(MAX_HIGHLIGHTED_EDGES)
+            .map(|(a, b)| vec![mesh.vertices[*a], mesh.vertices[*b]]));
+    }
+
+    /// Shows the weld tolerance and the button to clean the mesh, with what the last clean removed.
+    /// The mesh is cleaned in the background, and the cleaned triangles are returned once they are ready.
+    fn clean_ui(&mut self, ui: &mut egui::Ui, triangles: &Arc<Vec<Triangle>>, unit: Unit, view: &mut ViewState)
+    -> Option<Vec<Triangle>> {
+        if let Some((mesh, report)) = self.clean_request.as_mut().and_then(|request| request.take()) {
+            self.clean_request = None;
+            let cleaned = mesh.to_triangles();
+            // The issues found before refer to the old triangles
+            if self.mesh.is_some() {
+                self.analyze(mesh);
+            }
+            self.select(None, view);
+            self.cleaned = Some(report);
+            return Some(cleaned);
+        }
+        let tolerance = self.weld_tolerance.get_or_insert_with(|| triangle_mesh::default_tolerance(triangles));
+        // Dragging changes the tolerance in proportion to its size, since it can be any order of magnitude
+        let speed = tolerance.max(f32::EPSILON) as f64 * 0.1;
+        let mut clean = false;
+        ui.horizontal(|ui| {
+            let button = egui::Button::new(tr("issues-clean"));
+            clean = ui.add_enabled(self.clean_request.is_none(), button).on_hover_text(tr("issues-clean-hint")).clicked();
+            ui.label(tr("issues-weld-tolerance"));
+            ui.add(NumericEdit::new("weld_tolerance", tolerance, Quantity::Length(unit)).speed(speed));
+            *tolerance = tolerance.max(0.);
+            if self.clean_request.is_some() {
+                ui.spinner();
+            }
+        });
+        if let Some(report) = &self.cleaned {
+            ui.label(tr_args("issues-cleaned", &[
+                ("triangles_before", &report.triangles_before),
+                ("triangles_after", &report.triangles_after),
+                ("vertices_before", &report.vertices_before),
+                ("vertices_after", &report.vertices_after),
+                ("degenerate", &report.degenerate),
+                ("duplicates", &report.duplicates),
+                ("unreferenced", &report.unreferenced)]));
+            if report.thin > 0 {
+                ui.label(tr_args("issues-cleaned-thin", &[("thin", &report.thin)]))
+                    .on_hover_text(tr("issues-cleaned-thin-hint"));
+            }
+        }
+        if clean {
+            let (triangles, tolerance) = (triangles.clone(), tolerance.max(f32::EPSILON));
+            self.clean_request = Some(Request::spawn(ui.ctx(), move |_| TriangleMesh::clean(&triangles, tolerance)));
+        }
+        return None;
+    }
+
+    /// Shows the issue list, with controls to run the analysis and export the list
+    pub fn ui(&mut self, ui: &mut egui::Ui, triangles: &Arc<Vec<Triangle>>, cache_key: Option<&str>, view: &mut ViewState, unit: Unit)
+    -> IssueResponse {
+        ui.horizontal(|ui| {
+            ui.label(tr("issues-thin-threshold"));
+            ui.add(NumericEdit::new("thin_wall_threshold", &mut self.thin_wall_threshold, Quantity::Length(unit)));
+        });
+        if let Some(analysis) = self.request.as_mut().and_then(|request| request.take()) {
+            self.request = None;
+            self.apply(analysis);
+            self.select(None, view);
+        }
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.request.is_none(), egui::Button::new(tr("issues-analyze"))).clicked() {
+                self.analyze_triangles(ui.ctx(), triangles, cache_key);
+            }
+            if self.request.is_some() {
+                ui.spinner();
+            }
+        });
+        if let Some(triangles) = self.clean_ui(ui, triangles, unit, view) {
+            return IssueResponse::Fixed(triangles);
+        }
+        if self.mesh.is_none() {
+            return IssueResponse::None;
+        }
+        let mut export = false;
+        ui.horizontal(|ui| {
+            ui.label(tr_args("issues-count", &[("count", &self.issues.len())]));
+            export = ui.button(tr("issues-export")).clicked();
+        });
+        let mut show_edges = false;
+        if let Some(edges) = &self.edges {
+            if edges.is_closed() {
+                ui.label(tr("issues-watertight"));
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label(tr_args("issues-not-watertight", &[
+                        ("open", &edges.boundary.len()),
+                        ("non_manifold", &edges.non_manifold.len()),
+                        ("misoriented", &edges.misoriented.len())]));
+                    show_edges = ui.button(tr("issues-show-edges")).clicked();
+                });
+            }
+        }
+        let mut fill_holes = false;
+        let hole_count = self.issues.iter().filter(|issue| issue.kind == IssueKind::Hole).count();
+        if hole_count > 0 {
+            ui.horizontal(|ui| {
+                let fillable = self.issues.iter()
+                    .filter(|issue| issue.kind == IssueKind::Hole && issue.size <= self.fill_perimeter)
+                    .count();
+                fill_holes = ui.add_enabled(fillable > 0, egui::Button::new(tr_args("issues-fill-holes", &[("count", &fillable)])))
+                    .on_hover_text(tr("issues-fill-holes-hint"))
+                    .clicked();
+                ui.label(tr("issues-fill-perimeter"));
+                ui.add(NumericEdit::new("fill_perimeter", &mut self.fill_perimeter, Quantity::Length(unit)));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("issues-fill-mode"));
+                ui.radio_value(&mut self.hole_fill, HoleFill::Flat, tr("issues-fill-flat"))
+                    .on_hover_text(tr("issues-fill-flat-hint"));
+                ui.radio_value(&mut self.hole_fill, HoleFill::Smooth, tr("issues-fill-smooth"))
+                    .on_hover_text(tr("issues-fill-smooth-hint"));
+            });
+        }
+
+        let mut selection = self.selection;
+        let mut fixed = None;
+        egui::ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+            for kind in IssueKind::ALL {
+                let count = self.issues.iter().filter(|issue| issue.kind == kind).count();
+                if count == 0 {
+                    continue;
+                }
+                let header = format!("{} ({})", kind.label(), count);
+                egui::CollapsingHeader::new(header).id_source(kind.code()).show(ui, |ui| {
+                    let entries = self.issues.iter().enumerate().filter(|(_, issue)| issue.kind == kind);
+                    for (number, (i, issue)) in entries.enumerate() {
+                        ui.horizontal(|ui| {
+                            let label = issue_label(number + 1, issue, unit);
+                            if ui.selectable_label(self.selection == Some(i), label).clicked() {
+                                selection = if self.selection == Some(i) { None } else { Some(i) };
+                            }
+                            if issue.fix.is_some() && ui.button(fix_label(kind)).clicked() {
+                                fixed = Some(i);
+                            }
+                        });
+                    }
+                });
+            }
+        });
+
+        let fixes: Vec<usize> = if fill_holes {
+            // Filling only adds vertices and triangles, so every hole can be filled in one go
+            (0..self.issues.len())
+                .filter(|i| self.issues[*i].kind == IssueKind::Hole && self.issues[*i].size <= self.fill_perimeter)
+                .collect()
+        } else {
+            fixed.into_iter().collect()
+        };
+        if !fixes.is_empty() && self.mesh.is_some() {
+            let mut mesh = self.mesh.take().unwrap();
+            for i in fixes {
+                self.issues[i].apply_fix(&mut mesh, self.hole_fill);
+            }
+            let triangles = mesh.to_triangles();
+            // Fixes can renumber triangles, so the other issues have to be found again
+            self.analyze(mesh);
+            self.select(None, view);
+            return IssueResponse::Fixed(triangles);
+        }
+        if selection != self.selection {
+            self.select(selection, view);
+        }
+        if show_edges {
+            self.show_edges(view);
+        }
+        if export {
+            return IssueResponse::Export;
+        }
+        return IssueResponse::None;
+    }
+}
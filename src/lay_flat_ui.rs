@@ -0,0 +1,130 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::i18n::{tr, tr_args};
+use crate::mesh_widget::Hit;
+use crate::transformation_ui::TransformationUI;
+use crate::triangle::{self, Triangle};
+
+/// How the face laid on the bed is picked
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LayFlatMode {
+    /// The face of the triangle clicked
+    Face,
+    /// The plane through three clicked points
+    Points
+}
+
+/// The rotation turning a face with this outward normal to face straight down, as an axis and an angle in degrees
+fn face_down(normal: &Vec3) -> (Vec3, f32) {
+    let down = -Vec3::z();
+    let normal = normal.normalize();
+    let degrees = normal.dot(&down).clamp(-1., 1.).acos().to_degrees();
+    return match normal.cross(&down).try_normalize(1e-6) {
+        Some(axis) => (axis, degrees),
+        // Facing straight up or down already, so any level axis turns it
+        None => (Vec3::x(), degrees)
+    };
+}
+
+/// A dialog to turn a model so a face picked in the view lies on the bed, at Z = 0,
+/// as a model is usually laid before it is printed
+pub struct LayFlatUI {
+    /// Index of the model being laid flat, while the dialog is open
+    pub model: Option<usize>,
+    pub mode: LayFlatMode,
+    /// The points clicked so far, in the scene
+    points: Vec<Vec3>
+}
+
+impl LayFlatUI {
+    pub fn new() -> Self {
+        return Self { model: None, mode: LayFlatMode::Face, points: Vec::new() };
+    }
+
+    pub fn open(&mut self, model: usize) {
+        self.close();
+        self.model = Some(model);
+    }
+
+    pub fn close(&mut self) {
+        self.model = None;
+        self.points.clear();
+    }
+
+    /// Picks a face or point where the model was clicked, and once a face is picked,
+    /// adds the steps laying it on the bed to the model's transformations
+    pub fn pick(&mut self, hit: &Hit, triangles: &[Triangle], transformation_ui: &mut TransformationUI) {
+        if self.model != Some(hit.model) || hit.triangle >= triangles.len() {
+            return;
+        }
+        let placement = transformation_ui.get_matrix();
+        let (normal, point) = match self.mode {
+            LayFlatMode::Face => {
+                let [a, b, c] = triangle::transform(&triangles[hit.triangle], &placement);
+                (glm::cross(&(b - a), &(c - a)), hit.point)
+            },
+            LayFlatMode::Points => {
+                self.points.push(hit.point);
+                if self.points.len() < 3 {
+                    return;
+                }
+                let [a, b, c] = [self.points[0], self.points[1], self.points[2]];
+                self.points.clear();
+                let center = (a + b + c) / 3.;
+                let normal = glm::cross(&(b - a), &(c - a));
+                // The points don't say which side is outside, so the plane faces away from the model
+                let middle = match triangle::transformed_bounds(triangles, &placement) {
+                    Some((min, max)) => (min + max) / 2.,
+                    None => center
+                };
+                (if normal.dot(&(center - middle)) < 0. { -normal } else { normal }, center)
+            }
+        };
+        if normal.norm() < f32::EPSILON {
+            return;
+        }
+        let (axis, degrees) = face_down(&normal);
+        let rotation = Mat4::new_rotation(axis * degrees.to_radians());
+        let turned = rotation.transform_point(&point.into()).coords;
+        if degrees.abs() > 1e-3 {
+            transformation_ui.rotate(axis, degrees);
+        }
+        // The picked point stays over the same spot of the bed
+        transformation_ui.translate(Vec3::new(point.x - turned.x, point.y - turned.y, -turned.z));
+    }
+
+    /// Lines joining the points clicked so far
+    pub fn outlines(&self) -> Vec<Vec<Vec3>> {
+        if self.model.is_none() || self.points.len() < 2 {
+            return Vec::new();
+        }
+        return vec![self.points.clone()];
+    }
+
+    /// Shows the dialog if it is open
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.model.is_none() {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new(tr("lay-flat"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.radio_value(&mut self.mode, LayFlatMode::Face, tr("lay-flat-face")).clicked() {
+                    self.points.clear();
+                }
+                ui.radio_value(&mut self.mode, LayFlatMode::Points, tr("lay-flat-points"));
+            });
+            match self.mode {
+                LayFlatMode::Face => ui.label(tr("lay-flat-face-hint")),
+                LayFlatMode::Points => ui.label(tr_args("lay-flat-points-hint", &[("count", &self.points.len())]))
+            };
+        });
+        if !open {
+            self.close();
+        }
+    }
+}
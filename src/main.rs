@@ -6,12 +6,53 @@ use std::sync::{Arc, Mutex};
 use eframe;
 use eframe::glow;
 use egui::{TextureHandle, Ui};
+use i18n::{tr, tr_args};
 use mesh_widget::*;
-use transformation_ui::TransformationUI;
+use meshtools_core::{annotation, build_volume, bvh, curvature, decimate, deviation, extrude, file_info, fitting, gltf, heightmap, i18n,
+    issues, json, mass_properties, measurement, mesh_cache, mesh_diff, mesh_file, morph, offset, oriented_box, overhang,
+    plane_cut, registration, shapes, simplify, slice_export, spatial_grid, thickness, triangle, triangle_mesh, units, version};
+#[macro_use]
+mod profiling;
 mod mesh_widget;
 extern crate nalgebra_glm as glm;
-mod triangle;
 mod transformation_ui;
+use transformation_ui::TransformationUI;
+mod settings;
+mod theme;
+mod numeric_input;
+use numeric_input::{NumericEdit, Quantity};
+mod issue_ui;
+mod thread_request;
+mod recent_files;
+mod scene;
+mod auto_run;
+mod simplify_ui;
+mod printer;
+mod info_ui;
+mod cut_ui;
+mod screenshot_ui;
+mod section_ui;
+mod slice_ui;
+mod offset_ui;
+mod morph_ui;
+mod measurement_ui;
+mod annotation_ui;
+mod islands_ui;
+mod select_ui;
+mod deviation_ui;
+mod fit_ui;
+mod lay_flat_ui;
+mod align_ui;
+mod shape_ui;
+mod mold_box_ui;
+mod extrude_ui;
+mod heightmap_ui;
+mod thumbnail;
+mod watch_folder;
+mod update_check;
+mod point_cloud_ui;
+mod view_toolbar;
+mod analysis_ui;
 
 macro_rules! unwrap_or_return {
     ( $e:expr ) => {
@@ -25,58 +66,553 @@ macro_rules! unwrap_or_return {
 struct AppState {
     gl: Arc<glow::Context>,
     alert: Option<Arc<Mutex<String>>>,
-    triangles: Option<Vec<Triangle>>,
+    /// The loaded models, in the same order as the models of the view
+    scene: Vec<scene::SceneModel>,
     mesh: Option<ViewState>,
     texture: Option<TextureHandle>,
-    transformation_ui: transformation_ui::TransformationUI
+    issue_ui: issue_ui::IssueUI,
+    scene_ui: scene::SceneUI,
+    simplify_ui: simplify_ui::SimplifyUI,
+    cut_ui: cut_ui::CutUI,
+    section_ui: section_ui::SectionUI,
+    screenshot_ui: screenshot_ui::ScreenshotUI,
+    slice_ui: slice_ui::SliceUI,
+    offset_ui: offset_ui::OffsetUI,
+    morph_ui: morph_ui::MorphUI,
+    measurement_ui: measurement_ui::MeasurementUI,
+    annotation_ui: annotation_ui::AnnotationUI,
+    turntable: view_toolbar::Turntable,
+    /// Viewpoints saved from the view toolbar, with their names
+    saved_views: Vec<(String, Camera)>,
+    islands_ui: islands_ui::IslandsUI,
+    select_ui: select_ui::SelectUI,
+    deviation_ui: deviation_ui::DeviationUI,
+    fit_ui: fit_ui::FitUI,
+    lay_flat_ui: lay_flat_ui::LayFlatUI,
+    align_ui: align_ui::AlignUI,
+    shape_ui: shape_ui::ShapeUI,
+    mold_box_ui: mold_box_ui::MoldBoxUI,
+    extrude_ui: extrude_ui::ExtrudeUI,
+    heightmap_ui: heightmap_ui::HeightmapUI,
+    point_cloud_ui: point_cloud_ui::PointCloudUI,
+    printer_ui: printer::PrinterUI,
+    info_ui: info_ui::InfoUI,
+    analysis_ui: analysis_ui::AnalysisUI,
+    settings: settings::Settings,
+    recent_files: recent_files::RecentFiles,
+    /// Path of the file that was opened, rather than added to the scene
+    current_file: Option<String>,
+    /// The point on the mesh under the cursor
+    cursor: Option<glm::Vec3>,
+    /// The model and triangle under the cursor
+    hover: Option<Hit>,
+    /// Save transformed meshes as they are seen, with the view's rotation
+    bake_view_rotation: bool,
+    loading: Option<Loading>,
+    decimate_prompt: Option<DecimatePrompt>,
+    /// The latest release, being asked for at startup
+    update_check: Option<thread_request::Request<Result<String, std::io::Error>>>,
+    #[cfg(feature = "profiling")]
+    timing_ui: profiling::TimingUI
+}
+
+/// A mesh file being read, or decimated, in the background
+struct Loading {
+    path: String,
+    /// Add the mesh to the scene instead of replacing it
+    add: bool,
+    /// The result is a decimated preview of the file
+    decimated: bool,
+    request: thread_request::Request<Result<Loaded, std::io::Error>>
+}
+
+/// Triangles read from a file, with the key of the data derived from them in the disk cache
+struct Loaded {
+    triangles: Vec<Triangle>,
+    cache_key: Option<String>
+}
+
+/// A large mesh waiting for the user to choose whether to decimate it
+struct DecimatePrompt {
+    path: String,
+    add: bool,
+    loaded: Loaded
 }
 
 fn new_alert(alert: String) -> Option<Arc<Mutex<String>>> {
     Some(Arc::new(Mutex::new(alert)))
 }
 
+/// A file dialog filtering for these lowercase extensions and their uppercase forms,
+/// since some platforms match extensions case sensitively
+fn file_dialog(name: &str, extensions: &[&str]) -> rfd::FileDialog {
+    let upper: Vec<String> = extensions.iter().map(|extension| extension.to_uppercase()).collect();
+    let all: Vec<&str> = extensions.iter().copied().chain(upper.iter().map(String::as_str)).collect();
+    return rfd::FileDialog::new().add_filter(name, &all);
+}
+
 impl eframe::App for AppState {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.settings.save(storage);
+        self.remember_camera();
+        self.recent_files.save(storage);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.finish_loading(ctx);
+        self.recover_lost_context();
+        self.continue_upload(ctx);
+        self.continue_smoothing(ctx);
+        self.continue_reducing(ctx);
+        for model in &mut self.scene {
+            model.finish_bvh();
+        }
+        self.finish_update_check();
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            self.show_status(ui);
+        });
         egui::CentralPanel::default().show(ctx, |ui| {
-            let mut render_flag = false;
+            let mut depth_flag = false;
             egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Open").clicked() {
-                        self.open_mesh_file();
+                ui.menu_button(tr("menu-file"), |ui| {
+                    if ui.add_enabled(!self.busy(), egui::Button::new(tr("menu-open"))).clicked() {
+                        self.open_mesh_file(ctx, false);
+                    }
+                    if ui.add_enabled(!self.busy(), egui::Button::new(tr("menu-create-shape")))
+                        .on_hover_text(tr("menu-create-shape-hint"))
+                        .clicked() {
+                        self.shape_ui.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(!self.busy(), egui::Button::new(tr("menu-import-heightmap")))
+                        .on_hover_text(tr("menu-import-heightmap-hint"))
+                        .clicked() {
+                        self.import_heightmap(ctx);
+                        ui.close_menu();
                     }
+                    if ui.add_enabled(!self.busy(), egui::Button::new(tr("menu-import-point-cloud")))
+                        .on_hover_text(tr("menu-import-point-cloud-hint"))
+                        .clicked() {
+                        if let Some(path) = file_dialog("points", &mesh_file::POINT_CLOUD_EXTENSIONS).pick_file() {
+                            self.point_cloud_ui.load(ctx, path.display().to_string());
+                        }
+                        ui.close_menu();
+                    }
+                    let has_clouds = self.mesh.as_ref().is_some_and(|mesh| !mesh.point_clouds.is_empty());
+                    if ui.add_enabled(has_clouds, egui::Button::new(tr("menu-point-clouds"))).clicked() {
+                        self.point_cloud_ui.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("menu-quick-info")).on_hover_text(tr("menu-quick-info-hint")).clicked() {
+                        if let Some(path) = file_dialog("mesh", &mesh_file::SUPPORTED_EXTENSIONS).pick_file() {
+                            self.info_ui.inspect(ctx, path.display().to_string());
+                        }
+                        ui.close_menu();
+                    }
+                    ui.add_enabled_ui(!self.busy() && !self.recent_files.files.is_empty(), |ui| {
+                        ui.menu_button(tr("menu-recent"), |ui| {
+                            let mut reopen = None;
+                            for file in &self.recent_files.files {
+                                if ui.button(file.name()).on_hover_text(&file.path).clicked() {
+                                    reopen = Some(file.path.clone());
+                                }
+                            }
+                            if let Some(path) = reopen {
+                                self.load_mesh_file(ctx, path, false);
+                                ui.close_menu();
+                            }
+                        });
+                    });
                     if self.mesh.is_some() {
-                        if ui.button("Save").clicked() {
+                        if ui.button(tr("menu-save")).clicked() {
                             self.save_mesh_file_menu();
                         }
-                        if ui.button("Save Render").clicked() {
-                            render_flag = true;
+                        if ui.button(tr("menu-save-each")).on_hover_text(tr("menu-save-each-hint")).clicked() {
+                            self.save_each_model();
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("menu-export-visible")).on_hover_text(tr("menu-export-visible-hint")).clicked() {
+                            self.export_visible_models();
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-save-screenshot"))).clicked() {
+                            self.screenshot_ui.open = true;
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("menu-save-depth")).on_hover_text(tr("menu-save-depth-hint")).clicked() {
+                            depth_flag = true;
+                        }
+                        if ui.button(tr("menu-export-gltf")).clicked() {
+                            self.export_gltf();
+                        }
+                    }
+                });
+                ui.menu_button(tr("menu-operations"), |ui| {
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-simplify"))).clicked() {
+                        self.cancel_simplify();
+                        self.cut_ui.close();
+                        self.offset_ui.close();
+                        self.islands_ui.close();
+                        self.simplify_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-cut"))).clicked() {
+                        self.cancel_simplify();
+                        self.offset_ui.close();
+                        self.islands_ui.close();
+                        let selected = self.scene_ui.selected;
+                        if let Some(model) = self.scene.get_mut(selected) {
+                            let bounds = model.transformed_bounds(&model.transformation_ui.get_matrix());
+                            self.cut_ui.open(selected, bounds);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-slice")))
+                        .on_hover_text(tr("menu-slice-hint"))
+                        .clicked() {
+                        let selected = self.scene_ui.selected;
+                        if let Some(model) = self.scene.get_mut(selected) {
+                            let bounds = model.transformed_bounds(&model.transformation_ui.get_matrix());
+                            self.slice_ui.open(selected, bounds);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-offset"))).clicked() {
+                        self.cancel_simplify();
+                        self.cut_ui.close();
+                        self.islands_ui.close();
+                        self.offset_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-extrude")))
+                        .on_hover_text(tr("menu-extrude-hint"))
+                        .clicked() {
+                        self.extrude_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.scene.len() >= 2, egui::Button::new(tr("menu-morph")))
+                        .on_hover_text(tr("menu-morph-hint"))
+                        .clicked() {
+                        self.morph_ui.open(self.scene_ui.selected, self.scene.len());
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.scene.len() >= 2, egui::Button::new(tr("menu-deviation")))
+                        .on_hover_text(tr("menu-deviation-hint"))
+                        .clicked() {
+                        self.clear_deviation_colors();
+                        self.deviation_ui.open(self.scene_ui.selected, self.scene.len());
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-separate-bodies")))
+                        .on_hover_text(tr("menu-separate-bodies-hint"))
+                        .clicked() {
+                        self.separate_bodies(ctx);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-islands")))
+                        .on_hover_text(tr("menu-islands-hint"))
+                        .clicked() {
+                        self.cancel_simplify();
+                        self.cut_ui.close();
+                        self.offset_ui.close();
+                        self.islands_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-select")))
+                        .on_hover_text(tr("menu-select-hint"))
+                        .clicked() {
+                        self.cancel_simplify();
+                        self.cut_ui.close();
+                        self.offset_ui.close();
+                        self.islands_ui.close();
+                        self.clear_selection();
+                        self.select_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-measure")))
+                        .on_hover_text(tr("menu-measure-hint"))
+                        .clicked() {
+                        self.measurement_ui.open = true;
+                        self.measurement_ui.restart();
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-annotate")))
+                        .on_hover_text(tr("menu-annotate-hint"))
+                        .clicked() {
+                        self.annotation_ui.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-section")))
+                        .on_hover_text(tr("menu-section-hint"))
+                        .clicked() {
+                        self.section_ui.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-fit")))
+                        .on_hover_text(tr("menu-fit-hint"))
+                        .clicked() {
+                        self.fit_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-lay-flat")))
+                        .on_hover_text(tr("menu-lay-flat-hint"))
+                        .clicked() {
+                        self.lay_flat_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.scene.len() >= 2, egui::Button::new(tr("menu-align")))
+                        .on_hover_text(tr("menu-align-hint"))
+                        .clicked() {
+                        self.align_ui.open(self.scene_ui.selected, self.scene.len());
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.mesh.is_some(), egui::Button::new(tr("menu-mold-box")))
+                        .on_hover_text(tr("menu-mold-box-hint"))
+                        .clicked() {
+                        self.mold_box_ui.open(self.scene_ui.selected);
+                        ui.close_menu();
+                    }
+                    ui.add_enabled_ui(self.mesh.is_some(), |ui| {
+                        ui.menu_button(tr("menu-duplicate-mirror"), |ui| {
+                            for (axis, name) in ["X", "Y", "Z"].iter().enumerate() {
+                                if ui.button(tr_args("menu-mirror-axis", &[("axis", name)])).clicked() {
+                                    self.duplicate_mirrored(ctx, axis);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                });
+                ui.menu_button(tr("menu-settings"), |ui| {
+                    ui.menu_button(tr("menu-language"), |ui| {
+                        for language in i18n::Language::ALL {
+                            if ui.radio_value(&mut self.settings.language, language, language.native_name()).clicked() {
+                                i18n::set_language(language);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.menu_button(tr("menu-theme"), |ui| {
+                        for theme in theme::Theme::ALL {
+                            if ui.radio_value(&mut self.settings.theme, theme, theme.label()).clicked() {
+                                self.apply_theme(ctx);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.menu_button(tr("menu-units"), |ui| {
+                        for unit in units::Unit::ALL {
+                            if ui.radio_value(&mut self.settings.unit, unit, unit.label()).clicked() {
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut self.settings.gpu_picking, tr("menu-gpu-picking"));
+                    ui.checkbox(&mut self.settings.reduce_detail, tr("menu-reduce-detail"))
+                        .on_hover_text(tr("menu-reduce-detail-hint"));
+                    ui.menu_button(tr("menu-antialiasing"), |ui| {
+                        ui.radio_value(&mut self.settings.antialiasing, 1, tr("menu-antialiasing-off"));
+                        for samples in [2, 4, 8] {
+                            ui.radio_value(&mut self.settings.antialiasing, samples,
+                                tr_args("menu-antialiasing-samples", &[("samples", &samples)]));
+                        }
+                    }).response.on_hover_text(tr("menu-antialiasing-hint"));
+                    ui.menu_button(tr("menu-decimate"), |ui| {
+                        for choice in decimate::AutoDecimate::ALL {
+                            ui.radio_value(&mut self.settings.auto_decimate, choice, choice.label());
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(tr("decimate-threshold"));
+                            ui.add(egui::DragValue::new(&mut self.settings.decimate_threshold)
+                                .speed(10000)
+                                .clamp_range(1000..=usize::MAX));
+                        });
+                    });
+                    ui.menu_button(tr("menu-auto-run"), |ui| {
+                        ui.label(tr("auto-run-bvh"));
+                        for choice in auto_run::AutoRun::ALL {
+                            ui.radio_value(&mut self.settings.auto_bvh, choice, choice.label());
+                        }
+                        ui.separator();
+                        ui.label(tr("auto-run-issues"));
+                        for choice in auto_run::AutoRun::ALL {
+                            ui.radio_value(&mut self.settings.auto_issues, choice, choice.label());
+                        }
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label(tr("auto-run-threshold"));
+                            ui.add(egui::DragValue::new(&mut self.settings.auto_run_threshold)
+                                .speed(10000)
+                                .clamp_range(0..=usize::MAX));
+                        });
+                    });
+                    ui.menu_button(tr("menu-printer"), |ui| {
+                        for i in 0..self.settings.printers.len() {
+                            let name = self.settings.printers[i].name.clone();
+                            if ui.radio_value(&mut self.settings.printer, i, name).clicked() {
+                                self.apply_printer();
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        ui.checkbox(&mut self.settings.check_build_volume, tr("menu-build-volume-check"));
+                        if ui.button(tr("menu-edit-printers")).clicked() {
+                            self.printer_ui.open = true;
+                            ui.close_menu();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.settings.disk_cache, tr("menu-disk-cache"))
+                            .on_hover_text(tr("menu-disk-cache-hint"));
+                        if ui.button(tr("menu-clear-cache")).clicked() {
+                            if let Err(err) = mesh_cache::clear() {
+                                self.alert = new_alert(tr_args("alert-clear-cache-failed", &[("error", &err)]));
+                            }
+                            ui.close_menu();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("menu-update-url"));
+                        ui.text_edit_singleline(&mut self.settings.update_url)
+                            .on_hover_text(tr("menu-update-url-hint"));
+                    });
+                    if !self.settings.update_url.is_empty() {
+                        if let Err(err) = update_check::check_url(&self.settings.update_url) {
+                            ui.colored_label(ui.visuals().error_fg_color, tr_args("menu-update-url-invalid", &[("error", &err)]));
                         }
                     }
+                    ui.separator();
+                    ui.label(version::description());
                 });
+                #[cfg(feature = "profiling")]
+                if ui.button(tr("timings")).clicked() {
+                    self.timing_ui.open = !self.timing_ui.open;
+                }
             });
 
+            if self.mesh.is_some() {
+                let toolbar = match view_toolbar::show(ui, &mut self.turntable, &mut self.saved_views) {
+                    view_toolbar::ToolbarResponse::None => view_toolbar::shortcut(ctx),
+                    response => response
+                };
+                self.apply_toolbar(toolbar);
+                self.turntable.advance(ctx, self.mesh.as_mut().unwrap());
+                self.update_grid();
+            }
+            self.update_warnings();
+            let mut view_response = None;
             ui.horizontal_centered(|ui| {
                 self.show_controls(ui);
                 let size = egui::Vec2::new(ui.available_width(), ui.available_height());
-                if render_flag {
-                    self.save_render(size.x as usize, size.y as usize);
+                let (width, height, transparent) =
+                    (self.screenshot_ui.width, self.screenshot_ui.height, self.screenshot_ui.transparent);
+                match self.screenshot_ui.show(ui.ctx(), size) {
+                    screenshot_ui::ScreenshotResponse::None => {},
+                    screenshot_ui::ScreenshotResponse::Save => self.save_render(width, height, transparent),
+                    screenshot_ui::ScreenshotResponse::SaveTurntable => self.save_turntable(width, height, transparent)
+                }
+                if depth_flag {
+                    self.save_depth(size.x as usize, size.y as usize);
                 }
                 if let Some(mesh) = &mut self.mesh {
-                    if self.transformation_ui.transformations.len() > 0 {
-                        if mesh.models.len() == 1 {
-                            mesh.models.push(mesh.models[0].clone());
-                        }
-                        if mesh.models.len() > 1 {
-                            mesh.models[1].1 = self.transformation_ui.get_matrix();
-                        }
-                    } else if mesh.models.len() > 1{
-                        mesh.models.pop();
+                    // Each model is drawn with its transformations as they are edited
+                    for (model, view_model) in self.scene.iter().zip(mesh.models.iter_mut()) {
+                        view_model.local_transform = model.transformation_ui.get_matrix();
+                    }
+                    mesh.samples = self.settings.antialiasing;
+                    mesh.reduce_detail = self.settings.reduce_detail;
+                    let response = ui.add(mesh_widget::mesh_view(size, mesh));
+                    if mesh.exaggerated() {
+                        let e = mesh.exaggeration;
+                        ui.painter().text(
+                            response.rect.left_top() + egui::vec2(8., 8.),
+                            egui::Align2::LEFT_TOP,
+                            tr_args("view-exaggerated", &[
+                                ("x", &format!("{:.2}", e.x)),
+                                ("y", &format!("{:.2}", e.y)),
+                                ("z", &format!("{:.2}", e.z))]),
+                            egui::FontId::proportional(16.),
+                            egui::Color32::from_rgb(255, 80, 80));
                     }
-                    ui.add(mesh_widget::mesh_view(size, mesh));
+                    self.annotation_ui.draw(ui.painter(), mesh, response.rect);
+                    view_response = Some(response);
                 }
             });
+            let clicked = view_response.as_ref().is_some_and(|response| response.clicked());
+            self.update_cursor(view_response.clone());
+            self.show_simplify(ctx);
+            self.show_cut(ctx);
+            self.show_section(ctx);
+            self.show_slice(ctx);
+            self.show_fit(ctx, clicked);
+            self.show_lay_flat(ctx, clicked);
+            self.show_align(ctx, clicked);
+            self.show_select(ctx, view_response.as_ref());
+            let clicked = clicked && self.fit_ui.model.is_none() && self.lay_flat_ui.model.is_none()
+                && self.align_ui.source.is_none() && self.select_ui.model.is_none();
+            let pinned = self.show_annotations(ctx, clicked);
+            self.show_measurement(ctx, clicked && !pinned);
+            self.show_offset(ctx);
+            self.show_extrude(ctx);
+            self.show_islands(ctx);
+            self.show_morph(ctx);
+            self.show_shapes(ctx);
+            self.show_mold_box(ctx);
+            self.show_heightmap(ctx);
+            self.show_point_clouds(ctx);
+            self.show_deviation(ctx);
+            self.info_ui.show(ctx, self.settings.unit);
+            if self.printer_ui.show(ctx, &mut self.settings) {
+                self.apply_printer();
+            }
+            if let Some(loading) = &self.loading {
+                egui::Window::new(tr("loading-title"))
+                    .collapsible(false)
+                    .show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        let key = if loading.decimated { "decimate-working" } else { "loading-file" };
+                        ui.label(tr_args(key, &[("file", &loading.path)]));
+                        ui.add(egui::ProgressBar::new(loading.request.progress()).show_percentage());
+                        if ui.button(tr("loading-cancel")).clicked() {
+                            loading.request.cancel();
+                        }
+                    })
+                });
+            }
+            if let Some(prompt) = &self.decimate_prompt {
+                let mut choice = None;
+                egui::Window::new(tr("decimate-title"))
+                    .collapsible(false)
+                    .show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.label(tr_args("decimate-prompt", &[
+                            ("count", &prompt.loaded.triangles.len()),
+                            ("target", &self.settings.decimate_threshold)]));
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("decimate-yes")).clicked() {
+                                choice = Some(true);
+                            }
+                            if ui.button(tr("decimate-no")).clicked() {
+                                choice = Some(false);
+                            }
+                        });
+                    })
+                });
+                if let Some(decimate) = choice {
+                    let prompt = self.decimate_prompt.take().unwrap();
+                    if decimate {
+                        self.start_decimation(ctx, prompt.path, prompt.add, prompt.loaded);
+                    } else {
+                        self.show_loaded(ctx, prompt.path, prompt.add, false, prompt.loaded);
+                    }
+                }
+            }
+            #[cfg(feature = "profiling")]
+            if self.timing_ui.show(ctx) {
+                self.save_trace();
+            }
             if let Some(alert) = self.alert.clone() {
-                egui::Window::new("Alert")
+                egui::Window::new(tr("alert-title"))
                     .collapsible(false)
                     .show(ctx, |ui| {
                     let alert = alert.lock().unwrap();
@@ -84,7 +620,7 @@ impl eframe::App for AppState {
                         ui.spacing();
                         ui.label(alert.as_str());
                         ui.spacing();
-                        if ui.button("OK").clicked() {
+                        if ui.button(tr("alert-ok")).clicked() {
                             self.alert = None;
                         }
                     })
@@ -95,68 +631,1118 @@ impl eframe::App for AppState {
 }
 
 impl AppState {
-    fn new(gl: Arc<glow::Context>) -> Self {
+    fn new(cc: &eframe::CreationContext) -> Self {
+        let settings = settings::Settings::load(cc.storage);
+        i18n::set_language(settings.language);
+        cc.egui_ctx.set_visuals(settings.theme.visuals());
+        thread_request::spawn_job(thread_request::Priority::Background, || {
+            let _ = mesh_cache::prune();
+        });
+        let mut issue_ui = issue_ui::IssueUI::new();
+        issue_ui.thin_wall_threshold = settings.min_wall();
+        let mut analysis_ui = analysis_ui::AnalysisUI::new();
+        analysis_ui.thickness_threshold = settings.min_wall();
+        analysis_ui.max_overhang = settings.max_overhang();
+        let mut alert = None;
+        let update_check = if settings.update_url.is_empty() {
+            None
+        } else if let Err(err) = update_check::check_url(&settings.update_url) {
+            // A mistyped address would never work, so it is reported, unlike a check that fails offline
+            alert = new_alert(tr_args("alert-update-check-failed", &[("url", &settings.update_url), ("error", &err)]));
+            None
+        } else {
+            let url = settings.update_url.clone();
+            Some(thread_request::Request::spawn_with(&cc.egui_ctx, thread_request::Priority::Background,
+                move |_| update_check::latest_version(&url)))
+        };
         return Self{
-            gl: gl,
-            alert: None,
-            triangles: None,
+            gl: cc.gl.to_owned().expect("Could not get gl context"),
+            alert,
+            scene: Vec::new(),
             mesh: None,
             texture: None,
-            transformation_ui: TransformationUI::new()
+            issue_ui,
+            scene_ui: scene::SceneUI::new(),
+            simplify_ui: simplify_ui::SimplifyUI::new(),
+            cut_ui: cut_ui::CutUI::new(),
+            section_ui: section_ui::SectionUI::new(),
+            screenshot_ui: screenshot_ui::ScreenshotUI::new(),
+            slice_ui: slice_ui::SliceUI::new(),
+            offset_ui: offset_ui::OffsetUI::new(),
+            morph_ui: morph_ui::MorphUI::new(),
+            measurement_ui: measurement_ui::MeasurementUI::new(),
+            annotation_ui: annotation_ui::AnnotationUI::new(),
+            turntable: view_toolbar::Turntable::new(),
+            saved_views: Vec::new(),
+            islands_ui: islands_ui::IslandsUI::new(),
+            select_ui: select_ui::SelectUI::new(),
+            deviation_ui: deviation_ui::DeviationUI::new(),
+            fit_ui: fit_ui::FitUI::new(),
+            lay_flat_ui: lay_flat_ui::LayFlatUI::new(),
+            align_ui: align_ui::AlignUI::new(),
+            shape_ui: shape_ui::ShapeUI::new(),
+            mold_box_ui: mold_box_ui::MoldBoxUI::new(),
+            extrude_ui: extrude_ui::ExtrudeUI::new(),
+            heightmap_ui: heightmap_ui::HeightmapUI::new(),
+            point_cloud_ui: point_cloud_ui::PointCloudUI::new(),
+            printer_ui: printer::PrinterUI::new(),
+            info_ui: info_ui::InfoUI::new(),
+            analysis_ui,
+            settings,
+            recent_files: recent_files::RecentFiles::load(cc.storage),
+            current_file: None,
+            cursor: None,
+            hover: None,
+            bake_view_rotation: false,
+            loading: None,
+            decimate_prompt: None,
+            update_check,
+            #[cfg(feature = "profiling")]
+            timing_ui: profiling::TimingUI::new()
         }
     }
+    /// Tells the user about a newer release, once the check started at startup is done.
+    /// A failed check is ignored, since it shouldn't get in the way of working offline;
+    /// an address that could never work is reported when the check would start instead.
+    fn finish_update_check(&mut self) {
+        let result = match self.update_check.as_mut().and_then(|request| request.take()) {
+            Some(result) => result,
+            None => return
+        };
+        self.update_check = None;
+        if let Ok(latest) = result {
+            if update_check::is_newer(&latest, version::VERSION) {
+                self.alert = new_alert(tr_args("alert-update-available", &[
+                    ("latest", &latest), ("current", &version::VERSION)]));
+            }
+        }
+    }
+    /// Takes the unit and thresholds from the current printer
+    fn apply_printer(&mut self) {
+        self.settings.unit = self.settings.printer().unit;
+        self.issue_ui.thin_wall_threshold = self.settings.min_wall();
+        self.analysis_ui.thickness_threshold = self.settings.min_wall();
+        self.analysis_ui.max_overhang = self.settings.max_overhang();
+    }
+    /// Applies the theme from the settings to the UI and the viewport
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        ctx.set_visuals(self.settings.theme.visuals());
+        if let Some(mesh) = &mut self.mesh {
+            let palette = self.settings.theme.palette();
+            mesh.background = palette.background;
+            mesh.overlay = palette.overlay;
+        }
+    }
+    /// Shows the unit, coordinate convention, and the position under the cursor
+    fn show_status(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr_args("status-unit", &[("unit", &self.settings.unit.label())]));
+            ui.separator();
+            let right_handed = !matches!(&self.mesh, Some(mesh) if !mesh.right_handed);
+            ui.label(if right_handed { tr("status-right-handed") } else { tr("status-left-handed") });
+            if let Some(mesh) = &self.mesh {
+                ui.separator();
+                ui.label(tr_args("status-faces", &[
+                    ("input", &mesh.get_triangle_count()),
+                    ("rendered", &mesh.get_rendered_count())]))
+                    .on_hover_text(tr("status-faces-hint"));
+            }
+            if let Some(cursor) = self.cursor {
+                ui.separator();
+                let suffix = self.settings.unit.suffix();
+                ui.label(tr_args("status-cursor", &[
+                    ("x", &format!("{:.3} {}", cursor.x, suffix)),
+                    ("y", &format!("{:.3} {}", cursor.y, suffix)),
+                    ("z", &format!("{:.3} {}", cursor.z, suffix))]));
+            }
+            if let Some(fraction) = self.upload_progress() {
+                ui.separator();
+                ui.label(tr("status-uploading"));
+                ui.add(egui::ProgressBar::new(fraction).desired_width(120.).show_percentage());
+            }
+        });
+    }
+    /// Uploads the models again if their buffers were lost with the graphics context,
+    /// instead of leaving the view empty
+    fn recover_lost_context(&mut self) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if !mesh.resources_lost() {
+            return;
+        }
+        mesh.release_resources();
+        self.alert = new_alert(tr("alert-context-lost"));
+    }
+    /// Uploads the next chunk of the first model which isn't fully on the GPU.
+    /// Only one chunk is uploaded each frame, so large meshes don't freeze the UI.
+    fn continue_upload(&mut self, ctx: &egui::Context) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if let Err(err) = mesh.upload_point_clouds() {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+        }
+        for i in 0..mesh.models.len() {
+            if !mesh.models[i].uploaded() {
+                if let Err(err) = mesh.upload_chunk(i) {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                }
+                ctx.request_repaint();
+                return;
+            }
+        }
+    }
+    /// Gives each fully uploaded model averaged corner normals while the view is smooth shaded.
+    /// They are found in the background, and kept on the GPU until the model changes.
+    fn continue_smoothing(&mut self, ctx: &egui::Context) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if !mesh.smooth {
+            return;
+        }
+        for (i, model) in self.scene.iter_mut().enumerate() {
+            let view_model = &mesh.models[i];
+            // Previews have different triangles, and get the face normals
+            let pending = !model.triangles.is_empty() && view_model.corner_normals.is_empty()
+                && view_model.uploaded() && Arc::ptr_eq(&view_model.triangles, &model.triangles);
+            if !pending {
+                continue;
+            }
+            if let Some(normals) = model.smooth_normals(ctx) {
+                if let Err(err) = mesh.set_corner_normals(i, Some(&normals)) {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                }
+            }
+        }
+    }
+    /// Gives each fully uploaded large model a reduced copy, to draw while the view moves.
+    /// They are made in the background, and kept on the GPU until the model changes.
+    fn continue_reducing(&mut self, ctx: &egui::Context) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if !mesh.reduce_detail {
+            return;
+        }
+        for (i, model) in self.scene.iter_mut().enumerate() {
+            // Previews have different triangles than the scene's
+            let view_model = &mesh.models[i];
+            if !view_model.needs_reduced() || !Arc::ptr_eq(&view_model.triangles, &model.triangles) {
+                continue;
+            }
+            if let Some(reduced) = model.reduced(ctx) {
+                if let Err(err) = mesh.set_reduced(i, &reduced) {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                }
+            }
+        }
+    }
+    /// The fraction of the scene's triangles on the GPU, if some are still being uploaded
+    fn upload_progress(&self) -> Option<f32> {
+        let mesh = self.mesh.as_ref()?;
+        let total: usize = mesh.models.iter().map(|model| model.triangles.len()).sum();
+        let uploaded: usize = mesh.models.iter().map(|model| model.triangle_count()).sum();
+        if uploaded >= total {
+            return None;
+        }
+        return Some(uploaded as f32 / total as f32);
+    }
+    /// Shows the simplify dialog, and swaps which triangles the view shows
+    /// for the model it works on
+    fn show_simplify(&mut self, ctx: &egui::Context) {
+        let index = unwrap_or_return!(self.simplify_ui.model);
+        let (mesh, model) = match (&mut self.mesh, self.scene.get_mut(index)) {
+            (Some(mesh), Some(model)) => (mesh, model),
+            _ => {
+                self.simplify_ui.close();
+                return;
+            }
+        };
+        let result = match self.simplify_ui.show(ctx, &model.triangles, model.cache_key.as_deref(), self.settings.unit) {
+            simplify_ui::SimplifyResponse::None => return,
+            simplify_ui::SimplifyResponse::Preview => {
+                mesh.replace_model(index, self.simplify_ui.preview_of(index).unwrap())
+            },
+            simplify_ui::SimplifyResponse::Apply(triangles) => {
+                model.set_triangles(ctx, triangles);
+                self.issue_ui.clear(mesh);
+                mesh.replace_model(index, &model.triangles)
+            },
+            simplify_ui::SimplifyResponse::Restore => mesh.replace_model(index, &model.triangles)
+        };
+        if let Err(err) = result {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+        }
+    }
+    /// Shows the cut dialog, drawing its plane, and splits the model once it is cut
+    fn show_cut(&mut self, ctx: &egui::Context) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        mesh.guides.clear();
+        let index = unwrap_or_return!(self.cut_ui.model);
+        let model = match self.scene.get_mut(index) {
+            Some(model) => model,
+            None => {
+                self.cut_ui.close();
+                return;
+            }
+        };
+        let placement = model.transformation_ui.get_matrix();
+        let bounds = model.transformed_bounds(&placement);
+        if let Some((min, max)) = bounds {
+            mesh.guides.push(self.cut_ui.outline(&min, &max));
+        }
+        let (front, back) = match self.cut_ui.show(ctx, &model.triangles, &placement, bounds) {
+            cut_ui::CutResponse::None => return,
+            cut_ui::CutResponse::Cut(front, back) => (front, back)
+        };
+        mesh.guides.clear();
+        if front.is_empty() || back.is_empty() {
+            self.alert = new_alert(tr("alert-cut-missed"));
+            return;
+        }
+        // The halves replace the model, already placed, so they start without transformations
+        let axis = cut_ui::AXES[self.cut_ui.axis];
+        let build_bvh = model.bvh.is_some() || model.building_bvh();
+        let mut halves = Vec::<scene::SceneModel>::new();
+        for (triangles, sign) in [(front, "+"), (back, "-")] {
+            let mut half = scene::SceneModel::new(model.path.clone(), triangles, model.decimated);
+            half.name = tr_args("scene-cut-name", &[("name", &model.name), ("side", &format!("{}{}", sign, axis))]);
+            if build_bvh {
+                half.start_bvh(ctx);
+            }
+            halves.push(half);
+        }
+        let back = halves.pop().unwrap();
+        let front = halves.pop().unwrap();
+        if let Err(err) = mesh.replace_model(index, &front.triangles)
+            .and_then(|_| mesh.add_model(&back.triangles)) {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+            return;
+        }
+        self.scene[index] = front;
+        self.scene.push(back);
+        self.issue_ui.clear(mesh);
+    }
+    /// Shows the offset dialog, and replaces the model with the offset surface once it is built
+    fn show_offset(&mut self, ctx: &egui::Context) {
+        let index = unwrap_or_return!(self.offset_ui.model);
+        let (mesh, model) = match (&mut self.mesh, self.scene.get_mut(index)) {
+            (Some(mesh), Some(model)) => (mesh, model),
+            _ => {
+                self.offset_ui.close();
+                return;
+            }
+        };
+        let placement = model.transformation_ui.get_matrix();
+        let bounds = model.transformed_bounds(&placement);
+        let triangles = match self.offset_ui.show(ctx, &model.triangles, &placement, bounds, self.settings.unit) {
+            offset_ui::OffsetResponse::None => return,
+            offset_ui::OffsetResponse::Apply(triangles) => triangles
+        };
+        if triangles.is_empty() {
+            self.alert = new_alert(tr("alert-offset-empty"));
+            return;
+        }
+        // The offset surface is already placed, so it starts without transformations
+        model.set_triangles(ctx, triangles);
+        model.transformation_ui = TransformationUI::new();
+        self.issue_ui.clear(mesh);
+        if let Err(err) = mesh.replace_model(index, &model.triangles) {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+        }
+    }
+    /// Shows the islands dialog, and replaces the model with what is left once they are deleted
+    fn show_islands(&mut self, ctx: &egui::Context) {
+        let index = unwrap_or_return!(self.islands_ui.model);
+        let (mesh, model) = match (&mut self.mesh, self.scene.get_mut(index)) {
+            (Some(mesh), Some(model)) => (mesh, model),
+            _ => {
+                self.islands_ui.close();
+                return;
+            }
+        };
+        let placement = model.transformation_ui.get_matrix();
+        let response = self.islands_ui.show(ctx, &model.triangles, model.cache_key.as_deref(), &placement,
+            self.settings.unit);
+        let (triangles, deleted) = match response {
+            islands_ui::IslandsResponse::None => return,
+            islands_ui::IslandsResponse::Apply(triangles, deleted) => (triangles, deleted)
+        };
+        let before = model.triangles.len();
+        model.set_triangles(ctx, triangles);
+        self.issue_ui.clear(mesh);
+        if let Err(err) = mesh.replace_model(index, &model.triangles) {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+            return;
+        }
+        self.alert = new_alert(tr_args("alert-islands-deleted", &[
+            ("count", &deleted),
+            ("triangles", &(before - model.triangles.len()))]));
+    }
+    /// Shows the face selection dialog, selecting faces clicked or dragged around in the view,
+    /// and deletes them from the model when asked
+    fn show_select(&mut self, ctx: &egui::Context, view_response: Option<&egui::Response>) {
+        if let Some(mesh) = &mut self.mesh {
+            mesh.rotate_on_drag = !self.select_ui.drags();
+        }
+        let index = unwrap_or_return!(self.select_ui.model);
+        if self.mesh.is_none() || index >= self.scene.len() {
+            self.select_ui.close();
+            return;
+        }
+        if let Some(response) = view_response {
+            if response.clicked() {
+                let hit = response.interact_pointer_pos().and_then(|position| self.pick(position, response.rect));
+                self.select_ui.click(hit, ctx.input().modifiers);
+            }
+            if response.clicked() || response.dragged_by(egui::PointerButton::Primary) {
+                let hit = response.interact_pointer_pos().and_then(|position| self.pick(position, response.rect));
+                let placement = self.mesh.as_ref().unwrap().models[index].local_transform;
+                self.select_ui.paint(hit, &self.scene[index].triangles, &placement, ctx.input().modifiers);
+            }
+            self.select_ui.drag(response, self.mesh.as_ref().unwrap(), &self.scene[index].triangles);
+        }
+        let mesh = self.mesh.as_mut().unwrap();
+        let model = &mut self.scene[index];
+        let placement = mesh.models[index].local_transform;
+        mesh.guides.extend(self.select_ui.brush_outline(self.hover, &model.triangles, &placement));
+        match self.select_ui.show(ctx, &model.triangles, mesh.overlay, self.settings.unit) {
+            select_ui::SelectResponse::None => {
+                if self.select_ui.model.is_none() {
+                    let _ = mesh.set_corner_colors(index, None);
+                }
+            },
+            select_ui::SelectResponse::Colors(index, colors) => {
+                if let Err(err) = mesh.set_corner_colors(index, Some(&colors)) {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                }
+            },
+            select_ui::SelectResponse::Delete(triangles, deleted) => {
+                model.set_triangles(ctx, triangles);
+                self.issue_ui.clear(mesh);
+                if let Err(err) = mesh.replace_model(index, &model.triangles) {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                    return;
+                }
+                self.alert = new_alert(tr_args("alert-faces-deleted", &[("count", &deleted)]));
+            },
+            select_ui::SelectResponse::Export(index, triangles) => {
+                let placement = mesh.models[index].local_transform;
+                let placed = triangles.iter().map(|t| triangle::transform(t, &placement)).collect();
+                self.save_triangles(placed);
+            }
+        }
+    }
+    /// Closes the face selection dialog, and stops highlighting the faces it selected
+    fn clear_selection(&mut self) {
+        let index = unwrap_or_return!(self.select_ui.close());
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if index < mesh.models.len() {
+            let _ = mesh.set_corner_colors(index, None);
+        }
+    }
+    /// Shows the morph dialog, adding the blended surfaces it makes to the scene
+    fn show_morph(&mut self, ctx: &egui::Context) {
+        match self.morph_ui.show(ctx, &self.scene) {
+            morph_ui::MorphResponse::None => {},
+            morph_ui::MorphResponse::Add(name, triangles) => {
+                let mesh = unwrap_or_return!(&mut self.mesh);
+                let source = &self.scene[self.morph_ui.source.unwrap_or(0)];
+                let mut blend = scene::SceneModel::new(source.path.clone(), triangles, false);
+                blend.name = name;
+                if let Err(err) = mesh.add_model(&blend.triangles) {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                    return;
+                }
+                self.scene.push(blend);
+            },
+            morph_ui::MorphResponse::Exported(Ok(files)) => {
+                self.alert = new_alert(tr_args("alert-morph-exported", &[
+                    ("count", &files.len()),
+                    ("first", &files.first().cloned().unwrap_or_default()),
+                    ("last", &files.last().cloned().unwrap_or_default())]));
+            },
+            morph_ui::MorphResponse::Exported(Err(err)) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            }
+        }
+    }
+    /// Shows the shape dialog, adding the shapes it creates to the scene
+    fn show_shapes(&mut self, ctx: &egui::Context) {
+        match self.shape_ui.show(ctx, self.settings.unit) {
+            shape_ui::ShapeResponse::None => {},
+            shape_ui::ShapeResponse::Add(name, triangles) => self.add_created_model(name, triangles)
+        }
+    }
+    /// Shows the extrude dialog, adding the solid it makes to the scene
+    fn show_extrude(&mut self, ctx: &egui::Context) {
+        let index = unwrap_or_return!(self.extrude_ui.model);
+        let model = match self.scene.get(index) {
+            Some(model) => model,
+            None => {
+                self.extrude_ui.close();
+                return;
+            }
+        };
+        let placement = model.transformation_ui.get_matrix();
+        match self.extrude_ui.show(ctx, &model.name, &model.triangles, &placement, self.settings.unit) {
+            extrude_ui::ExtrudeResponse::None => {},
+            extrude_ui::ExtrudeResponse::Add(name, triangles) => self.add_created_model(name, triangles)
+        }
+    }
+    /// Shows the heightmap dialog, adding the solid it makes to the scene
+    fn show_heightmap(&mut self, ctx: &egui::Context) {
+        match self.heightmap_ui.show(ctx, self.settings.unit) {
+            heightmap_ui::HeightmapResponse::None => {},
+            heightmap_ui::HeightmapResponse::Add(name, triangles) => self.add_created_model(name, triangles),
+            heightmap_ui::HeightmapResponse::Failed(name, err) => {
+                self.alert = new_alert(tr_args("alert-import-failed", &[("file", &name), ("error", &err)]));
+            }
+        }
+    }
+    /// Shows the point cloud window, adding clouds to the view once they are read
+    fn show_point_clouds(&mut self, ctx: &egui::Context) {
+        let (name, cloud) = match self.point_cloud_ui.show(ctx, self.mesh.as_mut()) {
+            point_cloud_ui::PointCloudResponse::None => return,
+            point_cloud_ui::PointCloudResponse::Loaded(name, cloud) => (name, Arc::new(cloud)),
+            point_cloud_ui::PointCloudResponse::Failed(name, err) => {
+                self.alert = new_alert(tr_args("alert-import-failed", &[("file", &name), ("error", &err)]));
+                return;
+            }
+        };
+        if self.mesh.is_none() {
+            // Points alone start an empty view, which is framed on them
+            let mut mesh_view_state = match ViewState::new_empty(self.gl.to_owned()) {
+                Ok(mesh_view_state) => mesh_view_state,
+                Err(err) => {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                    return;
+                }
+            };
+            let palette = self.settings.theme.palette();
+            mesh_view_state.background = palette.background;
+            mesh_view_state.overlay = palette.overlay;
+            self.issue_ui.clear(&mut mesh_view_state);
+            self.scene = Vec::new();
+            self.scene_ui = scene::SceneUI::new();
+            self.mesh = Some(mesh_view_state);
+        }
+        let mesh = self.mesh.as_mut().unwrap();
+        if let Err(err) = mesh.add_point_cloud(&cloud) {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+            return;
+        }
+        self.point_cloud_ui.names.push(name);
+        if mesh.models.is_empty() && mesh.point_clouds.len() == 1 {
+            if let Some((center, radius)) = mesh.point_clouds[0].bounding_sphere() {
+                mesh.frame(center, radius);
+            }
+        }
+    }
+    /// Asks for an image, and starts reading it for the heightmap dialog to turn it into a solid
+    fn import_heightmap(&mut self, ctx: &egui::Context) {
+        let path = unwrap_or_return!(rfd::FileDialog::new().add_filter("png", &["png", "PNG"]).pick_file());
+        self.heightmap_ui.load(ctx, path.display().to_string());
+    }
+    /// Shows the mold box dialog, outlining the box around the model and adding it to the scene when asked
+    fn show_mold_box(&mut self, ctx: &egui::Context) {
+        let index = unwrap_or_return!(self.mold_box_ui.model);
+        let (mesh, model) = match (&mut self.mesh, self.scene.get_mut(index)) {
+            (Some(mesh), Some(model)) => (mesh, model),
+            _ => {
+                self.mold_box_ui.close();
+                return;
+            }
+        };
+        let placement = model.transformation_ui.get_matrix();
+        let (min, max) = unwrap_or_return!(model.transformed_bounds(&placement));
+        mesh.guides.extend(self.mold_box_ui.outlines(&min, &max));
+        let name = model.name.clone();
+        match self.mold_box_ui.show(ctx, &name, &min, &max, self.settings.unit) {
+            mold_box_ui::MoldBoxResponse::None => {},
+            mold_box_ui::MoldBoxResponse::Add(name, triangles) => self.add_created_model(name, triangles)
+        }
+    }
+    /// Adds a model made in the application to the scene, starting the view if the scene is empty
+    fn add_created_model(&mut self, name: String, triangles: Vec<triangle::Triangle>) {
+        let model = scene::SceneModel::new(name, triangles, false);
+        match &mut self.mesh {
+            Some(mesh) => {
+                if let Err(err) = mesh.add_model(&model.triangles) {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                    return;
+                }
+                self.scene.push(model);
+                self.scene_ui.selected = self.scene.len() - 1;
+                self.issue_ui.clear(mesh);
+            },
+            None => {
+                // The first model of an empty scene starts the view, as opening a file would
+                let mut mesh_view_state = match ViewState::new(self.gl.to_owned(), &model.triangles) {
+                    Ok(mesh_view_state) => mesh_view_state,
+                    Err(err) => {
+                        self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                        return;
+                    }
+                };
+                let palette = self.settings.theme.palette();
+                mesh_view_state.background = palette.background;
+                mesh_view_state.overlay = palette.overlay;
+                self.issue_ui.clear(&mut mesh_view_state);
+                self.scene = vec![model];
+                self.scene_ui = scene::SceneUI::new();
+                self.mesh = Some(mesh_view_state);
+            }
+        }
+    }
+    /// Shows the cross section dialog, drawing the section in the view
+    fn show_slice(&mut self, ctx: &egui::Context) {
+        let index = unwrap_or_return!(self.slice_ui.model);
+        let (mesh, model) = match (&mut self.mesh, self.scene.get_mut(index)) {
+            (Some(mesh), Some(model)) => (mesh, model),
+            _ => {
+                self.slice_ui.close();
+                return;
+            }
+        };
+        let placement = model.transformation_ui.get_matrix();
+        let bounds = model.transformed_bounds(&placement);
+        let response = self.slice_ui.show(ctx, &model.triangles, &placement, bounds, self.settings.unit);
+        mesh.guides.extend(self.slice_ui.outlines());
+        match response {
+            slice_ui::SliceResponse::None => {},
+            slice_ui::SliceResponse::Exported(Ok(files)) => {
+                self.alert = new_alert(tr_args("alert-slices-exported", &[
+                    ("count", &files.len()),
+                    ("first", &files.first().cloned().unwrap_or_default()),
+                    ("last", &files.last().cloned().unwrap_or_default())]));
+            },
+            slice_ui::SliceResponse::Exported(Err(err)) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            }
+        }
+    }
+    /// Shows the primitive fitting dialog, fitting the region of the model where the view was clicked,
+    /// and outlines the last region fitted
+    fn show_fit(&mut self, ctx: &egui::Context, clicked: bool) {
+        let index = unwrap_or_return!(self.fit_ui.model);
+        let (mesh, model) = match (&mut self.mesh, self.scene.get(index)) {
+            (Some(mesh), Some(model)) => (mesh, model),
+            _ => {
+                self.fit_ui.close();
+                return;
+            }
+        };
+        if let (true, Some(cursor)) = (clicked, self.cursor) {
+            let placement = model.transformation_ui.get_matrix();
+            self.fit_ui.pick(&cursor, &model.triangles, model.cache_key.as_deref(), &placement);
+        }
+        mesh.guides.extend(self.fit_ui.outlines());
+        match self.fit_ui.show(ctx, self.settings.unit) {
+            fit_ui::FitResponse::None => {},
+            fit_ui::FitResponse::Exported(Ok(file)) => {
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &file)]));
+            },
+            fit_ui::FitResponse::Exported(Err(err)) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            }
+        }
+    }
+    /// Shows the lay flat dialog, turning the model when a face of it is picked in the view
+    fn show_lay_flat(&mut self, ctx: &egui::Context, clicked: bool) {
+        let index = unwrap_or_return!(self.lay_flat_ui.model);
+        let (mesh, model) = match (&mut self.mesh, self.scene.get_mut(index)) {
+            (Some(mesh), Some(model)) => (mesh, model),
+            _ => {
+                self.lay_flat_ui.close();
+                return;
+            }
+        };
+        if let (true, Some(hit)) = (clicked, self.hover) {
+            self.lay_flat_ui.pick(&hit, &model.triangles, &mut model.transformation_ui);
+        }
+        mesh.guides.extend(self.lay_flat_ui.outlines());
+        self.lay_flat_ui.show(ctx);
+    }
+    /// Shows the alignment dialog, pairing points clicked in the view, and moves the model when asked
+    fn show_align(&mut self, ctx: &egui::Context, clicked: bool) {
+        if let (true, Some(hit)) = (clicked, self.hover) {
+            self.align_ui.pick(&hit);
+        }
+        if let Some(mesh) = &mut self.mesh {
+            mesh.guides.extend(self.align_ui.outlines());
+        }
+        match self.align_ui.show(ctx, &self.scene, self.settings.unit) {
+            align_ui::AlignResponse::None => {},
+            align_ui::AlignResponse::Align(index, transformation) => {
+                let model = unwrap_or_return!(self.scene.get_mut(index));
+                model.transformation_ui.transform(transformation);
+            }
+        }
+    }
+    /// Shows the section window, clipping the view by its planes and drawing them while it is open
+    fn show_section(&mut self, ctx: &egui::Context) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        let bounds = scene::visible_bounds(&mut self.scene, mesh);
+        self.section_ui.show(ctx, mesh, bounds);
+        mesh.clip_planes = self.section_ui.clip_planes();
+        if let Some((min, max)) = bounds {
+            mesh.guides.extend(self.section_ui.outlines(&min, &max));
+        }
+    }
+    /// Shows the annotations window, dropping a pin where the view was clicked if one is being placed.
+    /// Returns true if the click dropped a pin.
+    fn show_annotations(&mut self, ctx: &egui::Context, clicked: bool) -> bool {
+        let placing = self.annotation_ui.placing();
+        if let (true, true, Some(cursor)) = (clicked, placing, self.cursor) {
+            self.annotation_ui.place(cursor);
+        }
+        match self.annotation_ui.show(ctx, self.current_file.as_deref()) {
+            annotation_ui::AnnotationResponse::None => {},
+            annotation_ui::AnnotationResponse::Saved(Ok(file)) => {
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &file)]));
+            },
+            annotation_ui::AnnotationResponse::Loaded(Ok(file)) => {
+                self.alert = new_alert(tr_args("alert-annotations-loaded", &[
+                    ("count", &self.annotation_ui.annotations.len()),
+                    ("file", &file)]));
+            },
+            annotation_ui::AnnotationResponse::Saved(Err(err)) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            },
+            annotation_ui::AnnotationResponse::Loaded(Err(err)) => {
+                self.alert = new_alert(tr_args("alert-annotations-failed", &[("error", &err)]));
+            }
+        }
+        return clicked && placing;
+    }
+    /// Shows the measurement window, placing the next landmark where the view was clicked,
+    /// and marks the landmarks placed so far
+    fn show_measurement(&mut self, ctx: &egui::Context, clicked: bool) {
+        let cursor = self.snapped_cursor();
+        if let (true, Some(cursor)) = (clicked, cursor) {
+            self.measurement_ui.place(&self.settings, cursor);
+        }
+        let unit = self.settings.unit;
+        match self.measurement_ui.show(ctx, &mut self.settings, unit) {
+            measurement_ui::MeasurementResponse::None => {},
+            measurement_ui::MeasurementResponse::Exported(Ok(file)) => {
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &file)]));
+            },
+            measurement_ui::MeasurementResponse::Exported(Err(err)) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            }
+        }
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        let model = unwrap_or_return!(self.scene.get_mut(self.scene_ui.selected));
+        let size = model.transformed_bounds(&model.transformation_ui.get_matrix())
+            .map_or(1., |(min, max)| glm::distance(&min, &max) * 0.01);
+        mesh.guides.extend(self.measurement_ui.markers(size, cursor));
+    }
+    /// The point under the cursor, snapped to the face there as the measurement window is set to
+    fn snapped_cursor(&self) -> Option<glm::Vec3> {
+        let hit = self.hover?;
+        let mesh = self.mesh.as_ref()?;
+        let corners = self.scene.get(hit.model)?.triangles.get(hit.triangle)?;
+        let placed = triangle::transform(corners, &mesh.models.get(hit.model)?.local_transform);
+        return Some(self.measurement_ui.snap.apply(&hit.point, &placed));
+    }
+    /// Shows the deviation dialog, coloring the measured model by tolerance band
+    fn show_deviation(&mut self, ctx: &egui::Context) {
+        let (index, colors) = match self.deviation_ui.show(ctx, &self.scene, self.settings.unit) {
+            deviation_ui::DeviationResponse::None => return,
+            deviation_ui::DeviationResponse::Colors(index, colors) => (index, colors)
+        };
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if let Err(err) = mesh.set_corner_colors(index, colors.as_deref()) {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+        }
+    }
+    /// Closes the deviation dialog, and stops coloring the model it measured
+    fn clear_deviation_colors(&mut self) {
+        let index = unwrap_or_return!(self.deviation_ui.close());
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if index < mesh.models.len() {
+            let _ = mesh.set_corner_colors(index, None);
+        }
+    }
+    /// Fits the visible models in the view, turning it to a standard view first if one was chosen,
+    /// or saves the viewpoint or moves to a saved one
+    fn apply_toolbar(&mut self, response: view_toolbar::ToolbarResponse) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        let view = match response {
+            view_toolbar::ToolbarResponse::None => return,
+            view_toolbar::ToolbarResponse::Fit => None,
+            view_toolbar::ToolbarResponse::View(view) => Some(view),
+            view_toolbar::ToolbarResponse::SaveView => {
+                let name = tr_args("view-saved-name", &[("number", &(self.saved_views.len() + 1))]);
+                self.saved_views.push((name, mesh.destination()));
+                return;
+            },
+            view_toolbar::ToolbarResponse::Jump(camera) => {
+                mesh.move_to(camera);
+                return;
+            }
+        };
+        let bounds = scene::visible_bounds(&mut self.scene, mesh);
+        let mut camera = mesh.destination();
+        if let Some(view) = view {
+            camera.rotation = view.rotation();
+        }
+        if let Some((min, max)) = bounds {
+            camera.fit(&min, &max);
+        }
+        mesh.move_to(camera);
+    }
+    /// Keeps the grid's floor under the lowest visible model
+    fn update_grid(&mut self) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        if !mesh.grid.visible {
+            return;
+        }
+        if let Some((min, _)) = scene::visible_bounds(&mut self.scene, mesh) {
+            mesh.grid.height = min.z;
+        }
+    }
+    /// Finds the point on the loaded triangles under the cursor, if it is over the view
+    fn update_cursor(&mut self, view_response: Option<egui::Response>) {
+        self.hover = view_response.and_then(|response| {
+            return response.hover_pos().and_then(|position| self.pick(position, response.rect));
+        });
+        self.cursor = self.hover.map(|hit| hit.point);
+    }
+    /// Finds the model and triangle under a point of the view, on the CPU once every visible model's
+    /// picking hierarchy is built, and otherwise by drawing ids on the GPU
+    fn pick(&self, position: egui::Pos2, rect: egui::Rect) -> Option<Hit> {
+        let mesh = self.mesh.as_ref()?;
+        let picking: Vec<Option<(&[Triangle], &bvh::Bvh)>> = self.scene.iter().map(|model| model.picking()).collect();
+        let cpu_ready = mesh.models.iter().zip(&picking).all(|(view_model, picking)| !view_model.visible || picking.is_some());
+        // Only the GPU knows which parts are clipped away
+        if cpu_ready && !self.settings.gpu_picking && mesh.clip_planes.is_empty() {
+            return mesh.pick_at(position, rect, &picking);
+        }
+        let (origin, direction) = mesh.unproject(position, rect)?;
+        let (index, triangle) = mesh.pick_ids(position, rect).ok()??;
+        let view_model = mesh.models.get(index)?;
+        let inverse = view_model.local_transform.try_inverse()?;
+        let local_origin = (inverse * origin.push(1.)).xyz();
+        let local_direction = (inverse * direction.push(0.)).xyz();
+        let t = spatial_grid::line_triangle(&local_origin, &local_direction, view_model.triangles.get(triangle)?)?;
+        return Some(Hit { model: index, triangle, point: origin + direction * t });
+    }
     fn show_controls(&mut self, ui: &mut Ui) {
         if self.mesh.is_some() {
             ui.vertical(|ui| {
-                ui.toggle_value(&mut self.mesh.as_mut().unwrap().right_handed, "right handed");
-                ui.collapsing("Lighting", |ui| {
+                // The models a dialog works on can't change until it is closed
+                ui.set_enabled(self.simplify_ui.model.is_none() && self.cut_ui.model.is_none()
+                    && self.offset_ui.model.is_none() && self.islands_ui.model.is_none()
+                    && self.morph_ui.source.is_none() && self.deviation_ui.model.is_none()
+                    && self.fit_ui.model.is_none() && self.select_ui.model.is_none());
+                egui::CollapsingHeader::new(tr("scene")).default_open(true).show(ui, |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    match self.scene_ui.ui(ui, &mut self.scene, mesh) {
+                        scene::SceneResponse::Add => self.open_mesh_file(ui.ctx(), true),
+                        scene::SceneResponse::Remove(index) => self.remove_model(index),
+                        scene::SceneResponse::Selected => self.issue_ui.clear(mesh),
+                        scene::SceneResponse::None => {}
+                    }
+                });
+                if self.mesh.is_none() {
+                    return;
+                }
+                ui.toggle_value(&mut self.mesh.as_mut().unwrap().right_handed, tr("view-right-handed"));
+                ui.collapsing(tr("projection"), |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut mesh.projection, Projection::Orthographic, tr("projection-orthographic"));
+                        ui.radio_value(&mut mesh.projection, Projection::Perspective, tr("projection-perspective"));
+                    });
+                    let perspective = mesh.projection == Projection::Perspective;
+                    let unit = self.settings.unit;
+                    ui.add_enabled(perspective, egui::Slider::new(&mut mesh.fov, 10.0..=120.0)
+                        .suffix("°")
+                        .text(tr("projection-fov")));
+                    ui.horizontal(|ui| {
+                        ui.label(tr("projection-near"));
+                        ui.add_enabled(perspective, NumericEdit::new("near", &mut mesh.near, Quantity::Length(unit))
+                            .speed(0.01));
+                        mesh.near = mesh.near.clamp(0.001, 10.);
+                        ui.label(tr("projection-far"));
+                        ui.add(NumericEdit::new("far", &mut mesh.far, Quantity::Length(unit)));
+                        mesh.far = mesh.far.clamp(1., 100_000.);
+                    }).response.on_hover_text(tr("projection-clip-hint"));
+                });
+                ui.collapsing(tr("render-mode"), |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    ui.radio_value(&mut mesh.render_mode, RenderMode::Shaded, tr("render-shaded"));
+                    ui.radio_value(&mut mesh.render_mode, RenderMode::Wireframe, tr("render-wireframe"));
+                    ui.radio_value(&mut mesh.render_mode, RenderMode::ShadedEdges, tr("render-shaded-edges"));
+                    ui.radio_value(&mut mesh.render_mode, RenderMode::Depth, tr("render-depth"))
+                        .on_hover_text(tr("render-depth-hint"));
+                    ui.checkbox(&mut mesh.smooth, tr("render-smooth")).on_hover_text(tr("render-smooth-hint"));
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut mesh.highlight_back_faces, tr("render-back-faces"))
+                            .on_hover_text(tr("render-back-faces-hint"));
+                        ui.color_edit_button_rgb(&mut mesh.back_face_color);
+                    });
+                });
+                ui.collapsing(tr("viewport"), |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    let unit = self.settings.unit;
+                    ui.horizontal(|ui| {
+                        ui.label(tr("viewport-background"));
+                        ui.color_edit_button_rgb(&mut mesh.background);
+                    });
+                    ui.horizontal(|ui| {
+                        let mut gradient = mesh.background_gradient.is_some();
+                        if ui.checkbox(&mut gradient, tr("viewport-gradient")).changed() {
+                            mesh.background_gradient = if gradient { Some([0.3, 0.3, 0.35]) } else { None };
+                        }
+                        if let Some(bottom) = &mut mesh.background_gradient {
+                            ui.color_edit_button_rgb(bottom);
+                        }
+                    });
+                    ui.checkbox(&mut mesh.grid.visible, tr("viewport-grid")).on_hover_text(tr("viewport-grid-hint"));
+                    ui.add_enabled_ui(mesh.grid.visible, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(tr("viewport-grid-spacing"));
+                            ui.add(NumericEdit::new("grid_spacing", &mut mesh.grid.spacing, Quantity::Length(unit))
+                                .speed(0.1));
+                            mesh.grid.spacing = mesh.grid.spacing.clamp(0.001, 10_000.);
+                            ui.label(tr("viewport-grid-count"));
+                            ui.add(egui::DragValue::new(&mut mesh.grid.count).clamp_range(1..=100));
+                        });
+                    });
+                });
+                ui.collapsing(tr("exaggeration"), |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    ui.label(tr("exaggeration-hint"));
+                    ui.horizontal(|ui| {
+                        for (i, name) in ["X", "Y", "Z"].iter().enumerate() {
+                            ui.label(*name);
+                            ui.add(egui::DragValue::new(&mut mesh.exaggeration[i])
+                                .speed(0.05)
+                                .clamp_range(0.1..=100.)
+                                .prefix("×"));
+                        }
+                    });
+                    if ui.add_enabled(mesh.exaggerated(), egui::Button::new(tr("exaggeration-reset"))).clicked() {
+                        mesh.exaggeration = glm::Vec3::new(1., 1., 1.);
+                    }
+                });
+                ui.collapsing(tr("lighting"), |ui| {
                     let mesh = self.mesh.as_mut().unwrap();
-                    ui.label("Ambient: ");
+                    ui.label(tr("lighting-ambient"));
                     ui.color_edit_button_rgb(&mut mesh.ambient);
-                    ui.label("Diffuse: ");
+                    ui.label(tr("lighting-diffuse"));
                     ui.color_edit_button_rgb(&mut mesh.diffuse);
-                    ui.label("Specular: ");
+                    ui.label(tr("lighting-specular"));
                     ui.color_edit_button_rgb(&mut mesh.specular);
-                    ui.label("Light Source:");
-                    let light_dir = -mesh.light_direction.normalize();
-                    let mut light_yaw = f32::atan2(light_dir.y, light_dir.x);
-                    // If the light is vertical, gimble lock to 0
-                    if light_dir.z > 0.999 || light_dir.z < -0.999 {
-                        light_yaw = 0.0;
-                    }
-                    light_yaw = (light_yaw * 180.0 / PI).round() * PI/180.0;
-                    let mut light_pitch = light_dir.z.asin();
-                    light_pitch = (light_pitch * 180.0 / PI).round() * PI/180.0;
-                    ui.drag_angle(&mut light_yaw);
-                    ui.drag_angle(&mut light_pitch);
-                    light_pitch = light_pitch.clamp(-PI/2., PI/2.);
-                    mesh.light_direction = -glm::Vec3::new(
-                        light_yaw.cos() * light_pitch.cos(),
-                        light_yaw.sin() * light_pitch.cos(),
-                        light_pitch.sin());
-                });
-                ui.collapsing("Transformations", |ui| {
-                    self.transformation_ui.ui(ui);
-                    if let Some(triangles) = &self.triangles {
-                        if ui.button("Save Transformed Mesh").clicked() {
-                            if let Some(rfd_result) = rfd::FileDialog::new().add_filter("stl", &["stl", "STL"]).save_file() {
-                                let save_file = rfd_result.display().to_string();
-                                match triangle::write_transformed_stl_binary(&save_file.as_str(), triangles, &self.transformation_ui.get_matrix()) {
-                                    Err(err) => {
-                                        self.alert = new_alert(format!("Could not save mesh:\n\t{}", err));
-                                    },
-                                    Ok(_) => {
-                                        self.alert = new_alert(format!("Saved: {}", save_file));
-                                    }
+                    let mut removed = None;
+                    for (i, light) in mesh.lights.iter_mut().enumerate() {
+                        ui.push_id(i, |ui| {
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                ui.label(tr_args("lighting-source", &[("number", &(i + 1))]));
+                                ui.color_edit_button_rgb(&mut light.color);
+                                ui.add(egui::DragValue::new(&mut light.intensity)
+                                    .speed(0.01)
+                                    .clamp_range(0.0..=10.)
+                                    .prefix("×"));
+                                if ui.small_button("🗑").on_hover_text(tr("lighting-remove")).clicked() {
+                                    removed = Some(i);
+                                }
+                            });
+                            let light_dir = -light.direction.normalize();
+                            let mut light_yaw = f32::atan2(light_dir.y, light_dir.x);
+                            // If the light is vertical, gimble lock to 0
+                            if light_dir.z > 0.999 || light_dir.z < -0.999 {
+                                light_yaw = 0.0;
+                            }
+                            light_yaw = (light_yaw * 180.0 / PI).round() * PI/180.0;
+                            let mut light_pitch = light_dir.z.asin();
+                            light_pitch = (light_pitch * 180.0 / PI).round() * PI/180.0;
+                            ui.horizontal(|ui| {
+                                ui.drag_angle(&mut light_yaw);
+                                ui.drag_angle(&mut light_pitch);
+                            });
+                            light_pitch = light_pitch.clamp(-PI/2., PI/2.);
+                            light.direction = -glm::Vec3::new(
+                                light_yaw.cos() * light_pitch.cos(),
+                                light_yaw.sin() * light_pitch.cos(),
+                                light_pitch.sin());
+                            ui.checkbox(&mut light.follows_camera, tr("lighting-follow"))
+                                .on_hover_text(tr("lighting-follow-hint"));
+                        });
+                    }
+                    if let Some(i) = removed {
+                        mesh.lights.remove(i);
+                    }
+                    if ui.add_enabled(mesh.lights.len() < MAX_LIGHTS, egui::Button::new(tr("lighting-add"))).clicked() {
+                        mesh.lights.push(Light::headlight());
+                    }
+                });
+                ui.collapsing(tr("material"), |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    let default = mesh.default_material();
+                    let view_model = unwrap_or_return!(mesh.models.get_mut(self.scene_ui.selected));
+                    ui.add(egui::Slider::new(&mut view_model.opacity, 0.0..=1.0).text(tr("material-opacity")))
+                        .on_hover_text(tr("material-opacity-hint"));
+                    let mut own = view_model.material.is_some();
+                    if ui.checkbox(&mut own, tr("material-own")).on_hover_text(tr("material-own-hint")).changed() {
+                        // A new material starts from the lighting, so the model doesn't change until it is edited
+                        view_model.material = if own { Some(default) } else { None };
+                    }
+                    let material = unwrap_or_return!(&mut view_model.material);
+                    egui::Grid::new("material").show(ui, |ui| {
+                        ui.label(tr("lighting-ambient"));
+                        ui.color_edit_button_rgb(&mut material.ambient);
+                        ui.end_row();
+                        ui.label(tr("lighting-diffuse"));
+                        ui.color_edit_button_rgb(&mut material.diffuse);
+                        ui.end_row();
+                        ui.label(tr("lighting-specular"));
+                        ui.color_edit_button_rgb(&mut material.specular);
+                        ui.end_row();
+                    });
+                });
+                ui.collapsing(tr("transformations"), |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    let selected = self.scene_ui.selected;
+                    let model = unwrap_or_return!(self.scene.get_mut(selected));
+                    model.transformation_ui.ui(ui, self.settings.unit);
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("transformations-import")).clicked() {
+                            if let Some(path) = file_dialog("transform", &["json", "txt"]).pick_file() {
+                                let path = path.display().to_string();
+                                if let Err(err) = model.transformation_ui.import(&path) {
+                                    self.alert = new_alert(tr_args("alert-import-failed", &[("file", &path), ("error", &err)]));
+                                }
+                            }
+                        }
+                        if ui.button(tr("transformations-export")).clicked() {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("json", &["json", "JSON"])
+                                .add_filter("txt", &["txt", "TXT"])
+                                .save_file() {
+                                let path = path.display().to_string();
+                                if let Err(err) = model.transformation_ui.export(&path) {
+                                    self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+                                }
+                            }
+                        }
+                    });
+                    if ui.add_enabled(!model.transformation_ui.transformations.is_empty(),
+                        egui::Button::new(tr("transformations-apply")))
+                        .on_hover_text(tr("transformations-apply-hint"))
+                        .clicked() {
+                        model.bake(ui.ctx(), &model.transformation_ui.get_matrix());
+                        model.transformation_ui = TransformationUI::new();
+                        if let Err(err) = mesh.replace_model(selected, &model.triangles) {
+                            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                        }
+                        self.issue_ui.clear(mesh);
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.bake_view_rotation, tr("transformations-view-rotation"));
+                    let mut transformation = model.transformation_ui.get_matrix();
+                    if self.bake_view_rotation {
+                        transformation = mesh.camera.rotation * transformation;
+                    }
+                    if let Some((min, max)) = model.transformed_bounds(&transformation) {
+                        let size = max - min;
+                        let suffix = self.settings.unit.suffix();
+                        ui.label(tr_args("transformations-bounds", &[
+                            ("x", &format!("{:.3} {}", size.x, suffix)),
+                            ("y", &format!("{:.3} {}", size.y, suffix)),
+                            ("z", &format!("{:.3} {}", size.z, suffix))]));
+                        let volume = self.settings.build_volume();
+                        if volume.enabled && !volume.contains(&min, &max) {
+                            ui.colored_label(egui::Color32::RED, tr("transformations-outside-volume"));
+                            // Offsets are found as the mesh would be saved, which may include the view rotation
+                            let view_rotation = if self.bake_view_rotation { mesh.camera.rotation } else { glm::Mat4::identity() };
+                            let to_stack = view_rotation.try_inverse().unwrap_or_else(glm::Mat4::identity);
+                            ui.horizontal(|ui| {
+                                if ui.button(tr("transformations-center-volume")).clicked() {
+                                    let offset = volume.center_offset(&min, &max);
+                                    model.transformation_ui.translate(to_stack.transform_vector(&offset));
+                                }
+                                let scale = volume.fit_scale(&min, &max);
+                                if scale < 1. && ui.button(tr("transformations-fit-volume")).clicked() {
+                                    model.transformation_ui.scale(scale);
+                                    let offset = volume.center_offset(&(min * scale), &(max * scale));
+                                    model.transformation_ui.translate(to_stack.transform_vector(&offset));
+                                }
+                            });
+                        }
+                    }
+                    if let Some((min, max)) = model.transformed_bounds(&model.transformation_ui.get_matrix()) {
+                        let center = (min + max) / 2.;
+                        ui.menu_button(tr("transformations-mirror"), |ui| {
+                            for (axis, name) in ["X", "Y", "Z"].iter().enumerate() {
+                                if ui.button(tr_args("transformations-mirror-axis", &[("axis", name)])).clicked() {
+                                    let normal = glm::Vec3::ith(axis, 1.);
+                                    model.transformation_ui.mirror(normal, center[axis]);
+                                    ui.close_menu();
+                                }
+                            }
+                        }).response.on_hover_text(tr("transformations-mirror-hint"));
+                    }
+                    // Mesh files have no units, so a model drawn in other units is scaled into the ones shown
+                    let unit = self.settings.unit;
+                    ui.menu_button(tr("transformations-convert-units"), |ui| {
+                        for from in units::Unit::ALL.into_iter().filter(|from| *from != unit) {
+                            if ui.button(tr_args("transformations-convert-from", &[("unit", &from.label())])).clicked() {
+                                model.transformation_ui.scale(from.scale_to(unit));
+                                ui.close_menu();
+                            }
+                        }
+                    }).response.on_hover_text(tr_args("transformations-convert-units-hint", &[("unit", &unit.label())]));
+                    let triangles = &model.triangles;
+                    if ui.button(tr("transformations-save")).clicked() {
+                        if let Some(rfd_result) = rfd::FileDialog::new().add_filter("stl", &["stl", "STL"]).save_file() {
+                            let save_file = rfd_result.display().to_string();
+                            match triangle::write_transformed_stl_binary(&save_file.as_str(), triangles, &transformation) {
+                                Err(err) => {
+                                    self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+                                },
+                                Ok(_) => {
+                                    let bounds = triangle::transformed_bounds(triangles, &transformation);
+                                    self.alert = new_alert(self.saved_message(&save_file, bounds));
                                 }
                             }
                         }
                     }
                 });
-                if ui.button("Screenshot").clicked() {
+                ui.collapsing(tr("analysis"), |ui| {
+                    let selected = self.scene_ui.selected;
+                    let model = unwrap_or_return!(self.scene.get_mut(selected));
+                    let mesh = unwrap_or_return!(&mut self.mesh);
+                    let colored = mesh.models.get(selected).is_some_and(|m| !m.corner_colors.is_empty());
+                    match self.analysis_ui.ui(ui, selected, model, colored, self.settings.unit) {
+                        analysis_ui::AnalysisResponse::None => (),
+                        analysis_ui::AnalysisResponse::Colors(index, colors) => {
+                            if index < mesh.models.len() {
+                                if let Err(err) = mesh.set_corner_colors(index, colors.as_deref()) {
+                                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                                }
+                            }
+                        }
+                    }
+                });
+                ui.collapsing(tr("issues"), |ui| {
+                    let mesh = self.mesh.as_mut().unwrap();
+                    let selected = self.scene_ui.selected;
+                    let model = unwrap_or_return!(self.scene.get_mut(selected));
+                    match self.issue_ui.ui(ui, &model.triangles, model.cache_key.as_deref(), mesh, self.settings.unit) {
+                        issue_ui::IssueResponse::Fixed(fixed) => {
+                            model.set_triangles(ui.ctx(), fixed);
+                            if let Err(err) = mesh.replace_model(selected, &model.triangles) {
+                                self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                            }
+                        },
+                        issue_ui::IssueResponse::Export => self.export_issues(),
+                        issue_ui::IssueResponse::None => {}
+                    }
+                });
+                if ui.button(tr("view-screenshot")).clicked() {
                     let color_image = egui::ColorImage::from_rgba_unmultiplied(
                         [200,200],
-                        &self.mesh.as_ref().unwrap().draw_pixels(200,200).unwrap());
+                        &self.mesh.as_ref().unwrap().draw_pixels(200, 200, true).unwrap());
                     self.texture = Some(
                         ui.ctx().load_texture(
                             "screenshot",
@@ -170,48 +1756,407 @@ impl AppState {
             });
         }
     }
-    fn open_mesh_file(&mut self) {
-        if let Some(rfd_result) = rfd::FileDialog::new().pick_file() {
-            let input_file = rfd_result.display().to_string();
-            self.mesh = match triangle::read_stl_binary(input_file.as_str()) {
-                Err(_) => {
-                    self.alert = new_alert(format!("Could not open file {}", input_file));
-                    None
-                },
-                Ok(mesh) => {
-                    let mesh_view_state = ViewState::new(self.gl.to_owned(), &mesh).unwrap();
-                    self.triangles = Some(mesh);
-                    Some(mesh_view_state)
-                }
+    /// Asks for a mesh file, and starts reading it in the background.
+    /// If add is true, it will be added to the scene instead of replacing it.
+    fn open_mesh_file(&mut self, ctx: &egui::Context, add: bool) {
+        let rfd_result = file_dialog("mesh", &mesh_file::SUPPORTED_EXTENSIONS).pick_file();
+        let rfd_result = unwrap_or_return!(rfd_result);
+        self.load_mesh_file(ctx, rfd_result.display().to_string(), add);
+    }
+    /// Starts reading a mesh file in the background
+    fn load_mesh_file(&mut self, ctx: &egui::Context, input_file: String, add: bool) {
+        if self.busy() {
+            return;
+        }
+        let path = input_file.clone();
+        let use_cache = self.settings.disk_cache;
+        let request = thread_request::Request::spawn_with(ctx, thread_request::Priority::Urgent, move |progress| {
+            let cache_key = if use_cache {
+                mesh_cache::file_key(&path).ok()
+            } else {
+                None
+            };
+            let triangles = mesh_file::read_mesh_file_with_progress(path.as_str(), |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            })?;
+            return Ok(Loaded { triangles, cache_key });
+        });
+        self.loading = Some(Loading { path: input_file, add, decimated: false, request });
+    }
+    /// Stores how the current file is viewed in the recent files list
+    fn remember_camera(&mut self) {
+        let mesh = unwrap_or_return!(&self.mesh);
+        let current_file = unwrap_or_return!(&self.current_file);
+        self.recent_files.set_camera(current_file, recent_files::LastView::from_view(mesh));
+    }
+    /// True while a mesh is being loaded, so another can't be started
+    fn busy(&self) -> bool {
+        return self.loading.is_some() || self.decimate_prompt.is_some();
+    }
+    /// Shows the mesh being read in the background once it is ready,
+    /// or decides whether to decimate it first
+    fn finish_loading(&mut self, ctx: &egui::Context) {
+        let loading = unwrap_or_return!(&mut self.loading);
+        if loading.request.cancelled() {
+            self.loading = None;
+            return;
+        }
+        if let Some(message) = loading.request.panic_message() {
+            self.alert = new_alert(tr_args("alert-open-failed", &[("file", &loading.path), ("error", &message)]));
+            self.loading = None;
+            return;
+        }
+        let result = unwrap_or_return!(loading.request.take());
+        let loading = self.loading.take().unwrap();
+        let loaded = match result {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                // The scene stays as it was, rather than being lost to a file that couldn't be read
+                self.alert = new_alert(tr_args("alert-open-failed", &[("file", &loading.path), ("error", &err)]));
+                return;
+            }
+        };
+        let large = !loading.decimated && loaded.triangles.len() > self.settings.decimate_threshold;
+        match self.settings.auto_decimate {
+            decimate::AutoDecimate::Always if large => {
+                self.start_decimation(ctx, loading.path, loading.add, loaded);
+            },
+            decimate::AutoDecimate::Ask if large => {
+                self.decimate_prompt = Some(DecimatePrompt { path: loading.path, add: loading.add, loaded });
+            },
+            _ => self.show_loaded(ctx, loading.path, loading.add, loading.decimated, loaded)
+        }
+    }
+    /// Starts reducing a loaded mesh to the triangle threshold in the background,
+    /// unless the result is already in the disk cache
+    fn start_decimation(&mut self, ctx: &egui::Context, path: String, add: bool, loaded: Loaded) {
+        let target = self.settings.decimate_threshold;
+        let request = thread_request::Request::spawn_with(ctx, thread_request::Priority::Urgent, move |progress| {
+            let cache_key = loaded.cache_key.map(|key| format!("{}-decimated-{}", key, target));
+            if let Some(triangles) = cache_key.as_deref().and_then(mesh_cache::get_triangles) {
+                return Ok(Loaded { triangles, cache_key });
+            }
+            let triangles = decimate::decimate(&loaded.triangles, target, |fraction| progress.set(fraction));
+            if let Some(key) = &cache_key {
+                let _ = mesh_cache::put_triangles(key, &triangles);
+            }
+            return Ok(Loaded { triangles, cache_key });
+        });
+        self.loading = Some(Loading { path, add, decimated: true, request });
+    }
+    /// Puts loaded triangles in the view, either replacing the scene or adding to it,
+    /// and starts the analyses that the settings run automatically
+    fn show_loaded(&mut self, ctx: &egui::Context, input_file: String, add: bool, decimated: bool, loaded: Loaded) {
+        self.cancel_simplify();
+        let triangle_count = loaded.triangles.len();
+        let threshold = self.settings.auto_run_threshold;
+        let mut model = scene::SceneModel::new(input_file.clone(), loaded.triangles, decimated);
+        model.cache_key = loaded.cache_key;
+        if self.settings.auto_bvh.runs_for(triangle_count, threshold) {
+            model.start_bvh(ctx);
+        }
+        if let (true, Some(mesh)) = (add, &mut self.mesh) {
+            if let Err(err) = mesh.add_model(&model.triangles) {
+                self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                return;
+            }
+            self.recent_files.add(&input_file);
+            self.scene.push(model);
+            return;
+        }
+        let mut mesh_view_state = match ViewState::new(self.gl.to_owned(), &model.triangles) {
+            Ok(mesh_view_state) => mesh_view_state,
+            Err(err) => {
+                self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                return;
+            }
+        };
+        self.remember_camera();
+        self.analysis_ui.cancel_all();
+        if let Some(camera) = self.recent_files.get(&input_file).and_then(|file| file.camera.as_ref()) {
+            camera.apply(&mut mesh_view_state);
+        }
+        self.recent_files.add(&input_file);
+        self.annotation_ui.load_sidecar(&input_file);
+        self.current_file = Some(input_file);
+        let palette = self.settings.theme.palette();
+        mesh_view_state.background = palette.background;
+        mesh_view_state.overlay = palette.overlay;
+        self.issue_ui.clear(&mut mesh_view_state);
+        if self.settings.auto_issues.runs_for(triangle_count, threshold) {
+            self.issue_ui.analyze_triangles(ctx, &model.triangles, model.cache_key.as_deref());
+        }
+        self.scene = vec![model];
+        self.scene_ui = scene::SceneUI::new();
+        self.mesh = Some(mesh_view_state);
+    }
+    /// Adds a mirrored copy of the selected model, as it is placed in the scene,
+    /// beside it along an axis
+    fn duplicate_mirrored(&mut self, ctx: &egui::Context, axis: usize) {
+        self.cancel_simplify();
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        let index = self.scene_ui.selected;
+        let model = unwrap_or_return!(self.scene.get(index));
+        let placement = model.transformation_ui.get_matrix();
+        let (min, max) = unwrap_or_return!(triangle::transformed_bounds(&model.triangles, &placement));
+        // Reflecting across a plane just past the far side leaves a small gap between the pair
+        let plane = max[axis] + (max[axis] - min[axis]) * 0.05;
+        let triangles: Vec<triangle::Triangle> = model.triangles.iter()
+            .map(|t| triangle::mirror(&triangle::transform(t, &placement), axis, plane))
+            .collect();
+        let mut copy = scene::SceneModel::new(model.path.clone(), triangles, model.decimated);
+        copy.name = tr_args("scene-mirrored-name", &[("name", &model.name), ("axis", &["X", "Y", "Z"][axis])]);
+        if model.bvh.is_some() || model.building_bvh() {
+            copy.start_bvh(ctx);
+        }
+        if let Err(err) = mesh.add_model(&copy.triangles) {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+            return;
+        }
+        self.scene.push(copy);
+        self.scene_ui.selected = self.scene.len() - 1;
+        self.issue_ui.clear(mesh);
+    }
+    /// Marks the parts of the visible models outside the build volume, as they would be saved
+    fn update_warnings(&mut self) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        mesh.warnings.clear();
+        let volume = self.settings.build_volume();
+        if !volume.enabled {
+            return;
+        }
+        // Saving can include the view rotation, which turns the volume in the view
+        let view_rotation = if self.bake_view_rotation { mesh.camera.rotation } else { glm::Mat4::identity() };
+        let to_scene = view_rotation.try_inverse().unwrap_or_else(glm::Mat4::identity);
+        let mut any_outside = false;
+        for (model, view_model) in self.scene.iter_mut().zip(&mesh.models) {
+            if !view_model.visible {
+                continue;
+            }
+            let placement = model.transformation_ui.get_matrix();
+            let outside = model.outside(&(view_rotation * placement), &volume);
+            any_outside |= !outside.is_empty();
+            let room = build_volume::MAX_DRAWN_TRIANGLES.saturating_sub(mesh.warnings.len());
+            let shown: Vec<usize> = outside.iter().take(room).copied().collect();
+            mesh.warnings.extend(shown.iter()
+                .map(|t| triangle::transform(&model.triangles[*t], &placement).to_vec()));
+        }
+        if any_outside {
+            mesh.warnings.extend(volume.outline().into_iter()
+                .map(|edges| edges.iter().map(|p| to_scene.transform_point(&(*p).into()).coords).collect()));
+        }
+    }
+    /// The message after saving a mesh with the given bounds,
+    /// warning if it doesn't fit the build volume
+    fn saved_message(&self, file: &str, bounds: Option<(glm::Vec3, glm::Vec3)>) -> String {
+        let volume = self.settings.build_volume();
+        if let (true, Some((min, max))) = (volume.enabled, bounds) {
+            if !volume.contains(&min, &max) {
+                return tr_args("alert-saved-outside-volume", &[("file", &file)]);
+            }
+        }
+        return tr_args("alert-saved", &[("file", &file)]);
+    }
+    /// Closes the simplify dialog, showing the model's own triangles again
+    fn cancel_simplify(&mut self) {
+        let index = unwrap_or_return!(self.simplify_ui.model);
+        self.simplify_ui.close();
+        if let (Some(mesh), Some(model)) = (&mut self.mesh, self.scene.get(index)) {
+            if let Err(err) = mesh.replace_model(index, &model.triangles) {
+                self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+            }
+        }
+    }
+    /// Takes a model out of the scene and the view
+    fn remove_model(&mut self, index: usize) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        mesh.models.remove(index);
+        self.scene.remove(index);
+        self.scene_ui.removed(index);
+        self.issue_ui.clear(mesh);
+        if self.scene.is_empty() {
+            self.remember_camera();
+            self.mesh = None;
+            self.current_file = None;
+        }
+    }
+    /// Splits the selected model into the bodies it is made of,
+    /// each a model of its own, in its own color
+    fn separate_bodies(&mut self, ctx: &egui::Context) {
+        self.cancel_simplify();
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        let index = self.scene_ui.selected;
+        let model = unwrap_or_return!(self.scene.get(index));
+        let welded = mesh_cache::welded(model.cache_key.as_deref(), &model.triangles);
+        let bodies = welded.bodies();
+        if bodies.len() < 2 {
+            self.alert = new_alert(tr("alert-one-body"));
+            return;
+        }
+        // The bodies are already placed, so they start without transformations
+        let placement = model.transformation_ui.get_matrix();
+        let build_bvh = model.bvh.is_some() || model.building_bvh();
+        let mut parts = Vec::<scene::SceneModel>::new();
+        for (number, body) in bodies.iter().enumerate() {
+            let triangles = body.iter()
+                .map(|t| triangle::transform(&welded.triangle(*t), &placement))
+                .collect();
+            let mut part = scene::SceneModel::new(model.path.clone(), triangles, model.decimated);
+            part.name = tr_args("scene-body-name", &[("name", &model.name), ("number", &(number + 1))]);
+            if build_bvh {
+                part.start_bvh(ctx);
+            }
+            parts.push(part);
+        }
+        // The largest body replaces the model, and the rest go at the end
+        let mut parts = parts.into_iter();
+        let largest = parts.next().unwrap();
+        if let Err(err) = mesh.replace_model(index, &largest.triangles) {
+            self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+            return;
+        }
+        mesh.models[index].color = body_color(0);
+        self.scene[index] = largest;
+        for (number, part) in parts.enumerate() {
+            if let Err(err) = mesh.add_model(&part.triangles) {
+                self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                break;
+            }
+            mesh.models.last_mut().unwrap().color = body_color(number + 1);
+            self.scene.push(part);
+        }
+        self.issue_ui.clear(mesh);
+    }
+    /// Saves each visible model to its own numbered STL file, as placed in the scene
+    fn save_each_model(&mut self) {
+        let mesh = unwrap_or_return!(&self.mesh);
+        let rfd_result = rfd::FileDialog::new().add_filter("stl", &["stl", "STL"]).save_file();
+        let path = unwrap_or_return!(rfd_result).display().to_string();
+        let models: Vec<&scene::SceneModel> = self.scene.iter()
+            .zip(&mesh.models)
+            .filter(|(_, view_model)| view_model.visible)
+            .map(|(model, _)| model)
+            .collect();
+        let mut files = Vec::<String>::new();
+        for (number, model) in models.iter().enumerate() {
+            let file = mesh_file::numbered_path(&path, number + 1, models.len());
+            if let Err(err) = triangle::write_transformed_stl_binary(&file, &model.triangles, &model.transformation_ui.get_matrix()) {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+                return;
+            }
+            files.push(file);
+        }
+        self.alert = new_alert(tr_args("alert-saved-each", &[
+            ("count", &files.len()),
+            ("first", &files.first().cloned().unwrap_or_default()),
+            ("last", &files.last().cloned().unwrap_or_default())]));
+    }
+    /// Saves every visible model, as placed in the scene, together in one file
+    fn export_visible_models(&mut self) {
+        let mesh = unwrap_or_return!(&self.mesh);
+        let triangles: Vec<Triangle> = mesh.models.iter()
+            .take(self.scene.len())
+            .filter(|view_model| view_model.visible)
+            .flat_map(|view_model| view_model.scene_triangles())
+            .collect();
+        if triangles.is_empty() {
+            self.alert = new_alert(tr("alert-no-triangles"));
+            return;
+        }
+        self.save_triangles(triangles);
+    }
+    /// Asks where to save triangles, and writes them as STL or OBJ depending on the extension chosen
+    fn save_triangles(&mut self, triangles: Vec<Triangle>) {
+        let rfd_result = rfd::FileDialog::new()
+            .add_filter("stl", &["stl", "STL"])
+            .add_filter("obj", &["obj", "OBJ"])
+            .save_file();
+        let save_file = unwrap_or_return!(rfd_result).display().to_string();
+        match mesh_file::write_mesh_file(&save_file, &triangles) {
+            Err(err) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            },
+            Ok(_) => {
+                let bounds = triangle::transformed_bounds(&triangles, &glm::Mat4::identity());
+                self.alert = new_alert(self.saved_message(&save_file, bounds));
             }
         }
     }
     fn save_mesh_file_menu(&mut self) {
-        if let Some(triangles) = &self.triangles {
+        if let Some(model) = self.scene.get(self.scene_ui.selected) {
+            let triangles = &model.triangles;
             if let Some(rfd_result) = rfd::FileDialog::new().add_filter("stl", &["stl", "STL"]).save_file() {
                 let save_file = rfd_result.display().to_string();
                 match triangle::write_stl_binary(save_file.as_str(), &triangles) {
                     Err(err) => {
-                        self.alert = new_alert(format!("Could not save mesh:\n\t{}", err));
+                        self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
                     },
                     Ok(_) => {
-                        self.alert = new_alert(format!("Saved: {}", save_file));
+                        let bounds = triangle::transformed_bounds(triangles, &glm::Mat4::identity());
+                        self.alert = new_alert(self.saved_message(&save_file, bounds));
                     }
                 }
             }
         } else {
-            self.alert = new_alert("There is no triangle data to save".to_string());
+            self.alert = new_alert(tr("alert-no-triangles"));
+        }
+    }
+    fn export_issues(&mut self) {
+        let rfd_result = rfd::FileDialog::new().add_filter("csv", &["csv", "CSV"]).save_file();
+        let rfd_result = unwrap_or_return!(rfd_result);
+        let save_file = rfd_result.display().to_string();
+        match issues::write_csv(save_file.as_str(), &self.issue_ui.issues) {
+            Err(err) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            },
+            Ok(_) => {
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &save_file)]));
+            }
+        }
+    }
+    #[cfg(feature = "profiling")]
+    fn save_trace(&mut self) {
+        let rfd_result = rfd::FileDialog::new().add_filter("json", &["json", "JSON"]).save_file();
+        let rfd_result = unwrap_or_return!(rfd_result);
+        let save_file = rfd_result.display().to_string();
+        match profiling::write_trace(save_file.as_str()) {
+            Err(err) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            },
+            Ok(_) => {
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &save_file)]));
+            }
+        }
+    }
+    fn export_gltf(&mut self) {
+        let mesh = unwrap_or_return!(&self.mesh);
+        let rfd_result = rfd::FileDialog::new().add_filter("glb", &["glb", "GLB"]).save_file();
+        let rfd_result = unwrap_or_return!(rfd_result);
+        let save_file = rfd_result.display().to_string();
+        let models: Vec<(&[Triangle], glm::Mat4)> = self.scene.iter()
+            .zip(&mesh.models)
+            .filter(|(_, view_model)| view_model.visible)
+            .map(|(model, view_model)| (model.triangles.as_slice(), view_model.local_transform))
+            .collect();
+        match gltf::write_glb(save_file.as_str(), &models) {
+            Err(err) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            },
+            Ok(_) => {
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &save_file)]));
+            }
         }
     }
-    fn save_render(&mut self, width: usize, height: usize) {
+    fn save_render(&mut self, width: usize, height: usize, transparent: bool) {
         let mesh = unwrap_or_return!(&mut self.mesh);
         let rfd_result = rfd::FileDialog::new().add_filter("png", &["png", "PNG"]).save_file();
         let rfd_result = unwrap_or_return!(rfd_result);
         let save_file = rfd_result.display().to_string();
-        let pixels = match mesh.draw_pixels(width, height) {
+        let pixels = match mesh.draw_pixels(width, height, transparent) {
             Ok(x) => {x},
             Err(err) => {
-                self.alert = new_alert(format!("Could not render mesh:\n\t{}", err));
+                self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
                 return
             }
         };
@@ -222,22 +2167,366 @@ impl AppState {
             height as u32,
             image::ColorType::Rgba8) {
             Err(err) => {
-                self.alert = new_alert(format!("Could not save mesh:\n\t{}", err));
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+            },
+            Ok(_) => {
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &save_file)]));
+            }
+        }
+    }
+    /// Saves numbered images of the view turning all the way around the models, one for each frame of the turntable
+    fn save_turntable(&mut self, width: usize, height: usize, transparent: bool) {
+        let mesh = unwrap_or_return!(&self.mesh);
+        let rfd_result = rfd::FileDialog::new().add_filter("png", &["png", "PNG"]).save_file();
+        let mut path = unwrap_or_return!(rfd_result).display().to_string();
+        if !path.to_lowercase().ends_with(".png") {
+            path += ".png";
+        }
+        let frames = self.screenshot_ui.frames;
+        let mut view = mesh.clone();
+        let mut files = Vec::<String>::new();
+        for frame in 0..frames {
+            view.camera.rotation = self.screenshot_ui.turntable_rotation(&mesh.camera.rotation, frame);
+            let pixels = match view.draw_pixels(width, height, transparent) {
+                Ok(x) => {x},
+                Err(err) => {
+                    self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                    return
+                }
+            };
+            let file = mesh_file::numbered_path(&path, frame + 1, frames);
+            if let Err(err) = image::save_buffer(&file, pixels.as_slice(), width as u32, height as u32, image::ColorType::Rgba8) {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
+                return;
+            }
+            files.push(file);
+        }
+        self.alert = new_alert(tr_args("alert-saved-frames", &[
+            ("count", &files.len()),
+            ("first", &files.first().cloned().unwrap_or_default()),
+            ("last", &files.last().cloned().unwrap_or_default())]));
+    }
+    fn save_depth(&mut self, width: usize, height: usize) {
+        let mesh = unwrap_or_return!(&mut self.mesh);
+        let rfd_result = rfd::FileDialog::new().add_filter("png", &["png", "PNG"]).save_file();
+        let rfd_result = unwrap_or_return!(rfd_result);
+        let save_file = rfd_result.display().to_string();
+        let depths = match mesh.draw_depths(width, height) {
+            Ok(x) => {x},
+            Err(err) => {
+                self.alert = new_alert(tr_args("alert-render-failed", &[("error", &err)]));
+                return
+            }
+        };
+        let heights = height_map(&depths);
+        match image::save_buffer(
+            &save_file,
+            bytemuck::cast_slice(&heights),
+            width as u32,
+            height as u32,
+            image::ColorType::L16) {
+            Err(err) => {
+                self.alert = new_alert(tr_args("alert-save-failed", &[("error", &err)]));
             },
             Ok(_) => {
-                self.alert = new_alert(format!("Saved: {}", save_file));
+                self.alert = new_alert(tr_args("alert-saved", &[("file", &save_file)]));
             }
         }
     }
 }
 
+/// Turns depths from 0 to 1 into 16 bit heights, where the nearest of the models is white and the farthest is
+/// just above black. Pixels where nothing was drawn have a depth of 1, and are black.
+fn height_map(depths: &[f32]) -> Vec<u16> {
+    let drawn = || depths.iter().filter(|depth| **depth < 1.);
+    let nearest = drawn().fold(1., |a: f32, b| a.min(*b));
+    let farthest = drawn().fold(0., |a: f32, b| a.max(*b));
+    let range = (farthest - nearest).max(f32::EPSILON);
+    return depths.iter().map(|depth| {
+        if *depth >= 1. {
+            return 0;
+        }
+        return (1. + (farthest - depth) / range * 65534.).round() as u16;
+    }).collect();
+}
+
+/// A distinct color for each of the bodies a model is separated into
+fn body_color(number: usize) -> [f32; 3] {
+    // Stepping the hue by the golden ratio keeps neighbors far apart however many there are
+    let hue = (number as f32 * 0.618034).fract();
+    let color = egui::Rgba::from(egui::color::Hsva::new(hue, 0.45, 1., 1.));
+    return [color.r(), color.g(), color.b()];
+}
+
+/// Prints the statistics of each file named on the command line,
+/// returning the exit code
+fn print_file_info(paths: &[String]) -> i32 {
+    i18n::set_language(i18n::Language::from_environment().unwrap_or(i18n::Language::English));
+    let mut code = 0;
+    for path in paths {
+        println!("{}", path);
+        match file_info::file_info(path, |_| true) {
+            Ok(info) => {
+                for line in info.lines("") {
+                    println!("  {}", line);
+                }
+            },
+            Err(err) => {
+                eprintln!("  {}", tr_args("info-failed", &[("error", &err)]));
+                code = 1;
+            }
+        }
+    }
+    return code;
+}
+
+/// Compares two mesh files, for `meshtools diff A B [--tolerance T]`.
+/// Returns 0 if they match, 1 if they differ, and 2 if they couldn't be compared.
+fn print_diff(args: &[String]) -> i32 {
+    i18n::set_language(i18n::Language::from_environment().unwrap_or(i18n::Language::English));
+    let mut paths = Vec::<&String>::new();
+    let mut tolerance = 0.;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg != "--tolerance" {
+            paths.push(arg);
+            continue;
+        }
+        match rest.next().and_then(|value| value.parse::<f32>().ok()) {
+            Some(value) => tolerance = value,
+            None => {
+                eprintln!("{}", tr("diff-usage"));
+                return 2;
+            }
+        }
+    }
+    if paths.len() != 2 {
+        eprintln!("{}", tr("diff-usage"));
+        return 2;
+    }
+    let mut meshes = Vec::new();
+    for path in &paths {
+        match mesh_file::read_mesh_file(path) {
+            Ok(triangles) => meshes.push(triangles),
+            Err(err) => {
+                eprintln!("{}", tr_args("diff-failed", &[("file", path), ("error", &err)]));
+                return 2;
+            }
+        }
+    }
+    let diff = mesh_diff::MeshDiff::new(&meshes[0], &meshes[1]);
+    println!("{}", tr_args("diff-header", &[("first", paths[0]), ("second", paths[1])]));
+    for line in diff.lines(tolerance) {
+        println!("{}", line);
+    }
+    if diff.matches(tolerance) {
+        println!("{}", tr("diff-match"));
+        return 0;
+    }
+    println!("{}", tr("diff-differ"));
+    return 1;
+}
+
+/// Runs the intake pipeline on files arriving in a directory, for
+/// `meshtools watch INCOMING OUTPUT [--interval SECONDS] [--fill flat|smooth] [--thumbnail PIXELS]`.
+/// Only returns, with an exit code, if the arguments are wrong or a directory can't be used.
+fn watch(args: &[String]) -> i32 {
+    i18n::set_language(i18n::Language::from_environment().unwrap_or(i18n::Language::English));
+    let mut directories = Vec::<&String>::new();
+    let mut interval = 2.;
+    let mut pipeline = watch_folder::Pipeline { hole_fill: triangle_mesh::HoleFill::Smooth, thumbnail_size: 256 };
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let value = match arg.as_str() {
+            "--interval" | "--fill" | "--thumbnail" => rest.next().map(|value| value.as_str()),
+            _ => {
+                directories.push(arg);
+                continue;
+            }
+        };
+        let valid = match (arg.as_str(), value) {
+            ("--interval", Some(value)) => match value.parse::<f32>() {
+                Ok(seconds) if seconds > 0. => { interval = seconds; true },
+                _ => false
+            },
+            ("--fill", Some("flat")) => { pipeline.hole_fill = triangle_mesh::HoleFill::Flat; true },
+            ("--fill", Some("smooth")) => { pipeline.hole_fill = triangle_mesh::HoleFill::Smooth; true },
+            ("--thumbnail", Some(value)) => match value.parse::<usize>() {
+                Ok(size) if size <= thumbnail::MAX_SIZE => { pipeline.thumbnail_size = size; true },
+                _ => false
+            },
+            _ => false
+        };
+        if !valid {
+            eprintln!("{}", tr("watch-usage"));
+            return 2;
+        }
+    }
+    if directories.len() != 2 {
+        eprintln!("{}", tr("watch-usage"));
+        return 2;
+    }
+    let (incoming, output) = (std::path::Path::new(directories[0]), std::path::Path::new(directories[1]));
+    println!("{}", tr_args("watch-started", &[("incoming", directories[0]), ("output", directories[1])]));
+    let result = watch_folder::watch(incoming, output, std::time::Duration::from_secs_f32(interval), &pipeline,
+        |line| println!("{}", line));
+    if let Err(err) = result {
+        eprintln!("{}", tr_args("watch-stopped", &[("error", &err)]));
+    }
+    return 1;
+}
+
+/// Lists the statistics and issues of a mesh file, for `meshtools analyze FILE [--thin-wall DISTANCE]`.
+/// Walls are only checked for thinness when a distance is given.
+/// Returns 0 if no issues were found, 1 if some were, and 2 if the file couldn't be analyzed.
+fn analyze(args: &[String]) -> i32 {
+    i18n::set_language(i18n::Language::from_environment().unwrap_or(i18n::Language::English));
+    let mut paths = Vec::<&String>::new();
+    let mut thin_wall = 0.;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg != "--thin-wall" {
+            paths.push(arg);
+            continue;
+        }
+        match rest.next().and_then(|value| value.parse::<f32>().ok()) {
+            Some(value) if value >= 0. => thin_wall = value,
+            _ => {
+                eprintln!("{}", tr("analyze-usage"));
+                return 2;
+            }
+        }
+    }
+    if paths.len() != 1 {
+        eprintln!("{}", tr("analyze-usage"));
+        return 2;
+    }
+    let triangles = match mesh_file::read_mesh_file(paths[0]) {
+        Ok(triangles) => triangles,
+        Err(err) => {
+            eprintln!("{}", tr_args("info-failed", &[("error", &err)]));
+            return 2;
+        }
+    };
+    println!("{}", paths[0]);
+    for line in file_info::FileInfo::from_triangles(&triangles).lines("") {
+        println!("  {}", line);
+    }
+    let mesh = triangle_mesh::TriangleMesh::new(&triangles, triangle_mesh::default_tolerance(&triangles));
+    let found = issues::find_issues(&mesh, thin_wall);
+    for kind in issues::IssueKind::ALL {
+        let count = found.iter().filter(|issue| issue.kind == kind).count();
+        if count > 0 {
+            println!("{}", tr_args("analyze-issues", &[("kind", &kind.label()), ("count", &count)]));
+        }
+    }
+    if found.is_empty() {
+        println!("{}", tr("analyze-clean"));
+        return 0;
+    }
+    return 1;
+}
+
+/// Rewrites a mesh file in another format, for `meshtools convert INPUT OUTPUT`.
+/// Returns 0 if it was written, and 2 otherwise.
+fn convert(args: &[String]) -> i32 {
+    i18n::set_language(i18n::Language::from_environment().unwrap_or(i18n::Language::English));
+    if args.len() != 2 {
+        eprintln!("{}", tr("convert-usage"));
+        return 2;
+    }
+    let triangles = match mesh_file::read_mesh_file(&args[0]) {
+        Ok(triangles) => triangles,
+        Err(err) => {
+            eprintln!("{}", tr_args("diff-failed", &[("file", &args[0]), ("error", &err)]));
+            return 2;
+        }
+    };
+    if let Err(err) = mesh_file::write_mesh_file(&args[1], &triangles) {
+        eprintln!("{}", tr_args("alert-save-failed", &[("error", &err)]));
+        return 2;
+    }
+    println!("{}", tr_args("alert-saved", &[("file", &args[1])]));
+    return 0;
+}
+
+/// Draws a mesh file to a PNG, for `meshtools render INPUT OUTPUT [--size WIDTHxHEIGHT]`.
+/// It is drawn on the CPU like the watch folder's thumbnails, so it runs without a display.
+/// Returns 0 if the image was written, and 2 otherwise.
+fn render(args: &[String]) -> i32 {
+    i18n::set_language(i18n::Language::from_environment().unwrap_or(i18n::Language::English));
+    let mut paths = Vec::<&String>::new();
+    let (mut width, mut height) = (1024, 1024);
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg != "--size" {
+            paths.push(arg);
+            continue;
+        }
+        let size = rest.next()
+            .and_then(|value| value.split_once('x'))
+            .and_then(|(w, h)| Some((w.parse::<usize>().ok()?, h.parse::<usize>().ok()?)));
+        match size {
+            Some((w, h)) if (1..=thumbnail::MAX_SIZE).contains(&w) && (1..=thumbnail::MAX_SIZE).contains(&h) => (width, height) = (w, h),
+            _ => {
+                eprintln!("{}", tr("render-usage"));
+                return 2;
+            }
+        }
+    }
+    if paths.len() != 2 {
+        eprintln!("{}", tr("render-usage"));
+        return 2;
+    }
+    let triangles = match mesh_file::read_mesh_file(paths[0]) {
+        Ok(triangles) => triangles,
+        Err(err) => {
+            eprintln!("{}", tr_args("diff-failed", &[("file", paths[0]), ("error", &err)]));
+            return 2;
+        }
+    };
+    if let Err(err) = thumbnail::save(paths[1], &triangles, width, height) {
+        eprintln!("{}", tr_args("alert-save-failed", &[("error", &err)]));
+        return 2;
+    }
+    println!("{}", tr_args("alert-saved", &[("file", paths[1])]));
+    return 0;
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // `meshtools info FILE...` prints statistics without opening a window
+    if args.get(1).map(|arg| arg.as_str()) == Some("info") {
+        std::process::exit(print_file_info(&args[2..]));
+    }
+    // `meshtools diff A B --tolerance T` checks that a result hasn't changed, for scripts and CI
+    if args.get(1).map(|arg| arg.as_str()) == Some("diff") {
+        std::process::exit(print_diff(&args[2..]));
+    }
+    // `meshtools watch INCOMING OUTPUT` runs unattended, repairing and reporting on each file that arrives
+    if args.get(1).map(|arg| arg.as_str()) == Some("watch") {
+        std::process::exit(watch(&args[2..]));
+    }
+    // `meshtools analyze FILE` lists the problems found in a mesh, for scripts
+    if args.get(1).map(|arg| arg.as_str()) == Some("analyze") {
+        std::process::exit(analyze(&args[2..]));
+    }
+    // `meshtools convert INPUT OUTPUT` rewrites a mesh in the format of the output's extension
+    if args.get(1).map(|arg| arg.as_str()) == Some("convert") {
+        std::process::exit(convert(&args[2..]));
+    }
+    // `meshtools render INPUT OUTPUT` draws a mesh to a PNG without a window or graphics card
+    if args.get(1).map(|arg| arg.as_str()) == Some("render") {
+        std::process::exit(render(&args[2..]));
+    }
+    #[cfg(feature = "profiling")]
+    profiling::init();
     let mut options = eframe::NativeOptions::default();
     options.initial_window_size = Some(egui::vec2(800., 600.));
     eframe::run_native(
         "Mesh Tools",
         options,
         Box::new(|cc|
-            Box::new(AppState::new(cc.gl.to_owned().expect("Could not get gl context"))))
+            Box::new(AppState::new(cc)))
     )
 }
\ No newline at end of file
@@ -0,0 +1,440 @@
+use std::io::Error;
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::i18n::{tr, tr_args};
+use crate::measurement::{self, Measurement, MeasurementKind, MeasurementTemplate, Snap};
+use crate::settings::Settings;
+use crate::units::Unit;
+
+/// What the application should do after the measurement window is shown
+pub enum MeasurementResponse {
+    None,
+    /// The report was written to this file, or failed to be
+    Exported(Result<String, Error>)
+}
+
+/// What clicking the scene does while the measurement window is open
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MeasureTool {
+    /// Place the next landmark of the template
+    Template,
+    /// Measure the distance between two points
+    Distance,
+    /// Measure the angle at the second of three points
+    Angle
+}
+
+impl MeasureTool {
+    pub const ALL: [MeasureTool; 3] = [MeasureTool::Template, MeasureTool::Distance, MeasureTool::Angle];
+
+    pub fn label(&self) -> String {
+        match self {
+            MeasureTool::Template => tr("measure-tool-template"),
+            MeasureTool::Distance => tr("measure-distance"),
+            MeasureTool::Angle => tr("measure-angle")
+        }
+    }
+
+    /// Points picked for one measurement with the tool
+    fn points(&self) -> usize {
+        match self {
+            MeasureTool::Template => 0,
+            MeasureTool::Distance => 2,
+            MeasureTool::Angle => 3
+        }
+    }
+}
+
+/// A window to fill in a measurement template by placing its landmarks on the scene
+/// in order, and to set up the templates.
+/// Distances and angles can also be measured between any points, and are listed until cleared.
+pub struct MeasurementUI {
+    pub open: bool,
+    pub tool: MeasureTool,
+    /// Where picked points move to on the face under the cursor
+    pub snap: Snap,
+    /// Index of the template being filled in
+    pub template: usize,
+    /// Where each landmark of the template was placed in the scene, in the template's order
+    pub points: Vec<Option<Vec3>>,
+    /// Points picked so far for the next distance or angle
+    picked: Vec<Vec3>,
+    /// Distances and angles measured with the tools, each with the points it was taken between
+    results: Vec<(Measurement, Vec<Option<Vec3>>)>,
+    /// Show the template editor rather than the report
+    editing: bool
+}
+
+impl MeasurementUI {
+    pub fn new() -> Self {
+        return Self {
+            open: false,
+            tool: MeasureTool::Template,
+            snap: Snap::Off,
+            template: 0,
+            points: Vec::new(),
+            picked: Vec::new(),
+            results: Vec::new(),
+            editing: false
+        };
+    }
+
+    /// Clears the placed landmarks, to start the template again, and the points picked
+    /// for an unfinished distance or angle
+    pub fn restart(&mut self) {
+        self.points.clear();
+        self.picked.clear();
+    }
+
+    /// The landmark the next click on the scene places, while a template is being filled in
+    pub fn next_landmark(&self, settings: &Settings) -> Option<usize> {
+        if !self.open || self.editing || self.tool != MeasureTool::Template {
+            return None;
+        }
+        let count = settings.templates.get(self.template)?.landmarks.len();
+        return (0..count).find(|i| self.points.get(*i).copied().flatten().is_none());
+    }
+
+    /// Places the next landmark at a point picked on the scene, or the next point of a distance
+    /// or angle, which is listed with the results once all its points are picked
+    pub fn place(&mut self, settings: &Settings, point: Vec3) {
+        if self.open && self.tool != MeasureTool::Template {
+            self.picked.push(point);
+            if self.picked.len() < self.tool.points() {
+                return;
+            }
+            let number = self.results.len() + 1;
+            let (name, kind) = match self.tool {
+                MeasureTool::Angle => (tr_args("measure-angle-name", &[("number", &number)]), MeasurementKind::Angle(0, 1, 2)),
+                _ => (tr_args("measure-distance-name", &[("number", &number)]), MeasurementKind::Distance(0, 1))
+            };
+            let points = self.picked.drain(..).map(Some).collect();
+            self.results.push((Measurement { name, kind }, points));
+            return;
+        }
+        if let Some(index) = self.next_landmark(settings) {
+            if self.points.len() <= index {
+                self.points.resize(index + 1, None);
+            }
+            self.points[index] = Some(point);
+        }
+    }
+
+    /// Markers for the placed landmarks and picked points, of about the given size, with one where
+    /// the cursor would place the next point. Lines join the points of each distance and angle.
+    pub fn markers(&self, size: f32, cursor: Option<Vec3>) -> Vec<Vec<Vec3>> {
+        if !self.open {
+            return Vec::new();
+        }
+        let mut markers = Vec::new();
+        let measured = self.results.iter().flat_map(|(_, points)| points.iter().flatten());
+        for (_, points) in &self.results {
+            let points: Vec<Vec3> = points.iter().flatten().copied().collect();
+            for pair in points.windows(2) {
+                markers.push(pair.to_vec());
+            }
+        }
+        for pair in self.picked.windows(2) {
+            markers.push(pair.to_vec());
+        }
+        let placing = cursor.filter(|_| {
+            return self.tool != MeasureTool::Template || (!self.editing && self.points.iter().any(|p| p.is_none()));
+        });
+        for point in self.points.iter().flatten().chain(measured).chain(&self.picked).chain(&placing) {
+            // A diamond in each axis plane, so the marker is seen from any side
+            for axis in 0..3 {
+                let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+                markers.push([(1., 0.), (0., 1.), (-1., 0.), (0., -1.)].iter()
+                    .map(|(first, second)| {
+                        let mut corner = *point;
+                        corner[a] += first * size;
+                        corner[b] += second * size;
+                        corner
+                    })
+                    .collect());
+            }
+        }
+        return markers;
+    }
+
+    /// Shows the window if it is open. Templates are edited in the settings, so they persist.
+    pub fn show(&mut self, ctx: &egui::Context, settings: &mut Settings, unit: Unit) -> MeasurementResponse {
+        if !self.open {
+            return MeasurementResponse::None;
+        }
+        if settings.templates.is_empty() {
+            settings.templates.push(MeasurementTemplate::example());
+        }
+        let mut template = self.template.min(settings.templates.len() - 1);
+        let mut export = false;
+        let mut open = true;
+        egui::Window::new(tr("measure"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("measure-tool"));
+                for tool in MeasureTool::ALL {
+                    if ui.radio_value(&mut self.tool, tool, tool.label()).changed() {
+                        self.picked.clear();
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("measure-snap"));
+                for snap in Snap::ALL {
+                    ui.radio_value(&mut self.snap, snap, snap.label());
+                }
+            });
+            ui.separator();
+            if self.tool != MeasureTool::Template {
+                self.pick_points(ui);
+                self.results(ui, unit);
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.label(tr("measure-template"));
+                egui::ComboBox::from_id_source("measure_template")
+                    .selected_text(&settings.templates[template].name)
+                    .show_ui(ui, |ui| {
+                    for (i, t) in settings.templates.iter().enumerate() {
+                        ui.selectable_value(&mut template, i, &t.name);
+                    }
+                });
+                ui.toggle_value(&mut self.editing, tr("measure-edit"));
+            });
+            ui.separator();
+            if self.editing {
+                if let Some(added) = self.edit_templates(ui, settings, template) {
+                    template = added;
+                }
+                template = template.min(settings.templates.len() - 1);
+            } else {
+                export = self.report(ui, &settings.templates[template], unit);
+            }
+            self.results(ui, unit);
+        });
+        if template != self.template {
+            self.template = template;
+            self.restart();
+        }
+        if !open {
+            self.open = false;
+            return MeasurementResponse::None;
+        }
+        if export {
+            let path = rfd::FileDialog::new().add_filter("csv", &["csv", "CSV"]).save_file();
+            if let Some(path) = path {
+                let path = path.display().to_string();
+                let result = measurement::write_report(&path, &settings.templates[self.template], &self.points, unit.suffix());
+                return MeasurementResponse::Exported(result.map(|_| path));
+            }
+        }
+        return MeasurementResponse::None;
+    }
+
+    /// Shows which point of the distance or angle the next click picks
+    fn pick_points(&mut self, ui: &mut egui::Ui) {
+        let key = if self.picked.is_empty() {
+            "measure-pick-first"
+        } else if self.picked.len() + 1 == self.tool.points() {
+            "measure-pick-last"
+        } else {
+            "measure-pick-vertex"
+        };
+        ui.strong(tr(key));
+        if ui.add_enabled(!self.picked.is_empty(), egui::Button::new(tr("measure-back"))).clicked() {
+            self.picked.pop();
+        }
+    }
+
+    /// Lists the distances and angles measured with the tools, until they are cleared
+    fn results(&mut self, ui: &mut egui::Ui, unit: Unit) {
+        if self.results.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.label(tr("measure-results"));
+        let suffix = unit.suffix();
+        let mut removed = None;
+        egui::ScrollArea::vertical().id_source("measure_tool_results").max_height(160.).show(ui, |ui| {
+            egui::Grid::new("measure_tool_results").striped(true).show(ui, |ui| {
+                for (i, (measurement, points)) in self.results.iter().enumerate() {
+                    ui.label(&measurement.name);
+                    ui.label(match (measurement.value(points), &measurement.kind) {
+                        (Some(value), MeasurementKind::Distance(..)) => format!("{:.3} {}", value, suffix),
+                        (Some(value), MeasurementKind::Angle(..)) => format!("{:.2}°", value),
+                        (None, _) => "—".to_string()
+                    });
+                    if ui.small_button("🗑").on_hover_text(tr("measure-remove")).clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(i) = removed {
+            self.results.remove(i);
+        }
+        if ui.button(tr("measure-clear")).clicked() {
+            self.results.clear();
+        }
+    }
+
+    /// Shows the landmarks to place and the measurements taken so far.
+    /// Returns true if the report should be exported.
+    fn report(&mut self, ui: &mut egui::Ui, template: &MeasurementTemplate, unit: Unit) -> bool {
+        let count = template.landmarks.len();
+        self.points.resize(count, None);
+        match (0..count).find(|i| self.points[*i].is_none()) {
+            Some(next) => ui.strong(tr_args("measure-place", &[
+                ("name", &template.landmarks[next]),
+                ("number", &(next + 1)),
+                ("count", &count)])),
+            None => ui.strong(tr("measure-done"))
+        };
+        let suffix = unit.suffix();
+        egui::Grid::new("measure_landmarks").striped(true).show(ui, |ui| {
+            for (name, point) in template.landmarks.iter().zip(&self.points) {
+                ui.label(name);
+                ui.label(match point {
+                    Some(p) => format!("{:.3}, {:.3}, {:.3} {}", p.x, p.y, p.z, suffix),
+                    None => "—".to_string()
+                });
+                ui.end_row();
+            }
+        });
+        ui.horizontal(|ui| {
+            let last = self.points.iter().rposition(|p| p.is_some());
+            if ui.add_enabled(last.is_some(), egui::Button::new(tr("measure-back"))).clicked() {
+                self.points[last.unwrap()] = None;
+            }
+            if ui.button(tr("measure-restart")).clicked() {
+                self.restart();
+            }
+        });
+        ui.separator();
+        egui::Grid::new("measure_results").striped(true).show(ui, |ui| {
+            for measurement in &template.measurements {
+                ui.label(&measurement.name);
+                ui.label(match (measurement.value(&self.points), &measurement.kind) {
+                    (Some(value), MeasurementKind::Distance(..)) => format!("{:.3} {}", value, suffix),
+                    (Some(value), MeasurementKind::Angle(..)) => format!("{:.2}°", value),
+                    (None, _) => "—".to_string()
+                });
+                ui.end_row();
+            }
+        });
+        return ui.button(tr("measure-export")).clicked();
+    }
+
+    /// Shows the editor for the templates, starting with the selected one.
+    /// Returns the index of a template that was added, to select it.
+    fn edit_templates(&mut self, ui: &mut egui::Ui, settings: &mut Settings, index: usize) -> Option<usize> {
+        let mut added = None;
+        let mut remove_template = false;
+        ui.horizontal(|ui| {
+            if ui.button(tr("measure-new")).clicked() {
+                settings.templates.push(MeasurementTemplate {
+                    name: tr("measure-new-name"),
+                    landmarks: Vec::new(),
+                    measurements: Vec::new()
+                });
+                added = Some(settings.templates.len() - 1);
+            }
+            if ui.add_enabled(settings.templates.len() > 1, egui::Button::new(tr("measure-remove-template"))).clicked() {
+                remove_template = true;
+            }
+        });
+        if remove_template {
+            settings.templates.remove(index);
+            return None;
+        }
+        if added.is_some() {
+            return added;
+        }
+        let template = &mut settings.templates[index];
+        ui.horizontal(|ui| {
+            ui.label(tr("measure-name"));
+            ui.text_edit_singleline(&mut template.name);
+        });
+
+        ui.label(tr("measure-landmarks"));
+        let mut removed = None;
+        for (i, name) in template.landmarks.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}.", i + 1));
+                ui.text_edit_singleline(name);
+                if ui.small_button("🗑").on_hover_text(tr("measure-remove")).clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            template.remove_landmark(i);
+            if i < self.points.len() {
+                self.points.remove(i);
+            }
+        }
+        if ui.button(tr("measure-add-landmark")).clicked() {
+            template.landmarks.push(tr_args("measure-landmark-name", &[("number", &(template.landmarks.len() + 1))]));
+        }
+
+        ui.separator();
+        ui.label(tr("measure-measurements"));
+        let landmarks = template.landmarks.clone();
+        let landmark_combo = |ui: &mut egui::Ui, id: String, value: &mut usize| {
+            egui::ComboBox::from_id_source(id)
+                .selected_text(landmarks.get(*value).map_or("", |name| name.as_str()))
+                .show_ui(ui, |ui| {
+                for (i, name) in landmarks.iter().enumerate() {
+                    ui.selectable_value(value, i, name);
+                }
+            });
+        };
+        let mut removed = None;
+        for (i, measurement) in template.measurements.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut measurement.name);
+                match &mut measurement.kind {
+                    MeasurementKind::Distance(a, b) => {
+                        ui.label(tr("measure-distance"));
+                        landmark_combo(ui, format!("measure_{}_a", i), a);
+                        landmark_combo(ui, format!("measure_{}_b", i), b);
+                    },
+                    MeasurementKind::Angle(a, vertex, b) => {
+                        ui.label(tr("measure-angle"));
+                        landmark_combo(ui, format!("measure_{}_a", i), a);
+                        landmark_combo(ui, format!("measure_{}_vertex", i), vertex);
+                        landmark_combo(ui, format!("measure_{}_b", i), b);
+                    }
+                }
+                if ui.small_button("🗑").on_hover_text(tr("measure-remove")).clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            template.measurements.remove(i);
+        }
+        ui.horizontal(|ui| {
+            let count = template.landmarks.len();
+            if ui.add_enabled(count >= 2, egui::Button::new(tr("measure-add-distance"))).clicked() {
+                template.measurements.push(Measurement {
+                    name: tr("measure-distance"),
+                    kind: MeasurementKind::Distance(0, 1)
+                });
+            }
+            if ui.add_enabled(count >= 3, egui::Button::new(tr("measure-add-angle")))
+                .on_hover_text(tr("measure-add-angle-hint"))
+                .clicked() {
+                template.measurements.push(Measurement {
+                    name: tr("measure-angle"),
+                    kind: MeasurementKind::Angle(0, 1, 2)
+                });
+            }
+        });
+        return None;
+    }
+}
@@ -0,0 +1,99 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Qua, Vec3};
+
+/// Seconds a transition between viewpoints takes
+pub const TRANSITION_SECONDS: f32 = 0.3;
+
+/// Where the scene is seen from: what point is in the middle of the view,
+/// how large it is drawn, and which way it is turned
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera {
+    /// Moves the scene so the point in the middle of the view is at the origin
+    pub translation: Vec3,
+    /// Size of the scene in the view, where the view's height is 2
+    pub scale: f32,
+    /// Turns the scene toward the viewer
+    pub rotation: Mat4
+}
+
+impl Camera {
+    /// Combines the camera with a stretch along each axis of the scene into a single transformation
+    pub fn matrix(&self, exaggeration: &Vec3) -> Mat4 {
+        let scale = glm::scale(&Mat4::identity(), &Vec3::new(self.scale, self.scale, self.scale));
+        let exaggeration = glm::scale(&Mat4::identity(), exaggeration);
+        let translation = glm::translate(&Mat4::identity(), &self.translation);
+        return self.rotation * scale * exaggeration * translation;
+    }
+
+    /// Centers the view on a point, scaled so that a sphere
+    /// with the given radius fills half the height of the view
+    pub fn frame(&mut self, center: Vec3, radius: f32) {
+        self.translation = -center;
+        if radius > 0. {
+            self.scale = 0.5 / radius;
+        }
+    }
+
+    /// Centers the view on a box, scaled so it fits in the view from any direction, with a margin
+    pub fn fit(&mut self, min: &Vec3, max: &Vec3) {
+        let radius = glm::distance(min, max) / 2.;
+        // `frame` fills half the view's height, and this fills most of it
+        self.frame((min + max) / 2., radius * 0.55);
+    }
+
+    /// The camera a fraction of the way to another, turning along the shortest arc
+    /// and zooming evenly in and out
+    pub fn interpolate(&self, other: &Camera, t: f32) -> Camera {
+        let rotation = glm::quat_to_mat4(&slerp(&glm::to_quat(&self.rotation), &glm::to_quat(&other.rotation), t));
+        return Camera {
+            // The center moves in a straight line through the scene
+            translation: glm::lerp(&self.translation, &other.translation, t),
+            scale: self.scale * (other.scale / self.scale).powf(t),
+            rotation
+        };
+    }
+}
+
+/// Spherical interpolation between two rotations, which doesn't fail when they are the same
+fn slerp(from: &Qua<f32>, to: &Qua<f32>, t: f32) -> Qua<f32> {
+    let mut to = *to;
+    let mut cosine = from.coords.dot(&to.coords);
+    // Both signs of a quaternion are the same rotation, and the nearer one turns the short way
+    if cosine < 0. {
+        to = -to;
+        cosine = -cosine;
+    }
+    if cosine > 0.9995 {
+        return glm::quat_normalize(&(from + (to - from) * t));
+    }
+    let angle = cosine.acos();
+    return (from * ((1. - t) * angle).sin() + to * (t * angle).sin()) / angle.sin();
+}
+
+/// A camera moving smoothly from one viewpoint to another
+#[derive(Clone)]
+pub struct Transition {
+    from: Camera,
+    pub to: Camera,
+    /// Seconds since the transition started
+    elapsed: f32
+}
+
+impl Transition {
+    pub fn new(from: Camera, to: Camera) -> Self {
+        return Self { from, to, elapsed: 0. };
+    }
+
+    /// Moves the transition along by some seconds, returning where the camera is,
+    /// and whether it has arrived
+    pub fn advance(&mut self, seconds: f32) -> (Camera, bool) {
+        self.elapsed += seconds;
+        let t = (self.elapsed / TRANSITION_SECONDS).clamp(0., 1.);
+        if t >= 1. {
+            return (self.to, true);
+        }
+        // Eases in and out, so the view doesn't jerk at either end
+        let eased = t * t * (3. - 2. * t);
+        return (self.from.interpolate(&self.to, eased), false);
+    }
+}
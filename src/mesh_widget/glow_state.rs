@@ -1,52 +1,132 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
 
 use eframe::egui_glow::glow;
 extern crate nalgebra_glm as glm;
 
-use super::Triangle;
+use super::{RenderError, Triangle};
+use crate::mesh_file::PointCloud;
 
 pub struct GlowState {
     pub vertex_buffer: glow::Buffer,
     pub vertex_array: glow::VertexArray,
     pub triangle_count: usize,
-    pub shader_program: glow::Program,
+    /// Triangles with any area, which are the ones that can cover pixels. Those without
+    /// are uploaded too, so that the index of each primitive matches its triangle.
+    pub rendered_count: usize,
+    /// Index of this buffer's first triangle in the whole model
+    pub first_triangle: usize,
+    /// Middle of the box around the triangles, for drawing transparent buffers back to front
+    pub center: glm::Vec3,
+    /// Half the diagonal of the box around the triangles
+    pub radius: f32,
+    pub shaders: Arc<Shaders>,
     pub gl: Arc<glow::Context>
 }
 
+/// The shader programs the models are drawn with, shared by every GlowState of a gl context,
+/// so they are compiled once rather than for each buffer.
+///
+/// The programs are deleted when the last GlowState using them is dropped.
+pub struct Shaders {
+    pub program: glow::Program,
+    /// Draws the model id and triangle index of each pixel, for picking
+    pub id_program: glow::Program,
+    /// Draws point clouds as round dots
+    pub point_program: glow::Program,
+    gl: Arc<glow::Context>
+}
+
+/// The shaders of each gl context that has any models, which are compiled the first time they are needed
+static SHADERS: Mutex<Vec<Weak<Shaders>>> = Mutex::new(Vec::new());
+
+impl Shaders {
+    /// The shaders for a gl context, compiling them if no models use them yet
+    pub fn get(gl: &Arc<glow::Context>) -> Result<Arc<Self>, RenderError> {
+        use glow::HasContext as _;
+        let mut cache = SHADERS.lock().unwrap();
+        cache.retain(|shaders| shaders.strong_count() > 0);
+        if let Some(shaders) = cache.iter().filter_map(Weak::upgrade).find(|shaders| Arc::ptr_eq(&shaders.gl, gl)) {
+            return Ok(shaders);
+        }
+        let program = create_shader_program(gl, VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE)?;
+        let id_program = match create_shader_program(gl, ID_VERTEX_SHADER_SOURCE, ID_FRAGMENT_SHADER_SOURCE) {
+            Ok(id_program) => id_program,
+            Err(err) => {
+                unsafe { gl.delete_program(program); }
+                return Err(err);
+            }
+        };
+        let point_program = match create_shader_program(gl, POINT_VERTEX_SHADER_SOURCE, POINT_FRAGMENT_SHADER_SOURCE) {
+            Ok(point_program) => point_program,
+            Err(err) => {
+                unsafe {
+                    gl.delete_program(program);
+                    gl.delete_program(id_program);
+                }
+                return Err(err);
+            }
+        };
+        let shaders = Arc::new(Self { program, id_program, point_program, gl: gl.clone() });
+        cache.push(Arc::downgrade(&shaders));
+        return Ok(shaders);
+    }
+}
+
+impl Drop for Shaders {
+    fn drop(&mut self) {
+        use glow::HasContext as _;
+        unsafe {
+            self.gl.delete_program(self.program);
+            self.gl.delete_program(self.id_program);
+            self.gl.delete_program(self.point_program);
+        }
+    }
+}
+
 impl GlowState {
     /// Creates a GlowState from a list of Triangles
     ///
-    /// This function creates buffers and shaders for the gl context,
-    /// which are cleaned up when the GlowState is dropped.
+    /// This function creates buffers for the gl context, which are cleaned up
+    /// when the GlowState is dropped. The shaders are shared; see `Shaders`.
     ///
     /// A successful result is wrapped in an Arc to allow a clear
     /// way to clone this state without risking the GL data being destroyed
     /// while there is still a copy of the state being used.
-    pub fn new(gl: Arc<glow::Context>, triangles: &Vec::<Triangle>) -> Result<Arc<Self>, String> {
+    ///
+    /// The triangles may be part of a larger model, starting at `first_triangle`.
+    pub fn new(gl: Arc<glow::Context>, triangles: &[Triangle], first_triangle: usize) -> Result<Arc<Self>, RenderError> {
         use glow::HasContext as _;
-        let mut triangle_vertices = Vec::<f32>::new();
-        for t in triangles {
-            // Only add triangles with non-zero area
-            let cross_product = glm::cross(&(t[1] - t[0]), &(t[2] - t[0]));
-            if glm::dot(&cross_product, &cross_product) > 0.0 {
-                let normal = cross_product.normalize();
-                for v in t {
-                    triangle_vertices.append(&mut vec![v.x, v.y, v.z]);
-                    triangle_vertices.append(&mut vec![normal.x, normal.y, normal.z]);
-                }
-            }
-        }
+        profile_span!("upload_mesh");
+        let shaders = Shaders::get(&gl)?;
+        let size = triangles.len() * BYTES_PER_TRIANGLE;
         unsafe {
-            let u8_buffer: &[u8] = bytemuck::cast_slice(&triangle_vertices[..]);
             let vertex_buffer = gl.create_buffer()?;
             gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
-            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+            // Write the vertices straight into the GPU buffer when it can be mapped,
+            // so that a copy of the whole mesh is never held in memory
+            gl.buffer_data_size(glow::ARRAY_BUFFER, size as i32, glow::STATIC_DRAW);
+            let mapped = if size > 0 {
+                gl.map_buffer_range(glow::ARRAY_BUFFER, 0, size as i32,
+                    glow::MAP_WRITE_BIT | glow::MAP_INVALIDATE_BUFFER_BIT)
+            } else {
+                std::ptr::null_mut()
+            };
+            let rendered_count = if mapped.is_null() {
+                let mut vertex_bytes = vec![0u8; size];
+                let count = write_vertices(triangles, &mut vertex_bytes);
+                gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, &vertex_bytes, glow::STATIC_DRAW);
+                count
+            } else {
+                let count = write_vertices(triangles, std::slice::from_raw_parts_mut(mapped, size));
+                gl.unmap_buffer(glow::ARRAY_BUFFER);
+                count
+            };
             let vertex_array = match gl.create_vertex_array() {
                 Ok(val) => { val },
                 Err(val) => {
                     // Delete the vertex buffer before erroring
                     gl.as_ref().delete_buffer(vertex_buffer);
-                    return Err(val);
+                    return Err(val.into());
                 }
             };
             gl.bind_vertex_array(Some(vertex_array));
@@ -56,52 +136,216 @@ impl GlowState {
             gl.enable_vertex_attrib_array(1);
             gl.vertex_attrib_pointer_f32(1, 3, glow::FLOAT, false, bpv * 2, bpv);
 
+            let (center, radius) = get_bounding_sphere(triangles);
             return Ok(Arc::new(Self {
                 vertex_buffer,
                 vertex_array,
-                shader_program: create_shader_program(&gl)?,
+                shaders,
                 triangle_count: triangles.len(),
+                rendered_count,
+                first_triangle,
+                center,
+                radius,
                 gl
             }));
         }
     }
 }
 
+/// A color or normal for each corner of the triangles in a GlowState, in a buffer of its own
+/// so a model can be colored or smoothed, or stop being, without uploading it again
+pub struct CornerBuffer {
+    pub buffer: glow::Buffer,
+    gl: Arc<glow::Context>
+}
+
+impl CornerBuffer {
+    pub fn new(gl: Arc<glow::Context>, values: &[[f32; 3]]) -> Result<Arc<Self>, RenderError> {
+        use glow::HasContext as _;
+        unsafe {
+            let buffer = gl.create_buffer()?;
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, bytemuck::cast_slice(values), glow::STATIC_DRAW);
+            return Ok(Arc::new(Self { buffer, gl }));
+        }
+    }
+}
+
+impl Drop for CornerBuffer {
+    fn drop(&mut self) {
+        use glow::HasContext as _;
+        unsafe {
+            self.gl.as_ref().delete_buffer(self.buffer);
+        }
+    }
+}
+
+/// A point cloud on the GPU, with a color for each point if it has them
+pub struct PointBuffer {
+    pub vertex_buffer: glow::Buffer,
+    pub color_buffer: Option<glow::Buffer>,
+    pub vertex_array: glow::VertexArray,
+    pub point_count: usize,
+    pub shaders: Arc<Shaders>,
+    gl: Arc<glow::Context>
+}
+
+impl PointBuffer {
+    /// Uploads the points and colors of a point cloud, which are cleaned up when the buffer is dropped
+    pub fn new(gl: Arc<glow::Context>, cloud: &PointCloud) -> Result<Arc<Self>, RenderError> {
+        use glow::HasContext as _;
+        profile_span!("upload_points");
+        let shaders = Shaders::get(&gl)?;
+        let positions: Vec<[f32; 3]> = cloud.points.iter().map(|p| [p.x, p.y, p.z]).collect();
+        unsafe {
+            let vertex_buffer = gl.create_buffer()?;
+            let vertex_array = match gl.create_vertex_array() {
+                Ok(val) => { val },
+                Err(val) => {
+                    gl.as_ref().delete_buffer(vertex_buffer);
+                    return Err(val.into());
+                }
+            };
+            // Owned by the buffer from here, so they are cleaned up if the colors fail
+            let mut buffer = Self {
+                vertex_buffer,
+                color_buffer: None,
+                vertex_array,
+                point_count: positions.len(),
+                shaders,
+                gl: gl.clone()
+            };
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, bytemuck::cast_slice(&positions), glow::STATIC_DRAW);
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 12, 0);
+            if let Some(colors) = &cloud.colors {
+                let color_buffer = gl.create_buffer()?;
+                buffer.color_buffer = Some(color_buffer);
+                gl.bind_buffer(glow::ARRAY_BUFFER, Some(color_buffer));
+                gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, bytemuck::cast_slice(colors), glow::STATIC_DRAW);
+                gl.enable_vertex_attrib_array(1);
+                gl.vertex_attrib_pointer_f32(1, 3, glow::FLOAT, false, 12, 0);
+            }
+            return Ok(Arc::new(buffer));
+        }
+    }
+}
+
+impl Drop for PointBuffer {
+    fn drop(&mut self) {
+        use glow::HasContext as _;
+        unsafe {
+            self.gl.as_ref().delete_vertex_array(self.vertex_array);
+            self.gl.as_ref().delete_buffer(self.vertex_buffer);
+            if let Some(color_buffer) = self.color_buffer {
+                self.gl.as_ref().delete_buffer(color_buffer);
+            }
+        }
+    }
+}
+
+/// The middle of the box around some triangles, and half its diagonal
+fn get_bounding_sphere(triangles: &[Triangle]) -> (glm::Vec3, f32) {
+    let mut min = glm::Vec3::repeat(f32::MAX);
+    let mut max = glm::Vec3::repeat(f32::MIN);
+    for vertex in triangles.iter().flatten() {
+        min = glm::min2(&min, vertex);
+        max = glm::max2(&max, vertex);
+    }
+    if triangles.is_empty() {
+        return (glm::Vec3::zeros(), 0.);
+    }
+    return ((min + max) / 2., glm::distance(&min, &max) / 2.);
+}
+
+/// Bytes for the three vertices of a triangle, each with a position and a normal
+const BYTES_PER_TRIANGLE: usize = 3 * 6 * 4;
+
+/// Writes the interleaved positions and normals of triangles to a buffer
+/// with room for exactly that many triangles. Returns how many have any area.
+fn write_vertices(triangles: &[Triangle], output: &mut [u8]) -> usize {
+    let mut rendered = 0;
+    for (t, bytes) in triangles.iter().zip(output.chunks_exact_mut(BYTES_PER_TRIANGLE)) {
+        // Triangles with no area are kept, so that the index of each
+        // primitive matches its triangle, but they get a zero normal
+        let cross_product = glm::cross(&(t[1] - t[0]), &(t[2] - t[0]));
+        let normal = if glm::dot(&cross_product, &cross_product) > 0.0 {
+            rendered += 1;
+            cross_product.normalize()
+        } else {
+            cross_product
+        };
+        let vertices: [f32; 18] = [
+            t[0].x, t[0].y, t[0].z, normal.x, normal.y, normal.z,
+            t[1].x, t[1].y, t[1].z, normal.x, normal.y, normal.z,
+            t[2].x, t[2].y, t[2].z, normal.x, normal.y, normal.z];
+        bytes.copy_from_slice(bytemuck::bytes_of(&vertices));
+    }
+    return rendered;
+}
+
 impl Drop for GlowState {
     fn drop(&mut self) {
         use glow::HasContext as _;
         unsafe {
             self.gl.as_ref().delete_vertex_array(self.vertex_array);
             self.gl.as_ref().delete_buffer(self.vertex_buffer);
-            self.gl.as_ref().delete_program(self.shader_program);
         }
     }
 }
 
+/// Most planes the shaders can clip the models by at once
+pub const MAX_CLIP_PLANES: usize = 3;
+/// Most lights the shader can light the models with at once
+pub const MAX_LIGHTS: usize = 4;
+
 const VERTEX_SHADER_SOURCE: &str = r#"
 #version 330 core
 layout (location = 0) in vec3 a_pos;
 layout (location = 1) in vec3 a_normal;
+// White unless the model is colored, as by a deviation analysis
+layout (location = 2) in vec3 a_color;
 uniform mat4 u_transformation;
-uniform vec3 light_direction;
+// Places the model in the scene, where it is clipped
+uniform mat4 u_local;
+// Directions the lights shine in, in view coordinates, and their colors scaled by their intensities
+uniform vec3 light_directions[4];
+uniform vec3 light_colors[4];
+uniform int light_count;
 uniform vec3 ambient;
 uniform vec3 diffuse;
+uniform vec3 tint;
 uniform vec3 specular;
-uniform float aspect_ratio;
+uniform mat4 u_projection;
 out vec3 v_color;
+// One at a corner of the triangle and zero along the opposite edge, for drawing edges
+out vec3 v_barycentric;
+out vec3 v_scene_pos;
+// Distance toward the viewer, before the projection
+out float v_depth;
 void main() {
     // Position
-    gl_Position = u_transformation * vec4(a_pos.x, a_pos.y, a_pos.z , 1.0);
-    gl_Position.x /= aspect_ratio;
-    gl_Position.z *= 0.001;
+    gl_Position = u_projection * u_transformation * vec4(a_pos.x, a_pos.y, a_pos.z , 1.0);
+    v_scene_pos = (u_local * vec4(a_pos, 1.0)).xyz;
+    v_depth = (u_transformation * vec4(a_pos, 1.0)).z;
 
     // Color
-    mat3 rotation = mat3(u_transformation);
+    // The inverse transpose keeps normals perpendicular under uneven scaling
+    mat3 rotation = transpose(inverse(mat3(u_transformation)));
     vec3 normal_3 = normalize(rotation * a_normal);
-    float d = dot(normal_3, light_direction);
-    vec3 reflection = light_direction - normal_3 * d * 2.;
-    float s = max(0., dot(vec3(0.,0.,1.), normalize(reflection)));
-    v_color = ambient + diffuse * max(0, -d) + specular * pow(s, 8);
+    v_color = ambient * a_color;
+    for (int i = 0; i < light_count; i++) {
+        float d = dot(normal_3, light_directions[i]);
+        vec3 reflection = light_directions[i] - normal_3 * d * 2.;
+        float s = max(0., dot(vec3(0.,0.,1.), normalize(reflection)));
+        v_color += (diffuse * tint * max(0, -d) * a_color + specular * pow(s, 8)) * light_colors[i];
+    }
+
+    // Every triangle has its own three vertices, in order
+    v_barycentric = vec3(0.);
+    v_barycentric[gl_VertexID % 3] = 1.;
 }
 "#;
 
@@ -109,21 +353,155 @@ const FRAGMENT_SHADER_SOURCE: &str = r#"
 #version 330 core
 precision mediump float;
 in vec3 v_color;
+in vec3 v_barycentric;
+in vec3 v_scene_pos;
+in float v_depth;
+// 0 for shaded, 1 for wireframe, 2 for shaded with edges, 3 for depth
+uniform int render_mode;
+// The depths drawn black and white, for the depth mode
+uniform vec2 depth_range;
+uniform float opacity;
+uniform bool highlight_back_faces;
+uniform vec3 back_face_color;
+// Normals and offsets of planes, hiding everything on the side their normals point to
+uniform vec4 clip_planes[3];
+uniform int clip_plane_count;
+uniform bool caps;
+uniform vec3 cap_color;
+out vec4 out_color;
+void main() {
+    for (int i = 0; i < clip_plane_count; i++) {
+        if (dot(clip_planes[i].xyz, v_scene_pos) > clip_planes[i].w) {
+            discard;
+        }
+    }
+    if (render_mode == 3) {
+        float brightness = clamp((v_depth - depth_range.x) / (depth_range.y - depth_range.x), 0., 1.);
+        out_color = vec4(vec3(brightness) * opacity, opacity);
+        return;
+    }
+    vec3 color = v_color;
+    // Inside a closed model cut open, only back faces can be seen, so coloring them looks like a solid cap
+    if (caps && clip_plane_count > 0 && !gl_FrontFacing) {
+        color = cap_color;
+    }
+    if (highlight_back_faces && !gl_FrontFacing) {
+        color = back_face_color;
+    }
+    // Colors are premultiplied by their opacity, for blending over what is behind them
+    if (render_mode == 0) {
+        out_color = vec4(color * opacity, opacity);
+        return;
+    }
+    // About a pixel and a half wide, however large the triangle is on screen
+    vec3 width = fwidth(v_barycentric) * 1.5;
+    vec3 inside = smoothstep(vec3(0.), width, v_barycentric);
+    float edge = 1. - min(min(inside.x, inside.y), inside.z);
+    if (render_mode == 1) {
+        if (edge < 0.5) {
+            discard;
+        }
+        out_color = vec4(color * opacity, opacity);
+    } else {
+        out_color = vec4(mix(color, vec3(0.), edge * 0.8) * opacity, opacity);
+    }
+}
+"#;
+
+const ID_VERTEX_SHADER_SOURCE: &str = r#"
+#version 330 core
+layout (location = 0) in vec3 a_pos;
+uniform mat4 u_transformation;
+uniform mat4 u_local;
+uniform mat4 u_projection;
+uniform vec2 pick_center;
+uniform vec2 pick_scale;
+out vec3 v_scene_pos;
+void main() {
+    gl_Position = u_projection * u_transformation * vec4(a_pos.x, a_pos.y, a_pos.z , 1.0);
+    v_scene_pos = (u_local * vec4(a_pos, 1.0)).xyz;
+    // Zoom in on the picked pixel, so a tiny buffer is enough.
+    // The center is scaled by w, since clip space is divided by it afterward.
+    gl_Position.xy = (gl_Position.xy - pick_center * gl_Position.w) * pick_scale;
+}
+"#;
+
+const ID_FRAGMENT_SHADER_SOURCE: &str = r#"
+#version 330 core
+uniform uint model_id;
+uniform uint first_triangle;
+in vec3 v_scene_pos;
+// Clipped away parts can't be picked
+uniform vec4 clip_planes[3];
+uniform int clip_plane_count;
+out uvec2 out_id;
+void main() {
+    for (int i = 0; i < clip_plane_count; i++) {
+        if (dot(clip_planes[i].xyz, v_scene_pos) > clip_planes[i].w) {
+            discard;
+        }
+    }
+    out_id = uvec2(model_id, first_triangle + uint(gl_PrimitiveID));
+}
+"#;
+
+const POINT_VERTEX_SHADER_SOURCE: &str = r#"
+#version 330 core
+layout (location = 0) in vec3 a_pos;
+// White unless the cloud has colors
+layout (location = 1) in vec3 a_color;
+uniform mat4 u_transformation;
+uniform mat4 u_local;
+uniform mat4 u_projection;
+// Width of each dot, in pixels
+uniform float point_size;
+out vec3 v_color;
+out vec3 v_scene_pos;
+void main() {
+    gl_Position = u_projection * u_transformation * vec4(a_pos, 1.0);
+    v_scene_pos = (u_local * vec4(a_pos, 1.0)).xyz;
+    v_color = a_color;
+    gl_PointSize = point_size;
+}
+"#;
+
+const POINT_FRAGMENT_SHADER_SOURCE: &str = r#"
+#version 330 core
+precision mediump float;
+in vec3 v_color;
+in vec3 v_scene_pos;
+uniform vec3 tint;
+uniform vec4 clip_planes[3];
+uniform int clip_plane_count;
 out vec4 out_color;
 void main() {
-    out_color = vec4(v_color, 1.0);
+    for (int i = 0; i < clip_plane_count; i++) {
+        if (dot(clip_planes[i].xyz, v_scene_pos) > clip_planes[i].w) {
+            discard;
+        }
+    }
+    // Each square sprite is cut to a circle, and darkened toward its rim like a tiny ball,
+    // so overlapping dots can be told apart
+    vec2 offset = gl_PointCoord * 2. - 1.;
+    float distance_squared = dot(offset, offset);
+    if (distance_squared > 1.) {
+        discard;
+    }
+    float shade = 0.6 + 0.4 * sqrt(1. - distance_squared);
+    out_color = vec4(v_color * tint * shade, 1.);
 }
 "#;
 
-fn create_shader_program(gl: &Arc<glow::Context>) -> Result<glow::Program, String>{
+pub(super) fn create_shader_program(gl: &Arc<glow::Context>, vertex_source: &str, fragment_source: &str)
+-> Result<glow::Program, RenderError>{
     use glow::HasContext as _;
 
     unsafe {
         let shader_program = gl.create_program()?;
 
         let shader_sources = [
-            (glow::VERTEX_SHADER, VERTEX_SHADER_SOURCE),
-            (glow::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE),
+            (glow::VERTEX_SHADER, vertex_source),
+            (glow::FRAGMENT_SHADER, fragment_source),
         ];
 
         let mut shaders: Vec<glow::NativeShader> = Vec::new();
@@ -132,9 +510,7 @@ fn create_shader_program(gl: &Arc<glow::Context>) -> Result<glow::Program, Strin
             gl.shader_source(shader, shader_source);
             gl.compile_shader(shader);
             if !gl.get_shader_compile_status(shader) {
-                return Err(format!(
-                    "Failed to compile shader: {}",
-                    gl.get_shader_info_log(shader)));
+                return Err(RenderError::Shader(gl.get_shader_info_log(shader)));
             }
             gl.attach_shader(shader_program, shader);
             shaders.push(shader);
@@ -142,7 +518,7 @@ fn create_shader_program(gl: &Arc<glow::Context>) -> Result<glow::Program, Strin
 
         gl.link_program(shader_program);
         if !gl.get_program_link_status(shader_program) {
-            return Err(format!("{}", gl.get_program_info_log(shader_program)));
+            return Err(RenderError::Shader(gl.get_program_info_log(shader_program)));
         }
 
         for shader in shaders {
@@ -0,0 +1,53 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use super::ViewState;
+
+/// A reference grid on a level plane under the models, with the scene's X and Y axes through it,
+/// to judge their size and which way they face
+#[derive(Clone)]
+pub struct Grid {
+    pub visible: bool,
+    /// Distance between the lines, in scene units
+    pub spacing: f32,
+    /// Number of lines on each side of the axes
+    pub count: usize,
+    /// Height of the plane along the scene's Z axis
+    pub height: f32
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        return Self { visible: false, spacing: 10., count: 10, height: 0. };
+    }
+
+    /// The lines of the grid, drawn over the background of a viewport
+    pub fn shapes(&self, view: &ViewState, rect: egui::Rect) -> Vec<egui::Shape> {
+        if !self.visible || self.spacing <= 0. {
+            return Vec::new();
+        }
+        let line = egui::Stroke::new(1., egui::Color32::from_gray(128).linear_multiply(0.5));
+        let x_axis = egui::Stroke::new(2., egui::Color32::from_rgb(200, 60, 60));
+        let y_axis = egui::Stroke::new(2., egui::Color32::from_rgb(60, 180, 60));
+        let extent = self.spacing * self.count as f32;
+        let mut shapes = Vec::new();
+        // The axes go last, so the other lines don't cross over them
+        let mut axes = Vec::new();
+        for i in -(self.count as i32)..=self.count as i32 {
+            let offset = i as f32 * self.spacing;
+            // The lines through the origin are the axes
+            let (along_x, along_y) = if i == 0 { (x_axis, y_axis) } else { (line, line) };
+            let lines = [
+                (Vec3::new(-extent, offset, self.height), Vec3::new(extent, offset, self.height), along_x),
+                (Vec3::new(offset, -extent, self.height), Vec3::new(offset, extent, self.height), along_y)];
+            for (start, end, stroke) in lines {
+                if let Some(points) = view.project_segment(&start, &end, rect) {
+                    let target = if i == 0 { &mut axes } else { &mut shapes };
+                    target.push(egui::Shape::line_segment(points, stroke));
+                }
+            }
+        }
+        shapes.extend(axes);
+        return shapes;
+    }
+}
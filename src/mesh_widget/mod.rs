@@ -6,20 +6,30 @@ use glm::Vec3;
 
 pub type Triangle = [Vec3; 3];
 
+mod camera;
 mod glow_state;
+mod grid;
+mod multisample;
 mod view_state;
-pub use glow_state::GlowState;
-pub use view_state::ViewState;
+mod render_buffer;
+mod render_error;
+mod texture_renderer;
+pub use camera::Camera;
+pub use glow_state::{GlowState, MAX_CLIP_PLANES, MAX_LIGHTS};
+pub use grid::Grid;
+pub use render_error::RenderError;
+pub use view_state::{Hit, Light, Projection, RenderMode, ViewState, REDUCED_TRIANGLES};
 
 /// A simple Widget to view Triangles in 3D space
 ///
 /// Primary mouse drag rotates the model
 /// Secondary mouse drag translates the model
 /// Middle mouse drag scales the model
+/// Scrolling zooms toward the cursor
 fn mesh_ui(ui: &mut egui::Ui, view_size: egui::Vec2, state: &mut ViewState)
 -> egui::Response {
     let (rect, response) =
-        ui.allocate_exact_size(view_size, egui::Sense::drag());
+        ui.allocate_exact_size(view_size, egui::Sense::click_and_drag());
 
     // Avoids division by zero for translation (and saves a bit of processing)
     if view_size.x * view_size.y == 0. {
@@ -29,36 +39,83 @@ fn mesh_ui(ui: &mut egui::Ui, view_size: egui::Vec2, state: &mut ViewState)
     let aspect_ratio = view_size.x/view_size.y;
     {
 
-        if response.dragged_by(egui::PointerButton::Primary) {
+        let animating = state.animate(ui.input().unstable_dt);
+        if animating {
+            ui.ctx().request_repaint();
+        }
+        if response.dragged_by(egui::PointerButton::Primary) && state.rotate_on_drag {
+            state.stop_moving();
             state.rotate_y(-response.drag_delta().x * 0.01);
             state.rotate_x(-response.drag_delta().y * 0.01);
         }
         if response.dragged_by(egui::PointerButton::Secondary) {
+            state.stop_moving();
             let matrix = state.combine_transformations();
             if let Some(inverse_matrix) = matrix.try_inverse() {
                 let delta4 = inverse_matrix * glm::Vec4::new(
                     aspect_ratio * 2. * response.drag_delta().x / view_size.x,
                     -2. * response.drag_delta().y / view_size.y,
                     0., 0.);
-                state.translation += Vec3::new(delta4.x, delta4.y, delta4.z);
+                state.camera.translation += Vec3::new(delta4.x, delta4.y, delta4.z);
             }
         }
         if response.dragged_by(egui::PointerButton::Middle) {
-            state.scale *= std::f32::consts::E.powf(-response.drag_delta().y * 0.01);
+            state.stop_moving();
+            state.camera.scale *= std::f32::consts::E.powf(-response.drag_delta().y * 0.01);
+        }
+        let scroll = ui.input().scroll_delta.y;
+        if let (true, Some(position)) = (scroll != 0., response.hover_pos()) {
+            state.zoom_toward(std::f32::consts::E.powf(scroll * 0.002), position, rect);
+        }
+        if state.choose_detail(animating || response.dragged() || scroll != 0., ui.input().unstable_dt) {
+            ui.ctx().request_repaint();
         }
     }
 
+    let to_color = |color: [f32; 3]| {
+        let [r, g, b] = color.map(|c| (c * 255.) as u8);
+        return egui::Color32::from_rgb(r, g, b);
+    };
+    let top = to_color(state.background);
+    let bottom = state.background_gradient.map_or(top, to_color);
+    let mut background = egui::Mesh::default();
+    for (corner, color) in [(rect.left_top(), top), (rect.right_top(), top),
+        (rect.right_bottom(), bottom), (rect.left_bottom(), bottom)] {
+        background.colored_vertex(corner, color);
+    }
+    background.add_triangle(0, 1, 2);
+    background.add_triangle(0, 2, 3);
+    let grid = state.grid.shapes(state, rect);
+    let [r, g, b] = state.overlay.map(|c| (c * 255.) as u8);
+    let overlay = egui::Stroke::new(2., egui::Color32::from_rgb(r, g, b));
+    let highlights: Vec<egui::Shape> = state.highlights.iter().chain(&state.guides)
+        .map(|points| egui::Shape::closed_line(
+            points.iter().map(|p| state.project(p, rect)).collect(),
+            overlay))
+        .collect();
+    let warning = egui::Stroke::new(2., egui::Color32::RED);
+    let warnings: Vec<egui::Shape> = state.warnings.iter()
+        .map(|points| egui::Shape::closed_line(
+            points.iter().map(|p| state.project(p, rect)).collect(),
+            warning))
+        .collect();
+
     // Now that the state is updated, send a clone of it to the callback function.
     let state = state.clone();
     let cb = egui_glow::CallbackFn::new(move |_info, _painter| {
-        state.draw(aspect_ratio);
+        state.draw_smoothed(aspect_ratio);
     });
 
     if ui.is_rect_visible(rect) {
+        ui.painter().add(background);
+        // The grid is drawn before the models, so it stays beneath them
+        ui.painter().extend(grid);
         ui.painter().add(egui::PaintCallback {
             rect,
             callback: Arc::new(cb),
         });
+        ui.painter().extend(warnings);
+        ui.painter().extend(highlights);
     }
     return response;
 }
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use eframe::{egui_glow::glow, glow::HasContext};
+
+use super::RenderError;
+use super::render_buffer::{self, BufferFormat, RenderBuffer};
+use super::texture_renderer::TextureRenderer;
+
+/// An offscreen framebuffer with several samples for each pixel, which the view is drawn into
+/// and then averaged onto the screen, so the edges of the models are smooth instead of jagged.
+///
+/// The gl objects are deleted when the MultisampleBuffer is dropped.
+pub struct MultisampleBuffer {
+    pub width: usize,
+    pub height: usize,
+    /// Samples asked for each pixel, which may be more than the GPU gives
+    pub samples: u8,
+    /// Samples the GPU gives for each pixel
+    supported: i32,
+    framebuffer: glow::Framebuffer,
+    color_buffer: glow::Renderbuffer,
+    depth_buffer: glow::Renderbuffer,
+    /// Holds the averaged pixels, to be drawn over the screen
+    resolved: RenderBuffer,
+    renderer: TextureRenderer,
+    gl: Arc<glow::Context>
+}
+
+impl MultisampleBuffer {
+    /// Creates a buffer, with no more samples than the GPU supports
+    pub fn new(gl: Arc<glow::Context>, width: usize, height: usize, samples: u8) -> Result<Self, RenderError> {
+        unsafe {
+            let supported = gl.get_parameter_i32(glow::MAX_SAMPLES).clamp(1, samples.max(1) as i32);
+            let framebuffer = gl.create_framebuffer()?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            let color_buffer = gl.create_renderbuffer()?;
+            let depth_buffer = gl.create_renderbuffer()?;
+            allocate(&gl, color_buffer, depth_buffer, supported, width, height);
+            gl.framebuffer_renderbuffer(glow::FRAMEBUFFER, glow::COLOR_ATTACHMENT0, glow::RENDERBUFFER, Some(color_buffer));
+            gl.framebuffer_renderbuffer(glow::FRAMEBUFFER, glow::DEPTH_ATTACHMENT, glow::RENDERBUFFER, Some(depth_buffer));
+            let status = render_buffer::check_framebuffer(&gl);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if let Err(err) = status {
+                gl.delete_framebuffer(framebuffer);
+                gl.delete_renderbuffer(color_buffer);
+                gl.delete_renderbuffer(depth_buffer);
+                return Err(err);
+            }
+
+            return Ok(Self {
+                width,
+                height,
+                samples,
+                supported,
+                framebuffer,
+                color_buffer,
+                depth_buffer,
+                resolved: RenderBuffer::new(gl.clone(), width, height, BufferFormat::Color)?,
+                renderer: TextureRenderer::new(gl.clone())?,
+                gl
+            });
+        }
+    }
+
+    /// Changes the size of the buffer to fit a viewport, keeping the number of samples
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        allocate(&self.gl, self.color_buffer, self.depth_buffer, self.supported, width, height);
+        self.resolved.resize(width, height);
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Directs drawing into this buffer, and clears it to transparent
+    pub fn bind(&self) {
+        let gl = &self.gl;
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer));
+            gl.viewport(0, 0, self.width as i32, self.height as i32);
+            // The UI's clip rectangle doesn't apply offscreen
+            gl.disable(glow::SCISSOR_TEST);
+            gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Averages the samples of each pixel, and draws them over the screen's viewport,
+    /// given by its corner in pixels from the bottom left. Drawing is directed back to the screen.
+    pub fn resolve(&self, left: i32, bottom: i32) {
+        self.resolved.copy_from(self.framebuffer);
+        self.resolved.composite(&self.renderer, left, bottom);
+    }
+}
+
+/// Makes room for the samples of each pixel's color and depth, replacing what was there
+fn allocate(gl: &glow::Context, color_buffer: glow::Renderbuffer, depth_buffer: glow::Renderbuffer, samples: i32,
+    width: usize, height: usize) {
+    unsafe {
+        // Colors pass through unchanged, as they would drawing straight to the screen
+        gl.bind_renderbuffer(glow::RENDERBUFFER, Some(color_buffer));
+        gl.renderbuffer_storage_multisample(glow::RENDERBUFFER, samples, glow::RGBA8, width as i32, height as i32);
+        gl.bind_renderbuffer(glow::RENDERBUFFER, Some(depth_buffer));
+        gl.renderbuffer_storage_multisample(glow::RENDERBUFFER, samples, glow::DEPTH_COMPONENT24, width as i32, height as i32);
+    }
+}
+
+impl Drop for MultisampleBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_framebuffer(self.framebuffer);
+            self.gl.delete_renderbuffer(self.color_buffer);
+            self.gl.delete_renderbuffer(self.depth_buffer);
+        }
+    }
+}
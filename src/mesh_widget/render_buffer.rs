@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use eframe::{egui_glow::glow, glow::HasContext};
+
+use super::RenderError;
+use super::texture_renderer::TextureRenderer;
+
+/// What kind of values a RenderBuffer holds for each pixel
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BufferFormat {
+    /// 8 bit color with alpha, for images
+    Color,
+    /// Two unsigned integers, for model and triangle ids
+    Ids
+}
+
+/// An offscreen framebuffer with a color texture and a depth buffer.
+///
+/// Drawing happens here instead of on the screen while it is bound.
+/// The gl objects are deleted when the RenderBuffer is dropped.
+pub struct RenderBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub format: BufferFormat,
+    framebuffer: glow::Framebuffer,
+    texture: glow::Texture,
+    depth_buffer: glow::Renderbuffer,
+    gl: Arc<glow::Context>
+}
+
+impl RenderBuffer {
+    pub fn new(gl: Arc<glow::Context>, width: usize, height: usize, format: BufferFormat)
+    -> Result<Self, RenderError> {
+        unsafe {
+            let framebuffer = gl.create_framebuffer()?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            let texture = gl.create_texture()?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
+            let depth_buffer = gl.create_renderbuffer()?;
+            allocate(&gl, texture, depth_buffer, format, width, height);
+            gl.framebuffer_renderbuffer(glow::FRAMEBUFFER, glow::DEPTH_ATTACHMENT, glow::RENDERBUFFER, Some(depth_buffer));
+            gl.framebuffer_texture(glow::FRAMEBUFFER, glow::COLOR_ATTACHMENT0, Some(texture), 0);
+            gl.draw_buffer(glow::COLOR_ATTACHMENT0);
+            let status = check_framebuffer(&gl);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            // Dropping it deletes the gl objects
+            let buffer = Self { width, height, format, framebuffer, texture, depth_buffer, gl };
+            status?;
+            return Ok(buffer);
+        }
+    }
+
+    /// Changes the size of the buffer, making room for the new number of pixels.
+    /// What was drawn in it is lost, unless the size stays the same.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        allocate(&self.gl, self.texture, self.depth_buffer, self.format, width, height);
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Directs drawing into this buffer, and clears it
+    pub fn bind(&self) {
+        let gl = &self.gl;
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer));
+            gl.viewport(0, 0, self.width as i32, self.height as i32);
+            // The UI's clip rectangle doesn't apply offscreen
+            gl.disable(glow::SCISSOR_TEST);
+            match self.format {
+                BufferFormat::Color => {
+                    gl.clear_color(0.0, 0.0, 0.0, 0.0);
+                    gl.clear(glow::COLOR_BUFFER_BIT);
+                },
+                // Zero means no model was drawn at a pixel
+                BufferFormat::Ids => gl.clear_buffer_u32_slice(glow::COLOR, 0, &[0, 0, 0, 0])
+            }
+            gl.clear(glow::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Directs drawing back to the screen
+    pub fn unbind(&self) {
+        unsafe {
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// Copies the colors of another framebuffer the same size into this Color buffer,
+    /// averaging the samples of each pixel if it has several
+    pub fn copy_from(&self, framebuffer: glow::Framebuffer) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        unsafe {
+            self.gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(framebuffer));
+            self.gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, Some(self.framebuffer));
+            self.gl.blit_framebuffer(0, 0, width, height, 0, 0, width, height, glow::COLOR_BUFFER_BIT, glow::NEAREST);
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// Draws a Color buffer over the screen's viewport, given by its corner in pixels from the bottom left,
+    /// so what was drawn offscreen shows in the view. Drawing is directed back to the screen.
+    pub fn composite(&self, renderer: &TextureRenderer, left: i32, bottom: i32) {
+        renderer.draw(self.texture, left, bottom, self.width as i32, self.height as i32);
+    }
+
+    /// Reads a Color buffer as RGBA bytes, with the top row first
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let (width, height) = (self.width, self.height);
+        let mut buffer = vec![0u8; width * height * 4];
+        unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            self.gl.get_tex_image(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(buffer.as_mut_slice()));
+        }
+        // OpenGL puts the bottom row first
+        let mut flipped_buffer = vec![0u8; width * height * 4];
+        for y in 0..height {
+            let row = width * 4;
+            let source = (height - 1 - y) * row;
+            flipped_buffer[y * row..(y + 1) * row].copy_from_slice(&buffer[source..source + row]);
+        }
+        return flipped_buffer;
+    }
+
+    /// Reads the depth of each pixel, from 0 at the near plane to 1 at the far plane,
+    /// with the top row first. Pixels where nothing was drawn are 1.
+    pub fn read_depths(&self) -> Vec<f32> {
+        let (width, height) = (self.width, self.height);
+        let mut depths = vec![0f32; width * height];
+        unsafe {
+            self.gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.framebuffer));
+            self.gl.read_pixels(
+                0, 0, width as i32, height as i32,
+                glow::DEPTH_COMPONENT,
+                glow::FLOAT,
+                glow::PixelPackData::Slice(bytemuck::cast_slice_mut(&mut depths)));
+            self.gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+        }
+        // OpenGL puts the bottom row first
+        let mut flipped = vec![0f32; width * height];
+        for y in 0..height {
+            let source = (height - 1 - y) * width;
+            flipped[y * width..(y + 1) * width].copy_from_slice(&depths[source..source + width]);
+        }
+        return flipped;
+    }
+
+    /// Reads the two ids at a pixel of an Ids buffer, counting rows from the bottom
+    pub fn read_ids(&self, x: usize, y: usize) -> [u32; 2] {
+        let mut ids = [0u32; 2];
+        unsafe {
+            self.gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.framebuffer));
+            self.gl.read_pixels(
+                x as i32, y as i32, 1, 1,
+                glow::RG_INTEGER,
+                glow::UNSIGNED_INT,
+                glow::PixelPackData::Slice(bytemuck::cast_slice_mut(&mut ids)));
+            self.gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+        }
+        return ids;
+    }
+}
+
+/// Checks that the bound framebuffer can be drawn into, which it can't if the GPU
+/// doesn't support its formats, or couldn't find room for them
+pub(super) fn check_framebuffer(gl: &glow::Context) -> Result<(), RenderError> {
+    let status = unsafe { gl.check_framebuffer_status(glow::FRAMEBUFFER) };
+    if status != glow::FRAMEBUFFER_COMPLETE {
+        return Err(RenderError::Framebuffer(status));
+    }
+    return Ok(());
+}
+
+/// Makes room for the pixels of a buffer's texture and depths, replacing what was there
+fn allocate(gl: &glow::Context, texture: glow::Texture, depth_buffer: glow::Renderbuffer, format: BufferFormat,
+    width: usize, height: usize) {
+    unsafe {
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        match format {
+            BufferFormat::Color => gl.tex_image_2d(
+                glow::TEXTURE_2D, 0, glow::RGBA8 as i32, width as i32, height as i32, 0,
+                glow::RGBA, glow::UNSIGNED_BYTE, None),
+            BufferFormat::Ids => gl.tex_image_2d(
+                glow::TEXTURE_2D, 0, glow::RG32UI as i32, width as i32, height as i32, 0,
+                glow::RG_INTEGER, glow::UNSIGNED_INT, None)
+        }
+        gl.bind_renderbuffer(glow::RENDERBUFFER, Some(depth_buffer));
+        // Floating point depths keep the most precision for depth maps
+        gl.renderbuffer_storage(glow::RENDERBUFFER, glow::DEPTH_COMPONENT32F, width as i32, height as i32);
+    }
+}
+
+impl Drop for RenderBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_framebuffer(self.framebuffer);
+            self.gl.delete_texture(self.texture);
+            self.gl.delete_renderbuffer(self.depth_buffer);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt;
+
+use crate::i18n::tr_args;
+
+/// Why the models couldn't be put on the GPU, or drawn
+#[derive(Clone, Debug)]
+pub enum RenderError {
+    /// The GPU couldn't make a buffer, texture, or other object, with the driver's message
+    Create(String),
+    /// A shader didn't compile or link, with the driver's log
+    Shader(String),
+    /// An offscreen framebuffer can't be drawn into, with the status the GPU gave for it
+    Framebuffer(u32)
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            RenderError::Create(error) => tr_args("render-error-create", &[("error", error)]),
+            RenderError::Shader(log) => tr_args("render-error-shader", &[("error", log)]),
+            RenderError::Framebuffer(status) => tr_args("render-error-framebuffer", &[("status", &format!("{:#x}", status))])
+        };
+        return write!(f, "{}", message);
+    }
+}
+
+/// glow reports failures to create objects as strings
+impl From<String> for RenderError {
+    fn from(error: String) -> Self {
+        return RenderError::Create(error);
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use eframe::{egui_glow::glow, glow::HasContext};
+
+use super::RenderError;
+use super::glow_state::create_shader_program;
+
+/// Draws a texture over a viewport of the screen with a single quad, blended over what is behind it.
+/// Offscreen renders are put on the screen with it, so they can be drawn and processed at their own
+/// size before they are shown.
+///
+/// The gl objects are deleted when the TextureRenderer is dropped.
+pub struct TextureRenderer {
+    program: glow::Program,
+    /// Empty, since the corners come from the vertex ids, but one must be bound to draw
+    vertex_array: glow::VertexArray,
+    gl: Arc<glow::Context>
+}
+
+impl TextureRenderer {
+    pub fn new(gl: Arc<glow::Context>) -> Result<Self, RenderError> {
+        unsafe {
+            let program = create_shader_program(&gl, VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE)?;
+            let vertex_array = match gl.create_vertex_array() {
+                Ok(vertex_array) => vertex_array,
+                Err(err) => {
+                    gl.delete_program(program);
+                    return Err(err.into());
+                }
+            };
+            return Ok(Self { program, vertex_array, gl });
+        }
+    }
+
+    /// Draws a texture of premultiplied colors over the screen's viewport, given by its corner
+    /// in pixels from the bottom left and its size. Where the texture is transparent, the screen shows through.
+    pub fn draw(&self, texture: glow::Texture, left: i32, bottom: i32, width: i32, height: i32) {
+        let gl = &self.gl;
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.viewport(left, bottom, width, height);
+            gl.enable(glow::SCISSOR_TEST);
+            gl.disable(glow::DEPTH_TEST);
+            gl.enable(glow::BLEND);
+            gl.blend_func(glow::ONE, glow::ONE_MINUS_SRC_ALPHA);
+            gl.use_program(Some(self.program));
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.uniform_1_i32(gl.get_uniform_location(self.program, "u_image").as_ref(), 0);
+            gl.bind_vertex_array(Some(self.vertex_array));
+            gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
+        }
+    }
+}
+
+impl Drop for TextureRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_program(self.program);
+            self.gl.delete_vertex_array(self.vertex_array);
+        }
+    }
+}
+
+const VERTEX_SHADER_SOURCE: &str = r#"
+#version 330 core
+out vec2 v_uv;
+void main() {
+    // The corners of the viewport, in the order of a triangle strip
+    vec2 corner = vec2(gl_VertexID % 2, gl_VertexID / 2);
+    v_uv = corner;
+    gl_Position = vec4(corner * 2. - 1., 0., 1.);
+}
+"#;
+
+const FRAGMENT_SHADER_SOURCE: &str = r#"
+#version 330 core
+uniform sampler2D u_image;
+in vec2 v_uv;
+out vec4 out_color;
+void main() {
+    out_color = texture(u_image, v_uv);
+}
+"#;
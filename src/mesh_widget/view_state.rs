@@ -1,30 +1,302 @@
 use eframe::{egui_glow::glow, glow::HasContext};
 
 extern crate nalgebra_glm as glm;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use glm::{Vec3, Mat4, Vec4};
 
-use super::{GlowState, Triangle, glow_state};
+use super::{Camera, GlowState, Grid, RenderError, Triangle, glow_state};
+use super::glow_state::{PointBuffer, MAX_CLIP_PLANES, MAX_LIGHTS};
+use super::camera::Transition;
+use crate::bvh::Bvh;
+use crate::mesh_file::PointCloud;
+use crate::triangle;
+use crate::plane_cut::Plane;
+use super::multisample::MultisampleBuffer;
+use super::render_buffer::{BufferFormat, RenderBuffer};
+
+/// Most triangles uploaded to the GPU at once. Larger models are split
+/// into several buffers, uploaded over several frames, so the UI keeps responding.
+pub const UPLOAD_CHUNK: usize = 250_000;
+
+/// Triangles in the reduced copy of a large model, drawn in place of it while the view moves.
+/// They fit in one buffer.
+pub const REDUCED_TRIANGLES: usize = UPLOAD_CHUNK;
+
+/// Models with more triangles than this get a reduced copy
+const REDUCE_ABOVE: usize = REDUCED_TRIANGLES * 4;
+
+/// Seconds a frame may take while the view moves before the reduced copies are drawn instead
+const SLOW_FRAME: f32 = 1. / 20.;
+
+/// How a surface reflects the light
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Material {
+    /// Light reflected everywhere, even facing away from the light
+    pub ambient: [f32; 3],
+    /// Light reflected by faces turned toward the light
+    pub diffuse: [f32; 3],
+    /// Highlights where the light glints toward the viewer
+    pub specular: [f32; 3]
+}
+
+/// A light shining on the models from far away, in one direction
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Light {
+    /// The direction the light shines in, in the scene, or in the view if it follows the camera
+    pub direction: Vec3,
+    pub color: [f32; 3],
+    /// Multiplies the color, to brighten or dim the light
+    pub intensity: f32,
+    /// Keep shining from the same side of the view as it turns, like a headlight
+    pub follows_camera: bool
+}
+
+impl Light {
+    /// A white light from over the viewer's right shoulder, which follows the camera
+    pub fn headlight() -> Self {
+        return Self {
+            direction: Vec3::new(-1.0, -1.0, -1.0),
+            color: [1., 1., 1.],
+            intensity: 1.,
+            follows_camera: true
+        };
+    }
+}
+
+/// A model in the view, and where it is placed
+#[derive(Clone)]
+pub struct Model {
+    /// The model's triangles, in its own coordinates, shared with whatever else works on them.
+    /// They stay on the CPU after they are uploaded, so the buffers can be built again from them.
+    pub triangles: Arc<Vec<Triangle>>,
+    /// Buffers holding the model's triangles in order, each uploaded separately
+    pub glow_states: Vec<Arc<GlowState>>,
+    /// Transformation from the model's coordinates into the scene
+    pub local_transform: Mat4,
+    /// Hidden models are not drawn or picked
+    pub visible: bool,
+    /// Multiplies the diffuse light on the model, to tell models apart
+    pub color: [f32; 3],
+    /// The model's own material, in place of the view's lighting
+    pub material: Option<Material>,
+    /// How much of what is behind the model it hides, from 0 to 1
+    pub opacity: f32,
+    /// Colors of the corners of each buffer's triangles, if the model is colored by an analysis
+    pub corner_colors: Vec<Arc<glow_state::CornerBuffer>>,
+    /// Averaged normals at the corners of each buffer's triangles, for smooth shading, once they are found
+    pub corner_normals: Vec<Arc<glow_state::CornerBuffer>>,
+    /// A copy of a large model with fewer triangles, to draw while the view moves, once it is made
+    pub reduced: Option<Arc<GlowState>>,
+    /// The corners of the box around the triangles, in the model's own coordinates,
+    /// or None if it has none. Models whose box is out of view aren't drawn.
+    pub bounds: Option<(Vec3, Vec3)>
+}
+
+impl Model {
+    /// Creates a model with nothing uploaded yet
+    pub fn new(triangles: &Arc<Vec<Triangle>>) -> Self {
+        return Self {
+            triangles: triangles.clone(),
+            glow_states: Vec::new(),
+            local_transform: Mat4::identity(),
+            visible: true,
+            color: [1., 1., 1.],
+            material: None,
+            opacity: 1.,
+            corner_colors: Vec::new(),
+            corner_normals: Vec::new(),
+            reduced: None,
+            bounds: triangle::transformed_bounds(triangles, &Mat4::identity())
+        };
+    }
+
+    /// The number of triangles uploaded so far
+    pub fn triangle_count(&self) -> usize {
+        return self.glow_states.iter().map(|glow_state| glow_state.triangle_count).sum();
+    }
+
+    /// The number of triangles uploaded so far that have any area, and can be seen
+    pub fn rendered_count(&self) -> usize {
+        return self.glow_states.iter().map(|glow_state| glow_state.rendered_count).sum();
+    }
+
+    /// Whether all of the model's triangles are on the GPU
+    pub fn uploaded(&self) -> bool {
+        return self.triangle_count() == self.triangles.len();
+    }
+
+    /// Whether the model is large enough to draw a reduced copy of while the view moves, and doesn't have one yet
+    pub fn needs_reduced(&self) -> bool {
+        return self.reduced.is_none() && self.triangles.len() > REDUCE_ABOVE && self.uploaded();
+    }
+
+    /// Whether the model's box is entirely outside the view, given the transformation from the model's
+    /// coordinates to the clip space of the view. A box on the outer side of one of the view's sides is.
+    pub fn outside_view(&self, clip: &Mat4) -> bool {
+        let (min, max) = match self.bounds {
+            Some(bounds) => bounds,
+            None => return true
+        };
+        let corners: Vec<Vec4> = (0..8)
+            .map(|i| {
+                let corner = Vec3::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z });
+                return clip * corner.push(1.);
+            })
+            .collect();
+        return (0..3).any(|axis| {
+            return corners.iter().all(|corner| corner[axis] < -corner.w)
+                || corners.iter().all(|corner| corner[axis] > corner.w);
+        });
+    }
+
+    /// The model's triangles in scene coordinates
+    pub fn scene_triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        return self.triangles.iter().map(|triangle| triangle::transform(triangle, &self.local_transform));
+    }
+}
+
+/// A point cloud in the view, drawn as a dot at each point
+#[derive(Clone)]
+pub struct PointCloudModel {
+    /// The points, in the cloud's own coordinates. They stay on the CPU after they are uploaded,
+    /// so the buffer can be built again from them.
+    pub cloud: Arc<PointCloud>,
+    /// The points on the GPU, once they are uploaded
+    pub buffer: Option<Arc<PointBuffer>>,
+    /// Transformation from the cloud's coordinates into the scene
+    pub local_transform: Mat4,
+    pub visible: bool,
+    /// Multiplies the points' colors, or is their color if they have none
+    pub color: [f32; 3],
+    /// Width of the dots, in pixels
+    pub point_size: f32
+}
+
+impl PointCloudModel {
+    /// The middle of the box around the points, and half its diagonal
+    pub fn bounding_sphere(&self) -> Option<(Vec3, f32)> {
+        let first = self.cloud.points.first()?;
+        let (min, max) = self.cloud.points.iter().fold((*first, *first), |(min, max), p| (min.inf(p), max.sup(p)));
+        return Some(((min + max) / 2., glm::distance(&min, &max) / 2.));
+    }
+}
+
+/// How depth is drawn in the view
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Projection {
+    /// Parallel lines stay parallel, so sizes can be compared anywhere in the view
+    Orthographic,
+    /// Farther things are drawn smaller, as the eye sees them
+    Perspective
+}
+
+/// How the models' surfaces are drawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    Shaded,
+    /// Only the edges of the triangles, so hidden parts can be seen through the front
+    Wireframe,
+    /// The shaded surface with the edges of its triangles drawn over it
+    ShadedEdges,
+    /// Brightness by distance from the viewer, from black at the back of the models to white at the front
+    Depth
+}
+
+impl RenderMode {
+    /// The number the shader is given for the mode
+    fn shader_value(&self) -> i32 {
+        match self {
+            RenderMode::Shaded => 0,
+            RenderMode::Wireframe => 1,
+            RenderMode::ShadedEdges => 2,
+            RenderMode::Depth => 3
+        }
+    }
+}
+
+/// Where a ray through the view hit a model
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hit {
+    pub model: usize,
+    /// Index of the triangle in the model's list
+    pub triangle: usize,
+    /// The point hit, in scene coordinates
+    pub point: Vec3
+}
 
 /// All of the data required to display a triangle mesh.
 ///
-/// Provides scaling, translation, and rotation fields,
+/// Provides a camera to move the view around,
 /// as well as helper functions for rotation.
 #[derive(Clone)]
 pub struct ViewState {
-    /// Position of the mesh (relative to its original coordinate system)
-    pub translation: Vec3,
-    /// Size of the mesh during render
-    pub scale: f32,
-    /// Rotation matrix for the mesh.
-    pub rotation: Mat4,
+    /// Where the scene is seen from
+    pub camera: Camera,
+    /// The camera moving to a new viewpoint, if it is on its way
+    transition: Option<Transition>,
+    /// Stretch along each axis of the models, to make shallow relief visible.
+    /// It only changes how they are drawn.
+    pub exaggeration: Vec3,
     pub right_handed: bool,
-    pub light_direction: Vec3,
+    pub projection: Projection,
+    /// Vertical field of view in degrees, for the perspective projection
+    pub fov: f32,
+    /// Distances from the eye to the nearest and farthest things drawn with perspective,
+    /// in units of half the view's height. Orthographic views draw everything up to the far
+    /// distance in front of or behind the center.
+    pub near: f32,
+    pub far: f32,
+    pub render_mode: RenderMode,
+    /// Shade models with their corner normals, where they have them, rather than a normal for each face
+    pub smooth: bool,
+    /// Color the backs of triangles, so flipped ones stand out
+    pub highlight_back_faces: bool,
+    pub back_face_color: [f32; 3],
+    /// Up to `MAX_LIGHTS` lights shining on the models
+    pub lights: Vec<Light>,
     pub ambient: [f32; 3],
     pub diffuse: [f32; 3],
     pub specular: [f32; 3],
-    pub models: Vec<(Arc<GlowState>, Mat4)>,
+    /// Color of the viewport behind the models
+    pub background: [f32; 3],
+    /// Color at the bottom of the viewport, fading up into the background color, if it has a gradient
+    pub background_gradient: Option<[f32; 3]>,
+    /// Planes hiding what is in front of them, to see inside the models, up to `MAX_CLIP_PLANES`
+    pub clip_planes: Vec<Plane>,
+    /// Color the inside of clipped models, so they look solid where they are cut
+    pub caps: bool,
+    pub cap_color: [f32; 3],
+    pub grid: Grid,
+    /// Color for highlights and markers drawn over the models
+    pub overlay: [f32; 3],
+    /// Closed loops of points, drawn over the models in the overlay color
+    pub highlights: Vec<Vec<Vec3>>,
+    /// Closed loops of points, drawn over the models in red
+    pub warnings: Vec<Vec<Vec3>>,
+    /// Outlines of tools placed in the scene, such as a cutting plane, drawn in the overlay color
+    pub guides: Vec<Vec<Vec3>>,
+    /// Whether dragging with the primary button turns the view. Tools that drag
+    /// in the view, like a selection box, turn this off.
+    pub rotate_on_drag: bool,
+    pub models: Vec<Model>,
+    /// Point clouds, drawn along with the models but kept apart from them,
+    /// since they have no triangles to pick or analyze
+    pub point_clouds: Vec<PointCloudModel>,
+    /// Samples for each pixel of the viewport, to smooth the edges of the models. 1 or less turns this off.
+    pub samples: u8,
+    /// Draw the reduced copies of large models while the view moves, if drawing them in full is slow
+    pub reduce_detail: bool,
+    /// Whether the view moved in the last frame
+    moving: bool,
+    /// Whether the reduced copies are drawn this frame
+    reducing: bool,
+    /// The buffer the viewport was last drawn into with multisampling, shared by clones of the state
+    /// so it is only made again when the viewport's size changes
+    multisample: Arc<Mutex<Option<MultisampleBuffer>>>,
     gl: Arc<glow::Context>
 }
 
@@ -39,12 +311,13 @@ impl ViewState {
     ///
     /// This function creates buffers and shaders for the gl context,
     /// which are cleaned up when the RenderableMesh is dropped.
-    pub fn new(gl: Arc<glow::Context>, triangles: &Vec::<Triangle>) -> Result<Self, String> {
+    /// Only the first chunk of a large mesh is uploaded; see `upload_chunk`.
+    pub fn new(gl: Arc<glow::Context>, triangles: &Arc<Vec<Triangle>>) -> Result<Self, RenderError> {
         let mut scale = 1.;
         if triangles.len() > 0 {
             let mut min_point = triangles[0][0].to_owned();
             let mut max_point = triangles[0][0].to_owned();
-            for triangle in triangles {
+            for triangle in triangles.iter() {
                 for vertex in triangle {
                     for i in 0..3 {
                         min_point[i] = min_point[i].min(vertex[i]);
@@ -56,178 +329,825 @@ impl ViewState {
                 scale = 1.0/(max_point - min_point).max();
             }
         }
-        return Ok(Self {
-            scale,
-            translation: -get_center(triangles) * scale,
-            rotation: Mat4::identity(),
+        let mut state = Self {
+            camera: Camera { translation: -get_center(triangles) * scale, scale, rotation: Mat4::identity() },
+            transition: None,
+            exaggeration: Vec3::new(1., 1., 1.),
             right_handed: true,
-            light_direction: Vec3::new(-1.0, -1.0, -1.0),
+            projection: Projection::Orthographic,
+            fov: 45.,
+            near: 0.01,
+            far: 1000.,
+            render_mode: RenderMode::Shaded,
+            smooth: false,
+            highlight_back_faces: false,
+            back_face_color: [0.8, 0.1, 0.1],
+            lights: vec![Light::headlight()],
             ambient: [0.1, 0.1, 0.15],
             diffuse: [0.5, 0.5, 0.45],
             specular: [0.2, 0.2, 0.2],
-            models: vec![(GlowState::new(gl.clone(), triangles)?, Mat4::identity())],
+            background: [0.1, 0.1, 0.1],
+            background_gradient: None,
+            clip_planes: Vec::new(),
+            caps: true,
+            cap_color: [0.6, 0.6, 0.3],
+            grid: Grid::new(),
+            overlay: [1.0, 0.6, 0.1],
+            highlights: Vec::new(),
+            warnings: Vec::new(),
+            guides: Vec::new(),
+            rotate_on_drag: true,
+            models: vec![Model::new(triangles)],
+            point_clouds: Vec::new(),
+            samples: 4,
+            reduce_detail: true,
+            moving: false,
+            reducing: false,
+            multisample: Arc::new(Mutex::new(None)),
             gl
-        });
+        };
+        state.upload_chunk(0)?;
+        return Ok(state);
     }
     
     /// Creates a renderable state with no initial models
-    pub fn new_empty(gl: Arc<glow::Context>) -> Result<Self, String> {
+    pub fn new_empty(gl: Arc<glow::Context>) -> Result<Self, RenderError> {
         return Ok(Self {
-            scale: 1.0,
-            translation: Vec3::zeros(),
-            rotation: Mat4::identity(),
+            camera: Camera { translation: Vec3::zeros(), scale: 1., rotation: Mat4::identity() },
+            transition: None,
+            exaggeration: Vec3::new(1., 1., 1.),
             right_handed: true,
-            light_direction: Vec3::new(-1.0, -1.0, -1.0),
+            projection: Projection::Orthographic,
+            fov: 45.,
+            near: 0.01,
+            far: 1000.,
+            render_mode: RenderMode::Shaded,
+            smooth: false,
+            highlight_back_faces: false,
+            back_face_color: [0.8, 0.1, 0.1],
+            lights: vec![Light::headlight()],
             ambient: [0.1, 0.1, 0.15],
             diffuse: [0.5, 0.5, 0.45],
             specular: [0.2, 0.2, 0.2],
-            models: Vec::<(Arc<GlowState>, Mat4)>::new(),
+            background: [0.1, 0.1, 0.1],
+            background_gradient: None,
+            clip_planes: Vec::new(),
+            caps: true,
+            cap_color: [0.6, 0.6, 0.3],
+            grid: Grid::new(),
+            overlay: [1.0, 0.6, 0.1],
+            highlights: Vec::new(),
+            warnings: Vec::new(),
+            guides: Vec::new(),
+            rotate_on_drag: true,
+            models: Vec::<Model>::new(),
+            point_clouds: Vec::new(),
+            samples: 4,
+            reduce_detail: true,
+            moving: false,
+            reducing: false,
+            multisample: Arc::new(Mutex::new(None)),
             gl
         });
     }
     
-    /// Adds a model to this view_state
-    pub fn add_model(&mut self, gl: Arc<glow::Context>, triangles: &Vec::<Triangle>) -> Result<(), String> {
-        self.models.push((GlowState::new(gl, triangles)?, Mat4::identity()));
+    /// Replaces the triangles of a model, keeping its local transformation.
+    /// Only the first chunk of a large mesh is uploaded; see `upload_chunk`.
+    pub fn replace_model(&mut self, index: usize, triangles: &Arc<Vec<Triangle>>) -> Result<(), RenderError> {
+        self.models[index].triangles = triangles.clone();
+        self.models[index].glow_states.clear();
+        self.models[index].corner_colors.clear();
+        self.models[index].corner_normals.clear();
+        self.models[index].reduced = None;
+        self.models[index].bounds = triangle::transformed_bounds(triangles, &Mat4::identity());
+        self.upload_chunk(index)?;
+        return Ok(());
+    }
+
+    /// Adds a model to this view_state.
+    /// Only the first chunk of a large mesh is uploaded; see `upload_chunk`.
+    pub fn add_model(&mut self, triangles: &Arc<Vec<Triangle>>) -> Result<(), RenderError> {
+        self.models.push(Model::new(triangles));
+        self.upload_chunk(self.models.len() - 1)?;
+        return Ok(());
+    }
+
+    /// Adds a point cloud to the view, uploading all of its points
+    pub fn add_point_cloud(&mut self, cloud: &Arc<PointCloud>) -> Result<(), RenderError> {
+        self.point_clouds.push(PointCloudModel {
+            cloud: cloud.clone(),
+            buffer: Some(PointBuffer::new(self.gl.clone(), cloud)?),
+            local_transform: Mat4::identity(),
+            visible: true,
+            color: [1., 1., 1.],
+            point_size: 3.
+        });
+        return Ok(());
+    }
+
+    /// Uploads the point clouds whose buffers were released, as after the graphics context was lost
+    pub fn upload_point_clouds(&mut self) -> Result<(), RenderError> {
+        for point_cloud in self.point_clouds.iter_mut().filter(|point_cloud| point_cloud.buffer.is_none()) {
+            point_cloud.buffer = Some(PointBuffer::new(self.gl.clone(), &point_cloud.cloud)?);
+        }
         return Ok(());
     }
 
-    /// Combines the transformations (translation, scale, rotatioin)
+    /// Uploads the next chunk of a model's triangles. Returns true once all of them are uploaded.
+    pub fn upload_chunk(&mut self, index: usize) -> Result<bool, RenderError> {
+        let model = &mut self.models[index];
+        let start = model.triangle_count();
+        let end = (start + UPLOAD_CHUNK).min(model.triangles.len());
+        if start < end {
+            let glow_state = GlowState::new(self.gl.clone(), &model.triangles[start..end], start)?;
+            model.glow_states.push(glow_state);
+        }
+        return Ok(end == model.triangles.len());
+    }
+
+    /// Whether the models' buffers on the GPU are gone, as they are when the graphics context
+    /// is lost, like when the window moves to another GPU or the driver resets.
+    /// Nothing is drawn until they are uploaded again.
+    pub fn resources_lost(&self) -> bool {
+        let glow_state = match self.models.iter().find_map(|model| model.glow_states.first()) {
+            Some(glow_state) => glow_state,
+            None => return false
+        };
+        unsafe {
+            return !self.gl.is_buffer(glow_state.vertex_buffer) || !self.gl.is_program(glow_state.shaders.program);
+        }
+    }
+
+    /// Drops every model's buffers on the GPU, after they were lost with the graphics context,
+    /// so that `upload_chunk` builds them again from the triangles each model keeps. Corner colors and normals have to be set again too.
+    pub fn release_resources(&mut self) {
+        for model in &mut self.models {
+            model.glow_states.clear();
+            model.corner_colors.clear();
+            model.corner_normals.clear();
+            model.reduced = None;
+        }
+        for point_cloud in &mut self.point_clouds {
+            point_cloud.buffer = None;
+        }
+        *self.multisample.lock().unwrap() = None;
+    }
+
+    /// Colors each corner of a model's triangles, three colors to a triangle,
+    /// or stops coloring it. The model should be fully uploaded.
+    pub fn set_corner_colors(&mut self, index: usize, colors: Option<&[[f32; 3]]>) -> Result<(), RenderError> {
+        let buffers = match colors {
+            Some(colors) => self.corner_buffers(index, colors)?,
+            None => Vec::new()
+        };
+        self.models[index].corner_colors = buffers;
+        return Ok(());
+    }
+
+    /// Gives each corner of a model's triangles a normal for smooth shading, three to a triangle,
+    /// or drops them. The model should be fully uploaded.
+    pub fn set_corner_normals(&mut self, index: usize, normals: Option<&[[f32; 3]]>) -> Result<(), RenderError> {
+        let buffers = match normals {
+            Some(normals) => self.corner_buffers(index, normals)?,
+            None => Vec::new()
+        };
+        self.models[index].corner_normals = buffers;
+        return Ok(());
+    }
+
+    /// Uploads the reduced copy of a model, drawn in its place while the view moves
+    pub fn set_reduced(&mut self, index: usize, triangles: &[Triangle]) -> Result<(), RenderError> {
+        self.models[index].reduced = Some(GlowState::new(self.gl.clone(), triangles, 0)?);
+        return Ok(());
+    }
+
+    /// Chooses whether the reduced copies of large models are drawn this frame, given whether the view
+    /// is moving and the seconds since the last frame. Once a frame is slow, they are drawn until the view stops.
+    /// Returns true if they are, and the view should be drawn again to show the models in full once it stops.
+    pub fn choose_detail(&mut self, moving: bool, frame_time: f32) -> bool {
+        // After a still frame, the time is how long the app waited for input rather than how long drawing took
+        let slow = self.moving && frame_time > SLOW_FRAME;
+        self.reducing = self.reduce_detail && moving && (self.reducing || slow);
+        self.moving = moving;
+        return self.reducing;
+    }
+
+    /// Splits values for the corners of a model's triangles into a buffer for each of its GlowStates
+    fn corner_buffers(&self, index: usize, values: &[[f32; 3]]) -> Result<Vec<Arc<glow_state::CornerBuffer>>, RenderError> {
+        let mut buffers = Vec::new();
+        for glow_state in &self.models[index].glow_states {
+            let start = glow_state.first_triangle * 3;
+            let end = start + glow_state.triangle_count * 3;
+            if end > values.len() {
+                break;
+            }
+            buffers.push(glow_state::CornerBuffer::new(self.gl.clone(), &values[start..end])?);
+        }
+        return Ok(buffers);
+    }
+
+    /// Combines the transformations (translation, exaggeration, scale, rotatioin)
     /// into a single transformation matrix.
     pub fn combine_transformations(&self) -> Mat4 {
-        let scale_vec = Vec3::new(self.scale, self.scale, self.scale);
-        let scale = glm::scale(&Mat4::identity(),&scale_vec);
-        let translation = glm::translate(&Mat4::identity(), &self.translation);
-        return
-            self.rotation * scale * translation;
+        return self.camera.matrix(&self.exaggeration);
+    }
+
+    /// Moves the camera smoothly to a viewpoint, over the next few frames
+    pub fn move_to(&mut self, camera: Camera) {
+        self.transition = Some(Transition::new(self.camera, camera));
+    }
+
+    /// Leaves the camera where it is, if it was moving to a viewpoint
+    pub fn stop_moving(&mut self) {
+        self.transition = None;
+    }
+
+    /// The viewpoint the camera is moving to, or where it is if it isn't moving
+    pub fn destination(&self) -> Camera {
+        return self.transition.as_ref().map_or(self.camera, |transition| transition.to);
+    }
+
+    /// Moves the camera along by some seconds toward the viewpoint it is moving to.
+    /// Returns true while it has farther to go.
+    pub fn animate(&mut self, seconds: f32) -> bool {
+        let transition = match &mut self.transition {
+            Some(transition) => transition,
+            None => return false
+        };
+        let (camera, arrived) = transition.advance(seconds);
+        self.camera = camera;
+        if arrived {
+            self.transition = None;
+        }
+        return !arrived;
+    }
+
+    /// The material of models without their own, from the view's lighting
+    pub fn default_material(&self) -> Material {
+        return Material { ambient: self.ambient, diffuse: self.diffuse, specular: self.specular };
+    }
+
+    /// Sets the lights of a shader program, turning the ones fixed in the scene along with the camera
+    unsafe fn set_lights(&self, program: glow::Program) {
+        let gl = &self.gl;
+        let lights = &self.lights[..self.lights.len().min(MAX_LIGHTS)];
+        let directions: Vec<f32> = lights.iter()
+            .flat_map(|light| {
+                let direction = if light.follows_camera {
+                    light.direction
+                } else {
+                    (self.camera.rotation * light.direction.push(0.)).xyz()
+                };
+                let direction = if direction.norm() > 0. { direction.normalize() } else { direction };
+                [direction.x, direction.y, direction.z]
+            })
+            .collect();
+        let colors: Vec<f32> = lights.iter()
+            .flat_map(|light| light.color.map(|c| c * light.intensity))
+            .collect();
+        gl.uniform_1_i32(
+            gl.get_uniform_location(program, "light_count").as_ref(),
+            lights.len() as i32);
+        if !lights.is_empty() {
+            gl.uniform_3_f32_slice(
+                gl.get_uniform_location(program, "light_directions").as_ref(),
+                &directions);
+            gl.uniform_3_f32_slice(
+                gl.get_uniform_location(program, "light_colors").as_ref(),
+                &colors);
+        }
+    }
+
+    /// Sets the clipping planes of a shader program, for drawing a model placed by a transformation
+    unsafe fn set_clip_planes(&self, program: glow::Program, local_transform: &Mat4) {
+        let gl = &self.gl;
+        let planes: Vec<f32> = self.clip_planes.iter()
+            .take(MAX_CLIP_PLANES)
+            .flat_map(|plane| [plane.normal.x, plane.normal.y, plane.normal.z, plane.offset])
+            .collect();
+        gl.uniform_matrix_4_f32_slice(
+            gl.get_uniform_location(program, "u_local").as_ref(),
+            false,
+            local_transform.as_slice());
+        gl.uniform_1_i32(
+            gl.get_uniform_location(program, "clip_plane_count").as_ref(),
+            planes.len() as i32 / 4);
+        if !planes.is_empty() {
+            gl.uniform_4_f32_slice(
+                gl.get_uniform_location(program, "clip_planes").as_ref(),
+                &planes);
+        }
+    }
+
+    /// True if any axis is stretched for display
+    pub fn exaggerated(&self) -> bool {
+        return self.exaggeration != Vec3::new(1., 1., 1.);
+    }
+
+    /// Centers the view on a point, scaled so that a sphere
+    /// with the given radius fills half the height of the view
+    pub fn frame(&mut self, center: Vec3, radius: f32) {
+        self.stop_moving();
+        self.camera.frame(center, radius);
+    }
+
+    /// Scales the view by a factor, keeping the point under a position of the viewport in place
+    pub fn zoom_toward(&mut self, factor: f32, position: egui::Pos2, rect: egui::Rect) {
+        let inverse = match self.combine_transformations().try_inverse() {
+            Some(inverse) => inverse,
+            None => return
+        };
+        let offset = (position - rect.center()) / (rect.height() / 2.);
+        // The point on the plane through the center of the view, which projects the same either way
+        let anchor = (inverse * Vec4::new(offset.x, -offset.y, 0., 1.)).xyz();
+        self.stop_moving();
+        self.camera.scale *= factor;
+        self.camera.translation = (anchor + self.camera.translation) / factor - anchor;
+    }
+
+    /// Maps the combined transformations' coordinates, where the view's height spans -1 to 1,
+    /// into clip space for a viewport with an aspect ratio. Nearer points come out with a smaller depth.
+    pub fn projection_matrix(&self, aspect_ratio: f32) -> Mat4 {
+        // The models are seen from +Z when right handed, so left handed views are flipped to match
+        let handedness = if self.right_handed {
+            Mat4::identity()
+        } else {
+            glm::scale(&Mat4::identity(), &Vec3::new(1., 1., -1.))
+        };
+        return match self.projection {
+            Projection::Orthographic => glm::ortho(-aspect_ratio, aspect_ratio, -1., 1., -self.far, self.far) * handedness,
+            Projection::Perspective => {
+                let fov = self.fov.clamp(1., 179.).to_radians();
+                let near = self.near.max(1e-4);
+                let far = self.far.max(near * 2.);
+                // The eye is far enough back that the center of the view is the same size as orthographic
+                let distance = 1. / (fov / 2.).tan();
+                let eye = glm::translate(&Mat4::identity(), &Vec3::new(0., 0., -distance));
+                glm::perspective(aspect_ratio, fov, near, far) * eye * handedness
+            }
+        };
+    }
+
+    /// Projects a point into the screen space of a viewport
+    pub fn project(&self, point: &Vec3, rect: egui::Rect) -> egui::Pos2 {
+        let aspect_ratio = rect.width() / rect.height();
+        let clip = self.projection_matrix(aspect_ratio) * self.combine_transformations() * point.push(1.);
+        let projected = clip.xy() / clip.w;
+        return rect.center() + egui::Vec2::new(projected.x * rect.width(), -projected.y * rect.height()) / 2.;
+    }
+
+    /// Projects a line segment into the screen space of a viewport, cut off where it passes
+    /// nearer than the near plane, or None if all of it does
+    pub fn project_segment(&self, start: &Vec3, end: &Vec3, rect: egui::Rect) -> Option<[egui::Pos2; 2]> {
+        let matrix = self.projection_matrix(rect.width() / rect.height()) * self.combine_transformations();
+        let mut start = matrix * start.push(1.);
+        let mut end = matrix * end.push(1.);
+        // Clip space puts the near plane where z is -w
+        let (start_depth, end_depth) = (start.z + start.w, end.z + end.w);
+        if start_depth < 0. && end_depth < 0. {
+            return None;
+        }
+        if start_depth < 0. {
+            start = glm::lerp(&start, &end, start_depth / (start_depth - end_depth));
+        } else if end_depth < 0. {
+            end = glm::lerp(&end, &start, end_depth / (end_depth - start_depth));
+        }
+        let to_screen = |clip: Vec4| {
+            let projected = clip.xy() / clip.w;
+            return rect.center() + egui::Vec2::new(projected.x * rect.width(), -projected.y * rect.height()) / 2.;
+        };
+        return Some([to_screen(start), to_screen(end)]);
+    }
+
+    /// Finds the line through the models under a point of a viewport.
+    ///
+    /// Returns the point on the line where the nearest things are drawn, and the direction
+    /// to where the farthest are, in model coordinates.
+    pub fn unproject(&self, position: egui::Pos2, rect: egui::Rect) -> Option<(Vec3, Vec3)> {
+        let aspect_ratio = rect.width() / rect.height();
+        let inverse = (self.projection_matrix(aspect_ratio) * self.combine_transformations()).try_inverse()?;
+        let offset = position - rect.center();
+        let (x, y) = (2. * offset.x / rect.width(), -2. * offset.y / rect.height());
+        let near = inverse * Vec4::new(x, y, -1., 1.);
+        let far = inverse * Vec4::new(x, y, 1., 1.);
+        let near = near.xyz() / near.w;
+        return Some((near, far.xyz() / far.w - near));
+    }
+
+    /// Finds the nearest visible triangle hit by a line in scene coordinates, with the direction
+    /// pointing away from the viewer. Each model's triangles and picking hierarchy are given in
+    /// the same order as the models, and models without them are skipped.
+    pub fn pick(&self, origin: &Vec3, direction: &Vec3, models: &[Option<(&[Triangle], &Bvh)>]) -> Option<Hit> {
+        profile_span!("pick");
+        let mut nearest: Option<(Hit, f32)> = None;
+        for (i, (model, picking)) in self.models.iter().zip(models).enumerate() {
+            let (triangles, bvh) = match (model.visible, picking) {
+                (true, Some(picking)) => picking,
+                _ => continue
+            };
+            let inverse = match model.local_transform.try_inverse() {
+                Some(inverse) => inverse,
+                None => continue
+            };
+            // Distances along the line are the same in every model's coordinates
+            let local_origin = (inverse * origin.push(1.)).xyz();
+            let local_direction = (inverse * direction.push(0.)).xyz();
+            if let Some((triangle, t)) = bvh.pick(triangles, &local_origin, &local_direction) {
+                if !matches!(nearest, Some((_, nearest_t)) if nearest_t <= t) {
+                    nearest = Some((Hit { model: i, triangle, point: origin + direction * t }, t));
+                }
+            }
+        }
+        return nearest.map(|(hit, _)| hit);
+    }
+
+    /// Finds the nearest visible triangle under a point of a viewport, like `pick`
+    pub fn pick_at(&self, position: egui::Pos2, rect: egui::Rect, models: &[Option<(&[Triangle], &Bvh)>]) -> Option<Hit> {
+        let (origin, direction) = self.unproject(position, rect)?;
+        return self.pick(&origin, &direction, models);
     }
 
     /// Renders the mesh to its glow::Context using its combined transformations
-    /// As side effects, this enables the depth test, clears and uses the depth buffer,
+    /// As side effects, this enables the depth test and blending, clears and uses the depth buffer,
     /// and sets the shader program to that of the Renderable Mesh
     pub fn draw(&self, aspect_ratio: f32) {
-        if self.models.len() == 0 {
+        profile_span!("draw");
+        if self.models.len() == 0 && self.point_clouds.is_empty() {
             return;
         }
         let transformation_matrix = self.combine_transformations();
+        let projection = self.projection_matrix(aspect_ratio);
         let gl = &self.gl;
         unsafe {
             gl.enable(glow::DEPTH_TEST);
-            if self.right_handed {
-                gl.depth_range_f32(1., -1.);
-            } else {
-                gl.depth_range_f32(-1., 1.);
-            }
+            gl.depth_range_f32(0., 1.);
             gl.clear(glow::DEPTH_BUFFER_BIT);
-            for (glow_state, local_transform) in &self.models {
-                let transformation = (transformation_matrix * local_transform).as_slice().to_owned();
-                gl.use_program(Some(glow_state.shader_program));
-                gl.uniform_matrix_4_f32_slice(
-                    gl.get_uniform_location(glow_state.shader_program, "u_transformation").as_ref(),
-                    false,
-                    &transformation,
-                );
-                gl.uniform_3_f32_slice(
-                    gl.get_uniform_location(glow_state.shader_program, "light_direction").as_ref(),
-                    self.light_direction.normalize().as_slice());
-                gl.uniform_3_f32_slice(
-                    gl.get_uniform_location(glow_state.shader_program, "ambient").as_ref(),
-                    self.ambient.as_slice());
-                gl.uniform_3_f32_slice(
-                    gl.get_uniform_location(glow_state.shader_program, "diffuse").as_ref(),
-                    self.diffuse.as_slice());
-                gl.uniform_3_f32_slice(
-                    gl.get_uniform_location(glow_state.shader_program, "specular").as_ref(),
-                    self.specular.as_slice());
-                gl.uniform_1_f32(
-                    gl.get_uniform_location(glow_state.shader_program, "aspect_ratio").as_ref(),
-                    aspect_ratio);
-                gl.bind_vertex_array(Some(glow_state.vertex_array));
-                gl.draw_arrays(glow::TRIANGLES, 0, self.get_triangle_count() as i32 * 3);
+            gl.enable(glow::BLEND);
+            gl.blend_func(glow::ONE, glow::ONE_MINUS_SRC_ALPHA);
+            let depth_range = self.depth_range();
+            // Opaque models go first, so transparent ones blend over everything behind them
+            let mut transparent = Vec::new();
+            for model in self.models.iter().filter(|model| model.visible) {
+                let transformation = transformation_matrix * model.local_transform;
+                if model.outside_view(&(projection * transformation)) {
+                    continue;
+                }
+                for (glow_state, corners) in self.buffers(model) {
+                    if model.opacity < 1. {
+                        let center = projection * transformation * glow_state.center.push(1.);
+                        transparent.push((center.z / center.w, model, glow_state, corners));
+                    } else {
+                        self.draw_buffer(model, glow_state, corners, &transformation, &projection, depth_range);
+                    }
+                }
+            }
+            self.draw_point_clouds(&transformation_matrix, &projection);
+            // Transparent buffers are drawn back to front, and don't hide each other in the depth buffer
+            transparent.sort_by(|a, b| b.0.total_cmp(&a.0));
+            gl.depth_mask(false);
+            gl.enable(glow::CULL_FACE);
+            for (_, model, glow_state, corners) in transparent {
+                let transformation = transformation_matrix * model.local_transform;
+                // The far side of a closed model is drawn first, so the near side blends over it
+                for culled in [glow::FRONT, glow::BACK] {
+                    gl.cull_face(culled);
+                    self.draw_buffer(model, glow_state, corners, &transformation, &projection, depth_range);
+                }
+            }
+            gl.disable(glow::CULL_FACE);
+            gl.front_face(glow::CCW);
+            gl.depth_mask(true);
+        }
+    }
+
+    /// Renders like `draw` into the current viewport, with the edges of the models smoothed
+    /// by multisampling if it is turned on and the buffer for it can be made
+    pub fn draw_smoothed(&self, aspect_ratio: f32) {
+        if self.samples <= 1 {
+            self.draw(aspect_ratio);
+            return;
+        }
+        let mut viewport = [0; 4];
+        unsafe {
+            self.gl.get_parameter_i32_slice(glow::VIEWPORT, &mut viewport);
+        }
+        let [left, bottom, width, height] = viewport;
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (width, height) = (width as usize, height as usize);
+        let mut multisample = self.multisample.lock().unwrap();
+        match &mut *multisample {
+            // Resizing keeps the buffer, so the viewport can change size every frame while it is dragged
+            Some(buffer) if buffer.samples == self.samples => buffer.resize(width, height),
+            _ => {
+                // The old buffer goes first, to free its memory for the new one
+                *multisample = None;
+                *multisample = MultisampleBuffer::new(self.gl.clone(), width, height, self.samples).ok();
+            }
+        }
+        match &*multisample {
+            Some(buffer) => {
+                buffer.bind();
+                self.draw(aspect_ratio);
+                buffer.resolve(left, bottom);
+            },
+            None => self.draw(aspect_ratio)
+        }
+    }
+
+    /// The buffers to draw a model with this frame, each with the index of its corner colors and normals.
+    /// Models colored by an analysis are always drawn in full, since the colors are for their own triangles.
+    fn buffers<'a>(&self, model: &'a Model) -> Vec<(&'a GlowState, Option<usize>)> {
+        if let (true, Some(reduced), true) = (self.reducing, &model.reduced, model.corner_colors.is_empty()) {
+            return vec![(reduced.as_ref(), None)];
+        }
+        return model.glow_states.iter().enumerate().map(|(i, glow_state)| (glow_state.as_ref(), Some(i))).collect();
+    }
+
+    /// Draws one of a model's buffers with the model's transformation into the view.
+    /// Its corner colors and normals are used if it has them.
+    unsafe fn draw_buffer(&self, model: &Model, glow_state: &GlowState, corners: Option<usize>, transformation: &Mat4,
+        projection: &Mat4, depth_range: [f32; 2]) {
+        let gl = &self.gl;
+        let material = model.material.unwrap_or_else(|| self.default_material());
+        // Mirrored models wind their triangles the other way on screen, and so does a left handed view
+        let mirrored = glm::mat4_to_mat3(&model.local_transform).determinant() < 0.;
+        gl.front_face(if mirrored == self.right_handed { glow::CW } else { glow::CCW });
+        gl.use_program(Some(glow_state.shaders.program));
+        gl.uniform_matrix_4_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "u_transformation").as_ref(),
+            false,
+            transformation.as_slice(),
+        );
+        gl.uniform_matrix_4_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "u_projection").as_ref(),
+            false,
+            projection.as_slice());
+        self.set_lights(glow_state.shaders.program);
+        gl.uniform_3_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "ambient").as_ref(),
+            material.ambient.as_slice());
+        gl.uniform_3_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "diffuse").as_ref(),
+            material.diffuse.as_slice());
+        gl.uniform_3_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "tint").as_ref(),
+            model.color.as_slice());
+        gl.uniform_3_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "specular").as_ref(),
+            material.specular.as_slice());
+        gl.uniform_1_i32(
+            gl.get_uniform_location(glow_state.shaders.program, "render_mode").as_ref(),
+            self.render_mode.shader_value());
+        gl.uniform_2_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "depth_range").as_ref(),
+            &depth_range);
+        gl.uniform_1_f32(
+            gl.get_uniform_location(glow_state.shaders.program, "opacity").as_ref(),
+            model.opacity.clamp(0., 1.));
+        self.set_clip_planes(glow_state.shaders.program, &model.local_transform);
+        gl.uniform_1_i32(
+            gl.get_uniform_location(glow_state.shaders.program, "caps").as_ref(),
+            self.caps as i32);
+        gl.uniform_3_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "cap_color").as_ref(),
+            self.cap_color.as_slice());
+        gl.uniform_1_i32(
+            gl.get_uniform_location(glow_state.shaders.program, "highlight_back_faces").as_ref(),
+            self.highlight_back_faces as i32);
+        gl.uniform_3_f32_slice(
+            gl.get_uniform_location(glow_state.shaders.program, "back_face_color").as_ref(),
+            self.back_face_color.as_slice());
+        gl.bind_vertex_array(Some(glow_state.vertex_array));
+        // The vertex array remembers where normals come from, so it is pointed back at
+        // the face normals whenever the smooth ones aren't used
+        match corners.and_then(|i| model.corner_normals.get(i)) {
+            Some(normals) if self.smooth => {
+                gl.bind_buffer(glow::ARRAY_BUFFER, Some(normals.buffer));
+                gl.vertex_attrib_pointer_f32(1, 3, glow::FLOAT, false, 12, 0);
             }
+            _ => {
+                gl.bind_buffer(glow::ARRAY_BUFFER, Some(glow_state.vertex_buffer));
+                gl.vertex_attrib_pointer_f32(1, 3, glow::FLOAT, false, 24, 12);
+            }
+        }
+        if let Some(colors) = corners.and_then(|i| model.corner_colors.get(i)) {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(colors.buffer));
+            gl.enable_vertex_attrib_array(2);
+            gl.vertex_attrib_pointer_f32(2, 3, glow::FLOAT, false, 12, 0);
+        } else {
+            gl.disable_vertex_attrib_array(2);
+            gl.vertex_attrib_3_f32(2, 1., 1., 1.);
         }
+        gl.draw_arrays(glow::TRIANGLES, 0, glow_state.triangle_count as i32 * 3);
     }
     
-    /// Draws the model to an RGBA pixel buffer
-    pub fn draw_pixels(&self, width: usize, height: usize) -> Result<Vec<u8>, String> {
+    /// Draws the visible point clouds as dots, which hide what is behind them like the opaque models
+    unsafe fn draw_point_clouds(&self, transformation_matrix: &Mat4, projection: &Mat4) {
         let gl = &self.gl;
-        unsafe {
-            let framebuffer  = gl.create_framebuffer()?;
-            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
-            let gl_texture = gl.create_texture()?;
-            gl.bind_texture(glow::TEXTURE_2D, Some(gl_texture));
-            gl.tex_image_2d(glow::TEXTURE_2D, 0, glow::SRGB8_ALPHA8 as i32, width as i32, height as i32, 0, glow::RGBA, glow::UNSIGNED_BYTE, None);
-            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
-            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
-            let depth_buffer = gl.create_renderbuffer()?;
-            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(depth_buffer));
-            gl.renderbuffer_storage(glow::RENDERBUFFER, glow::DEPTH_COMPONENT, width as i32, height as i32);
-            gl.framebuffer_renderbuffer(glow::FRAMEBUFFER, glow::DEPTH_ATTACHMENT, glow::RENDERBUFFER, Some(depth_buffer));
-            gl.framebuffer_texture(glow::FRAMEBUFFER, glow::COLOR_ATTACHMENT0, Some(gl_texture), 0);
-            gl.draw_buffer(glow::COLOR_ATTACHMENT0);
-
-            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
-            gl.viewport(0, 0, width as i32, height as i32);
-            gl.clear_color(0.0, 0.0, 0.0, 0.0);
-            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
-            self.draw(width as f32/height as f32);
-            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
-
-            let mut buffer = vec![0 as u8; (width * height * 4) as usize];
-            gl.get_tex_image(
-                glow::TEXTURE_2D,
-                0,
-                glow::RGBA,
-                glow::UNSIGNED_BYTE,
-                glow::PixelPackData::Slice(buffer.as_mut_slice()));
-            let mut flipped_buffer = vec![0 as u8; (width * height * 4) as usize];
-            for x in 0..width as usize{
-                for y in 0..height as usize{
-                    let i1 = (x + width * y) * 4;
-                    let i2 = (x + width * ((height - 1) - y)) * 4;
-                    flipped_buffer[i1] = buffer[i2];
-                    flipped_buffer[i1 + 1] = buffer[i2 + 1];
-                    flipped_buffer[i1 + 2] = buffer[i2 + 2];
-                    flipped_buffer[i1 + 3] = buffer[i2 + 3];
+        // Lets the shader set the size of the dots
+        gl.enable(glow::PROGRAM_POINT_SIZE);
+        for point_cloud in self.point_clouds.iter().filter(|point_cloud| point_cloud.visible) {
+            let buffer = match &point_cloud.buffer {
+                Some(buffer) => buffer,
+                None => continue
+            };
+            let program = buffer.shaders.point_program;
+            let transformation = transformation_matrix * point_cloud.local_transform;
+            gl.use_program(Some(program));
+            gl.uniform_matrix_4_f32_slice(
+                gl.get_uniform_location(program, "u_transformation").as_ref(),
+                false,
+                transformation.as_slice());
+            gl.uniform_matrix_4_f32_slice(
+                gl.get_uniform_location(program, "u_projection").as_ref(),
+                false,
+                projection.as_slice());
+            gl.uniform_1_f32(
+                gl.get_uniform_location(program, "point_size").as_ref(),
+                point_cloud.point_size.max(1.));
+            gl.uniform_3_f32_slice(
+                gl.get_uniform_location(program, "tint").as_ref(),
+                point_cloud.color.as_slice());
+            self.set_clip_planes(program, &point_cloud.local_transform);
+            gl.bind_vertex_array(Some(buffer.vertex_array));
+            if buffer.color_buffer.is_none() {
+                gl.vertex_attrib_3_f32(1, 1., 1., 1.);
+            }
+            gl.draw_arrays(glow::POINTS, 0, buffer.point_count as i32);
+        }
+        gl.disable(glow::PROGRAM_POINT_SIZE);
+    }
+
+    /// The farthest and nearest distances toward the viewer of the visible models, before the projection,
+    /// measured around each of their buffers
+    fn view_depths(&self) -> Option<(f32, f32)> {
+        let transformation_matrix = self.combine_transformations();
+        let mut range: Option<(f32, f32)> = None;
+        for model in self.models.iter().filter(|model| model.visible) {
+            let transformation = transformation_matrix * model.local_transform;
+            // The largest stretch along any axis, which the radius grows by at most
+            let stretch = (0..3).map(|i| transformation.column(i).xyz().norm()).fold(0., f32::max);
+            for glow_state in &model.glow_states {
+                let depth = (transformation * glow_state.center.push(1.)).z;
+                let radius = glow_state.radius * stretch;
+                range = Some(match range {
+                    Some((low, high)) => (low.min(depth - radius), high.max(depth + radius)),
+                    None => (depth - radius, depth + radius)
+                });
+            }
+        }
+        return range;
+    }
+
+    /// The depths drawn black and white in the depth mode. The viewer is toward +Z when right handed.
+    fn depth_range(&self) -> [f32; 2] {
+        let (low, high) = self.view_depths().unwrap_or((-1., 1.));
+        return if self.right_handed { [low, high] } else { [high, low] };
+    }
+
+    /// Draws the model to an RGBA pixel buffer, with the top row first. Where nothing was drawn is
+    /// either transparent, or the background, with the same gradient as the viewport.
+    pub fn draw_pixels(&self, width: usize, height: usize, transparent: bool) -> Result<Vec<u8>, RenderError> {
+        let buffer = RenderBuffer::new(self.gl.clone(), width, height, BufferFormat::Color)?;
+        buffer.bind();
+        self.draw(width as f32/height as f32);
+        buffer.unbind();
+        let mut pixels = buffer.read_pixels();
+        let top = Vec3::from(self.background);
+        let bottom = Vec3::from(self.background_gradient.unwrap_or(self.background));
+        for (y, row) in pixels.chunks_exact_mut(width * 4).enumerate() {
+            let background = glm::lerp(&top, &bottom, y as f32 / (height.max(2) - 1) as f32) * 255.;
+            for pixel in row.chunks_exact_mut(4) {
+                let alpha = pixel[3] as f32 / 255.;
+                for c in 0..3 {
+                    // The colors are drawn premultiplied by their alpha
+                    pixel[c] = if transparent {
+                        if alpha > 0. { (pixel[c] as f32 / alpha).min(255.) as u8 } else { 0 }
+                    } else {
+                        (pixel[c] as f32 + background[c] * (1. - alpha)).min(255.) as u8
+                    };
+                }
+                if !transparent {
+                    pixel[3] = 255;
                 }
             }
+        }
+        return Ok(pixels);
+    }
 
-            gl.delete_framebuffer(framebuffer);
-            gl.delete_texture(gl_texture);
-            gl.delete_renderbuffer(depth_buffer);
+    /// Draws the models to a depth buffer, and reads the depth of each pixel, with the top row first.
+    /// Depths go from 0 at the nearest to 1 at the farthest, and pixels where nothing was drawn are 1.
+    ///
+    /// Orthographic views are drawn with the depth range just fitting the models, for the most precision,
+    /// so the depths are proportional to distance along the view.
+    pub fn draw_depths(&self, width: usize, height: usize) -> Result<Vec<f32>, RenderError> {
+        let mut view = self.clone();
+        if let (Projection::Orthographic, Some((low, high))) = (view.projection, view.view_depths()) {
+            view.far = low.abs().max(high.abs()) * 1.01;
+        }
+        let buffer = RenderBuffer::new(self.gl.clone(), width, height, BufferFormat::Color)?;
+        buffer.bind();
+        view.draw(width as f32/height as f32);
+        buffer.unbind();
+        return Ok(buffer.read_depths());
+    }
 
-            return Ok(flipped_buffer);
+    /// Finds which model and triangle are drawn at a point of a viewport.
+    ///
+    /// The ids are drawn to a one pixel offscreen buffer, zoomed in on the point,
+    /// so this works without any preparation of the triangles on the CPU.
+    pub fn pick_ids(&self, position: egui::Pos2, rect: egui::Rect) -> Result<Option<(usize, usize)>, RenderError> {
+        profile_span!("pick_ids");
+        if rect.width() * rect.height() == 0. {
+            return Ok(None);
         }
+        let buffer = RenderBuffer::new(self.gl.clone(), 1, 1, BufferFormat::Ids)?;
+        let offset = position - rect.center();
+        let pick_center = [2. * offset.x / rect.width(), -2. * offset.y / rect.height()];
+        let pick_scale = [rect.width(), rect.height()];
+        let transformation_matrix = self.combine_transformations();
+        let projection = self.projection_matrix(rect.width() / rect.height());
+        let gl = &self.gl;
+        buffer.bind();
+        unsafe {
+            gl.enable(glow::DEPTH_TEST);
+            gl.depth_range_f32(0., 1.);
+            for (i, model) in self.models.iter().enumerate() {
+                if !model.visible {
+                    continue;
+                }
+                let transformation = (transformation_matrix * model.local_transform).as_slice().to_owned();
+                for glow_state in &model.glow_states {
+                    let program = glow_state.shaders.id_program;
+                    gl.use_program(Some(program));
+                    gl.uniform_matrix_4_f32_slice(
+                        gl.get_uniform_location(program, "u_transformation").as_ref(),
+                        false,
+                        &transformation);
+                    gl.uniform_matrix_4_f32_slice(
+                        gl.get_uniform_location(program, "u_projection").as_ref(),
+                        false,
+                        projection.as_slice());
+                    gl.uniform_2_f32_slice(
+                        gl.get_uniform_location(program, "pick_center").as_ref(),
+                        &pick_center);
+                    gl.uniform_2_f32_slice(
+                        gl.get_uniform_location(program, "pick_scale").as_ref(),
+                        &pick_scale);
+                    // Model ids start at 1, since 0 is the background
+                    gl.uniform_1_u32(
+                        gl.get_uniform_location(program, "model_id").as_ref(),
+                        i as u32 + 1);
+                    gl.uniform_1_u32(
+                        gl.get_uniform_location(program, "first_triangle").as_ref(),
+                        glow_state.first_triangle as u32);
+                    self.set_clip_planes(program, &model.local_transform);
+                    gl.bind_vertex_array(Some(glow_state.vertex_array));
+                    gl.draw_arrays(glow::TRIANGLES, 0, glow_state.triangle_count as i32 * 3);
+                }
+            }
+        }
+        buffer.unbind();
+        let [model, triangle] = buffer.read_ids(0, 0);
+        if model == 0 {
+            return Ok(None);
+        }
+        return Ok(Some((model as usize - 1, triangle as usize)));
     }
     /// Reference to the glow::Context used to create this mesh's buffers and shaders
     #[allow(dead_code)]
     pub fn get_gl(&self) -> Arc<glow::Context> {
         return self.gl.to_owned();}
     /// The number of triangles in the vertex buffers
-    #[allow(dead_code)]
     pub fn get_triangle_count(&self) -> usize{
         let mut acc = 0;
-        for (model, _) in &self.models {
-            acc += model.triangle_count;
+        for model in &self.models {
+            acc += model.triangle_count();
         }
         return acc;
     }
+    /// The number of triangles in the vertex buffers with any area
+    pub fn get_rendered_count(&self) -> usize {
+        return self.models.iter().map(|model| model.rendered_count()).sum();
+    }
     /// Sets the rotation matrix back to the identity matrix
     #[allow(dead_code)]
     pub fn reset_rotation(&mut self) {
-        self.rotation = Mat4::identity();}
+        self.camera.rotation = Mat4::identity();}
     /// Rotate around the x axis (relative to the model's current rotation)
     #[allow(dead_code)]
     pub fn rotate_x(&mut self, radians: f32) {
-        self.rotation = glm::rotate_x(&self.rotation, radians);}
+        self.camera.rotation = glm::rotate_x(&self.camera.rotation, radians);}
     /// Rotate around the y axis (relative to the model's current rotation)
     #[allow(dead_code)]
     pub fn rotate_y(&mut self, radians: f32) {
-        self.rotation = glm::rotate_y(&self.rotation, radians);}
+        self.camera.rotation = glm::rotate_y(&self.camera.rotation, radians);}
     /// Rotate around the z axis (relative to the model's current rotation)
     #[allow(dead_code)]
     pub fn rotate_z(&mut self, radians: f32) {
-        self.rotation = glm::rotate_z(&self.rotation, radians);}
+        self.camera.rotation = glm::rotate_z(&self.camera.rotation, radians);}
 }
 
 fn get_bounds(mesh: &Vec<Triangle>) -> Option<(Vec3, Vec3)> {
@@ -0,0 +1,100 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::i18n::{tr, tr_args};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::shapes;
+use crate::triangle::Triangle;
+use crate::units::Unit;
+
+/// Steepest draft allowed, past which the block would lean more than it stands
+const MAX_DRAFT: f32 = 45.;
+
+/// What the application should do after the mold box dialog is shown
+pub enum MoldBoxResponse {
+    None,
+    /// Add a model with this name and these triangles to the scene
+    Add(String, Vec<Triangle>)
+}
+
+/// A dialog to make a block around a model, as placed in the scene, to cut the model's shape out of
+/// when making a mold
+pub struct MoldBoxUI {
+    /// Index of the model the box is made around, while the dialog is open
+    pub model: Option<usize>,
+    /// The space left between the model and each side of the box, on each axis
+    pub margin: Vec3,
+    /// How far the sides lean in toward the top, in degrees
+    pub draft: f32
+}
+
+impl MoldBoxUI {
+    pub fn new() -> Self {
+        return Self { model: None, margin: Vec3::repeat(5.), draft: 0. };
+    }
+
+    pub fn open(&mut self, model: usize) {
+        self.model = Some(model);
+    }
+
+    pub fn close(&mut self) {
+        self.model = None;
+    }
+
+    fn valid(&self) -> bool {
+        return self.margin.iter().all(|m| *m >= 0.) && (0. ..MAX_DRAFT).contains(&self.draft);
+    }
+
+    /// The edges of the box that would be made around a model with these bounds in the scene
+    pub fn outlines(&self, min: &Vec3, max: &Vec3) -> Vec<Vec<Vec3>> {
+        if self.model.is_none() || !self.valid() {
+            return Vec::new();
+        }
+        let (bottom, top) = shapes::mold_box_corners(min, max, &self.margin, self.draft);
+        let mut lines = vec![
+            bottom.iter().chain(&bottom[..1]).copied().collect(),
+            top.iter().chain(&top[..1]).copied().collect()];
+        lines.extend(bottom.iter().zip(&top).map(|(b, t)| vec![*b, *t]));
+        return lines;
+    }
+
+    /// Shows the dialog if it is open, for the model with this name and these bounds in the scene
+    pub fn show(&mut self, ctx: &egui::Context, name: &str, min: &Vec3, max: &Vec3, unit: Unit) -> MoldBoxResponse {
+        if self.model.is_none() {
+            return MoldBoxResponse::None;
+        }
+        let mut open = true;
+        let mut create = false;
+        let length = Quantity::Length(unit);
+        egui::Window::new(tr("mold-box"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.label(tr_args("mold-box-around", &[("name", &name)]));
+            egui::Grid::new("mold_box_sizes").show(ui, |ui| {
+                for (axis, axis_name) in ["X", "Y", "Z"].iter().enumerate() {
+                    ui.label(tr_args("mold-box-margin", &[("axis", axis_name)]));
+                    ui.add(NumericEdit::new(("margin", axis), &mut self.margin[axis], length));
+                    ui.end_row();
+                }
+                ui.label(tr("mold-box-draft"));
+                ui.add(NumericEdit::new("draft", &mut self.draft, Quantity::Angle))
+                    .on_hover_text(tr("mold-box-draft-hint"));
+                ui.end_row();
+            });
+            if !self.valid() {
+                ui.label(tr("mold-box-invalid"));
+            }
+            create = ui.add_enabled(self.valid(), egui::Button::new(tr("mold-box-create"))).clicked();
+        });
+        if !open {
+            self.close();
+        }
+        if !create {
+            return MoldBoxResponse::None;
+        }
+        self.close();
+        let triangles = shapes::mold_box(min, max, &self.margin, self.draft);
+        return MoldBoxResponse::Add(tr_args("mold-box-name", &[("name", &name)]), triangles);
+    }
+}
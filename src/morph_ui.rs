@@ -0,0 +1,199 @@
+use std::io::Error;
+use std::sync::Arc;
+
+use crate::i18n::{tr, tr_args};
+use crate::morph::Morph;
+use crate::scene::SceneModel;
+use crate::thread_request::Request;
+use crate::triangle::{self, Triangle};
+
+/// What the application should do after the morph dialog is shown
+pub enum MorphResponse {
+    None,
+    /// Add a blended surface to the scene, with this name. It is already placed.
+    Add(String, Vec<Triangle>),
+    /// Stages were written to these files, or failed to be
+    Exported(Result<Vec<String>, Error>)
+}
+
+/// A dialog to blend one model into the shape of another, aligned with it,
+/// giving the intermediate surfaces between them
+pub struct MorphUI {
+    /// Index of the model being blended, while the dialog is open
+    pub source: Option<usize>,
+    /// Index of the model whose shape it is blended toward
+    pub target: usize,
+    /// How far along the blend is, from the source at 0 to the target at 1
+    pub t: f32,
+    /// Number of files written by "Export stages", including both ends
+    pub stages: usize,
+    /// The matched vertices, once found for the current source and target
+    morph: Option<Arc<Morph>>,
+    request: Option<Request<Option<Morph>>>,
+    /// Stages being written in the background
+    export: Option<Request<Result<Vec<String>, Error>>>
+}
+
+impl MorphUI {
+    pub fn new() -> Self {
+        return Self { source: None, target: 0, t: 0.5, stages: 5, morph: None, request: None, export: None };
+    }
+
+    /// Opens the dialog for a model, blending toward the next model in the scene
+    pub fn open(&mut self, source: usize, model_count: usize) {
+        self.close();
+        self.source = Some(source);
+        self.target = (source + 1) % model_count.max(1);
+    }
+
+    pub fn close(&mut self) {
+        self.forget();
+        self.cancel_export();
+        self.source = None;
+    }
+
+    fn cancel_export(&mut self) {
+        if let Some(request) = &self.export {
+            request.cancel();
+        }
+        self.export = None;
+    }
+
+    /// Drops the matched vertices, for when either model changes
+    fn forget(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.request = None;
+        self.morph = None;
+    }
+
+    /// Starts matching the source's vertices to the target in the background,
+    /// both as placed in the scene
+    fn start(&mut self, ctx: &egui::Context, source: &SceneModel, target: &SceneModel) {
+        self.forget();
+        let placed = |model: &SceneModel| -> Vec<Triangle> {
+            let placement = model.transformation_ui.get_matrix();
+            return model.triangles.iter().map(|t| triangle::transform(t, &placement)).collect();
+        };
+        let (source, target) = (placed(source), placed(target));
+        self.request = Some(Request::spawn(ctx, move |progress| {
+            return Morph::new(&source, &target, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            });
+        }));
+    }
+
+    /// Asks where to save the stages, and starts writing them in the background
+    fn start_export(&mut self, ctx: &egui::Context, morph: Arc<Morph>) {
+        let path = rfd::FileDialog::new().add_filter("stl", &["stl", "STL"]).save_file();
+        let path = match path {
+            Some(path) => path.display().to_string(),
+            None => return
+        };
+        let stages = self.stages;
+        self.export = Some(Request::spawn(ctx, move |progress| {
+            return morph.export_stages(&path, stages, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            });
+        }));
+    }
+
+    /// Shows the dialog if it is open
+    pub fn show(&mut self, ctx: &egui::Context, scene: &[SceneModel]) -> MorphResponse {
+        let source = match self.source {
+            Some(source) if source < scene.len() && self.target < scene.len() => source,
+            Some(_) => {
+                self.close();
+                return MorphResponse::None;
+            },
+            None => return MorphResponse::None
+        };
+        if let Some(result) = self.request.as_mut().and_then(|request| request.take()) {
+            self.request = None;
+            self.morph = result.map(Arc::new);
+        }
+        if let Some(result) = self.export.as_mut().and_then(|request| request.take()) {
+            self.export = None;
+            return MorphResponse::Exported(result);
+        }
+        if self.morph.is_none() && self.request.is_none() && source != self.target {
+            self.start(ctx, &scene[source], &scene[self.target]);
+        }
+
+        let mut open = true;
+        let mut target = self.target;
+        let mut add = false;
+        let mut export = false;
+        let mut close = false;
+        egui::Window::new(tr("morph"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.label(tr_args("morph-from", &[("name", &scene[source].name)]));
+            ui.horizontal(|ui| {
+                ui.label(tr("morph-to"));
+                egui::ComboBox::from_id_source("morph_target")
+                    .selected_text(&scene[target].name)
+                    .show_ui(ui, |ui| {
+                    for (i, model) in scene.iter().enumerate().filter(|(i, _)| *i != source) {
+                        ui.selectable_value(&mut target, i, &model.name);
+                    }
+                });
+            });
+            if source == target {
+                ui.label(tr("morph-needs-two"));
+            }
+            if let Some(request) = &self.request {
+                ui.horizontal(|ui| {
+                    ui.label(tr("morph-matching"));
+                    ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+                });
+            }
+            ui.add_enabled_ui(self.morph.is_some(), |ui| {
+                ui.add(egui::Slider::new(&mut self.t, 0.0..=1.0).text(tr("morph-blend")));
+                add = ui.button(tr("morph-add")).clicked();
+                ui.horizontal(|ui| {
+                    ui.label(tr("morph-stages"));
+                    ui.add(egui::DragValue::new(&mut self.stages).clamp_range(2..=100));
+                    match &self.export {
+                        Some(request) => {
+                            ui.add(egui::ProgressBar::new(request.progress()).show_percentage().desired_width(120.));
+                            if ui.button(tr("morph-cancel-export")).clicked() {
+                                self.cancel_export();
+                            }
+                        },
+                        None => export = ui.button(tr("morph-export")).clicked()
+                    }
+                });
+            });
+            close = ui.button(tr("morph-close")).clicked();
+        });
+
+        if close || !open {
+            self.close();
+            return MorphResponse::None;
+        }
+        if target != self.target {
+            self.target = target;
+            self.forget();
+        }
+        let morph = match &self.morph {
+            Some(morph) => morph.clone(),
+            None => return MorphResponse::None
+        };
+        if add {
+            let name = tr_args("scene-morph-name", &[
+                ("from", &scene[source].name),
+                ("to", &scene[self.target].name),
+                ("percent", &format!("{:.0}", self.t * 100.))]);
+            return MorphResponse::Add(name, morph.at(self.t));
+        }
+        if export {
+            self.start_export(ctx, morph);
+        }
+        return MorphResponse::None;
+    }
+}
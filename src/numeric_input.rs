@@ -0,0 +1,188 @@
+use std::hash::Hash;
+
+use egui::{DragValue, Id, Key, Response, TextEdit, Widget};
+
+use crate::units::{self, Unit};
+
+/// What a numeric value measures, which decides the suffixes it accepts
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Quantity {
+    /// A plain number, like a scale factor
+    Number,
+    /// A length in the given unit. Typed values may use any length suffix.
+    Length(Unit),
+    /// A volume in the cube of the given unit. Typed values may use any length suffix,
+    /// optionally followed by "³".
+    Volume(Unit),
+    /// An angle in degrees. Typed values may use "°", "deg", or "rad".
+    Angle
+}
+
+impl Quantity {
+    fn suffix(&self) -> &'static str {
+        match self {
+            Quantity::Number => "",
+            Quantity::Length(unit) => unit.suffix(),
+            Quantity::Volume(unit) => match unit {
+                Unit::Millimeter => "mm³",
+                Unit::Centimeter => "cm³",
+                Unit::Inch => "in³"
+            },
+            Quantity::Angle => "°"
+        }
+    }
+
+    /// Evaluates typed text, converting any suffix into this quantity's unit
+    pub fn parse(&self, text: &str) -> Option<f32> {
+        let text = match self {
+            Quantity::Volume(_) => text.trim().trim_end_matches('³'),
+            _ => text
+        };
+        let (expression, suffix) = units::split_suffix(text);
+        let value = evaluate(&expression.replace(',', "."))? as f32;
+        return match self {
+            Quantity::Number => if suffix.is_empty() { Some(value) } else { None },
+            Quantity::Length(unit) => units::length_in_unit(value, suffix, *unit),
+            Quantity::Volume(unit) => units::length_in_unit(1., suffix, *unit).map(|scale| value * scale.powi(3)),
+            Quantity::Angle => match suffix {
+                "" | "°" | "deg" => Some(value),
+                "rad" => Some(value.to_degrees()),
+                _ => None
+            }
+        };
+    }
+}
+
+/// Evaluates an arithmetic expression with +, -, *, /, and parentheses
+pub fn evaluate(expression: &str) -> Option<f64> {
+    let tokens: Vec<char> = expression.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut position = 0;
+    let value = parse_sum(&tokens, &mut position)?;
+    if position != tokens.len() {
+        return None;
+    }
+    return Some(value);
+}
+
+fn parse_sum(tokens: &[char], position: &mut usize) -> Option<f64> {
+    let mut value = parse_product(tokens, position)?;
+    while let Some(operator) = tokens.get(*position) {
+        match operator {
+            '+' => { *position += 1; value += parse_product(tokens, position)?; },
+            '-' => { *position += 1; value -= parse_product(tokens, position)?; },
+            _ => break
+        }
+    }
+    return Some(value);
+}
+
+fn parse_product(tokens: &[char], position: &mut usize) -> Option<f64> {
+    let mut value = parse_factor(tokens, position)?;
+    while let Some(operator) = tokens.get(*position) {
+        match operator {
+            '*' => { *position += 1; value *= parse_factor(tokens, position)?; },
+            '/' => { *position += 1; value /= parse_factor(tokens, position)?; },
+            _ => break
+        }
+    }
+    return Some(value);
+}
+
+fn parse_factor(tokens: &[char], position: &mut usize) -> Option<f64> {
+    match tokens.get(*position)? {
+        '-' => {
+            *position += 1;
+            return Some(-parse_factor(tokens, position)?);
+        },
+        '+' => {
+            *position += 1;
+            return parse_factor(tokens, position);
+        },
+        '(' => {
+            *position += 1;
+            let value = parse_sum(tokens, position)?;
+            if tokens.get(*position) != Some(&')') {
+                return None;
+            }
+            *position += 1;
+            return Some(value);
+        },
+        _ => {
+            let start = *position;
+            while tokens.get(*position).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                *position += 1;
+            }
+            return tokens[start..*position].iter().collect::<String>().parse().ok();
+        }
+    }
+}
+
+/// A DragValue that also accepts typed expressions with unit suffixes,
+/// such as "12.5mm", "0.5in", or "3/8in".
+///
+/// Dragging behaves like a normal DragValue. Clicking opens a text field,
+/// which is evaluated when it loses focus. Escape or invalid text
+/// leaves the value unchanged.
+///
+/// The id source must be unique within the parent Ui, since it identifies
+/// which field is being typed in.
+pub struct NumericEdit<'a> {
+    id_source: Id,
+    value: &'a mut f32,
+    quantity: Quantity,
+    /// Change per pixel dragged, which also sets the decimals shown
+    speed: f64
+}
+
+impl<'a> NumericEdit<'a> {
+    pub fn new(id_source: impl Hash, value: &'a mut f32, quantity: Quantity) -> Self {
+        return Self {
+            id_source: Id::new(id_source),
+            value,
+            quantity,
+            speed: 1.
+        }
+    }
+
+    /// Sets the change per pixel dragged, for values much smaller or larger than 1
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        return self;
+    }
+}
+
+impl<'a> Widget for NumericEdit<'a> {
+    fn ui(self, ui: &mut egui::Ui) -> Response {
+        let id = ui.make_persistent_id(self.id_source);
+        if !ui.memory().has_focus(id) {
+            let response = ui.add(DragValue::new(self.value)
+                .speed(self.speed)
+                .suffix(self.quantity.suffix()));
+            // Take the focus from the DragValue's own text field
+            if response.clicked() {
+                ui.memory().request_focus(id);
+            }
+            return response;
+        }
+
+        let mut text = ui.memory().data.get_temp::<String>(id)
+            .unwrap_or_else(|| format!("{}{}", self.value, self.quantity.suffix()));
+        let mut response = ui.add(TextEdit::singleline(&mut text)
+            .id(id)
+            .desired_width(ui.spacing().interact_size.x * 1.5));
+        if response.lost_focus() {
+            ui.memory().data.remove::<String>(id);
+            if !ui.input().key_pressed(Key::Escape) {
+                if let Some(value) = self.quantity.parse(&text) {
+                    if value.is_finite() && value != *self.value {
+                        *self.value = value;
+                        response.mark_changed();
+                    }
+                }
+            }
+        } else {
+            ui.memory().data.insert_temp(id, text);
+        }
+        return response;
+    }
+}
@@ -0,0 +1,121 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::i18n::{tr, tr_args};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::offset::{self, OffsetMode};
+use crate::thread_request::Request;
+use crate::triangle::{self, Triangle};
+use crate::units::Unit;
+
+/// What the application should do after the offset dialog is shown
+pub enum OffsetResponse {
+    None,
+    /// Replace the model's triangles with these, which are already placed in the scene
+    Apply(Vec<Triangle>)
+}
+
+/// A dialog to thicken a surface into a shell, or grow or shrink a closed mesh
+pub struct OffsetUI {
+    /// Index of the model being offset, while the dialog is open
+    pub model: Option<usize>,
+    pub mode: OffsetMode,
+    /// Thickness of a shell, or how far an inflated surface moves
+    pub distance: f32,
+    /// Grid points along the longest side
+    pub resolution: usize,
+    request: Option<Request<Option<Vec<Triangle>>>>
+}
+
+impl OffsetUI {
+    pub fn new() -> Self {
+        return Self { model: None, mode: OffsetMode::Shell, distance: 2., resolution: 200, request: None };
+    }
+
+    pub fn open(&mut self, model: usize) {
+        self.close();
+        self.model = Some(model);
+    }
+
+    pub fn close(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.model = None;
+        self.request = None;
+    }
+
+    /// Shows the dialog if it is open, given the triangles of the model,
+    /// the transformation placing them in the scene, and their bounds once placed.
+    /// The offset is measured in the scene, so it is the thickness that gets printed.
+    pub fn show(&mut self, ctx: &egui::Context, triangles: &[Triangle], placement: &Mat4,
+        bounds: Option<(Vec3, Vec3)>, unit: Unit)
+    -> OffsetResponse {
+        if self.model.is_none() {
+            return OffsetResponse::None;
+        }
+        if let Some(result) = self.request.as_mut().and_then(|request| request.take()) {
+            self.close();
+            return match result {
+                Some(triangles) => OffsetResponse::Apply(triangles),
+                None => OffsetResponse::None
+            };
+        }
+
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new(tr("offset"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.add_enabled_ui(self.request.is_none(), |ui| {
+                ui.horizontal(|ui| {
+                    for mode in OffsetMode::ALL {
+                        ui.radio_value(&mut self.mode, mode, mode.label());
+                    }
+                });
+                ui.label(match self.mode {
+                    OffsetMode::Shell => tr("offset-shell-hint"),
+                    OffsetMode::Inflate => tr("offset-inflate-hint")
+                });
+                ui.horizontal(|ui| {
+                    ui.label(match self.mode {
+                        OffsetMode::Shell => tr("offset-thickness"),
+                        OffsetMode::Inflate => tr("offset-distance")
+                    });
+                    ui.add(NumericEdit::new("offset_distance", &mut self.distance, Quantity::Length(unit)));
+                });
+                ui.add(egui::Slider::new(&mut self.resolution, 50..=offset::MAX_RESOLUTION)
+                    .text(tr("offset-resolution")));
+                if let Some((min, max)) = bounds {
+                    let spacing = offset::grid_spacing(&min, &max, self.mode, self.distance, self.resolution);
+                    ui.label(tr_args("offset-spacing", &[("spacing", &format!("{:.3} {}", spacing, unit.suffix()))]));
+                }
+            });
+            if let Some(request) = &self.request {
+                ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+            }
+            ui.horizontal(|ui| {
+                apply = ui.add_enabled(self.request.is_none(), egui::Button::new(tr("offset-apply"))).clicked();
+                cancel = ui.button(tr("offset-cancel")).clicked();
+            });
+        });
+
+        if cancel || !open {
+            self.close();
+        } else if apply {
+            let placed: Vec<Triangle> = triangles.iter()
+                .map(|t| triangle::transform(t, placement))
+                .collect();
+            let (mode, distance, resolution) = (self.mode, self.distance, self.resolution);
+            self.request = Some(Request::spawn(ctx, move |progress| {
+                return offset::offset(&placed, mode, distance, resolution, |fraction| {
+                    progress.set(fraction);
+                    return !progress.cancelled();
+                });
+            }));
+        }
+        return OffsetResponse::None;
+    }
+}
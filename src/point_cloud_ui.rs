@@ -0,0 +1,115 @@
+use crate::i18n::{tr, tr_args};
+use crate::mesh_file::{self, PointCloud};
+use crate::mesh_widget::ViewState;
+use crate::thread_request::Request;
+
+/// What the application should do after the point cloud window is shown
+pub enum PointCloudResponse {
+    None,
+    /// Add this point cloud, read from a file, to the view
+    Loaded(String, PointCloud),
+    /// The file with this name couldn't be read
+    Failed(String, std::io::Error)
+}
+
+/// A window listing the point clouds in the view, and reading more from files in the background
+pub struct PointCloudUI {
+    pub open: bool,
+    /// Names of the point clouds in the view, in the same order
+    pub names: Vec<String>,
+    /// The name of the file being read, and the points read from it
+    request: Option<(String, Request<Result<PointCloud, std::io::Error>>)>
+}
+
+impl PointCloudUI {
+    pub fn new() -> Self {
+        return Self { open: false, names: Vec::new(), request: None };
+    }
+
+    /// Starts reading a point cloud file, opening the window to show its progress
+    pub fn load(&mut self, ctx: &egui::Context, path: String) {
+        self.cancel();
+        let name = std::path::Path::new(&path).file_stem()
+            .map_or(path.clone(), |stem| stem.to_string_lossy().to_string());
+        self.open = true;
+        self.request = Some((name, Request::spawn(ctx, move |progress| {
+            return mesh_file::read_point_cloud_with_progress(&path, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            });
+        })));
+    }
+
+    fn cancel(&mut self) {
+        if let Some((_, request)) = &self.request {
+            request.cancel();
+        }
+        self.request = None;
+    }
+
+    /// Shows the window if it is open, with controls for the point clouds in the view
+    pub fn show(&mut self, ctx: &egui::Context, mesh: Option<&mut ViewState>) -> PointCloudResponse {
+        if let Some(result) = self.request.as_mut().and_then(|(_, request)| request.take()) {
+            let (name, _) = self.request.take().unwrap();
+            return match result {
+                Ok(cloud) => PointCloudResponse::Loaded(name, cloud),
+                Err(err) => PointCloudResponse::Failed(name, err)
+            };
+        }
+        let mut clouds = mesh.map(|mesh| &mut mesh.point_clouds);
+        // Point clouds leave the view with it, as when another file is opened
+        self.names.truncate(clouds.as_ref().map_or(0, |clouds| clouds.len()));
+        if !self.open {
+            return PointCloudResponse::None;
+        }
+
+        let mut open = true;
+        let mut cancel = false;
+        egui::Window::new(tr("point-clouds"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            if let Some((name, request)) = &self.request {
+                ui.label(tr_args("point-cloud-reading", &[("name", name)]));
+                ui.horizontal(|ui| {
+                    ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+                    cancel = ui.button(tr("point-cloud-cancel")).clicked();
+                });
+            }
+            let clouds = match &mut clouds {
+                Some(clouds) if !clouds.is_empty() => clouds,
+                _ => {
+                    if self.request.is_none() {
+                        ui.label(tr("point-cloud-none"));
+                    }
+                    return;
+                }
+            };
+            let mut remove = None;
+            egui::Grid::new("point_clouds").show(ui, |ui| {
+                for (i, cloud) in clouds.iter_mut().enumerate() {
+                    ui.checkbox(&mut cloud.visible, &self.names[i]);
+                    ui.label(tr_args("point-cloud-count", &[("count", &cloud.cloud.points.len())]));
+                    ui.color_edit_button_rgb(&mut cloud.color).on_hover_text(tr("point-cloud-color-hint"));
+                    ui.add(egui::Slider::new(&mut cloud.point_size, 1. ..=10.).text(tr("point-cloud-size")));
+                    if ui.button(tr("point-cloud-remove")).clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(i) = remove {
+                clouds.remove(i);
+                self.names.remove(i);
+            }
+        });
+        if cancel {
+            self.cancel();
+        }
+        if !open {
+            self.cancel();
+            self.open = false;
+        }
+        return PointCloudResponse::None;
+    }
+}
@@ -0,0 +1,135 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::i18n::tr;
+use crate::json::{self, Json};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::settings::Settings;
+use crate::units::Unit;
+
+/// The limits of a printer, which the checks of a mesh are made against
+#[derive(Clone, PartialEq, Debug)]
+pub struct PrinterProfile {
+    pub name: String,
+    /// Width and depth of the bed, and the build height
+    pub build_volume: Vec3,
+    /// Unit of the sizes in the profile, and of the meshes printed with it
+    pub unit: Unit,
+    /// Walls thinner than this are reported as thin
+    pub min_wall: f32,
+    /// Largest angle from vertical a surface can lean before it needs support, in degrees
+    pub max_overhang: f32
+}
+
+impl PrinterProfile {
+    /// A profile suiting most desktop printers
+    pub fn generic() -> Self {
+        return Self {
+            name: tr("printer-generic"),
+            build_volume: Vec3::new(220., 220., 250.),
+            unit: Unit::Millimeter,
+            min_wall: 0.8,
+            max_overhang: 45.
+        };
+    }
+
+    /// A length in the profile's unit, converted to another unit
+    pub fn length_in(&self, length: f32, unit: Unit) -> f32 {
+        return length * self.unit.millimeters() / unit.millimeters();
+    }
+
+    pub fn to_json(&self) -> String {
+        let size = self.build_volume;
+        return format!(
+            r#"{{"name": {}, "build_volume": [{}, {}, {}], "unit": {}, "min_wall": {}, "max_overhang": {}}}"#,
+            json::quote(&self.name), size.x, size.y, size.z,
+            json::quote(self.unit.suffix()), self.min_wall, self.max_overhang);
+    }
+
+    pub fn from_json(value: &Json) -> Option<Self> {
+        let size = value.get("build_volume")?.as_f32s()?;
+        if size.len() != 3 {
+            return None;
+        }
+        return Some(Self {
+            name: value.get("name")?.as_str()?.to_string(),
+            build_volume: Vec3::new(size[0], size[1], size[2]),
+            unit: Unit::from_suffix(value.get("unit")?.as_str()?)?,
+            min_wall: value.get("min_wall")?.as_f64()? as f32,
+            max_overhang: value.get("max_overhang")?.as_f64()? as f32
+        });
+    }
+}
+
+/// A window to add, remove and edit printer profiles
+pub struct PrinterUI {
+    pub open: bool
+}
+
+impl PrinterUI {
+    pub fn new() -> Self {
+        return Self { open: false };
+    }
+
+    /// Shows the window if it is open. Returns true if the current printer changed,
+    /// so the thresholds taken from it have to be applied again.
+    pub fn show(&mut self, ctx: &egui::Context, settings: &mut Settings) -> bool {
+        if !self.open {
+            return false;
+        }
+        let before = settings.printer().clone();
+        let mut removed = None;
+        egui::Window::new(tr("printers"))
+            .open(&mut self.open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for (i, printer) in settings.printers.iter().enumerate() {
+                    ui.selectable_value(&mut settings.printer, i, &printer.name);
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button(tr("printers-add")).clicked() {
+                    let mut copy = settings.printer().clone();
+                    copy.name = format!("{} 2", copy.name);
+                    settings.printers.push(copy);
+                    settings.printer = settings.printers.len() - 1;
+                }
+                if ui.add_enabled(settings.printers.len() > 1, egui::Button::new(tr("printers-remove"))).clicked() {
+                    removed = Some(settings.printer);
+                }
+            });
+            ui.separator();
+            let printer = &mut settings.printers[settings.printer];
+            egui::Grid::new("printer").show(ui, |ui| {
+                ui.label(tr("printers-name"));
+                ui.text_edit_singleline(&mut printer.name);
+                ui.end_row();
+                ui.label(tr("printers-unit"));
+                ui.horizontal(|ui| {
+                    for unit in Unit::ALL {
+                        ui.radio_value(&mut printer.unit, unit, unit.label());
+                    }
+                });
+                ui.end_row();
+                let unit = printer.unit;
+                for (i, key) in ["build-volume-width", "build-volume-depth", "build-volume-height"].iter().enumerate() {
+                    ui.label(tr(key));
+                    ui.add(NumericEdit::new(key, &mut printer.build_volume[i], Quantity::Length(unit)));
+                    ui.end_row();
+                }
+                ui.label(tr("printers-min-wall"));
+                ui.add(NumericEdit::new("min_wall", &mut printer.min_wall, Quantity::Length(unit)));
+                ui.end_row();
+                ui.label(tr("printers-max-overhang"));
+                ui.add(NumericEdit::new("max_overhang", &mut printer.max_overhang, Quantity::Angle));
+                ui.end_row();
+            });
+        });
+        if let Some(index) = removed {
+            settings.printers.remove(index);
+            settings.printer = settings.printer.min(settings.printers.len() - 1);
+        }
+        return *settings.printer() != before;
+    }
+}
@@ -0,0 +1,260 @@
+/// Times the rest of the enclosing block under the given name.
+///
+/// With the `profiling` feature, this opens a `tracing` span, which is recorded
+/// for the timing panel and for saving as a trace. Otherwise it does nothing.
+#[cfg(feature = "profiling")]
+macro_rules! profile_span {
+    ( $name:expr ) => {
+        let _profile_span = tracing::info_span!($name).entered();
+    }
+}
+
+/// Times the rest of the enclosing block under the given name
+#[cfg(not(feature = "profiling"))]
+macro_rules! profile_span {
+    ( $name:expr ) => {}
+}
+
+#[cfg(feature = "profiling")]
+pub use recorder::*;
+
+#[cfg(feature = "profiling")]
+mod recorder {
+    use std::cell::RefCell;
+    use std::collections::{HashMap, VecDeque};
+    use std::fs::File;
+    use std::io::Write;
+    use std::sync::{Mutex, OnceLock};
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    use tracing::{span, Event, Metadata, Subscriber};
+
+    use crate::i18n::tr;
+
+    /// Most timings kept, dropping the oldest first
+    const MAX_TIMINGS: usize = 10000;
+
+    /// One finished span
+    #[derive(Clone)]
+    pub struct Timing {
+        pub name: &'static str,
+        /// Small number identifying the thread the span ran on
+        pub thread: usize,
+        /// How many spans were open around this one
+        pub depth: usize,
+        /// Time since recording started
+        pub start: Duration,
+        pub duration: Duration
+    }
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    static TIMINGS: Mutex<VecDeque<Timing>> = Mutex::new(VecDeque::new());
+    static NEXT_THREAD: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        /// The spans entered on this thread, with when they were entered
+        static STACK: RefCell<Vec<(u64, Instant)>> = RefCell::new(Vec::new());
+        static THREAD: usize = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A tracing subscriber which records how long each span is entered
+    struct TimingSubscriber {
+        next_id: AtomicU64,
+        /// Name and reference count of each open span
+        spans: Mutex<HashMap<u64, (&'static str, usize)>>
+    }
+
+    impl Subscriber for TimingSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            return true;
+        }
+
+        fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            self.spans.lock().unwrap().insert(id, (attributes.metadata().name(), 1));
+            return span::Id::from_u64(id);
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, span: &span::Id) {
+            STACK.with(|stack| stack.borrow_mut().push((span.into_u64(), Instant::now())));
+        }
+
+        fn exit(&self, span: &span::Id) {
+            let entered = STACK.with(|stack| {
+                let mut stack = stack.borrow_mut();
+                let index = stack.iter().rposition(|(id, _)| *id == span.into_u64())?;
+                let (_, entered) = stack.remove(index);
+                Some((entered, index))
+            });
+            let (entered, depth) = match entered {
+                Some(entered) => entered,
+                None => return
+            };
+            let name = match self.spans.lock().unwrap().get(&span.into_u64()) {
+                Some((name, _)) => *name,
+                None => return
+            };
+            let epoch = *EPOCH.get_or_init(Instant::now);
+            let timing = Timing {
+                name,
+                thread: THREAD.with(|thread| *thread),
+                depth,
+                start: entered.saturating_duration_since(epoch),
+                duration: entered.elapsed()
+            };
+            let mut timings = TIMINGS.lock().unwrap();
+            if timings.len() >= MAX_TIMINGS {
+                timings.pop_front();
+            }
+            timings.push_back(timing);
+        }
+
+        fn clone_span(&self, span: &span::Id) -> span::Id {
+            if let Some((_, count)) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+                *count += 1;
+            }
+            return span.clone();
+        }
+
+        fn try_close(&self, span: span::Id) -> bool {
+            let mut spans = self.spans.lock().unwrap();
+            if let Some((_, count)) = spans.get_mut(&span.into_u64()) {
+                *count -= 1;
+                if *count == 0 {
+                    spans.remove(&span.into_u64());
+                    return true;
+                }
+            }
+            return false;
+        }
+    }
+
+    /// Starts recording spans. Call once, before any spans are opened.
+    pub fn init() {
+        EPOCH.get_or_init(Instant::now);
+        let subscriber = TimingSubscriber {
+            // Span ids must not be zero
+            next_id: AtomicU64::new(1),
+            spans: Mutex::new(HashMap::new())
+        };
+        let _ = tracing::subscriber::set_global_default(subscriber);
+    }
+
+    /// A copy of the recorded timings, oldest first
+    pub fn timings() -> Vec<Timing> {
+        return TIMINGS.lock().unwrap().iter().cloned().collect();
+    }
+
+    pub fn clear() {
+        TIMINGS.lock().unwrap().clear();
+    }
+
+    /// Saves the timings in the Chrome trace format,
+    /// which can be opened in chrome://tracing or Perfetto
+    pub fn write_trace(path: &str) -> Result<(), std::io::Error> {
+        let mut output = File::create(path)?;
+        write!(output, "{{\"traceEvents\":[")?;
+        for (i, timing) in timings().iter().enumerate() {
+            if i > 0 {
+                write!(output, ",")?;
+            }
+            write!(output,
+                "\n{{\"name\":\"{}\",\"ph\":\"X\",\"pid\":0,\"tid\":{},\"ts\":{},\"dur\":{}}}",
+                timing.name, timing.thread, timing.start.as_micros(), timing.duration.as_micros())?;
+        }
+        writeln!(output, "\n]}}")?;
+        return Ok(());
+    }
+
+    /// A window showing recent spans as bars along a time line,
+    /// stacked by thread and nesting depth
+    pub struct TimingUI {
+        pub open: bool,
+        /// How many seconds of history to show
+        pub window: f32
+    }
+
+    impl TimingUI {
+        pub fn new() -> Self {
+            return Self { open: false, window: 5. };
+        }
+
+        /// Shows the window if it is open.
+        /// Returns true if the user asked to save a trace.
+        pub fn show(&mut self, ctx: &egui::Context) -> bool {
+            let mut save = false;
+            let mut open = self.open;
+            egui::Window::new(tr("timings")).open(&mut open).show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Slider::new(&mut self.window, 0.1..=60.).logarithmic(true).suffix(" s"));
+                    if ui.button(tr("timings-clear")).clicked() {
+                        clear();
+                    }
+                    save = ui.button(tr("timings-save")).clicked();
+                });
+                self.timeline(ui);
+            });
+            self.open = open;
+            if self.open {
+                ctx.request_repaint();
+            }
+            return save;
+        }
+
+        fn timeline(&self, ui: &mut egui::Ui) {
+            const ROW_HEIGHT: f32 = 16.;
+            let timings = timings();
+            let end = EPOCH.get().map(|epoch| epoch.elapsed()).unwrap_or_default().as_secs_f32();
+            let start = end - self.window;
+            let visible: Vec<&Timing> = timings.iter()
+                .filter(|t| (t.start + t.duration).as_secs_f32() >= start)
+                .collect();
+            // Each thread gets a band of rows, as deep as its deepest span
+            let mut rows = HashMap::<usize, usize>::new();
+            for timing in &visible {
+                let depth = rows.entry(timing.thread).or_insert(0);
+                *depth = (*depth).max(timing.depth + 1);
+            }
+            let mut threads: Vec<usize> = rows.keys().copied().collect();
+            threads.sort();
+            let mut offsets = HashMap::<usize, usize>::new();
+            let mut total_rows = 0;
+            for thread in threads {
+                offsets.insert(thread, total_rows);
+                total_rows += rows[&thread];
+            }
+
+            let size = egui::vec2(ui.available_width().max(300.), ROW_HEIGHT * total_rows.max(1) as f32);
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+            let x = |seconds: f32| rect.left() + (seconds - start) / self.window * rect.width();
+            let pointer = ui.ctx().pointer_hover_pos();
+            for timing in visible {
+                let row = offsets[&timing.thread] + timing.depth;
+                let top = rect.top() + row as f32 * ROW_HEIGHT;
+                let bar = egui::Rect::from_x_y_ranges(
+                    x(timing.start.as_secs_f32())..=x((timing.start + timing.duration).as_secs_f32()).max(x(timing.start.as_secs_f32()) + 1.),
+                    top..=top + ROW_HEIGHT - 1.);
+                // Color by name, so repeated spans are easy to follow
+                let hash = timing.name.bytes().fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+                let color = egui::Color32::from_rgb(100 + (hash % 120) as u8, 100 + (hash / 120 % 120) as u8, 180);
+                painter.rect_filled(bar, 2., color);
+                if bar.width() > 40. {
+                    painter.text(bar.left_center() + egui::vec2(2., 0.), egui::Align2::LEFT_CENTER,
+                        timing.name, egui::FontId::proportional(11.), egui::Color32::BLACK);
+                }
+                if pointer.map_or(false, |p| bar.contains(p)) {
+                    egui::show_tooltip_text(ui.ctx(), egui::Id::new("timing_tooltip"),
+                        format!("{}: {:.3} ms", timing.name, timing.duration.as_secs_f64() * 1000.));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::mesh_widget::{Camera, ViewState};
+
+/// Most files remembered in the recent files list
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// The parts of a ViewState that decide what part of a model is seen
+#[derive(Clone)]
+pub struct LastView {
+    pub camera: Camera,
+    pub right_handed: bool
+}
+
+impl LastView {
+    pub fn from_view(view: &ViewState) -> Self {
+        return Self {
+            camera: view.destination(),
+            right_handed: view.right_handed
+        };
+    }
+
+    pub fn apply(&self, view: &mut ViewState) {
+        view.stop_moving();
+        view.camera = self.camera;
+        view.right_handed = self.right_handed;
+    }
+
+    /// Writes the camera as space separated numbers
+    fn encode(&self) -> String {
+        let mut values: Vec<String> = self.camera.translation.iter()
+            .chain(std::iter::once(&self.camera.scale))
+            .chain(self.camera.rotation.iter())
+            .map(|v| v.to_string())
+            .collect();
+        values.push(self.right_handed.to_string());
+        return values.join(" ");
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        if tokens.len() != 21 {
+            return None;
+        }
+        let mut numbers = Vec::<f32>::with_capacity(20);
+        for token in &tokens[..20] {
+            numbers.push(token.parse::<f32>().ok()?);
+        }
+        return Some(Self {
+            camera: Camera {
+                translation: Vec3::new(numbers[0], numbers[1], numbers[2]),
+                scale: numbers[3],
+                rotation: Mat4::from_column_slice(&numbers[4..20])
+            },
+            right_handed: tokens[20].parse::<bool>().ok()?
+        });
+    }
+}
+
+/// A recently opened file, and how it was last viewed
+pub struct RecentFile {
+    pub path: String,
+    pub camera: Option<LastView>
+}
+
+impl RecentFile {
+    /// The file's name without its directory
+    pub fn name(&self) -> String {
+        return std::path::Path::new(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.clone());
+    }
+}
+
+/// The most recently opened files, newest first, which persist between sessions
+pub struct RecentFiles {
+    pub files: Vec<RecentFile>
+}
+
+impl RecentFiles {
+    pub fn new() -> Self {
+        return Self { files: Vec::new() };
+    }
+
+    /// Loads the list from storage. Each line holds a camera, a tab, and a path.
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut recent = Self::new();
+        let text = match storage.and_then(|storage| storage.get_string("recent_files")) {
+            Some(text) => text,
+            None => return recent
+        };
+        for line in text.lines().take(MAX_RECENT_FILES) {
+            if let Some((camera, path)) = line.split_once('\t') {
+                recent.files.push(RecentFile {
+                    path: path.to_string(),
+                    camera: LastView::decode(camera)
+                });
+            }
+        }
+        return recent;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        let lines: Vec<String> = self.files.iter()
+            .map(|file| format!("{}\t{}",
+                file.camera.as_ref().map(|c| c.encode()).unwrap_or_default(),
+                file.path))
+            .collect();
+        storage.set_string("recent_files", lines.join("\n"));
+    }
+
+    pub fn get(&self, path: &str) -> Option<&RecentFile> {
+        return self.files.iter().find(|file| file.path == path);
+    }
+
+    /// Moves a file to the top of the list, keeping its camera if it was already there
+    pub fn add(&mut self, path: &str) {
+        let file = match self.files.iter().position(|file| file.path == path) {
+            Some(index) => self.files.remove(index),
+            None => RecentFile { path: path.to_string(), camera: None }
+        };
+        self.files.insert(0, file);
+        self.files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Remembers how a file in the list is being viewed
+    pub fn set_camera(&mut self, path: &str, camera: LastView) {
+        if let Some(file) = self.files.iter_mut().find(|file| file.path == path) {
+            file.camera = Some(camera);
+        }
+    }
+}
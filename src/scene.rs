@@ -0,0 +1,451 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::build_volume::BuildVolume;
+use crate::bvh::Bvh;
+use crate::curvature::CornerCurvature;
+use crate::decimate;
+use crate::i18n::tr;
+use crate::mass_properties::MassProperties;
+use crate::oriented_box::OrientedBox;
+use crate::thickness::WallThickness;
+use crate::mesh_cache;
+use crate::mesh_widget::{ViewState, REDUCED_TRIANGLES};
+use crate::thread_request::{Priority, Request};
+use crate::transformation_ui::TransformationUI;
+use crate::triangle::{self, Triangle};
+use crate::triangle_mesh::{self, TriangleMesh};
+
+/// The next version given to a set of triangles, so no two sets ever share one
+static NEXT_VERSION: AtomicUsize = AtomicUsize::new(0);
+
+fn next_version() -> usize {
+    return NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Measurements of triangles as placed in the scene
+#[derive(Clone, Copy, Debug)]
+pub struct Measures {
+    pub area: f32,
+    /// The mass properties, if the triangles enclose a volume
+    pub mass: Option<MassProperties>
+}
+
+impl Measures {
+    pub fn new(triangles: &[Triangle], transformation: &Mat4) -> Self {
+        // Summed in double precision, like the mass properties
+        let area: f64 = triangles.iter()
+            .map(|t| {
+                let [a, b, c] = triangle::transform(t, transformation);
+                return glm::cross(&(b - a), &(c - a)).norm() as f64 / 2.;
+            })
+            .sum();
+        return Self { area: area as f32, mass: MassProperties::new(triangles, transformation) };
+    }
+}
+
+/// How the triangles connect once their vertices are welded, which placing them doesn't change
+#[derive(Clone, Copy, Debug)]
+pub struct Topology {
+    pub vertices: usize,
+    /// Pieces that share no vertices with each other
+    pub bodies: usize,
+    /// Edges of only one triangle
+    pub boundary_edges: usize,
+    /// Edges of more than two triangles
+    pub non_manifold_edges: usize,
+    /// Edges whose triangles are wound against each other
+    pub misoriented_edges: usize
+}
+
+impl Topology {
+    pub fn new(mesh: &TriangleMesh) -> Self {
+        let report = mesh.edge_report();
+        return Self {
+            vertices: mesh.vertices.len(),
+            bodies: mesh.bodies().len(),
+            boundary_edges: report.boundary.len(),
+            non_manifold_edges: report.non_manifold.len(),
+            misoriented_edges: report.misoriented.len()
+        };
+    }
+
+    pub fn is_closed(&self) -> bool {
+        return self.boundary_edges == 0 && self.non_manifold_edges == 0 && self.misoriented_edges == 0;
+    }
+}
+
+/// The triangles of a loaded model, and what is needed to pick them.
+///
+/// Models in the scene line up with the models of the ViewState.
+pub struct SceneModel {
+    pub name: String,
+    /// The file the model was loaded from
+    pub path: String,
+    /// Shared with the view's model, and with the work done on them in the background
+    pub triangles: Arc<Vec<Triangle>>,
+    /// Changes whenever the triangles are replaced, so what was found from them can tell it is out of date
+    pub version: usize,
+    /// The triangles are a simplified preview of the file
+    pub decimated: bool,
+    /// Key of the data derived from these triangles in the disk cache.
+    /// It is dropped when the triangles change.
+    pub cache_key: Option<String>,
+    /// The transformations placing the model in the scene
+    pub transformation_ui: TransformationUI,
+    /// The last bounds previewed, and the transformation they were found with
+    bounds_preview: Option<(Mat4, Option<(Vec3, Vec3)>)>,
+    /// The triangles last found outside a build volume, and what they were found with
+    outside_preview: Option<(Mat4, BuildVolume, Vec<usize>)>,
+    /// The last measurements found, and the transformation they were found with
+    measures: Option<(Mat4, Measures)>,
+    /// How the triangles connect, once found
+    topology: Option<Topology>,
+    /// The last oriented box found, and the transformation it was found with
+    oriented_box: Option<(Mat4, OrientedBox)>,
+    /// The curvature at the triangles' corners, once found
+    curvature: Option<Arc<CornerCurvature>>,
+    /// The last wall thickness found, and the transformation it was found with
+    thickness: Option<(Mat4, Arc<WallThickness>)>,
+    /// Hierarchy for picking on the CPU
+    pub bvh: Option<Bvh>,
+    /// The hierarchy being built in the background. Until it is done, picking uses the GPU.
+    bvh_request: Option<Request<Bvh>>,
+    /// The averaged corner normals being found in the background, for smooth shading
+    normals_request: Option<Request<Vec<[f32; 3]>>>,
+    /// The reduced copy drawn while the view moves, being made in the background
+    reduced_request: Option<Request<Vec<Triangle>>>
+}
+
+impl SceneModel {
+    /// Creates a model named after its file. Its picking hierarchy isn't built until `start_bvh`.
+    pub fn new(path: String, triangles: Vec<Triangle>, decimated: bool) -> Self {
+        let name = std::path::Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        return Self {
+            name,
+            path,
+            triangles: Arc::new(triangles),
+            version: next_version(),
+            decimated,
+            cache_key: None,
+            transformation_ui: TransformationUI::new(),
+            bounds_preview: None,
+            outside_preview: None,
+            measures: None,
+            topology: None,
+            oriented_box: None,
+            curvature: None,
+            thickness: None,
+            bvh: None,
+            bvh_request: None,
+            normals_request: None,
+            reduced_request: None
+        };
+    }
+
+    /// Replaces the triangles, such as after a repair.
+    /// The picking hierarchy is rebuilt if there was one.
+    pub fn set_triangles(&mut self, ctx: &egui::Context, triangles: impl Into<Arc<Vec<Triangle>>>) {
+        self.triangles = triangles.into();
+        self.version = next_version();
+        self.cache_key = None;
+        self.bounds_preview = None;
+        self.outside_preview = None;
+        self.measures = None;
+        self.topology = None;
+        self.oriented_box = None;
+        self.curvature = None;
+        self.thickness = None;
+        if let Some(request) = self.normals_request.take() {
+            request.cancel();
+        }
+        if let Some(request) = self.reduced_request.take() {
+            request.cancel();
+        }
+        if self.bvh.is_some() || self.building_bvh() {
+            self.start_bvh(ctx);
+        } else {
+            self.bvh = None;
+        }
+    }
+
+    /// Moves the triangles themselves by a transformation
+    pub fn bake(&mut self, ctx: &egui::Context, transformation: &Mat4) {
+        let baked: Vec<Triangle> = self.triangles.iter()
+            .map(|t| triangle::transform(t, transformation))
+            .collect();
+        self.set_triangles(ctx, baked);
+    }
+
+    /// The box around the triangles once they are transformed.
+    /// It is only found again when the transformation changes.
+    pub fn transformed_bounds(&mut self, transformation: &Mat4) -> Option<(Vec3, Vec3)> {
+        if let Some((previewed, bounds)) = &self.bounds_preview {
+            if previewed == transformation {
+                return *bounds;
+            }
+        }
+        let bounds = triangle::transformed_bounds(&self.triangles, transformation);
+        self.bounds_preview = Some((*transformation, bounds));
+        return bounds;
+    }
+
+    /// The last measurements found since the triangles changed, and the transformation they were found with,
+    /// which may not be the current one. They are found in the background and kept with `set_measures`.
+    pub fn measures(&self) -> Option<(Mat4, Measures)> {
+        return self.measures;
+    }
+
+    pub fn set_measures(&mut self, transformation: &Mat4, measures: Measures) {
+        self.measures = Some((*transformation, measures));
+    }
+
+    /// How the triangles connect, if it was found since they last changed.
+    /// Like the curvature, it is found in the background and kept with `set_topology`.
+    pub fn topology(&self) -> Option<Topology> {
+        return self.topology;
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = Some(topology);
+    }
+
+    /// The oriented box last found around the triangles, if it was found with this transformation.
+    /// It is too slow to find here, so it is found in the background and kept with `set_oriented_box`.
+    pub fn oriented_box(&self, transformation: &Mat4) -> Option<OrientedBox> {
+        return match &self.oriented_box {
+            Some((found_with, oriented_box)) if found_with == transformation => Some(*oriented_box),
+            _ => None
+        };
+    }
+
+    pub fn set_oriented_box(&mut self, transformation: &Mat4, oriented_box: OrientedBox) {
+        self.oriented_box = Some((*transformation, oriented_box));
+    }
+
+    /// The curvature at the triangles' corners, if it was found since they last changed.
+    /// Like the oriented box, it is found in the background and kept with `set_curvature`.
+    pub fn curvature(&self) -> Option<Arc<CornerCurvature>> {
+        return self.curvature.clone();
+    }
+
+    pub fn set_curvature(&mut self, curvature: Arc<CornerCurvature>) {
+        self.curvature = Some(curvature);
+    }
+
+    /// The wall thickness behind each triangle, if it was found with this transformation.
+    /// Scaling changes it, so it is kept with the transformation like the oriented box.
+    pub fn thickness(&self, transformation: &Mat4) -> Option<Arc<WallThickness>> {
+        return match &self.thickness {
+            Some((found_with, thickness)) if found_with == transformation => Some(thickness.clone()),
+            _ => None
+        };
+    }
+
+    pub fn set_thickness(&mut self, transformation: &Mat4, thickness: Arc<WallThickness>) {
+        self.thickness = Some((*transformation, thickness));
+    }
+
+    /// Indices of the triangles outside a build volume once transformed.
+    /// Like the bounds, they are only found again when something changes.
+    pub fn outside(&mut self, transformation: &Mat4, volume: &BuildVolume) -> &[usize] {
+        let fresh = matches!(&self.outside_preview,
+            Some((previewed, previous, _)) if previewed == transformation && previous == volume);
+        if !fresh {
+            let outside = match self.transformed_bounds(transformation) {
+                Some((min, max)) if !volume.contains(&min, &max) => volume.outside(&self.triangles, transformation),
+                _ => Vec::new()
+            };
+            self.outside_preview = Some((*transformation, *volume, outside));
+        }
+        return &self.outside_preview.as_ref().unwrap().2;
+    }
+
+    /// Starts building the picking hierarchy in the background
+    pub fn start_bvh(&mut self, ctx: &egui::Context) {
+        self.bvh = None;
+        if let Some(request) = &self.bvh_request {
+            request.cancel();
+        }
+        let triangles = self.triangles.clone();
+        let cache_key = self.cache_key.clone();
+        self.bvh_request = Some(Request::spawn_with(ctx, Priority::Background, move |_| {
+            let key = match cache_key {
+                Some(key) => key,
+                None => return Bvh::new(&triangles)
+            };
+            if let Some(bvh) = mesh_cache::get_bvh(&key, triangles.len()) {
+                return bvh;
+            }
+            let bvh = Bvh::new(&triangles);
+            let _ = mesh_cache::put_bvh(&key, &bvh);
+            return bvh;
+        }));
+    }
+
+    pub fn building_bvh(&self) -> bool {
+        return self.bvh_request.is_some();
+    }
+
+    /// Switches to CPU picking once the hierarchy is built
+    pub fn finish_bvh(&mut self) {
+        let request = match &mut self.bvh_request {
+            Some(request) => request,
+            None => return
+        };
+        if let Some(bvh) = request.take() {
+            self.bvh = Some(bvh);
+            self.bvh_request = None;
+        }
+    }
+
+    /// Finds the averaged normals at the triangles' corners in the background,
+    /// returning them once, when they are found
+    pub fn smooth_normals(&mut self, ctx: &egui::Context) -> Option<Vec<[f32; 3]>> {
+        let request = match &mut self.normals_request {
+            Some(request) => request,
+            None => {
+                let triangles = self.triangles.clone();
+                self.normals_request = Some(Request::spawn_with(ctx, Priority::Background, move |_| {
+                    return triangle_mesh::smooth_normals(&triangles);
+                }));
+                return None;
+            }
+        };
+        let normals = request.take()?;
+        self.normals_request = None;
+        return Some(normals);
+    }
+
+    /// Makes a copy of the triangles with about `REDUCED_TRIANGLES` of them in the background,
+    /// returning it once, when it is made
+    pub fn reduced(&mut self, ctx: &egui::Context) -> Option<Vec<Triangle>> {
+        let request = match &mut self.reduced_request {
+            Some(request) => request,
+            None => {
+                let triangles = self.triangles.clone();
+                self.reduced_request = Some(Request::spawn_with(ctx, Priority::Background, move |_| {
+                    return decimate::decimate(&triangles, REDUCED_TRIANGLES, |_| {});
+                }));
+                return None;
+            }
+        };
+        let reduced = request.take()?;
+        self.reduced_request = None;
+        return Some(reduced);
+    }
+
+    /// The triangles and hierarchy for picking on the CPU, if the hierarchy is ready
+    pub fn picking(&self) -> Option<(&[Triangle], &Bvh)> {
+        return Some((&self.triangles, self.bvh.as_ref()?));
+    }
+}
+
+/// The box around every visible model, as placed in the view
+pub fn visible_bounds(scene: &mut [SceneModel], view: &ViewState) -> Option<(Vec3, Vec3)> {
+    let mut bounds: Option<(Vec3, Vec3)> = None;
+    for (model, view_model) in scene.iter_mut().zip(&view.models) {
+        if !view_model.visible {
+            continue;
+        }
+        if let Some((min, max)) = model.transformed_bounds(&view_model.local_transform) {
+            bounds = Some(match bounds {
+                Some((low, high)) => (glm::min2(&low, &min), glm::max2(&high, &max)),
+                None => (min, max)
+            });
+        }
+    }
+    return bounds;
+}
+
+/// What the application should do after the scene list is shown
+pub enum SceneResponse {
+    None,
+    /// Load another file into the scene
+    Add,
+    /// Remove the model at this index
+    Remove(usize),
+    /// A different model was selected
+    Selected
+}
+
+/// A panel listing the models in the scene, with controls to
+/// show, hide, rename, add and remove them
+pub struct SceneUI {
+    /// Index of the model that other panels work on
+    pub selected: usize,
+    /// Index of the model whose name is being edited
+    renaming: Option<usize>
+}
+
+impl SceneUI {
+    pub fn new() -> Self {
+        return Self { selected: 0, renaming: None };
+    }
+
+    /// Keeps the selection valid after the model at an index is removed
+    pub fn removed(&mut self, index: usize) {
+        if self.selected > index || (self.selected == index && self.selected > 0) {
+            self.selected -= 1;
+        }
+        self.renaming = None;
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, scene: &mut [SceneModel], view: &mut ViewState) -> SceneResponse {
+        let mut response = SceneResponse::None;
+        for (i, model) in scene.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(view_model) = view.models.get_mut(i) {
+                        ui.checkbox(&mut view_model.visible, "")
+                            .on_hover_text(tr("scene-visible"));
+                        ui.color_edit_button_rgb(&mut view_model.color)
+                            .on_hover_text(tr("scene-color"));
+                    }
+                    if self.renaming == Some(i) {
+                        let edit = ui.text_edit_singleline(&mut model.name);
+                        if edit.lost_focus() {
+                            self.renaming = None;
+                        } else {
+                            edit.request_focus();
+                        }
+                    } else {
+                        let name = if model.decimated {
+                            format!("{} ({})", model.name, tr("scene-decimated"))
+                        } else {
+                            model.name.clone()
+                        };
+                        let label = ui.selectable_label(self.selected == i, name)
+                            .on_hover_text(&model.path);
+                        if label.clicked() && self.selected != i {
+                            self.selected = i;
+                            response = SceneResponse::Selected;
+                        }
+                        if label.double_clicked() {
+                            self.renaming = Some(i);
+                        }
+                    }
+                    if model.bvh.is_none() && !model.building_bvh()
+                        && ui.small_button(tr("scene-build-bvh")).on_hover_text(tr("scene-build-bvh-hint")).clicked() {
+                        model.start_bvh(ui.ctx());
+                    }
+                    if ui.small_button(tr("scene-rename")).clicked() {
+                        self.renaming = Some(i);
+                    }
+                    if ui.small_button(tr("scene-remove")).clicked() {
+                        response = SceneResponse::Remove(i);
+                    }
+                });
+            });
+        }
+        if ui.button(tr("scene-add")).clicked() {
+            response = SceneResponse::Add;
+        }
+        return response;
+    }
+}
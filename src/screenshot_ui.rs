@@ -0,0 +1,104 @@
+use std::f32::consts::PI;
+
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::i18n::{tr, tr_args};
+
+/// Largest width or height of a screenshot, which most GPUs can draw offscreen
+pub const MAX_SIZE: usize = 8192;
+
+/// Common sizes for a screenshot, in pixels
+const PRESETS: [(usize, usize); 3] = [(1280, 720), (1920, 1080), (3840, 2160)];
+
+/// What the application should do after the screenshot dialog is shown
+pub enum ScreenshotResponse {
+    None,
+    /// Save the view as one image
+    Save,
+    /// Save numbered images of the view turning all the way around
+    SaveTurntable
+}
+
+/// A dialog to save the view as a PNG image, drawn at any size rather than the size of the window,
+/// or as a series of images turning around the models, to make an animation from
+pub struct ScreenshotUI {
+    pub open: bool,
+    pub width: usize,
+    pub height: usize,
+    /// Leave the background out, so the image can be placed over something else
+    pub transparent: bool,
+    /// Number of images in a full turn
+    pub frames: usize,
+    /// Index of the scene axis turned around
+    pub axis: usize
+}
+
+impl ScreenshotUI {
+    pub fn new() -> Self {
+        return Self { open: false, width: 1920, height: 1080, transparent: false, frames: 36, axis: 2 };
+    }
+
+    /// The rotation of the view for one of the turntable's images, turned from the rotation it starts at
+    pub fn turntable_rotation(&self, start: &Mat4, frame: usize) -> Mat4 {
+        let mut axis = Vec3::zeros();
+        axis[self.axis.min(2)] = 1.;
+        let angle = 2. * PI * frame as f32 / self.frames.max(1) as f32;
+        return glm::rotate(start, angle, &axis);
+    }
+
+    /// Shows the dialog if it is open, given the size of the view in pixels.
+    /// The images are saved at the dialog's width and height.
+    pub fn show(&mut self, ctx: &egui::Context, view_size: egui::Vec2) -> ScreenshotResponse {
+        if !self.open {
+            return ScreenshotResponse::None;
+        }
+        let mut response = ScreenshotResponse::None;
+        egui::Window::new(tr("screenshot"))
+            .open(&mut self.open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            egui::Grid::new("screenshot").show(ui, |ui| {
+                ui.label(tr("screenshot-width"));
+                ui.add(egui::DragValue::new(&mut self.width).clamp_range(1..=MAX_SIZE).suffix(" px"));
+                ui.end_row();
+                ui.label(tr("screenshot-height"));
+                ui.add(egui::DragValue::new(&mut self.height).clamp_range(1..=MAX_SIZE).suffix(" px"));
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                if ui.button(tr("screenshot-view-size")).clicked() {
+                    self.width = (view_size.x as usize).clamp(1, MAX_SIZE);
+                    self.height = (view_size.y as usize).clamp(1, MAX_SIZE);
+                }
+                for (width, height) in PRESETS {
+                    if ui.button(tr_args("screenshot-preset", &[("width", &width), ("height", &height)])).clicked() {
+                        self.width = width;
+                        self.height = height;
+                    }
+                }
+            });
+            ui.checkbox(&mut self.transparent, tr("screenshot-transparent"))
+                .on_hover_text(tr("screenshot-transparent-hint"));
+            if ui.button(tr("screenshot-save")).clicked() {
+                response = ScreenshotResponse::Save;
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(tr("screenshot-turntable"));
+                ui.add(egui::DragValue::new(&mut self.frames).clamp_range(2..=720))
+                    .on_hover_text(tr("screenshot-frames-hint"));
+                for (i, name) in ["X", "Y", "Z"].iter().enumerate() {
+                    ui.radio_value(&mut self.axis, i, *name);
+                }
+            });
+            if ui.button(tr("screenshot-save-turntable")).on_hover_text(tr("screenshot-save-turntable-hint")).clicked() {
+                response = ScreenshotResponse::SaveTurntable;
+            }
+        });
+        if !matches!(response, ScreenshotResponse::None) {
+            self.open = false;
+        }
+        return response;
+    }
+}
@@ -0,0 +1,107 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::cut_ui::{self, AXES};
+use crate::i18n::{tr, tr_args};
+use crate::mesh_widget::{ViewState, MAX_CLIP_PLANES};
+use crate::plane_cut::Plane;
+
+/// A plane across one of the axes, hiding everything on one side of it
+#[derive(Clone, Copy)]
+pub struct SectionPlane {
+    pub enabled: bool,
+    pub axis: usize,
+    /// Hide what is below the plane along the axis, rather than above it
+    pub flipped: bool,
+    /// Where the plane crosses the axis, in scene coordinates
+    pub position: f32
+}
+
+impl SectionPlane {
+    /// The plane, with its normal toward the side that is hidden
+    pub fn plane(&self) -> Plane {
+        let sign = if self.flipped { -1. } else { 1. };
+        let mut normal = Vec3::zeros();
+        normal[self.axis] = sign;
+        return Plane { normal, offset: sign * self.position };
+    }
+}
+
+/// A window to clip the view with planes, to see inside closed models without changing them
+pub struct SectionUI {
+    pub open: bool,
+    pub planes: [SectionPlane; MAX_CLIP_PLANES]
+}
+
+impl SectionUI {
+    pub fn new() -> Self {
+        let mut planes = [SectionPlane { enabled: false, axis: 0, flipped: false, position: 0. }; MAX_CLIP_PLANES];
+        for (i, plane) in planes.iter_mut().enumerate() {
+            plane.axis = i % 3;
+        }
+        return Self { open: false, planes };
+    }
+
+    /// The planes clipping the view. They keep clipping after the window is closed.
+    pub fn clip_planes(&self) -> Vec<Plane> {
+        return self.planes.iter()
+            .filter(|plane| plane.enabled)
+            .map(|plane| plane.plane())
+            .collect();
+    }
+
+    /// Outlines of the planes where they cross the box around the models, while the window is open
+    pub fn outlines(&self, min: &Vec3, max: &Vec3) -> Vec<Vec<Vec3>> {
+        if !self.open {
+            return Vec::new();
+        }
+        return self.planes.iter()
+            .filter(|plane| plane.enabled)
+            .map(|plane| cut_ui::outline(plane.axis, plane.position, min, max))
+            .collect();
+    }
+
+    /// Shows the window if it is open, given the box around the models
+    pub fn show(&mut self, ctx: &egui::Context, view: &mut ViewState, bounds: Option<(Vec3, Vec3)>) {
+        if !self.open {
+            return;
+        }
+        egui::Window::new(tr("section"))
+            .open(&mut self.open)
+            .show(ctx, |ui| {
+            for (i, plane) in self.planes.iter_mut().enumerate() {
+                ui.push_id(i, |ui| {
+                    let enabled = ui.checkbox(&mut plane.enabled, tr_args("section-plane", &[("number", &(i + 1))]));
+                    // A plane outside the models wouldn't hide anything, so it starts across their middle
+                    if let (true, Some((min, max))) = (enabled.changed() && plane.enabled, bounds) {
+                        if !(min[plane.axis]..=max[plane.axis]).contains(&plane.position) {
+                            plane.position = (min[plane.axis] + max[plane.axis]) / 2.;
+                        }
+                    }
+                    ui.add_enabled_ui(plane.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            for (axis, name) in AXES.iter().enumerate() {
+                                if ui.radio_value(&mut plane.axis, axis, *name).clicked() {
+                                    if let Some((min, max)) = bounds {
+                                        plane.position = (min[axis] + max[axis]) / 2.;
+                                    }
+                                }
+                            }
+                            ui.checkbox(&mut plane.flipped, tr("section-flip"))
+                                .on_hover_text(tr("section-flip-hint"));
+                        });
+                        if let Some((min, max)) = bounds {
+                            ui.add(egui::Slider::new(&mut plane.position, min[plane.axis]..=max[plane.axis])
+                                .text(tr_args("cut-position", &[("axis", &AXES[plane.axis])])));
+                        }
+                    });
+                });
+                ui.separator();
+            }
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut view.caps, tr("section-caps")).on_hover_text(tr("section-caps-hint"));
+                ui.color_edit_button_rgb(&mut view.cap_color);
+            });
+        });
+    }
+}
@@ -0,0 +1,384 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::i18n::{tr, tr_args};
+use crate::mesh_widget::{Hit, ViewState};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::triangle::{self, Triangle};
+use crate::triangle_mesh::{self, TriangleMesh};
+use crate::units::Unit;
+
+/// Screen distance the pointer moves before another lasso point is added
+const LASSO_SPACING: f32 = 4.;
+/// Segments in the circle drawn around the brush
+const BRUSH_SEGMENTS: usize = 32;
+
+/// How faces are picked out in the view
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SelectMode {
+    Click,
+    Box,
+    Lasso,
+    Brush
+}
+
+impl SelectMode {
+    pub const ALL: [SelectMode; 4] = [SelectMode::Click, SelectMode::Box, SelectMode::Lasso, SelectMode::Brush];
+
+    pub fn label(&self) -> String {
+        match self {
+            SelectMode::Click => tr("select-click"),
+            SelectMode::Box => tr("select-box"),
+            SelectMode::Lasso => tr("select-lasso"),
+            SelectMode::Brush => tr("select-brush")
+        }
+    }
+}
+
+/// What the application should do after the selection dialog is shown
+pub enum SelectResponse {
+    None,
+    /// The selection changed, so the model's corners should be colored again
+    Colors(usize, Vec<[f32; 3]>),
+    /// Replace the model's triangles with these, after deleting this many
+    Delete(Vec<Triangle>, usize),
+    /// Save these selected triangles of the model to a file of their own
+    Export(usize, Vec<Triangle>)
+}
+
+/// A dialog to select faces of a model in the view, by clicking them or by dragging
+/// a box or lasso around them, and delete them, like cutting away scan artifacts
+pub struct SelectUI {
+    /// Index of the model faces are selected on, while the dialog is open
+    pub model: Option<usize>,
+    pub mode: SelectMode,
+    /// Whether a box or lasso only takes faces turned toward the viewer
+    pub facing_only: bool,
+    /// How far the brush reaches from the face under the pointer, in scene coordinates
+    pub radius: f32,
+    /// Whether each of the model's triangles is selected
+    selected: Vec<bool>,
+    /// Points of the box or lasso being dragged, in screen coordinates
+    path: Vec<egui::Pos2>,
+    /// The model's triangles welded, with the triangles around each vertex,
+    /// found the first time the brush is used on it
+    connectivity: Option<(TriangleMesh, Vec<Vec<usize>>)>,
+    /// The selection changed since the model was last colored
+    changed: bool
+}
+
+impl SelectUI {
+    pub fn new() -> Self {
+        return Self {
+            model: None,
+            mode: SelectMode::Click,
+            facing_only: true,
+            radius: 5.,
+            selected: Vec::new(),
+            path: Vec::new(),
+            connectivity: None,
+            changed: false
+        };
+    }
+
+    pub fn open(&mut self, model: usize) {
+        self.close();
+        self.model = Some(model);
+    }
+
+    /// Closes the dialog, returning the model whose highlight should be cleared
+    pub fn close(&mut self) -> Option<usize> {
+        self.selected.clear();
+        self.path.clear();
+        self.connectivity = None;
+        return self.model.take();
+    }
+
+    /// Whether dragging with the primary button selects instead of turning the view
+    pub fn drags(&self) -> bool {
+        return self.model.is_some() && self.mode != SelectMode::Click;
+    }
+
+    pub fn count(&self) -> usize {
+        return self.selected.iter().filter(|s| **s).count();
+    }
+
+    /// Keeps the selection the same length as the model, starting over if the model changed
+    fn fit(&mut self, triangle_count: usize) {
+        if self.selected.len() != triangle_count {
+            self.selected = vec![false; triangle_count];
+            self.connectivity = None;
+            self.changed = true;
+        }
+    }
+
+    /// Selects the face clicked in the view. Shift adds or removes it, and clicking
+    /// without shift selects only it, or nothing when the click missed.
+    pub fn click(&mut self, hit: Option<Hit>, modifiers: egui::Modifiers) {
+        let index = match (self.model, self.mode) {
+            (Some(index), SelectMode::Click) => index,
+            _ => return
+        };
+        let hit = hit.filter(|hit| hit.model == index && hit.triangle < self.selected.len());
+        if !modifiers.shift {
+            self.selected.iter_mut().for_each(|s| *s = false);
+        }
+        if let Some(hit) = hit {
+            self.selected[hit.triangle] = !modifiers.shift || !self.selected[hit.triangle];
+        }
+        self.changed = true;
+    }
+
+    /// Paints the selection with the brush around a face hit in the view, spreading across
+    /// connected faces while their corners are within the radius of the point hit.
+    /// Ctrl takes faces away from the selection instead.
+    pub fn paint(&mut self, hit: Option<Hit>, triangles: &[Triangle], placement: &Mat4, modifiers: egui::Modifiers) {
+        let index = match (self.model, self.mode) {
+            (Some(index), SelectMode::Brush) => index,
+            _ => return
+        };
+        let hit = match hit.filter(|hit| hit.model == index && hit.triangle < triangles.len()) {
+            Some(hit) => hit,
+            None => return
+        };
+        self.fit(triangles.len());
+        let (mesh, around) = self.connectivity.get_or_insert_with(|| {
+            // Indices match the model's, so the welded triangles can mark its selection directly
+            let mesh = TriangleMesh::with_all_triangles(triangles, triangle_mesh::default_tolerance(triangles));
+            let mut around = vec![Vec::<usize>::new(); mesh.vertices.len()];
+            for (t, corners) in mesh.triangles.iter().enumerate() {
+                for v in corners {
+                    around[*v].push(t);
+                }
+            }
+            (mesh, around)
+        });
+        let reached: Vec<bool> = mesh.vertices.iter()
+            .map(|v| glm::distance(&placement.transform_point(&(*v).into()).coords, &hit.point) <= self.radius)
+            .collect();
+        let value = !modifiers.command;
+        let mut visited = vec![false; mesh.triangles.len()];
+        let mut queue = vec![hit.triangle];
+        visited[hit.triangle] = true;
+        while let Some(t) = queue.pop() {
+            self.selected[t] = value;
+            for v in mesh.triangles[t].iter().filter(|v| reached[**v]) {
+                for next in &around[*v] {
+                    if !visited[*next] {
+                        visited[*next] = true;
+                        queue.push(*next);
+                    }
+                }
+            }
+        }
+        self.changed = true;
+    }
+
+    /// A circle the size of the brush around the point under the pointer, lying on the face there,
+    /// to draw in the view
+    pub fn brush_outline(&self, hover: Option<Hit>, triangles: &[Triangle], placement: &Mat4) -> Option<Vec<Vec3>> {
+        let hit = hover.filter(|hit| {
+            return self.model == Some(hit.model) && self.mode == SelectMode::Brush && hit.triangle < triangles.len();
+        })?;
+        let [a, b, c] = triangle::transform(&triangles[hit.triangle], placement);
+        let normal = glm::cross(&(b - a), &(c - a)).try_normalize(0.)?;
+        let u = (b - a).normalize();
+        let v = glm::cross(&normal, &u);
+        return Some((0..BRUSH_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / BRUSH_SEGMENTS as f32 * std::f32::consts::TAU;
+                hit.point + (u * angle.cos() + v * angle.sin()) * self.radius
+            })
+            .collect());
+    }
+
+    /// Follows a box or lasso dragged in the view, selecting the faces inside once it is let go.
+    /// Shift adds to the selection, and ctrl takes away from it.
+    pub fn drag(&mut self, response: &egui::Response, view: &ViewState, triangles: &[Triangle]) {
+        if !self.drags() {
+            self.path.clear();
+            return;
+        }
+        if response.dragged_by(egui::PointerButton::Primary) {
+            let position = match response.interact_pointer_pos() {
+                Some(position) => position,
+                None => return
+            };
+            match self.mode {
+                SelectMode::Box => {
+                    let start = self.path.first().copied().unwrap_or(position);
+                    self.path = vec![start, position];
+                },
+                SelectMode::Brush => return,
+                _ => {
+                    if !matches!(self.path.last(), Some(last) if last.distance(position) < LASSO_SPACING) {
+                        self.path.push(position);
+                    }
+                }
+            }
+        } else if response.drag_released() && !self.path.is_empty() {
+            let region = match self.mode {
+                SelectMode::Box => {
+                    let rect = egui::Rect::from_two_pos(self.path[0], self.path[self.path.len() - 1]);
+                    vec![rect.left_top(), rect.right_top(), rect.right_bottom(), rect.left_bottom()]
+                },
+                _ => std::mem::take(&mut self.path)
+            };
+            self.path.clear();
+            let modifiers = response.ctx.input().modifiers;
+            self.select_region(&region, view, response.rect, triangles, modifiers);
+        }
+    }
+
+    /// Selects the faces whose centers are drawn inside a polygon of the view
+    fn select_region(&mut self, region: &[egui::Pos2], view: &ViewState, rect: egui::Rect, triangles: &[Triangle],
+        modifiers: egui::Modifiers) {
+        let index = match self.model {
+            Some(index) => index,
+            None => return
+        };
+        let (placement, direction) = match (view.models.get(index), view.unproject(rect.center(), rect)) {
+            (Some(model), Some((_, direction))) => (model.local_transform, direction),
+            _ => return
+        };
+        if region.len() < 3 {
+            return;
+        }
+        self.fit(triangles.len());
+        let value = !modifiers.command;
+        if !modifiers.shift && !modifiers.command {
+            self.selected.iter_mut().for_each(|s| *s = false);
+        }
+        for (i, t) in triangles.iter().enumerate() {
+            let [a, b, c] = triangle::transform(t, &placement);
+            if self.facing_only && glm::cross(&(b - a), &(c - a)).dot(&direction) >= 0. {
+                continue;
+            }
+            if contains(region, view.project(&((a + b + c) / 3.), rect)) {
+                self.selected[i] = value;
+            }
+        }
+        self.changed = true;
+    }
+
+    /// The color of each corner, three to a triangle, with selected faces in the highlight color
+    fn colors(&self, highlight: [f32; 3]) -> Vec<[f32; 3]> {
+        return self.selected.iter()
+            .flat_map(|selected| [if *selected { highlight } else { [1., 1., 1.] }; 3])
+            .collect();
+    }
+
+    /// Shows the dialog if it is open, with the box or lasso being dragged,
+    /// given the model's triangles and the color to highlight selected faces in
+    pub fn show(&mut self, ctx: &egui::Context, triangles: &[Triangle], highlight: [f32; 3], unit: Unit) -> SelectResponse {
+        let index = match self.model {
+            Some(index) => index,
+            None => return SelectResponse::None
+        };
+        self.fit(triangles.len());
+        if self.path.len() >= 2 {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("selection")));
+            let [r, g, b] = highlight.map(|c| (c * 255.) as u8);
+            let stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(r, g, b));
+            let shape = match self.mode {
+                SelectMode::Box => egui::Shape::rect_stroke(
+                    egui::Rect::from_two_pos(self.path[0], self.path[self.path.len() - 1]), 0., stroke),
+                _ => egui::Shape::closed_line(self.path.clone(), stroke)
+            };
+            painter.add(shape);
+        }
+
+        let mut open = true;
+        let mut delete = false;
+        let mut export = false;
+        let count = self.count();
+        egui::Window::new(tr("select"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for mode in SelectMode::ALL {
+                    ui.radio_value(&mut self.mode, mode, mode.label());
+                }
+            });
+            if self.mode == SelectMode::Brush {
+                ui.horizontal(|ui| {
+                    ui.label(tr("select-radius"));
+                    ui.add(NumericEdit::new("select_radius", &mut self.radius, Quantity::Length(unit)));
+                });
+            } else {
+                ui.add_enabled(self.mode != SelectMode::Click, egui::Checkbox::new(&mut self.facing_only, tr("select-facing")));
+            }
+            ui.label(tr(match self.mode {
+                SelectMode::Click => "select-click-hint",
+                SelectMode::Brush => "select-brush-hint",
+                _ => "select-drag-hint"
+            }));
+            ui.label(tr_args("select-count", &[("count", &count), ("total", &triangles.len())]));
+            ui.horizontal(|ui| {
+                if ui.button(tr("select-all")).clicked() {
+                    self.selected.iter_mut().for_each(|s| *s = true);
+                    self.changed = true;
+                }
+                if ui.button(tr("select-none")).clicked() {
+                    self.selected.iter_mut().for_each(|s| *s = false);
+                    self.changed = true;
+                }
+                if ui.button(tr("select-invert")).clicked() {
+                    self.selected.iter_mut().for_each(|s| *s = !*s);
+                    self.changed = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                delete = ui.add_enabled(count > 0, egui::Button::new(tr("select-delete")))
+                    .on_hover_text(tr("select-delete-hint"))
+                    .clicked();
+                export = ui.add_enabled(count > 0, egui::Button::new(tr("select-export")))
+                    .on_hover_text(tr("select-export-hint"))
+                    .clicked();
+            });
+        });
+        // The Delete key works while nothing else has the keyboard, like a text field
+        if count > 0 && ctx.memory().focus().is_none() && ctx.input().key_pressed(egui::Key::Delete) {
+            delete = true;
+        }
+
+        if !open {
+            self.close();
+            return SelectResponse::None;
+        }
+        if export {
+            let selected = triangles.iter().zip(&self.selected)
+                .filter(|(_, selected)| **selected)
+                .map(|(t, _)| *t)
+                .collect();
+            return SelectResponse::Export(index, selected);
+        }
+        if delete {
+            let kept: Vec<Triangle> = triangles.iter().zip(&self.selected)
+                .filter(|(_, selected)| !**selected)
+                .map(|(t, _)| *t)
+                .collect();
+            let deleted = triangles.len() - kept.len();
+            self.selected = vec![false; kept.len()];
+            self.changed = false;
+            return SelectResponse::Delete(kept, deleted);
+        }
+        if self.changed {
+            self.changed = false;
+            return SelectResponse::Colors(index, self.colors(highlight));
+        }
+        return SelectResponse::None;
+    }
+}
+
+/// Whether a point is inside a polygon, by counting the edges a line to its right crosses
+fn contains(polygon: &[egui::Pos2], point: egui::Pos2) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    return inside;
+}
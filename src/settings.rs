@@ -0,0 +1,198 @@
+use crate::auto_run::AutoRun;
+use crate::build_volume::BuildVolume;
+use crate::decimate::AutoDecimate;
+use crate::i18n::Language;
+use crate::json::Json;
+use crate::measurement::MeasurementTemplate;
+use crate::printer::PrinterProfile;
+use crate::theme::Theme;
+use crate::units::Unit;
+
+/// User preferences that persist between sessions.
+///
+/// Each setting is stored as a string under its own key in the eframe storage,
+/// so adding a setting never invalidates the ones saved by older versions.
+pub struct Settings {
+    pub language: Language,
+    pub theme: Theme,
+    /// Unit of the mesh coordinates
+    pub unit: Unit,
+    /// Pick with the GPU even when the CPU picking hierarchy is ready
+    pub gpu_picking: bool,
+    /// Samples for each pixel of the view, to smooth the edges of the models. 1 turns this off.
+    pub antialiasing: u8,
+    /// Draw large models with fewer triangles while the view moves, if drawing them is slow
+    pub reduce_detail: bool,
+    pub auto_decimate: AutoDecimate,
+    /// Meshes with more triangles than this are decimated to about this many
+    pub decimate_threshold: usize,
+    /// When the picking hierarchy is built for a loaded mesh
+    pub auto_bvh: AutoRun,
+    /// When a loaded mesh is checked for issues
+    pub auto_issues: AutoRun,
+    /// Meshes with at most this many triangles count as small for `AutoRun::SmallMeshes`
+    pub auto_run_threshold: usize,
+    /// Keep data derived from loaded files in a temporary directory, to reopen them faster
+    pub disk_cache: bool,
+    /// Warn about geometry outside the current printer's build volume
+    pub check_build_volume: bool,
+    /// The printers the user has set up. There is always at least one.
+    pub printers: Vec<PrinterProfile>,
+    /// Index of the printer the mesh is checked against
+    pub printer: usize,
+    /// Measurement protocols the user has set up
+    pub templates: Vec<MeasurementTemplate>,
+    /// Where to ask for the latest release at startup, or empty to not check
+    pub update_url: String
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        return Self {
+            language: Language::from_environment().unwrap_or(Language::English),
+            theme: Theme::Dark,
+            unit: Unit::Millimeter,
+            gpu_picking: false,
+            antialiasing: 4,
+            reduce_detail: true,
+            auto_decimate: AutoDecimate::Ask,
+            decimate_threshold: 2_000_000,
+            auto_bvh: AutoRun::SmallMeshes,
+            auto_issues: AutoRun::OnDemand,
+            auto_run_threshold: 1_000_000,
+            disk_cache: true,
+            check_build_volume: true,
+            printers: vec![PrinterProfile::generic()],
+            printer: 0,
+            templates: vec![MeasurementTemplate::example()],
+            update_url: String::new()
+        }
+    }
+
+    /// Loads the settings from storage, using defaults for anything missing
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut settings = Settings::new();
+        let storage = match storage {
+            Some(storage) => storage,
+            None => return settings
+        };
+        if let Some(language) = storage.get_string("language")
+            .and_then(|code| Language::from_code(&code)) {
+            settings.language = language;
+        }
+        if let Some(theme) = storage.get_string("theme")
+            .and_then(|code| Theme::from_code(&code)) {
+            settings.theme = theme;
+        }
+        if let Some(unit) = storage.get_string("unit")
+            .and_then(|suffix| Unit::from_suffix(&suffix)) {
+            settings.unit = unit;
+        }
+        if let Some(gpu_picking) = storage.get_string("gpu_picking")
+            .and_then(|value| value.parse::<bool>().ok()) {
+            settings.gpu_picking = gpu_picking;
+        }
+        if let Some(samples) = storage.get_string("antialiasing")
+            .and_then(|value| value.parse::<u8>().ok()) {
+            settings.antialiasing = samples;
+        }
+        if let Some(reduce_detail) = storage.get_string("reduce_detail")
+            .and_then(|value| value.parse::<bool>().ok()) {
+            settings.reduce_detail = reduce_detail;
+        }
+        if let Some(auto_decimate) = storage.get_string("auto_decimate")
+            .and_then(|code| AutoDecimate::from_code(&code)) {
+            settings.auto_decimate = auto_decimate;
+        }
+        if let Some(threshold) = storage.get_string("decimate_threshold")
+            .and_then(|value| value.parse::<usize>().ok()) {
+            settings.decimate_threshold = threshold;
+        }
+        if let Some(auto_bvh) = storage.get_string("auto_bvh")
+            .and_then(|code| AutoRun::from_code(&code)) {
+            settings.auto_bvh = auto_bvh;
+        }
+        if let Some(auto_issues) = storage.get_string("auto_issues")
+            .and_then(|code| AutoRun::from_code(&code)) {
+            settings.auto_issues = auto_issues;
+        }
+        if let Some(threshold) = storage.get_string("auto_run_threshold")
+            .and_then(|value| value.parse::<usize>().ok()) {
+            settings.auto_run_threshold = threshold;
+        }
+        if let Some(disk_cache) = storage.get_string("disk_cache")
+            .and_then(|value| value.parse::<bool>().ok()) {
+            settings.disk_cache = disk_cache;
+        }
+        if let Some(enabled) = storage.get_string("check_build_volume")
+            .and_then(|value| value.parse::<bool>().ok()) {
+            settings.check_build_volume = enabled;
+        }
+        let printers: Option<Vec<PrinterProfile>> = storage.get_string("printers")
+            .and_then(|value| Json::parse(&value).ok())
+            .and_then(|value| value.as_array()?.iter().map(PrinterProfile::from_json).collect());
+        if let Some(printers) = printers.filter(|printers| !printers.is_empty()) {
+            settings.printers = printers;
+        }
+        if let Some(name) = storage.get_string("printer") {
+            settings.printer = settings.printers.iter().position(|p| p.name == name).unwrap_or(0);
+        }
+        let templates: Option<Vec<MeasurementTemplate>> = storage.get_string("measurement_templates")
+            .and_then(|value| Json::parse(&value).ok())
+            .and_then(|value| value.as_array()?.iter().map(MeasurementTemplate::from_json).collect());
+        if let Some(templates) = templates {
+            settings.templates = templates;
+        }
+        if let Some(url) = storage.get_string("update_url") {
+            settings.update_url = url;
+        }
+        return settings;
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string("language", self.language.code().to_string());
+        storage.set_string("theme", self.theme.code().to_string());
+        storage.set_string("unit", self.unit.suffix().to_string());
+        storage.set_string("gpu_picking", self.gpu_picking.to_string());
+        storage.set_string("antialiasing", self.antialiasing.to_string());
+        storage.set_string("reduce_detail", self.reduce_detail.to_string());
+        storage.set_string("auto_decimate", self.auto_decimate.code().to_string());
+        storage.set_string("decimate_threshold", self.decimate_threshold.to_string());
+        storage.set_string("auto_bvh", self.auto_bvh.code().to_string());
+        storage.set_string("auto_issues", self.auto_issues.code().to_string());
+        storage.set_string("auto_run_threshold", self.auto_run_threshold.to_string());
+        storage.set_string("disk_cache", self.disk_cache.to_string());
+        storage.set_string("check_build_volume", self.check_build_volume.to_string());
+        let printers: Vec<String> = self.printers.iter().map(|p| p.to_json()).collect();
+        storage.set_string("printers", format!("[{}]", printers.join(", ")));
+        storage.set_string("printer", self.printer().name.clone());
+        let templates: Vec<String> = self.templates.iter().map(|t| t.to_json()).collect();
+        storage.set_string("measurement_templates", format!("[{}]", templates.join(", ")));
+        storage.set_string("update_url", self.update_url.clone());
+    }
+
+    /// The printer the mesh is checked against
+    pub fn printer(&self) -> &PrinterProfile {
+        return &self.printers[self.printer.min(self.printers.len() - 1)];
+    }
+
+    /// The current printer's build volume, in the mesh's unit
+    pub fn build_volume(&self) -> BuildVolume {
+        let printer = self.printer();
+        return BuildVolume {
+            size: printer.build_volume.map(|length| printer.length_in(length, self.unit)),
+            enabled: self.check_build_volume
+        };
+    }
+
+    /// The current printer's thinnest wall, in the mesh's unit
+    pub fn min_wall(&self) -> f32 {
+        let printer = self.printer();
+        return printer.length_in(printer.min_wall, self.unit);
+    }
+
+    /// The current printer's steepest overhang printed without support, in degrees from vertical
+    pub fn max_overhang(&self) -> f32 {
+        return self.printer().max_overhang;
+    }
+}
@@ -0,0 +1,135 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::i18n::{tr, tr_args};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::shapes::{self, ShapeKind};
+use crate::triangle::Triangle;
+use crate::units::Unit;
+
+/// What the application should do after the shape dialog is shown
+pub enum ShapeResponse {
+    None,
+    /// Add a model with this name and these triangles to the scene
+    Add(String, Vec<Triangle>)
+}
+
+/// A dialog to create boxes, spheres, cylinders, cones, and rings as new models,
+/// for reference geometry or as stock to cut molds from
+pub struct ShapeUI {
+    pub open: bool,
+    pub kind: ShapeKind,
+    /// The size of a box on each axis
+    pub size: Vec3,
+    /// The radius of a sphere, cylinder, or cone's base, or of a ring's center line
+    pub radius: f32,
+    /// The height of a cylinder or cone
+    pub height: f32,
+    /// The radius of a ring's tube
+    pub tube_radius: f32,
+    /// Steps around the curved shapes
+    pub segments: usize,
+    /// Shapes created so far, for numbering their names
+    created: usize
+}
+
+impl ShapeUI {
+    pub fn new() -> Self {
+        return Self {
+            open: false,
+            kind: ShapeKind::Box,
+            size: Vec3::repeat(10.),
+            radius: 5.,
+            height: 10.,
+            tube_radius: 1.,
+            segments: 64,
+            created: 0
+        };
+    }
+
+    /// The triangles of the chosen shape, with the sizes entered
+    fn triangles(&self) -> Vec<Triangle> {
+        return match self.kind {
+            ShapeKind::Box => shapes::cuboid(&self.size),
+            ShapeKind::Sphere => shapes::sphere(self.radius, self.segments),
+            ShapeKind::Cylinder => shapes::frustum(self.radius, self.radius, self.height, self.segments),
+            ShapeKind::Cone => shapes::frustum(self.radius, 0., self.height, self.segments),
+            ShapeKind::Torus => shapes::torus(self.radius, self.tube_radius, self.segments)
+        };
+    }
+
+    /// Shows the dialog if it is open
+    pub fn show(&mut self, ctx: &egui::Context, unit: Unit) -> ShapeResponse {
+        if !self.open {
+            return ShapeResponse::None;
+        }
+        let mut open = true;
+        let mut create = false;
+        let length = Quantity::Length(unit);
+        egui::Window::new(tr("shape"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for kind in ShapeKind::ALL {
+                    ui.radio_value(&mut self.kind, kind, kind.label());
+                }
+            });
+            egui::Grid::new("shape_sizes").show(ui, |ui| {
+                match self.kind {
+                    ShapeKind::Box => {
+                        for (axis, name) in ["X", "Y", "Z"].iter().enumerate() {
+                            ui.label(tr_args("shape-size", &[("axis", name)]));
+                            ui.add(NumericEdit::new(("size", axis), &mut self.size[axis], length));
+                            ui.end_row();
+                        }
+                    },
+                    ShapeKind::Sphere | ShapeKind::Cylinder | ShapeKind::Cone => {
+                        ui.label(tr("shape-radius"));
+                        ui.add(NumericEdit::new("radius", &mut self.radius, length));
+                        ui.end_row();
+                    },
+                    ShapeKind::Torus => {
+                        ui.label(tr("shape-ring-radius"));
+                        ui.add(NumericEdit::new("radius", &mut self.radius, length));
+                        ui.end_row();
+                        ui.label(tr("shape-tube-radius"));
+                        ui.add(NumericEdit::new("tube_radius", &mut self.tube_radius, length));
+                        ui.end_row();
+                    }
+                }
+                if matches!(self.kind, ShapeKind::Cylinder | ShapeKind::Cone) {
+                    ui.label(tr("shape-height"));
+                    ui.add(NumericEdit::new("height", &mut self.height, length));
+                    ui.end_row();
+                }
+                if self.kind != ShapeKind::Box {
+                    ui.label(tr("shape-segments"));
+                    ui.add(egui::DragValue::new(&mut self.segments).clamp_range(3..=512));
+                    ui.end_row();
+                }
+            });
+            let valid = match self.kind {
+                ShapeKind::Box => self.size.iter().all(|s| *s > 0.),
+                ShapeKind::Sphere => self.radius > 0.,
+                ShapeKind::Cylinder | ShapeKind::Cone => self.radius > 0. && self.height > 0.,
+                // A tube wider than the ring would pass through itself
+                ShapeKind::Torus => self.tube_radius > 0. && self.radius > self.tube_radius
+            };
+            if !valid {
+                ui.label(tr("shape-invalid"));
+            }
+            ui.label(tr("shape-hint"));
+            create = ui.add_enabled(valid, egui::Button::new(tr("shape-create"))).clicked();
+        });
+        if !open {
+            self.open = false;
+        }
+        if !create {
+            return ShapeResponse::None;
+        }
+        self.created += 1;
+        let name = tr_args("shape-name", &[("kind", &self.kind.label()), ("number", &self.created)]);
+        return ShapeResponse::Add(name, self.triangles());
+    }
+}
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use crate::i18n::{tr, tr_args};
+use crate::mesh_cache;
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::simplify;
+use crate::thread_request::Request;
+use crate::triangle::Triangle;
+use crate::triangle_mesh::TriangleMesh;
+use crate::units::Unit;
+
+/// What the application should do after the simplify dialog is shown
+pub enum SimplifyResponse {
+    None,
+    /// A new preview is ready, and should be shown in place of the model
+    Preview,
+    /// Replace the model's triangles with these
+    Apply(Arc<Vec<Triangle>>),
+    /// Show the model's own triangles again
+    Restore
+}
+
+/// The model's welded mesh and its simplified triangles, or None if simplifying was cancelled
+type Simplified = Option<(Arc<TriangleMesh>, Vec<Triangle>)>;
+
+/// A dialog to reduce the triangle count of a model, previewing the result
+/// in the view as the settings change
+pub struct SimplifyUI {
+    /// Index of the model being simplified, while the dialog is open
+    pub model: Option<usize>,
+    /// Percentage of the triangles to keep
+    pub percent: f32,
+    /// Stop before any part of the surface moves further than the maximum error
+    pub limit_error: bool,
+    pub max_error: f32,
+    /// The model's welded mesh, kept between previews
+    mesh: Option<Arc<TriangleMesh>>,
+    request: Option<Request<Simplified>>,
+    preview: Option<Arc<Vec<Triangle>>>
+}
+
+impl SimplifyUI {
+    pub fn new() -> Self {
+        return Self {
+            model: None,
+            percent: 25.,
+            limit_error: false,
+            max_error: 0.1,
+            mesh: None,
+            request: None,
+            preview: None
+        };
+    }
+
+    /// Opens the dialog for a model, dropping any previous preview
+    pub fn open(&mut self, model: usize) {
+        self.close();
+        self.model = Some(model);
+    }
+
+    pub fn close(&mut self) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        self.model = None;
+        self.mesh = None;
+        self.request = None;
+        self.preview = None;
+    }
+
+    /// The triangles shown in place of a model, if it is being previewed
+    pub fn preview_of(&self, model: usize) -> Option<&Arc<Vec<Triangle>>> {
+        if self.model != Some(model) {
+            return None;
+        }
+        return self.preview.as_ref();
+    }
+
+    /// Shows the dialog if it is open, given the triangles of the model being simplified
+    /// and their key in the disk cache
+    pub fn show(&mut self, ctx: &egui::Context, triangles: &[Triangle], cache_key: Option<&str>, unit: Unit)
+    -> SimplifyResponse {
+        if self.model.is_none() {
+            return SimplifyResponse::None;
+        }
+        let mut response = SimplifyResponse::None;
+        if let Some(result) = self.request.as_mut().and_then(|request| request.take()) {
+            self.request = None;
+            if let Some((mesh, preview)) = result {
+                self.mesh = Some(mesh);
+                self.preview = Some(Arc::new(preview));
+                response = SimplifyResponse::Preview;
+            }
+        }
+
+        let mut open = true;
+        let mut changed = self.request.is_none() && self.preview.is_none();
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new(tr("simplify"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+            let after = self.preview.as_ref().map_or("…".to_string(), |preview| preview.len().to_string());
+            ui.label(tr_args("simplify-count", &[("before", &triangles.len()), ("after", &after)]));
+            let slider = ui.add(egui::Slider::new(&mut self.percent, 1.0..=100.0)
+                .suffix("%")
+                .text(tr("simplify-keep")));
+            // Only start over once the slider is let go
+            changed |= slider.drag_released() || (slider.changed() && !slider.dragged());
+            ui.horizontal(|ui| {
+                changed |= ui.checkbox(&mut self.limit_error, tr("simplify-max-error")).changed();
+                changed |= ui.add_enabled(self.limit_error,
+                    NumericEdit::new("max_error", &mut self.max_error, Quantity::Length(unit))).changed();
+            });
+            if let Some(request) = &self.request {
+                ui.add(egui::ProgressBar::new(request.progress()).show_percentage());
+            }
+            ui.horizontal(|ui| {
+                let ready = self.preview.is_some() && self.request.is_none();
+                apply = ui.add_enabled(ready, egui::Button::new(tr("simplify-apply"))).clicked();
+                cancel = ui.button(tr("simplify-cancel")).clicked();
+            });
+        });
+
+        if apply {
+            let preview = self.preview.take().unwrap();
+            self.close();
+            return SimplifyResponse::Apply(preview);
+        }
+        if cancel || !open {
+            self.close();
+            return SimplifyResponse::Restore;
+        }
+        if changed {
+            self.start(ctx, triangles, cache_key);
+        }
+        return response;
+    }
+
+    /// Starts simplifying in the background, replacing any simplification in progress
+    fn start(&mut self, ctx: &egui::Context, triangles: &[Triangle], cache_key: Option<&str>) {
+        if let Some(request) = &self.request {
+            request.cancel();
+        }
+        let mesh = self.mesh.clone();
+        // The triangles are only needed to weld the mesh the first time
+        let triangles = if mesh.is_none() { triangles.to_vec() } else { Vec::new() };
+        let cache_key = cache_key.map(|key| key.to_string());
+        let percent = self.percent;
+        let max_error = if self.limit_error { Some(self.max_error) } else { None };
+        self.request = Some(Request::spawn(ctx, move |progress| {
+            let mesh = mesh.unwrap_or_else(|| Arc::new(mesh_cache::welded(cache_key.as_deref(), &triangles)));
+            let target = (mesh.triangles.len() as f32 * percent / 100.).ceil() as usize;
+            let simplified = simplify::simplify(&mesh, target, max_error, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            })?;
+            return Some((mesh, simplified.to_triangles()));
+        }));
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::Error;
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::i18n::{tr, tr_args};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::plane_cut::{self, Plane, SectionLoop};
+use crate::slice_export::{self, SliceFormat};
+use crate::thread_request::Request;
+use crate::triangle::{self, Triangle};
+use crate::units::Unit;
+
+/// Width and height of the flat drawing of the cross section, in points
+const PREVIEW_SIZE: f32 = 240.;
+
+/// What the application should do after the cross section dialog is shown
+pub enum SliceResponse {
+    None,
+    /// The slice stack was written to these files, or failed to be
+    Exported(Result<Vec<String>, Error>)
+}
+
+/// A dialog to look through a model one height at a time, drawing the cross section
+/// in the view and measuring its loops
+pub struct SliceUI {
+    /// Index of the model being sliced, while the dialog is open
+    pub model: Option<usize>,
+    /// Height of the slice in scene coordinates
+    pub height: f32,
+    /// How far the step buttons move the slice, and how far apart exported slices are
+    pub step: f32,
+    pub format: SliceFormat,
+    /// The last cross section found, with the model, placement, triangle count, and height it is for
+    section: Option<(usize, Mat4, usize, f32, Vec<SectionLoop>)>,
+    /// The slice stack being written
+    export: Option<Request<Result<Vec<String>, Error>>>
+}
+
+impl SliceUI {
+    pub fn new() -> Self {
+        return Self { model: None, height: 0., step: 0.2, format: SliceFormat::Svg, section: None, export: None };
+    }
+
+    /// Opens the dialog for a model with the given bounds, slicing through its middle
+    pub fn open(&mut self, model: usize, bounds: Option<(Vec3, Vec3)>) {
+        self.model = Some(model);
+        self.section = None;
+        if let Some((min, max)) = bounds {
+            self.height = (min.z + max.z) / 2.;
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.cancel_export();
+        self.model = None;
+        self.section = None;
+    }
+
+    fn cancel_export(&mut self) {
+        if let Some(request) = &self.export {
+            request.cancel();
+        }
+        self.export = None;
+    }
+
+    /// Asks where to save the slice stack, and starts writing it in the background
+    fn start_export(&mut self, ctx: &egui::Context, triangles: &[Triangle], placement: &Mat4, unit: Unit) {
+        let extension = self.format.extension();
+        let upper = extension.to_uppercase();
+        let path = rfd::FileDialog::new().add_filter(extension, &[extension, &upper]).save_file();
+        let path = match path {
+            Some(path) => path.display().to_string(),
+            None => return
+        };
+        let placed: Vec<Triangle> = triangles.iter().map(|t| triangle::transform(t, placement)).collect();
+        let (layer_height, format) = (self.step, self.format);
+        self.export = Some(Request::spawn(ctx, move |progress| {
+            return slice_export::export_stack(&path, &placed, layer_height, format, unit, |fraction| {
+                progress.set(fraction);
+                return !progress.cancelled();
+            });
+        }));
+    }
+
+    /// The loops of the cross section, to draw in the view
+    pub fn outlines(&self) -> Vec<Vec<Vec3>> {
+        return match (&self.model, &self.section) {
+            (Some(_), Some((_, _, _, _, loops))) => loops.iter().map(|l| l.points.clone()).collect(),
+            _ => Vec::new()
+        };
+    }
+
+    /// Finds the cross section of the model at the current height, unless it was already found
+    fn update(&mut self, index: usize, triangles: &[Triangle], placement: &Mat4) {
+        let current = matches!(&self.section,
+            Some((i, p, count, height, _)) if *i == index && p == placement
+                && *count == triangles.len() && *height == self.height);
+        if current {
+            return;
+        }
+        let placed: Vec<Triangle> = triangles.iter().map(|t| triangle::transform(t, placement)).collect();
+        let plane = Plane { normal: Vec3::z(), offset: self.height };
+        let loops = plane_cut::section(&placed, &plane);
+        self.section = Some((index, *placement, triangles.len(), self.height, loops));
+    }
+
+    /// Shows the dialog if it is open, given the triangles of the model being sliced,
+    /// the transformation placing them in the scene, and their bounds once placed
+    pub fn show(&mut self, ctx: &egui::Context, triangles: &[Triangle], placement: &Mat4, bounds: Option<(Vec3, Vec3)>,
+        unit: Unit) -> SliceResponse {
+        let index = match self.model {
+            Some(index) => index,
+            None => return SliceResponse::None
+        };
+        if let Some(result) = self.export.as_mut().and_then(|request| request.take()) {
+            self.export = None;
+            return SliceResponse::Exported(result);
+        }
+        let suffix = unit.suffix();
+        let mut open = true;
+        let mut export = false;
+        egui::Window::new(tr("slice"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+            if let Some((min, max)) = bounds {
+                ui.add(egui::Slider::new(&mut self.height, min.z..=max.z).text(tr("slice-height")));
+            }
+            ui.horizontal(|ui| {
+                if ui.button("⬆").on_hover_text(tr("slice-up")).clicked() {
+                    self.height += self.step;
+                }
+                if ui.button("⬇").on_hover_text(tr("slice-down")).clicked() {
+                    self.height -= self.step;
+                }
+                ui.label(tr("slice-step"));
+                ui.add(NumericEdit::new("slice_step", &mut self.step, Quantity::Length(unit)));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("slice-format"));
+                for format in SliceFormat::ALL {
+                    ui.radio_value(&mut self.format, format, format.label());
+                }
+                match &self.export {
+                    Some(request) => {
+                        ui.add(egui::ProgressBar::new(request.progress()).show_percentage().desired_width(120.));
+                        if ui.button(tr("slice-cancel-export")).clicked() {
+                            self.cancel_export();
+                        }
+                    },
+                    None => {
+                        export = ui.button(tr("slice-export")).on_hover_text(tr("slice-export-hint")).clicked();
+                    }
+                }
+            });
+            ui.separator();
+            self.update(index, triangles, placement);
+            let loops = match &self.section {
+                Some((_, _, _, _, loops)) => loops,
+                None => return
+            };
+            if loops.is_empty() {
+                ui.label(tr("slice-empty"));
+                return;
+            }
+            let perimeter: f32 = loops.iter().map(|l| l.perimeter()).sum();
+            let area: f32 = loops.iter().map(|l| l.area(&Vec3::z())).sum();
+            ui.label(tr_args("slice-totals", &[
+                ("count", &loops.len().to_string()),
+                ("perimeter", &format!("{:.3} {}", perimeter, suffix)),
+                ("area", &format!("{:.3} {}²", area, suffix))]));
+            if loops.iter().any(|l| !l.closed) {
+                ui.colored_label(egui::Color32::RED, tr("slice-open-hint"));
+            }
+            if let Some((min, max)) = bounds {
+                draw_section(ui, loops, &min, &max);
+            }
+            egui::ScrollArea::vertical().max_height(160.).show(ui, |ui| {
+                egui::Grid::new("slice_loops").striped(true).show(ui, |ui| {
+                    ui.label(tr("slice-loop"));
+                    ui.label(tr("slice-perimeter"));
+                    ui.label(tr("slice-area"));
+                    ui.end_row();
+                    for (i, section_loop) in loops.iter().enumerate() {
+                        ui.label((i + 1).to_string());
+                        ui.label(format!("{:.3} {}", section_loop.perimeter(), suffix));
+                        if section_loop.closed {
+                            ui.label(format!("{:.3} {}²", section_loop.area(&Vec3::z()), suffix));
+                        } else {
+                            ui.label(tr("slice-open"));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+        if !open {
+            self.close();
+        } else if export {
+            self.start_export(ctx, triangles, placement, unit);
+        }
+        return SliceResponse::None;
+    }
+}
+
+/// Draws the loops flat, seen from above, fitting the model's footprint in the preview.
+/// Open chains are drawn in red.
+fn draw_section(ui: &mut egui::Ui, loops: &[SectionLoop], min: &Vec3, max: &Vec3) {
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(PREVIEW_SIZE), egui::Sense::hover());
+    let rect = response.rect.shrink(4.);
+    painter.rect_stroke(response.rect, 0., ui.visuals().widgets.noninteractive.bg_stroke);
+    let extent = (max.x - min.x).max(max.y - min.y).max(f32::EPSILON);
+    let scale = rect.width() / extent;
+    let center = (min + max) / 2.;
+    // Up in the preview is +Y, like looking down on the build plate
+    let to_screen = |p: &Vec3| rect.center() + egui::vec2((p.x - center.x) * scale, (center.y - p.y) * scale);
+    let closed = egui::Stroke::new(1.5, ui.visuals().text_color());
+    let open = egui::Stroke::new(1.5, egui::Color32::RED);
+    for section_loop in loops {
+        let points: Vec<egui::Pos2> = section_loop.points.iter().map(to_screen).collect();
+        if section_loop.closed {
+            painter.add(egui::Shape::closed_line(points, closed));
+        } else {
+            painter.add(egui::Shape::line(points, open));
+        }
+    }
+}
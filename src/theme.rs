@@ -0,0 +1,101 @@
+use egui::{Color32, Stroke, Visuals};
+
+use crate::i18n::tr;
+
+/// Colors used by the 3D viewport for a theme
+pub struct Palette {
+    /// Clear color behind the models
+    pub background: [f32; 3],
+    /// Color for lines and markers drawn over the models
+    pub overlay: [f32; 3]
+}
+
+/// The look of the UI and the viewport
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme {
+    Dark,
+    Light,
+    HighContrast
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::HighContrast];
+
+    /// The name used to store the theme in the settings
+    pub fn code(&self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+            Theme::HighContrast => "high-contrast"
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        return Theme::ALL.into_iter().find(|t| t.code() == code);
+    }
+
+    /// The translated name of the theme
+    pub fn label(&self) -> String {
+        match self {
+            Theme::Dark => tr("theme-dark"),
+            Theme::Light => tr("theme-light"),
+            Theme::HighContrast => tr("theme-high-contrast")
+        }
+    }
+
+    pub fn visuals(&self) -> Visuals {
+        match self {
+            Theme::Dark => Visuals::dark(),
+            Theme::Light => Visuals::light(),
+            Theme::HighContrast => high_contrast_visuals()
+        }
+    }
+
+    pub fn palette(&self) -> Palette {
+        match self {
+            Theme::Dark => Palette {
+                background: [0.1, 0.1, 0.1],
+                overlay: [1.0, 0.6, 0.1]
+            },
+            Theme::Light => Palette {
+                background: [0.85, 0.85, 0.88],
+                overlay: [0.8, 0.2, 0.0]
+            },
+            Theme::HighContrast => Palette {
+                background: [0.0, 0.0, 0.0],
+                overlay: [1.0, 1.0, 0.0]
+            }
+        }
+    }
+}
+
+/// Dark visuals with pure black and white, thick outlines,
+/// and yellow for anything selected or active
+fn high_contrast_visuals() -> Visuals {
+    let mut visuals = Visuals::dark();
+    let white = Stroke::new(1.5, Color32::WHITE);
+    let yellow = Stroke::new(2.0, Color32::YELLOW);
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::from_gray(20);
+    visuals.code_bg_color = Color32::BLACK;
+    visuals.hyperlink_color = Color32::from_rgb(0, 255, 255);
+    visuals.selection.bg_fill = Color32::from_rgb(0, 0, 160);
+    visuals.selection.stroke = yellow;
+    visuals.widgets.noninteractive.bg_fill = Color32::BLACK;
+    visuals.widgets.noninteractive.bg_stroke = white;
+    visuals.widgets.noninteractive.fg_stroke = white;
+    visuals.widgets.inactive.bg_fill = Color32::BLACK;
+    visuals.widgets.inactive.bg_stroke = white;
+    visuals.widgets.inactive.fg_stroke = white;
+    visuals.widgets.hovered.bg_fill = Color32::from_gray(40);
+    visuals.widgets.hovered.bg_stroke = yellow;
+    visuals.widgets.hovered.fg_stroke = yellow;
+    visuals.widgets.active.bg_fill = Color32::from_gray(60);
+    visuals.widgets.active.bg_stroke = yellow;
+    visuals.widgets.active.fg_stroke = yellow;
+    visuals.widgets.open.bg_fill = Color32::from_gray(40);
+    visuals.widgets.open.bg_stroke = yellow;
+    visuals.widgets.open.fg_stroke = yellow;
+    return visuals;
+}
@@ -0,0 +1,332 @@
+use std::any::Any;
+use std::cmp;
+use std::collections::BinaryHeap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread;
+
+/// Most jobs run at once. More would mostly compete for the same cores,
+/// since the heavy work inside them already runs on several threads.
+const MAX_WORKERS: usize = 4;
+
+/// Which queued job starts first when a worker is free. Jobs of the same priority start in the order they were queued.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Priority {
+    /// Work nobody is waiting on yet, like caches and faster ways to draw
+    Background,
+    /// Work for something shown in the UI
+    Normal,
+    /// Work the user is waiting on before they can do anything else, like opening a file
+    Urgent
+}
+
+/// A queued job, ordered so the queue's greatest is the one to start next
+struct Job {
+    priority: Priority,
+    /// Counts up as jobs are queued
+    order: u64,
+    run: Box<dyn FnOnce() + Send>
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        return self.cmp(other) == cmp::Ordering::Equal;
+    }
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for Job {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        return self.priority.cmp(&other.priority).then(other.order.cmp(&self.order));
+    }
+}
+
+/// The jobs waiting for a worker, and the order of the next one queued
+struct Queue {
+    jobs: BinaryHeap<Job>,
+    next_order: u64
+}
+
+/// A fixed set of worker threads, started the first time a job is queued, taking jobs from one queue
+struct Pool {
+    queue: Mutex<Queue>,
+    queued: Condvar
+}
+
+impl Pool {
+    fn get() -> &'static Pool {
+        static POOL: OnceLock<Pool> = OnceLock::new();
+        let mut created = false;
+        let pool = POOL.get_or_init(|| {
+            created = true;
+            return Pool { queue: Mutex::new(Queue { jobs: BinaryHeap::new(), next_order: 0 }), queued: Condvar::new() };
+        });
+        if created {
+            let workers = thread::available_parallelism().map_or(2, |n| n.get()).clamp(2, MAX_WORKERS);
+            for _ in 0..workers {
+                thread::spawn(move || pool.work());
+            }
+        }
+        return pool;
+    }
+
+    fn push(&self, priority: Priority, run: Box<dyn FnOnce() + Send>) {
+        let mut queue = self.queue.lock().unwrap();
+        let order = queue.next_order;
+        queue.next_order += 1;
+        queue.jobs.push(Job { priority, order, run });
+        self.queued.notify_one();
+    }
+
+    /// Runs jobs as they are queued, forever
+    fn work(&self) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    match queue.jobs.pop() {
+                        Some(job) => break job,
+                        None => queue = self.queued.wait(queue).unwrap()
+                    }
+                }
+            };
+            // Requests keep their own panics for the UI; this keeps any other job that panics from taking the worker with it
+            let _ = panic::catch_unwind(AssertUnwindSafe(job.run));
+        }
+    }
+}
+
+/// Queues work for the pool of worker threads, without waiting for its result
+pub fn spawn_job(priority: Priority, job: impl FnOnce() + Send + 'static) {
+    Pool::get().push(priority, Box::new(job));
+}
+
+/// Lets work on a background thread report how far along it is,
+/// and find out if it should stop early.
+#[derive(Clone)]
+pub struct Progress {
+    fraction: Arc<AtomicU32>,
+    cancelled: Arc<AtomicBool>,
+    ctx: egui::Context
+}
+
+impl Progress {
+    /// Records how much of the work is done, from 0 to 1, and asks the UI to redraw
+    pub fn set(&self, fraction: f32) {
+        let previous = f32::from_bits(self.fraction.swap(fraction.to_bits(), Ordering::Relaxed));
+        // Only redraw for visible changes, since this may be called very often
+        if (fraction - previous).abs() >= 0.001 {
+            self.ctx.request_repaint();
+        }
+    }
+
+    /// True once the request has been cancelled, so the work should stop
+    pub fn cancelled(&self) -> bool {
+        return self.cancelled.load(Ordering::Relaxed);
+    }
+}
+
+/// Work continuing from a request's result, or from the message of the panic that stopped it
+type Continuation<T> = Box<dyn FnOnce(Result<T, String>) + Send>;
+
+/// What a request's work leaves for the UI
+struct Outcome<T> {
+    result: Option<T>,
+    /// The message of the panic that stopped the work, in place of a result
+    panic: Option<String>,
+    /// Continues with the result, or the panic, once it is ready, in place of keeping it,
+    /// if set by `Request::then`
+    then: Option<Continuation<T>>
+}
+
+impl<T> Outcome<T> {
+    /// Keeps the result of finished work, or passes it on to the work continuing from it
+    fn finish(&mut self, value: Result<T, String>) {
+        match (self.then.take(), value) {
+            (Some(then), value) => then(value),
+            (None, Ok(value)) => self.result = Some(value),
+            (None, Err(message)) => self.panic = Some(message)
+        }
+    }
+}
+
+/// The message a panic was raised with
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    return "Unknown panic".to_string();
+}
+
+/// Work queued for the worker threads, which the UI can poll each frame
+/// without blocking.
+pub struct Request<T> {
+    outcome: Arc<Mutex<Outcome<T>>>,
+    progress: Progress,
+    priority: Priority
+}
+
+impl<T: Send + 'static> Request<T> {
+    /// Queues the work with normal priority. The UI is redrawn when it finishes.
+    pub fn spawn(ctx: &egui::Context, work: impl FnOnce(&Progress) -> T + Send + 'static) -> Self {
+        return Self::spawn_with(ctx, Priority::Normal, work);
+    }
+
+    /// Queues the work to start before or after other jobs. The UI is redrawn when it finishes.
+    /// If the request is cancelled before the work starts, it never runs.
+    pub fn spawn_with(ctx: &egui::Context, priority: Priority, work: impl FnOnce(&Progress) -> T + Send + 'static) -> Self {
+        let progress = Progress {
+            fraction: Arc::new(AtomicU32::new(0f32.to_bits())),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            ctx: ctx.clone()
+        };
+        let request = Self::waiting(progress, priority);
+        Self::queue(request.outcome.clone(), request.progress.clone(), priority, work);
+        return request;
+    }
+
+    /// A request whose work isn't queued yet
+    fn waiting(progress: Progress, priority: Priority) -> Self {
+        let outcome = Outcome { result: None, panic: None, then: None };
+        return Self { outcome: Arc::new(Mutex::new(outcome)), progress, priority };
+    }
+
+    /// Queues the work that finishes a request
+    fn queue(outcome: Arc<Mutex<Outcome<T>>>, progress: Progress, priority: Priority,
+        work: impl FnOnce(&Progress) -> T + Send + 'static) {
+        spawn_job(priority, move || {
+            if progress.cancelled() {
+                return;
+            }
+            let value = panic::catch_unwind(AssertUnwindSafe(|| work(&progress)))
+                .map_err(|payload| panic_message(payload.as_ref()));
+            outcome.lock().unwrap().finish(value);
+            progress.ctx.request_repaint();
+        });
+    }
+
+    /// Continues with more work on the result, once this request's work finishes, as a new request
+    /// with the same priority. The result goes to that work instead of being kept.
+    /// Cancelling the new request stops this request's work too, if it hasn't finished.
+    /// If this request's work panics, the new request ends with the same panic.
+    pub fn then<U: Send + 'static>(self, work: impl FnOnce(T, &Progress) -> U + Send + 'static) -> Request<U> {
+        let progress = Progress {
+            fraction: Arc::new(AtomicU32::new(0f32.to_bits())),
+            cancelled: self.progress.cancelled.clone(),
+            ctx: self.progress.ctx.clone()
+        };
+        let next = Request::<U>::waiting(progress, self.priority);
+        let (next_outcome, next_progress, priority) = (next.outcome.clone(), next.progress.clone(), next.priority);
+        let start = move |value: Result<T, String>| {
+            match value {
+                Ok(value) => Request::queue(next_outcome, next_progress, priority, move |progress| work(value, progress)),
+                // The work continuing from a panic can't run, so it fails the same way
+                Err(message) => next_outcome.lock().unwrap().finish(Err(message))
+            }
+        };
+        let mut outcome = self.outcome.lock().unwrap();
+        match (outcome.result.take(), outcome.panic.take()) {
+            (Some(value), _) => start(Ok(value)),
+            (None, Some(message)) => start(Err(message)),
+            (None, None) => outcome.then = Some(Box::new(start))
+        }
+        return next;
+    }
+
+    /// How much of the work is done, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        return f32::from_bits(self.progress.fraction.load(Ordering::Relaxed));
+    }
+
+    /// Asks the work to stop. Any result it still produces is discarded.
+    pub fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// True once the request has been cancelled
+    pub fn cancelled(&self) -> bool {
+        return self.progress.cancelled();
+    }
+
+    /// True once the work has finished, panicked, or been cancelled, and its result hasn't been taken
+    pub fn is_finished(&self) -> bool {
+        let outcome = self.outcome.lock().unwrap();
+        return self.cancelled() || outcome.result.is_some() || outcome.panic.is_some();
+    }
+
+    /// The message of the panic that stopped the work, if it panicked instead of finishing
+    pub fn panic_message(&self) -> Option<String> {
+        return self.outcome.lock().unwrap().panic.clone();
+    }
+
+    /// Takes the result, if the work has finished and was not cancelled
+    pub fn take(&mut self) -> Option<T> {
+        if self.progress.cancelled() {
+            return None;
+        }
+        return self.outcome.lock().unwrap().result.take();
+    }
+}
+
+/// A request for work on some input, like a model's triangles and placement, given by a key.
+/// The request is cancelled as soon as the key it was started with is no longer current,
+/// so its result is never used for a different input.
+pub struct KeyedRequest<K, T> {
+    pending: Option<(K, Request<T>)>
+}
+
+impl<K: PartialEq, T: Send + 'static> KeyedRequest<K, T> {
+    pub fn new() -> Self {
+        return Self { pending: None };
+    }
+
+    /// Cancels the request, unless it is for the input that is current now
+    pub fn keep_current(&mut self, key: &K) {
+        if !matches!(&self.pending, Some((pending, _)) if pending == key) {
+            self.cancel();
+        }
+    }
+
+    /// Starts work for an input, in place of any request before it
+    pub fn start(&mut self, key: K, request: Request<T>) {
+        self.cancel();
+        self.pending = Some((key, request));
+    }
+
+    /// Takes the result once the work finishes. Nothing is pending after that.
+    pub fn take(&mut self) -> Option<T> {
+        let (_, request) = self.pending.as_mut()?;
+        if !request.is_finished() {
+            return None;
+        }
+        let result = request.take();
+        self.pending = None;
+        return result;
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some((_, request)) = self.pending.take() {
+            request.cancel();
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        return self.pending.is_some();
+    }
+
+    /// How much of the pending work is done, from 0 to 1, if there is any
+    pub fn progress(&self) -> Option<f32> {
+        return self.pending.as_ref().map(|(_, request)| request.progress());
+    }
+}
@@ -0,0 +1,98 @@
+extern crate nalgebra_glm as glm;
+use glm::Vec3;
+
+use crate::triangle::Triangle;
+
+/// Widest or tallest image drawn, since the image and its depth buffer are kept in memory
+pub const MAX_SIZE: usize = 4096;
+
+/// Color of the lit surface
+const SURFACE: [f32; 3] = [0.55, 0.7, 0.9];
+/// Direction toward the light, which is off to the side of the eye so faces can be told apart
+const LIGHT: [f32; 3] = [0.4, -0.7, 1.];
+
+/// Draws triangles on the CPU, seen from the front right and above, filling the image.
+///
+/// This needs no window or graphics context, so it can run unattended.
+/// Returns RGBA pixels, row by row from the top, with a transparent background.
+pub fn render(triangles: &[Triangle], width: usize, height: usize) -> Vec<u8> {
+    profile_span!("render_thumbnail");
+    let mut pixels = vec![0u8; width * height * 4];
+    if width == 0 || height == 0 {
+        return pixels;
+    }
+    let toward_eye = Vec3::new(1., -1., 1.).normalize();
+    let right = Vec3::new(1., 1., 0.).normalize();
+    let up = right.cross(&-toward_eye);
+    let light = Vec3::from(LIGHT).normalize();
+    let project = |p: &Vec3| Vec3::new(p.dot(&right), p.dot(&up), p.dot(&toward_eye));
+
+    let projected: Vec<[Vec3; 3]> = triangles.iter().map(|t| t.map(|p| project(&p))).collect();
+    let mut min = Vec3::repeat(f32::MAX);
+    let mut max = Vec3::repeat(f32::MIN);
+    for corner in projected.iter().flatten() {
+        min = glm::min2(&min, corner);
+        max = glm::max2(&max, corner);
+    }
+    let extent = (max.x - min.x).max(max.y - min.y);
+    if extent <= 0. {
+        return pixels;
+    }
+    // A small margin, and centered on the shorter side
+    let (width_f, height_f) = (width as f32, height as f32);
+    let scale = (width_f / (max.x - min.x)).min(height_f / (max.y - min.y)) * 0.9;
+    let offset_x = (width_f - (max.x - min.x) * scale) / 2.;
+    let offset_y = (height_f - (max.y - min.y) * scale) / 2.;
+    let to_pixel = |p: &Vec3| Vec3::new(
+        (p.x - min.x) * scale + offset_x,
+        height_f - ((p.y - min.y) * scale + offset_y),
+        p.z);
+
+    let mut depth = vec![f32::NEG_INFINITY; width * height];
+    for (triangle, view) in triangles.iter().zip(&projected) {
+        let normal = match (triangle[1] - triangle[0]).cross(&(triangle[2] - triangle[0])).try_normalize(0.) {
+            Some(normal) => normal,
+            None => continue
+        };
+        // Back faces are lit the same, so holes don't show as black
+        let brightness = 0.25 + 0.75 * normal.dot(&light).abs();
+        let color = SURFACE.map(|c| (c * brightness * 255.) as u8);
+
+        let [a, b, c] = view.map(|p| to_pixel(&p));
+        let area = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+        if area == 0. {
+            continue;
+        }
+        let first_column = a.x.min(b.x).min(c.x).floor().max(0.) as usize;
+        let last_column = (a.x.max(b.x).max(c.x).ceil() as usize).min(width - 1);
+        let first_row = a.y.min(b.y).min(c.y).floor().max(0.) as usize;
+        let last_row = (a.y.max(b.y).max(c.y).ceil() as usize).min(height - 1);
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                let (x, y) = (column as f32 + 0.5, row as f32 + 0.5);
+                // Barycentric weights, which are all the same sign as the area inside the triangle
+                let wa = ((b.x - x) * (c.y - y) - (b.y - y) * (c.x - x)) / area;
+                let wb = ((c.x - x) * (a.y - y) - (c.y - y) * (a.x - x)) / area;
+                let wc = 1. - wa - wb;
+                if wa < 0. || wb < 0. || wc < 0. {
+                    continue;
+                }
+                let z = wa * a.z + wb * b.z + wc * c.z;
+                let pixel = row * width + column;
+                if z <= depth[pixel] {
+                    continue;
+                }
+                depth[pixel] = z;
+                pixels[pixel * 4..pixel * 4 + 4].copy_from_slice(&[color[0], color[1], color[2], 255]);
+            }
+        }
+    }
+    return pixels;
+}
+
+/// Draws triangles to a PNG file
+pub fn save(path: &str, triangles: &[Triangle], width: usize, height: usize) -> Result<(), String> {
+    let pixels = render(triangles, width, height);
+    return image::save_buffer(path, &pixels, width as u32, height as u32, image::ColorType::Rgba8)
+        .map_err(|err| err.to_string());
+}
@@ -1,21 +1,118 @@
 use std::fmt::Display;
 
-use egui::{Button, DragValue};
+use egui::Button;
 use glm::{Vec3, Mat4};
 
+use crate::i18n::{tr, tr_args};
+use crate::json::{self, Json};
+use crate::numeric_input::{NumericEdit, Quantity};
+use crate::units::Unit;
+use crate::version;
+
 pub trait Transformation: Display{
     fn matrix(&self) -> Mat4;
-    fn ui(&mut self, ui: &mut egui::Ui);
+    fn ui(&mut self, ui: &mut egui::Ui, unit: Unit);
+    /// The step as a JSON object, for transform files
+    fn to_json(&self) -> String;
+    /// The step as a rotation, if it is one, so snapped rotations can be added onto it
+    fn as_rotation(&mut self) -> Option<&mut Rotation> {
+        return None;
+    }
+}
+
+/// Steps offered by the snap rotation buttons, in degrees
+const SNAP_ANGLES: [f32; 3] = [5., 15., 90.];
+
+/// The angle and axis of the rotation in a transformation, ignoring any scale.
+/// The angle is zero when there is no rotation.
+fn rotation_of(matrix: &Mat4) -> (f32, Vec3) {
+    let mut rotation = matrix.fixed_slice::<3, 3>(0, 0).into_owned();
+    for mut column in rotation.column_iter_mut() {
+        let length = column.norm();
+        if length > 0. {
+            column /= length;
+        }
+    }
+    let cosine = ((rotation.trace() - 1.) / 2.).clamp(-1., 1.);
+    let skew = Vec3::new(
+        rotation[(2, 1)] - rotation[(1, 2)],
+        rotation[(0, 2)] - rotation[(2, 0)],
+        rotation[(1, 0)] - rotation[(0, 1)]);
+    let axis = if skew.norm() > 1e-4 {
+        skew.normalize()
+    } else {
+        // Half turns have no skew part, but R + I is the axis times itself, twice
+        let symmetric = rotation + glm::Mat3::identity();
+        let column = (0..3)
+            .map(|i| symmetric.column(i).into_owned())
+            .max_by(|a, b| a.norm().total_cmp(&b.norm()))
+            .unwrap();
+        if column.norm() > 0. { column.normalize() } else { *Vec3::z_axis() }
+    };
+    return (cosine.acos().to_degrees(), axis);
+}
+
+fn vec3_json(vector: &Vec3) -> String {
+    return format!("[{}, {}, {}]", vector.x, vector.y, vector.z);
+}
+
+fn json_vec3(value: Option<&Json>) -> Option<Vec3> {
+    let values = value?.as_f32s()?;
+    if values.len() != 3 {
+        return None;
+    }
+    return Some(Vec3::new(values[0], values[1], values[2]));
+}
+
+/// Reads a step written by `Transformation::to_json`
+fn transformation_from_json(value: &Json) -> Option<Box<dyn Transformation>> {
+    return match value.get("type")?.as_str()? {
+        "rotation" => Some(Box::new(Rotation {
+            axis: json_vec3(value.get("axis"))?,
+            degrees: value.get("degrees")?.as_f64()? as f32
+        })),
+        "scale" => Some(Box::new(Scale { scale: json_vec3(value.get("scale"))? })),
+        "translation" => Some(Box::new(Translation { translation: json_vec3(value.get("translation"))? })),
+        "mirror" => Some(Box::new(Mirror {
+            normal: json_vec3(value.get("normal"))?,
+            offset: value.get("offset")?.as_f64()? as f32
+        })),
+        "matrix" => Some(Box::new(MatrixTransformation { matrix: json_matrix(value.get("matrix")?)? })),
+        _ => None
+    };
+}
+
+/// A matrix as JSON, an array of rows
+fn matrix_json(matrix: &Mat4) -> String {
+    let rows: Vec<String> = (0..4)
+        .map(|i| {
+            let row = matrix.row(i);
+            format!("[{}, {}, {}, {}]", row[0], row[1], row[2], row[3])
+        })
+        .collect();
+    return format!("[{}]", rows.join(", "));
+}
+
+fn json_matrix(value: &Json) -> Option<Mat4> {
+    let rows = value.as_array()?;
+    let mut values = Vec::<f32>::with_capacity(16);
+    for row in rows {
+        values.extend(row.as_f32s()?);
+    }
+    if rows.len() != 4 || values.len() != 16 {
+        return None;
+    }
+    return Some(Mat4::from_row_slice(&values));
 }
 
-fn vec3_control(ui: &mut egui::Ui, vector: &mut Vec3) {
+fn vec3_control(ui: &mut egui::Ui, vector: &mut Vec3, quantity: Quantity) {
     ui.horizontal(|ui| {
         ui.label("X");
-        ui.add(DragValue::new(&mut vector.x));
+        ui.add(NumericEdit::new("x", &mut vector.x, quantity));
         ui.label("Y");
-        ui.add(DragValue::new(&mut vector.y));
+        ui.add(NumericEdit::new("y", &mut vector.y, quantity));
         ui.label("Z");
-        ui.add(DragValue::new(&mut vector.z));
+        ui.add(NumericEdit::new("z", &mut vector.z, quantity));
     });
 }
 
@@ -25,23 +122,26 @@ pub struct Rotation {
 }
 impl Display for Rotation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Rotate {}°", self.degrees)
+        write!(f, "{}", tr_args("transformation-rotation-summary", &[("degrees", &self.degrees)]))
     }
 }
 impl Transformation for Rotation {
     fn matrix(&self) -> Mat4 {
         return Mat4::new_rotation(self.axis.normalize() * self.degrees.to_radians());
     }
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        ui.label("Axis");
-        ui.horizontal(|ui| {
-            vec3_control(ui, &mut self.axis);
-        });
-        ui.label("Angle");
+    fn ui(&mut self, ui: &mut egui::Ui, _unit: Unit) {
+        ui.label(tr("transformation-axis"));
         ui.horizontal(|ui| {
-            ui.add(DragValue::new(&mut self.degrees));
-            ui.label("°")
+            vec3_control(ui, &mut self.axis, Quantity::Number);
         });
+        ui.label(tr("transformation-angle"));
+        ui.add(NumericEdit::new("degrees", &mut self.degrees, Quantity::Angle));
+    }
+    fn to_json(&self) -> String {
+        return format!(r#"{{"type": "rotation", "axis": {}, "degrees": {}}}"#, vec3_json(&self.axis), self.degrees);
+    }
+    fn as_rotation(&mut self) -> Option<&mut Rotation> {
+        return Some(self);
     }
 }
 
@@ -50,15 +150,19 @@ pub struct Scale {
 }
 impl Display for Scale {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Scale {}, {}, {}", self.scale.x, self.scale.y, self.scale.z)
+        write!(f, "{}", tr_args("transformation-scale-summary",
+            &[("x", &self.scale.x), ("y", &self.scale.y), ("z", &self.scale.z)]))
     }
 }
 impl Transformation for Scale {
     fn matrix(&self) -> Mat4 {
         Mat4::new_nonuniform_scaling(&self.scale)
     }
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        vec3_control(ui, &mut self.scale);
+    fn ui(&mut self, ui: &mut egui::Ui, _unit: Unit) {
+        vec3_control(ui, &mut self.scale, Quantity::Number);
+    }
+    fn to_json(&self) -> String {
+        return format!(r#"{{"type": "scale", "scale": {}}}"#, vec3_json(&self.scale));
     }
 }
 pub struct Translation {
@@ -67,15 +171,81 @@ pub struct Translation {
 impl Display for Translation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let translation = self.translation;
-        write!(f, "Translation {}, {}, {}", translation.x, translation.y, translation.z)
+        write!(f, "{}", tr_args("transformation-translation-summary",
+            &[("x", &translation.x), ("y", &translation.y), ("z", &translation.z)]))
     }
 }
 impl Transformation for Translation {
     fn matrix(&self) -> Mat4 {
         Mat4::new_translation(&self.translation)
     }
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        vec3_control(ui, &mut self.translation);
+    fn ui(&mut self, ui: &mut egui::Ui, unit: Unit) {
+        vec3_control(ui, &mut self.translation, Quantity::Length(unit));
+    }
+    fn to_json(&self) -> String {
+        return format!(r#"{{"type": "translation", "translation": {}}}"#, vec3_json(&self.translation));
+    }
+}
+
+/// A reflection across a plane, given by its normal and its distance from the origin along the normal
+pub struct Mirror {
+    normal: Vec3,
+    offset: f32
+}
+impl Display for Mirror {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", tr_args("transformation-mirror-summary",
+            &[("x", &self.normal.x), ("y", &self.normal.y), ("z", &self.normal.z), ("offset", &self.offset)]))
+    }
+}
+impl Transformation for Mirror {
+    fn matrix(&self) -> Mat4 {
+        let normal = match self.normal.try_normalize(0.) {
+            Some(normal) => normal,
+            None => return Mat4::identity()
+        };
+        // Points move twice their distance past the plane, back along the normal
+        let reflection = glm::Mat3::identity() - normal * normal.transpose() * 2.;
+        let mut matrix = glm::mat3_to_mat4(&reflection);
+        matrix.set_column(3, &(normal * self.offset * 2.).push(1.));
+        return matrix;
+    }
+    fn ui(&mut self, ui: &mut egui::Ui, unit: Unit) {
+        ui.label(tr("transformation-normal"));
+        vec3_control(ui, &mut self.normal, Quantity::Number);
+        ui.label(tr("transformation-offset"));
+        ui.add(NumericEdit::new("offset", &mut self.offset, Quantity::Length(unit)));
+    }
+    fn to_json(&self) -> String {
+        return format!(r#"{{"type": "mirror", "normal": {}, "offset": {}}}"#, vec3_json(&self.normal), self.offset);
+    }
+}
+
+/// Any affine transformation, such as a registration imported from other software
+pub struct MatrixTransformation {
+    matrix: Mat4
+}
+impl Display for MatrixTransformation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", tr("transformation-matrix"))
+    }
+}
+impl Transformation for MatrixTransformation {
+    fn matrix(&self) -> Mat4 {
+        return self.matrix;
+    }
+    fn ui(&mut self, ui: &mut egui::Ui, _unit: Unit) {
+        egui::Grid::new("matrix").show(ui, |ui| {
+            for row in 0..4 {
+                for column in 0..4 {
+                    ui.add(NumericEdit::new((row, column), &mut self.matrix[(row, column)], Quantity::Number));
+                }
+                ui.end_row();
+            }
+        });
+    }
+    fn to_json(&self) -> String {
+        return format!(r#"{{"type": "matrix", "matrix": {}}}"#, matrix_json(&self.matrix));
     }
 }
 
@@ -91,7 +261,7 @@ impl TransformationUI {
             selection: None
         }
     }
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
+    pub fn ui(&mut self, ui: &mut egui::Ui, unit: Unit) {
         ui.set_max_width(200.0);
         for i in 0..self.transformations.len() {
             let mut removed = Option::<usize>::None;
@@ -100,7 +270,7 @@ impl TransformationUI {
                     removed = Some(i);
                     return;
                 }
-                if ui.button("edit").clicked() {
+                if ui.button(tr("transformation-edit")).clicked() {
                     self.selection = Some(i);
                 }
                 if ui.add_enabled(i > 0, Button::new("^")).clicked() {
@@ -142,26 +312,142 @@ impl TransformationUI {
             ui.label(self.transformations[i].to_string());
             if let Some(selection) = self.selection {
                 if selection == i {
-                    self.transformations[i].ui(ui);
+                    ui.push_id(i, |ui| {
+                        self.transformations[i].ui(ui, unit);
+                    });
                 }
             }
             ui.separator();
         }
+        ui.label(tr("transformation-snap"));
+        egui::Grid::new("snap_rotation").spacing([2., 2.]).show(ui, |ui| {
+            for (axis, name) in [(Vec3::x_axis(), "X"), (Vec3::y_axis(), "Y"), (Vec3::z_axis(), "Z")] {
+                ui.label(name);
+                let steps = SNAP_ANGLES.iter().rev().map(|a| -a).chain(SNAP_ANGLES);
+                for degrees in steps {
+                    if ui.small_button(format!("{:+}", degrees)).clicked() {
+                        self.rotate(*axis, degrees);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        let (degrees, axis) = rotation_of(&self.get_matrix());
+        if degrees.abs() < 0.01 {
+            ui.label(tr("transformation-not-rotated"));
+        } else {
+            ui.label(tr_args("transformation-rotated", &[
+                ("degrees", &format!("{:.1}", degrees)),
+                ("x", &format!("{:.2}", axis.x)),
+                ("y", &format!("{:.2}", axis.y)),
+                ("z", &format!("{:.2}", axis.z))]));
+        }
+        ui.separator();
         ui.menu_button("+", |ui| {
-            if ui.button("Rotation").clicked() {
+            if ui.button(tr("transformation-rotation")).clicked() {
                 self.transformations.push(
                     Box::new(Rotation{axis: *Vec3::z_axis(), degrees: 0.0}));
             }
-            if ui.button("Scale").clicked() {
+            if ui.button(tr("transformation-scale")).clicked() {
                 self.transformations.push(
                     Box::new(Scale{scale: Vec3::new(1.,1.,1.)}));
             }
-            if ui.button("Translation").clicked() {
+            if ui.button(tr("transformation-translation")).clicked() {
                 self.transformations.push(
                     Box::new(Translation{translation: Vec3::zeros()}));
             }
+            if ui.button(tr("transformation-mirror")).clicked() {
+                self.transformations.push(
+                    Box::new(Mirror{normal: *Vec3::x_axis(), offset: 0.0}));
+            }
         });
     }
+    /// Saves the combined matrix and the list of steps, with the version of MeshTools that saved them.
+    /// Files ending in .txt get just the matrix, as four rows of four numbers.
+    pub fn export(&self, path: &str) -> Result<(), std::io::Error> {
+        let matrix = self.get_matrix();
+        let text = if path.to_lowercase().ends_with(".txt") {
+            (0..4)
+                .map(|i| {
+                    let row = matrix.row(i);
+                    format!("{} {} {} {}\n", row[0], row[1], row[2], row[3])
+                })
+                .collect::<String>()
+        } else {
+            let steps: Vec<String> = self.transformations.iter()
+                .map(|t| format!("    {}", t.to_json()))
+                .collect();
+            format!("{{\n  {}: {},\n  {}: {},\n  {}: [\n{}\n  ]\n}}\n",
+                json::quote("generator"), json::quote(&version::description()),
+                json::quote("matrix"), matrix_json(&matrix),
+                json::quote("steps"), steps.join(",\n"))
+        };
+        return std::fs::write(path, text);
+    }
+    /// Adds the transformations from a file to the end of the list,
+    /// returning how many were added.
+    ///
+    /// JSON files written by `export` add their steps, or their matrix if the steps
+    /// aren't there. Other files must hold 16 numbers, the rows of a 4×4 matrix.
+    pub fn import(&mut self, path: &str) -> Result<usize, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        if text.trim_start().starts_with('{') {
+            let value = Json::parse(&text)?;
+            let steps: Option<Vec<Box<dyn Transformation>>> = value.get("steps")
+                .and_then(|steps| steps.as_array())
+                .and_then(|steps| steps.iter().map(transformation_from_json).collect());
+            if let Some(steps) = steps {
+                let count = steps.len();
+                self.transformations.extend(steps);
+                return Ok(count);
+            }
+            let matrix = value.get("matrix")
+                .and_then(json_matrix)
+                .ok_or_else(|| tr("transformation-import-invalid"))?;
+            self.transformations.push(Box::new(MatrixTransformation { matrix }));
+            return Ok(1);
+        }
+        let values: Result<Vec<f32>, _> = text
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f32>())
+            .collect();
+        match values {
+            Ok(values) if values.len() == 16 => {
+                self.transformations.push(Box::new(MatrixTransformation { matrix: Mat4::from_row_slice(&values) }));
+                return Ok(1);
+            },
+            _ => return Err(tr("transformation-import-invalid"))
+        }
+    }
+    /// Moves by a further offset
+    pub fn translate(&mut self, offset: Vec3) {
+        self.transformations.push(Box::new(Translation { translation: offset }));
+    }
+    /// Applies a further matrix
+    pub fn transform(&mut self, matrix: Mat4) {
+        self.transformations.push(Box::new(MatrixTransformation { matrix }));
+    }
+    /// Scales by the same factor on every axis
+    pub fn scale(&mut self, factor: f32) {
+        self.transformations.push(Box::new(Scale { scale: Vec3::repeat(factor) }));
+    }
+    /// Reflects across a plane, given by its normal and its distance from the origin along the normal
+    pub fn mirror(&mut self, normal: Vec3, offset: f32) {
+        self.transformations.push(Box::new(Mirror { normal, offset }));
+    }
+    /// Rotates by a further angle about an axis. If the last step is
+    /// a rotation about the same axis, the angle is added to it instead.
+    pub fn rotate(&mut self, axis: Vec3, degrees: f32) {
+        if let Some(rotation) = self.transformations.last_mut().and_then(|t| t.as_rotation()) {
+            if rotation.axis.normalize() == axis.normalize() {
+                // Keep the angle between -180 and 180, so turning back and forth stays readable
+                rotation.degrees = (rotation.degrees + degrees + 180.).rem_euclid(360.) - 180.;
+                return;
+            }
+        }
+        self.transformations.push(Box::new(Rotation { axis, degrees }));
+    }
     pub fn get_matrix(&self) -> Mat4{
         let mut result = Mat4::identity();
         for t in &self.transformations {
@@ -0,0 +1,98 @@
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::version::VERSION;
+
+/// Longest update check response read, since only the first line is used
+const MAX_RESPONSE: u64 = 64 * 1024;
+
+/// Longest wait for the server, since the check runs unattended at startup
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether a version like "1.2.10" is later than another, comparing each number in turn
+pub fn is_newer(version: &str, than: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> {
+        return version.trim().trim_start_matches('v')
+            .split('.')
+            .map(|part| part.chars().take_while(|c| c.is_ascii_digit()).collect::<String>().parse().unwrap_or(0))
+            .collect();
+    };
+    return numbers(version) > numbers(than);
+}
+
+/// Checks that an update address is an http:// or https:// address with a host,
+/// so a mistyped setting is reported rather than quietly never checked
+pub fn check_url(url: &str) -> Result<(), Error> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidInput, message.to_string());
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"))
+        .ok_or_else(|| invalid("The address must start with http:// or https://"))?;
+    if rest.split('/').next().unwrap_or_default().is_empty() {
+        return Err(invalid("The address has no host"));
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(invalid("The address contains spaces"));
+    }
+    return Ok(());
+}
+
+/// Asks a server for the latest released version. The response's body has the version
+/// on its first line, like "0.2.0".
+///
+/// https:// addresses are fetched with the system's curl, which brings its own TLS,
+/// and plain http:// addresses, such as a file on an intranet server, are fetched directly.
+pub fn latest_version(url: &str) -> Result<String, Error> {
+    check_url(url)?;
+    let body = match url.strip_prefix("http://") {
+        Some(rest) => get_http(rest)?,
+        None => get_with_curl(url)?
+    };
+    return body.lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .map(|line| line.trim_start_matches('v').to_string())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No version in the response"));
+}
+
+/// The body of a plain HTTP GET, given the address without its http://
+fn get_http(rest: &str) -> Result<String, Error> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidInput, message.to_string());
+    let (host, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/")
+    };
+    let address = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+    let address = address.to_socket_addrs()?.next().ok_or_else(|| invalid("Unknown host"))?;
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: MeshTools/{}\r\nConnection: close\r\n\r\n",
+        path, host, VERSION)?;
+    let mut response = String::new();
+    stream.take(MAX_RESPONSE).read_to_string(&mut response)?;
+
+    let (head, body) = response.split_once("\r\n\r\n").ok_or_else(|| invalid("Incomplete response"))?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(Error::other(status.to_string()));
+    }
+    return Ok(body.to_string());
+}
+
+/// The body of an HTTPS GET, fetched by curl, which fails on an error status
+fn get_with_curl(url: &str) -> Result<String, Error> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--proto", "=https", "--proto-redir", "=https"])
+        .args(["--max-time", &TIMEOUT.as_secs().to_string(), "--max-filesize", &MAX_RESPONSE.to_string()])
+        .arg("--user-agent").arg(format!("MeshTools/{}", VERSION))
+        .arg("--").arg(url)
+        .output()
+        .map_err(|err| match err.kind() {
+            ErrorKind::NotFound => Error::new(ErrorKind::NotFound, "Checking https:// addresses needs curl to be installed"),
+            _ => err
+        })?;
+    if !output.status.success() {
+        return Err(Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+}
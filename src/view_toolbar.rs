@@ -0,0 +1,200 @@
+extern crate nalgebra_glm as glm;
+use glm::{Mat4, Vec3};
+
+use crate::i18n::{tr, tr_args};
+use crate::mesh_widget::{Camera, ViewState};
+
+/// A direction to look at the scene from, with Z up
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StandardView {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Isometric
+}
+
+impl StandardView {
+    pub const ALL: [StandardView; 7] = [StandardView::Front, StandardView::Back, StandardView::Left,
+        StandardView::Right, StandardView::Top, StandardView::Bottom, StandardView::Isometric];
+
+    pub fn label(&self) -> String {
+        match self {
+            StandardView::Front => tr("view-front"),
+            StandardView::Back => tr("view-back"),
+            StandardView::Left => tr("view-left"),
+            StandardView::Right => tr("view-right"),
+            StandardView::Top => tr("view-top"),
+            StandardView::Bottom => tr("view-bottom"),
+            StandardView::Isometric => tr("view-isometric")
+        }
+    }
+
+    /// The key that jumps to the view, like the number pad in other 3D programs
+    pub fn key(&self) -> egui::Key {
+        match self {
+            StandardView::Front => egui::Key::Num1,
+            StandardView::Back => egui::Key::Num2,
+            StandardView::Left => egui::Key::Num3,
+            StandardView::Right => egui::Key::Num4,
+            StandardView::Top => egui::Key::Num5,
+            StandardView::Bottom => egui::Key::Num6,
+            StandardView::Isometric => egui::Key::Num0
+        }
+    }
+
+    /// The key's name, as printed on it
+    fn key_name(&self) -> &'static str {
+        match self {
+            StandardView::Front => "1",
+            StandardView::Back => "2",
+            StandardView::Left => "3",
+            StandardView::Right => "4",
+            StandardView::Top => "5",
+            StandardView::Bottom => "6",
+            StandardView::Isometric => "0"
+        }
+    }
+
+    /// The direction from the scene toward the viewer, and the direction that is up on the screen
+    fn directions(&self) -> (Vec3, Vec3) {
+        match self {
+            StandardView::Front => (-Vec3::y(), Vec3::z()),
+            StandardView::Back => (Vec3::y(), Vec3::z()),
+            StandardView::Left => (-Vec3::x(), Vec3::z()),
+            StandardView::Right => (Vec3::x(), Vec3::z()),
+            StandardView::Top => (Vec3::z(), Vec3::y()),
+            StandardView::Bottom => (-Vec3::z(), -Vec3::y()),
+            StandardView::Isometric => (Vec3::new(1., -1., 1.).normalize(), Vec3::z())
+        }
+    }
+
+    /// The rotation that turns the scene to be seen from this direction
+    pub fn rotation(&self) -> Mat4 {
+        let (toward_viewer, up) = self.directions();
+        let right = up.cross(&toward_viewer).normalize();
+        let up = toward_viewer.cross(&right);
+        // The rows are the scene's directions that become the view's X, Y, and Z
+        return Mat4::new(
+            right.x, right.y, right.z, 0.,
+            up.x, up.y, up.z, 0.,
+            toward_viewer.x, toward_viewer.y, toward_viewer.z, 0.,
+            0., 0., 0., 1.);
+    }
+}
+
+/// Turns the view steadily around an axis of the scene, to look over a model hands-free
+pub struct Turntable {
+    pub enabled: bool,
+    /// Degrees turned each second
+    pub speed: f32,
+    /// Index of the scene axis turned around
+    pub axis: usize
+}
+
+impl Turntable {
+    pub fn new() -> Self {
+        return Self { enabled: false, speed: 30., axis: 2 };
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.toggle_value(&mut self.enabled, tr("view-turntable")).on_hover_text(tr("view-turntable-hint"));
+        if !self.enabled {
+            return;
+        }
+        ui.add(egui::DragValue::new(&mut self.speed).speed(1.).clamp_range(-360.0..=360.).suffix("°/s"));
+        for (i, name) in ["X", "Y", "Z"].iter().enumerate() {
+            ui.radio_value(&mut self.axis, i, *name);
+        }
+    }
+
+    /// Turns the view by however far it goes in the time since the last frame,
+    /// and asks for another frame to keep turning
+    pub fn advance(&self, ctx: &egui::Context, view: &mut ViewState) {
+        if !self.enabled || self.speed == 0. {
+            return;
+        }
+        // Capped, so a long pause doesn't jump the model around
+        let seconds = ctx.input().unstable_dt.min(0.1);
+        let mut axis = Vec3::zeros();
+        axis[self.axis.min(2)] = 1.;
+        view.camera.rotation = glm::rotate(&view.camera.rotation, (self.speed * seconds).to_radians(), &axis);
+        ctx.request_repaint();
+    }
+}
+
+/// What the application should do to the view after the toolbar is shown
+pub enum ToolbarResponse {
+    None,
+    /// Fit the scene in the view, keeping the rotation
+    Fit,
+    /// Turn to a standard view and fit the scene in it
+    View(StandardView),
+    /// Remember where the view is now
+    SaveView,
+    /// Move to a saved viewpoint
+    Jump(Camera)
+}
+
+/// Shows a row of buttons to fit the scene in the view and turn to standard views,
+/// a menu of saved viewpoints, and the turntable's controls
+pub fn show(ui: &mut egui::Ui, turntable: &mut Turntable, saved: &mut Vec<(String, Camera)>) -> ToolbarResponse {
+    let mut response = ToolbarResponse::None;
+    ui.horizontal(|ui| {
+        if ui.button(tr("view-fit")).on_hover_text(tr("view-fit-hint")).clicked() {
+            response = ToolbarResponse::Fit;
+        }
+        for view in StandardView::ALL {
+            if ui.button(view.label()).on_hover_text(tr_args("view-shortcut", &[("key", &view.key_name())])).clicked() {
+                response = ToolbarResponse::View(view);
+            }
+        }
+        ui.separator();
+        ui.menu_button(tr("view-saved"), |ui| {
+            let mut removed = None;
+            for (i, (name, camera)) in saved.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.button("➡").on_hover_text(tr("view-saved-go")).clicked() {
+                        response = ToolbarResponse::Jump(*camera);
+                        ui.close_menu();
+                    }
+                    ui.text_edit_singleline(name);
+                    if ui.small_button("🗑").on_hover_text(tr("view-saved-remove")).clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                saved.remove(i);
+            }
+            if ui.button(tr("view-save")).clicked() {
+                response = ToolbarResponse::SaveView;
+            }
+        });
+        ui.separator();
+        turntable.ui(ui);
+    });
+    return response;
+}
+
+/// Checks the keyboard shortcuts for the toolbar, while nothing else has the keyboard
+pub fn shortcut(ctx: &egui::Context) -> ToolbarResponse {
+    if ctx.memory().focus().is_some() {
+        return ToolbarResponse::None;
+    }
+    let input = ctx.input();
+    if input.modifiers.any() {
+        return ToolbarResponse::None;
+    }
+    if input.key_pressed(egui::Key::F) {
+        return ToolbarResponse::Fit;
+    }
+    for view in StandardView::ALL {
+        if input.key_pressed(view.key()) {
+            return ToolbarResponse::View(view);
+        }
+    }
+    return ToolbarResponse::None;
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::file_info::FileInfo;
+use crate::i18n::{tr, tr_args};
+use crate::issues::{self, IssueKind};
+use crate::mesh_file;
+use crate::thumbnail;
+use crate::triangle::{self, Triangle};
+use crate::triangle_mesh::{self, HoleFill, TriangleMesh};
+use crate::version;
+
+/// The steps run on each file that arrives in a watched directory
+pub struct Pipeline {
+    pub hole_fill: HoleFill,
+    /// Width and height of the thumbnail in pixels, or 0 for no thumbnail.
+    /// Sizes past `thumbnail::MAX_SIZE` are drawn at that size.
+    pub thumbnail_size: usize
+}
+
+impl Pipeline {
+    /// Cleans a mesh, makes its triangles face the same way, and fills its holes.
+    /// Returns the repaired triangles, and translated lines describing the repair.
+    pub fn repair(&self, triangles: &[Triangle]) -> (Vec<Triangle>, Vec<String>) {
+        profile_span!("repair_preset");
+        let (mut mesh, cleaned) = TriangleMesh::clean(triangles, triangle_mesh::default_tolerance(triangles));
+        let flipped = mesh.flipped_triangles();
+        for t in &flipped {
+            mesh.flip(*t);
+        }
+        // Holes are found after flipping, so their boundaries run the right way
+        let holes = mesh.holes();
+        for hole in &holes {
+            mesh.fill_loop(&hole.vertices, self.hole_fill);
+        }
+        let mut lines = vec![
+            tr_args("watch-cleaned", &[
+                ("degenerate", &cleaned.degenerate),
+                ("duplicates", &cleaned.duplicates),
+                ("unreferenced", &cleaned.unreferenced)]),
+            tr_args("watch-repaired", &[("flipped", &flipped.len()), ("filled", &holes.len())])
+        ];
+        if cleaned.thin > 0 {
+            lines.insert(1, tr_args("watch-cleaned-thin", &[("thin", &cleaned.thin)]));
+        }
+        let remaining = issues::find_issues(&mesh, 0.);
+        for kind in IssueKind::ALL {
+            let count = remaining.iter().filter(|issue| issue.kind == kind).count();
+            if count > 0 {
+                lines.push(tr_args("watch-remaining", &[("kind", &kind.label()), ("count", &count)]));
+            }
+        }
+        return (mesh.to_triangles(), lines);
+    }
+
+    /// Runs the pipeline on a mesh file, writing the repaired mesh, a report, and a thumbnail
+    /// to the output directory, named after the file with its extension, so "part.stl" and
+    /// "part.obj" don't overwrite each other's results. Returns the report's lines.
+    pub fn process(&self, path: &Path, output: &Path) -> Result<Vec<String>, Error> {
+        let name = path.display().to_string();
+        let stem = output_name(path);
+        let triangles = mesh_file::read_mesh_file(&name)?;
+        let (repaired, repair_lines) = self.repair(&triangles);
+
+        let mut lines = vec![name.clone(), version::description(), tr("watch-before")];
+        lines.extend(FileInfo::from_triangles(&triangles).lines("").into_iter().map(|line| format!("  {}", line)));
+        lines.extend(repair_lines);
+        lines.push(tr("watch-after"));
+        lines.extend(FileInfo::from_triangles(&repaired).lines("").into_iter().map(|line| format!("  {}", line)));
+
+        let repaired_path = output.join(format!("{}-repaired.stl", stem));
+        triangle::write_stl_binary(&repaired_path.display().to_string(), &repaired)?;
+        if self.thumbnail_size > 0 {
+            let thumbnail_path = output.join(format!("{}.png", stem)).display().to_string();
+            let size = self.thumbnail_size.min(thumbnail::MAX_SIZE);
+            thumbnail::save(&thumbnail_path, &repaired, size, size).map_err(Error::other)?;
+        }
+        fs::write(output.join(format!("{}-report.txt", stem)), lines.join("\n") + "\n")?;
+        return Ok(lines);
+    }
+}
+
+/// The name the results of a file are written under: its file name, with the extension
+fn output_name(path: &Path) -> String {
+    return path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+}
+
+/// Moves a file, copying it if it is on another drive
+fn move_file(from: &Path, to: &Path) -> Result<(), Error> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    return fs::remove_file(from);
+}
+
+/// Mesh files in a directory, with their sizes
+fn mesh_files(directory: &Path) -> Result<HashMap<PathBuf, u64>, Error> {
+    let mut files = HashMap::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        let supported = path.extension()
+            .map(|e| mesh_file::SUPPORTED_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+            .unwrap_or(false);
+        if supported && entry.file_type()?.is_file() {
+            files.insert(path, entry.metadata()?.len());
+        }
+    }
+    return Ok(files);
+}
+
+/// Watches a directory for mesh files, running the pipeline on each one that arrives,
+/// then moving it to the output directory with its results. Files that can't be processed
+/// are moved too, with the error written next to them, so they aren't tried again.
+///
+/// A file is only processed once its size stays the same between two checks,
+/// so files still being copied in are left alone. Each file processed is passed
+/// to the log with the outcome. This only returns if a directory can't be read, or a file can't be moved.
+///
+/// The directories can't be the same or inside one another, or the results would be picked up as new files.
+pub fn watch(incoming: &Path, output: &Path, interval: Duration, pipeline: &Pipeline, mut log: impl FnMut(String))
+-> Result<(), Error> {
+    fs::create_dir_all(output)?;
+    let (incoming_path, output_path) = (incoming.canonicalize()?, output.canonicalize()?);
+    if output_path.starts_with(&incoming_path) || incoming_path.starts_with(&output_path) {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("watch-overlap")));
+    }
+    let mut previous = HashMap::<PathBuf, u64>::new();
+    loop {
+        let files = mesh_files(incoming)?;
+        let mut settled: Vec<&PathBuf> = files.iter()
+            .filter(|(path, size)| previous.get(*path) == Some(size))
+            .map(|(path, _)| path)
+            .collect();
+        settled.sort();
+        for path in settled {
+            let name = path.file_name().unwrap_or_default();
+            let stem = output_name(path);
+            match pipeline.process(path, output) {
+                Ok(_) => log(tr_args("watch-processed", &[("file", &path.display().to_string())])),
+                Err(err) => {
+                    let _ = fs::write(output.join(format!("{}-error.txt", stem)), err.to_string() + "\n");
+                    log(tr_args("watch-failed", &[("file", &path.display().to_string()), ("error", &err)]));
+                }
+            }
+            // Left where it is, it would be processed again and again
+            move_file(path, &output.join(name))?;
+        }
+        previous = files;
+        std::thread::sleep(interval);
+    }
+}